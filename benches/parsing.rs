@@ -0,0 +1,154 @@
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use execdump::deps::pe_imported_dlls;
+use execdump::disasm::{disasm_elf_code, disasm_pe_code};
+use execdump::elf::parse_elf;
+use execdump::exec::{guess_exectype, ExecType};
+use execdump::pe::{parse_pe_from_reader, PEReader, PE};
+
+/*
+ * Parsing and disassembly benchmarks
+ *
+ * Runs on the fixtures of tests/fixtures, more files can be given with EXECDUMP_BENCH_FILES,
+ * a list of paths separated like PATH (':' on Unix, ';' on Windows)
+ */
+
+const FIXTURES: [&str; 5] = ["app32.exe", "app64.exe", "lib64.dll", "bootx64.efi", "hello.elf"];
+
+struct BenchFile {
+    name: String,
+    path: PathBuf,
+    bytes: Vec<u8>,
+    exec_type: ExecType,
+}
+
+fn bench_files() -> Vec<BenchFile> {
+    let fixtures_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+
+    let mut paths: Vec<PathBuf> = FIXTURES.iter().map(|f| fixtures_dir.join(f)).collect();
+
+    if let Some(files) = std::env::var_os("EXECDUMP_BENCH_FILES") {
+        paths.extend(std::env::split_paths(&files).filter(|p| !p.as_os_str().is_empty()));
+    }
+
+    let mut files = Vec::new();
+
+    for path in paths {
+        let exec_type = match guess_exectype(&path) {
+            Ok(exec_type) => exec_type,
+            Err(e) => {
+                eprintln!("Skipping {}: {}", path.display(), e);
+                continue;
+            }
+        };
+
+        let bytes = std::fs::read(&path).expect("Unable to read benchmark file");
+
+        files.push(BenchFile {
+            name: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+            path,
+            bytes,
+            exec_type,
+        });
+    }
+
+    return files;
+}
+
+fn pe_files(files: &[BenchFile]) -> impl Iterator<Item = &BenchFile> {
+    return files.iter().filter(|f| matches!(f.exec_type, ExecType::PE));
+}
+
+fn bench_header_parse(c: &mut Criterion) {
+    let files = bench_files();
+    let mut group = c.benchmark_group("header_parse");
+
+    for file in pe_files(&files) {
+        group.bench_with_input(BenchmarkId::from_parameter(&file.name), &file.bytes, |b, bytes| {
+            b.iter(|| {
+                let mut pe = PE::new();
+                black_box(pe.parse_headers(&mut PEReader::from_bytes(bytes)).unwrap());
+            });
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_full_parse(c: &mut Criterion) {
+    let files = bench_files();
+    let mut group = c.benchmark_group("full_parse");
+
+    for file in files.iter() {
+        match file.exec_type {
+            ExecType::PE => {
+                group.bench_with_input(BenchmarkId::from_parameter(&file.name), &file.bytes, |b, bytes| {
+                    b.iter(|| black_box(parse_pe_from_reader(&mut PEReader::from_bytes(bytes), true).unwrap()));
+                });
+            }
+            /* The ELF parser reads the file itself */
+            ExecType::ELF => {
+                group.bench_with_input(BenchmarkId::from_parameter(&file.name), &file.path, |b, path| {
+                    b.iter(|| black_box(parse_elf(path).unwrap()));
+                });
+            }
+        }
+    }
+
+    group.finish();
+}
+
+fn bench_import_walk(c: &mut Criterion) {
+    let files = bench_files();
+    let mut group = c.benchmark_group("import_walk");
+
+    for file in pe_files(&files) {
+        let pe = parse_pe_from_reader(&mut PEReader::from_bytes(&file.bytes), false).unwrap();
+
+        group.bench_with_input(BenchmarkId::from_parameter(&file.name), &pe, |b, pe| {
+            b.iter(|| black_box(pe_imported_dlls(pe)));
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_disasm(c: &mut Criterion) {
+    let files = bench_files();
+    let mut group = c.benchmark_group("disasm");
+
+    for file in files.iter() {
+        match file.exec_type {
+            ExecType::PE => {
+                let pe = parse_pe_from_reader(&mut PEReader::from_bytes(&file.bytes), true).unwrap();
+
+                for (index, section) in pe.sections.iter().enumerate().filter(|(_, s)| s.contains_code()) {
+                    let id = format!("{}/{}#{}", file.name, section.header.name, index);
+
+                    group.bench_function(id, |b| {
+                        b.iter(|| black_box(disasm_pe_code(&pe, &section.data, section.header.virtual_address as u64).unwrap()));
+                    });
+                }
+            }
+            ExecType::ELF => {
+                let elf = parse_elf(&file.path).unwrap();
+
+                for (name, section) in elf.sections.iter().filter(|(_, s)| s.contains_code()) {
+                    let id = format!("{}/{}", file.name, name);
+
+                    group.bench_function(id, |b| {
+                        b.iter(|| black_box(disasm_elf_code(&elf, &section.data, section.header.virtual_address()).unwrap()));
+                    });
+                }
+            }
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_header_parse, bench_full_parse, bench_import_walk, bench_disasm);
+criterion_main!(benches);
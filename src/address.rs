@@ -0,0 +1,472 @@
+use crate::args::AddrArgs;
+use crate::demangle::label_name;
+use crate::dump::{Dump, DumpRawData};
+use crate::elf::{SectionFlags as ELFSectionFlags, SectionType, ELF, STT_FUNC, STT_GNU_IFUNC, STT_OBJECT};
+use crate::exec::Exec;
+use crate::format::format_hex_lines_with_gutter;
+use crate::patch::parse_address;
+use crate::pe::{SectionFlags, PE};
+
+/*
+ * Location of an address in the file and in memory
+ */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressKind {
+    Offset,
+    Rva,
+    VirtualAddress,
+}
+
+/// "off:0x400", "rva:0x1000" or "va:0x140001000", the kind is None for bare numbers
+pub fn parse_address_query(value: &str) -> Result<(Option<AddressKind>, u64), Box<dyn std::error::Error>> {
+    let value = value.trim();
+
+    let (kind, number) = match value.split_once(':') {
+        Some((prefix, number)) => match prefix.to_ascii_lowercase().as_str() {
+            "off" | "offset" => (Some(AddressKind::Offset), number),
+            "rva" => (Some(AddressKind::Rva), number),
+            "va" => (Some(AddressKind::VirtualAddress), number),
+            _ => return Err(format!("Unknown address kind \"{}\", expected off, rva or va", prefix).into()),
+        },
+        None => (None, value),
+    };
+
+    return Ok((kind, parse_address(number)?));
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AddressLocation {
+    /// Section name, "(headers)", "(overlay)" or "(unmapped)"
+    pub region: String,
+    /// Distance from the start of the region
+    pub region_offset: u64,
+    /// None for the zero-filled memory not backed by the file
+    pub offset: Option<u64>,
+    /// None for ELF, which has no image base
+    pub rva: Option<u64>,
+    /// None for the file data not mapped in memory
+    pub virtual_address: Option<u64>,
+    /// "R-X", None outside of the mapped image
+    pub permissions: Option<String>,
+    /// Closest export or symbol at or before the address, with the distance to it
+    pub nearest_symbol: Option<(String, u64)>,
+}
+
+fn permissions_string(read: bool, write: bool, execute: bool) -> String {
+    return format!("{}{}{}", if read { 'R' } else { '-' }, if write { 'W' } else { '-' }, if execute { 'X' } else { '-' });
+}
+
+fn nearest_symbol(symbols: impl Iterator<Item = (u64, String)>, address: u64) -> Option<(String, u64)> {
+    return symbols
+        .filter(|(symbol_address, _)| *symbol_address <= address)
+        .max_by_key(|(symbol_address, _)| *symbol_address)
+        .map(|(symbol_address, name)| (label_name(&name), address - symbol_address));
+}
+
+impl AddressLocation {
+    /// Bare numbers are virtual addresses when at or above the PE image base, RVAs otherwise, and always virtual addresses for ELF
+    pub fn locate(exec: &Exec, query: &str, file_size: u64) -> Result<AddressLocation, Box<dyn std::error::Error>> {
+        let (kind, address) = parse_address_query(query)?;
+
+        return AddressLocation::locate_address(exec, kind, address, file_size);
+    }
+
+    pub fn locate_address(exec: &Exec, kind: Option<AddressKind>, address: u64, file_size: u64) -> Result<AddressLocation, Box<dyn std::error::Error>> {
+        match exec {
+            Exec::PE(pe) => return AddressLocation::locate_pe(pe, kind, address, file_size),
+            Exec::ELF(elf) => return AddressLocation::locate_elf(elf, kind, address, file_size),
+        }
+    }
+
+    pub fn locate_pe(pe: &PE, kind: Option<AddressKind>, address: u64, file_size: u64) -> Result<AddressLocation, Box<dyn std::error::Error>> {
+        let image_base = pe.get_optional_header().get_image_base();
+
+        let mut location = match kind {
+            Some(AddressKind::Offset) => AddressLocation::pe_from_offset(pe, address, file_size)?,
+            Some(AddressKind::Rva) => AddressLocation::pe_from_rva(pe, address)?,
+            Some(AddressKind::VirtualAddress) => {
+                let rva = address.checked_sub(image_base).ok_or_else(|| format!("Address {:#x} is below the image base {:#x}", address, image_base))?;
+                AddressLocation::pe_from_rva(pe, rva)?
+            }
+            None if address >= image_base => AddressLocation::pe_from_rva(pe, address - image_base)?,
+            None => AddressLocation::pe_from_rva(pe, address)?,
+        };
+
+        location.virtual_address = location.rva.map(|rva| image_base + rva);
+
+        if let Some(rva) = location.rva {
+            let exports = pe
+                .export_data
+                .iter()
+                .flat_map(|e| e.entries.iter())
+                .filter(|e| e.forwarder.is_none())
+                .map(|e| (e.rva as u64, e.display_name()));
+
+            let symbols = pe.pdb_symbols.iter().map(|(rva, name)| (*rva as u64, name.clone()));
+
+            location.nearest_symbol = nearest_symbol(exports.chain(symbols), rva);
+        }
+
+        return Ok(location);
+    }
+
+    fn pe_from_rva(pe: &PE, rva: u64) -> Result<AddressLocation, Box<dyn std::error::Error>> {
+        let size_of_headers = pe.get_optional_header().get_size_of_headers() as u64;
+        let first_section = pe.sections.iter().map(|s| s.header.virtual_address as u64).min().unwrap_or(size_of_headers);
+
+        if rva < first_section.max(size_of_headers) {
+            return Ok(AddressLocation {
+                region: String::from("(headers)"),
+                region_offset: rva,
+                offset: (rva < size_of_headers).then_some(rva),
+                rva: Some(rva),
+                permissions: Some(permissions_string(true, false, false)),
+                ..AddressLocation::default()
+            });
+        }
+
+        let section = u32::try_from(rva)
+            .ok()
+            .and_then(|rva| pe.get_section_for_rva(rva))
+            .ok_or_else(|| format!("RVA {:#x} is outside of the image", rva))?;
+
+        let characteristics = section.header.characteristics;
+
+        return Ok(AddressLocation {
+            region: section.header.name.clone(),
+            region_offset: rva - section.header.virtual_address as u64,
+            offset: pe.convert_rva_to_file_offset(rva as u32),
+            rva: Some(rva),
+            permissions: Some(permissions_string(
+                (characteristics & SectionFlags::MemRead as u32) != 0,
+                (characteristics & SectionFlags::MemWrite as u32) != 0,
+                (characteristics & SectionFlags::MemExecute as u32) != 0,
+            )),
+            ..AddressLocation::default()
+        });
+    }
+
+    fn pe_from_offset(pe: &PE, offset: u64, file_size: u64) -> Result<AddressLocation, Box<dyn std::error::Error>> {
+        if offset >= file_size {
+            return Err(format!("Offset {:#x} is past the end of the file ({:#x} bytes)", offset, file_size).into());
+        }
+
+        if let Some(rva) = pe.convert_file_offset_to_rva(offset) {
+            let mut location = AddressLocation::pe_from_rva(pe, rva as u64)?;
+            location.offset = Some(offset);
+            return Ok(location);
+        }
+
+        /* The raw data past the virtual size of a section is in the file only */
+        let section = pe.sections.iter().find(|s| {
+            let start = s.header.ptr_to_raw_data as u64;
+            offset >= start && offset < start + s.header.size_of_raw_data as u64
+        });
+
+        let size_of_headers = pe.get_optional_header().get_size_of_headers() as u64;
+        let overlay_start = pe.sections.iter().map(|s| s.header.ptr_to_raw_data as u64 + s.header.size_of_raw_data as u64).max().unwrap_or(0).max(size_of_headers);
+
+        let (region, region_offset) = match section {
+            Some(section) => (section.header.name.clone(), offset - section.header.ptr_to_raw_data as u64),
+            None if offset < size_of_headers => return AddressLocation::pe_from_rva(pe, offset),
+            None if offset >= overlay_start => (String::from("(overlay)"), offset - overlay_start),
+            None => (String::from("(unmapped)"), 0),
+        };
+
+        return Ok(AddressLocation {
+            region,
+            region_offset,
+            offset: Some(offset),
+            ..AddressLocation::default()
+        });
+    }
+
+    /// ELF files have no image base, RVAs are taken as virtual addresses
+    pub fn locate_elf(elf: &ELF, kind: Option<AddressKind>, address: u64, file_size: u64) -> Result<AddressLocation, Box<dyn std::error::Error>> {
+        let mut location = match kind {
+            Some(AddressKind::Offset) => AddressLocation::elf_from_offset(elf, address, file_size)?,
+            _ => AddressLocation::elf_from_address(elf, address)?,
+        };
+
+        if let Some(virtual_address) = location.virtual_address {
+            let symbols = elf
+                .symbols
+                .iter()
+                .chain(elf.dynamic_symbols.iter())
+                .filter(|s| s.is_defined() && s.value != 0 && !s.name.is_empty())
+                .filter(|s| matches!(s.symbol_type(), STT_FUNC | STT_OBJECT | STT_GNU_IFUNC))
+                .map(|s| (s.value, s.name.clone()));
+
+            location.nearest_symbol = nearest_symbol(symbols, virtual_address);
+        }
+
+        return Ok(location);
+    }
+
+    fn elf_permissions(flags: u64) -> String {
+        return permissions_string(
+            (flags & ELFSectionFlags::Alloc as u64) != 0,
+            (flags & ELFSectionFlags::Write as u64) != 0,
+            (flags & ELFSectionFlags::ExecInstr as u64) != 0,
+        );
+    }
+
+    fn elf_from_address(elf: &ELF, address: u64) -> Result<AddressLocation, Box<dyn std::error::Error>> {
+        let section = elf.get_section_for_address(address).ok_or_else(|| format!("Address {:#x} is outside of the sections", address))?;
+        let region_offset = address - section.header.virtual_address();
+
+        return Ok(AddressLocation {
+            region: section.name.clone(),
+            region_offset,
+            offset: (section.header.section_type() != SectionType::Nobits).then(|| section.offset() + region_offset),
+            virtual_address: Some(address),
+            permissions: Some(AddressLocation::elf_permissions(section.header.flags())),
+            ..AddressLocation::default()
+        });
+    }
+
+    fn elf_from_offset(elf: &ELF, offset: u64, file_size: u64) -> Result<AddressLocation, Box<dyn std::error::Error>> {
+        if offset >= file_size {
+            return Err(format!("Offset {:#x} is past the end of the file ({:#x} bytes)", offset, file_size).into());
+        }
+
+        let section = elf
+            .sections
+            .values()
+            .filter(|s| s.header.section_type() != SectionType::Nobits && s.size() > 0)
+            .find(|s| offset >= s.offset() && offset < s.offset() + s.size());
+
+        if let Some(section) = section {
+            let region_offset = offset - section.offset();
+            let is_mapped = (section.header.flags() & ELFSectionFlags::Alloc as u64) != 0 && section.header.virtual_address() != 0;
+
+            return Ok(AddressLocation {
+                region: section.name.clone(),
+                region_offset,
+                offset: Some(offset),
+                virtual_address: is_mapped.then(|| section.header.virtual_address() + region_offset),
+                permissions: is_mapped.then(|| AddressLocation::elf_permissions(section.header.flags())),
+                ..AddressLocation::default()
+            });
+        }
+
+        let header = elf.get_elf_header();
+        let first_section = elf.sections.values().filter(|s| s.size() > 0 && s.offset() > 0).map(|s| s.offset()).min().unwrap_or(file_size);
+
+        /* The section header table usually comes last, after the section data */
+        let overlay_start = elf
+            .sections
+            .values()
+            .filter(|s| s.header.section_type() != SectionType::Nobits)
+            .map(|s| s.offset() + s.size())
+            .chain(std::iter::once(header.section_headers_offset() + header.section_headers_num_entries() * header.section_headers_entry_sz()))
+            .max()
+            .unwrap_or(0);
+
+        let (region, region_offset) = match offset {
+            o if o < first_section => (String::from("(headers)"), o),
+            o if o >= overlay_start => (String::from("(overlay)"), o - overlay_start),
+            _ => (String::from("(unmapped)"), 0),
+        };
+
+        return Ok(AddressLocation {
+            region,
+            region_offset,
+            offset: Some(offset),
+            ..AddressLocation::default()
+        });
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Where Is");
+
+        match self.region.as_str() {
+            "(unmapped)" => dump.push_field("Region", self.region.clone(), None),
+            _ => dump.push_field("Region", format!("{} + {:#x}", self.region, self.region_offset), None),
+        }
+
+        match self.offset {
+            Some(offset) => dump.push_field("Offset", format!("{:#x}", offset), None),
+            None => dump.push_field("Offset", String::from("None (zero-filled in memory)"), None),
+        }
+
+        if let Some(rva) = self.rva {
+            dump.push_field("Rva", format!("{:#x}", rva), None);
+        }
+
+        match self.virtual_address {
+            Some(virtual_address) => dump.push_field("VirtualAddress", format!("{:#x}", virtual_address), None),
+            None => dump.push_field("VirtualAddress", String::from("None (outside of the mapped sections)"), None),
+        }
+
+        if let Some(ref permissions) = self.permissions {
+            dump.push_field("Permissions", permissions.clone(), None);
+        }
+
+        match self.nearest_symbol {
+            Some((ref name, 0)) => dump.push_field("NearestSymbol", name.clone(), None),
+            Some((ref name, distance)) => dump.push_field("NearestSymbol", format!("{} + {:#x}", name, distance), None),
+            None if self.virtual_address.is_some() => dump.push_field("NearestSymbol", String::from("None"), None),
+            None => {}
+        }
+
+        return dump;
+    }
+}
+
+/*
+ * Hex dump of a file range
+ */
+
+/// "rva:0x1000:0x100", "off:0x400:64" or "0x140001000:256", the start is parsed like the --whereis addresses
+pub fn parse_hex_range(value: &str) -> Result<(Option<AddressKind>, u64, u64), Box<dyn std::error::Error>> {
+    let (start, length) = value.trim().rsplit_once(':').ok_or("Expected <address>:<length>")?;
+
+    let (kind, address) = parse_address_query(start)?;
+    let length = parse_address(length)?;
+
+    if length == 0 {
+        return Err("The length of the range is zero".into());
+    }
+
+    return Ok((kind, address, length));
+}
+
+/// RVA for PE, virtual address for ELF, None when the offset is not mapped in memory
+fn mapped_address(exec: &Exec, offset: u64, file_size: u64) -> Option<u64> {
+    let location = match exec {
+        Exec::PE(pe) => AddressLocation::pe_from_offset(pe, offset, file_size),
+        Exec::ELF(elf) => AddressLocation::elf_from_offset(elf, offset, file_size),
+    };
+
+    return location.ok().and_then(|l| l.rva.or(l.virtual_address));
+}
+
+/// Hex and ASCII view of a file range, with the file offsets and the RVAs (virtual addresses for ELF) in the gutter
+pub fn dump_hex_range(exec: &Exec, range: &str, file_bytes: &[u8]) -> Result<Dump, Box<dyn std::error::Error>> {
+    let (kind, address, length) = parse_hex_range(range)?;
+    let file_size = file_bytes.len() as u64;
+
+    let start = AddressLocation::locate_address(exec, kind, address, file_size)?;
+
+    let offset = start.offset.ok_or_else(|| format!("{:#x} is in the zero-filled part of {}, not backed by the file", address, start.region))?;
+    let end = offset.saturating_add(length).min(file_size);
+    let data = &file_bytes[offset as usize..end as usize];
+
+    let mut dump = Dump::new("Hexdump");
+
+    dump.push_field("Start", format!("{} + {:#x}", start.region, start.region_offset), None);
+
+    match data.len() as u64 == length {
+        true => dump.push_field("Size", format!("{:#x} bytes", length), None),
+        false => dump.push_field("Size", format!("{:#x} bytes, truncated at the end of the file", data.len()), None),
+    }
+
+    match exec {
+        Exec::PE(_) => dump.push_field("Gutter", String::from("file offset, RVA"), None),
+        Exec::ELF(_) => dump.push_field("Gutter", String::from("file offset, virtual address"), None),
+    }
+
+    dump.set_raw_data(DumpRawData::Code(format_hex_lines_with_gutter(data, |index| {
+        let memory_address = mapped_address(exec, offset + index, file_size).map(|a| format!("{:08X}", a));
+
+        format!("{:08X}  {}", offset + index, memory_address.unwrap_or_else(|| String::from("--------")))
+    })));
+
+    return Ok(dump);
+}
+
+/*
+ * Batch conversions of the addr subcommand
+ */
+
+#[derive(Debug, Clone)]
+pub struct AddressConversion {
+    /// "rva2off", "off2rva" or "va2rva"
+    pub label: &'static str,
+    pub input: String,
+    /// Converted address and the location of the input, or why it cannot be converted
+    pub result: Result<(u64, AddressLocation), String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AddressConversions {
+    pub conversions: Vec<AddressConversion>,
+}
+
+impl AddressConversion {
+    /// The address of the third kind, neither the input nor the converted one: the VA of the RVAs and offsets, the offset of the VAs
+    /// backed by the file. ELF has no RVAs, its offsets only convert to a virtual address
+    fn describe_third(&self, location: &AddressLocation) -> Option<String> {
+        match self.label {
+            "va2rva" => return location.offset.map(|offset| format!("off {:#x}", offset)),
+            _ => return location.rva.and(location.virtual_address).map(|virtual_address| format!("va {:#x}", virtual_address)),
+        }
+    }
+
+    fn convert(exec: &Exec, label: &'static str, kind: AddressKind, input: &str, file_size: u64) -> AddressConversion {
+        let result = parse_address(input).map_err(|e| format!("Invalid address \"{}\": {}", input, e)).and_then(|address| {
+            let location = AddressLocation::locate_address(exec, Some(kind), address, file_size).map_err(|e| e.to_string())?;
+
+            let converted = match kind {
+                AddressKind::Rva => location.offset.ok_or_else(|| format!("RVA {:#x} is zero-filled, not backed by the file", address))?,
+                AddressKind::Offset | AddressKind::VirtualAddress => location
+                    .rva
+                    .or(location.virtual_address)
+                    .ok_or_else(|| format!("Offset {:#x} in {} is not mapped in memory", address, location.region))?,
+            };
+
+            Ok((converted, location))
+        });
+
+        return AddressConversion {
+            label,
+            input: input.trim().to_string(),
+            result,
+        };
+    }
+}
+
+impl AddressConversions {
+    pub fn from_args(exec: &Exec, args: &AddrArgs, file_size: u64) -> AddressConversions {
+        let mut conversions = Vec::new();
+
+        for (label, kind, inputs) in [
+            ("rva2off", AddressKind::Rva, &args.rva_to_offset),
+            ("off2rva", AddressKind::Offset, &args.offset_to_rva),
+            ("va2rva", AddressKind::VirtualAddress, &args.va_to_rva),
+        ] {
+            for input in inputs.iter() {
+                conversions.push(AddressConversion::convert(exec, label, kind, input, file_size));
+            }
+        }
+
+        return AddressConversions { conversions };
+    }
+
+    pub fn failures(&self) -> usize {
+        return self.conversions.iter().filter(|c| c.result.is_err()).count();
+    }
+
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new_from_string(format!("Addresses ({})", self.conversions.len()));
+
+        for conversion in self.conversions.iter() {
+            match conversion.result {
+                Ok((address, ref location)) => {
+                    let region = format!("{} + {:#x}", location.region, location.region_offset);
+
+                    match conversion.describe_third(location) {
+                        Some(third) => dump.push_field("", format!("{} {} -> {:#x} ({}, {})", conversion.label, conversion.input, address, region, third), None),
+                        None => dump.push_field("", format!("{} {} -> {:#x} ({})", conversion.label, conversion.input, address, region), None),
+                    }
+                }
+                Err(ref e) => dump.push_field("", format!("{} {} -> error: {}", conversion.label, conversion.input, e), None),
+            }
+        }
+
+        return dump;
+    }
+}
@@ -0,0 +1,744 @@
+use crate::dump::Dump;
+use crate::elf::{ELFClass, ELFTargetISA, SectionFlags as ELFSectionFlags, SectionType, ELF};
+use crate::pe::{MachineType, SectionFlags, PE};
+
+use capstone::prelude::*;
+use strum_macros::IntoStaticStr;
+
+/*
+ * Entropy
+ */
+
+/// Shannon entropy of the given bytes, in bits per byte (0.0 to 8.0)
+pub fn shannon_entropy(data: &[u8]) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+
+    let mut counts = [0usize; 256];
+
+    for &byte in data {
+        counts[byte as usize] += 1;
+    }
+
+    let len = data.len() as f64;
+
+    return counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum();
+}
+
+/*
+ * Packing detection
+ */
+
+/// Entropy above which section data is most likely compressed or encrypted
+pub const HIGH_ENTROPY_THRESHOLD: f64 = 7.2;
+
+/// Sections smaller than this are too small for their entropy to mean anything
+pub const MIN_ENTROPY_SAMPLE_SIZE: usize = 512;
+
+/// Entropy of the section data, "7.93 (high, compressed or encrypted)" past the threshold,
+/// the resources are not marked as they commonly hold compressed images
+pub fn describe_section_entropy(data: &[u8], resources: bool) -> String {
+    let entropy = shannon_entropy(data);
+
+    if !resources && data.len() >= MIN_ENTROPY_SAMPLE_SIZE && entropy > HIGH_ENTROPY_THRESHOLD {
+        return format!("{:.2} (high, compressed or encrypted)", entropy);
+    }
+
+    return format!("{:.2}", entropy);
+}
+
+/// Linker generated ELF code sections laid out before .text, ignored when looking for the first code section
+const ELF_CODE_STUB_SECTIONS: [&str; 4] = [".init", ".plt", ".plt.got", ".plt.sec"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum PackingIndicator {
+    WritableExecutable,
+    HighEntropy,
+    VirtualSizeInflation,
+    EntryPointNotInFirstCodeSection,
+}
+
+#[derive(Debug, Clone)]
+pub struct PackingReason {
+    pub section: String,
+    pub indicator: PackingIndicator,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum PackedVerdict {
+    NotPacked,
+    PossiblyPacked,
+    LikelyPacked,
+}
+
+#[derive(Debug, Clone)]
+pub struct PackingReport {
+    pub verdict: PackedVerdict,
+    pub reasons: Vec<PackingReason>,
+}
+
+/// Format-agnostic view of a section, enough for the packing heuristics
+struct SectionProfile<'a> {
+    name: String,
+    virtual_address: u64,
+    virtual_size: u64,
+    raw_size: u64,
+    writable: bool,
+    executable: bool,
+    data: &'a [u8],
+}
+
+impl SectionProfile<'_> {
+    fn contains(&self, address: u64) -> bool {
+        return address >= self.virtual_address
+            && address < self.virtual_address + self.virtual_size.max(self.raw_size);
+    }
+}
+
+impl PackingReport {
+    fn from_profiles(
+        mut profiles: Vec<SectionProfile>,
+        entry_point: u64,
+        resource_section: Option<&str>,
+        code_stub_sections: &[&str],
+    ) -> PackingReport {
+        profiles.sort_by_key(|p| p.virtual_address);
+
+        let mut reasons = Vec::new();
+
+        for profile in profiles.iter() {
+            if profile.writable && profile.executable {
+                reasons.push(PackingReason {
+                    section: profile.name.clone(),
+                    indicator: PackingIndicator::WritableExecutable,
+                    detail: "section is both writable and executable".to_string(),
+                });
+            }
+
+            if profile.data.len() >= MIN_ENTROPY_SAMPLE_SIZE && Some(profile.name.as_str()) != resource_section {
+                let entropy = shannon_entropy(profile.data);
+
+                if entropy > HIGH_ENTROPY_THRESHOLD {
+                    reasons.push(PackingReason {
+                        section: profile.name.clone(),
+                        indicator: PackingIndicator::HighEntropy,
+                        detail: format!("entropy of {:.2} bits per byte", entropy),
+                    });
+                }
+            }
+
+            if profile.executable && profile.virtual_size >= 0x1000 && profile.raw_size * 8 < profile.virtual_size {
+                reasons.push(PackingReason {
+                    section: profile.name.clone(),
+                    indicator: PackingIndicator::VirtualSizeInflation,
+                    detail: format!(
+                        "virtual size of {:#x} backed by only {:#x} bytes of raw data",
+                        profile.virtual_size, profile.raw_size
+                    ),
+                });
+            }
+        }
+
+        let first_code_section = profiles
+            .iter()
+            .find(|p| p.executable && !code_stub_sections.contains(&p.name.as_str()));
+        let entry_section = profiles.iter().find(|p| p.contains(entry_point));
+
+        if let (Some(first), Some(entry)) = (first_code_section, entry_section) && first.virtual_address != entry.virtual_address {
+            reasons.push(PackingReason {
+                section: entry.name.clone(),
+                indicator: PackingIndicator::EntryPointNotInFirstCodeSection,
+                detail: format!(
+                    "contains the entry point ({:#x}) but the first code section is {}",
+                    entry_point, first.name
+                ),
+            });
+        }
+
+        let mut indicators: Vec<PackingIndicator> = reasons.iter().map(|r| r.indicator).collect();
+        indicators.sort_by_key(|i| *i as u8);
+        indicators.dedup();
+
+        /* A single kind of indicator (e.g. high entropy from compressed data) is common in legit binaries */
+        let verdict = match indicators.as_slice() {
+            [] => PackedVerdict::NotPacked,
+            [_] => PackedVerdict::PossiblyPacked,
+            _ => PackedVerdict::LikelyPacked,
+        };
+
+        return PackingReport { verdict, reasons };
+    }
+
+    pub fn is_packed(&self) -> bool {
+        return self.verdict == PackedVerdict::LikelyPacked;
+    }
+
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Packing Analysis");
+
+        let verdict: &'static str = self.verdict.into();
+        dump.push_field("Verdict", verdict.to_string(), None);
+
+        let mut reasons_dump = Dump::new_from_string(format!("Reasons ({})", self.reasons.len()));
+
+        for reason in self.reasons.iter() {
+            let indicator: &'static str = reason.indicator.into();
+            reasons_dump.push_field("", format!("{}: {} ({})", reason.section, reason.detail, indicator), None);
+        }
+
+        dump.push_child(reasons_dump);
+
+        return dump;
+    }
+}
+
+pub fn analyze_pe_packing(pe: &PE) -> PackingReport {
+    let profiles = pe
+        .sections
+        .iter()
+        .map(|section| SectionProfile {
+            name: section.header.name.clone(),
+            virtual_address: section.header.virtual_address as u64,
+            virtual_size: section.header.virtual_size as u64,
+            raw_size: section.header.size_of_raw_data as u64,
+            writable: (section.header.characteristics & SectionFlags::MemWrite as u32) != 0,
+            executable: section.contains_code()
+                || (section.header.characteristics & SectionFlags::MemExecute as u32) != 0,
+            data: section.raw_data(),
+        })
+        .collect();
+
+    let entry_point = pe.get_optional_header().get_address_of_entry_point() as u64;
+
+    return PackingReport::from_profiles(profiles, entry_point, Some(".rsrc"), &[]);
+}
+
+pub fn analyze_elf_packing(elf: &ELF) -> PackingReport {
+    let profiles = elf
+        .sections
+        .values()
+        .filter(|section| section.header.virtual_address() != 0)
+        .map(|section| {
+            let no_bits = section.header.section_type() == SectionType::Nobits;
+
+            SectionProfile {
+                name: section.name.clone(),
+                virtual_address: section.header.virtual_address(),
+                virtual_size: section.size(),
+                raw_size: if no_bits { 0 } else { section.size() },
+                writable: (section.header.flags() & ELFSectionFlags::Write as u64) != 0,
+                executable: (section.header.flags() & ELFSectionFlags::ExecInstr as u64) != 0,
+                data: section.data.as_slice(),
+            }
+        })
+        .collect();
+
+    return PackingReport::from_profiles(
+        profiles,
+        elf.get_elf_header().entry_point(),
+        None,
+        &ELF_CODE_STUB_SECTIONS,
+    );
+}
+
+/*
+ * Embedded files identification
+ */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum EmbeddedFileType {
+    Zip,
+    SevenZip,
+    Rar,
+    Gzip,
+    Cab,
+    Png,
+    Pdf,
+    PE,
+    ELF,
+    Certificate,
+}
+
+/// Size of the windows used to look for high-entropy blobs
+const ENTROPY_WINDOW_SIZE: usize = 4096;
+
+fn read_u32_le(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    return Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+}
+
+/// Identifies the file type starting at the beginning of the data, along with its size when the format records it
+pub fn identify_file_type(data: &[u8]) -> Option<(EmbeddedFileType, Option<usize>)> {
+    if data.starts_with(b"PK\x03\x04") {
+        /* Local file header sanity checks (version needed, compression method, file name length) */
+        let version = u16::from_le_bytes([*data.get(4)?, *data.get(5)?]);
+        let method = u16::from_le_bytes([*data.get(8)?, *data.get(9)?]);
+        let name_length = u16::from_le_bytes([*data.get(26)?, *data.get(27)?]);
+
+        if version <= 63 && matches!(method, 0 | 8 | 9 | 12 | 14 | 93 | 95 | 98 | 99) && name_length > 0 {
+            return Some((EmbeddedFileType::Zip, None));
+        }
+
+        return None;
+    }
+
+    if data.starts_with(b"7z\xbc\xaf\x27\x1c") {
+        return Some((EmbeddedFileType::SevenZip, None));
+    }
+
+    if data.starts_with(b"Rar!\x1a\x07") {
+        return Some((EmbeddedFileType::Rar, None));
+    }
+
+    if data.starts_with(b"\x1f\x8b\x08") {
+        return Some((EmbeddedFileType::Gzip, None));
+    }
+
+    if data.starts_with(b"MSCF\0\0\0\0") {
+        let size = read_u32_le(data, 8).map(|s| s as usize);
+        return Some((EmbeddedFileType::Cab, size));
+    }
+
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some((EmbeddedFileType::Png, None));
+    }
+
+    if data.starts_with(b"%PDF-") {
+        return Some((EmbeddedFileType::Pdf, None));
+    }
+
+    if data.starts_with(b"MZ") {
+        /* Requires a valid e_lfanew pointing to the NT signature, "MZ" alone is way too common */
+        let e_lfanew = read_u32_le(data, 0x3c)? as usize;
+
+        if e_lfanew >= 0x40 && data.get(e_lfanew..e_lfanew + 4) == Some(b"PE\0\0".as_slice()) {
+            return Some((EmbeddedFileType::PE, None));
+        }
+
+        return None;
+    }
+
+    if data.starts_with(b"\x7fELF")
+        && data.len() > 6
+        && matches!(data[4], 1 | 2)
+        && matches!(data[5], 1 | 2)
+        && data[6] == 1
+    {
+        return Some((EmbeddedFileType::ELF, None));
+    }
+
+    /* DER encoded X.509: SEQUENCE { SEQUENCE (TBSCertificate) { [0] { INTEGER 2 (v3) } ... */
+    if data.len() > 13
+        && data[0..2] == [0x30, 0x82]
+        && data[4..6] == [0x30, 0x82]
+        && data[8..13] == [0xa0, 0x03, 0x02, 0x01, 0x02]
+    {
+        let size = u16::from_be_bytes([data[2], data[3]]) as usize + 4;
+        return Some((EmbeddedFileType::Certificate, Some(size)));
+    }
+
+    if data.starts_with(b"-----BEGIN CERTIFICATE-----") {
+        return Some((EmbeddedFileType::Certificate, None));
+    }
+
+    return None;
+}
+
+#[derive(Debug, Clone)]
+pub struct EmbeddedFile {
+    pub location: String,
+    pub file_offset: u64,
+    pub file_type: EmbeddedFileType,
+    pub size: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HighEntropyBlob {
+    pub location: String,
+    pub file_offset: u64,
+    pub size: usize,
+    pub entropy: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EmbeddedReport {
+    pub files: Vec<EmbeddedFile>,
+    pub blobs: Vec<HighEntropyBlob>,
+}
+
+impl EmbeddedReport {
+    /// Looks for known file signatures anywhere in the data
+    fn scan_signatures(&mut self, data: &[u8], location: &str, file_offset: u64) {
+        let mut offset = 0;
+
+        while offset < data.len() {
+            if let Some((file_type, size)) = identify_file_type(&data[offset..]) {
+                self.files.push(EmbeddedFile {
+                    location: format!("{}+{:#x}", location, offset),
+                    file_offset: file_offset + offset as u64,
+                    file_type,
+                    size,
+                });
+
+                /* Don't report the content of an embedded file whose extent is known */
+                offset += size.unwrap_or(1).max(1);
+            } else {
+                offset += 1;
+            }
+        }
+    }
+
+    /// Merges consecutive high-entropy windows into blobs
+    fn scan_entropy(&mut self, data: &[u8], location: &str, file_offset: u64) {
+        let mut blob_start: Option<usize> = None;
+
+        for (i, window) in data.chunks(ENTROPY_WINDOW_SIZE).enumerate() {
+            let high = window.len() >= MIN_ENTROPY_SAMPLE_SIZE && shannon_entropy(window) > HIGH_ENTROPY_THRESHOLD;
+            let start = i * ENTROPY_WINDOW_SIZE;
+
+            match (high, blob_start) {
+                (true, None) => blob_start = Some(start),
+                (false, Some(blob)) => {
+                    self.push_blob(&data[blob..start], location, file_offset, blob);
+                    blob_start = None;
+                },
+                _ => {},
+            }
+        }
+
+        if let Some(blob) = blob_start {
+            self.push_blob(&data[blob..], location, file_offset, blob);
+        }
+    }
+
+    fn push_blob(&mut self, blob: &[u8], location: &str, file_offset: u64, offset: usize) {
+        self.blobs.push(HighEntropyBlob {
+            location: format!("{}+{:#x}", location, offset),
+            file_offset: file_offset + offset as u64,
+            size: blob.len(),
+            entropy: shannon_entropy(blob),
+        });
+    }
+
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Embedded Data");
+
+        let mut files_dump = Dump::new_from_string(format!("Embedded Files ({})", self.files.len()));
+
+        for file in self.files.iter() {
+            let file_type: &'static str = file.file_type.into();
+
+            let mut file_dump = Dump::new(file_type);
+            file_dump.push_field("Location", file.location.clone(), None);
+            file_dump.push_field("FileOffset", format!("{:#x}", file.file_offset), None);
+
+            if let Some(size) = file.size {
+                file_dump.push_field("Size", format!("{:#x} ({} bytes)", size, size), None);
+            }
+
+            files_dump.push_child(file_dump);
+        }
+
+        dump.push_child(files_dump);
+
+        let mut blobs_dump = Dump::new_from_string(format!("High Entropy Blobs ({})", self.blobs.len()));
+
+        for blob in self.blobs.iter() {
+            blobs_dump.push_field(
+                "",
+                format!(
+                    "{} (file offset {:#x}, {} bytes, entropy {:.2})",
+                    blob.location, blob.file_offset, blob.size, blob.entropy
+                ),
+                None,
+            );
+        }
+
+        dump.push_child(blobs_dump);
+
+        return dump;
+    }
+}
+
+pub fn find_pe_embedded_files(pe: &PE) -> EmbeddedReport {
+    let mut report = EmbeddedReport::default();
+
+    let mut sections: Vec<_> = pe.sections.iter().collect();
+    sections.sort_by_key(|s| s.header.virtual_address);
+
+    let resource_va = pe.get_optional_header().get_resource_table_idd().virtual_address;
+
+    for section in sections {
+        let start = section.header.virtual_address;
+        let end = start + section.header.virtual_size.max(section.header.size_of_raw_data);
+
+        /* The resource section is covered entry by entry below */
+        if pe.resource_table.is_some() && resource_va >= start && resource_va < end {
+            continue;
+        }
+
+        let location = format!("section {}", section.header.name);
+        let file_offset = section.header.ptr_to_raw_data as u64;
+
+        report.scan_signatures(section.raw_data(), &location, file_offset);
+        report.scan_entropy(section.raw_data(), &location, file_offset);
+    }
+
+    if let Some(ref resource_table) = pe.resource_table {
+        for entry in resource_table.entries.iter() {
+            let location = format!("resource {}", entry.path());
+            let file_offset = pe.convert_rva_to_file_offset(entry.data_entry.data_rva).unwrap_or(0);
+
+            report.scan_signatures(&entry.data, &location, file_offset);
+            report.scan_entropy(&entry.data, &location, file_offset);
+        }
+    }
+
+    return report;
+}
+
+pub fn find_elf_embedded_files(elf: &ELF) -> EmbeddedReport {
+    let mut report = EmbeddedReport::default();
+
+    let mut sections: Vec<_> = elf.sections.values().collect();
+    sections.sort_by_key(|s| s.offset());
+
+    for section in sections {
+        let location = format!("section {}", section.name);
+
+        report.scan_signatures(&section.data, &location, section.offset());
+        report.scan_entropy(&section.data, &location, section.offset());
+    }
+
+    return report;
+}
+
+/*
+ * Anti-analysis detection
+ */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum AntiAnalysisCategory {
+    AntiDebug,
+    AntiVM,
+    AntiDump,
+}
+
+#[derive(Debug, Clone)]
+pub struct AntiAnalysisFinding {
+    pub technique: &'static str,
+    pub category: AntiAnalysisCategory,
+    pub locations: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AntiAnalysisReport {
+    pub findings: Vec<AntiAnalysisFinding>,
+}
+
+/* Maximum number of locations printed per finding */
+const MAX_PRINTED_LOCATIONS: usize = 16;
+
+/* Two rdtsc closer than this (in instructions) are considered a timing check */
+const RDTSC_TIMING_WINDOW: usize = 64;
+
+#[rustfmt::skip]
+const ANTI_DEBUG_IMPORTS: &[&str] = &[
+    "IsDebuggerPresent",
+    "CheckRemoteDebuggerPresent",
+    "NtQueryInformationProcess",
+    "ZwQueryInformationProcess",
+    "NtSetInformationThread",
+    "ZwSetInformationThread",
+    "NtQueryObject",
+    "OutputDebugStringA",
+    "OutputDebugStringW",
+    "DebugActiveProcess",
+    "BlockInput",
+];
+
+impl AntiAnalysisReport {
+    fn push(&mut self, technique: &'static str, category: AntiAnalysisCategory, location: String) {
+        match self.findings.iter_mut().find(|f| f.technique == technique) {
+            Some(finding) => finding.locations.push(location),
+            None => self.findings.push(AntiAnalysisFinding {
+                technique,
+                category,
+                locations: vec![location],
+            }),
+        }
+    }
+
+    /// Looks for instructions commonly used to detect debuggers and virtual machines
+    fn scan_code(&mut self, cs: &Capstone, code: &[u8], address: u64) {
+        let mut last_rdtsc: Option<(usize, u64)> = None;
+        let mut offset = 0;
+        let mut i = 0;
+
+        /* Capstone stops at the first undecodable byte, resume right after it */
+        while offset < code.len() {
+            let Ok(instructions) = cs.disasm_all(&code[offset..], address + offset as u64) else {
+                return;
+            };
+
+            match instructions.iter().last() {
+                Some(last) => offset = (last.address() - address) as usize + last.len(),
+                None => {
+                    offset += 1;
+                    continue;
+                },
+            }
+
+            for insn in instructions.iter() {
+                i += 1;
+                self.scan_instruction(insn, i, &mut last_rdtsc);
+            }
+        }
+    }
+
+    fn scan_instruction(&mut self, insn: &capstone::Insn, i: usize, last_rdtsc: &mut Option<(usize, u64)>) {
+        let location = format!("{:#x}", insn.address());
+
+        match (insn.mnemonic().unwrap_or(""), insn.op_str().unwrap_or("")) {
+            ("rdtsc" | "rdtscp", _) => {
+                if let Some((previous, previous_address)) = *last_rdtsc && i - previous <= RDTSC_TIMING_WINDOW {
+                    self.push("rdtsc timing check", AntiAnalysisCategory::AntiDebug, format!("{:#x}", previous_address));
+                }
+
+                *last_rdtsc = Some((i, insn.address()));
+            },
+            ("cpuid", _) => self.push("cpuid (hypervisor/vendor check)", AntiAnalysisCategory::AntiVM, location),
+            ("int", "0x2d") => self.push("int 0x2d debugger trap", AntiAnalysisCategory::AntiDebug, location),
+            ("int1", _) => self.push("int1/icebp debugger trap", AntiAnalysisCategory::AntiDebug, location),
+            ("sidt" | "sgdt" | "sldt" | "str", _) => {
+                self.push("Descriptor table check (red pill)", AntiAnalysisCategory::AntiVM, location)
+            },
+            ("mov", op) if op.ends_with("0x564d5868") => {
+                self.push("VMware backdoor port (VMXh)", AntiAnalysisCategory::AntiVM, location)
+            },
+            _ => {},
+        }
+    }
+
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new_from_string(format!("Anti-Analysis ({})", self.findings.len()));
+
+        for finding in self.findings.iter() {
+            let mut finding_dump = Dump::new(finding.technique);
+
+            let category: &'static str = finding.category.into();
+            finding_dump.push_field("Category", category.to_string(), None);
+
+            let mut locations = finding.locations.iter().take(MAX_PRINTED_LOCATIONS).cloned().collect::<Vec<_>>().join(", ");
+
+            if finding.locations.len() > MAX_PRINTED_LOCATIONS {
+                locations.push_str(&format!(" (+{} more)", finding.locations.len() - MAX_PRINTED_LOCATIONS));
+            }
+
+            finding_dump.push_field("Locations", locations, None);
+
+            dump.push_child(finding_dump);
+        }
+
+        return dump;
+    }
+}
+
+pub fn x86_capstone(is_32_bits: bool) -> Option<Capstone> {
+    let mode = match is_32_bits {
+        true => arch::x86::ArchMode::Mode32,
+        false => arch::x86::ArchMode::Mode64,
+    };
+
+    return Capstone::new().x86().mode(mode).syntax(arch::x86::ArchSyntax::Intel).build().ok();
+}
+
+pub fn detect_pe_anti_analysis(pe: &PE) -> AntiAnalysisReport {
+    let mut report = AntiAnalysisReport::default();
+
+    if let Some(ref tls) = pe.tls_directory {
+        for callback in tls.callbacks.iter() {
+            report.push("TLS callback (runs before the entry point)", AntiAnalysisCategory::AntiDebug, format!("{:#x}", callback));
+        }
+    }
+
+    if let Some(ref hnt) = pe.hint_name_table {
+        for dll in hnt.entries.iter() {
+            for entry in dll.entries.iter() {
+                if let Some(api) = ANTI_DEBUG_IMPORTS.iter().find(|api| **api == entry.name) {
+                    report.push("Debugger detection import", AntiAnalysisCategory::AntiDebug, format!("{}!{}", dll.dll_name, api));
+                }
+            }
+        }
+    }
+
+    /* SizeOfImage that disagrees with the section layout breaks memory dumpers */
+    let alignment = pe.get_optional_header().get_section_alignment().max(1) as u64;
+
+    let computed_size_of_image = pe
+        .sections
+        .iter()
+        .map(|s| s.header.virtual_address as u64 + (s.header.virtual_size as u64).max(1))
+        .max()
+        .map(|end| end.div_ceil(alignment) * alignment)
+        .unwrap_or(0);
+
+    let size_of_image = pe.get_optional_header().get_size_of_image() as u64;
+
+    if computed_size_of_image != 0 && size_of_image != computed_size_of_image {
+        report.push(
+            "SizeOfImage mismatch",
+            AntiAnalysisCategory::AntiDump,
+            format!("header {:#x}, sections end at {:#x}", size_of_image, computed_size_of_image),
+        );
+    }
+
+    let machine: MachineType = pe.get_nt_header().coff_header.machine.into();
+
+    if matches!(machine, MachineType::I386 | MachineType::AMD64) && let Some(cs) = x86_capstone(pe.is_32_bits()) {
+        let image_base = pe.get_optional_header().get_image_base();
+
+        let mut sections: Vec<_> = pe.sections.iter().filter(|s| s.contains_code()).collect();
+        sections.sort_by_key(|s| s.header.virtual_address);
+
+        for section in sections {
+            report.scan_code(&cs, section.raw_data(), image_base + section.header.virtual_address as u64);
+        }
+    }
+
+    return report;
+}
+
+pub fn detect_elf_anti_analysis(elf: &ELF) -> AntiAnalysisReport {
+    let mut report = AntiAnalysisReport::default();
+
+    let machine = elf.get_elf_header().machine();
+
+    if machine != ELFTargetISA::X86 as u16 && machine != ELFTargetISA::AMDX86_64 as u16 {
+        return report;
+    }
+
+    if let Some(cs) = x86_capstone(matches!(elf.class(), ELFClass::ELF32)) {
+        let mut sections: Vec<_> = elf.sections.values().filter(|s| s.contains_code()).collect();
+        sections.sort_by_key(|s| s.header.virtual_address());
+
+        for section in sections {
+            report.scan_code(&cs, &section.data, section.header.virtual_address());
+        }
+    }
+
+    return report;
+}
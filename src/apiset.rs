@@ -0,0 +1,139 @@
+/*
+ * API set contracts (api-ms-win-*, ext-ms-win-*), virtual DLL names the loader redirects to their host DLL
+ * https://learn.microsoft.com/en-us/windows/win32/apiindex/windows-apisets
+ */
+
+/* Hosts of the Windows 10/11 schema, by contract name prefix: the longest prefix matching the contract wins.
+ * Contracts are matched without their version ("-l1-2-0"), the schema maps every version of a contract to the same host */
+const API_SET_SCHEMA: &[(&str, &str)] = &[
+    ("api-ms-win-appmodel-runtime", "kernel.appcore.dll"),
+    ("api-ms-win-appmodel-state", "kernel.appcore.dll"),
+    ("api-ms-win-base-util", "advapi32.dll"),
+    ("api-ms-win-com-", "combase.dll"),
+    ("api-ms-win-core-", "kernelbase.dll"),
+    ("api-ms-win-core-apiquery", "ntdll.dll"),
+    ("api-ms-win-core-atoms", "kernel32.dll"),
+    ("api-ms-win-core-com-", "combase.dll"),
+    ("api-ms-win-core-comm-", "kernelbase.dll"),
+    ("api-ms-win-core-crt", "ntdll.dll"),
+    ("api-ms-win-core-delayload", "kernelbase.dll"),
+    ("api-ms-win-core-kernel32-legacy", "kernel32.dll"),
+    ("api-ms-win-core-kernel32-private", "kernel32.dll"),
+    ("api-ms-win-core-marshal", "combase.dll"),
+    ("api-ms-win-core-privateprofile", "kernel32.dll"),
+    ("api-ms-win-core-psm-appnotify", "twinapi.appcore.dll"),
+    ("api-ms-win-core-rtlsupport", "ntdll.dll"),
+    ("api-ms-win-core-url", "kernelbase.dll"),
+    ("api-ms-win-core-winrt", "combase.dll"),
+    ("api-ms-win-core-wow64", "kernelbase.dll"),
+    ("api-ms-win-crt-", "ucrtbase.dll"),
+    ("api-ms-win-devices-config", "cfgmgr32.dll"),
+    ("api-ms-win-devices-query", "cfgmgr32.dll"),
+    ("api-ms-win-downlevel-advapi32", "advapi32.dll"),
+    ("api-ms-win-downlevel-kernel32", "kernel32.dll"),
+    ("api-ms-win-downlevel-normaliz", "normaliz.dll"),
+    ("api-ms-win-downlevel-ole32", "combase.dll"),
+    ("api-ms-win-downlevel-shell32", "shell32.dll"),
+    ("api-ms-win-downlevel-shlwapi", "shlwapi.dll"),
+    ("api-ms-win-downlevel-user32", "user32.dll"),
+    ("api-ms-win-downlevel-version", "version.dll"),
+    ("api-ms-win-eventing-classicprovider", "kernelbase.dll"),
+    ("api-ms-win-eventing-consumer", "sechost.dll"),
+    ("api-ms-win-eventing-controller", "sechost.dll"),
+    ("api-ms-win-eventing-legacy", "advapi32.dll"),
+    ("api-ms-win-eventing-obsolete", "sechost.dll"),
+    ("api-ms-win-eventing-provider", "kernelbase.dll"),
+    ("api-ms-win-eventing-tdh", "tdh.dll"),
+    ("api-ms-win-gdi-", "gdi32.dll"),
+    ("api-ms-win-mm-", "winmm.dll"),
+    ("api-ms-win-ntuser-", "user32.dll"),
+    ("api-ms-win-ole32-ie", "ole32.dll"),
+    ("api-ms-win-power-", "powrprof.dll"),
+    ("api-ms-win-rtcore-ntuser-", "user32.dll"),
+    ("api-ms-win-security-activedirectoryclient", "advapi32.dll"),
+    ("api-ms-win-security-appcontainer", "kernelbase.dll"),
+    ("api-ms-win-security-audit", "sechost.dll"),
+    ("api-ms-win-security-base", "kernelbase.dll"),
+    ("api-ms-win-security-capability", "sechost.dll"),
+    ("api-ms-win-security-credentials", "sechost.dll"),
+    ("api-ms-win-security-cryptoapi", "cryptsp.dll"),
+    ("api-ms-win-security-grouppolicy", "kernelbase.dll"),
+    ("api-ms-win-security-lsalookup", "sechost.dll"),
+    ("api-ms-win-security-lsapolicy", "sechost.dll"),
+    ("api-ms-win-security-provider", "ntmarta.dll"),
+    ("api-ms-win-security-sddl", "sechost.dll"),
+    ("api-ms-win-security-systemfunctions", "advapi32.dll"),
+    ("api-ms-win-security-trustee", "advapi32.dll"),
+    ("api-ms-win-service-", "sechost.dll"),
+    ("api-ms-win-service-private", "sechost.dll"),
+    ("api-ms-win-shcore-", "shcore.dll"),
+    ("api-ms-win-shell-namespace", "windows.storage.dll"),
+    ("api-ms-win-shell-shellcom", "kernelbase.dll"),
+    ("api-ms-win-shell-shellfolders", "windows.storage.dll"),
+    ("api-ms-win-shlwapi-", "kernelbase.dll"),
+    ("api-ms-win-stateseparation-helpers", "kernelbase.dll"),
+    ("api-ms-win-storage-exports-internal", "windows.storage.dll"),
+    ("api-ms-win-winrt-", "combase.dll"),
+    ("ext-ms-win-advapi32-", "advapi32.dll"),
+    ("ext-ms-win-com-ole32", "ole32.dll"),
+    ("ext-ms-win-gdi-", "gdi32full.dll"),
+    ("ext-ms-win-kernel32-", "kernel32.dll"),
+    ("ext-ms-win-ntuser-", "user32.dll"),
+    ("ext-ms-win-ole32-", "ole32.dll"),
+    ("ext-ms-win-oleacc-", "oleacc.dll"),
+    ("ext-ms-win-rtcore-gdi-", "gdi32.dll"),
+    ("ext-ms-win-rtcore-ntuser-", "user32.dll"),
+    ("ext-ms-win-security-cryptui", "cryptui.dll"),
+    ("ext-ms-win-session-usermgr", "usermgrcli.dll"),
+    ("ext-ms-win-shell-comdlg32", "comdlg32.dll"),
+    ("ext-ms-win-shell-shell32", "shell32.dll"),
+    ("ext-ms-win-shell32-", "shell32.dll"),
+    ("ext-ms-win-uxtheme-", "uxtheme.dll"),
+    ("ext-ms-win-version-", "version.dll"),
+];
+
+/// API set contracts are virtual DLLs redirected by the loader, they never exist on disk
+pub fn is_api_set(dll_name: &str) -> bool {
+    let lower = dll_name.to_ascii_lowercase();
+
+    return lower.starts_with("api-ms-") || lower.starts_with("ext-ms-");
+}
+
+/// Contract name without its extension and version, "api-ms-win-core-synch-l1-2-0.dll" becomes "api-ms-win-core-synch"
+pub fn contract_name(dll_name: &str) -> String {
+    let lower = dll_name.to_ascii_lowercase();
+    let stem = lower.strip_suffix(".dll").unwrap_or(&lower);
+
+    /* The version starts at the "-l<level>" part, the last one as some contract names hold digits */
+    let mut parts: Vec<&str> = stem.split('-').collect();
+
+    if let Some(level) = parts.iter().rposition(|p| p.len() > 1 && p.starts_with('l') && p[1..].chars().all(|c| c.is_ascii_digit())) {
+        parts.truncate(level);
+    }
+
+    return parts.join("-");
+}
+
+/// Host DLL the loader redirects the contract to, None for the names that are not contracts or are unknown to the bundled schema
+pub fn resolve_api_set(dll_name: &str) -> Option<&'static str> {
+    if !is_api_set(dll_name) {
+        return None;
+    }
+
+    let contract = contract_name(dll_name);
+
+    return API_SET_SCHEMA
+        .iter()
+        .filter(|(prefix, _)| contract.starts_with(prefix) || contract == prefix.trim_end_matches('-'))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, host)| *host);
+}
+
+/// "name -> host" for the contracts, the name alone otherwise
+pub fn display_with_host(dll_name: &str) -> String {
+    match (is_api_set(dll_name), resolve_api_set(dll_name)) {
+        (true, Some(host)) => return format!("{} -> {}", dll_name, host),
+        (true, None) => return format!("{} -> unknown host", dll_name),
+        _ => return String::from(dll_name),
+    }
+}
@@ -1,31 +1,75 @@
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 
 use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
-#[command(version, about = "Parser/Dumper for portable executable files on Windows")]
+#[command(
+    version,
+    about = "Parser/Dumper for portable executable files on Windows",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Opens the executable in the terminal-based user interface for exploration
     #[arg(long, short, default_value_t = false)]
     pub tui: bool,
 
+    /// Prints a one-screen overview: format, architecture, entry point and its section, signed/packed/managed verdicts, the packer or protector identified (UPX, ASPack, Themida, MPRESS, PECompact), the likely toolchain (MSVC, MinGW-GCC, Clang/LLD, Delphi, Go), counts and hashes (with the ssdeep, TLSH, imphash and Rich header hash)
+    #[arg(long, default_value_t = false)]
+    pub summary: bool,
+
+    /// Dumps the MD5, SHA-1 and SHA-256 of the file and of the raw data of every section, with the Rich header hash of the PE
+    #[arg(long, default_value_t = false)]
+    pub hashes: bool,
+
+    /// Dumps the ssdeep and TLSH similarity hashes of the file and of the raw data of the executable sections
+    #[arg(long, default_value_t = false)]
+    pub fuzzy_hashes: bool,
+
+    /// Dumps the Go build information (version, main module, dependencies, build settings), the rustc release and crates of Rust binaries and the packages and units of Delphi binaries
+    #[arg(long, default_value_t = false)]
+    pub buildinfo: bool,
+
     /*
      * PE
      */
 
+    /// Parses the input as a module dumped from process memory: the sections are read at their virtual address instead of their raw pointer, the certificates and the COFF symbols, never mapped, are skipped
+    #[arg(long, default_value_t = false)]
+    pub memory_image: bool,
+
     /// Dumps the PE legacy MS-DOS compatible header
     #[arg(long, default_value_t = false)]
     pub pe_dos_header: bool,
 
+    /// Dumps the MS-DOS stub run in place of the PE under MS-DOS: its message, whether it is the standard one, and its bytes or its 16-bit disassembly with --disasm
+    #[arg(long, default_value_t = false)]
+    pub dos_stub: bool,
+
     /// Dumps the PE NT Header (most recent)
     #[arg(long, default_value_t = false)]
     pub pe_nt_header: bool,
 
+    /// Dumps the TE header of the UEFI Terse Executable images, which replaces their DOS and NT headers
+    #[arg(long, default_value_t = false)]
+    pub te_header: bool,
+
     /// Dumps the PE Optional (either 32/64) header
     #[arg(long, default_value_t = false)]
     pub pe_optional_header: bool,
 
-    /// Dump all the PE data related to imports, if any
+    /// Resolves the entry point to its section and file offset, warns when it is outside of the executable sections or inside the headers, disassembled with --disasm
+    #[arg(long, default_value_t = false)]
+    pub entry: bool,
+
+    /// Number of instructions disassembled at the entry point by --entry --disasm
+    #[arg(long, default_value_t = 32)]
+    pub entry_instructions: usize,
+
+    /// Dump the PE imports grouped by DLL (bind timestamp, functions with their hint or ordinal and IAT slot, the ordinals of the common system DLLs resolved to names) and the imphash, if any
     #[arg(long, default_value_t = false)]
     pub pe_import: bool,
 
@@ -33,22 +77,186 @@ pub struct Args {
     #[arg(long, default_value_t = false)]
     pub pe_import_directory_table: bool,
 
+    /// Dump the Import Lookup Tables as stored, if any
+    #[arg(long, default_value_t = false)]
+    pub pe_import_lookup_table: bool,
+
     /// Dump the Hint/Name Table, if any
     #[arg(long, default_value_t = false)]
     pub pe_hint_name_table: bool,
 
-    /// Dump the DLLs names imported, if any
+    /// Dump the DLLs names imported with the host DLL of the API set contracts, if any
     #[arg(long, default_value_t = false)]
     pub pe_dlls: bool,
 
-    /// Dump the debug information from the Debug Directory, if any
+    /// Dumps the Import Address Table directory, every slot with its thunk and the function it resolves to, from the import descriptors or the thunk itself when the lookup table is stripped, if any
+    #[arg(long, default_value_t = false)]
+    pub iat: bool,
+
+    /// Dumps the exported functions with their ordinal and RVA (or forwarder), and the export timestamp, if any
+    #[arg(long, default_value_t = false)]
+    pub exports: bool,
+
+    /// Dump every entry of the Debug Directory with its decoded CodeView, POGO, REPRO and VC_FEATURE data, if any
     #[arg(long, default_value_t = false)]
     pub pe_debug_directory: bool,
 
-    /// Dump the exception information from the Exception Table, if any
+    /// Dump the exception information from the Exception Table with the decoded x64 unwind info (prolog codes, frame register, handler and chained entries) and the ARM64 packed unwind data, if any
     #[arg(long, default_value_t = false)]
     pub pe_exc_table: bool,
 
+    /// Dumps the TLS Directory and its callbacks with their section and symbol, disassembled with --disasm, if any
+    #[arg(long, alias = "pe-tls", default_value_t = false)]
+    pub tls: bool,
+
+    /// Dumps the Load Config Directory: security cookie, SEH table, Control Flow Guard fields and flags, dependent load flags, if any
+    #[arg(long, default_value_t = false)]
+    pub load_config: bool,
+
+    /// Dumps the functions of the Control Flow Guard table (the valid indirect call targets) with their name, if any
+    #[arg(long, default_value_t = false)]
+    pub cfg: bool,
+
+    /// Dumps the safe exception handlers registered in the SafeSEH table of the 32-bit images, with their name, if any
+    #[arg(long, default_value_t = false)]
+    pub safeseh: bool,
+
+    /// Dumps the native ARM64 and the EC views of the ARM64X hybrid images: the header fields the ARM64X dynamic relocations rewrite, the ARM64EC metadata with its code ranges and the fixups applied to the image
+    #[arg(long, default_value_t = false)]
+    pub arm64x: bool,
+
+    /// Dumps the base relocations grouped by page, with their number per relocation type
+    #[arg(long, default_value_t = false)]
+    pub relocs: bool,
+
+    /// Dumps the COFF symbol table of the PE (MinGW builds) with the value, section, type, storage class and auxiliary records of each symbol
+    #[arg(long, default_value_t = false)]
+    pub symbols: bool,
+
+    /// Dumps the resource tree: types, names or IDs, and the languages with their data entry, if any
+    #[arg(long, default_value_t = false)]
+    pub resources: bool,
+
+    /// Dumps the version information resource: file and product versions, flags and the StringFileInfo strings (CompanyName, OriginalFilename...), if any
+    #[arg(long, default_value_t = false)]
+    pub version_info: bool,
+
+    /// Dumps the embedded application manifest with its requested execution level and dependent assemblies, the XML is written to --output when given
+    #[arg(long, default_value_t = false)]
+    pub manifest: bool,
+
+    /// Dumps the strings of the string table resources by language, with their ID, if any
+    #[arg(long, default_value_t = false)]
+    pub resource_strings: bool,
+
+    /// Summarizes the resources by type and language, with the largest entries
+    #[arg(long, default_value_t = false)]
+    pub resource_stats: bool,
+
+    /// Dumps the Authenticode signature of the certificate table: file digest, signer subject, issuer, serial number, digest algorithm and the validity dates of the embedded certificates
+    #[arg(long, default_value_t = false)]
+    pub signature: bool,
+
+    /// Evaluates the security mitigations (ASLR, high entropy VA, DEP, CFG, SafeSEH, /GS, CET compatibility, Authenticode) and prints a pass/fail table with the DLL characteristics and the extended ones holding the CET shadow stack policy
+    #[arg(long, default_value_t = false)]
+    pub mitigations: bool,
+
+    /// Cross-checks the COFF timestamp against the export, debug directory and resource timestamps and the Authenticode signing time, and flags the zeroed, implausible or inconsistent values
+    #[arg(long, default_value_t = false)]
+    pub timestamps: bool,
+
+    /// Checks the structure of PE files against a set of rules (overlapping or truncated sections, nonstandard alignments, header sizes, entry point, data directories, checksum) and reports each anomaly with a severity and a code
+    #[arg(long, default_value_t = false)]
+    pub lint: bool,
+
+    /// Resolves the imported and delay-loaded DLLs recursively and reports the missing DLLs, unresolved functions and stale bindings as a tree
+    #[arg(long, default_value_t = false)]
+    pub deps: bool,
+
+    /// Classifies every DLL of the dependency tree as found, system or missing and lists the functions that cannot be resolved, exits with an error on failures
+    #[arg(long, default_value_t = false)]
+    pub missing_deps: bool,
+
+    /// Directory searched for the DLLs with --deps and --missing-deps, a Windows filesystem root is searched in its system folders (can be repeated)
+    #[arg(long)]
+    pub deps_path: Vec<PathBuf>,
+
+    /// Downloads the PDB matching the CodeView record of the debug directory, its public symbols name the exports and the functions in the disassembly
+    #[arg(long, default_value_t = false)]
+    pub fetch_pdb: bool,
+
+    /// Symbol server the PDB is fetched from with --fetch-pdb, a URL or a symbol store directory (can be repeated), defaults to the srv* entries of _NT_SYMBOL_PATH, else to the Microsoft symbol server
+    #[arg(long)]
+    pub symbol_server: Vec<String>,
+
+    /// Directory the fetched PDBs are cached in, defaults to the cache of _NT_SYMBOL_PATH, else to the user cache directory
+    #[arg(long)]
+    pub symbol_cache: Option<PathBuf>,
+
+    /// Dumps the mangled C++ names (exports, imports, symbols) as is, without their demangled form
+    #[arg(long, default_value_t = false)]
+    pub no_demangle: bool,
+
+    /// strftime format of the timestamps (COFF header, exports, imports, debug directory, resources), printed next to their raw value, defaults to ISO-8601
+    #[arg(long)]
+    pub time_format: Option<String>,
+
+    /// Time zone the timestamps are printed in
+    #[arg(long, value_enum, default_value_t = TimeZone::Utc)]
+    pub timezone: TimeZone,
+
+    /// Runs the kernel driver checks (INIT/PAGE sections, dispatch routines, IRP and device import heuristics, kernel API families, checksum validity, signing requirements)
+    #[arg(long, default_value_t = false)]
+    pub driver: bool,
+
+    /// Runs the UEFI image checks (image kind, default boot file, relocations, runtime driver alignment, W^X and Secure Boot requirements, SBAT metadata)
+    #[arg(long, default_value_t = false)]
+    pub efi: bool,
+
+    /// Writes every resource to a file named after its type, name and language in the given directory, icons, cursors and bitmaps are rebuilt into .ico, .cur and .bmp files
+    #[arg(long)]
+    pub extract_resources: Option<PathBuf>,
+
+    /// Writes the PKCS#7 signature blob of the security directory to the given file and each embedded X.509 certificate next to it as <name>-<index>, PEM encoded for a .pem path and DER otherwise, readable with openssl
+    #[arg(long)]
+    pub extract_cert: Option<PathBuf>,
+
+    /// Writes a copy of the PE to the given path with the debug directory, debug data (PDB path) and Rich header zeroed, and the checksum recomputed
+    #[arg(long)]
+    pub strip_debug: Option<PathBuf>,
+
+    /// Moves the preferred ImageBase (0x140000000, 140000000h or decimal) and applies the base relocations, see --output
+    #[arg(long)]
+    pub rebase: Option<String>,
+
+    /// Recomputes the optional header CheckSum and writes it, see --output
+    #[arg(long, default_value_t = false)]
+    pub fix_checksum: bool,
+
+    /// Drops the Authenticode signature: clears the security data directory and truncates the appended certificates, see --output
+    #[arg(long, default_value_t = false)]
+    pub remove_signature: bool,
+
+    /// Dumps the CLR Runtime Header of the .NET images: runtime version, flags, entry point and strong name signature
+    #[arg(long, default_value_t = false)]
+    pub dotnet: bool,
+
+    /// Dumps the .NET metadata: streams, table row counts and the Module, TypeDef, MethodDef and AssemblyRef tables
+    #[arg(long, default_value_t = false)]
+    pub dotnet_metadata: bool,
+
+    /// Lists the assemblies referenced by a .NET image with their version and public key token, and whether the image is strong-name signed
+    #[arg(long, default_value_t = false)]
+    pub dotnet_refs: bool,
+
+    /// Dumps the .NET assembly identity (name, version, culture, public key token, target framework) and referenced assemblies
+    #[arg(long, default_value_t = false)]
+    pub dotnet_assembly: bool,
+
+    /// Disassembles the CIL of the .NET methods with the given MethodDef token (0x06000001) or name (Method, Type::Method, Namespace.Type::Method), with the member references resolved
+    #[arg(long)]
+    pub disasm_cil: Option<String>,
+
     /*
      * ELF
      */
@@ -65,11 +273,15 @@ pub struct Args {
     #[arg(long, default_value_t = false)]
     pub elf_program_headers: bool,
 
+    /// Dumps the ELF symbol tables (.dynsym and .symtab), with the demangled C++ and Rust names
+    #[arg(long, default_value_t = false)]
+    pub elf_symbols: bool,
+
     /*
      * Common
      */
 
-    /// Dumps the Sections
+    /// Dumps the Sections with the entropy of their data, marked above 7.2 bits per byte outside of the resources
     #[arg(long, default_value_t = false)]
     pub sections: bool,
 
@@ -77,14 +289,82 @@ pub struct Args {
     #[arg(long, default_value = ".*")]
     pub sections_filter: String,
 
-    /// Dumps the Sections data along with the headers
+    /// Dumps the Sections data along with the headers, as a hex dump with the offsets in the section and the ASCII characters
     #[arg(long, default_value_t = false)]
     pub sections_data: bool,
 
+    /// Number of bytes of each Section dumped by --sections-data, 0 dumps the whole Sections
+    #[arg(long, default_value_t = 256)]
+    pub max_bytes: usize,
+
     /// Disassemble the code found in the Sections containing code
     #[arg(long, default_value_t = false)]
     pub disasm: bool,
 
+    /// Layout of the --disasm listing, objdump mimics `objdump -d -M intel` (addresses, bytes, tab-separated instructions, symbol headers) for the tools built around its output
+    #[arg(long, value_enum, default_value_t = DisasmFormat::Execdump)]
+    pub disasm_format: DisasmFormat,
+
+    /// Prints the section (or headers, overlay) holding an address with its file offset, RVA, VA, permissions and nearest symbol, bare numbers are VAs above the image base, RVAs below, "off:", "rva:" and "va:" force the kind
+    #[arg(long)]
+    pub whereis: Option<String>,
+
+    /// Dumps the bytes of a file range in hex and ASCII with the file offsets and RVAs in the gutter, as <address>:<length> with the address parsed like --whereis ("rva:0x1000:0x100", "off:0x400:64")
+    #[arg(long)]
+    pub hexdump: Option<String>,
+
+    /// Reports whether the executable looks packed, with the section indicators behind the verdict
+    #[arg(long, default_value_t = false)]
+    pub packed: bool,
+
+    /// Reports anti-debug, anti-VM and anti-dump tricks (TLS callbacks, imports, timing and VM detection instructions)
+    #[arg(long, default_value_t = false)]
+    pub anti_analysis: bool,
+
+    /// Infers the capabilities of the executable (injection, keylogging, networking...) from its imports
+    #[arg(long, default_value_t = false)]
+    pub capabilities: bool,
+
+    /// Maps the inferred capabilities to MITRE ATT&CK technique IDs, implies --capabilities
+    #[arg(long, default_value_t = false)]
+    pub attack: bool,
+
+    /// Dumps the functions found in the code along with their similarity hashes
+    #[arg(long, default_value_t = false)]
+    pub function_hashes: bool,
+
+    /// Matches the functions against another executable, reporting identical, changed, new and removed functions
+    #[arg(long)]
+    pub compare_functions: Option<PathBuf>,
+
+    /// Compares the exports against a reference build of the DLL, reporting the functions added, removed or whose ordinal changed, exits with an error if any is removed or renumbered
+    #[arg(long)]
+    pub compare_exports: Option<PathBuf>,
+
+    /// Cross-checks the exports and imports of every PE/ELF file found in a directory: links, dead exports and unresolved imports
+    #[arg(long)]
+    pub link_map: Option<PathBuf>,
+
+    /// Writes the raw bytes of the Sections matching the name (or regular expression) to files in --output-dir
+    #[arg(long)]
+    pub extract_section: Option<String>,
+
+    /// Extracts the Sections as mapped in memory, zero-filled up to their virtual size, instead of their bytes in the file
+    #[arg(long, default_value_t = false)]
+    pub extract_virtual_size: bool,
+
+    /// Path the modified executable (or the --manifest XML) is written to, the file is modified in place (with a .bak backup) when omitted
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+
+    /// Directory the extracted files are written to, created if needed
+    #[arg(long, default_value = ".")]
+    pub output_dir: PathBuf,
+
+    /// Identifies files embedded in the Sections and resources (archives, images, executables, certificates) and high-entropy blobs
+    #[arg(long, default_value_t = false)]
+    pub embedded: bool,
+
     /*
      * Formatting
      */
@@ -93,5 +373,425 @@ pub struct Args {
     #[arg(long, default_value_t = 4)]
     pub padding_size: usize,
 
+    /// Format of the report, JSON and HTML reports gather all the requested dumps in a single document
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output_format: OutputFormat,
+
+    #[arg(required_unless_present = "link_map")]
+    pub file_path: Option<PathBuf>,
+}
+
+impl Args {
+    /// Whether the requested dumps look at the content of the sections, the parser skips reading it otherwise
+    pub fn needs_section_data(&self) -> bool {
+        return self.tui
+            || self.summary
+            || self.hashes
+            || self.fuzzy_hashes
+            || self.buildinfo
+            || self.sections
+            || self.sections_data
+            || self.disasm
+            || self.packed
+            || self.anti_analysis
+            || self.embedded
+            || self.function_hashes
+            || self.compare_functions.is_some()
+            || self.driver
+            || self.disasm_cil.is_some()
+            || self.efi;
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Compares two executables of the same format (headers, sections, imports, exports and resources)
+    Diff(DiffArgs),
+
+    /// Overwrites bytes at a file offset or RVA, checking the patch stays inside the file and its section
+    Patch(PatchArgs),
+
+    /// Appends a new section to a PE, fixing NumberOfSections, SizeOfImage and SizeOfHeaders
+    AddSection(AddSectionArgs),
+
+    /// Adds a DLL to the imports of a PE, the import directory is copied to a new section with the new descriptor
+    AddImport(AddImportArgs),
+
+    /// Renames a section of a PE
+    RenameSection(RenameSectionArgs),
+
+    /// Converts a PE dumped from process memory back to its file layout, rewriting the raw pointers and sizes of the sections
+    Unmap(UnmapArgs),
+
+    /// Rebuilds the import directory of a dumped PE from the absolute addresses of its import address table
+    RebuildIat(RebuildIatArgs),
+
+    /// Sets every PE timestamp (COFF, export, debug, resource) to a fixed value and blanks the PDB path for reproducible builds
+    Normalize(NormalizeArgs),
+
+    /// Lists the modules, threads and memory ranges of a minidump (.dmp), and extracts the modules as mapped in the process
+    Minidump(MinidumpArgs),
+
+    /// Scans any file for the PE images embedded past its first byte (droppers, firmware blobs, self-extractors), validates their headers and lists or extracts them
+    Carve(CarveArgs),
+
+    /// Extracts the printable ASCII and UTF-16LE strings of any file with their file offset, and the section and virtual address holding them in an executable
+    Strings(StringsArgs),
+
+    /// Converts file offsets, RVAs and virtual addresses in batch, for the scripts going between a debugger and the file
+    Addr(AddrArgs),
+
+    /// Opens an interactive prompt to query the executable (imports, disassembly, hex, address conversions), it is parsed only once
+    Repl(ReplArgs),
+
+    /// Starts an HTTP server answering with JSON, executables are uploaded (or loaded from --root) then queried for their structures, hashes and disassembly
+    Serve(ServeArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct DiffArgs {
+    /// Opens the diff in the terminal-based user interface, alongside the old executable
+    #[arg(long, short, default_value_t = false)]
+    pub tui: bool,
+
+    /// Padding size to apply when dumping information for better readability
+    #[arg(long, default_value_t = 4)]
+    pub padding_size: usize,
+
+    /// Format of the report
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output_format: OutputFormat,
+
+    pub old_file_path: PathBuf,
+
+    pub new_file_path: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct PatchArgs {
+    /// File offset to patch at (0x400, 400h or decimal), a RVA with --rva
+    #[arg(long)]
+    pub at: String,
+
+    /// Interprets --at as a RVA (a virtual address for ELF) instead of a file offset
+    #[arg(long, default_value_t = false)]
+    pub rva: bool,
+
+    /// Bytes to write, as hex digits ("9090EB05", "90 90 EB 05")
+    #[arg(long)]
+    pub bytes: String,
+
+    /// Copies the original file to <file>.bak before patching
+    #[arg(long, default_value_t = false)]
+    pub backup: bool,
+
+    /// Recomputes the PE CheckSum after patching
+    #[arg(long, default_value_t = false)]
+    pub fix_checksum: bool,
+
+    /// Padding size to apply when dumping information for better readability
+    #[arg(long, default_value_t = 4)]
+    pub padding_size: usize,
+
+    /// Format of the report
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output_format: OutputFormat,
+
+    pub file_path: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct AddSectionArgs {
+    /// Name of the new section, up to 8 characters
+    #[arg(long)]
+    pub name: String,
+
+    /// Characteristics of the section, as a number or flag names ("CNT_CODE|MEM_EXECUTE|MEM_READ")
+    #[arg(long, default_value = "CNT_INITIALIZED_DATA|MEM_READ")]
+    pub characteristics: String,
+
+    /// File holding the data of the section, the section is empty when omitted
+    #[arg(long)]
+    pub data: Option<PathBuf>,
+
+    /// Virtual size of the section, defaults to the data size
+    #[arg(long)]
+    pub virtual_size: Option<u32>,
+
+    /// Path the modified executable is written to, the file is modified in place (with a .bak backup) when omitted
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+
+    /// Padding size to apply when dumping information for better readability
+    #[arg(long, default_value_t = 4)]
+    pub padding_size: usize,
+
+    /// Format of the report
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output_format: OutputFormat,
+
+    pub file_path: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct AddImportArgs {
+    /// Name of the imported DLL
+    #[arg(long)]
+    pub dll: String,
+
+    /// Function imported from the DLL, by name or by ordinal ("#12"), repeat for several functions
+    #[arg(long = "function", required = true)]
+    pub functions: Vec<String>,
+
+    /// Name of the section holding the new import directory
+    #[arg(long, default_value = ".idata2")]
+    pub section_name: String,
+
+    /// Path the modified executable is written to, the file is modified in place (with a .bak backup) when omitted
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+
+    /// Padding size to apply when dumping information for better readability
+    #[arg(long, default_value_t = 4)]
+    pub padding_size: usize,
+
+    /// Format of the report
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output_format: OutputFormat,
+
+    pub file_path: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct RenameSectionArgs {
+    /// Current name of the section
+    #[arg(long)]
+    pub name: String,
+
+    /// New name of the section, up to 8 characters
+    #[arg(long)]
+    pub new_name: String,
+
+    /// Path the modified executable is written to, the file is modified in place (with a .bak backup) when omitted
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+
+    /// Padding size to apply when dumping information for better readability
+    #[arg(long, default_value_t = 4)]
+    pub padding_size: usize,
+
+    /// Format of the report
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output_format: OutputFormat,
+
+    pub file_path: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct UnmapArgs {
+    /// Path the file image is written to, the dump is modified in place (with a .bak backup) when omitted
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+
+    /// Padding size to apply when dumping information for better readability
+    #[arg(long, default_value_t = 4)]
+    pub padding_size: usize,
+
+    /// Format of the report
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output_format: OutputFormat,
+
+    pub file_path: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct RebuildIatArgs {
+    /// Module loaded in the dumped process, as "path" or "path=base" (0x7ffb12340000), the preferred ImageBase is used without a base
+    #[arg(long = "module", required = true)]
+    pub modules: Vec<String>,
+
+    /// RVA of the import address table, defaults to the IAT data directory, else to the longest run of resolved addresses
+    #[arg(long)]
+    pub iat: Option<String>,
+
+    /// Size of the import address table in bytes, defaults to where the resolved addresses end
+    #[arg(long)]
+    pub iat_size: Option<String>,
+
+    /// Name of the section holding the rebuilt import directory
+    #[arg(long, default_value = ".idata2")]
+    pub section_name: String,
+
+    /// Path the rebuilt executable is written to, the file is modified in place (with a .bak backup) when omitted
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+
+    /// Padding size to apply when dumping information for better readability
+    #[arg(long, default_value_t = 4)]
+    pub padding_size: usize,
+
+    /// Format of the report
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output_format: OutputFormat,
+
+    pub file_path: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct NormalizeArgs {
+    /// Timestamp to write, defaults to the SOURCE_DATE_EPOCH environment variable, else zero
+    #[arg(long)]
+    pub timestamp: Option<u64>,
+
+    /// Writes the normalized PE to this path instead of modifying the file in place
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+
+    /// Padding size to apply when dumping information for better readability
+    #[arg(long, default_value_t = 4)]
+    pub padding_size: usize,
+
+    /// Format of the report
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output_format: OutputFormat,
+
+    pub file_path: PathBuf,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeZone {
+    Utc,
+    Local,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisasmFormat {
+    Execdump,
+    Objdump,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Html,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct MinidumpArgs {
+    /// Modules to extract to --output-dir, by file name (ntdll.dll) or full path, can be repeated or separated by commas; the images are parsed back with --memory-image
+    #[arg(long, value_delimiter = ',')]
+    pub extract: Vec<String>,
+
+    /// Directory the extracted modules are written to, created if needed
+    #[arg(long, default_value = ".")]
+    pub output_dir: PathBuf,
+
+    /// Padding size to apply when dumping information for better readability
+    #[arg(long, default_value_t = 4)]
+    pub padding_size: usize,
+
+    /// Format of the report
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output_format: OutputFormat,
+
+    pub file_path: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct CarveArgs {
+    /// Writes the carved executables to --output-dir, named after the input file and their offset
+    #[arg(long, default_value_t = false)]
+    pub extract: bool,
+
+    /// Directory the carved executables are written to, created if needed
+    #[arg(long, default_value = ".")]
+    pub output_dir: PathBuf,
+
+    /// Padding size to apply when dumping information for better readability
+    #[arg(long, default_value_t = 4)]
+    pub padding_size: usize,
+
+    /// Format of the report
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output_format: OutputFormat,
+
+    pub file_path: PathBuf,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StringsEncoding {
+    All,
+    Ascii,
+    Utf16,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct StringsArgs {
+    /// Minimum number of characters of a string
+    #[arg(long, short = 'n', default_value_t = 4)]
+    pub min_length: usize,
+
+    /// Encodings of the strings to extract, utf16 is little-endian
+    #[arg(long, value_enum, default_value_t = StringsEncoding::All)]
+    pub encoding: StringsEncoding,
+
+    /// Regular expression the strings must match to be listed
+    #[arg(long)]
+    pub filter: Option<String>,
+
+    /// Padding size to apply when dumping information for better readability
+    #[arg(long, default_value_t = 4)]
+    pub padding_size: usize,
+
+    /// Format of the report
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output_format: OutputFormat,
+
+    pub file_path: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct AddrArgs {
+    /// RVAs to convert to file offsets (0x1000, 1000h or decimal), can be repeated or separated by commas
+    #[arg(long = "rva2off", value_delimiter = ',')]
+    pub rva_to_offset: Vec<String>,
+
+    /// File offsets to convert to RVAs, can be repeated or separated by commas
+    #[arg(long = "off2rva", value_delimiter = ',')]
+    pub offset_to_rva: Vec<String>,
+
+    /// Virtual addresses to convert to RVAs, can be repeated or separated by commas (ELF has no image base, its RVAs are the virtual addresses)
+    #[arg(long = "va2rva", value_delimiter = ',')]
+    pub va_to_rva: Vec<String>,
+
+    /// Padding size to apply when dumping information for better readability
+    #[arg(long, default_value_t = 4)]
+    pub padding_size: usize,
+
+    /// Format of the report
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output_format: OutputFormat,
+
+    pub file_path: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ReplArgs {
+    /// Padding size to apply when dumping information for better readability
+    #[arg(long, default_value_t = 4)]
+    pub padding_size: usize,
+
     pub file_path: PathBuf,
 }
+
+#[derive(clap::Args, Debug)]
+pub struct ServeArgs {
+    /// Address and port to listen on, reachable from other machines when not a loopback address
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    pub listen: String,
+
+    /// Directory the executables can be loaded from by path, only uploads are accepted without it
+    #[arg(long)]
+    pub root: Option<PathBuf>,
+}
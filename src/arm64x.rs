@@ -0,0 +1,148 @@
+use crate::dump::Dump;
+use crate::lint::DATA_DIRECTORY_NAMES;
+use crate::pe::{Arm64ECMetadata, Arm64XFixup, MachineType, PE};
+
+/*
+ * ARM64X hybrid images, one file holding a native ARM64 and an ARM64EC (x64 compatible) view
+ * The headers describe the native view, the loader of the emulated processes applies the ARM64X dynamic relocations
+ * on them to get the EC view
+ */
+
+/* Offset in the optional header of AddressOfEntryPoint */
+const ENTRY_POINT_OFFSET: u64 = 16;
+
+/// Header field the ARM64X fixups may rewrite
+#[derive(Debug, Clone)]
+pub struct Arm64XViewField {
+    pub name: &'static str,
+    pub native: String,
+    pub ec: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Arm64XReport {
+    pub native_machine: u16,
+    pub ec_machine: u16,
+    /// Header fields that differ between the native and the EC views
+    pub fields: Vec<Arm64XViewField>,
+    pub header_fixups: usize,
+    /// Fixups past the headers with the section they patch
+    pub image_fixups: Vec<(Arm64XFixup, Option<String>)>,
+    pub metadata: Option<Arm64ECMetadata>,
+}
+
+fn read_le(bytes: &[u8], offset: u64, size: usize) -> u64 {
+    let mut value = [0u8; 8];
+
+    if let Some(field) = bytes.get(offset as usize..offset as usize + size) {
+        value[..size].copy_from_slice(field);
+    }
+
+    return u64::from_le_bytes(value);
+}
+
+/// Whether the load config carries ARM64X dynamic relocations or ARM64EC metadata
+pub fn is_hybrid(pe: &PE) -> bool {
+    return pe.load_config.as_ref().is_some_and(|load_config| {
+        load_config.arm64ec_metadata.is_some()
+            || load_config.dynamic_relocations.as_ref().is_some_and(|table| table.arm64x_fixups().next().is_some())
+    });
+}
+
+impl Arm64XReport {
+    /// Applies the header fixups to a copy of the headers read from the file and compares the two views
+    pub fn from_pe(pe: &PE, file_bytes: &[u8]) -> Arm64XReport {
+        let mut report = Arm64XReport::default();
+
+        let Some(ref load_config) = pe.load_config else {
+            return report;
+        };
+
+        let size_of_headers = (pe.get_optional_header().get_size_of_headers() as usize).min(file_bytes.len());
+        let native_headers = &file_bytes[..size_of_headers];
+        let mut ec_headers = native_headers.to_vec();
+
+        for fixup in load_config.dynamic_relocations.iter().flat_map(|table| table.arm64x_fixups()) {
+            if (fixup.rva as usize) < size_of_headers {
+                fixup.apply(&mut ec_headers);
+                report.header_fixups += 1;
+            } else {
+                report.image_fixups.push((fixup.clone(), pe.get_section_for_rva(fixup.rva).map(|section| section.header.name.clone())));
+            }
+        }
+
+        let machine_offset = pe.get_coff_header_offset();
+
+        report.native_machine = read_le(native_headers, machine_offset, 2) as u16;
+        report.ec_machine = read_le(&ec_headers, machine_offset, 2) as u16;
+
+        let entry_point_offset = pe.get_optional_header_offset() + ENTRY_POINT_OFFSET;
+
+        if read_le(native_headers, entry_point_offset, 4) != read_le(&ec_headers, entry_point_offset, 4) {
+            report.fields.push(Arm64XViewField {
+                name: "AddressOfEntryPoint",
+                native: format!("{:#x}", read_le(native_headers, entry_point_offset, 4)),
+                ec: format!("{:#x}", read_le(&ec_headers, entry_point_offset, 4)),
+            });
+        }
+
+        for (index, name) in DATA_DIRECTORY_NAMES.iter().enumerate() {
+            let offset = pe.get_data_directory_offset(index);
+
+            let native = (read_le(native_headers, offset, 4), read_le(native_headers, offset + 4, 4));
+            let ec = (read_le(&ec_headers, offset, 4), read_le(&ec_headers, offset + 4, 4));
+
+            if native != ec {
+                report.fields.push(Arm64XViewField {
+                    name,
+                    native: format!("address: {:#x} sz: {:#x}", native.0, native.1),
+                    ec: format!("address: {:#x} sz: {:#x}", ec.0, ec.1),
+                });
+            }
+        }
+
+        report.metadata = load_config.arm64ec_metadata.clone();
+
+        return report;
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("ARM64X Hybrid Image");
+
+        dump.push_field("Views", format!("native {:?}, EC {:?}", MachineType::from(self.native_machine), MachineType::from(self.ec_machine)), None);
+        dump.push_field("HeaderFixups", format!("{}", self.header_fixups), None);
+        dump.push_field("ImageFixups", format!("{}", self.image_fixups.len()), None);
+
+        let mut native_dump = Dump::new("Native View");
+        let mut ec_dump = Dump::new("EC View");
+
+        native_dump.push_field("Machine", format!("{:#x} ({:?})", self.native_machine, MachineType::from(self.native_machine)), None);
+        ec_dump.push_field("Machine", format!("{:#x} ({:?})", self.ec_machine, MachineType::from(self.ec_machine)), None);
+
+        for field in self.fields.iter() {
+            native_dump.push_field(field.name, field.native.clone(), None);
+            ec_dump.push_field(field.name, field.ec.clone(), None);
+        }
+
+        dump.push_child(native_dump);
+        dump.push_child(ec_dump);
+
+        if let Some(ref metadata) = self.metadata {
+            dump.push_child(metadata.dump());
+        }
+
+        let mut fixups_dump = Dump::new_from_string(format!("Image Fixups ({})", self.image_fixups.len()));
+
+        for (fixup, section) in self.image_fixups.iter() {
+            match section {
+                Some(section) => fixups_dump.push_field("", format!("{}  ({})", fixup.describe(), section), None),
+                None => fixups_dump.push_field("", format!("{}  (outside of the sections)", fixup.describe()), None),
+            }
+        }
+
+        dump.push_child(fixups_dump);
+
+        return dump;
+    }
+}
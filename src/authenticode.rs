@@ -0,0 +1,421 @@
+use chrono::NaiveDate;
+
+use crate::dump::*;
+use crate::format::format_unix_time;
+use crate::pe::PE;
+
+/*
+ * Authenticode signature, the PKCS#7 SignedData of the certificate table
+ * https://learn.microsoft.com/en-us/windows-hardware/drivers/install/authenticode
+ */
+
+/* Universal DER tags */
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+
+/* Context-specific constructed tags, [0] and [1] */
+const TAG_CONTEXT_0: u8 = 0xA0;
+const TAG_CONTEXT_1: u8 = 0xA1;
+
+const OID_SIGNED_DATA: &str = "1.2.840.113549.1.7.2";
+const OID_SIGNING_TIME: &str = "1.2.840.113549.1.9.5";
+
+/// Element of a DER encoding: its tag, its content and its whole encoding (tag and length included)
+#[derive(Debug, Clone, Copy)]
+struct DerElement<'a> {
+    tag: u8,
+    content: &'a [u8],
+    encoding: &'a [u8],
+}
+
+/// Reads the element at the start of data, returning it with the bytes following it. Only the definite lengths of DER are supported
+fn read_der(data: &[u8]) -> Option<(DerElement<'_>, &[u8])> {
+    let tag = *data.first()?;
+    let first_length = *data.get(1)? as usize;
+
+    let (length, header) = match first_length {
+        0..=0x7f => (first_length, 2),
+        0x81..=0x84 => {
+            let count = first_length & 0x7f;
+            let length = data.get(2..2 + count)?.iter().fold(0usize, |length, b| (length << 8) | *b as usize);
+            (length, 2 + count)
+        },
+        _ => return None,
+    };
+
+    let end = header.checked_add(length)?;
+    let element = DerElement { tag, content: data.get(header..end)?, encoding: &data[..end] };
+
+    return Some((element, &data[end..]));
+}
+
+impl<'a> DerElement<'a> {
+    fn children(&self) -> Vec<DerElement<'a>> {
+        let mut children = Vec::new();
+        let mut rest = self.content;
+
+        while let Some((child, next)) = read_der(rest) {
+            children.push(child);
+            rest = next;
+        }
+
+        return children;
+    }
+
+    /// The element itself when it has the tag
+    fn expect(self, tag: u8) -> Option<DerElement<'a>> {
+        return (self.tag == tag).then_some(self);
+    }
+
+    fn oid(&self) -> Option<String> {
+        if self.tag != TAG_OID || self.content.is_empty() {
+            return None;
+        }
+
+        let first = self.content[0] as u64;
+        let mut arcs = vec![(first / 40).min(2), first - (first / 40).min(2) * 40];
+        let mut value = 0u64;
+
+        for byte in self.content[1..].iter() {
+            value = (value << 7) | (*byte & 0x7f) as u64;
+
+            if (byte & 0x80) == 0 {
+                arcs.push(value);
+                value = 0;
+            }
+        }
+
+        return Some(arcs.iter().map(|arc| arc.to_string()).collect::<Vec<String>>().join("."));
+    }
+
+    /// Text of the string types found in the names (UTF8String, PrintableString, IA5String, BMPString...)
+    fn text(&self) -> String {
+        match self.tag {
+            /* BMPString */
+            0x1e => {
+                let units: Vec<u16> = self.content.chunks_exact(2).map(|b| u16::from_be_bytes([b[0], b[1]])).collect();
+                return String::from_utf16_lossy(&units);
+            },
+            _ => return String::from_utf8_lossy(self.content).to_string(),
+        }
+    }
+
+    /// Seconds since the Unix epoch of an UTCTime ("YYMMDDHHMMSSZ") or a GeneralizedTime ("YYYYMMDDHHMMSSZ")
+    fn time(&self) -> Option<i64> {
+        let text = std::str::from_utf8(self.content).ok()?.trim_end_matches('Z');
+
+        let (year, rest) = match self.tag {
+            /* Years 50 to 99 are in the 20th century */
+            TAG_UTC_TIME => {
+                let year: i32 = text.get(0..2)?.parse().ok()?;
+                (if year >= 50 { 1900 + year } else { 2000 + year }, text.get(2..)?)
+            },
+            TAG_GENERALIZED_TIME => (text.get(0..4)?.parse().ok()?, text.get(4..)?),
+            _ => return None,
+        };
+
+        let field = |i: usize| -> Option<u32> { rest.get(i * 2..i * 2 + 2)?.parse().ok() };
+
+        let date = NaiveDate::from_ymd_opt(year, field(0)?, field(1)?)?;
+        let time = date.and_hms_opt(field(2)?, field(3)?, field(4).unwrap_or(0))?;
+
+        return Some(time.and_utc().timestamp());
+    }
+}
+
+fn hex_string(bytes: &[u8]) -> String {
+    return bytes.iter().map(|b| format!("{:02x}", b)).collect();
+}
+
+/// Short name of the attribute types of the distinguished names
+fn attribute_name(oid: &str) -> Option<&'static str> {
+    match oid {
+        "2.5.4.3" => return Some("CN"),
+        "2.5.4.5" => return Some("serialNumber"),
+        "2.5.4.6" => return Some("C"),
+        "2.5.4.7" => return Some("L"),
+        "2.5.4.8" => return Some("ST"),
+        "2.5.4.9" => return Some("street"),
+        "2.5.4.10" => return Some("O"),
+        "2.5.4.11" => return Some("OU"),
+        "2.5.4.15" => return Some("businessCategory"),
+        "1.2.840.113549.1.9.1" => return Some("emailAddress"),
+        "1.3.6.1.4.1.311.60.2.1.1" => return Some("jurisdictionL"),
+        "1.3.6.1.4.1.311.60.2.1.2" => return Some("jurisdictionST"),
+        "1.3.6.1.4.1.311.60.2.1.3" => return Some("jurisdictionC"),
+        _ => return None,
+    }
+}
+
+/// Name of the digest and signature algorithms, the OID itself when unknown
+pub fn algorithm_name(oid: &str) -> String {
+    let name = match oid {
+        "1.2.840.113549.2.5" => "MD5",
+        "1.3.14.3.2.26" => "SHA1",
+        "2.16.840.1.101.3.4.2.1" => "SHA256",
+        "2.16.840.1.101.3.4.2.2" => "SHA384",
+        "2.16.840.1.101.3.4.2.3" => "SHA512",
+        "1.2.840.113549.1.1.1" => "RSA",
+        "1.2.840.113549.1.1.4" => "MD5 with RSA",
+        "1.2.840.113549.1.1.5" => "SHA1 with RSA",
+        "1.2.840.113549.1.1.11" => "SHA256 with RSA",
+        "1.2.840.113549.1.1.12" => "SHA384 with RSA",
+        "1.2.840.113549.1.1.13" => "SHA512 with RSA",
+        "1.2.840.10045.2.1" => "ECDSA",
+        "1.2.840.10045.4.3.2" => "ECDSA with SHA256",
+        "1.2.840.10045.4.3.3" => "ECDSA with SHA384",
+        "1.2.840.10045.4.3.4" => "ECDSA with SHA512",
+        _ => return String::from(oid),
+    };
+
+    return String::from(name);
+}
+
+/// AlgorithmIdentifier, a sequence starting with the OID of the algorithm
+fn algorithm_of(element: &DerElement) -> String {
+    return element.children().first().and_then(|oid| oid.oid()).map(|oid| algorithm_name(&oid)).unwrap_or_default();
+}
+
+/// Distinguished name in the order of its RDNs, "C=US, O=Example, CN=Example"
+fn format_name(name: &DerElement) -> String {
+    let mut attributes = Vec::new();
+
+    for rdn in name.children() {
+        for attribute in rdn.children() {
+            let parts = attribute.children();
+
+            let (Some(oid), Some(value)) = (parts.first().and_then(|p| p.oid()), parts.get(1)) else {
+                continue;
+            };
+
+            match attribute_name(&oid) {
+                Some(short_name) => attributes.push(format!("{}={}", short_name, value.text())),
+                None => attributes.push(format!("{}={}", oid, value.text())),
+            }
+        }
+    }
+
+    return attributes.join(", ");
+}
+
+/// Value of the first attribute of the name with the given type
+fn name_attribute(name: &DerElement, oid: &str) -> Option<String> {
+    for rdn in name.children() {
+        for attribute in rdn.children() {
+            let parts = attribute.children();
+
+            if parts.first().and_then(|p| p.oid()).as_deref() == Some(oid) {
+                return parts.get(1).map(|value| value.text());
+            }
+        }
+    }
+
+    return None;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Certificate {
+    pub subject: String,
+    /// Common name of the subject, the whole subject when it has none
+    pub common_name: String,
+    pub issuer: String,
+    /// Serial number in hexadecimal, as printed by openssl
+    pub serial_number: String,
+    pub signature_algorithm: String,
+    pub not_before: Option<i64>,
+    pub not_after: Option<i64>,
+    /// DER encoding of the whole certificate
+    pub der: Vec<u8>,
+}
+
+impl Certificate {
+    fn from_der(element: &DerElement) -> Option<Certificate> {
+        let parts = element.children();
+        let mut tbs = parts.first()?.expect(TAG_SEQUENCE)?.children().into_iter().peekable();
+
+        /* The version is optional, v1 certificates omit it */
+        if tbs.peek().is_some_and(|e| e.tag == TAG_CONTEXT_0) {
+            tbs.next();
+        }
+
+        let serial = tbs.next()?.expect(TAG_INTEGER)?;
+        let signature = tbs.next()?;
+        let issuer = tbs.next()?;
+        let validity = tbs.next()?.children();
+        let subject = tbs.next()?;
+
+        return Some(Certificate {
+            subject: format_name(&subject),
+            common_name: name_attribute(&subject, "2.5.4.3").unwrap_or(format_name(&subject)),
+            issuer: format_name(&issuer),
+            serial_number: hex_string(serial.content),
+            signature_algorithm: algorithm_of(&signature),
+            not_before: validity.first().and_then(|t| t.time()),
+            not_after: validity.get(1).and_then(|t| t.time()),
+            der: element.encoding.to_vec(),
+        });
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self, label: &str) -> Dump {
+        let mut dump = Dump::new(label);
+
+        dump.push_field("Subject", self.subject.clone(), None);
+        dump.push_field("Issuer", self.issuer.clone(), None);
+        dump.push_field("SerialNumber", self.serial_number.clone(), None);
+        dump.push_field("SignatureAlgorithm", self.signature_algorithm.clone(), None);
+        dump.push_field("NotBefore", self.not_before.map(format_unix_time).unwrap_or(String::from("invalid")), None);
+        dump.push_field("NotAfter", self.not_after.map(format_unix_time).unwrap_or(String::from("invalid")), None);
+
+        return dump;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AuthenticodeSignature {
+    /// Algorithm and digest of the image, from the SpcIndirectDataContent
+    pub digest_algorithm: String,
+    pub file_digest: Vec<u8>,
+    pub certificates: Vec<Certificate>,
+    /// Certificate of the signer, found by the issuer and serial number of the SignerInfo
+    pub signer: Option<usize>,
+    pub signer_issuer: String,
+    pub signer_serial_number: String,
+    pub signer_digest_algorithm: String,
+    pub signer_encryption_algorithm: String,
+    pub signing_time: Option<i64>,
+}
+
+impl AuthenticodeSignature {
+    pub fn from_der(data: &[u8]) -> Result<AuthenticodeSignature, Box<dyn std::error::Error>> {
+        return AuthenticodeSignature::parse(data).ok_or_else(|| "Malformed PKCS#7 SignedData".into());
+    }
+
+    fn parse(data: &[u8]) -> Option<AuthenticodeSignature> {
+        let (content_info, _) = read_der(data)?;
+        let content_info = content_info.expect(TAG_SEQUENCE)?.children();
+
+        if content_info.first()?.oid()? != OID_SIGNED_DATA {
+            return None;
+        }
+
+        let signed_data = content_info.get(1)?.expect(TAG_CONTEXT_0)?.children().first()?.expect(TAG_SEQUENCE)?.children();
+
+        let mut signature = AuthenticodeSignature::default();
+
+        /* version, digestAlgorithms, encapContentInfo, [0] certificates, [1] crls, signerInfos */
+        let content = signed_data.get(2)?.children();
+
+        if let Some(indirect_data) = content.get(1).and_then(|c| c.children().first().copied()) && let Some(digest_info) = indirect_data.children().get(1) {
+            let digest_info = digest_info.children();
+
+            signature.digest_algorithm = digest_info.first().map(algorithm_of).unwrap_or_default();
+            signature.file_digest = digest_info.get(1).and_then(|d| d.expect(TAG_OCTET_STRING)).map(|d| d.content.to_vec()).unwrap_or_default();
+        }
+
+        for element in signed_data.iter().skip(3) {
+            match element.tag {
+                TAG_CONTEXT_0 => signature.certificates.extend(element.children().iter().filter_map(Certificate::from_der)),
+                TAG_CONTEXT_1 => {},
+                TAG_SET => {
+                    if let Some(signer_info) = element.children().first() {
+                        signature.parse_signer_info(signer_info);
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        return Some(signature);
+    }
+
+    /// version, issuerAndSerialNumber, digestAlgorithm, [0] authenticatedAttributes, digestEncryptionAlgorithm, encryptedDigest
+    fn parse_signer_info(&mut self, signer_info: &DerElement) {
+        let parts = signer_info.children();
+
+        if let Some(issuer_and_serial) = parts.get(1) {
+            let issuer_and_serial = issuer_and_serial.children();
+
+            self.signer_issuer = issuer_and_serial.first().map(format_name).unwrap_or_default();
+            self.signer_serial_number = issuer_and_serial.get(1).map(|s| hex_string(s.content)).unwrap_or_default();
+        }
+
+        self.signer_digest_algorithm = parts.get(2).map(algorithm_of).unwrap_or_default();
+
+        let mut rest = parts.iter().skip(3);
+        let mut next = rest.next();
+
+        if let Some(attributes) = next.filter(|e| e.tag == TAG_CONTEXT_0) {
+            for attribute in attributes.children() {
+                let attribute = attribute.children();
+
+                if attribute.first().and_then(|o| o.oid()).as_deref() == Some(OID_SIGNING_TIME) {
+                    self.signing_time = attribute.get(1).and_then(|values| values.children().first().and_then(|t| t.time()));
+                }
+            }
+
+            next = rest.next();
+        }
+
+        self.signer_encryption_algorithm = next.map(algorithm_of).unwrap_or_default();
+
+        self.signer = self
+            .certificates
+            .iter()
+            .position(|c| c.serial_number == self.signer_serial_number && c.issuer == self.signer_issuer);
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Authenticode Signature");
+
+        dump.push_field("DigestAlgorithm", self.digest_algorithm.clone(), None);
+        dump.push_field("FileDigest", hex_string(&self.file_digest), None);
+
+        let mut signer_dump = match self.signer.and_then(|i| self.certificates.get(i)) {
+            Some(certificate) => certificate.dump("Signer"),
+            None => {
+                let mut signer_dump = Dump::new("Signer");
+                signer_dump.push_field("Subject", String::from("Unknown (certificate not embedded)"), None);
+                signer_dump.push_field("Issuer", self.signer_issuer.clone(), None);
+                signer_dump.push_field("SerialNumber", self.signer_serial_number.clone(), None);
+                signer_dump
+            },
+        };
+
+        signer_dump.push_field("DigestAlgorithm", self.signer_digest_algorithm.clone(), None);
+        signer_dump.push_field("EncryptionAlgorithm", self.signer_encryption_algorithm.clone(), None);
+
+        if let Some(signing_time) = self.signing_time {
+            signer_dump.push_field("SigningTime", format_unix_time(signing_time), None);
+        }
+
+        dump.push_child(signer_dump);
+
+        let mut certificates_dump = Dump::new_from_string(format!("Certificates ({})", self.certificates.len()));
+
+        for (i, certificate) in self.certificates.iter().enumerate() {
+            certificates_dump.push_child(certificate.dump(&format!("Certificate {}", i)));
+        }
+
+        dump.push_child(certificates_dump);
+
+        return dump;
+    }
+}
+
+/// Signature of the PKCS#7 entry of the certificate table
+pub fn pe_signature(pe: &PE) -> Result<AuthenticodeSignature, Box<dyn std::error::Error>> {
+    let certificate = pe
+        .certificate_table
+        .as_ref()
+        .and_then(|table| table.pkcs7_signature())
+        .ok_or("No Authenticode signature found in PE")?;
+
+    return AuthenticodeSignature::from_der(&certificate.data);
+}
@@ -0,0 +1,483 @@
+use regex::bytes::Regex;
+
+use crate::address::{AddressKind, AddressLocation};
+use crate::dump::Dump;
+use crate::exec::Exec;
+use crate::resources::{ResourceId, ResourceType};
+
+/*
+ * Build metadata the toolchains embed in the executables: the Go build information, the Rust toolchain and crates
+ * named by the panic locations, the packages and units of the Delphi package information
+ */
+
+/* Go */
+
+/* Header of the Go build information, followed by the pointer size and the flags, 32 bytes in total */
+const GO_BUILD_INFO_MAGIC: &[u8] = b"\xff Go buildinf:";
+const GO_BUILD_INFO_HEADER_SIZE: usize = 32;
+const GO_FLAG_BIG_ENDIAN: u8 = 0x1;
+/* Since Go 1.18 the strings follow the header instead of being pointed to */
+const GO_FLAG_INLINE_STRINGS: u8 = 0x2;
+/* The module information is framed by 16 bytes of sentinels */
+const GO_MODULE_INFO_SENTINEL_SIZE: usize = 16;
+
+#[derive(Debug, Clone, Default)]
+pub struct GoModule {
+    pub path: String,
+    pub version: String,
+    /// "h1:" hash of go.sum, None for the main module
+    pub sum: Option<String>,
+    /// "path version" of the replace directive
+    pub replaced_by: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GoBuildInfo {
+    pub go_version: String,
+    /// Package path of the main package
+    pub path: Option<String>,
+    pub main_module: Option<GoModule>,
+    pub dependencies: Vec<GoModule>,
+    /// "-compiler=gc", "GOOS=linux", "vcs.revision=..."
+    pub settings: Vec<String>,
+}
+
+fn read_uvarint(data: &[u8], position: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+
+    for shift in (0..64).step_by(7) {
+        let byte = *data.get(*position)?;
+        *position += 1;
+
+        value |= ((byte & 0x7F) as u64) << shift;
+
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+
+    return None;
+}
+
+fn read_varint_string(data: &[u8], position: &mut usize) -> Option<String> {
+    let length = read_uvarint(data, position)? as usize;
+    let bytes = data.get(*position..position.checked_add(length)?)?;
+
+    *position += length;
+
+    return Some(String::from_utf8_lossy(bytes).to_string());
+}
+
+/// Bytes of the file at a virtual address, up to the end of the file
+fn file_data_at<'a>(exec: &Exec, file_bytes: &'a [u8], address: u64) -> Option<&'a [u8]> {
+    let file_size = file_bytes.len() as u64;
+
+    let location = AddressLocation::locate_address(exec, Some(AddressKind::VirtualAddress), address, file_size).ok()?;
+
+    return file_bytes.get(location.offset? as usize..);
+}
+
+/// Go string header (data pointer, length) pointed to by the build information of Go 1.17 and earlier
+fn read_go_string_pointer(exec: &Exec, file_bytes: &[u8], pointer: u64, pointer_size: usize, big_endian: bool) -> Option<String> {
+    let read_pointer = |data: &[u8]| -> Option<u64> {
+        let bytes = data.get(..pointer_size)?;
+
+        let value = match big_endian {
+            true => bytes.iter().fold(0u64, |value, b| (value << 8) | *b as u64),
+            false => bytes.iter().rev().fold(0u64, |value, b| (value << 8) | *b as u64),
+        };
+
+        return Some(value);
+    };
+
+    let header = file_data_at(exec, file_bytes, pointer)?;
+    let data_pointer = read_pointer(header)?;
+    let length = read_pointer(header.get(pointer_size..)?)? as usize;
+
+    let data = file_data_at(exec, file_bytes, data_pointer)?.get(..length)?;
+
+    return Some(String::from_utf8_lossy(data).to_string());
+}
+
+impl GoModule {
+    /// "<path>\t<version>\t<sum>" fields of a "mod" or "dep" line
+    fn from_fields(fields: &[&str]) -> GoModule {
+        return GoModule {
+            path: fields.first().map(|s| s.to_string()).unwrap_or_default(),
+            version: fields.get(1).map(|s| s.to_string()).unwrap_or_default(),
+            sum: fields.get(2).filter(|s| !s.is_empty()).map(|s| s.to_string()),
+            replaced_by: None,
+        };
+    }
+
+    fn describe(&self) -> String {
+        let mut description = format!("{} {}", self.path, self.version);
+
+        if let Some(ref replaced_by) = self.replaced_by {
+            description.push_str(&format!(" => {}", replaced_by));
+        }
+
+        if let Some(ref sum) = self.sum {
+            description.push_str(&format!(" {}", sum));
+        }
+
+        return description;
+    }
+}
+
+impl GoBuildInfo {
+    /// Finds the build information in the .go.buildinfo section of ELF, in the data sections of PE
+    pub fn from_exec(exec: &Exec, file_bytes: &[u8]) -> Option<GoBuildInfo> {
+        let data = match exec {
+            Exec::ELF(elf) => match elf.sections.get(".go.buildinfo") {
+                Some(section) => section.data.as_slice(),
+                None => file_bytes,
+            },
+            Exec::PE(_) => file_bytes,
+        };
+
+        /* The header is aligned on 16 bytes */
+        let start = (0..data.len())
+            .step_by(16)
+            .find(|offset| data.len() - offset >= GO_BUILD_INFO_HEADER_SIZE && data[*offset..].starts_with(GO_BUILD_INFO_MAGIC))?;
+
+        let header = &data[start..];
+        let pointer_size = header[GO_BUILD_INFO_MAGIC.len()] as usize;
+        let flags = header[GO_BUILD_INFO_MAGIC.len() + 1];
+
+        let (go_version, module_info) = match flags & GO_FLAG_INLINE_STRINGS != 0 {
+            true => {
+                let mut position = GO_BUILD_INFO_HEADER_SIZE;
+                (read_varint_string(header, &mut position)?, read_varint_string(header, &mut position).unwrap_or_default())
+            }
+            false => {
+                if pointer_size != 4 && pointer_size != 8 {
+                    return None;
+                }
+
+                let big_endian = flags & GO_FLAG_BIG_ENDIAN != 0;
+                let pointer_at = |offset: usize| -> Option<u64> {
+                    let bytes = header.get(offset..offset + pointer_size)?;
+
+                    match big_endian {
+                        true => return Some(bytes.iter().fold(0u64, |value, b| (value << 8) | *b as u64)),
+                        false => return Some(bytes.iter().rev().fold(0u64, |value, b| (value << 8) | *b as u64)),
+                    }
+                };
+
+                let version_pointer = pointer_at(16)?;
+                let module_pointer = pointer_at(16 + pointer_size)?;
+
+                (
+                    read_go_string_pointer(exec, file_bytes, version_pointer, pointer_size, big_endian)?,
+                    read_go_string_pointer(exec, file_bytes, module_pointer, pointer_size, big_endian).unwrap_or_default(),
+                )
+            }
+        };
+
+        let mut info = GoBuildInfo { go_version, ..GoBuildInfo::default() };
+
+        let module_info = match module_info.len() > 2 * GO_MODULE_INFO_SENTINEL_SIZE {
+            true => module_info.get(GO_MODULE_INFO_SENTINEL_SIZE..module_info.len() - GO_MODULE_INFO_SENTINEL_SIZE).unwrap_or_default(),
+            false => "",
+        };
+
+        for line in module_info.lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+
+            match fields[0] {
+                "path" => info.path = fields.get(1).map(|s| s.to_string()),
+                "mod" => info.main_module = Some(GoModule::from_fields(&fields[1..])),
+                "dep" => info.dependencies.push(GoModule::from_fields(&fields[1..])),
+                "=>" => {
+                    if let Some(dependency) = info.dependencies.last_mut() {
+                        dependency.replaced_by = Some(fields[1..].iter().take(2).copied().collect::<Vec<&str>>().join(" "));
+                        dependency.sum = fields.get(3).filter(|s| !s.is_empty()).map(|s| s.to_string());
+                    }
+                }
+                "build" => info.settings.push(fields[1..].join("\t")),
+                _ => {}
+            }
+        }
+
+        return Some(info);
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Go");
+
+        dump.push_field("Version", self.go_version.clone(), None);
+
+        if let Some(ref path) = self.path {
+            dump.push_field("Path", path.clone(), None);
+        }
+
+        if let Some(ref main_module) = self.main_module {
+            dump.push_field("Module", main_module.describe(), None);
+        }
+
+        let mut dependencies_dump = Dump::new_from_string(format!("Dependencies ({})", self.dependencies.len()));
+
+        for dependency in self.dependencies.iter() {
+            dependencies_dump.push_field("", dependency.describe(), None);
+        }
+
+        dump.push_child(dependencies_dump);
+
+        let mut settings_dump = Dump::new_from_string(format!("Build Settings ({})", self.settings.len()));
+
+        for setting in self.settings.iter() {
+            settings_dump.push_field("", setting.clone(), None);
+        }
+
+        dump.push_child(settings_dump);
+
+        return dump;
+    }
+}
+
+/* Rust */
+
+/* The panic locations of the standard library and of the crates keep the paths they were built from */
+const RUST_TOOLCHAIN_PATTERN: &str = r"(?-u)[/\\]rustc[/\\]([0-9a-f]{40})[/\\]";
+const RUST_CRATE_PATTERN: &str = r"(?-u)[/\\]registry[/\\]src[/\\][^/\\\x00]+[/\\]([A-Za-z0-9_\-]+?)-(\d+\.\d+\.\d+(?:-[0-9A-Za-z.\-]+)?(?:\+[0-9A-Za-z.\-]+)?)[/\\]";
+const RUST_PANIC_STRINGS: [&str; 2] = ["called `Option::unwrap()` on a `None` value", "called `Result::unwrap()` on an `Err` value"];
+
+#[derive(Debug, Clone, Default)]
+pub struct RustMetadata {
+    /// Commit of the rustc release, from the paths of the standard library
+    pub rustc_commit: Option<String>,
+    /// "rustc version 1.75.0 (82e1608df 2023-12-21)" of the ELF .comment section
+    pub rustc_version: Option<String>,
+    /// Crates from crates.io with their version, sorted by name
+    pub crates: Vec<(String, String)>,
+}
+
+impl RustMetadata {
+    pub fn from_exec(exec: &Exec, file_bytes: &[u8]) -> Option<RustMetadata> {
+        let mut metadata = RustMetadata::default();
+
+        let toolchain_regex = Regex::new(RUST_TOOLCHAIN_PATTERN).unwrap();
+        let crate_regex = Regex::new(RUST_CRATE_PATTERN).unwrap();
+
+        metadata.rustc_commit = toolchain_regex
+            .captures(file_bytes)
+            .map(|captures| String::from_utf8_lossy(&captures[1]).to_string());
+
+        if let Exec::ELF(elf) = exec {
+            metadata.rustc_version = elf.sections.get(".comment").and_then(|comment| {
+                comment
+                    .data
+                    .split(|b| *b == 0)
+                    .map(|entry| String::from_utf8_lossy(entry).to_string())
+                    .find(|entry| entry.starts_with("rustc version "))
+            });
+        }
+
+        for captures in crate_regex.captures_iter(file_bytes) {
+            let name = String::from_utf8_lossy(&captures[1]).to_string();
+            let version = String::from_utf8_lossy(&captures[2]).to_string();
+
+            if !metadata.crates.iter().any(|(n, v)| *n == name && *v == version) {
+                metadata.crates.push((name, version));
+            }
+        }
+
+        metadata.crates.sort();
+
+        let panics = RUST_PANIC_STRINGS.iter().any(|panic| file_bytes.windows(panic.len()).any(|window| window == panic.as_bytes()));
+
+        if metadata.rustc_commit.is_none() && metadata.rustc_version.is_none() && metadata.crates.is_empty() && !panics {
+            return None;
+        }
+
+        return Some(metadata);
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Rust");
+
+        if let Some(ref rustc_version) = self.rustc_version {
+            dump.push_field("RustcVersion", rustc_version.clone(), None);
+        }
+
+        match self.rustc_commit {
+            Some(ref rustc_commit) => dump.push_field("RustcCommit", rustc_commit.clone(), None),
+            None => dump.push_field("RustcCommit", String::from("None (no standard library path)"), None),
+        }
+
+        let mut crates_dump = Dump::new_from_string(format!("Crates ({})", self.crates.len()));
+
+        for (name, version) in self.crates.iter() {
+            crates_dump.push_field("", format!("{} {}", name, version), None);
+        }
+
+        dump.push_child(crates_dump);
+
+        return dump;
+    }
+}
+
+/* Delphi */
+
+/* RCDATA resources of the Delphi and C++Builder linkers */
+const DELPHI_PACKAGE_INFO: &str = "PACKAGEINFO";
+const DELPHI_LICENSE: &str = "DVCLAL";
+
+/* Producer bits of the package information flags */
+const DELPHI_PRODUCER_MASK: u32 = 0x0C000000;
+const DELPHI_PRODUCER_CPP_BUILDER: u32 = 0x08000000;
+const DELPHI_PRODUCER_DELPHI: u32 = 0x0C000000;
+
+#[derive(Debug, Clone, Default)]
+pub struct DelphiMetadata {
+    pub producer: Option<&'static str>,
+    /// The DVCLAL resource holding the edition of the compiler
+    pub license: bool,
+    pub required_packages: Vec<String>,
+    pub units: Vec<String>,
+}
+
+fn read_c_string(data: &[u8], position: &mut usize) -> Option<String> {
+    let length = data.get(*position..)?.iter().position(|b| *b == 0)?;
+    let value = String::from_utf8_lossy(&data[*position..*position + length]).to_string();
+
+    *position += length + 1;
+
+    return Some(value);
+}
+
+fn read_u32(data: &[u8], position: &mut usize) -> Option<u32> {
+    let value = u32::from_le_bytes(data.get(*position..*position + 4)?.try_into().ok()?);
+    *position += 4;
+    return Some(value);
+}
+
+impl DelphiMetadata {
+    /// Flags, the required packages (hash, name) and the contained units (flags, hash, name)
+    fn parse_package_info(&mut self, data: &[u8]) -> Option<()> {
+        let mut position = 0;
+        let flags = read_u32(data, &mut position)?;
+
+        self.producer = match flags & DELPHI_PRODUCER_MASK {
+            DELPHI_PRODUCER_DELPHI => Some("Delphi"),
+            DELPHI_PRODUCER_CPP_BUILDER => Some("C++Builder"),
+            _ => None,
+        };
+
+        for _ in 0..read_u32(data, &mut position)? {
+            position += 1;
+            self.required_packages.push(read_c_string(data, &mut position)?);
+        }
+
+        for _ in 0..read_u32(data, &mut position)? {
+            position += 2;
+            self.units.push(read_c_string(data, &mut position)?);
+        }
+
+        return Some(());
+    }
+
+    pub fn from_exec(exec: &Exec) -> Option<DelphiMetadata> {
+        let Exec::PE(pe) = exec else {
+            return None;
+        };
+
+        let resource_table = pe.resource_table.as_ref()?;
+
+        let rcdata = |name: &str| {
+            resource_table
+                .entries
+                .iter()
+                .find(|entry| entry.type_id == ResourceId::Id(ResourceType::Rcdata as u32) && entry.name_id == ResourceId::Name(String::from(name)))
+        };
+
+        let package_info = rcdata(DELPHI_PACKAGE_INFO);
+        let license = rcdata(DELPHI_LICENSE).is_some();
+
+        if package_info.is_none() && !license {
+            return None;
+        }
+
+        let mut metadata = DelphiMetadata { license, ..DelphiMetadata::default() };
+
+        /* A truncated package information keeps the names read before the end */
+        if let Some(entry) = package_info {
+            let _ = metadata.parse_package_info(&entry.data);
+        }
+
+        return Some(metadata);
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Delphi");
+
+        if let Some(producer) = self.producer {
+            dump.push_field("Producer", String::from(producer), None);
+        }
+
+        dump.push_field("License", String::from(if self.license { "DVCLAL resource" } else { "None" }), None);
+
+        let mut packages_dump = Dump::new_from_string(format!("Required Packages ({})", self.required_packages.len()));
+
+        for package in self.required_packages.iter() {
+            packages_dump.push_field("", package.clone(), None);
+        }
+
+        dump.push_child(packages_dump);
+
+        let mut units_dump = Dump::new_from_string(format!("Units ({})", self.units.len()));
+
+        for unit in self.units.iter() {
+            units_dump.push_field("", unit.clone(), None);
+        }
+
+        dump.push_child(units_dump);
+
+        return dump;
+    }
+}
+
+/* Report */
+
+#[derive(Debug, Clone, Default)]
+pub struct BuildInfo {
+    pub go: Option<GoBuildInfo>,
+    pub rust: Option<RustMetadata>,
+    pub delphi: Option<DelphiMetadata>,
+}
+
+impl BuildInfo {
+    pub fn from_exec(exec: &Exec, file_bytes: &[u8]) -> BuildInfo {
+        return BuildInfo {
+            go: GoBuildInfo::from_exec(exec, file_bytes),
+            rust: RustMetadata::from_exec(exec, file_bytes),
+            delphi: DelphiMetadata::from_exec(exec),
+        };
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.go.is_none() && self.rust.is_none() && self.delphi.is_none();
+    }
+
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Build Info");
+
+        if let Some(ref go) = self.go {
+            dump.push_child(go.dump());
+        }
+
+        if let Some(ref rust) = self.rust {
+            dump.push_child(rust.dump());
+        }
+
+        if let Some(ref delphi) = self.delphi {
+            dump.push_child(delphi.dump());
+        }
+
+        return dump;
+    }
+}
@@ -0,0 +1,247 @@
+use crate::dump::Dump;
+use crate::deps::{pe_imported_dlls, ImportRef};
+use crate::ordinals::ordinal_name;
+use crate::pe::PE;
+
+/*
+ * Capabilities inferred from imported APIs, optionally mapped to MITRE ATT&CK techniques
+ * https://attack.mitre.org/techniques/enterprise/
+ */
+
+#[derive(Debug, Clone, Copy)]
+pub struct AttackTechnique {
+    pub id: &'static str,
+    pub name: &'static str,
+}
+
+/// A capability is inferred when every group of APIs has at least one imported member
+#[derive(Debug, Clone, Copy)]
+pub struct CapabilityRule {
+    pub name: &'static str,
+    pub api_groups: &'static [&'static [&'static str]],
+    pub techniques: &'static [AttackTechnique],
+}
+
+const fn technique(id: &'static str, name: &'static str) -> AttackTechnique {
+    return AttackTechnique { id, name };
+}
+
+#[rustfmt::skip]
+pub const CAPABILITY_RULES: &[CapabilityRule] = &[
+    CapabilityRule {
+        name: "Process injection",
+        api_groups: &[
+            &["VirtualAllocEx", "NtAllocateVirtualMemory", "ZwAllocateVirtualMemory", "NtMapViewOfSection", "ZwMapViewOfSection"],
+            &["WriteProcessMemory", "NtWriteVirtualMemory", "ZwWriteVirtualMemory", "NtMapViewOfSection", "ZwMapViewOfSection"],
+            &["CreateRemoteThread", "NtCreateThreadEx", "RtlCreateUserThread", "QueueUserAPC", "NtQueueApcThread", "SetThreadContext"],
+        ],
+        techniques: &[technique("T1055", "Process Injection")],
+    },
+    CapabilityRule {
+        name: "Keylogging",
+        api_groups: &[&["SetWindowsHookEx", "GetAsyncKeyState", "GetKeyboardState", "RegisterRawInputDevices", "GetRawInputData"]],
+        techniques: &[technique("T1056.001", "Input Capture: Keylogging")],
+    },
+    CapabilityRule {
+        name: "Screen capture",
+        api_groups: &[&["BitBlt", "StretchBlt"], &["GetDC", "GetWindowDC", "CreateDC"]],
+        techniques: &[technique("T1113", "Screen Capture")],
+    },
+    CapabilityRule {
+        name: "Clipboard access",
+        api_groups: &[&["GetClipboardData", "SetClipboardData"]],
+        techniques: &[technique("T1115", "Clipboard Data")],
+    },
+    CapabilityRule {
+        name: "Registry modification",
+        api_groups: &[&["RegSetValue", "RegSetValueEx", "RegSetKeyValue", "RegCreateKey", "RegCreateKeyEx", "RegDeleteKey", "RegDeleteValue", "NtSetValueKey", "ZwSetValueKey"]],
+        techniques: &[technique("T1112", "Modify Registry")],
+    },
+    CapabilityRule {
+        name: "Service creation",
+        api_groups: &[&["OpenSCManager"], &["CreateService", "ChangeServiceConfig"]],
+        techniques: &[technique("T1543.003", "Create or Modify System Process: Windows Service")],
+    },
+    CapabilityRule {
+        name: "Kernel driver loading",
+        api_groups: &[&["NtLoadDriver", "ZwLoadDriver"]],
+        techniques: &[technique("T1543.003", "Create or Modify System Process: Windows Service")],
+    },
+    CapabilityRule {
+        name: "Command execution",
+        api_groups: &[&["CreateProcess", "CreateProcessAsUser", "CreateProcessWithLogon", "CreateProcessWithToken", "ShellExecute", "ShellExecuteEx", "WinExec", "system", "_wsystem"]],
+        techniques: &[technique("T1106", "Native API"), technique("T1059", "Command and Scripting Interpreter")],
+    },
+    CapabilityRule {
+        name: "Network communication",
+        api_groups: &[&["connect", "WSAConnect", "send", "recv", "WSASend", "WSARecv", "InternetOpen", "InternetConnect", "HttpSendRequest", "WinHttpOpen", "WinHttpSendRequest"]],
+        techniques: &[technique("T1071", "Application Layer Protocol")],
+    },
+    CapabilityRule {
+        name: "File download",
+        api_groups: &[&["URLDownloadToFile", "URLDownloadToCacheFile", "InternetReadFile", "WinHttpReadData"]],
+        techniques: &[technique("T1105", "Ingress Tool Transfer")],
+    },
+    CapabilityRule {
+        name: "Process discovery",
+        api_groups: &[&["CreateToolhelp32Snapshot", "Process32First", "Process32Next", "EnumProcesses", "NtQuerySystemInformation"]],
+        techniques: &[technique("T1057", "Process Discovery")],
+    },
+    CapabilityRule {
+        name: "System information discovery",
+        api_groups: &[&["GetComputerName", "GetComputerNameEx", "GetVersionEx", "RtlGetVersion", "GetNativeSystemInfo", "GetSystemInfo"]],
+        techniques: &[technique("T1082", "System Information Discovery")],
+    },
+    CapabilityRule {
+        name: "File and directory discovery",
+        api_groups: &[&["FindFirstFile", "FindFirstFileEx"], &["FindNextFile"]],
+        techniques: &[technique("T1083", "File and Directory Discovery")],
+    },
+    CapabilityRule {
+        name: "Debugger detection",
+        api_groups: &[&["IsDebuggerPresent", "CheckRemoteDebuggerPresent", "NtQueryInformationProcess", "OutputDebugString"]],
+        techniques: &[technique("T1622", "Debugger Evasion")],
+    },
+    CapabilityRule {
+        name: "Dynamic API resolution",
+        api_groups: &[&["LoadLibrary", "LoadLibraryEx", "LdrLoadDll"], &["GetProcAddress", "LdrGetProcedureAddress"]],
+        techniques: &[technique("T1027.007", "Obfuscated Files or Information: Dynamic API Resolution")],
+    },
+    CapabilityRule {
+        name: "Token manipulation",
+        api_groups: &[&["OpenProcessToken", "OpenThreadToken", "DuplicateToken", "DuplicateTokenEx"], &["AdjustTokenPrivileges", "ImpersonateLoggedOnUser", "SetThreadToken"]],
+        techniques: &[technique("T1134", "Access Token Manipulation")],
+    },
+    CapabilityRule {
+        name: "Credential access",
+        api_groups: &[&["CredEnumerate", "CredRead", "LsaRetrievePrivateData", "CryptUnprotectData", "SamQueryInformationUser"]],
+        techniques: &[technique("T1555", "Credentials from Password Stores")],
+    },
+    CapabilityRule {
+        name: "Data encryption",
+        api_groups: &[&["CryptEncrypt", "BCryptEncrypt", "CryptDecrypt", "BCryptDecrypt"]],
+        techniques: &[technique("T1486", "Data Encrypted for Impact")],
+    },
+    CapabilityRule {
+        name: "Timestomping",
+        api_groups: &[&["SetFileTime", "NtSetInformationFile"]],
+        techniques: &[technique("T1070.006", "Indicator Removal: Timestomp")],
+    },
+    CapabilityRule {
+        name: "System shutdown or reboot",
+        api_groups: &[&["ExitWindowsEx", "InitiateSystemShutdown", "InitiateSystemShutdownEx", "NtShutdownSystem"]],
+        techniques: &[technique("T1529", "System Shutdown/Reboot")],
+    },
+];
+
+fn is_rule_api(name: &str) -> bool {
+    return CAPABILITY_RULES.iter().any(|rule| rule.api_groups.iter().any(|group| group.contains(&name)));
+}
+
+/// Strips the ANSI/Unicode suffix of Win32 APIs, so that rules can use the base name
+pub fn normalize_api_name(name: &str) -> &str {
+    if name.len() > 1 && (name.ends_with('A') || name.ends_with('W')) && !is_rule_api(name) {
+        let base = &name[..name.len() - 1];
+
+        /* "CreateProcessW" -> "CreateProcess", the suffix mostly follows a lowercase letter or a digit but not always
+         * ("CreateDCW"), the base names of the rules are stripped whatever precedes the suffix */
+        if is_rule_api(base) || base.chars().last().is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit()) {
+            return base;
+        }
+    }
+
+    return name;
+}
+
+#[derive(Debug, Clone)]
+pub struct Capability {
+    pub rule: &'static CapabilityRule,
+    pub evidence: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CapabilitiesReport {
+    pub capabilities: Vec<Capability>,
+}
+
+impl CapabilitiesReport {
+    pub fn from_imports(imports: &[String]) -> CapabilitiesReport {
+        let mut report = CapabilitiesReport::default();
+
+        for rule in CAPABILITY_RULES.iter() {
+            let mut evidence = Vec::new();
+            let mut matched = true;
+
+            for group in rule.api_groups.iter() {
+                let group_evidence: Vec<&String> = imports
+                    .iter()
+                    .filter(|import| group.contains(&normalize_api_name(import)))
+                    .collect();
+
+                if group_evidence.is_empty() {
+                    matched = false;
+                    break;
+                }
+
+                for import in group_evidence {
+                    if !evidence.contains(import) {
+                        evidence.push(import.clone());
+                    }
+                }
+            }
+
+            if matched {
+                report.capabilities.push(Capability { rule, evidence });
+            }
+        }
+
+        return report;
+    }
+
+    pub fn dump(&self, attack: bool) -> Dump {
+        let mut dump = Dump::new_from_string(format!("Capabilities ({})", self.capabilities.len()));
+
+        for capability in self.capabilities.iter() {
+            let mut capability_dump = Dump::new(capability.rule.name);
+
+            capability_dump.push_field("APIs", capability.evidence.join(", "), None);
+
+            if attack {
+                let techniques: Vec<String> = capability
+                    .rule
+                    .techniques
+                    .iter()
+                    .map(|t| format!("{} ({})", t.id, t.name))
+                    .collect();
+
+                capability_dump.push_field("ATT&CK", techniques.join(", "), None);
+            }
+
+            dump.push_child(capability_dump);
+        }
+
+        return dump;
+    }
+}
+
+pub fn infer_pe_capabilities(pe: &PE) -> CapabilitiesReport {
+    let mut imports: Vec<String> = match pe.hint_name_table {
+        Some(ref hnt) => hnt
+            .entries
+            .iter()
+            .flat_map(|dll| dll.entries.iter().map(|entry| entry.name.clone()))
+            .collect(),
+        None => Vec::new(),
+    };
+
+    /* Winsock functions are commonly imported by ordinal */
+    for dll in pe_imported_dlls(pe) {
+        for function in dll.functions.iter() {
+            if let ImportRef::Ordinal(ordinal) = function {
+                imports.extend(ordinal_name(&dll.name, *ordinal).map(String::from));
+            }
+        }
+    }
+
+    return CapabilitiesReport::from_imports(&imports);
+}
@@ -0,0 +1,185 @@
+use std::path::{Path, PathBuf};
+
+use crate::analysis::{identify_file_type, EmbeddedFileType};
+use crate::dump::Dump;
+use crate::extract::sanitize_file_name;
+use crate::format::format_timestamp;
+use crate::pe::{CharacteristicsFlag, MachineType, PEReader, PE};
+
+/*
+ * Carving of the PE images embedded in arbitrary files (droppers, firmware blobs, self-extractors, memory captures)
+ */
+
+/* Older Windows loaders refuse images with more sections, more is garbage that happens to follow "PE\0\0" */
+const MAX_CARVED_SECTIONS: usize = 96;
+
+#[derive(Debug, Clone)]
+pub struct CarvedExecutable {
+    pub file_offset: u64,
+    /// From the headers up to the end of the last section raw data or of the certificates
+    pub size: u64,
+    /// The file ends before the size the headers describe
+    pub truncated: bool,
+    pub machine: u16,
+    pub is_32_bits: bool,
+    pub is_dll: bool,
+    pub number_of_sections: usize,
+    pub time_date_stamp: u32,
+    /// Index of the carved executable holding this one
+    pub parent: Option<usize>,
+    pub extracted: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RejectedCandidate {
+    pub file_offset: u64,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CarveReport {
+    pub executables: Vec<CarvedExecutable>,
+    pub rejected: Vec<RejectedCandidate>,
+}
+
+/// Parses and sanity checks the headers of a PE starting at the beginning of the data
+fn validate_candidate(data: &[u8], file_offset: u64) -> Result<CarvedExecutable, String> {
+    let mut pe = PE::new();
+    let section_headers = pe.parse_headers(&mut PEReader::from_bytes(data)).map_err(|e| e.to_string())?;
+
+    let machine = pe.get_nt_header().coff_header.machine;
+    let optional_header = pe.get_optional_header();
+
+    if MachineType::from(machine) == MachineType::Unknown {
+        return Err(format!("unknown machine {:#x}", machine));
+    }
+
+    if section_headers.is_empty() || section_headers.len() > MAX_CARVED_SECTIONS {
+        return Err(format!("{} sections", section_headers.len()));
+    }
+
+    if !optional_header.get_section_alignment().is_power_of_two() || !optional_header.get_file_alignment().is_power_of_two() {
+        return Err(format!("alignments {:#x}/{:#x} are not powers of two", optional_header.get_section_alignment(), optional_header.get_file_alignment()));
+    }
+
+    if optional_header.get_size_of_headers() > optional_header.get_size_of_image() {
+        return Err(format!("SizeOfHeaders {:#x} is past SizeOfImage {:#x}", optional_header.get_size_of_headers(), optional_header.get_size_of_image()));
+    }
+
+    let sections_end = section_headers
+        .iter()
+        .filter(|header| header.size_of_raw_data != 0)
+        .map(|header| header.ptr_to_raw_data as u64 + header.size_of_raw_data as u64)
+        .max()
+        .unwrap_or(0);
+
+    /* The certificates are the only data appended after the sections given by a file offset */
+    let certificate_idd = optional_header.get_certificate_table_idd();
+    let certificates_end = certificate_idd.virtual_address as u64 + certificate_idd.size as u64;
+
+    let size = (optional_header.get_size_of_headers() as u64).max(sections_end).max(certificates_end);
+
+    return Ok(CarvedExecutable {
+        file_offset,
+        size: size.min(data.len() as u64),
+        truncated: size > data.len() as u64,
+        machine,
+        is_32_bits: pe.is_32_bits(),
+        is_dll: (pe.get_nt_header().coff_header.characteristics & CharacteristicsFlag::DLL as u16) != 0,
+        number_of_sections: section_headers.len(),
+        time_date_stamp: pe.get_nt_header().coff_header.time_date_stamp,
+        parent: None,
+        extracted: None,
+    });
+}
+
+impl CarveReport {
+    /// Looks for the PE images past the first byte, the file itself is not reported
+    pub fn scan(bytes: &[u8]) -> CarveReport {
+        let mut report = CarveReport::default();
+
+        for offset in 1..bytes.len().saturating_sub(1) {
+            if bytes[offset] != b'M' || bytes[offset + 1] != b'Z' {
+                continue;
+            }
+
+            if !matches!(identify_file_type(&bytes[offset..]), Some((EmbeddedFileType::PE, _))) {
+                continue;
+            }
+
+            match validate_candidate(&bytes[offset..], offset as u64) {
+                Ok(mut executable) => {
+                    executable.parent = report
+                        .executables
+                        .iter()
+                        .rposition(|outer| (offset as u64) < outer.file_offset + outer.size);
+
+                    report.executables.push(executable);
+                }
+                Err(reason) => report.rejected.push(RejectedCandidate { file_offset: offset as u64, reason }),
+            }
+        }
+
+        return report;
+    }
+
+    /// Writes the carved executables to the output directory, named after the input file and their offset
+    pub fn extract(&mut self, bytes: &[u8], file_path: &Path, output_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::create_dir_all(output_dir)?;
+
+        let stem = sanitize_file_name(&file_path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default());
+
+        for executable in self.executables.iter_mut() {
+            let extension = if executable.is_dll { "dll" } else { "exe" };
+            let path = output_dir.join(format!("{}_{:x}.{}", stem, executable.file_offset, extension));
+
+            std::fs::write(&path, &bytes[executable.file_offset as usize..(executable.file_offset + executable.size) as usize])?;
+
+            executable.extracted = Some(path);
+        }
+
+        return Ok(());
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new_from_string(format!("Carved Executables ({})", self.executables.len()));
+
+        for (index, executable) in self.executables.iter().enumerate() {
+            let mut executable_dump = Dump::new_from_string(format!("#{} at {:#x}", index, executable.file_offset));
+
+            executable_dump.push_field("FileOffset", format!("{:#x}", executable.file_offset), None);
+
+            match executable.truncated {
+                true => executable_dump.push_field("Size", format!("{:#x} (truncated, the file ends first)", executable.size), None),
+                false => executable_dump.push_field("Size", format!("{:#x}", executable.size), None),
+            }
+
+            executable_dump.push_field("Format", String::from(if executable.is_32_bits { "PE32" } else { "PE32+" }), None);
+            executable_dump.push_field("Machine", format!("{:?}", MachineType::from(executable.machine)), None);
+            executable_dump.push_field("Type", String::from(if executable.is_dll { "DLL" } else { "EXE" }), None);
+            executable_dump.push_field("Sections", format!("{}", executable.number_of_sections), None);
+            executable_dump.push_field("TimeDateStamp", format_timestamp(executable.time_date_stamp), None);
+
+            if let Some(parent) = executable.parent {
+                executable_dump.push_field("ContainedIn", format!("#{} at {:#x}", parent, self.executables[parent].file_offset), None);
+            }
+
+            if let Some(ref path) = executable.extracted {
+                executable_dump.push_field("Output", path.display().to_string(), None);
+            }
+
+            dump.push_child(executable_dump);
+        }
+
+        let mut rejected_dump = Dump::new_from_string(format!("Rejected Candidates ({})", self.rejected.len()));
+
+        for candidate in self.rejected.iter() {
+            rejected_dump.push_field("", format!("{:#x}: {}", candidate.file_offset, candidate.reason), None);
+        }
+
+        dump.push_child(rejected_dump);
+
+        return dump;
+    }
+}
@@ -1,4 +1,4 @@
 
 pub fn is_digit(s: &str) -> bool {
-    return s.as_bytes().first().map_or(false, |&b| matches!(b, b'0'..=b'9'));
+    return s.as_bytes().first().is_some_and(|b| b.is_ascii_digit());
 }
@@ -0,0 +1,526 @@
+use crate::dotnet::{DotNetData, MetadataTable, SignatureReader, SIGNATURE_LOCAL_VARIABLES};
+use crate::dump::Dump;
+use crate::pe::PE;
+
+/*
+ * CIL (MSIL) method bodies and instructions
+ * ECMA-335, Partition II.25.4 (method bodies) and Partition III (instruction set)
+ */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperandType {
+    None,
+    ShortInt,
+    Int,
+    Long,
+    ShortFloat,
+    Float,
+    ShortVariable,
+    Variable,
+    ShortBranchTarget,
+    BranchTarget,
+    Switch,
+    Token,
+}
+
+/// Mnemonic and operand of an opcode, the two-byte opcodes are prefixed with 0xfe
+#[rustfmt::skip]
+pub fn opcode_info(opcode: u16) -> Option<(&'static str, OperandType)> {
+    use OperandType::*;
+
+    let info = match opcode {
+        0x00 => ("nop", None), 0x01 => ("break", None),
+        0x02 => ("ldarg.0", None), 0x03 => ("ldarg.1", None), 0x04 => ("ldarg.2", None), 0x05 => ("ldarg.3", None),
+        0x06 => ("ldloc.0", None), 0x07 => ("ldloc.1", None), 0x08 => ("ldloc.2", None), 0x09 => ("ldloc.3", None),
+        0x0a => ("stloc.0", None), 0x0b => ("stloc.1", None), 0x0c => ("stloc.2", None), 0x0d => ("stloc.3", None),
+        0x0e => ("ldarg.s", ShortVariable), 0x0f => ("ldarga.s", ShortVariable), 0x10 => ("starg.s", ShortVariable),
+        0x11 => ("ldloc.s", ShortVariable), 0x12 => ("ldloca.s", ShortVariable), 0x13 => ("stloc.s", ShortVariable),
+        0x14 => ("ldnull", None), 0x15 => ("ldc.i4.m1", None),
+        0x16 => ("ldc.i4.0", None), 0x17 => ("ldc.i4.1", None), 0x18 => ("ldc.i4.2", None), 0x19 => ("ldc.i4.3", None),
+        0x1a => ("ldc.i4.4", None), 0x1b => ("ldc.i4.5", None), 0x1c => ("ldc.i4.6", None), 0x1d => ("ldc.i4.7", None),
+        0x1e => ("ldc.i4.8", None), 0x1f => ("ldc.i4.s", ShortInt), 0x20 => ("ldc.i4", Int), 0x21 => ("ldc.i8", Long),
+        0x22 => ("ldc.r4", ShortFloat), 0x23 => ("ldc.r8", Float),
+        0x25 => ("dup", None), 0x26 => ("pop", None), 0x27 => ("jmp", Token), 0x28 => ("call", Token),
+        0x29 => ("calli", Token), 0x2a => ("ret", None),
+        0x2b => ("br.s", ShortBranchTarget), 0x2c => ("brfalse.s", ShortBranchTarget), 0x2d => ("brtrue.s", ShortBranchTarget),
+        0x2e => ("beq.s", ShortBranchTarget), 0x2f => ("bge.s", ShortBranchTarget), 0x30 => ("bgt.s", ShortBranchTarget),
+        0x31 => ("ble.s", ShortBranchTarget), 0x32 => ("blt.s", ShortBranchTarget), 0x33 => ("bne.un.s", ShortBranchTarget),
+        0x34 => ("bge.un.s", ShortBranchTarget), 0x35 => ("bgt.un.s", ShortBranchTarget), 0x36 => ("ble.un.s", ShortBranchTarget),
+        0x37 => ("blt.un.s", ShortBranchTarget),
+        0x38 => ("br", BranchTarget), 0x39 => ("brfalse", BranchTarget), 0x3a => ("brtrue", BranchTarget),
+        0x3b => ("beq", BranchTarget), 0x3c => ("bge", BranchTarget), 0x3d => ("bgt", BranchTarget),
+        0x3e => ("ble", BranchTarget), 0x3f => ("blt", BranchTarget), 0x40 => ("bne.un", BranchTarget),
+        0x41 => ("bge.un", BranchTarget), 0x42 => ("bgt.un", BranchTarget), 0x43 => ("ble.un", BranchTarget),
+        0x44 => ("blt.un", BranchTarget), 0x45 => ("switch", Switch),
+        0x46 => ("ldind.i1", None), 0x47 => ("ldind.u1", None), 0x48 => ("ldind.i2", None), 0x49 => ("ldind.u2", None),
+        0x4a => ("ldind.i4", None), 0x4b => ("ldind.u4", None), 0x4c => ("ldind.i8", None), 0x4d => ("ldind.i", None),
+        0x4e => ("ldind.r4", None), 0x4f => ("ldind.r8", None), 0x50 => ("ldind.ref", None), 0x51 => ("stind.ref", None),
+        0x52 => ("stind.i1", None), 0x53 => ("stind.i2", None), 0x54 => ("stind.i4", None), 0x55 => ("stind.i8", None),
+        0x56 => ("stind.r4", None), 0x57 => ("stind.r8", None),
+        0x58 => ("add", None), 0x59 => ("sub", None), 0x5a => ("mul", None), 0x5b => ("div", None),
+        0x5c => ("div.un", None), 0x5d => ("rem", None), 0x5e => ("rem.un", None), 0x5f => ("and", None),
+        0x60 => ("or", None), 0x61 => ("xor", None), 0x62 => ("shl", None), 0x63 => ("shr", None),
+        0x64 => ("shr.un", None), 0x65 => ("neg", None), 0x66 => ("not", None),
+        0x67 => ("conv.i1", None), 0x68 => ("conv.i2", None), 0x69 => ("conv.i4", None), 0x6a => ("conv.i8", None),
+        0x6b => ("conv.r4", None), 0x6c => ("conv.r8", None), 0x6d => ("conv.u4", None), 0x6e => ("conv.u8", None),
+        0x6f => ("callvirt", Token), 0x70 => ("cpobj", Token), 0x71 => ("ldobj", Token), 0x72 => ("ldstr", Token),
+        0x73 => ("newobj", Token), 0x74 => ("castclass", Token), 0x75 => ("isinst", Token), 0x76 => ("conv.r.un", None),
+        0x79 => ("unbox", Token), 0x7a => ("throw", None),
+        0x7b => ("ldfld", Token), 0x7c => ("ldflda", Token), 0x7d => ("stfld", Token), 0x7e => ("ldsfld", Token),
+        0x7f => ("ldsflda", Token), 0x80 => ("stsfld", Token), 0x81 => ("stobj", Token),
+        0x82 => ("conv.ovf.i1.un", None), 0x83 => ("conv.ovf.i2.un", None), 0x84 => ("conv.ovf.i4.un", None),
+        0x85 => ("conv.ovf.i8.un", None), 0x86 => ("conv.ovf.u1.un", None), 0x87 => ("conv.ovf.u2.un", None),
+        0x88 => ("conv.ovf.u4.un", None), 0x89 => ("conv.ovf.u8.un", None), 0x8a => ("conv.ovf.i.un", None),
+        0x8b => ("conv.ovf.u.un", None),
+        0x8c => ("box", Token), 0x8d => ("newarr", Token), 0x8e => ("ldlen", None), 0x8f => ("ldelema", Token),
+        0x90 => ("ldelem.i1", None), 0x91 => ("ldelem.u1", None), 0x92 => ("ldelem.i2", None), 0x93 => ("ldelem.u2", None),
+        0x94 => ("ldelem.i4", None), 0x95 => ("ldelem.u4", None), 0x96 => ("ldelem.i8", None), 0x97 => ("ldelem.i", None),
+        0x98 => ("ldelem.r4", None), 0x99 => ("ldelem.r8", None), 0x9a => ("ldelem.ref", None),
+        0x9b => ("stelem.i", None), 0x9c => ("stelem.i1", None), 0x9d => ("stelem.i2", None), 0x9e => ("stelem.i4", None),
+        0x9f => ("stelem.i8", None), 0xa0 => ("stelem.r4", None), 0xa1 => ("stelem.r8", None), 0xa2 => ("stelem.ref", None),
+        0xa3 => ("ldelem", Token), 0xa4 => ("stelem", Token), 0xa5 => ("unbox.any", Token),
+        0xb3 => ("conv.ovf.i1", None), 0xb4 => ("conv.ovf.u1", None), 0xb5 => ("conv.ovf.i2", None),
+        0xb6 => ("conv.ovf.u2", None), 0xb7 => ("conv.ovf.i4", None), 0xb8 => ("conv.ovf.u4", None),
+        0xb9 => ("conv.ovf.i8", None), 0xba => ("conv.ovf.u8", None),
+        0xc2 => ("refanyval", Token), 0xc3 => ("ckfinite", None), 0xc6 => ("mkrefany", Token),
+        0xd0 => ("ldtoken", Token), 0xd1 => ("conv.u2", None), 0xd2 => ("conv.u1", None), 0xd3 => ("conv.i", None),
+        0xd4 => ("conv.ovf.i", None), 0xd5 => ("conv.ovf.u", None), 0xd6 => ("add.ovf", None), 0xd7 => ("add.ovf.un", None),
+        0xd8 => ("mul.ovf", None), 0xd9 => ("mul.ovf.un", None), 0xda => ("sub.ovf", None), 0xdb => ("sub.ovf.un", None),
+        0xdc => ("endfinally", None), 0xdd => ("leave", BranchTarget), 0xde => ("leave.s", ShortBranchTarget),
+        0xdf => ("stind.i", None), 0xe0 => ("conv.u", None),
+        0xfe00 => ("arglist", None), 0xfe01 => ("ceq", None), 0xfe02 => ("cgt", None), 0xfe03 => ("cgt.un", None),
+        0xfe04 => ("clt", None), 0xfe05 => ("clt.un", None), 0xfe06 => ("ldftn", Token), 0xfe07 => ("ldvirtftn", Token),
+        0xfe09 => ("ldarg", Variable), 0xfe0a => ("ldarga", Variable), 0xfe0b => ("starg", Variable),
+        0xfe0c => ("ldloc", Variable), 0xfe0d => ("ldloca", Variable), 0xfe0e => ("stloc", Variable),
+        0xfe0f => ("localloc", None), 0xfe11 => ("endfilter", None), 0xfe12 => ("unaligned.", ShortVariable),
+        0xfe13 => ("volatile.", None), 0xfe14 => ("tail.", None), 0xfe15 => ("initobj", Token),
+        0xfe16 => ("constrained.", Token), 0xfe17 => ("cpblk", None), 0xfe18 => ("initblk", None),
+        0xfe19 => ("no.", ShortVariable), 0xfe1a => ("rethrow", None), 0xfe1c => ("sizeof", Token),
+        0xfe1d => ("refanytype", None), 0xfe1e => ("readonly.", None),
+        _ => return Option::None,
+    };
+
+    return Some(info);
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    None,
+    Int(i64),
+    Float(f64),
+    Variable(u16),
+    /// Offset of the target in the method body
+    Target(i64),
+    Switch(Vec<i64>),
+    Token(u32),
+}
+
+#[derive(Debug, Clone)]
+pub struct CilInstruction {
+    pub offset: u32,
+    pub mnemonic: &'static str,
+    pub operand: Operand,
+}
+
+fn read_bytes<const N: usize>(code: &[u8], position: &mut usize) -> Option<[u8; N]> {
+    let bytes = code.get(*position..*position + N)?.try_into().ok()?;
+    *position += N;
+
+    return Some(bytes);
+}
+
+impl CilInstruction {
+    /// Decodes the instruction at the position, which is moved past it
+    pub fn decode(code: &[u8], position: &mut usize) -> Result<CilInstruction, Box<dyn std::error::Error>> {
+        let offset = *position as u32;
+
+        let mut opcode = *code.get(*position).ok_or("Unexpected end of the method body")? as u16;
+        *position += 1;
+
+        if opcode == 0xfe {
+            opcode = 0xfe00 | *code.get(*position).ok_or("Unexpected end of the method body")? as u16;
+            *position += 1;
+        }
+
+        let (mnemonic, operand_type) = opcode_info(opcode).ok_or_else(|| format!("Invalid opcode {:#x} at IL_{:04x}", opcode, offset))?;
+
+        let truncated = || format!("Truncated operand of {} at IL_{:04x}", mnemonic, offset);
+
+        let operand = match operand_type {
+            OperandType::None => Operand::None,
+            OperandType::ShortInt => Operand::Int(i8::from_le_bytes(read_bytes(code, position).ok_or_else(truncated)?) as i64),
+            OperandType::Int => Operand::Int(i32::from_le_bytes(read_bytes(code, position).ok_or_else(truncated)?) as i64),
+            OperandType::Long => Operand::Int(i64::from_le_bytes(read_bytes(code, position).ok_or_else(truncated)?)),
+            OperandType::ShortFloat => Operand::Float(f32::from_le_bytes(read_bytes(code, position).ok_or_else(truncated)?) as f64),
+            OperandType::Float => Operand::Float(f64::from_le_bytes(read_bytes(code, position).ok_or_else(truncated)?)),
+            OperandType::ShortVariable => Operand::Variable(u8::from_le_bytes(read_bytes(code, position).ok_or_else(truncated)?) as u16),
+            OperandType::Variable => Operand::Variable(u16::from_le_bytes(read_bytes(code, position).ok_or_else(truncated)?)),
+            OperandType::Token => Operand::Token(u32::from_le_bytes(read_bytes(code, position).ok_or_else(truncated)?)),
+            /* Branch targets are relative to the next instruction */
+            OperandType::ShortBranchTarget => {
+                let delta = i8::from_le_bytes(read_bytes(code, position).ok_or_else(truncated)?) as i64;
+                Operand::Target(*position as i64 + delta)
+            }
+            OperandType::BranchTarget => {
+                let delta = i32::from_le_bytes(read_bytes(code, position).ok_or_else(truncated)?) as i64;
+                Operand::Target(*position as i64 + delta)
+            }
+            OperandType::Switch => {
+                let count = u32::from_le_bytes(read_bytes(code, position).ok_or_else(truncated)?) as usize;
+
+                if count > (code.len() - *position) / 4 {
+                    return Err(truncated().into());
+                }
+
+                let deltas: Vec<i64> = (0..count)
+                    .map(|_| i32::from_le_bytes(read_bytes(code, position).unwrap()) as i64)
+                    .collect();
+
+                Operand::Switch(deltas.iter().map(|delta| *position as i64 + delta).collect())
+            }
+        };
+
+        return Ok(CilInstruction { offset, mnemonic, operand });
+    }
+
+    pub fn format(&self, dotnet: &DotNetData) -> String {
+        let operand = match self.operand {
+            Operand::None => String::new(),
+            Operand::Int(value) => format!("{}", value),
+            Operand::Float(value) => format!("{:?}", value),
+            Operand::Variable(index) => format!("{}", index),
+            Operand::Target(target) => format!("IL_{:04x}", target),
+            Operand::Switch(ref targets) => format!("({})", targets.iter().map(|t| format!("IL_{:04x}", t)).collect::<Vec<_>>().join(", ")),
+            /* 0x70 tokens are offsets in the #US heap */
+            Operand::Token(token) if token >> 24 == 0x70 => match dotnet.heaps.user_string(token & 0x00ffffff) {
+                Some(string) => format!("\"{}\"", string.escape_debug()),
+                None => format!("{:#010x}", token),
+            },
+            Operand::Token(token) => dotnet.token_description(token),
+        };
+
+        return format!("IL_{:04x}:  {:<12} {}", self.offset, self.mnemonic, operand).trim_end().to_string();
+    }
+}
+
+/*
+ * Method bodies
+ */
+
+const TINY_FORMAT: u8 = 0x2;
+const FAT_FORMAT: u8 = 0x3;
+const FAT_MORE_SECTIONS: u16 = 0x8;
+const FAT_INIT_LOCALS: u16 = 0x10;
+
+const SECTION_EH_TABLE: u8 = 0x1;
+const SECTION_FAT_FORMAT: u8 = 0x40;
+const SECTION_MORE_SECTIONS: u8 = 0x80;
+
+/* MethodImplAttributes.CodeTypeMask */
+const METHOD_IMPL_CODE_TYPE_MASK: u32 = 0x3;
+const METHOD_IMPL_NATIVE: u32 = 0x1;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExceptionClause {
+    pub flags: u32,
+    pub try_offset: u32,
+    pub try_length: u32,
+    pub handler_offset: u32,
+    pub handler_length: u32,
+    /// Type caught by a catch clause or offset of the filter of a filter clause
+    pub class_token_or_filter_offset: u32,
+}
+
+impl ExceptionClause {
+    pub fn kind(&self) -> &'static str {
+        match self.flags & 0x7 {
+            0x0 => return "catch",
+            0x1 => return "filter",
+            0x2 => return "finally",
+            0x4 => return "fault",
+            _ => return "unknown",
+        }
+    }
+
+    pub fn format(&self, dotnet: &DotNetData) -> String {
+        let handler = match self.flags & 0x7 {
+            0x0 => format!("catch {}", dotnet.token_description(self.class_token_or_filter_offset)),
+            0x1 => format!("filter IL_{:04x}", self.class_token_or_filter_offset),
+            _ => String::from(self.kind()),
+        };
+
+        return format!(
+            "try IL_{:04x} to IL_{:04x} {} handler IL_{:04x} to IL_{:04x}",
+            self.try_offset,
+            self.try_offset + self.try_length,
+            handler,
+            self.handler_offset,
+            self.handler_offset + self.handler_length
+        );
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MethodBody {
+    pub fat: bool,
+    pub max_stack: u16,
+    pub init_locals: bool,
+    pub local_var_sig_token: u32,
+    pub code: Vec<u8>,
+    pub exception_clauses: Vec<ExceptionClause>,
+}
+
+impl MethodBody {
+    /// Parses a body from its header, data holds everything from the header to the end of its section
+    pub fn from_bytes(data: &[u8]) -> Result<MethodBody, Box<dyn std::error::Error>> {
+        let mut body = MethodBody::default();
+
+        let first = *data.first().ok_or("Empty method body")?;
+
+        let (header_size, code_size, more_sections) = match first & 0x3 {
+            TINY_FORMAT => {
+                body.max_stack = 8;
+                (1, (first >> 2) as usize, false)
+            }
+            FAT_FORMAT => {
+                let mut position = 0;
+                let header: [u8; 12] = read_bytes(data, &mut position).ok_or("Truncated fat method header")?;
+
+                let flags = u16::from_le_bytes([header[0], header[1]]);
+
+                body.fat = true;
+                body.max_stack = u16::from_le_bytes([header[2], header[3]]);
+                body.local_var_sig_token = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+                body.init_locals = flags & FAT_INIT_LOCALS != 0;
+
+                /* The size of the header is given in 4-byte units in its upper 4 bits */
+                let header_size = ((flags >> 12) as usize * 4).max(12);
+                let code_size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+
+                (header_size, code_size, flags & FAT_MORE_SECTIONS != 0)
+            }
+            _ => return Err(format!("Invalid method header format {:#x}", first).into()),
+        };
+
+        body.code = data.get(header_size..header_size + code_size).ok_or("Method body exceeds its section")?.to_vec();
+
+        let mut position = header_size + code_size;
+        let mut more_sections = more_sections;
+
+        while more_sections {
+            /* The extra data sections are 4-byte aligned */
+            position = position.next_multiple_of(4);
+
+            let kind = *data.get(position).ok_or("Truncated method data section")?;
+            let fat = kind & SECTION_FAT_FORMAT != 0;
+
+            let (size, clause_size) = match fat {
+                true => {
+                    let bytes = data.get(position + 1..position + 4).ok_or("Truncated method data section")?;
+                    (u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]) as usize, 24)
+                }
+                false => (*data.get(position + 1).ok_or("Truncated method data section")? as usize, 12),
+            };
+
+            let section = data.get(position..position + size).ok_or("Method data section exceeds its section")?;
+
+            if kind & SECTION_EH_TABLE != 0 {
+                for clause in section.get(4..).unwrap_or(&[]).chunks_exact(clause_size) {
+                    let u16_at = |i: usize| u16::from_le_bytes([clause[i], clause[i + 1]]) as u32;
+                    let u32_at = |i: usize| u32::from_le_bytes([clause[i], clause[i + 1], clause[i + 2], clause[i + 3]]);
+
+                    body.exception_clauses.push(match fat {
+                        true => ExceptionClause {
+                            flags: u32_at(0),
+                            try_offset: u32_at(4),
+                            try_length: u32_at(8),
+                            handler_offset: u32_at(12),
+                            handler_length: u32_at(16),
+                            class_token_or_filter_offset: u32_at(20),
+                        },
+                        false => ExceptionClause {
+                            flags: u16_at(0),
+                            try_offset: u16_at(2),
+                            try_length: clause[4] as u32,
+                            handler_offset: u16_at(5),
+                            handler_length: clause[7] as u32,
+                            class_token_or_filter_offset: u32_at(8),
+                        },
+                    });
+                }
+            }
+
+            more_sections = kind & SECTION_MORE_SECTIONS != 0;
+            position += size.max(4);
+        }
+
+        return Ok(body);
+    }
+
+    /// Decodes the instructions, stopping at the first invalid one
+    pub fn instructions(&self) -> (Vec<CilInstruction>, Option<String>) {
+        let mut instructions = Vec::new();
+        let mut position = 0;
+
+        while position < self.code.len() {
+            match CilInstruction::decode(&self.code, &mut position) {
+                Ok(instruction) => instructions.push(instruction),
+                Err(e) => return (instructions, Some(e.to_string())),
+            }
+        }
+
+        return (instructions, None);
+    }
+}
+
+/*
+ * Method lookup and disassembly
+ */
+
+#[derive(Debug, Clone, Default)]
+pub struct CilMethod {
+    pub token: u32,
+    pub name: String,
+    pub signature: String,
+    pub rva: u32,
+    pub body: Option<MethodBody>,
+    /// Why there is no body, or why it could not be read
+    pub error: Option<String>,
+}
+
+/// MethodDef rows matching a token (0x06000001) or a name (Method, Type::Method, Namespace.Type::Method)
+pub fn find_methods(dotnet: &DotNetData, query: &str) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+    if let Some(hex) = query.strip_prefix("0x").or_else(|| query.strip_prefix("0X")) {
+        let token = u32::from_str_radix(hex, 16)?;
+
+        if token >> 24 != MetadataTable::MethodDef as u32 {
+            return Err(format!("{:#010x} is not a MethodDef token (0x06xxxxxx)", token).into());
+        }
+
+        if token & 0x00ffffff == 0 || token & 0x00ffffff > dotnet.tables.row_count(MetadataTable::MethodDef) {
+            return Err(format!("No method with the token {:#010x}", token).into());
+        }
+
+        return Ok(vec![token & 0x00ffffff]);
+    }
+
+    let methods: Vec<u32> = (1..=dotnet.tables.row_count(MetadataTable::MethodDef))
+        .filter(|index| {
+            let full_name = dotnet.method_def_name(*index);
+            let name = full_name.rsplit("::").next().unwrap_or_default();
+
+            full_name == query || name == query || full_name.ends_with(&format!(".{}", query)) || full_name.ends_with(&format!("/{}", query))
+        })
+        .collect();
+
+    if methods.is_empty() {
+        return Err(format!("No method named \"{}\"", query).into());
+    }
+
+    return Ok(methods);
+}
+
+pub fn disassemble_method(pe: &PE, dotnet: &DotNetData, index: u32) -> CilMethod {
+    let mut method = CilMethod::default();
+
+    method.token = ((MetadataTable::MethodDef as u32) << 24) | index;
+    method.name = dotnet.method_def_name(index);
+    method.signature = dotnet.token_description(method.token);
+
+    let Some(row) = dotnet.tables.row(MetadataTable::MethodDef, index) else {
+        method.error = Some(String::from("Invalid MethodDef row"));
+        return method;
+    };
+
+    method.rva = row[0];
+
+    if row[1] & METHOD_IMPL_CODE_TYPE_MASK == METHOD_IMPL_NATIVE {
+        method.error = Some(String::from("Native method, its code is x86/x64 (see --disasm)"));
+        return method;
+    }
+
+    if method.rva == 0 {
+        method.error = Some(String::from("No body (abstract, P/Invoke or implemented by the runtime)"));
+        return method;
+    }
+
+    let Some(section) = pe.get_section_for_rva(method.rva) else {
+        method.error = Some(format!("Body RVA {:#x} is outside of the sections", method.rva));
+        return method;
+    };
+
+    let data = section.raw_data().get((method.rva - section.header.virtual_address) as usize..).unwrap_or(&[]);
+
+    match MethodBody::from_bytes(data) {
+        Ok(body) => method.body = Some(body),
+        Err(e) => method.error = Some(e.to_string()),
+    }
+
+    return method;
+}
+
+impl CilMethod {
+    fn locals(&self, dotnet: &DotNetData, token: u32) -> Option<Vec<String>> {
+        let row = dotnet.tables.row(MetadataTable::StandAloneSig, token & 0x00ffffff)?;
+        let mut reader = SignatureReader::new(dotnet, dotnet.heaps.blob(row[0]));
+
+        if reader.read_u8()? != SIGNATURE_LOCAL_VARIABLES {
+            return None;
+        }
+
+        return reader.read_type_list();
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self, dotnet: &DotNetData) -> Dump {
+        let mut dump = Dump::new_from_string(format!("{:#010x} {}", self.token, self.name));
+
+        dump.push_field("Signature", self.signature.clone(), None);
+        dump.push_field("RVA", format!("{:#x}", self.rva), None);
+
+        if let Some(ref error) = self.error {
+            dump.push_field("Body", error.clone(), None);
+        }
+
+        let Some(ref body) = self.body else {
+            return dump;
+        };
+
+        dump.push_field("Header", String::from(if body.fat { "Fat" } else { "Tiny" }), None);
+        dump.push_field("MaxStack", format!("{}", body.max_stack), None);
+        dump.push_field("CodeSize", format!("{:#x}", body.code.len()), None);
+
+        if body.local_var_sig_token != 0 {
+            let locals = self.locals(dotnet, body.local_var_sig_token).unwrap_or_default();
+            let locals: Vec<String> = locals.iter().enumerate().map(|(i, l)| format!("[{}] {}", i, l)).collect();
+
+            dump.push_field("Locals", format!("{}{}", if body.init_locals { "init " } else { "" }, locals.join(", ")), None);
+        }
+
+        let (instructions, error) = body.instructions();
+
+        let mut instructions_dump = Dump::new_from_string(format!("Instructions ({})", instructions.len()));
+
+        for instruction in instructions.iter() {
+            instructions_dump.push_field("", instruction.format(dotnet), None);
+        }
+
+        if let Some(error) = error {
+            instructions_dump.push_field("", error, None);
+        }
+
+        dump.push_child(instructions_dump);
+
+        if !body.exception_clauses.is_empty() {
+            let mut clauses_dump = Dump::new_from_string(format!("Exception Clauses ({})", body.exception_clauses.len()));
+
+            for clause in body.exception_clauses.iter() {
+                clauses_dump.push_field("", clause.format(dotnet), None);
+            }
+
+            dump.push_child(clauses_dump);
+        }
+
+        return dump;
+    }
+}
@@ -1,15 +1,22 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use cpp_demangle::{DemangleOptions, Symbol};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Compiler {
     MSVC,
     GCC,
     Clang,
+    Rust,
     Unknown,
 }
 
 pub fn detect_compiler(symbol: &str) -> Compiler {
     if symbol.starts_with('?') {
         Compiler::MSVC
-    } else if symbol.starts_with("_Z") {
+    } else if symbol.starts_with("_R") || is_rust_legacy_symbol(symbol) {
+        Compiler::Rust
+    } else if symbol.starts_with("_Z") || symbol.starts_with("__Z") {
         Compiler::GCC
     } else {
         Compiler::Unknown
@@ -17,13 +24,31 @@ pub fn detect_compiler(symbol: &str) -> Compiler {
 }
 
 pub fn is_mangled_symbol(symbol: &str) -> bool {
-    return symbol.starts_with('?') || symbol.starts_with("_Z");
+    return detect_compiler(symbol) != Compiler::Unknown;
+}
+
+/// Rust symbols mangled before v0 look like Itanium nested names ending with a 17h<hash> component
+fn is_rust_legacy_symbol(symbol: &str) -> bool {
+    /* LLVM may append suffixes such as .llvm.1234 */
+    let symbol = symbol.split('.').next().unwrap_or(symbol);
+
+    let Some(path) = symbol.strip_prefix("_ZN").and_then(|s| s.strip_suffix('E')) else {
+        return false;
+    };
+
+    return path.len() >= 19 && path[path.len() - 19..].strip_prefix("17h").is_some_and(|h| h.bytes().all(|b| b.is_ascii_hexdigit()));
 }
 
 pub fn demangle(symbol: &str) -> Result<String, String> {
+    /* Versioned ELF names (name@VERSION, name@@VERSION) keep their version */
+    if let (false, Some(at)) = (symbol.starts_with('?'), symbol.find('@')) {
+        return demangle(&symbol[..at]).map(|demangled| format!("{}{}", demangled, &symbol[at..]));
+    }
+
     match detect_compiler(symbol) {
         Compiler::MSVC => demangle_msvc(symbol),
         Compiler::GCC | Compiler::Clang => demangle_itanium(symbol),
+        Compiler::Rust => demangle_rust(symbol),
         Compiler::Unknown => Err("Unknown or unmangled symbol".to_string()),
     }
 }
@@ -39,13 +64,16 @@ pub fn demangle_msvc(symbol: &str) -> Result<String, String> {
 }
 
 pub fn demangle_itanium(symbol: &str) -> Result<String, String> {
+    /* 32-bit MinGW prefixes the C++ names with an extra underscore */
+    let symbol = symbol.strip_prefix('_').filter(|s| s.starts_with("_Z")).unwrap_or(symbol);
+
     if !symbol.starts_with("_Z") {
         return Err("Not an Itanium mangled symbol".to_string());
     }
 
-    let mut parser = ItaniumParser::new(&symbol[2..]);
+    let symbol = Symbol::new(symbol).map_err(|e| e.to_string())?;
 
-    return parser.parse();
+    return symbol.demangle(&DemangleOptions::default()).map_err(|e| e.to_string());
 }
 
 pub fn demangle_gcc(symbol: &str) -> Result<String, String> {
@@ -56,22 +84,88 @@ pub fn demangle_clang(symbol: &str) -> Result<String, String> {
     return demangle_itanium(symbol);
 }
 
-// Itanium C++ ABI Demangler
-struct ItaniumParser<'a> {
+/// Legacy and v0 Rust mangling, the hash of the legacy names is left out
+pub fn demangle_rust(symbol: &str) -> Result<String, String> {
+    match rustc_demangle::try_demangle(symbol) {
+        Ok(demangled) => return Ok(format!("{:#}", demangled)),
+        Err(_) => return Err("Not a Rust mangled symbol".to_string()),
+    }
+}
+
+/* Mangled names are dumped next to their demangled form, unless disabled with --no-demangle */
+static DEMANGLE_NAMES: AtomicBool = AtomicBool::new(true);
+
+pub fn set_demangle_names(enabled: bool) {
+    DEMANGLE_NAMES.store(enabled, Ordering::Relaxed);
+}
+
+/// Demangled form of a mangled name, None if demangling is disabled, the name is not mangled or cannot be demangled
+pub fn demangled_name(symbol: &str) -> Option<String> {
+    if !DEMANGLE_NAMES.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    return demangle(symbol).ok();
+}
+
+/// "mangled (demangled)" for mangled names, the name itself otherwise
+pub fn display_name(symbol: &str) -> String {
+    match demangled_name(symbol) {
+        Some(demangled) => return format!("{} ({})", symbol, demangled),
+        None => return symbol.to_string(),
+    }
+}
+
+/// Demangled form only, for the labels of the disassembly
+pub fn label_name(symbol: &str) -> String {
+    return demangled_name(symbol).unwrap_or_else(|| symbol.to_string());
+}
+
+// MSVC Demangler
+// https://en.wikiversity.org/wiki/Visual_C%2B%2B_name_mangling
+
+/* Names and parameter types are back referenced by a single digit */
+const MSVC_MAX_BACKREFS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MsvcSpecialName {
+    None,
+    Constructor,
+    Destructor,
+    Conversion,
+}
+
+struct MsvcParser<'a> {
     input: &'a str,
     pos: usize,
+    names: Vec<String>,
+    types: Vec<String>,
 }
 
-impl<'a> ItaniumParser<'a> {
-    fn new(input: &'a str) -> ItaniumParser<'a> {
-        return Self { input, pos: 0 };
+impl<'a> MsvcParser<'a> {
+    fn new(input: &'a str) -> MsvcParser<'a> {
+        return Self { input, pos: 0, names: Vec::new(), types: Vec::new() };
     }
 
     fn parse(&mut self) -> Result<String, String> {
-        let name = self.parse_name()?;
-        let params = self.parse_bare_function_type()?;
+        if self.input.starts_with("?@") {
+            return Err("MD5 hashed names cannot be demangled".to_string());
+        }
+
+        let (name, special) = self.parse_symbol_name()?;
 
-        return Ok(format!("{}{}", name, params));
+        let symbol = match self.consume() {
+            Some(class @ '0'..='4') => self.parse_variable(class, &name)?,
+            Some('6' | '7') => self.parse_vtable(&name)?,
+            Some(class) => self.parse_function(class, &name, special)?,
+            None => name,
+        };
+
+        if self.pos != self.input.len() {
+            return Err(format!("Unexpected characters at the end: {}", &self.input[self.pos..]));
+        }
+
+        return Ok(symbol);
     }
 
     fn peek(&self) -> Option<char> {
@@ -80,396 +174,623 @@ impl<'a> ItaniumParser<'a> {
 
     fn consume(&mut self) -> Option<char> {
         let c = self.peek()?;
+
         self.pos += c.len_utf8();
+
         return Some(c);
     }
 
-    fn parse_name(&mut self) -> Result<String, String> {
-        if self.peek() == Some('N') {
-            self.consume();
-            return self.parse_nested_name();
+    fn consume_prefix(&mut self, prefix: &str) -> bool {
+        if self.input[self.pos..].starts_with(prefix) {
+            self.pos += prefix.len();
+            return true;
         }
 
-        if self.peek() == Some('S') && self.input[self.pos..].starts_with("St") {
-            self.pos += 2;
-            let rest = self.parse_unqualified_name()?;
-            return Ok(format!("std::{}", rest));
-        }
+        return false;
+    }
 
-        return self.parse_unqualified_name();
+    fn at_end(&self) -> bool {
+        return self.pos >= self.input.len();
     }
 
-    fn parse_nested_name(&mut self) -> Result<String, String> {
-        let mut parts = Vec::new();
+    /// Qualified name of the symbol, its innermost part can be an operator or a constructor/destructor
+    fn parse_symbol_name(&mut self) -> Result<(String, MsvcSpecialName), String> {
+        let (name, special) = match !self.input[self.pos..].starts_with("?$") && self.consume_prefix("?") {
+            true => self.parse_operator_name()?,
+            false => (self.parse_name_fragment(false)?, MsvcSpecialName::None),
+        };
 
-        while matches!(self.peek(), Some('r') | Some('V') | Some('K')) {
-            self.consume();
-        }
+        let scope = self.parse_scope()?;
 
-        while self.peek() != Some('E') {
-            if self.peek().is_none() {
-                break;
-            }
+        let name = match special {
+            MsvcSpecialName::Constructor => scope.first().cloned().ok_or("Constructor outside of a class")?,
+            MsvcSpecialName::Destructor => format!("~{}", scope.first().ok_or("Destructor outside of a class")?),
+            _ => name,
+        };
+
+        return Ok((join_scope(scope, name), special));
+    }
+
+    /// Enclosing namespaces and classes, innermost first, up to the terminating '@'
+    fn parse_scope(&mut self) -> Result<Vec<String>, String> {
+        let mut scope = Vec::new();
 
-            if self.peek() == Some('S') && self.input[self.pos..].starts_with("St") {
-                self.pos += 2;
-                parts.push("std".to_string());
-            } else {
-                let part = self.parse_unqualified_name()?;
-                parts.push(part);
+        while !self.consume_prefix("@") {
+            if self.at_end() {
+                return Err("Unterminated qualified name".to_string());
             }
-        }
 
-        if self.peek() == Some('E') {
-            self.consume();
+            scope.push(self.parse_name_fragment(true)?);
         }
 
-        return Ok(parts.join("::"));
+        return Ok(scope);
     }
 
-    fn parse_unqualified_name(&mut self) -> Result<String, String> {
-        if let Some(c) = self.peek() {
-            if c.is_ascii_digit() {
-                return self.parse_source_name();
-            }
+    fn parse_type_name(&mut self) -> Result<String, String> {
+        let name = self.parse_name_fragment(true)?;
+        let scope = self.parse_scope()?;
 
-            match c {
-                'C' => {
-                    self.consume();
-                    self.consume();
-                    return Ok("constructor".into());
-                }
-                'D' => {
-                    self.consume();
-                    self.consume();
-                    return Ok("destructor".into());
-                }
-                _ => {}
-            }
+        return Ok(join_scope(scope, name));
+    }
+
+    /// The template instantiation naming the symbol itself is not memorized, unlike the ones of its scope and types
+    fn parse_name_fragment(&mut self, memorize_template: bool) -> Result<String, String> {
+        let c = self.peek().ok_or("Unexpected end of name")?;
+
+        if c.is_ascii_digit() {
+            self.consume();
+
+            let index = c as usize - '0' as usize;
+
+            return self.names.get(index).cloned().ok_or_else(|| format!("Invalid name back reference: {}", index));
         }
 
-        return Err("Failed to parse unqualified name".into());
-    }
+        let name = if self.consume_prefix("?$") {
+            let name = self.parse_template_name()?;
 
-    fn parse_source_name(&mut self) -> Result<String, String> {
-        let len = self.parse_number()?;
-        let name = self.input[self.pos..].chars().take(len).collect::<String>();
+            if !memorize_template {
+                return Ok(name);
+            }
 
-        self.pos += name.len();
+            name
+        } else if self.consume_prefix("?A") {
+            /* ?A0x<hash>@, the hash only makes the namespace unique */
+            self.parse_simple_name()?;
+            "`anonymous namespace'".to_string()
+        } else if c == '?' {
+            return Err("Nested symbols in names are not supported".to_string());
+        } else {
+            self.parse_simple_name()?
+        };
+
+        self.remember_name(&name);
 
         return Ok(name);
     }
 
-    fn parse_number(&mut self) -> Result<usize, String> {
-        let mut num_str = String::new();
+    fn parse_simple_name(&mut self) -> Result<String, String> {
+        let length = self.input[self.pos..].find('@').ok_or("Unterminated name")?;
+        let name = &self.input[self.pos..self.pos + length];
 
-        while let Some(c) = self.peek() {
-            if c.is_ascii_digit() {
-                num_str.push(c);
-                self.consume();
-            } else {
-                break;
-            }
+        if name.is_empty() {
+            return Err("Empty name".to_string());
         }
 
-        return num_str.parse().map_err(|_| "Invalid number".to_string());
+        self.pos += length + 1;
+
+        return Ok(name.to_string());
     }
 
-    fn parse_bare_function_type(&mut self) -> Result<String, String> {
-        let mut params = Vec::new();
-        let mut arg_count = 1;
+    fn remember_name(&mut self, name: &str) {
+        if self.names.len() < MSVC_MAX_BACKREFS && !self.names.iter().any(|n| n == name) {
+            self.names.push(name.to_string());
+        }
+    }
 
-        while self.pos < self.input.len() {
-            if self.peek() == Some('v') {
-                self.consume();
-                break;
-            }
+    /// name<arguments>, the arguments have their own back references
+    fn parse_template_name(&mut self) -> Result<String, String> {
+        let names = std::mem::take(&mut self.names);
+        let types = std::mem::take(&mut self.types);
 
-            match self.parse_type(&mut arg_count) {
-                Ok(t) => params.push(t),
-                Err(_) => break,
-            }
-        }
+        let name = self.parse_template_name_and_arguments();
 
-        if params.is_empty() {
-            return Ok("()".to_string());
-        } else {
-            return Ok(format!("({})", params.join(", ")));
-        }
+        self.names = names;
+        self.types = types;
+
+        return name;
     }
 
-    fn parse_type(&mut self, arg_count: &mut usize) -> Result<String, String> {
-        let c = self.peek().ok_or("Unexpected end")?;
-
-        let base_type = match c {
-            'v' => { self.consume(); "void".to_string() }
-            'b' => { self.consume(); "bool".to_string() }
-            'c' => { self.consume(); "char".to_string() }
-            'a' => { self.consume(); "signed char".to_string() }
-            'h' => { self.consume(); "unsigned char".to_string() }
-            's' => { self.consume(); "short".to_string() }
-            't' => { self.consume(); "unsigned short".to_string() }
-            'i' => { self.consume(); "int".to_string() }
-            'j' => { self.consume(); "unsigned int".to_string() }
-            'l' => { self.consume(); "long".to_string() }
-            'm' => { self.consume(); "unsigned long".to_string() }
-            'x' => { self.consume(); "long long".to_string() }
-            'y' => { self.consume(); "unsigned long long".to_string() }
-            'f' => { self.consume(); "float".to_string() }
-            'd' => { self.consume(); "double".to_string() }
-            'e' => { self.consume(); "long double".to_string() }
-            'w' => { self.consume(); "wchar_t".to_string() }
-
-            'P' => {
-                self.consume();
-                let inner = self.parse_type(arg_count)?;
-                format!("{}*", inner)
+    fn parse_template_name_and_arguments(&mut self) -> Result<String, String> {
+        let name = match self.consume_prefix("?") {
+            true => match self.parse_operator_name()? {
+                (name, MsvcSpecialName::None) => name,
+                _ => return Err("Unsupported special template name".to_string()),
+            },
+            false => {
+                let name = self.parse_simple_name()?;
+                self.remember_name(&name);
+                name
             }
-            'R' => {
-                self.consume();
-                let inner = self.parse_type(arg_count)?;
-                format!("{}&", inner)
-            }
-            'O' => {
-                self.consume();
-                let inner = self.parse_type(arg_count)?;
-                format!("{}&&", inner)
-            }
-            'K' => {
-                self.consume();
-                let inner = self.parse_type(arg_count)?;
-                format!("const {}", inner)
+        };
+
+        let mut arguments = Vec::new();
+
+        while !self.consume_prefix("@") {
+            if self.at_end() {
+                return Err("Unterminated template arguments".to_string());
             }
 
-            'N' | 'S' | _ if c.is_ascii_digit() => {
-                let saved_pos = self.pos;
-                match self.parse_name() {
-                    Ok(name) => name,
-                    Err(_) => {
-                        self.pos = saved_pos;
-                        return Err("Unknown type".to_string());
-                    }
-                }
+            if let Some(argument) = self.parse_template_argument()? {
+                arguments.push(argument);
             }
+        }
 
-            _ => return Err(format!("Unknown type encoding: {}", c)),
-        };
+        return Ok(format!("{}<{}>", name, arguments.join(", ")));
+    }
 
-        let param_name = format!("{}_arg{}", type_to_prefix(&base_type), arg_count);
-        *arg_count += 1;
+    fn parse_template_argument(&mut self) -> Result<Option<String>, String> {
+        /* Empty parameter packs */
+        if self.consume_prefix("$$V") || self.consume_prefix("$$Z") {
+            return Ok(None);
+        }
 
-        return Ok(format!("{} {}", base_type, param_name));
-    }
-}
+        if self.consume_prefix("$0") {
+            return Ok(Some(self.parse_number()?.to_string()));
+        }
 
-fn type_to_prefix(ty: &str) -> &str {
-    let base = ty.split_whitespace().last().unwrap_or(ty);
-    match base {
-        "void" => "void",
-        "bool" => "bool",
-        "char" => "char",
-        "int" => "int",
-        "short" => "short",
-        "long" => "long",
-        "float" => "float",
-        "double" => "double",
-        s if s.starts_with("unsigned") => "uint",
-        s if s.starts_with("signed") => "int",
-        s if s.ends_with('*') => "ptr",
-        s if s.ends_with('&') => "ref",
-        _ => "obj",
+        return Ok(Some(self.parse_argument_type()?));
     }
-}
 
-// MSVC Demangler
-struct MsvcParser<'a> {
-    input: &'a str,
-    pos: usize,
-}
+    fn parse_operator_name(&mut self) -> Result<(String, MsvcSpecialName), String> {
+        let name = match self.consume().ok_or("Unexpected end of operator name")? {
+            '0' => return Ok((String::new(), MsvcSpecialName::Constructor)),
+            '1' => return Ok((String::new(), MsvcSpecialName::Destructor)),
+            'B' => return Ok((String::from("operator"), MsvcSpecialName::Conversion)),
+            '2' => "operator new",
+            '3' => "operator delete",
+            '4' => "operator=",
+            '5' => "operator>>",
+            '6' => "operator<<",
+            '7' => "operator!",
+            '8' => "operator==",
+            '9' => "operator!=",
+            'A' => "operator[]",
+            'C' => "operator->",
+            'D' => "operator*",
+            'E' => "operator++",
+            'F' => "operator--",
+            'G' => "operator-",
+            'H' => "operator+",
+            'I' => "operator&",
+            'J' => "operator->*",
+            'K' => "operator/",
+            'L' => "operator%",
+            'M' => "operator<",
+            'N' => "operator<=",
+            'O' => "operator>",
+            'P' => "operator>=",
+            'Q' => "operator,",
+            'R' => "operator()",
+            'S' => "operator~",
+            'T' => "operator^",
+            'U' => "operator|",
+            'V' => "operator&&",
+            'W' => "operator||",
+            'X' => "operator*=",
+            'Y' => "operator+=",
+            'Z' => "operator-=",
+            '_' => match self.consume().ok_or("Unexpected end of operator name")? {
+                '0' => "operator/=",
+                '1' => "operator%=",
+                '2' => "operator>>=",
+                '3' => "operator<<=",
+                '4' => "operator&=",
+                '5' => "operator|=",
+                '6' => "operator^=",
+                '7' => "`vftable'",
+                '8' => "`vbtable'",
+                '9' => "`vcall'",
+                'A' => "`typeof'",
+                'B' => "`local static guard'",
+                'D' => "`vbase dtor'",
+                'E' => "`vector deleting dtor'",
+                'F' => "`default ctor closure'",
+                'G' => "`scalar deleting dtor'",
+                'H' => "`vector ctor iterator'",
+                'I' => "`vector dtor iterator'",
+                'J' => "`vector vbase ctor iterator'",
+                'K' => "`virtual displacement map'",
+                'L' => "`eh vector ctor iterator'",
+                'M' => "`eh vector dtor iterator'",
+                'N' => "`eh vector vbase ctor iterator'",
+                'O' => "`copy ctor closure'",
+                'S' => "`local vftable'",
+                'T' => "`local vftable ctor closure'",
+                'U' => "operator new[]",
+                'V' => "operator delete[]",
+                'X' => "`placement delete closure'",
+                'Y' => "`placement delete[] closure'",
+                c => return Err(format!("Unsupported special name: ?_{}", c)),
+            },
+            c => return Err(format!("Unsupported operator: ?{}", c)),
+        };
 
-impl<'a> MsvcParser<'a> {
-    fn new(input: &'a str) -> MsvcParser<'a> {
-        return Self { input, pos: 0 };
+        return Ok((name.to_string(), MsvcSpecialName::None));
     }
 
-    fn parse(&mut self) -> Result<String, String> {
-        let name = self.parse_name()?;
-        let signature = self.parse_signature()?;
+    fn parse_function(&mut self, class: char, name: &str, special: MsvcSpecialName) -> Result<String, String> {
+        let (access, member) = match class {
+            'A' | 'B' => ("private: ", true),
+            'C' | 'D' => ("private: static ", false),
+            'E' | 'F' => ("private: virtual ", true),
+            'I' | 'J' => ("protected: ", true),
+            'K' | 'L' => ("protected: static ", false),
+            'M' | 'N' => ("protected: virtual ", true),
+            'Q' | 'R' => ("public: ", true),
+            'S' | 'T' => ("public: static ", false),
+            'U' | 'V' => ("public: virtual ", true),
+            'Y' | 'Z' => ("", false),
+            _ => return Err(format!("Unsupported function class: {}", class)),
+        };
 
-        return Ok(format!("{}{}", name, signature));
-    }
+        let this_qualifiers = match member {
+            true => self.parse_this_qualifiers()?,
+            false => String::new(),
+        };
 
-    fn peek(&self) -> Option<char> {
-        return self.input[self.pos..].chars().next();
+        let calling_convention = self.parse_calling_convention()?;
+        let return_type = self.parse_return_type()?;
+        let parameters = self.parse_parameters()?;
+        let throw_specification = self.parse_throw_specification()?;
+
+        let suffix = format!("({}){}{}", parameters, this_qualifiers, throw_specification);
+
+        match (special, return_type) {
+            /* The target type is written both as the return type and in the name */
+            (MsvcSpecialName::Conversion, Some(return_type)) => {
+                return Ok(format!("{}{} {} {} {}{}", access, return_type, calling_convention, name, return_type, suffix));
+            }
+            (_, Some(return_type)) => {
+                return Ok(format!("{}{} {} {}{}", access, return_type, calling_convention, name, suffix));
+            }
+            (_, None) => return Ok(format!("{}{} {}{}", access, calling_convention, name, suffix)),
+        }
     }
 
-    fn consume(&mut self) -> Option<char> {
-        let c = self.peek()?;
+    fn parse_variable(&mut self, class: char, name: &str) -> Result<String, String> {
+        let access = match class {
+            '0' => "private: static ",
+            '1' => "protected: static ",
+            '2' => "public: static ",
+            _ => "",
+        };
 
-        self.pos += c.len_utf8();
+        let is_pointer = matches!(self.peek(), Some('P' | 'Q' | 'R' | 'S' | 'A' | 'B'));
+        let variable_type = self.parse_type()?;
 
-        return Some(c);
+        /* Storage class of the variable itself, after the pointer modifiers when it is a pointer, whose type already has it */
+        self.parse_pointer_modifiers();
+
+        let cv = match (self.parse_cv()?, is_pointer) {
+            (_, true) => "",
+            (cv, false) => cv,
+        };
+
+        return Ok(format!("{}{}", access, join_declarator(&format!("{}{}", variable_type, cv), name)));
     }
 
-    fn parse_name(&mut self) -> Result<String, String> {
-        let mut parts = Vec::new();
+    fn parse_vtable(&mut self, name: &str) -> Result<String, String> {
+        let cv = self.parse_cv()?;
 
-        loop {
-            if self.peek() == Some('@') {
-                self.consume();
-                if self.peek() == Some('@') {
-                    self.consume();
-                    break;
-                }
-            }
+        let mut vtable = format!("{} {}", cv.trim_start(), name).trim_start().to_string();
 
-            let part = self.parse_name_component()?;
-            if !part.is_empty() {
-                parts.push(part);
+        while !self.consume_prefix("@") {
+            if self.at_end() {
+                return Err("Unterminated virtual table".to_string());
             }
 
-            if self.peek() != Some('@') {
-                break;
-            }
+            vtable.push_str(&format!("{{for `{}'}}", self.parse_type_name()?));
         }
 
-        parts.reverse();
+        return Ok(vtable);
+    }
 
-        return Ok(parts.join("::"));
+    fn parse_cv(&mut self) -> Result<&'static str, String> {
+        match self.consume() {
+            Some('A') => return Ok(""),
+            Some('B') => return Ok(" const"),
+            Some('C') => return Ok(" volatile"),
+            Some('D') => return Ok(" const volatile"),
+            _ => return Err("Invalid cv qualifier".to_string()),
+        }
     }
 
-    fn parse_name_component(&mut self) -> Result<String, String> {
-        let mut name = String::new();
+    /// __ptr64 and __unaligned are not shown, __restrict is
+    fn parse_pointer_modifiers(&mut self) -> &'static str {
+        let mut modifiers = "";
 
-        while let Some(c) = self.peek() {
-            if c == '@' {
-                break;
+        while let Some(c @ ('E' | 'F' | 'I')) = self.peek() {
+            if c == 'I' {
+                modifiers = " __restrict";
             }
-            name.push(c);
+
             self.consume();
         }
 
-        return Ok(name);
+        return modifiers;
     }
 
-    fn parse_signature(&mut self) -> Result<String, String> {
-        while let Some(c) = self.peek() {
-            if matches!(c, 'Y' | 'A' | 'Q' | 'E' | 'I' | 'U' | 'S' | 'K' | 'M' | 'P' | 'R' | 'W') {
-                self.consume();
-            } else {
-                break;
+    fn parse_this_qualifiers(&mut self) -> Result<String, String> {
+        let mut reference = "";
+
+        let modifiers = loop {
+            match self.peek() {
+                Some('G') => reference = " &",
+                Some('H') => reference = " &&",
+                _ => break self.parse_pointer_modifiers(),
             }
+
+            self.consume();
+        };
+
+        let cv = self.parse_cv()?;
+
+        return Ok(format!("{}{}{}", cv, modifiers, reference));
+    }
+
+    fn parse_calling_convention(&mut self) -> Result<&'static str, String> {
+        match self.consume() {
+            Some('A' | 'B') => return Ok("__cdecl"),
+            Some('C' | 'D') => return Ok("__pascal"),
+            Some('E' | 'F') => return Ok("__thiscall"),
+            Some('G' | 'H') => return Ok("__stdcall"),
+            Some('I' | 'J') => return Ok("__fastcall"),
+            Some('M' | 'N') => return Ok("__clrcall"),
+            Some('Q') => return Ok("__vectorcall"),
+            _ => return Err("Invalid calling convention".to_string()),
+        }
+    }
+
+    /// None for constructors and destructors
+    fn parse_return_type(&mut self) -> Result<Option<String>, String> {
+        if self.consume_prefix("@") {
+            return Ok(None);
+        }
+
+        if self.consume_prefix("?") {
+            let cv = self.parse_cv()?;
+            return Ok(Some(format!("{}{}", self.parse_type()?, cv)));
         }
 
-        let mut params = Vec::new();
-        let mut arg_count = 1;
+        return Ok(Some(self.parse_type()?));
+    }
 
-        if self.pos < self.input.len() && self.peek() != Some('@') {
-            let _ = self.parse_msvc_type(&mut 0);
+    fn parse_parameters(&mut self) -> Result<String, String> {
+        if self.consume_prefix("X") {
+            return Ok("void".to_string());
         }
 
-        while self.pos < self.input.len() {
-            if self.peek() == Some('@') || self.peek() == Some('Z') {
+        let mut parameters = Vec::new();
+
+        loop {
+            if self.consume_prefix("@") {
                 break;
             }
 
-            if self.peek() == Some('X') {
-                self.consume();
+            /* Variadic functions end their parameters with Z instead of @ */
+            if self.consume_prefix("Z") {
+                parameters.push("...".to_string());
                 break;
             }
 
-            match self.parse_msvc_type(&mut arg_count) {
-                Ok(t) => params.push(t),
-                Err(_) => break,
+            if self.at_end() {
+                return Err("Unterminated parameters".to_string());
             }
+
+            parameters.push(self.parse_argument_type()?);
         }
 
-        if params.is_empty() {
-            return Ok("(void)".to_string());
-        } else {
-            return Ok(format!("({})", params.join(", ")));
+        return Ok(parameters.join(", "));
+    }
+
+    fn parse_throw_specification(&mut self) -> Result<&'static str, String> {
+        if self.consume_prefix("_E") {
+            return Ok(" noexcept");
+        }
+
+        if self.consume_prefix("Z") {
+            return Ok("");
+        }
+
+        return Err("Invalid throw specification".to_string());
+    }
+
+    /// Parameter or template argument type, the types longer than one character can be back referenced
+    fn parse_argument_type(&mut self) -> Result<String, String> {
+        if let Some(c) = self.peek().filter(|c| c.is_ascii_digit()) {
+            self.consume();
+
+            let index = c as usize - '0' as usize;
+
+            return self.types.get(index).cloned().ok_or_else(|| format!("Invalid type back reference: {}", index));
+        }
+
+        let start = self.pos;
+        let argument_type = self.parse_type()?;
+
+        if self.pos - start > 1 && self.types.len() < MSVC_MAX_BACKREFS {
+            self.types.push(argument_type.clone());
         }
+
+        return Ok(argument_type);
     }
 
-    fn parse_msvc_type(&mut self, arg_count: &mut usize) -> Result<String, String> {
-        let c = self.peek().ok_or("Unexpected end")?;
-
-        let base_type = match c {
-            'X' => { self.consume(); "void".to_string() }
-            'D' => { self.consume(); "char".to_string() }
-            'C' => { self.consume(); "signed char".to_string() }
-            'E' => { self.consume(); "unsigned char".to_string() }
-            'F' => { self.consume(); "short".to_string() }
-            'G' => { self.consume(); "unsigned short".to_string() }
-            'H' => { self.consume(); "int".to_string() }
-            'I' => { self.consume(); "unsigned int".to_string() }
-            'J' => { self.consume(); "long".to_string() }
-            'K' => { self.consume(); "unsigned long".to_string() }
+    fn parse_type(&mut self) -> Result<String, String> {
+        let c = self.consume().ok_or("Unexpected end of type")?;
+
+        if let Some(primitive) = msvc_primitive_type(c) {
+            return Ok(primitive.to_string());
+        }
+
+        match c {
             '_' => {
+                let c = self.consume().ok_or("Unexpected end of type")?;
+                return msvc_extended_type(c).map(|t| t.to_string()).ok_or_else(|| format!("Unknown MSVC type: _{}", c));
+            }
+            'T' => return Ok(format!("union {}", self.parse_type_name()?)),
+            'U' => return Ok(format!("struct {}", self.parse_type_name()?)),
+            'V' => return Ok(format!("class {}", self.parse_type_name()?)),
+            'W' => {
+                /* The underlying type of the enum, 4 for int */
                 self.consume();
-                match self.peek() {
-                    Some('J') => { self.consume(); "long long".to_string() }
-                    Some('K') => { self.consume(); "unsigned long long".to_string() }
-                    Some('N') => { self.consume(); "bool".to_string() }
-                    Some('W') => { self.consume(); "wchar_t".to_string() }
-                    _ => "unknown".to_string(),
-                }
+                return Ok(format!("enum {}", self.parse_type_name()?));
             }
-            'M' => { self.consume(); "float".to_string() }
-            'N' => { self.consume(); "double".to_string() }
-            'O' => { self.consume(); "long double".to_string() }
+            'P' => return self.parse_pointer("*", ""),
+            'Q' => return self.parse_pointer("*", "const"),
+            'R' => return self.parse_pointer("*", "volatile"),
+            'S' => return self.parse_pointer("*", "const volatile"),
+            'A' => return self.parse_pointer("&", ""),
+            'B' => return self.parse_pointer("&", "volatile"),
+            '?' => {
+                let cv = self.parse_cv()?;
+                return Ok(format!("{}{}", self.parse_type()?, cv));
+            }
+            '$' => {
+                if self.consume_prefix("$Q") {
+                    return self.parse_pointer("&&", "");
+                }
 
-            'P' => {
-                self.consume();
-                match self.peek() {
-                    Some('A') => {
-                        self.consume();
-                        let inner = self.parse_msvc_type(arg_count)?;
-                        format!("{}*", inner)
-                    }
-                    Some('B') => {
-                        self.consume();
-                        let inner = self.parse_msvc_type(arg_count)?;
-                        format!("const {}*", inner)
-                    }
-                    _ => {
-                        let inner = self.parse_msvc_type(arg_count)?;
-                        format!("{}*", inner)
-                    }
+                if self.consume_prefix("$R") {
+                    return self.parse_pointer("&&", "volatile");
+                }
+
+                if self.consume_prefix("$T") {
+                    return Ok("std::nullptr_t".to_string());
+                }
+
+                if self.consume_prefix("$C") {
+                    let cv = self.parse_cv()?;
+                    return Ok(format!("{}{}", self.parse_type()?, cv));
                 }
+
+                return Err("Unsupported MSVC type: $".to_string());
             }
+            _ => return Err(format!("Unknown MSVC type: {}", c)),
+        }
+    }
 
-            'A' => {
-                self.consume();
-                match self.peek() {
-                    Some('A') => {
-                        self.consume();
-                        let inner = self.parse_msvc_type(arg_count)?;
-                        format!("{}&", inner)
-                    }
-                    Some('B') => {
-                        self.consume();
-                        let inner = self.parse_msvc_type(arg_count)?;
-                        format!("const {}&", inner)
+    fn parse_pointer(&mut self, declarator: &str, pointer_cv: &str) -> Result<String, String> {
+        let modifiers = self.parse_pointer_modifiers();
+        let qualifiers = format!("{}{}", pointer_cv, modifiers).trim_start().to_string();
+
+        /* Pointer to function */
+        if self.consume_prefix("6") {
+            let calling_convention = self.parse_calling_convention()?;
+            let return_type = self.parse_return_type()?.unwrap_or_default();
+            let parameters = self.parse_parameters()?;
+            let throw_specification = self.parse_throw_specification()?;
+
+            return Ok(format!("{} ({} {}{})({}){}", return_type, calling_convention, declarator, qualifiers, parameters, throw_specification));
+        }
+
+        let cv = self.parse_cv()?;
+
+        /* The cv of a pointer to pointer repeats the one of the inner pointer type (PEBQEAH is int *const *) */
+        let cv = match self.peek() {
+            Some('P' | 'Q' | 'R' | 'S') => "",
+            _ => cv,
+        };
+
+        let pointee = format!("{}{}", self.parse_type()?, cv);
+
+        return Ok(format!("{}{}", join_declarator(&pointee, declarator), qualifiers));
+    }
+
+    /// 0-9 are 1-10, larger numbers are written in hexadecimal with the digits A-P and end with '@'
+    fn parse_number(&mut self) -> Result<i64, String> {
+        let negative = self.consume_prefix("?");
+
+        let value = match self.consume().ok_or("Unexpected end of number")? {
+            c @ '0'..='9' => c as i64 - '0' as i64 + 1,
+            c @ 'A'..='P' => {
+                let mut value = c as i64 - 'A' as i64;
+
+                loop {
+                    match self.consume() {
+                        Some('@') => break,
+                        Some(c @ 'A'..='P') => {
+                            value = value
+                                .checked_mul(16)
+                                .map(|v| v + (c as i64 - 'A' as i64))
+                                .ok_or("Number too large")?;
+                        }
+                        _ => return Err("Invalid number".to_string()),
                     }
-                    _ => "reference".to_string(),
                 }
-            }
 
-            'V' | 'U' => {
-                self.consume();
-                self.parse_name_component()?
+                value
             }
-
-            _ => return Err(format!("Unknown MSVC type: {}", c)),
+            _ => return Err("Invalid number".to_string()),
         };
 
-        if *arg_count > 0 {
-            let param_name = format!("{}_arg{}", type_to_prefix(&base_type), arg_count);
-            *arg_count += 1;
-            return Ok(format!("{} {}", base_type, param_name));
-        } else {
-            return Ok(base_type);
-        }
+        return Ok(if negative { -value } else { value });
+    }
+}
+
+fn msvc_primitive_type(c: char) -> Option<&'static str> {
+    match c {
+        'C' => return Some("signed char"),
+        'D' => return Some("char"),
+        'E' => return Some("unsigned char"),
+        'F' => return Some("short"),
+        'G' => return Some("unsigned short"),
+        'H' => return Some("int"),
+        'I' => return Some("unsigned int"),
+        'J' => return Some("long"),
+        'K' => return Some("unsigned long"),
+        'M' => return Some("float"),
+        'N' => return Some("double"),
+        'O' => return Some("long double"),
+        'X' => return Some("void"),
+        _ => return None,
+    }
+}
+
+fn msvc_extended_type(c: char) -> Option<&'static str> {
+    match c {
+        'D' => return Some("__int8"),
+        'E' => return Some("unsigned __int8"),
+        'F' => return Some("__int16"),
+        'G' => return Some("unsigned __int16"),
+        'H' => return Some("__int32"),
+        'I' => return Some("unsigned __int32"),
+        'J' => return Some("__int64"),
+        'K' => return Some("unsigned __int64"),
+        'L' => return Some("__int128"),
+        'M' => return Some("unsigned __int128"),
+        'N' => return Some("bool"),
+        'Q' => return Some("char8_t"),
+        'S' => return Some("char16_t"),
+        'U' => return Some("char32_t"),
+        'W' => return Some("wchar_t"),
+        _ => return None,
+    }
+}
+
+/// The scope is innermost first
+fn join_scope(scope: Vec<String>, name: String) -> String {
+    let mut parts = scope;
+
+    parts.reverse();
+    parts.push(name);
+
+    return parts.join("::");
+}
+
+/// "int *" and "p" give "int *p", "int" and "p" give "int p"
+fn join_declarator(left: &str, right: &str) -> String {
+    match left.ends_with('*') || left.ends_with('&') {
+        true => return format!("{}{}", left, right),
+        false => return format!("{} {}", left, right),
     }
 }
@@ -0,0 +1,556 @@
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use strum_macros::IntoStaticStr;
+
+use crate::apiset::{is_api_set, resolve_api_set};
+use crate::demangle::display_name;
+use crate::dump::Dump;
+use crate::format::format_timestamp;
+use crate::pe::{parse_pe_with, ExportData, HintNameTable, ImportLookupTable, PE};
+
+/*
+ * Dependency tree resolution, in the spirit of Dependency Walker
+ */
+
+/* Guards against pathological dependency chains */
+pub const MAX_DEPENDENCY_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportRef {
+    Name(String),
+    Ordinal(u16),
+}
+
+impl std::fmt::Display for ImportRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportRef::Name(name) => return write!(f, "{}", display_name(name)),
+            ImportRef::Ordinal(ordinal) => return write!(f, "#{}", ordinal),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportedDll {
+    pub name: String,
+    pub functions: Vec<ImportRef>,
+    /// Loaded on the first call to one of its functions instead of at startup
+    pub delay_load: bool,
+}
+
+/// Imported functions grouped by DLL, both by name and by ordinal
+pub fn pe_imported_dlls(pe: &PE) -> Vec<ImportedDll> {
+    match (&pe.hint_name_table, &pe.import_lookup_tables) {
+        (Some(hnt), Some(ilts)) => return imported_dlls(hnt, ilts, false),
+        _ => return Vec::new(),
+    }
+}
+
+/// Delay-loaded functions grouped by DLL
+pub fn pe_delay_imported_dlls(pe: &PE) -> Vec<ImportedDll> {
+    match pe.delay_import_table {
+        Some(ref table) => return imported_dlls(&table.hint_name_table, &table.lookup_tables, true),
+        None => return Vec::new(),
+    }
+}
+
+/// Timestamp the imports of a DLL were bound to, from the Bound Import Table (new-style binding) else from the descriptor (old-style)
+pub fn pe_bound_timestamp(pe: &PE, dll_name: &str, delay_load: bool) -> Option<u32> {
+    /* Bound delay-load descriptors carry the timestamp themselves */
+    if delay_load {
+        let table = pe.delay_import_table.as_ref()?;
+
+        return table
+            .descriptors
+            .iter()
+            .zip(table.hint_name_table.entries.iter())
+            .find(|(_, hnd)| hnd.dll_name.eq_ignore_ascii_case(dll_name))
+            .map(|(descriptor, _)| descriptor.time_date_stamp)
+            .filter(|time_date_stamp| *time_date_stamp != 0);
+    }
+
+    if let Some(time_date_stamp) = pe.bound_import_table.as_ref().and_then(|table| table.find(dll_name)) {
+        return Some(time_date_stamp);
+    }
+
+    let (idt, hnt) = (pe.import_directory_table.as_ref()?, pe.hint_name_table.as_ref()?);
+
+    return idt
+        .entries
+        .iter()
+        .zip(hnt.entries.iter())
+        .find(|(_, hnd)| hnd.dll_name.eq_ignore_ascii_case(dll_name))
+        .map(|(descriptor, _)| descriptor.time_date_stamp)
+        .filter(|time_date_stamp| *time_date_stamp != 0 && *time_date_stamp != u32::MAX);
+}
+
+fn imported_dlls(hnt: &HintNameTable, ilts: &[ImportLookupTable], delay_load: bool) -> Vec<ImportedDll> {
+    let mut dlls = Vec::new();
+
+    for (hnd, ilt) in hnt.entries.iter().zip(ilts.iter()) {
+        let mut dll = ImportedDll {
+            name: hnd.dll_name.clone(),
+            functions: Vec::new(),
+            delay_load,
+        };
+
+        /* The Hint/Name entries only exist for the functions imported by name, in lookup table order */
+        let mut names = hnd.entries.iter();
+
+        for entry in ilt.entries.iter() {
+            if entry.by_ordinal {
+                dll.functions.push(ImportRef::Ordinal(entry.ordinal_number));
+            } else if let Some(hne) = names.next() {
+                dll.functions.push(ImportRef::Name(hne.name.clone()));
+            }
+        }
+
+        dlls.push(dll);
+    }
+
+    return dlls;
+}
+
+pub fn export_resolves(export_data: &ExportData, import: &ImportRef) -> bool {
+    match import {
+        ImportRef::Name(name) => return export_data.entries.iter().any(|e| e.name.as_deref() == Some(name.as_str())),
+        ImportRef::Ordinal(ordinal) => return export_data.entries.iter().any(|e| e.ordinal == *ordinal as u32),
+    }
+}
+
+/* DLLs of every Windows installation (KnownDLLs, the core subsystems and the runtimes shipped with the OS), assumed present when the search paths do not hold them */
+pub const SYSTEM_DLLS: &[&str] = &[
+    "advapi32.dll", "bcrypt.dll", "bcryptprimitives.dll", "cabinet.dll", "cfgmgr32.dll", "clbcatq.dll", "combase.dll",
+    "comctl32.dll", "comdlg32.dll", "crypt32.dll", "cryptbase.dll", "cryptsp.dll", "d3d11.dll", "d3d9.dll", "dbghelp.dll",
+    "dhcpcsvc.dll", "difxapi.dll", "dnsapi.dll", "dwmapi.dll", "dxgi.dll", "gdi32.dll", "gdi32full.dll", "gdiplus.dll",
+    "hal.dll", "imagehlp.dll", "imm32.dll", "iphlpapi.dll", "kernel32.dll", "kernelbase.dll", "mpr.dll", "msasn1.dll",
+    "mscoree.dll", "msctf.dll", "msi.dll", "msimg32.dll", "msvcp_win.dll", "msvcrt.dll", "mswsock.dll", "ncrypt.dll",
+    "netapi32.dll", "normaliz.dll", "ntdll.dll", "ntoskrnl.exe", "ole32.dll", "oleacc.dll", "oleaut32.dll", "opengl32.dll",
+    "powrprof.dll", "profapi.dll", "psapi.dll", "rpcrt4.dll", "sechost.dll", "secur32.dll", "setupapi.dll", "shcore.dll",
+    "shell32.dll", "shlwapi.dll", "sspicli.dll", "ucrtbase.dll", "urlmon.dll", "user32.dll", "userenv.dll", "uxtheme.dll",
+    "version.dll", "wevtapi.dll", "win32u.dll", "wininet.dll", "winhttp.dll", "winmm.dll", "winspool.drv", "wintrust.dll",
+    "wldap32.dll", "ws2_32.dll", "wsock32.dll", "wtsapi32.dll",
+];
+
+pub fn is_system_dll(dll_name: &str) -> bool {
+    let lower = dll_name.to_ascii_lowercase();
+
+    return SYSTEM_DLLS.contains(&lower.as_str());
+}
+
+/// Expands a search path, a Windows filesystem root is searched in its system folders
+pub fn expand_search_path(path: &Path, is_32_bits: bool) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    if let Some(windows) = find_case_insensitive(path, "Windows") {
+        if is_32_bits && let Some(syswow64) = find_case_insensitive(&windows, "SysWOW64") {
+            dirs.push(syswow64);
+        }
+
+        if let Some(system32) = find_case_insensitive(&windows, "System32") {
+            dirs.push(system32);
+        }
+
+        dirs.push(windows);
+    }
+
+    dirs.push(path.to_path_buf());
+
+    return dirs;
+}
+
+/// Windows file names are case-insensitive, the extracted files may not share the case of the import
+fn find_case_insensitive(dir: &Path, name: &str) -> Option<PathBuf> {
+    let candidate = dir.join(name);
+
+    if candidate.exists() {
+        return Some(candidate);
+    }
+
+    let entries = std::fs::read_dir(dir).ok()?;
+
+    for entry in entries.flatten() {
+        if entry.file_name().to_string_lossy().eq_ignore_ascii_case(name) {
+            return Some(entry.path());
+        }
+    }
+
+    return None;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DependencyNode {
+    pub name: String,
+    pub path: Option<PathBuf>,
+    pub api_set: bool,
+    /// DLL the API set contract is redirected to, from the bundled schema
+    pub api_set_host: Option<&'static str>,
+    pub delay_load: bool,
+    /// Already expanded elsewhere in the tree
+    pub duplicate: bool,
+    /// Timestamp the imports were bound to and timestamp of the resolved DLL, they differ for a stale binding
+    pub bound_timestamp: Option<u32>,
+    pub dll_timestamp: Option<u32>,
+    pub error: Option<String>,
+    pub unresolved: Vec<ImportRef>,
+    pub children: Vec<DependencyNode>,
+}
+
+impl DependencyNode {
+    pub fn is_missing(&self) -> bool {
+        return self.path.is_none() && !self.api_set;
+    }
+
+    /// The loader ignores the bound addresses and patches the imports again when the DLL changed since the binding
+    pub fn is_stale_binding(&self) -> bool {
+        match (self.bound_timestamp, self.dll_timestamp) {
+            (Some(bound), Some(dll)) => return bound != dll,
+            _ => return false,
+        }
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let status = if self.api_set {
+            format!("API set -> {}", self.api_set_host.unwrap_or("unknown host"))
+        } else if self.duplicate {
+            String::from("see above")
+        } else {
+            match (&self.path, &self.error) {
+                (_, Some(error)) => format!("ERROR: {}", error),
+                (Some(path), None) => path.display().to_string(),
+                (None, None) => String::from("MISSING"),
+            }
+        };
+
+        let mut dump = match self.delay_load {
+            true => Dump::new_from_string(format!("{} ({}, delay-load)", self.name, status)),
+            false => Dump::new_from_string(format!("{} ({})", self.name, status)),
+        };
+
+        if let (true, Some(bound), Some(dll)) = (self.is_stale_binding(), self.bound_timestamp, self.dll_timestamp) {
+            dump.push_field("StaleBinding", format!("bound to {}, the DLL is {}", format_timestamp(bound), format_timestamp(dll)), None);
+        }
+
+        for import in self.unresolved.iter() {
+            dump.push_field("Unresolved", import.to_string(), None);
+        }
+
+        for child in self.children.iter() {
+            dump.push_child(child.dump());
+        }
+
+        return dump;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DependencyTree {
+    pub dependencies: Vec<DependencyNode>,
+    pub missing_dlls: Vec<String>,
+    pub unresolved_imports: Vec<String>,
+    pub stale_bindings: Vec<String>,
+}
+
+struct Resolver {
+    search_dirs: Vec<PathBuf>,
+    visited: HashSet<String>,
+}
+
+impl Resolver {
+    fn find_dll(&self, name: &str) -> Option<PathBuf> {
+        return self
+            .search_dirs
+            .iter()
+            .find_map(|dir| find_case_insensitive(dir, name).filter(|p| p.is_file()));
+    }
+
+    fn resolve_imports(&mut self, pe: &PE, depth: usize) -> Vec<DependencyNode> {
+        let mut nodes = Vec::new();
+
+        /* Delay-loaded DLLs are only loaded on the first call, a missing one fails at runtime instead of at startup */
+        for dll in pe_imported_dlls(pe).into_iter().chain(pe_delay_imported_dlls(pe)) {
+            let mut node = DependencyNode {
+                name: dll.name.clone(),
+                delay_load: dll.delay_load,
+                ..Default::default()
+            };
+
+            if is_api_set(&dll.name) {
+                node.api_set = true;
+                node.api_set_host = resolve_api_set(&dll.name);
+                nodes.push(node);
+                continue;
+            }
+
+            node.path = self.find_dll(&dll.name);
+
+            let Some(ref path) = node.path else {
+                node.unresolved = dll.functions.clone();
+                nodes.push(node);
+                continue;
+            };
+
+            /* Only the exports and imports of the dependencies are needed */
+            match parse_pe_with(path, false) {
+                Ok(dependency) => {
+                    node.unresolved = match dependency.export_data {
+                        Some(ref export_data) => dll.functions.iter().filter(|f| !export_resolves(export_data, f)).cloned().collect(),
+                        None => dll.functions.clone(),
+                    };
+
+                    node.duplicate = !self.visited.insert(dll.name.to_ascii_lowercase());
+
+                    node.bound_timestamp = pe_bound_timestamp(pe, &dll.name, dll.delay_load);
+                    node.dll_timestamp = Some(dependency.get_nt_header().coff_header.time_date_stamp);
+
+                    if !node.duplicate && depth < MAX_DEPENDENCY_DEPTH {
+                        node.children = self.resolve_imports(&dependency, depth + 1);
+                    }
+                }
+                Err(e) => node.error = Some(e.to_string()),
+            }
+
+            nodes.push(node);
+        }
+
+        return nodes;
+    }
+}
+
+impl DependencyTree {
+    /// Resolves the imports recursively, the directory of the executable is searched first like the Windows loader does
+    pub fn resolve(pe: &PE, exec_path: &Path, search_paths: &[PathBuf]) -> DependencyTree {
+        let mut search_dirs = Vec::new();
+
+        if let Some(exec_dir) = exec_path.parent() {
+            search_dirs.push(if exec_dir.as_os_str().is_empty() { PathBuf::from(".") } else { exec_dir.to_path_buf() });
+        }
+
+        for path in search_paths.iter() {
+            search_dirs.extend(expand_search_path(path, pe.is_32_bits()));
+        }
+
+        let mut resolver = Resolver {
+            search_dirs,
+            visited: HashSet::new(),
+        };
+
+        let mut tree = DependencyTree::default();
+        tree.dependencies = resolver.resolve_imports(pe, 0);
+
+        let mut stack: Vec<&DependencyNode> = tree.dependencies.iter().collect();
+        let mut missing = Vec::new();
+        let mut unresolved = Vec::new();
+        let mut stale = Vec::new();
+
+        while let Some(node) = stack.pop() {
+            let name = match node.delay_load {
+                true => format!("{} (delay-load)", node.name),
+                false => node.name.clone(),
+            };
+
+            if node.is_missing() && !missing.contains(&name) {
+                missing.push(name.clone());
+            }
+
+            if node.is_stale_binding() && !stale.contains(&node.name) {
+                stale.push(node.name.clone());
+            }
+
+            for import in node.unresolved.iter().filter(|_| node.path.is_some()) {
+                let entry = format!("{}!{}", node.name, import);
+
+                if !unresolved.contains(&entry) {
+                    unresolved.push(entry);
+                }
+            }
+
+            stack.extend(node.children.iter());
+        }
+
+        missing.sort();
+        unresolved.sort();
+
+        stale.sort();
+
+        tree.missing_dlls = missing;
+        tree.unresolved_imports = unresolved;
+        tree.stale_bindings = stale;
+
+        return tree;
+    }
+
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Dependencies");
+
+        dump.push_field("MissingDlls", format!("{}", self.missing_dlls.len()), None);
+        dump.push_field("UnresolvedImports", format!("{}", self.unresolved_imports.len()), None);
+        dump.push_field("StaleBindings", format!("{}", self.stale_bindings.len()), None);
+
+        let mut missing_dump = Dump::new_from_string(format!("Missing DLLs ({})", self.missing_dlls.len()));
+
+        for name in self.missing_dlls.iter() {
+            missing_dump.push_field("", name.clone(), None);
+        }
+
+        let mut unresolved_dump = Dump::new_from_string(format!("Unresolved Imports ({})", self.unresolved_imports.len()));
+
+        for name in self.unresolved_imports.iter() {
+            unresolved_dump.push_field("", name.clone(), None);
+        }
+
+        let mut stale_dump = Dump::new_from_string(format!("Stale Bindings ({})", self.stale_bindings.len()));
+
+        for name in self.stale_bindings.iter() {
+            stale_dump.push_field("", name.clone(), None);
+        }
+
+        let mut tree_dump = Dump::new("Tree");
+
+        for node in self.dependencies.iter() {
+            tree_dump.push_child(node.dump());
+        }
+
+        dump.push_child(missing_dump);
+        dump.push_child(unresolved_dump);
+        dump.push_child(stale_dump);
+        dump.push_child(tree_dump);
+
+        return dump;
+    }
+}
+
+/*
+ * Missing dependency report, each DLL of the tree is checked once
+ */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum DependencyStatus {
+    /// Found in the search paths, its exports are checked
+    Found,
+    /// Not in the search paths but part of Windows (or an API set), assumed present
+    System,
+    Missing,
+    /// Found but cannot be parsed
+    Invalid,
+}
+
+#[derive(Debug, Clone)]
+pub struct DependencyCheck {
+    pub name: String,
+    pub status: DependencyStatus,
+    pub required_by: String,
+    pub delay_load: bool,
+    pub path: Option<PathBuf>,
+    pub error: Option<String>,
+    /// Functions the DLL does not export, every imported function for a missing DLL
+    pub unresolved: Vec<ImportRef>,
+}
+
+impl DependencyCheck {
+    pub fn is_failure(&self) -> bool {
+        match self.status {
+            DependencyStatus::Found => return !self.unresolved.is_empty(),
+            DependencyStatus::System => return false,
+            DependencyStatus::Missing | DependencyStatus::Invalid => return true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MissingDependencies {
+    pub checks: Vec<DependencyCheck>,
+}
+
+impl MissingDependencies {
+    /// Walks the tree breadth first, a DLL is reported with the first module requiring it
+    pub fn from_tree(tree: &DependencyTree, exec_name: &str) -> MissingDependencies {
+        let mut report = MissingDependencies::default();
+        let mut seen = HashSet::new();
+        let mut queue: VecDeque<(&DependencyNode, &str)> = tree.dependencies.iter().map(|node| (node, exec_name)).collect();
+
+        while let Some((node, required_by)) = queue.pop_front() {
+            queue.extend(node.children.iter().map(|child| (child, node.name.as_str())));
+
+            if node.duplicate || !seen.insert(node.name.to_ascii_lowercase()) {
+                continue;
+            }
+
+            let status = if node.error.is_some() {
+                DependencyStatus::Invalid
+            } else if node.path.is_some() {
+                DependencyStatus::Found
+            } else if node.api_set || is_system_dll(&node.name) {
+                DependencyStatus::System
+            } else {
+                DependencyStatus::Missing
+            };
+
+            report.checks.push(DependencyCheck {
+                name: node.name.clone(),
+                status,
+                required_by: required_by.to_string(),
+                delay_load: node.delay_load,
+                path: node.path.clone(),
+                error: node.error.clone(),
+                unresolved: if status == DependencyStatus::System { Vec::new() } else { node.unresolved.clone() },
+            });
+        }
+
+        return report;
+    }
+
+    pub fn failures(&self) -> usize {
+        return self.checks.iter().filter(|c| c.is_failure()).count();
+    }
+
+    fn count(&self, status: DependencyStatus) -> usize {
+        return self.checks.iter().filter(|c| c.status == status).count();
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Missing Dependencies");
+
+        dump.push_field("Dlls", format!("{}", self.checks.len()), None);
+        dump.push_field("Found", format!("{}", self.count(DependencyStatus::Found)), Some("in the search paths"));
+        dump.push_field("System", format!("{}", self.count(DependencyStatus::System)), Some("assumed present on Windows"));
+        dump.push_field("Missing", format!("{}", self.count(DependencyStatus::Missing)), None);
+        dump.push_field("Failures", format!("{}", self.failures()), None);
+
+        /* Only the failures are detailed, the other DLLs take one line each */
+        for check in self.checks.iter().filter(|c| c.is_failure()) {
+            let status: &'static str = check.status.into();
+            let delay_load = if check.delay_load { ", delay-load" } else { "" };
+            let mut check_dump = Dump::new_from_string(format!("{} ({}{}, required by {})", check.name, status, delay_load, check.required_by));
+
+            if let Some(ref error) = check.error {
+                check_dump.push_field("Error", error.clone(), None);
+            }
+
+            for import in check.unresolved.iter() {
+                check_dump.push_field("Unresolved", import.to_string(), None);
+            }
+
+            dump.push_child(check_dump);
+        }
+
+        let mut resolved_dump = Dump::new_from_string(format!("Resolved ({})", self.checks.len() - self.failures()));
+
+        for check in self.checks.iter().filter(|c| !c.is_failure()) {
+            let status: &'static str = check.status.into();
+
+            match check.path {
+                Some(ref path) => resolved_dump.push_field("", format!("{} ({}, {})", check.name, status, path.display()), None),
+                None => resolved_dump.push_field("", format!("{} ({})", check.name, status), None),
+            }
+        }
+
+        dump.push_child(resolved_dump);
+
+        return dump;
+    }
+}
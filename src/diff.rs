@@ -0,0 +1,612 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+
+use crate::demangle::display_name;
+use crate::dump::Dump;
+use crate::elf::{ELFSection, ELFSectionHeader, ELF};
+use crate::exec::Exec;
+use crate::pe::PE;
+
+/*
+ * Structural diffing of two executables of the same format
+ */
+
+/* Changed byte runs closer than this are reported as a single range */
+pub const RANGE_MERGE_GAP: usize = 16;
+
+/* Maximum number of changed ranges shown per section in the dumps */
+pub const MAX_DUMPED_RANGES: usize = 16;
+
+/// A field of a header that differs, the value is None when the field only exists on one side
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub path: String,
+    pub key: &'static str,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SectionChange {
+    Added {
+        name: String,
+        size: usize,
+    },
+    Removed {
+        name: String,
+        size: usize,
+    },
+    Modified {
+        name: String,
+        old_size: usize,
+        new_size: usize,
+        changed_bytes: usize,
+        ranges: Vec<Range<usize>>,
+        header_changes: Vec<FieldChange>,
+    },
+}
+
+/// Imports and exports only change by being added or removed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SymbolChange {
+    Added(String),
+    Removed(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResourceChange {
+    Added {
+        path: String,
+        size: usize,
+    },
+    Removed {
+        path: String,
+        size: usize,
+    },
+    Modified {
+        path: String,
+        old_size: usize,
+        new_size: usize,
+    },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExecDiff {
+    pub header_changes: Vec<FieldChange>,
+    pub section_changes: Vec<SectionChange>,
+    pub import_changes: Vec<SymbolChange>,
+    pub export_changes: Vec<SymbolChange>,
+    pub resource_changes: Vec<ResourceChange>,
+}
+
+/*
+ * Generic helpers
+ */
+
+fn format_field_value(value: &Option<String>) -> String {
+    match value {
+        Some(v) => return v.clone(),
+        None => return String::from("<none>"),
+    }
+}
+
+/// Compares two dumps field by field, matching the fields by key and the children by label
+pub fn diff_dumps(old: &Dump, new: &Dump, path: &str, changes: &mut Vec<FieldChange>) {
+    let path = if path.is_empty() {
+        old.label().to_string()
+    } else {
+        format!("{}/{}", path, old.label())
+    };
+
+    let mut new_fields: Vec<Option<&crate::dump::DumpField>> = new.iter_fields().map(Some).collect();
+
+    for old_field in old.iter_fields() {
+        let matching = new_fields
+            .iter_mut()
+            .find(|f| f.is_some_and(|f| f.key == old_field.key))
+            .and_then(|f| f.take());
+
+        match matching {
+            Some(new_field) => {
+                if new_field.value != old_field.value {
+                    changes.push(FieldChange {
+                        path: path.clone(),
+                        key: old_field.key,
+                        old: Some(old_field.value.clone()),
+                        new: Some(new_field.value.clone()),
+                    });
+                }
+            }
+            None => changes.push(FieldChange {
+                path: path.clone(),
+                key: old_field.key,
+                old: Some(old_field.value.clone()),
+                new: None,
+            }),
+        }
+    }
+
+    for new_field in new_fields.into_iter().flatten() {
+        changes.push(FieldChange {
+            path: path.clone(),
+            key: new_field.key,
+            old: None,
+            new: Some(new_field.value.clone()),
+        });
+    }
+
+    let mut new_children: Vec<Option<&Dump>> = new.iter_children().map(Some).collect();
+
+    for old_child in old.iter_children() {
+        let matching = new_children
+            .iter_mut()
+            .find(|c| c.is_some_and(|c| c.label() == old_child.label()))
+            .and_then(|c| c.take());
+
+        match matching {
+            Some(new_child) => diff_dumps(old_child, new_child, &path, changes),
+            None => diff_dumps(old_child, &Dump::new(old_child.label()), &path, changes),
+        }
+    }
+
+    for new_child in new_children.into_iter().flatten() {
+        diff_dumps(&Dump::new(new_child.label()), new_child, &path, changes);
+    }
+}
+
+/// Returns the ranges of differing bytes, the tail of the longest buffer counts as changed
+pub fn diff_bytes(old: &[u8], new: &[u8]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let common = old.len().min(new.len());
+
+    let mut push = |start: usize, end: usize| {
+        if let Some(last) = ranges.last_mut() && start - last.end <= RANGE_MERGE_GAP {
+            last.end = end;
+            return;
+        }
+
+        ranges.push(start..end);
+    };
+
+    let mut i = 0;
+
+    while i < common {
+        if old[i] != new[i] {
+            let start = i;
+
+            while i < common && old[i] != new[i] {
+                i += 1;
+            }
+
+            push(start, i);
+        } else {
+            i += 1;
+        }
+    }
+
+    let longest = old.len().max(new.len());
+
+    if common < longest {
+        push(common, longest);
+    }
+
+    return ranges;
+}
+
+fn count_changed_bytes(old: &[u8], new: &[u8]) -> usize {
+    let differing = old.iter().zip(new.iter()).filter(|(a, b)| a != b).count();
+
+    return differing + old.len().abs_diff(new.len());
+}
+
+fn diff_symbols(old: &BTreeSet<String>, new: &BTreeSet<String>) -> Vec<SymbolChange> {
+    let mut changes: Vec<SymbolChange> = old
+        .difference(new)
+        .map(|s| SymbolChange::Removed(s.clone()))
+        .collect();
+
+    changes.extend(new.difference(old).map(|s| SymbolChange::Added(s.clone())));
+
+    return changes;
+}
+
+/// Sections are matched by name, the header of a section is compared through its dump
+struct SectionView<'a> {
+    header: Dump,
+    data: &'a [u8],
+}
+
+fn diff_sections(old: &BTreeMap<String, SectionView>, new: &BTreeMap<String, SectionView>) -> Vec<SectionChange> {
+    let mut changes = Vec::new();
+
+    for (name, old_section) in old.iter() {
+        match new.get(name) {
+            Some(new_section) => {
+                let mut header_changes = Vec::new();
+                diff_dumps(&old_section.header, &new_section.header, "", &mut header_changes);
+
+                let ranges = diff_bytes(old_section.data, new_section.data);
+
+                if header_changes.is_empty() && ranges.is_empty() {
+                    continue;
+                }
+
+                changes.push(SectionChange::Modified {
+                    name: name.clone(),
+                    old_size: old_section.data.len(),
+                    new_size: new_section.data.len(),
+                    changed_bytes: count_changed_bytes(old_section.data, new_section.data),
+                    ranges,
+                    header_changes,
+                });
+            }
+            None => changes.push(SectionChange::Removed {
+                name: name.clone(),
+                size: old_section.data.len(),
+            }),
+        }
+    }
+
+    for (name, new_section) in new.iter() {
+        if !old.contains_key(name) {
+            changes.push(SectionChange::Added {
+                name: name.clone(),
+                size: new_section.data.len(),
+            });
+        }
+    }
+
+    return changes;
+}
+
+/*
+ * PE
+ */
+
+/// The repeated names are told apart by their index in the section table
+fn pe_sections(pe: &PE) -> BTreeMap<String, SectionView<'_>> {
+    let mut sections = BTreeMap::new();
+
+    for (index, section) in pe.sections.iter().enumerate() {
+        let name = match sections.contains_key(&section.header.name) {
+            true => format!("{} #{}", section.header.name, index),
+            false => section.header.name.clone(),
+        };
+
+        sections.insert(name, SectionView { header: section.header.dump(), data: section.raw_data() });
+    }
+
+    return sections;
+}
+
+fn pe_imports(pe: &PE) -> BTreeSet<String> {
+    match pe.hint_name_table {
+        Some(ref hnt) => {
+            return hnt
+                .entries
+                .iter()
+                .flat_map(|dll| dll.entries.iter().map(move |entry| format!("{}!{}", dll.dll_name, entry.name)))
+                .collect();
+        }
+        None => return BTreeSet::new(),
+    }
+}
+
+fn pe_exports(pe: &PE) -> BTreeSet<String> {
+    match pe.export_data {
+        Some(ref export_data) => return export_data.entries.iter().map(|e| e.display_name()).collect(),
+        None => return BTreeSet::new(),
+    }
+}
+
+fn pe_resources(pe: &PE) -> BTreeMap<String, &[u8]> {
+    match pe.resource_table {
+        Some(ref resource_table) => {
+            return resource_table
+                .entries
+                .iter()
+                .map(|entry| (entry.path(), entry.data.as_slice()))
+                .collect();
+        }
+        None => return BTreeMap::new(),
+    }
+}
+
+fn diff_resources(old: &BTreeMap<String, &[u8]>, new: &BTreeMap<String, &[u8]>) -> Vec<ResourceChange> {
+    let mut changes = Vec::new();
+
+    for (path, old_data) in old.iter() {
+        match new.get(path) {
+            Some(new_data) => {
+                if old_data != new_data {
+                    changes.push(ResourceChange::Modified {
+                        path: path.clone(),
+                        old_size: old_data.len(),
+                        new_size: new_data.len(),
+                    });
+                }
+            }
+            None => changes.push(ResourceChange::Removed { path: path.clone(), size: old_data.len() }),
+        }
+    }
+
+    for (path, new_data) in new.iter() {
+        if !old.contains_key(path) {
+            changes.push(ResourceChange::Added { path: path.clone(), size: new_data.len() });
+        }
+    }
+
+    return changes;
+}
+
+pub fn diff_pe(old: &PE, new: &PE) -> ExecDiff {
+    let mut diff = ExecDiff::default();
+
+    diff_dumps(&old.get_dos_header().dump(), &new.get_dos_header().dump(), "", &mut diff.header_changes);
+    diff_dumps(&old.get_nt_header().dump(), &new.get_nt_header().dump(), "", &mut diff.header_changes);
+    diff_dumps(&old.get_optional_header().dump(), &new.get_optional_header().dump(), "", &mut diff.header_changes);
+
+    diff.section_changes = diff_sections(&pe_sections(old), &pe_sections(new));
+    diff.import_changes = diff_symbols(&pe_imports(old), &pe_imports(new));
+    diff.export_changes = diff_symbols(&pe_exports(old), &pe_exports(new));
+    diff.resource_changes = diff_resources(&pe_resources(old), &pe_resources(new));
+
+    return diff;
+}
+
+/*
+ * ELF
+ */
+
+fn elf_section_header_dump(section: &ELFSection) -> Dump {
+    match &section.header {
+        ELFSectionHeader::ELFSectionHeader32(h) => return h.dump(),
+        ELFSectionHeader::ELFSectionHeader64(h) => return h.dump(),
+    }
+}
+
+fn elf_sections(elf: &ELF) -> BTreeMap<String, SectionView<'_>> {
+    return elf
+        .sections
+        .iter()
+        .map(|(name, section)| {
+            (name.clone(), SectionView { header: elf_section_header_dump(section), data: section.data.as_slice() })
+        })
+        .collect();
+}
+
+pub fn diff_elf(old: &ELF, new: &ELF) -> ExecDiff {
+    let mut diff = ExecDiff::default();
+
+    diff_dumps(&old.get_elf_header().dump(), &new.get_elf_header().dump(), "", &mut diff.header_changes);
+
+    diff.section_changes = diff_sections(&elf_sections(old), &elf_sections(new));
+
+    return diff;
+}
+
+/// Computes the structural changes going from `a` to `b`, both executables must share the same format
+pub fn diff(a: &Exec, b: &Exec) -> Result<ExecDiff, Box<dyn std::error::Error>> {
+    match (a, b) {
+        (Exec::PE(old), Exec::PE(new)) => return Ok(diff_pe(old, new)),
+        (Exec::ELF(old), Exec::ELF(new)) => return Ok(diff_elf(old, new)),
+        _ => return Err("Cannot diff executables of different formats".into()),
+    }
+}
+
+impl ExecDiff {
+    pub fn is_empty(&self) -> bool {
+        return self.header_changes.is_empty()
+            && self.section_changes.is_empty()
+            && self.import_changes.is_empty()
+            && self.export_changes.is_empty()
+            && self.resource_changes.is_empty();
+    }
+
+    fn dump_field_changes(dump: &mut Dump, changes: &[FieldChange]) {
+        for change in changes.iter() {
+            dump.push_field(
+                change.key,
+                format!("{} -> {} ({})", format_field_value(&change.old), format_field_value(&change.new), change.path),
+                None,
+            );
+        }
+    }
+
+    fn dump_symbol_changes(label: &str, changes: &[SymbolChange]) -> Dump {
+        let mut dump = Dump::new_from_string(format!("{} ({})", label, changes.len()));
+
+        for change in changes.iter() {
+            match change {
+                SymbolChange::Added(name) => dump.push_field("+", name.clone(), None),
+                SymbolChange::Removed(name) => dump.push_field("-", name.clone(), None),
+            }
+        }
+
+        return dump;
+    }
+
+    pub fn dump_headers(&self) -> Dump {
+        let mut dump = Dump::new_from_string(format!("Header Changes ({})", self.header_changes.len()));
+
+        ExecDiff::dump_field_changes(&mut dump, &self.header_changes);
+
+        return dump;
+    }
+
+    #[rustfmt::skip]
+    pub fn dump_sections(&self) -> Dump {
+        let mut dump = Dump::new_from_string(format!("Section Changes ({})", self.section_changes.len()));
+
+        for change in self.section_changes.iter() {
+            match change {
+                SectionChange::Added { name, size } => {
+                    dump.push_field("+", format!("{} ({:#x} bytes)", name, size), None);
+                }
+                SectionChange::Removed { name, size } => {
+                    dump.push_field("-", format!("{} ({:#x} bytes)", name, size), None);
+                }
+                SectionChange::Modified { name, old_size, new_size, changed_bytes, ranges, header_changes } => {
+                    let mut section_dump = Dump::new_from_string(format!("~ {}", name));
+
+                    section_dump.push_field("Size", format!("{:#x} -> {:#x}", old_size, new_size), None);
+                    section_dump.push_field("ChangedBytes", format!("{:#x}", changed_bytes), None);
+
+                    let mut ranges_str: Vec<String> = ranges
+                        .iter()
+                        .take(MAX_DUMPED_RANGES)
+                        .map(|r| format!("{:#x}..{:#x}", r.start, r.end))
+                        .collect();
+
+                    if ranges.len() > MAX_DUMPED_RANGES {
+                        ranges_str.push(format!("... ({} more)", ranges.len() - MAX_DUMPED_RANGES));
+                    }
+
+                    section_dump.push_field("ChangedRanges", ranges_str.join(", "), None);
+
+                    ExecDiff::dump_field_changes(&mut section_dump, header_changes);
+
+                    dump.push_child(section_dump);
+                }
+            }
+        }
+
+        return dump;
+    }
+
+    pub fn dump_imports(&self) -> Dump {
+        return ExecDiff::dump_symbol_changes("Import Changes", &self.import_changes);
+    }
+
+    pub fn dump_exports(&self) -> Dump {
+        return ExecDiff::dump_symbol_changes("Export Changes", &self.export_changes);
+    }
+
+    #[rustfmt::skip]
+    pub fn dump_resources(&self) -> Dump {
+        let mut dump = Dump::new_from_string(format!("Resource Changes ({})", self.resource_changes.len()));
+
+        for change in self.resource_changes.iter() {
+            match change {
+                ResourceChange::Added { path, size } => dump.push_field("+", format!("{} ({:#x} bytes)", path, size), None),
+                ResourceChange::Removed { path, size } => dump.push_field("-", format!("{} ({:#x} bytes)", path, size), None),
+                ResourceChange::Modified { path, old_size, new_size } => {
+                    dump.push_field("~", format!("{} ({:#x} -> {:#x} bytes)", path, old_size, new_size), None)
+                }
+            }
+        }
+
+        return dump;
+    }
+
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Diff");
+
+        if self.is_empty() {
+            dump.push_field("", String::from("No structural differences"), None);
+            return dump;
+        }
+
+        dump.push_child(self.dump_headers());
+        dump.push_child(self.dump_sections());
+        dump.push_child(self.dump_imports());
+        dump.push_child(self.dump_exports());
+        dump.push_child(self.dump_resources());
+
+        return dump;
+    }
+}
+
+/*
+ * Export set comparison against a reference build of a DLL
+ */
+
+#[derive(Debug, Clone, Default)]
+pub struct ExportComparison {
+    pub reference_dll: String,
+    pub unchanged: usize,
+    /// Display name and ordinal of the exports only one side has
+    pub added: Vec<(String, u32)>,
+    pub removed: Vec<(String, u32)>,
+    /// Named exports found on both sides under different ordinals: name, reference ordinal, new ordinal
+    pub ordinal_changes: Vec<(String, u32, u32)>,
+}
+
+fn exports_by_key(pe: &PE) -> BTreeMap<String, u32> {
+    /* Named exports are matched by name, the others by ordinal */
+    match pe.export_data {
+        Some(ref export_data) => return export_data.entries.iter().map(|e| (e.display_name(), e.ordinal)).collect(),
+        None => return BTreeMap::new(),
+    }
+}
+
+impl ExportComparison {
+    pub fn compare(reference: &PE, new: &PE) -> ExportComparison {
+        let mut comparison = ExportComparison::default();
+
+        comparison.reference_dll = reference.export_data.as_ref().map(|e| e.dll_name.clone()).unwrap_or_default();
+
+        let reference_exports = exports_by_key(reference);
+        let new_exports = exports_by_key(new);
+
+        for (name, reference_ordinal) in reference_exports.iter() {
+            match new_exports.get(name) {
+                Some(new_ordinal) if new_ordinal == reference_ordinal => comparison.unchanged += 1,
+                Some(new_ordinal) => comparison.ordinal_changes.push((name.clone(), *reference_ordinal, *new_ordinal)),
+                None => comparison.removed.push((name.clone(), *reference_ordinal)),
+            }
+        }
+
+        for (name, new_ordinal) in new_exports.iter() {
+            if !reference_exports.contains_key(name) {
+                comparison.added.push((name.clone(), *new_ordinal));
+            }
+        }
+
+        return comparison;
+    }
+
+    /// Removed exports break the importers by name, changed ordinals the importers by ordinal
+    pub fn is_compatible(&self) -> bool {
+        return self.removed.is_empty() && self.ordinal_changes.is_empty();
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Export Comparison");
+
+        if !self.reference_dll.is_empty() {
+            dump.push_field("Reference", self.reference_dll.clone(), None);
+        }
+
+        dump.push_field("Unchanged", format!("{}", self.unchanged), None);
+        dump.push_field("Added", format!("{}", self.added.len()), None);
+        dump.push_field("Removed", format!("{}", self.removed.len()), None);
+        dump.push_field("OrdinalChanged", format!("{}", self.ordinal_changes.len()), None);
+        dump.push_field("Compatible", format!("{}", self.is_compatible()), Some("no export removed or renumbered"));
+
+        let mut added_dump = Dump::new_from_string(format!("Added ({})", self.added.len()));
+
+        for (name, ordinal) in self.added.iter() {
+            added_dump.push_field("+", format!("{} (ordinal {})", display_name(name), ordinal), None);
+        }
+
+        let mut removed_dump = Dump::new_from_string(format!("Removed ({})", self.removed.len()));
+
+        for (name, ordinal) in self.removed.iter() {
+            removed_dump.push_field("-", format!("{} (ordinal {})", display_name(name), ordinal), None);
+        }
+
+        let mut ordinals_dump = Dump::new_from_string(format!("Ordinal Changes ({})", self.ordinal_changes.len()));
+
+        for (name, reference_ordinal, new_ordinal) in self.ordinal_changes.iter() {
+            ordinals_dump.push_field("~", format!("{} ({} -> {})", display_name(name), reference_ordinal, new_ordinal), None);
+        }
+
+        dump.push_child(added_dump);
+        dump.push_child(removed_dump);
+        dump.push_child(ordinals_dump);
+
+        return dump;
+    }
+}
@@ -1,10 +1,12 @@
-use crate::pe::PE;
-use crate::elf::ELF;
+use crate::demangle::label_name;
+use crate::pe::{SectionFlags, PE};
+use crate::elf::{ELF, ELFClass, ELFSymbol, STB_LOCAL, STT_FUNC, STT_GNU_IFUNC, STT_NOTYPE, STT_OBJECT};
+use crate::x86_64::{Operand, RegisterClass, direct_target, memory_target, parse_immediate, parse_operands, split_operands};
 
 use capstone::Insn;
 use capstone::prelude::*;
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 #[derive(Debug, Clone)]
 pub struct BasicBlock {
@@ -44,14 +46,20 @@ pub enum XRefType {
 }
 
 pub fn is_padding_instruction(insn: &Insn) -> bool {
-    match (insn.mnemonic(), insn.op_str()) {
-        (Some("add"), Some("byte ptr [rax], al")) => true,
-        (Some("nop"), _) => true,
-        (Some("int3"), _) => true,
-        (Some("ud2"), _) => true,
-        (Some("hlt"), _) => true,
-        (Some("mov"), Some("eax, eax")) => true,
-        (Some("sub"), Some("rsp, 0")) => true,
+    let operands = parse_operands(insn.op_str().unwrap_or(""));
+
+    match (insn.mnemonic().unwrap_or(""), operands.as_slice()) {
+        ("nop" | "int3" | "ud2" | "hlt", _) => true,
+        /* Zero bytes decode as "add byte ptr [rax], al" */
+        ("add", [Operand::Memory(memory), Operand::Register(register)]) => {
+            register.name == "al" && memory.field_of().is_some_and(|(base, displacement)| matches!(base.name.as_str(), "rax" | "eax") && displacement == 0)
+        }
+        /* A register moved to itself, or loaded with its own address. 32-bit moves zero-extend in long mode, only the mov eax, eax filler is kept */
+        ("mov", [Operand::Register(destination), Operand::Register(source)]) => destination == source && (destination.size != 32 || destination.name == "eax"),
+        ("lea", [Operand::Register(destination), Operand::Memory(memory)]) => {
+            memory.field_of().is_some_and(|(base, displacement)| base == destination && displacement == 0)
+        }
+        ("sub", [Operand::Register(register), Operand::Immediate(0)]) => register.is_stack_pointer(),
         _ => false,
     }
 }
@@ -71,7 +79,7 @@ fn build_import_map(pe: &PE) -> HashMap<u64, String> {
 
             for (func_idx, func_entry) in hnt.entries[idx].entries.iter().enumerate() {
                 let func_rva = iat_rva + (func_idx * if pe.is_32_bits() { 4 } else { 8 }) as u64;
-                let full_name = format!("{}!{}", dll_name, func_entry.name);
+                let full_name = format!("{}!{}", dll_name, label_name(&func_entry.name));
                 map.insert(func_rva, full_name);
             }
         }
@@ -81,16 +89,18 @@ fn build_import_map(pe: &PE) -> HashMap<u64, String> {
 }
 
 /// Extract string references from code
-fn find_string_references(code: &[u8], base_addr: u64, pe: &PE) -> HashMap<u64, String> {
+fn find_string_references(_code: &[u8], _base_addr: u64, pe: &PE) -> HashMap<u64, String> {
     let mut strings = HashMap::new();
 
-    for (section_name, section) in &pe.sections {
+    for section in pe.sections.iter() {
+        let section_name = &section.header.name;
+
         if section_name.contains("data") || section_name.contains("rdata") {
             let mut current_string = Vec::new();
             let mut string_start = 0;
 
             for (i, &byte) in section.data.iter().enumerate() {
-                if byte >= 0x20 && byte <= 0x7E {
+                if (0x20..=0x7E).contains(&byte) {
                     if current_string.is_empty() {
                         string_start = i;
                     }
@@ -114,17 +124,12 @@ fn find_string_references(code: &[u8], base_addr: u64, pe: &PE) -> HashMap<u64,
 /// Analyze stack frame setup and teardown
 fn analyze_stack_frame(instructions: &[&Insn]) -> Option<i64> {
     for insn in instructions.iter().take(10) {
-        if let (Some("sub"), Some(op_str)) = (insn.mnemonic(), insn.op_str()) {
-            if op_str.starts_with("rsp,") || op_str.starts_with("esp,") {
-                let parts: Vec<&str> = op_str.split(',').collect();
-
-                if parts.len() == 2 {
-                    let size_str = parts[1].trim().trim_start_matches("0x");
-                    if let Ok(size) = i64::from_str_radix(size_str, 16) {
-                        return Some(size);
-                    }
-                }
-            }
+        if insn.mnemonic() != Some("sub") {
+            continue;
+        }
+
+        if let [Operand::Register(register), Operand::Immediate(size)] = parse_operands(insn.op_str().unwrap_or("")).as_slice() && register.is_stack_pointer() {
+            return Some(*size);
         }
     }
 
@@ -146,13 +151,9 @@ fn detect_functions(instructions: &[Insn]) -> Vec<u64> {
         let addr = insn.address();
 
         // Pattern 1: push rbp/ebp; mov rbp/ebp, rsp/esp (function prologue)
-        if let (Some("push"), Some(op)) = (insn.mnemonic(), insn.op_str()) {
-            if op == "rbp" || op == "ebp" {
-                if !seen_starts.contains(&addr) {
-                    function_starts.push(addr);
-                    seen_starts.insert(addr);
-                }
-            }
+        if insn.mnemonic() == Some("push") && let [Operand::Register(register)] = parse_operands(insn.op_str().unwrap_or("")).as_slice() && register.is_frame_pointer() && !seen_starts.contains(&addr) {
+            function_starts.push(addr);
+            seen_starts.insert(addr);
         }
 
         // Pattern 2: After a return, next instruction likely starts a function
@@ -172,6 +173,7 @@ fn detect_functions(instructions: &[Insn]) -> Vec<u64> {
 }
 
 /// Build control flow graph for basic blocks
+#[allow(dead_code)]
 fn build_cfg(instructions: &[Insn]) -> Vec<BasicBlock> {
     let mut blocks = Vec::new();
     let mut block_starts = HashSet::new();
@@ -182,19 +184,13 @@ fn build_cfg(instructions: &[Insn]) -> Vec<BasicBlock> {
     for (i, insn) in instructions.iter().enumerate() {
         if let Some(mnemonic) = insn.mnemonic() {
             // After control flow instruction, next insn starts a new block
-            if is_control_flow(mnemonic) || mnemonic == "ret" {
-                if i + 1 < instructions.len() {
-                    block_starts.insert(instructions[i + 1].address());
-                }
+            if (is_control_flow(mnemonic) || mnemonic == "ret") && i + 1 < instructions.len() {
+                block_starts.insert(instructions[i + 1].address());
             }
 
             // Target of jump/call starts a new block
-            if mnemonic.starts_with('j') || mnemonic == "call" {
-                if let Some(op_str) = insn.op_str() {
-                    if let Ok(target) = parse_hex_address(op_str) {
-                        block_starts.insert(target);
-                    }
-                }
+            if (mnemonic.starts_with('j') || mnemonic == "call") && let Some(target) = insn.op_str().and_then(direct_target) {
+                block_starts.insert(target);
             }
         }
     }
@@ -229,22 +225,20 @@ fn build_cfg(instructions: &[Insn]) -> Vec<BasicBlock> {
         ));
 
         // End block on control flow instruction
-        if let Some(mnemonic) = insn.mnemonic() {
-            if is_control_flow(mnemonic) || mnemonic == "ret" {
-                blocks.push(BasicBlock {
-                    start_addr: current_block_start,
-                    end_addr: addr,
-                    instructions: current_instrs.clone(),
-                    successors: Vec::new(),
-                    predecessors: Vec::new(),
-                });
-                current_instrs.clear();
+        if let Some(mnemonic) = insn.mnemonic() && (is_control_flow(mnemonic) || mnemonic == "ret") {
+            blocks.push(BasicBlock {
+                start_addr: current_block_start,
+                end_addr: addr,
+                instructions: current_instrs.clone(),
+                successors: Vec::new(),
+                predecessors: Vec::new(),
+            });
+            current_instrs.clear();
 
-                if let Some(next_insn) =
-                    instructions.get((addr - instructions[0].address()) as usize + 1)
-                {
-                    current_block_start = next_insn.address();
-                }
+            if let Some(next_insn) =
+                instructions.get((addr - instructions[0].address()) as usize + 1)
+            {
+                current_block_start = next_insn.address();
             }
         }
     }
@@ -271,61 +265,46 @@ fn build_xrefs(instructions: &[Insn], string_refs: &HashMap<u64, String>) -> Vec
         let from = insn.address();
 
         if let Some(mnemonic) = insn.mnemonic() {
-            if let Some(op_str) = insn.op_str() {
-                // Call/Jump xrefs
+            let operands = parse_operands(insn.op_str().unwrap_or(""));
+
+            // Call/Jump xrefs
+            if let [Operand::Immediate(target)] = operands.as_slice() {
                 if mnemonic == "call" {
-                    if let Ok(target) = parse_hex_address(op_str) {
-                        xrefs.push(CrossReference {
-                            from_addr: from,
-                            to_addr: target,
-                            xref_type: XRefType::Call,
-                        });
-                    }
+                    xrefs.push(CrossReference {
+                        from_addr: from,
+                        to_addr: *target as u64,
+                        xref_type: XRefType::Call,
+                    });
                 } else if mnemonic.starts_with('j') {
-                    if let Ok(target) = parse_hex_address(op_str) {
-                        xrefs.push(CrossReference {
-                            from_addr: from,
-                            to_addr: target,
-                            xref_type: XRefType::Jump,
-                        });
-                    }
+                    xrefs.push(CrossReference {
+                        from_addr: from,
+                        to_addr: *target as u64,
+                        xref_type: XRefType::Jump,
+                    });
                 }
+            }
 
-                // Data references
-                if op_str.contains('[') {
-                    if let Some(addr_start) = op_str.find("0x") {
-                        let addr_part = &op_str[addr_start..];
-                        let addr_end = addr_part
-                            .find(|c: char| !c.is_ascii_hexdigit() && c != 'x')
-                            .unwrap_or(addr_part.len());
-
-                        if let Ok(addr) = parse_hex_address(&addr_part[..addr_end]) {
-                            let xref_type =
-                                if mnemonic.starts_with("mov") && op_str.starts_with('[') {
-                                    XRefType::DataRead
-                                } else if mnemonic.starts_with("mov") {
-                                    XRefType::DataWrite
-                                } else {
-                                    XRefType::DataRead
-                                };
-
-                            // Check if it's a string reference
-                            if string_refs.contains_key(&addr) {
-                                xrefs.push(CrossReference {
-                                    from_addr: from,
-                                    to_addr: addr,
-                                    xref_type: XRefType::StringReference,
-                                });
-                            } else {
-                                xrefs.push(CrossReference {
-                                    from_addr: from,
-                                    to_addr: addr,
-                                    xref_type,
-                                });
-                            }
-                        }
-                    }
-                }
+            // Data references, the memory operand is written when it is the destination of a mov
+            let next = from + insn.bytes().len() as u64;
+
+            for (i, operand) in operands.iter().enumerate() {
+                let Some(addr) = operand.memory().and_then(|m| m.absolute_address(next)) else {
+                    continue;
+                };
+
+                let xref_type = if string_refs.contains_key(&addr) {
+                    XRefType::StringReference
+                } else if i == 0 && mnemonic.starts_with("mov") {
+                    XRefType::DataWrite
+                } else {
+                    XRefType::DataRead
+                };
+
+                xrefs.push(CrossReference {
+                    from_addr: from,
+                    to_addr: addr,
+                    xref_type,
+                });
             }
         }
     }
@@ -336,21 +315,14 @@ fn build_xrefs(instructions: &[Insn], string_refs: &HashMap<u64, String>) -> Vec
 /// Build a map of addresses that are targets of jumps/calls (for labeling)
 fn build_label_map(instructions: &[Insn]) -> HashMap<u64, String> {
     let mut map = HashMap::new();
-    let mut label_counter = 0 as usize;
+    let mut label_counter = 0_usize;
 
     for insn in instructions {
         if let Some(mnemonic) = insn.mnemonic() {
             // Check for control flow instructions
-            if mnemonic.starts_with('j') || mnemonic == "call" {
-                if let Some(op_str) = insn.op_str() {
-                    // Try to parse the target address
-                    if let Ok(target) = parse_hex_address(op_str) {
-                        if !map.contains_key(&target) {
-                            label_counter += 1;
-                            map.insert(target, format!("label_{}", label_counter));
-                        }
-                    }
-                }
+            if (mnemonic.starts_with('j') || mnemonic == "call") && let Some(target) = insn.op_str().and_then(direct_target) && let std::collections::hash_map::Entry::Vacant(e) = map.entry(target) {
+                label_counter += 1;
+                e.insert(format!("label_{}", label_counter));
             }
         }
     }
@@ -358,32 +330,11 @@ fn build_label_map(instructions: &[Insn]) -> HashMap<u64, String> {
     return map;
 }
 
-/// Parse hex address from operand string
-fn parse_hex_address(op_str: &str) -> Result<u64, ()> {
-    let cleaned = op_str.trim().trim_start_matches("0x");
-
-    return u64::from_str_radix(cleaned, 16).map_err(|_| ());
-}
-
-/// Parse a hex address from a memory reference
-fn parse_hex_address_from_memory_ref(op_str: &str) -> Result<u64, ()> {
-    if let Some(addr_start) = op_str.find("0x") {
-        let addr_part = &op_str[addr_start..];
-        let addr_end = addr_part
-            .find(|c: char| !c.is_ascii_hexdigit() && c != 'x')
-            .unwrap_or(addr_part.len());
-
-        return parse_hex_address(&addr_part[..addr_end]);
-    }
-
-    return Err(());
-}
-
-/// Parse a hex address from a rip memory reference
-fn parse_hex_address_from_rip_memory_reference(op_str: &str, insn_addr: u64) -> Result<u64, ()> {
-    let offset = parse_hex_address_from_memory_ref(op_str)?;
+/// Target of a call or jump, direct or through a memory slot (import thunks)
+fn control_flow_target(insn: &Insn) -> Option<u64> {
+    let op_str = insn.op_str().unwrap_or("");
 
-    return Ok(offset + insn_addr);
+    return direct_target(op_str).or_else(|| memory_target(op_str, insn.address() + insn.bytes().len() as u64));
 }
 
 /// Check if instruction is a call or jump
@@ -407,64 +358,43 @@ fn format_instruction(
     let mut comments = Vec::new();
 
     // Check if this is a call/jump to a known location
-    if is_control_flow(mnemonic) {
-        if op_str.contains("[rip") {
-            if let Ok(target) = parse_hex_address_from_rip_memory_reference(op_str, addr) {
-                let offset = target + insn.bytes().len() as u64;
-
-                if let Some(import_name) = import_map.get(&offset) {
-                    comments.push(import_name.clone());
-                }
-            }
-        } else if let Ok(target) = parse_hex_address_from_memory_ref(op_str) {
-            if let Some(import_name) = import_map.get(&target) {
-                comments.push(import_name.clone());
-            } else if let Some(label) = label_map.get(&target) {
-                return format!("    {:<8} {}  ; {}", mnemonic, label, comments.join(" | "));
-            }
+    if is_control_flow(mnemonic) && let Some(target) = control_flow_target(insn) {
+        if let Some(import_name) = import_map.get(&target) {
+            comments.push(import_name.clone());
+        } else if let Some(label) = label_map.get(&target) {
+            return format!("    {:<8} {}", mnemonic, label);
         }
     }
 
     // Add xrefs to this location as comments
-    if let Some(refs) = xrefs_to.get(&addr) {
-        if !refs.is_empty() {
-            let xref_addrs: Vec<String> = refs
-                .iter()
-                .take(3)
-                .map(|xref| format!("{:08X}", xref.from_addr))
-                .collect();
-            let more = if refs.len() > 3 {
-                format!(", +{} more", refs.len() - 3)
-            } else {
-                String::new()
-            };
-            comments.push(format!("XREF from: {}{}", xref_addrs.join(", "), more));
-        }
+    if let Some(refs) = xrefs_to.get(&addr) && !refs.is_empty() {
+        let xref_addrs: Vec<String> = refs
+            .iter()
+            .take(3)
+            .map(|xref| format!("{:08X}", xref.from_addr))
+            .collect();
+        let more = if refs.len() > 3 {
+            format!(", +{} more", refs.len() - 3)
+        } else {
+            String::new()
+        };
+        comments.push(format!("XREF from: {}{}", xref_addrs.join(", "), more));
     }
 
     // Check for memory references
-    if op_str.contains('[') && op_str.contains(']') {
-        if let Some(addr_start) = op_str.find("0x") {
-            let addr_part = &op_str[addr_start..];
-            let addr_end = addr_part
-                .find(|c: char| !c.is_ascii_hexdigit() && c != 'x')
-                .unwrap_or(addr_part.len());
-
-            if let Ok(addr) = parse_hex_address(&addr_part[..addr_end]) {
-                // Check for string reference
-                if let Some(string) = string_refs.get(&addr) {
-                    let truncated = if string.len() > 40 {
-                        format!("{}...", &string[..40])
-                    } else {
-                        string.clone()
-                    };
-                    comments.push(format!("\"{}\"", truncated));
-                }
-                // Check for import
-                else if let Some(import_name) = import_map.get(&addr) {
-                    comments.push(format!("-> {}", import_name));
-                }
-            }
+    if !is_control_flow(mnemonic) && let Some(addr) = memory_target(op_str, addr + insn.bytes().len() as u64) {
+        // Check for string reference
+        if let Some(string) = string_refs.get(&addr) {
+            let truncated = if string.len() > 40 {
+                format!("{}...", &string[..40])
+            } else {
+                string.clone()
+            };
+            comments.push(format!("\"{}\"", truncated));
+        }
+        // Check for import
+        else if let Some(import_name) = import_map.get(&addr) {
+            comments.push(format!("-> {}", import_name));
         }
     }
 
@@ -500,15 +430,22 @@ pub fn disasm_pe_code(
 
     let import_map = build_import_map(pe);
     let string_refs = find_string_references(code, addr, pe);
-    let label_map = build_label_map(instructions.as_ref());
+    let mut label_map = build_label_map(instructions.as_ref());
     let xrefs = build_xrefs(instructions.as_ref(), &string_refs);
 
+    /* The instructions are at their RVA, the targets named in the PDB are labeled with their symbol */
+    for (target, label) in label_map.iter_mut() {
+        if let Some(symbol) = u32::try_from(*target).ok().and_then(|rva| pe.pdb_symbols.get(&rva)) {
+            *label = label_name(symbol);
+        }
+    }
+
     let mut xrefs_to: HashMap<u64, Vec<CrossReference>> = HashMap::new();
 
     for xref in &xrefs {
         xrefs_to
             .entry(xref.to_addr)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(xref.clone());
     }
 
@@ -519,7 +456,7 @@ pub fn disasm_pe_code(
     let mut current_function_idx = 0;
 
     for insn in instructions.as_ref() {
-        if is_padding_instruction(&insn) {
+        if is_padding_instruction(insn) {
             continue;
         }
 
@@ -530,7 +467,10 @@ pub fn disasm_pe_code(
         {
             output.push(String::new());
             output.push(format!("; {}", "─".repeat(40)));
-            output.push(format!("; FUNC_{:08x}", insn_addr));
+            match u32::try_from(insn_addr).ok().and_then(|rva| pe.pdb_symbols.get(&rva)) {
+                Some(symbol) => output.push(format!("; {}", label_name(symbol))),
+                None => output.push(format!("; FUNC_{:08x}", insn_addr)),
+            }
 
             // Analyze stack frame for this function
             let remaining_insns: Vec<&Insn> = instructions
@@ -554,20 +494,18 @@ pub fn disasm_pe_code(
             output.push(format!("{}:", label));
         }
 
-        let formatted = format_instruction(&insn, &import_map, &label_map, &string_refs, &xrefs_to);
+        let formatted = format_instruction(insn, &import_map, &label_map, &string_refs, &xrefs_to);
 
         let line = format!("{:08x}  {}", insn_addr, formatted);
         output.push(line);
 
-        if let Some(mnemonic) = insn.mnemonic() {
-            if mnemonic == "ret" {
-                output.push(String::new());
-            }
+        if let Some(mnemonic) = insn.mnemonic() && mnemonic == "ret" {
+            output.push(String::new());
         }
     }
 
     output.push(String::new());
-    output.push(format!("; End"));
+    output.push("; End".to_string());
 
     return Ok(output);
 }
@@ -589,24 +527,444 @@ pub fn disasm_elf_code(
 
     let instructions = cs.disasm_all(code, addr).expect("Failed to disassemble");
 
+    let labels = elf.function_labels();
+
     output.push(format!("; Entry: 0x{:X}", addr));
 
     for insn in instructions.as_ref() {
-        if is_padding_instruction(&insn) {
+        if is_padding_instruction(insn) {
             continue;
         }
 
-        output.push(insn.to_string());
+        if let Some(label) = labels.get(&insn.address()) {
+            output.push(format!("; {}", label));
+        }
 
-        if let Some(mnemonic) = insn.mnemonic() {
-            if mnemonic == "ret" {
-                output.push(String::new());
-            }
+        let mnemonic = insn.mnemonic().unwrap_or("");
+        let target = control_flow_target(insn);
+
+        match target.filter(|_| is_control_flow(mnemonic)).and_then(|t| labels.get(&t)) {
+            Some(label) => output.push(format!("{}  ; {}", insn, label)),
+            None => output.push(insn.to_string()),
+        }
+
+        if let Some(mnemonic) = insn.mnemonic() && mnemonic == "ret" {
+            output.push(String::new());
         }
     }
 
     output.push(String::new());
-    output.push(format!("; End"));
+    output.push("; End".to_string());
 
     return Ok(output);
 }
+
+fn capstone_x86(is_32_bits: bool) -> Result<Capstone, Box<dyn std::error::Error>> {
+    let mode = if is_32_bits { arch::x86::ArchMode::Mode32 } else { arch::x86::ArchMode::Mode64 };
+
+    return Ok(Capstone::new().x86().mode(mode).syntax(arch::x86::ArchSyntax::Intel).detail(false).build()?);
+}
+
+/* Upper bound on the number of instructions of a disassembled DOS stub */
+const MAX_DOS_STUB_INSTRUCTIONS: usize = 256;
+
+/// Disassembles the real-mode code of a DOS stub, up to the int 21h/4Ch exit or its first return
+pub fn disasm_dos_stub(code: &[u8], addr: u64) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let cs = Capstone::new().x86().mode(arch::x86::ArchMode::Mode16).syntax(arch::x86::ArchSyntax::Intel).detail(false).build()?;
+
+    let instructions = cs.disasm_count(code, addr, MAX_DOS_STUB_INSTRUCTIONS)?;
+
+    let mut lines = Vec::new();
+    let mut exit_requested = false;
+
+    for insn in instructions.iter() {
+        let mnemonic = insn.mnemonic().unwrap_or_default();
+        let operands = insn.op_str().unwrap_or_default();
+
+        lines.push(format!("{:04x}  {} {}", insn.address(), mnemonic, operands).trim_end().to_string());
+
+        /* AH = 4Ch is the DOS terminate call, the bytes following int 21h are data */
+        let ax_exit = operands.starts_with("ax, 0x4c") && operands.len() == "ax, 0x4c00".len();
+
+        if mnemonic == "mov" && (ax_exit || operands == "ah, 0x4c") {
+            exit_requested = true;
+        }
+
+        if matches!(mnemonic, "ret" | "retf" | "iret") || (exit_requested && mnemonic == "int" && operands == "0x21") || (mnemonic == "int" && operands == "0x20") {
+            break;
+        }
+    }
+
+    if lines.is_empty() {
+        return Err("No instruction could be decoded at the entry point of the DOS stub".into());
+    }
+
+    return Ok(lines);
+}
+
+/// Disassembles up to count instructions at a virtual address (ImageBase included), without the function analysis
+pub fn disasm_pe_instructions(
+    pe: &PE,
+    code: &[u8],
+    addr: u64,
+    count: usize,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let cs = capstone_x86(pe.is_32_bits())?;
+
+    let (import_map, symbol_map) = pe_address_maps(pe);
+    let no_strings = HashMap::new();
+    let no_xrefs = HashMap::new();
+
+    let instructions = cs.disasm_count(code, addr, count)?;
+
+    return Ok(instructions
+        .iter()
+        .map(|insn| format!("{:08x}  {}", insn.address(), format_instruction(insn, &import_map, &symbol_map, &no_strings, &no_xrefs)))
+        .collect());
+}
+
+/// Imports and pdb symbols of a PE keyed by virtual address (ImageBase included)
+fn pe_address_maps(pe: &PE) -> (HashMap<u64, String>, HashMap<u64, String>) {
+    let image_base = pe.get_optional_header().get_image_base();
+
+    /* The import map is keyed by RVA, the instructions are at their virtual address */
+    let import_map: HashMap<u64, String> = build_import_map(pe)
+        .into_iter()
+        .map(|(rva, name)| (rva + image_base, name))
+        .collect();
+
+    let symbol_map: HashMap<u64, String> = pe
+        .pdb_symbols
+        .iter()
+        .map(|(rva, symbol)| (*rva as u64 + image_base, label_name(symbol)))
+        .collect();
+
+    return (import_map, symbol_map);
+}
+
+/// Disassembles the code at a virtual address up to its first return, for the functions whose bounds are unknown
+pub fn disasm_pe_until_return(pe: &PE, addr: u64, max_count: usize) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let rva = addr.wrapping_sub(pe.get_optional_header().get_image_base());
+
+    let section = u32::try_from(rva)
+        .ok()
+        .and_then(|rva| pe.get_section_for_rva(rva))
+        .ok_or_else(|| format!("{:#x} is not inside a section", addr))?;
+
+    let data = section.raw_data();
+    let start = ((rva - section.header.virtual_address as u64) as usize).min(data.len());
+
+    let cs = capstone_x86(pe.is_32_bits())?;
+
+    let (import_map, symbol_map) = pe_address_maps(pe);
+    let no_strings = HashMap::new();
+    let no_xrefs = HashMap::new();
+
+    let instructions = cs.disasm_count(&data[start..], addr, max_count)?;
+    let mut lines = Vec::new();
+
+    for insn in instructions.iter() {
+        lines.push(format!("{:08x}  {}", insn.address(), format_instruction(insn, &import_map, &symbol_map, &no_strings, &no_xrefs)));
+
+        if matches!(insn.mnemonic(), Some("ret") | Some("retf")) {
+            break;
+        }
+    }
+
+    if lines.is_empty() {
+        return Err(format!("No instruction could be decoded at {:#x}", addr).into());
+    }
+
+    return Ok(lines);
+}
+
+/// Disassembles up to count instructions at a virtual address
+pub fn disasm_elf_instructions(
+    elf: &ELF,
+    code: &[u8],
+    addr: u64,
+    count: usize,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let cs = capstone_x86(matches!(elf.class(), ELFClass::ELF32))?;
+
+    let labels = elf.function_labels();
+    let no_imports = HashMap::new();
+    let no_xrefs = HashMap::new();
+
+    let instructions = cs.disasm_count(code, addr, count)?;
+
+    return Ok(instructions
+        .iter()
+        .map(|insn| format!("{:08x}  {}", insn.address(), format_instruction(insn, &no_imports, &labels, &no_imports, &no_xrefs)))
+        .collect());
+}
+
+/*
+ * Listing laid out like `objdump -d -M intel`, for the scripts parsing its output
+ */
+
+/* objdump prints 7 bytes per line for x86, the remaining bytes go on continuation lines */
+const OBJDUMP_BYTES_PER_LINE: usize = 7;
+
+/* The longer names first, "word ptr" is also the end of "dword ptr" */
+const OBJDUMP_SIZE_QUALIFIERS: [&str; 9] = ["xmmword", "ymmword", "zmmword", "dword", "fword", "qword", "tbyte", "byte", "word"];
+
+/// Leading hex digits dropped from the instruction addresses of a section, a multiple of 4 like objdump
+fn objdump_skipped_digits(end_address: u64, digits: usize) -> usize {
+    let zeros = format!("{:0width$x}", end_address, width = digits).bytes().take_while(|b| *b == b'0').count();
+
+    if zeros == 0 || zeros == digits {
+        return 0;
+    }
+
+    return (zeros - 1) & !3;
+}
+
+/// Address with its leading zeros as spaces, "   140001000"
+fn objdump_address(address: u64, digits: usize, skipped_digits: usize) -> String {
+    let full = format!("{:0width$x}", address, width = digits);
+    let shown = &full[skipped_digits.min(full.len() - 1)..];
+    let zeros = shown.bytes().take_while(|b| *b == b'0').count().min(shown.len() - 1);
+
+    return format!("{}{}", " ".repeat(zeros), &shown[zeros..]);
+}
+
+/// "1139 <main>" or "1150 <main+0x17>" with a preceding symbol, "0x1139" otherwise
+fn objdump_reference(address: u64, symbols: &BTreeMap<u64, String>) -> String {
+    match symbols.range(..=address).next_back() {
+        Some((symbol_address, name)) if *symbol_address == address => return format!("{:x} <{}>", address, name),
+        Some((symbol_address, name)) => return format!("{:x} <{}+{:#x}>", address, name, address - symbol_address),
+        None => return format!("{:#x}", address),
+    }
+}
+
+/// Decimal numbers of capstone in hex, the scales ("rax*8") and FPU stack indices ("st(1)") stay decimal
+fn objdump_hex_numbers(operand: &str) -> String {
+    let mut result = String::new();
+    let mut word = String::new();
+    let mut previous = ' ';
+
+    for c in operand.chars().chain(std::iter::once(' ')) {
+        if c.is_ascii_alphanumeric() {
+            word.push(c);
+            continue;
+        }
+
+        match word.parse::<u64>() {
+            Ok(value) if previous != '*' && previous != '(' => result.push_str(&format!("{:#x}", value)),
+            _ => result.push_str(&word),
+        }
+
+        previous = c;
+        word.clear();
+        result.push(c);
+    }
+
+    result.pop();
+
+    return result;
+}
+
+/// Capstone operands in the objdump spelling: "QWORD PTR [rip+0x2fe2]", "rsp,0x28", "fs:0x28", negative immediates as unsigned
+fn objdump_operands(op_str: &str, is_32_bits: bool) -> String {
+    let parsed = parse_operands(op_str);
+
+    /* Negative immediates wrap at the size of the register they go with, else of the mode */
+    let immediate_size = match parsed.iter().find_map(|o| o.register()).filter(|r| r.class == RegisterClass::General) {
+        Some(register) => register.size as u32,
+        None => if is_32_bits { 32 } else { 64 },
+    };
+
+    let mut operands = Vec::new();
+
+    for (operand, text) in parsed.iter().zip(split_operands(op_str)) {
+        match operand {
+            Operand::Immediate(value) if *value < 0 => operands.push(format!("{:#x}", (*value as u64) & (u64::MAX >> (64 - immediate_size)))),
+            _ => operands.push(objdump_hex_numbers(text.trim())),
+        }
+    }
+
+    let mut operands = operands.join(",").replace(" + ", "+").replace(" - ", "-").replace(" * ", "*");
+
+    for qualifier in OBJDUMP_SIZE_QUALIFIERS {
+        operands = operands.replace(&format!("{} ptr ", qualifier), &format!("{} PTR ", qualifier.to_ascii_uppercase()));
+    }
+
+    /* Absolute addresses with a segment override are not bracketed */
+    for segment in ["cs", "ds", "es", "fs", "gs", "ss"] {
+        let prefix = format!("{}:[", segment);
+
+        if let Some(start) = operands.find(&prefix) {
+            let inner_start = start + prefix.len();
+
+            if let Some(length) = operands[inner_start..].find(']') {
+                let inner = operands[inner_start..inner_start + length].to_string();
+
+                if parse_immediate(&inner).is_some() {
+                    operands.replace_range(start..inner_start + length + 1, &format!("{}:{}", segment, inner));
+                }
+            }
+        }
+    }
+
+    return operands;
+}
+
+fn objdump_instruction(insn: &Insn, symbols: &BTreeMap<u64, String>, is_32_bits: bool) -> String {
+    let mnemonic = insn.mnemonic().unwrap_or("");
+    let op_str = insn.op_str().unwrap_or("");
+    let next_address = insn.address() + insn.bytes().len() as u64;
+
+    let text = match direct_target(op_str).filter(|_| is_control_flow(mnemonic)) {
+        Some(target) => format!("{:<6} {}", mnemonic, objdump_reference(target, symbols)),
+        None => format!("{:<6} {}", mnemonic, objdump_operands(op_str, is_32_bits)),
+    };
+
+    /* The rip-relative accesses are followed by the address they resolve to */
+    let rip_target = parse_operands(op_str)
+        .iter()
+        .filter_map(|operand| operand.memory())
+        .find(|memory| memory.is_rip_relative())
+        .and_then(|memory| memory.absolute_address(next_address));
+
+    match rip_target {
+        Some(target) => return format!("{}        # {}", text, objdump_reference(target, symbols)),
+        None => return text.trim_end().to_string(),
+    }
+}
+
+fn objdump_byte_lines(address: &str, bytes: &[u8], text: &str, lines: &mut Vec<String>, digits: usize, skipped_digits: usize, start: u64) {
+    for (i, chunk) in bytes.chunks(OBJDUMP_BYTES_PER_LINE).enumerate() {
+        let hex: String = chunk.iter().map(|b| format!("{:02x} ", b)).collect();
+
+        match i {
+            0 => lines.push(format!("{}:\t{:<width$}\t{}", address, hex, text, width = OBJDUMP_BYTES_PER_LINE * 3)),
+            _ => lines.push(format!("{}:\t{}", objdump_address(start + (i * OBJDUMP_BYTES_PER_LINE) as u64, digits, skipped_digits), hex)),
+        }
+    }
+}
+
+/// Sections as (name, bytes, address), disassembled in the given order
+fn objdump_listing(
+    file_name: &str,
+    file_format: &str,
+    is_32_bits: bool,
+    sections: Vec<(String, &[u8], u64)>,
+    symbols: &BTreeMap<u64, String>,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let cs = capstone_x86(is_32_bits)?;
+    let digits = if is_32_bits { 8 } else { 16 };
+
+    let mut lines = vec![String::new(), format!("{}:     file format {}", file_name, file_format), String::new()];
+
+    for (name, data, start) in sections {
+        let end = start + data.len() as u64;
+        let skipped_digits = objdump_skipped_digits(end, digits);
+
+        lines.push(String::new());
+        lines.push(format!("Disassembly of section {}:", name));
+
+        if symbols.get(&start).is_none() {
+            lines.push(String::new());
+            lines.push(format!("{:0width$x} <{}>:", start, name, width = digits));
+        }
+
+        let mut position = 0;
+
+        while position < data.len() {
+            let instructions = cs.disasm_all(&data[position..], start + position as u64)?;
+
+            for insn in instructions.iter() {
+                if let Some(symbol) = symbols.get(&insn.address()) {
+                    lines.push(String::new());
+                    lines.push(format!("{:0width$x} <{}>:", insn.address(), symbol, width = digits));
+                }
+
+                let address = objdump_address(insn.address(), digits, skipped_digits);
+                objdump_byte_lines(&address, insn.bytes(), &objdump_instruction(insn, symbols, is_32_bits), &mut lines, digits, skipped_digits, insn.address());
+
+                position += insn.bytes().len();
+            }
+
+            /* Capstone stops at the first byte it cannot decode */
+            if position < data.len() {
+                let address = start + position as u64;
+                objdump_byte_lines(&objdump_address(address, digits, skipped_digits), &data[position..position + 1], "(bad)", &mut lines, digits, skipped_digits, address);
+                position += 1;
+            }
+        }
+    }
+
+    return Ok(lines);
+}
+
+pub fn objdump_pe_listing(pe: &PE, file_name: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let image_base = pe.get_optional_header().get_image_base();
+
+    let mut symbols: BTreeMap<u64, String> = pe.pdb_symbols.iter().map(|(rva, name)| (image_base + *rva as u64, name.clone())).collect();
+
+    if let Some(ref export_data) = pe.export_data {
+        for entry in export_data.entries.iter().filter(|e| e.forwarder.is_none()) {
+            if let Some(ref name) = entry.name {
+                symbols.entry(image_base + entry.rva as u64).or_insert_with(|| name.clone());
+            }
+        }
+    }
+
+    let mut sections: Vec<_> = pe
+        .sections
+        .iter()
+        .filter(|s| s.contains_code() || (s.header.characteristics & SectionFlags::MemExecute as u32) != 0)
+        .map(|s| {
+            /* The section ends at its virtual size, the file alignment padding is left out */
+            let size = match s.header.virtual_size {
+                0 => s.raw_data().len(),
+                virtual_size => s.raw_data().len().min(virtual_size as usize),
+            };
+
+            (s.header.name.clone(), &s.raw_data()[..size], image_base + s.header.virtual_address as u64)
+        })
+        .collect();
+
+    sections.sort_by_key(|(_, _, address)| *address);
+
+    let file_format = if pe.is_32_bits() { "pei-i386" } else { "pei-x86-64" };
+
+    return objdump_listing(file_name, file_format, pe.is_32_bits(), sections, &symbols);
+}
+
+pub fn objdump_elf_listing(elf: &ELF, file_name: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut labels: BTreeMap<u64, &ELFSymbol> = BTreeMap::new();
+
+    /* Mangled names like objdump, a global symbol is preferred over a local one at the same address */
+    for symbol in elf.symbols.iter().chain(elf.dynamic_symbols.iter()) {
+        let is_label = matches!(symbol.symbol_type(), STT_NOTYPE | STT_OBJECT | STT_FUNC | STT_GNU_IFUNC);
+
+        if !is_label || !symbol.is_defined() || symbol.value == 0 || symbol.name.is_empty() {
+            continue;
+        }
+
+        match labels.get(&symbol.value) {
+            Some(label) if label.binding() != STB_LOCAL || symbol.binding() == STB_LOCAL => {}
+            _ => {
+                labels.insert(symbol.value, symbol);
+            }
+        }
+    }
+
+    let symbols = labels.into_iter().map(|(address, symbol)| (address, symbol.name.clone())).collect();
+
+    let mut sections: Vec<_> = elf
+        .sections
+        .values()
+        .filter(|s| s.contains_code() && !s.data.is_empty())
+        .map(|s| (s.name.clone(), s.data.as_slice(), s.header.virtual_address()))
+        .collect();
+
+    sections.sort_by_key(|(_, _, address)| *address);
+
+    let is_32_bits = matches!(elf.class(), ELFClass::ELF32);
+    let file_format = if is_32_bits { "elf32-i386" } else { "elf64-x86-64" };
+
+    return objdump_listing(file_name, file_format, is_32_bits, sections, &symbols);
+}
@@ -0,0 +1,1369 @@
+use sha1::{Digest, Sha1};
+
+use strum::IntoEnumIterator;
+use strum_macros::{EnumIter, IntoStaticStr};
+
+use crate::dump::*;
+use crate::pe::{ImageDataDirectory, PEReader, PE};
+
+/*
+ * .NET (CLI) metadata
+ * ECMA-335, Partition II: https://ecma-international.org/publications-and-standards/standards/ecma-335/
+ */
+
+/*
+ * CLR Runtime Header (IMAGE_COR20_HEADER)
+ */
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum CLRFlags {
+    IlOnly = 0x00000001,           // Image contains only IL code.
+    Required32Bit = 0x00000002,    // Image can only be loaded into a 32-bit process.
+    IlLibrary = 0x00000004,        // Image is a library (obsolete).
+    StrongNameSigned = 0x00000008, // Image has a strong name signature.
+    NativeEntryPoint = 0x00000010, // Entry point is a native method (RVA) rather than a metadata token.
+    TrackDebugData = 0x00010000,   // The loader and JIT track debug information (obsolete).
+    Preferred32Bit = 0x00020000,   // Image should be loaded into a 32-bit process when possible.
+}
+
+impl CLRFlags {
+    pub fn flags_as_string(flags: u32) -> String {
+        let flags: Vec<&'static str> = CLRFlags::iter()
+            .filter(|&flag| (flag as u32 & flags) != 0)
+            .map(|flag| flag.into())
+            .collect();
+
+        return flags.join(" | ");
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+#[repr(C)]
+pub struct CLRHeader {
+    pub cb: u32,
+    pub major_runtime_version: u16,
+    pub minor_runtime_version: u16,
+    pub metadata: ImageDataDirectory,
+    pub flags: u32,
+    pub entry_point_token: u32,
+    pub resources: ImageDataDirectory,
+    pub strong_name_signature: ImageDataDirectory,
+    pub code_manager_table: ImageDataDirectory,
+    pub vtable_fixups: ImageDataDirectory,
+    pub export_address_table_jumps: ImageDataDirectory,
+    pub managed_native_header: ImageDataDirectory,
+}
+
+impl CLRHeader {
+    pub fn from_parser(
+        cursor: &mut PEReader,
+    ) -> Result<CLRHeader, Box<dyn std::error::Error>> {
+        let mut header = CLRHeader::default();
+
+        header.cb = cursor.read_u32()?;
+        header.major_runtime_version = cursor.read_u16()?;
+        header.minor_runtime_version = cursor.read_u16()?;
+        header.metadata = ImageDataDirectory::from_parser(cursor)?;
+        header.flags = cursor.read_u32()?;
+        header.entry_point_token = cursor.read_u32()?;
+        header.resources = ImageDataDirectory::from_parser(cursor)?;
+        header.strong_name_signature = ImageDataDirectory::from_parser(cursor)?;
+        header.code_manager_table = ImageDataDirectory::from_parser(cursor)?;
+        header.vtable_fixups = ImageDataDirectory::from_parser(cursor)?;
+        header.export_address_table_jumps = ImageDataDirectory::from_parser(cursor)?;
+        header.managed_native_header = ImageDataDirectory::from_parser(cursor)?;
+
+        return Ok(header);
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("CLR Runtime Header");
+
+        dump.push_field("cb", format!("{:#x}", self.cb), None);
+        dump.push_field("MajorRuntimeVersion", format!("{}", self.major_runtime_version), None);
+        dump.push_field("MinorRuntimeVersion", format!("{}", self.minor_runtime_version), None);
+        dump.push_field("MetaData", format!("{:#x} ({:#x} bytes)", self.metadata.virtual_address, self.metadata.size), None);
+        dump.push_field("Flags", format!("{:#x} ({})", self.flags, CLRFlags::flags_as_string(self.flags)), None);
+        dump.push_field("EntryPointToken", format!("{:#x}", self.entry_point_token), None);
+        dump.push_field("Resources", format!("{:#x} ({:#x} bytes)", self.resources.virtual_address, self.resources.size), None);
+        dump.push_field("StrongNameSignature", format!("{:#x} ({:#x} bytes)", self.strong_name_signature.virtual_address, self.strong_name_signature.size), None);
+        dump.push_field("CodeManagerTable", format!("{:#x} ({:#x} bytes)", self.code_manager_table.virtual_address, self.code_manager_table.size), None);
+        dump.push_field("VTableFixups", format!("{:#x} ({:#x} bytes)", self.vtable_fixups.virtual_address, self.vtable_fixups.size), None);
+        dump.push_field("ExportAddressTableJumps", format!("{:#x} ({:#x} bytes)", self.export_address_table_jumps.virtual_address, self.export_address_table_jumps.size), None);
+        dump.push_field("ManagedNativeHeader", format!("{:#x} ({:#x} bytes)", self.managed_native_header.virtual_address, self.managed_native_header.size), None);
+
+        return dump;
+    }
+}
+
+/*
+ * Metadata root and streams
+ */
+
+/* "BSJB" */
+pub const METADATA_SIGNATURE: u32 = 0x424a5342;
+
+#[derive(Default, Clone, Debug)]
+pub struct MetadataStreamHeader {
+    pub offset: u32,
+    pub size: u32,
+    pub name: String,
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct MetadataRoot {
+    pub signature: u32,
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub reserved: u32,
+    pub version: String,
+    pub flags: u16,
+    pub streams: Vec<MetadataStreamHeader>,
+}
+
+impl MetadataRoot {
+    pub fn from_reader(reader: &mut PEReader) -> Result<MetadataRoot, Box<dyn std::error::Error>> {
+        let mut root = MetadataRoot::default();
+
+        root.signature = reader.read_u32()?;
+
+        if root.signature != METADATA_SIGNATURE {
+            return Err("Invalid .NET metadata signature".into());
+        }
+
+        root.major_version = reader.read_u16()?;
+        root.minor_version = reader.read_u16()?;
+        root.reserved = reader.read_u32()?;
+
+        let version_length = reader.read_u32()? as usize;
+        let version = reader.read_bytes(version_length)?;
+        let nul = version.iter().position(|&b| b == 0).unwrap_or(version.len());
+        root.version = String::from_utf8_lossy(&version[..nul]).to_string();
+
+        root.flags = reader.read_u16()?;
+
+        let number_of_streams = reader.read_u16()?;
+
+        for _ in 0..number_of_streams {
+            let mut stream = MetadataStreamHeader::default();
+
+            stream.offset = reader.read_u32()?;
+            stream.size = reader.read_u32()?;
+
+            /* Null-terminated name, padded to the next 4-byte boundary */
+            let mut name = Vec::new();
+
+            loop {
+                let chunk = reader.read_n::<4>()?;
+                let nul = chunk.iter().position(|&b| b == 0);
+
+                name.extend_from_slice(&chunk[..nul.unwrap_or(4)]);
+
+                if nul.is_some() {
+                    break;
+                }
+            }
+
+            stream.name = String::from_utf8_lossy(&name).to_string();
+
+            root.streams.push(stream);
+        }
+
+        return Ok(root);
+    }
+}
+
+/*
+ * Metadata tables (#~ stream)
+ */
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, IntoStaticStr)]
+pub enum MetadataTable {
+    Module = 0x00,
+    TypeRef = 0x01,
+    TypeDef = 0x02,
+    FieldPtr = 0x03,
+    Field = 0x04,
+    MethodPtr = 0x05,
+    MethodDef = 0x06,
+    ParamPtr = 0x07,
+    Param = 0x08,
+    InterfaceImpl = 0x09,
+    MemberRef = 0x0a,
+    Constant = 0x0b,
+    CustomAttribute = 0x0c,
+    FieldMarshal = 0x0d,
+    DeclSecurity = 0x0e,
+    ClassLayout = 0x0f,
+    FieldLayout = 0x10,
+    StandAloneSig = 0x11,
+    EventMap = 0x12,
+    EventPtr = 0x13,
+    Event = 0x14,
+    PropertyMap = 0x15,
+    PropertyPtr = 0x16,
+    Property = 0x17,
+    MethodSemantics = 0x18,
+    MethodImpl = 0x19,
+    ModuleRef = 0x1a,
+    TypeSpec = 0x1b,
+    ImplMap = 0x1c,
+    FieldRVA = 0x1d,
+    EncLog = 0x1e,
+    EncMap = 0x1f,
+    Assembly = 0x20,
+    AssemblyProcessor = 0x21,
+    AssemblyOS = 0x22,
+    AssemblyRef = 0x23,
+    AssemblyRefProcessor = 0x24,
+    AssemblyRefOS = 0x25,
+    File = 0x26,
+    ExportedType = 0x27,
+    ManifestResource = 0x28,
+    NestedClass = 0x29,
+    GenericParam = 0x2a,
+    MethodSpec = 0x2b,
+    GenericParamConstraint = 0x2c,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodedIndex {
+    TypeDefOrRef,
+    HasConstant,
+    HasCustomAttribute,
+    HasFieldMarshal,
+    HasDeclSecurity,
+    MemberRefParent,
+    HasSemantics,
+    MethodDefOrRef,
+    MemberForwarded,
+    Implementation,
+    CustomAttributeType,
+    ResolutionScope,
+    TypeOrMethodDef,
+}
+
+impl CodedIndex {
+    /// Tables referenced by the coded index, in tag order (None for unused tags)
+    pub fn tables(&self) -> &'static [Option<MetadataTable>] {
+        use MetadataTable as T;
+
+        match self {
+            CodedIndex::TypeDefOrRef => &[Some(T::TypeDef), Some(T::TypeRef), Some(T::TypeSpec)],
+            CodedIndex::HasConstant => &[Some(T::Field), Some(T::Param), Some(T::Property)],
+            CodedIndex::HasCustomAttribute => &[
+                Some(T::MethodDef), Some(T::Field), Some(T::TypeRef), Some(T::TypeDef),
+                Some(T::Param), Some(T::InterfaceImpl), Some(T::MemberRef), Some(T::Module),
+                Some(T::DeclSecurity), Some(T::Property), Some(T::Event), Some(T::StandAloneSig),
+                Some(T::ModuleRef), Some(T::TypeSpec), Some(T::Assembly), Some(T::AssemblyRef),
+                Some(T::File), Some(T::ExportedType), Some(T::ManifestResource), Some(T::GenericParam),
+                Some(T::GenericParamConstraint), Some(T::MethodSpec),
+            ],
+            CodedIndex::HasFieldMarshal => &[Some(T::Field), Some(T::Param)],
+            CodedIndex::HasDeclSecurity => &[Some(T::TypeDef), Some(T::MethodDef), Some(T::Assembly)],
+            CodedIndex::MemberRefParent => &[
+                Some(T::TypeDef), Some(T::TypeRef), Some(T::ModuleRef), Some(T::MethodDef), Some(T::TypeSpec),
+            ],
+            CodedIndex::HasSemantics => &[Some(T::Event), Some(T::Property)],
+            CodedIndex::MethodDefOrRef => &[Some(T::MethodDef), Some(T::MemberRef)],
+            CodedIndex::MemberForwarded => &[Some(T::Field), Some(T::MethodDef)],
+            CodedIndex::Implementation => &[Some(T::File), Some(T::AssemblyRef), Some(T::ExportedType)],
+            CodedIndex::CustomAttributeType => &[None, None, Some(T::MethodDef), Some(T::MemberRef), None],
+            CodedIndex::ResolutionScope => &[Some(T::Module), Some(T::ModuleRef), Some(T::AssemblyRef), Some(T::TypeRef)],
+            CodedIndex::TypeOrMethodDef => &[Some(T::TypeDef), Some(T::MethodDef)],
+        }
+    }
+
+    pub fn tag_bits(&self) -> u32 {
+        return usize::BITS - (self.tables().len() - 1).leading_zeros();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    U16,
+    U32,
+    String,
+    Guid,
+    Blob,
+    Table(MetadataTable),
+    Coded(CodedIndex),
+}
+
+impl MetadataTable {
+    pub fn from_index(index: usize) -> Option<MetadataTable> {
+        return MetadataTable::iter().find(|t| *t as usize == index);
+    }
+
+    #[rustfmt::skip]
+    pub fn schema(&self) -> &'static [Column] {
+        use Column::*;
+        use CodedIndex as C;
+        use MetadataTable as T;
+
+        match self {
+            T::Module => &[U16, String, Guid, Guid, Guid],
+            T::TypeRef => &[Coded(C::ResolutionScope), String, String],
+            T::TypeDef => &[U32, String, String, Coded(C::TypeDefOrRef), Table(T::Field), Table(T::MethodDef)],
+            T::FieldPtr => &[Table(T::Field)],
+            T::Field => &[U16, String, Blob],
+            T::MethodPtr => &[Table(T::MethodDef)],
+            T::MethodDef => &[U32, U16, U16, String, Blob, Table(T::Param)],
+            T::ParamPtr => &[Table(T::Param)],
+            T::Param => &[U16, U16, String],
+            T::InterfaceImpl => &[Table(T::TypeDef), Coded(C::TypeDefOrRef)],
+            T::MemberRef => &[Coded(C::MemberRefParent), String, Blob],
+            T::Constant => &[U16, Coded(C::HasConstant), Blob],
+            T::CustomAttribute => &[Coded(C::HasCustomAttribute), Coded(C::CustomAttributeType), Blob],
+            T::FieldMarshal => &[Coded(C::HasFieldMarshal), Blob],
+            T::DeclSecurity => &[U16, Coded(C::HasDeclSecurity), Blob],
+            T::ClassLayout => &[U16, U32, Table(T::TypeDef)],
+            T::FieldLayout => &[U32, Table(T::Field)],
+            T::StandAloneSig => &[Blob],
+            T::EventMap => &[Table(T::TypeDef), Table(T::Event)],
+            T::EventPtr => &[Table(T::Event)],
+            T::Event => &[U16, String, Coded(C::TypeDefOrRef)],
+            T::PropertyMap => &[Table(T::TypeDef), Table(T::Property)],
+            T::PropertyPtr => &[Table(T::Property)],
+            T::Property => &[U16, String, Blob],
+            T::MethodSemantics => &[U16, Table(T::MethodDef), Coded(C::HasSemantics)],
+            T::MethodImpl => &[Table(T::TypeDef), Coded(C::MethodDefOrRef), Coded(C::MethodDefOrRef)],
+            T::ModuleRef => &[String],
+            T::TypeSpec => &[Blob],
+            T::ImplMap => &[U16, Coded(C::MemberForwarded), String, Table(T::ModuleRef)],
+            T::FieldRVA => &[U32, Table(T::Field)],
+            T::EncLog => &[U32, U32],
+            T::EncMap => &[U32],
+            T::Assembly => &[U32, U16, U16, U16, U16, U32, Blob, String, String],
+            T::AssemblyProcessor => &[U32],
+            T::AssemblyOS => &[U32, U32, U32],
+            T::AssemblyRef => &[U16, U16, U16, U16, U32, Blob, String, String, Blob],
+            T::AssemblyRefProcessor => &[U32, Table(T::AssemblyRef)],
+            T::AssemblyRefOS => &[U32, U32, U32, Table(T::AssemblyRef)],
+            T::File => &[U32, String, Blob],
+            T::ExportedType => &[U32, U32, String, String, Coded(C::Implementation)],
+            T::ManifestResource => &[U32, U32, String, Coded(C::Implementation)],
+            T::NestedClass => &[Table(T::TypeDef), Table(T::TypeDef)],
+            T::GenericParam => &[U16, U16, Coded(C::TypeOrMethodDef), String],
+            T::MethodSpec => &[Coded(C::MethodDefOrRef), Blob],
+            T::GenericParamConstraint => &[Table(T::GenericParam), Coded(C::TypeDefOrRef)],
+        }
+    }
+}
+
+const MAX_METADATA_TABLES: usize = 64;
+
+#[derive(Clone, Debug)]
+pub struct MetadataTables {
+    pub major_version: u8,
+    pub minor_version: u8,
+    pub heap_sizes: u8,
+    pub valid: u64,
+    pub sorted: u64,
+    pub row_counts: [u32; MAX_METADATA_TABLES],
+    table_offsets: [usize; MAX_METADATA_TABLES],
+    row_sizes: [usize; MAX_METADATA_TABLES],
+    data: Vec<u8>,
+}
+
+impl Default for MetadataTables {
+    fn default() -> MetadataTables {
+        return MetadataTables {
+            major_version: 0,
+            minor_version: 0,
+            heap_sizes: 0,
+            valid: 0,
+            sorted: 0,
+            row_counts: [0; MAX_METADATA_TABLES],
+            table_offsets: [0; MAX_METADATA_TABLES],
+            row_sizes: [0; MAX_METADATA_TABLES],
+            data: Vec::new(),
+        };
+    }
+}
+
+impl MetadataTables {
+    pub fn from_stream(data: &[u8]) -> Result<MetadataTables, Box<dyn std::error::Error>> {
+        let mut tables = MetadataTables::default();
+        let mut reader = PEReader::from_bytes(data);
+
+        let _reserved = reader.read_u32()?;
+        tables.major_version = reader.read_u8()?;
+        tables.minor_version = reader.read_u8()?;
+        tables.heap_sizes = reader.read_u8()?;
+        let _reserved = reader.read_u8()?;
+        tables.valid = reader.read_u64()?;
+        tables.sorted = reader.read_u64()?;
+
+        for i in 0..MAX_METADATA_TABLES {
+            if (tables.valid >> i) & 1 != 0 {
+                tables.row_counts[i] = reader.read_u32()?;
+            }
+        }
+
+        /* Uncompressed (#-) streams can carry extra data after the row counts */
+        if (tables.heap_sizes & 0x40) != 0 {
+            reader.read_u32()?;
+        }
+
+        let mut offset = reader.position() as usize;
+
+        for i in 0..MAX_METADATA_TABLES {
+            if tables.row_counts[i] == 0 {
+                continue;
+            }
+
+            /* Tables we don't know the schema of make the following ones unreachable */
+            let Some(table) = MetadataTable::from_index(i) else {
+                break;
+            };
+
+            let row_size: usize = table.schema().iter().map(|c| tables.column_size(*c)).sum();
+
+            tables.table_offsets[i] = offset;
+            tables.row_sizes[i] = row_size;
+
+            offset += row_size * tables.row_counts[i] as usize;
+        }
+
+        tables.data = data.to_vec();
+
+        return Ok(tables);
+    }
+
+    fn column_size(&self, column: Column) -> usize {
+        match column {
+            Column::U16 => 2,
+            Column::U32 => 4,
+            Column::String => if (self.heap_sizes & 0x1) != 0 { 4 } else { 2 },
+            Column::Guid => if (self.heap_sizes & 0x2) != 0 { 4 } else { 2 },
+            Column::Blob => if (self.heap_sizes & 0x4) != 0 { 4 } else { 2 },
+            Column::Table(table) => if self.row_count(table) > 0xffff { 4 } else { 2 },
+            Column::Coded(coded) => {
+                let max_rows = coded
+                    .tables()
+                    .iter()
+                    .flatten()
+                    .map(|t| self.row_count(*t))
+                    .max()
+                    .unwrap_or(0);
+
+                if max_rows < (1 << (16 - coded.tag_bits())) { 2 } else { 4 }
+            },
+        }
+    }
+
+    pub fn row_count(&self, table: MetadataTable) -> u32 {
+        return self.row_counts[table as usize];
+    }
+
+    /// Column values of the given row (1-based, as metadata tokens are)
+    pub fn row(&self, table: MetadataTable, index: u32) -> Option<Vec<u32>> {
+        if index == 0 || index > self.row_count(table) || self.row_sizes[table as usize] == 0 {
+            return None;
+        }
+
+        let start = self.table_offsets[table as usize] + (index as usize - 1) * self.row_sizes[table as usize];
+
+        let mut reader = PEReader::from_bytes(self.data.get(start..start + self.row_sizes[table as usize])?);
+        let mut values = Vec::with_capacity(table.schema().len());
+
+        for column in table.schema().iter() {
+            let value = match self.column_size(*column) {
+                2 => reader.read_u16().ok()? as u32,
+                _ => reader.read_u32().ok()?,
+            };
+
+            values.push(value);
+        }
+
+        return Some(values);
+    }
+
+    /// Splits a coded index into the referenced table and row
+    pub fn decode_coded_index(coded: CodedIndex, value: u32) -> (Option<MetadataTable>, u32) {
+        let bits = coded.tag_bits();
+        let tag = (value & ((1 << bits) - 1)) as usize;
+
+        return (coded.tables().get(tag).copied().flatten(), value >> bits);
+    }
+}
+
+/*
+ * Heaps
+ */
+
+/// Reads a compressed unsigned integer (ECMA-335 II.23.2), returning the value and its encoded length
+pub fn read_compressed_u32(data: &[u8]) -> Option<(u32, usize)> {
+    let first = *data.first()? as u32;
+
+    if (first & 0x80) == 0 {
+        return Some((first, 1));
+    }
+
+    if (first & 0xc0) == 0x80 {
+        return Some((((first & 0x3f) << 8) | *data.get(1)? as u32, 2));
+    }
+
+    if (first & 0xe0) == 0xc0 {
+        let bytes = data.get(1..4)?;
+        return Some((
+            ((first & 0x1f) << 24) | ((bytes[0] as u32) << 16) | ((bytes[1] as u32) << 8) | bytes[2] as u32,
+            4,
+        ));
+    }
+
+    return None;
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct MetadataHeaps {
+    pub strings: Vec<u8>,
+    pub user_strings: Vec<u8>,
+    pub guids: Vec<u8>,
+    pub blobs: Vec<u8>,
+}
+
+impl MetadataHeaps {
+    pub fn string(&self, offset: u32) -> String {
+        let Some(data) = self.strings.get(offset as usize..) else {
+            return String::new();
+        };
+
+        let nul = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+
+        return String::from_utf8_lossy(&data[..nul]).to_string();
+    }
+
+    pub fn blob(&self, offset: u32) -> &[u8] {
+        let Some(data) = self.blobs.get(offset as usize..) else {
+            return &[];
+        };
+
+        return match read_compressed_u32(data) {
+            Some((length, header)) => data.get(header..header + length as usize).unwrap_or(&[]),
+            None => &[],
+        };
+    }
+
+    /// Entry of the #GUID heap, indexed from 1, in registry format
+    pub fn guid(&self, index: u32) -> Option<String> {
+        let start = (index as usize).checked_sub(1)? * 16;
+        let g = self.guids.get(start..start + 16)?;
+
+        return Some(format!(
+            "{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{}",
+            u32::from_le_bytes([g[0], g[1], g[2], g[3]]),
+            u16::from_le_bytes([g[4], g[5]]),
+            u16::from_le_bytes([g[6], g[7]]),
+            g[8],
+            g[9],
+            g[10..].iter().map(|b| format!("{:02X}", b)).collect::<String>()
+        ));
+    }
+
+    /// Entry of the #US heap, UTF-16 followed by a byte telling whether it has special characters
+    pub fn user_string(&self, offset: u32) -> Option<String> {
+        let data = self.user_strings.get(offset as usize..)?;
+        let (length, header) = read_compressed_u32(data)?;
+        let bytes = data.get(header..header + (length as usize & !1))?;
+
+        let units: Vec<u16> = bytes.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+
+        return Some(String::from_utf16_lossy(&units));
+    }
+}
+
+/*
+ * Signatures (blobs describing the types of the fields, methods and locals)
+ */
+
+/* Calling convention flags of the first byte of a signature */
+pub const SIGNATURE_HAS_THIS: u8 = 0x20;
+pub const SIGNATURE_GENERIC: u8 = 0x10;
+pub const SIGNATURE_FIELD: u8 = 0x06;
+pub const SIGNATURE_LOCAL_VARIABLES: u8 = 0x07;
+pub const SIGNATURE_GENERIC_INSTANCE: u8 = 0x0a;
+
+#[derive(Default, Clone, Debug)]
+pub struct MethodSignature {
+    pub has_this: bool,
+    pub generic_parameters: u32,
+    pub return_type: String,
+    pub parameters: Vec<String>,
+}
+
+impl MethodSignature {
+    /// ilasm-like rendering of a method: "instance void Namespace.Type::Name(int32, string)"
+    pub fn format(&self, name: &str) -> String {
+        return format!(
+            "{}{} {}({})",
+            if self.has_this { "instance " } else { "" },
+            self.return_type,
+            name,
+            self.parameters.join(", ")
+        );
+    }
+}
+
+/// Decodes the types of a signature blob (ECMA-335 II.23.2), the type names are resolved from the metadata
+pub struct SignatureReader<'a> {
+    dotnet: &'a DotNetData,
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> SignatureReader<'a> {
+    pub fn new(dotnet: &'a DotNetData, data: &'a [u8]) -> SignatureReader<'a> {
+        return SignatureReader { dotnet, data, position: 0 };
+    }
+
+    pub fn read_u8(&mut self) -> Option<u8> {
+        let value = *self.data.get(self.position)?;
+        self.position += 1;
+
+        return Some(value);
+    }
+
+    pub fn read_compressed(&mut self) -> Option<u32> {
+        let (value, length) = read_compressed_u32(self.data.get(self.position..)?)?;
+        self.position += length;
+
+        return Some(value);
+    }
+
+    /// Compressed signed integers keep their sign in the lowest bit, rotated by the width of the encoding
+    fn read_compressed_signed(&mut self) -> Option<i32> {
+        let (value, length) = read_compressed_u32(self.data.get(self.position..)?)?;
+        self.position += length;
+
+        let bits = match length {
+            1 => 7,
+            2 => 14,
+            _ => 29,
+        };
+
+        let magnitude = (value >> 1) as i32;
+
+        return Some(if value & 1 == 0 { magnitude } else { magnitude - (1 << (bits - 1)) });
+    }
+
+    fn read_type_def_or_ref(&mut self) -> Option<String> {
+        let coded = self.read_compressed()?;
+
+        return Some(self.dotnet.type_def_or_ref_name(coded));
+    }
+
+    pub fn read_type(&mut self) -> Option<String> {
+        let element = self.read_u8()?;
+
+        let name = match element {
+            0x01 => String::from("void"),
+            0x02 => String::from("bool"),
+            0x03 => String::from("char"),
+            0x04 => String::from("int8"),
+            0x05 => String::from("uint8"),
+            0x06 => String::from("int16"),
+            0x07 => String::from("uint16"),
+            0x08 => String::from("int32"),
+            0x09 => String::from("uint32"),
+            0x0a => String::from("int64"),
+            0x0b => String::from("uint64"),
+            0x0c => String::from("float32"),
+            0x0d => String::from("float64"),
+            0x0e => String::from("string"),
+            0x0f => format!("{}*", self.read_type()?),
+            0x10 => format!("{}&", self.read_type()?),
+            0x11 => format!("valuetype {}", self.read_type_def_or_ref()?),
+            0x12 => format!("class {}", self.read_type_def_or_ref()?),
+            0x13 => format!("!{}", self.read_compressed()?),
+            0x14 => {
+                let element_type = self.read_type()?;
+                let rank = self.read_compressed()?;
+
+                let sizes: Vec<u32> = (0..self.read_compressed()?).map(|_| self.read_compressed()).collect::<Option<_>>()?;
+                let lower_bounds: Vec<i32> = (0..self.read_compressed()?).map(|_| self.read_compressed_signed()).collect::<Option<_>>()?;
+
+                let dimensions: Vec<String> = (0..rank as usize)
+                    .map(|i| match (lower_bounds.get(i), sizes.get(i)) {
+                        (Some(low), Some(size)) => format!("{}...{}", low, *low as i64 + *size as i64 - 1),
+                        (Some(low), None) => format!("{}...", low),
+                        (None, Some(size)) => format!("{}", size),
+                        (None, None) => String::new(),
+                    })
+                    .collect();
+
+                format!("{}[{}]", element_type, dimensions.join(","))
+            }
+            0x15 => {
+                let generic_type = self.read_type()?;
+                let arguments: Vec<String> = (0..self.read_compressed()?).map(|_| self.read_type()).collect::<Option<_>>()?;
+
+                format!("{}<{}>", generic_type, arguments.join(", "))
+            }
+            0x16 => String::from("typedref"),
+            0x18 => String::from("native int"),
+            0x19 => String::from("native uint"),
+            0x1b => {
+                let signature = self.read_method_signature()?;
+                format!("method {} *({})", signature.return_type, signature.parameters.join(", "))
+            }
+            0x1c => String::from("object"),
+            0x1d => format!("{}[]", self.read_type()?),
+            0x1e => format!("!!{}", self.read_compressed()?),
+            0x1f => {
+                let modifier = self.read_type_def_or_ref()?;
+                format!("{} modreq({})", self.read_type()?, modifier)
+            }
+            0x20 => {
+                let modifier = self.read_type_def_or_ref()?;
+                format!("{} modopt({})", self.read_type()?, modifier)
+            }
+            0x41 => format!("..., {}", self.read_type()?),
+            0x45 => format!("{} pinned", self.read_type()?),
+            _ => return None,
+        };
+
+        return Some(name);
+    }
+
+    pub fn read_method_signature(&mut self) -> Option<MethodSignature> {
+        let mut signature = MethodSignature::default();
+
+        let flags = self.read_u8()?;
+
+        signature.has_this = flags & SIGNATURE_HAS_THIS != 0;
+
+        if flags & SIGNATURE_GENERIC != 0 {
+            signature.generic_parameters = self.read_compressed()?;
+        }
+
+        let count = self.read_compressed()?;
+
+        signature.return_type = self.read_type()?;
+        signature.parameters = (0..count).map(|_| self.read_type()).collect::<Option<_>>()?;
+
+        return Some(signature);
+    }
+
+    /// Types of a LocalVarSig or of a MethodSpec instantiation, after their leading byte
+    pub fn read_type_list(&mut self) -> Option<Vec<String>> {
+        let count = self.read_compressed()?;
+
+        return (0..count).map(|_| self.read_type()).collect();
+    }
+}
+
+/*
+ * Assembly identity
+ */
+
+/* AssemblyFlags.PublicKey: the blob holds the full public key instead of its token */
+const ASSEMBLY_FLAGS_PUBLIC_KEY: u32 = 0x0001;
+
+/// The public key token is the last 8 bytes of the SHA-1 of the public key, reversed
+pub fn public_key_token(public_key: &[u8]) -> Vec<u8> {
+    let hash = Sha1::digest(public_key);
+
+    return hash[hash.len() - 8..].iter().rev().copied().collect();
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct AssemblyIdentity {
+    pub name: String,
+    pub version: [u16; 4],
+    pub culture: String,
+    pub flags: u32,
+    pub hash_algorithm: Option<u32>,
+    pub public_key_token: Option<Vec<u8>>,
+}
+
+impl AssemblyIdentity {
+    fn from_blob(name: String, version: [u16; 4], culture: String, flags: u32, key: &[u8]) -> AssemblyIdentity {
+        let public_key_token = match key.len() {
+            0 => None,
+            _ if (flags & ASSEMBLY_FLAGS_PUBLIC_KEY) != 0 || key.len() != 8 => Some(public_key_token(key)),
+            _ => Some(key.to_vec()),
+        };
+
+        return AssemblyIdentity {
+            name,
+            version,
+            culture,
+            flags,
+            hash_algorithm: None,
+            public_key_token,
+        };
+    }
+
+    pub fn version_as_string(&self) -> String {
+        return format!("{}.{}.{}.{}", self.version[0], self.version[1], self.version[2], self.version[3]);
+    }
+
+    pub fn culture_as_string(&self) -> &str {
+        return if self.culture.is_empty() { "neutral" } else { self.culture.as_str() };
+    }
+
+    pub fn public_key_token_as_string(&self) -> String {
+        match self.public_key_token {
+            Some(ref token) => return token.iter().map(|b| format!("{:02x}", b)).collect(),
+            None => return String::from("null"),
+        }
+    }
+
+    /// Display name, as used by the runtime to bind assemblies
+    pub fn full_name(&self) -> String {
+        return format!(
+            "{}, Version={}, Culture={}, PublicKeyToken={}",
+            self.name,
+            self.version_as_string(),
+            self.culture_as_string(),
+            self.public_key_token_as_string()
+        );
+    }
+}
+
+/*
+ * .NET data of a managed PE
+ */
+
+#[derive(Default, Clone, Debug)]
+pub struct DotNetData {
+    pub clr_header: CLRHeader,
+    pub metadata_root: MetadataRoot,
+    pub tables: MetadataTables,
+    pub heaps: MetadataHeaps,
+    /// Parts of the metadata that could not be read, they are left empty
+    pub warnings: Vec<String>,
+}
+
+impl DotNetData {
+    /// Damaged metadata never fails the parsing of the PE, the streams that cannot be read are skipped with a warning
+    pub fn from_parser(
+        cursor: &mut PEReader,
+        pe: &PE,
+    ) -> DotNetData {
+        let mut dotnet = DotNetData::default();
+
+        match CLRHeader::from_parser(cursor) {
+            Ok(clr_header) => dotnet.clr_header = clr_header,
+            Err(e) => {
+                dotnet.warnings.push(format!("CLR header cannot be read: {}", e));
+                return dotnet;
+            }
+        }
+
+        if let Err(e) = dotnet.parse_metadata(cursor, pe) {
+            dotnet.warnings.push(format!("Metadata cannot be read: {}", e));
+        }
+
+        return dotnet;
+    }
+
+    fn parse_metadata(
+        &mut self,
+        cursor: &mut PEReader,
+        pe: &PE,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (metadata_rva, metadata_size) = (self.clr_header.metadata.virtual_address, self.clr_header.metadata.size);
+
+        let metadata_offset = pe
+            .convert_rva_to_file_offset(metadata_rva)
+            .ok_or_else(|| format!("RVA {:#x} is outside of the sections", metadata_rva))?;
+
+        /* The size comes from the file, only the bytes left in it are allocated */
+        let available = cursor.length().saturating_sub(metadata_offset);
+
+        if metadata_size as u64 > available {
+            self.warnings.push(format!("Metadata size {:#x} is past the end of the file, {:#x} bytes read", metadata_size, available));
+        }
+
+        let mut metadata = vec![0; (metadata_size as u64).min(available) as usize];
+
+        cursor.set_position(metadata_offset)?;
+        cursor.read_exact(&mut metadata)?;
+
+        let mut reader = PEReader::from_bytes(&metadata);
+        self.metadata_root = MetadataRoot::from_reader(&mut reader)?;
+
+        for stream in self.metadata_root.streams.iter() {
+            let start = stream.offset as usize;
+
+            let Some(data) = metadata.get(start..start.saturating_add(stream.size as usize)) else {
+                self.warnings.push(format!("Stream {} (offset {:#x}, size {:#x}) is past the end of the metadata, skipped", stream.name, stream.offset, stream.size));
+                continue;
+            };
+
+            match stream.name.as_str() {
+                "#~" | "#-" => match MetadataTables::from_stream(data) {
+                    Ok(tables) => self.tables = tables,
+                    Err(e) => self.warnings.push(format!("Stream {} cannot be read: {}, skipped", stream.name, e)),
+                },
+                "#Strings" => self.heaps.strings = data.to_vec(),
+                "#US" => self.heaps.user_strings = data.to_vec(),
+                "#GUID" => self.heaps.guids = data.to_vec(),
+                "#Blob" => self.heaps.blobs = data.to_vec(),
+                _ => {},
+            }
+        }
+
+        return Ok(());
+    }
+
+    pub fn assembly(&self) -> Option<AssemblyIdentity> {
+        let row = self.tables.row(MetadataTable::Assembly, 1)?;
+
+        let mut assembly = AssemblyIdentity::from_blob(
+            self.heaps.string(row[7]),
+            [row[1] as u16, row[2] as u16, row[3] as u16, row[4] as u16],
+            self.heaps.string(row[8]),
+            row[5],
+            self.heaps.blob(row[6]),
+        );
+
+        assembly.hash_algorithm = Some(row[0]);
+
+        return Some(assembly);
+    }
+
+    pub fn referenced_assemblies(&self) -> Vec<AssemblyIdentity> {
+        return (1..=self.tables.row_count(MetadataTable::AssemblyRef))
+            .filter_map(|i| self.tables.row(MetadataTable::AssemblyRef, i))
+            .map(|row| {
+                AssemblyIdentity::from_blob(
+                    self.heaps.string(row[6]),
+                    [row[0] as u16, row[1] as u16, row[2] as u16, row[3] as u16],
+                    self.heaps.string(row[7]),
+                    row[4],
+                    self.heaps.blob(row[5]),
+                )
+            })
+            .collect();
+    }
+
+    /// Namespace qualified name of a type, nested types are separated from their enclosing type with a '/'
+    pub fn type_def_name(&self, index: u32) -> String {
+        let Some(row) = self.tables.row(MetadataTable::TypeDef, index) else {
+            return format!("TypeDef#{}", index);
+        };
+
+        let name = self.heaps.string(row[1]);
+        let namespace = self.heaps.string(row[2]);
+
+        let enclosing = (1..=self.tables.row_count(MetadataTable::NestedClass))
+            .filter_map(|i| self.tables.row(MetadataTable::NestedClass, i))
+            .find(|nested| nested[0] == index && nested[1] != index)
+            .map(|nested| nested[1]);
+
+        match (enclosing, namespace.is_empty()) {
+            (Some(enclosing), _) => return format!("{}/{}", self.type_def_name(enclosing), name),
+            (None, true) => return name,
+            (None, false) => return format!("{}.{}", namespace, name),
+        }
+    }
+
+    /// Name of a referenced type, prefixed with the assembly it comes from as ildasm does
+    pub fn type_ref_name(&self, index: u32) -> String {
+        let Some(row) = self.tables.row(MetadataTable::TypeRef, index) else {
+            return format!("TypeRef#{}", index);
+        };
+
+        let name = self.heaps.string(row[1]);
+        let namespace = self.heaps.string(row[2]);
+        let qualified_name = if namespace.is_empty() { name.clone() } else { format!("{}.{}", namespace, name) };
+
+        let (scope, scope_index) = MetadataTables::decode_coded_index(CodedIndex::ResolutionScope, row[0]);
+
+        match scope {
+            Some(MetadataTable::TypeRef) if scope_index != index => return format!("{}/{}", self.type_ref_name(scope_index), name),
+            Some(MetadataTable::AssemblyRef) => {
+                let assembly = self.tables.row(MetadataTable::AssemblyRef, scope_index).map(|r| self.heaps.string(r[6])).unwrap_or_default();
+                return format!("[{}]{}", assembly, qualified_name);
+            }
+            Some(MetadataTable::ModuleRef) => {
+                let module = self.tables.row(MetadataTable::ModuleRef, scope_index).map(|r| self.heaps.string(r[0])).unwrap_or_default();
+                return format!("[.module {}]{}", module, qualified_name);
+            }
+            _ => return qualified_name,
+        }
+    }
+
+    pub fn type_spec_name(&self, index: u32) -> String {
+        let Some(row) = self.tables.row(MetadataTable::TypeSpec, index) else {
+            return format!("TypeSpec#{}", index);
+        };
+
+        return SignatureReader::new(self, self.heaps.blob(row[0])).read_type().unwrap_or_else(|| format!("TypeSpec#{}", index));
+    }
+
+    /// TypeDefOrRef coded index, as found in the tables and in the signatures
+    pub fn type_def_or_ref_name(&self, coded: u32) -> String {
+        let (table, index) = MetadataTables::decode_coded_index(CodedIndex::TypeDefOrRef, coded);
+
+        match table {
+            Some(MetadataTable::TypeDef) => return self.type_def_name(index),
+            Some(MetadataTable::TypeRef) => return self.type_ref_name(index),
+            Some(MetadataTable::TypeSpec) => return self.type_spec_name(index),
+            _ => return format!("{:#x}", coded),
+        }
+    }
+
+    /// TypeDef owning the method, the methods of a type run from its MethodList to the MethodList of the next type
+    pub fn method_def_owner(&self, index: u32) -> Option<u32> {
+        return (1..=self.tables.row_count(MetadataTable::TypeDef))
+            .filter_map(|i| self.tables.row(MetadataTable::TypeDef, i).map(|row| (i, row[5])))
+            .take_while(|(_, method_list)| *method_list <= index)
+            .last()
+            .map(|(i, _)| i);
+    }
+
+    /// Same as method_def_owner for the fields and the FieldList column
+    pub fn field_owner(&self, index: u32) -> Option<u32> {
+        return (1..=self.tables.row_count(MetadataTable::TypeDef))
+            .filter_map(|i| self.tables.row(MetadataTable::TypeDef, i).map(|row| (i, row[4])))
+            .take_while(|(_, field_list)| *field_list <= index)
+            .last()
+            .map(|(i, _)| i);
+    }
+
+    /// "Namespace.Type::Method"
+    pub fn method_def_name(&self, index: u32) -> String {
+        let name = self.tables.row(MetadataTable::MethodDef, index).map(|r| self.heaps.string(r[3])).unwrap_or_else(|| format!("MethodDef#{}", index));
+
+        match self.method_def_owner(index) {
+            Some(owner) => return format!("{}::{}", self.type_def_name(owner), name),
+            None => return name,
+        }
+    }
+
+    pub fn method_def_signature(&self, index: u32) -> Option<MethodSignature> {
+        let row = self.tables.row(MetadataTable::MethodDef, index)?;
+
+        return SignatureReader::new(self, self.heaps.blob(row[4])).read_method_signature();
+    }
+
+    fn member_ref_parent_name(&self, parent: u32) -> String {
+        let (table, index) = MetadataTables::decode_coded_index(CodedIndex::MemberRefParent, parent);
+
+        match table {
+            Some(MetadataTable::TypeDef) => return self.type_def_name(index),
+            Some(MetadataTable::TypeRef) => return self.type_ref_name(index),
+            Some(MetadataTable::TypeSpec) => return self.type_spec_name(index),
+            Some(MetadataTable::ModuleRef) => return format!("[.module {}]", self.tables.row(MetadataTable::ModuleRef, index).map(|r| self.heaps.string(r[0])).unwrap_or_default()),
+            Some(MetadataTable::MethodDef) => return self.method_def_name(index),
+            _ => return format!("{:#x}", parent),
+        }
+    }
+
+    /// Description of what a metadata token references, as shown by ildasm
+    pub fn token_description(&self, token: u32) -> String {
+        let index = token & 0x00ffffff;
+
+        let description = match MetadataTable::from_index((token >> 24) as usize) {
+            Some(MetadataTable::TypeDef) => Some(self.type_def_name(index)),
+            Some(MetadataTable::TypeRef) => Some(self.type_ref_name(index)),
+            Some(MetadataTable::TypeSpec) => Some(self.type_spec_name(index)),
+            Some(MetadataTable::MethodDef) => {
+                let name = self.method_def_name(index);
+                Some(self.method_def_signature(index).map(|s| s.format(&name)).unwrap_or(name))
+            }
+            Some(MetadataTable::Field) => self.tables.row(MetadataTable::Field, index).map(|row| {
+                let name = self.heaps.string(row[1]);
+                let name = match self.field_owner(index) {
+                    Some(owner) => format!("{}::{}", self.type_def_name(owner), name),
+                    None => name,
+                };
+
+                let mut reader = SignatureReader::new(self, self.heaps.blob(row[2]));
+                let field_type = reader.read_u8().filter(|b| *b == SIGNATURE_FIELD).and_then(|_| reader.read_type());
+
+                format!("{} {}", field_type.unwrap_or_default(), name)
+            }),
+            Some(MetadataTable::MemberRef) => self.tables.row(MetadataTable::MemberRef, index).map(|row| {
+                let name = format!("{}::{}", self.member_ref_parent_name(row[0]), self.heaps.string(row[1]));
+                let blob = self.heaps.blob(row[2]);
+
+                let mut reader = SignatureReader::new(self, blob);
+
+                match blob.first() {
+                    Some(&SIGNATURE_FIELD) => {
+                        reader.read_u8();
+                        format!("{} {}", reader.read_type().unwrap_or_default(), name)
+                    }
+                    _ => reader.read_method_signature().map(|s| s.format(&name)).unwrap_or(name),
+                }
+            }),
+            Some(MetadataTable::MethodSpec) => self.tables.row(MetadataTable::MethodSpec, index).map(|row| {
+                let (table, method_index) = MetadataTables::decode_coded_index(CodedIndex::MethodDefOrRef, row[0]);
+                let method = match table {
+                    Some(table) => self.token_description(((table as u32) << 24) | method_index),
+                    None => format!("{:#x}", row[0]),
+                };
+
+                let mut reader = SignatureReader::new(self, self.heaps.blob(row[1]));
+                let arguments = reader.read_u8().filter(|b| *b == SIGNATURE_GENERIC_INSTANCE).and_then(|_| reader.read_type_list()).unwrap_or_default();
+
+                /* The instantiation goes between the name of the method and its parameters */
+                match method.rfind('(') {
+                    Some(position) => format!("{}<{}>{}", &method[..position], arguments.join(", "), &method[position..]),
+                    None => format!("{}<{}>", method, arguments.join(", ")),
+                }
+            }),
+            Some(MetadataTable::StandAloneSig) => self.tables.row(MetadataTable::StandAloneSig, index).and_then(|row| {
+                let signature = SignatureReader::new(self, self.heaps.blob(row[0])).read_method_signature()?;
+                Some(format!("{}({})", signature.return_type, signature.parameters.join(", ")))
+            }),
+            _ => None,
+        };
+
+        return description.unwrap_or_else(|| format!("{:#010x}", token));
+    }
+
+    /// Name of the type a custom attribute constructor belongs to
+    fn custom_attribute_type_name(&self, constructor: u32) -> Option<String> {
+        let (table, index) = MetadataTables::decode_coded_index(CodedIndex::CustomAttributeType, constructor);
+
+        let type_row = match table? {
+            MetadataTable::MemberRef => {
+                let member = self.tables.row(MetadataTable::MemberRef, index)?;
+                let (parent, parent_index) = MetadataTables::decode_coded_index(CodedIndex::MemberRefParent, member[0]);
+
+                match parent? {
+                    MetadataTable::TypeRef => self.tables.row(MetadataTable::TypeRef, parent_index)?,
+                    _ => return None,
+                }
+            },
+            _ => return None,
+        };
+
+        return Some(self.heaps.string(type_row[1]));
+    }
+
+    /// Value of the assembly level TargetFrameworkAttribute, e.g. ".NETFramework,Version=v4.7.2"
+    pub fn target_framework(&self) -> Option<String> {
+        for i in 1..=self.tables.row_count(MetadataTable::CustomAttribute) {
+            let row = self.tables.row(MetadataTable::CustomAttribute, i)?;
+
+            let (parent, _) = MetadataTables::decode_coded_index(CodedIndex::HasCustomAttribute, row[0]);
+
+            if parent != Some(MetadataTable::Assembly) {
+                continue;
+            }
+
+            if self.custom_attribute_type_name(row[1]).as_deref() != Some("TargetFrameworkAttribute") {
+                continue;
+            }
+
+            /* Prolog (0x0001) followed by the first fixed argument, a SerString */
+            let value = self.heaps.blob(row[2]);
+            let (length, header) = read_compressed_u32(value.get(2..)?)?;
+            let string = value.get(2 + header..2 + header + length as usize)?;
+
+            return Some(String::from_utf8_lossy(string).to_string());
+        }
+
+        return None;
+    }
+
+    /// Whether the image claims a strong name and holds room for its signature
+    pub fn is_strong_name_signed(&self) -> bool {
+        return (self.clr_header.flags & CLRFlags::StrongNameSigned as u32) != 0 && self.clr_header.strong_name_signature.size != 0;
+    }
+
+    fn strong_name_status(&self) -> String {
+        let signature = &self.clr_header.strong_name_signature;
+
+        match (self.is_strong_name_signed(), signature.size) {
+            (true, size) => return format!("Yes ({:#x} bytes at {:#x})", size, signature.virtual_address),
+            (false, 0) => return String::from("No"),
+            /* Delay signed assemblies reserve the signature without setting the flag */
+            (false, size) => return format!("No ({:#x} bytes reserved, delay signed)", size),
+        }
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new(".NET");
+
+        let header = &self.clr_header;
+
+        dump.push_field("RuntimeVersion", self.metadata_root.version.clone(), None);
+        dump.push_field("Flags", format!("{:#x} ({})", header.flags, CLRFlags::flags_as_string(header.flags)), None);
+
+        let entry_point = match (header.flags & CLRFlags::NativeEntryPoint as u32 != 0, header.entry_point_token) {
+            (true, rva) => format!("{:#x} (native)", rva),
+            (false, 0) => String::from("None"),
+            (false, token) => format!("{:#x} ({})", token, self.token_description(token)),
+        };
+
+        dump.push_field("EntryPoint", entry_point, None);
+
+        dump.push_field("StrongNameSigned", self.strong_name_status(), None);
+
+        for warning in self.warnings.iter() {
+            dump.push_field("Warning", warning.clone(), None);
+        }
+
+        dump.push_child(header.dump());
+
+        return dump;
+    }
+
+    /// Metadata root, streams, row counts and the core tables: Module, TypeDef, MethodDef and AssemblyRef
+    #[rustfmt::skip]
+    pub fn dump_metadata(&self) -> Dump {
+        let mut dump = Dump::new(".NET Metadata");
+
+        let root = &self.metadata_root;
+
+        dump.push_field("Version", format!("{} ({}.{})", root.version, root.major_version, root.minor_version), None);
+        dump.push_field("TablesVersion", format!("{}.{}", self.tables.major_version, self.tables.minor_version), None);
+        dump.push_field("HeapSizes", format!("{:#x}", self.tables.heap_sizes), None);
+
+        for warning in self.warnings.iter() {
+            dump.push_field("Warning", warning.clone(), None);
+        }
+
+        let mut streams_dump = Dump::new_from_string(format!("Streams ({})", root.streams.len()));
+
+        for stream in root.streams.iter() {
+            streams_dump.push_field("", format!("{:<10} offset {:#08x}  size {:#x}", stream.name, stream.offset, stream.size), None);
+        }
+
+        dump.push_child(streams_dump);
+
+        let present: Vec<MetadataTable> = MetadataTable::iter().filter(|t| self.tables.row_count(*t) > 0).collect();
+        let mut tables_dump = Dump::new_from_string(format!("Tables ({})", present.len()));
+
+        for table in present.iter() {
+            let name: &'static str = table.into();
+            tables_dump.push_field("", format!("{:<24} {:>6} rows", name, self.tables.row_count(*table)), None);
+        }
+
+        dump.push_child(tables_dump);
+
+        if let Some(row) = self.tables.row(MetadataTable::Module, 1) {
+            let mut module_dump = Dump::new("Module");
+
+            module_dump.push_field("Name", self.heaps.string(row[1]), None);
+            module_dump.push_field("Mvid", self.heaps.guid(row[2]).unwrap_or(String::from("None")), None);
+
+            dump.push_child(module_dump);
+        }
+
+        let type_defs = self.tables.row_count(MetadataTable::TypeDef);
+        let mut type_defs_dump = Dump::new_from_string(format!("TypeDefs ({})", type_defs));
+
+        for index in 1..=type_defs {
+            let Some(row) = self.tables.row(MetadataTable::TypeDef, index) else {
+                break;
+            };
+
+            let mut line = format!("{:#010x}  flags {:#010x}  {}", (MetadataTable::TypeDef as u32) << 24 | index, row[0], self.type_def_name(index));
+
+            /* Interfaces and <Module> have no base type */
+            if row[3] != 0 {
+                line.push_str(&format!(" : {}", self.type_def_or_ref_name(row[3])));
+            }
+
+            type_defs_dump.push_field("", line, None);
+        }
+
+        dump.push_child(type_defs_dump);
+
+        let method_defs = self.tables.row_count(MetadataTable::MethodDef);
+        let mut method_defs_dump = Dump::new_from_string(format!("MethodDefs ({})", method_defs));
+
+        for index in 1..=method_defs {
+            let Some(row) = self.tables.row(MetadataTable::MethodDef, index) else {
+                break;
+            };
+
+            let name = self.method_def_name(index);
+            let signature = self.method_def_signature(index).map(|s| s.format(&name)).unwrap_or(name);
+
+            method_defs_dump.push_field("", format!("{:#010x}  rva {:#08x}  flags {:#06x}  {}", (MetadataTable::MethodDef as u32) << 24 | index, row[0], row[2], signature), None);
+        }
+
+        dump.push_child(method_defs_dump);
+
+        let references = self.referenced_assemblies();
+        let mut references_dump = Dump::new_from_string(format!("AssemblyRefs ({})", references.len()));
+
+        for (index, reference) in references.iter().enumerate() {
+            references_dump.push_field("", format!("{:#010x}  {}", (MetadataTable::AssemblyRef as u32) << 24 | (index as u32 + 1), reference.full_name()), None);
+        }
+
+        dump.push_child(references_dump);
+
+        return dump;
+    }
+
+    /// Referenced assemblies with their version and public key token, for dependency audits
+    #[rustfmt::skip]
+    pub fn dump_references(&self) -> Dump {
+        let mut dump = Dump::new(".NET References");
+
+        match self.assembly() {
+            Some(assembly) => dump.push_field("Assembly", assembly.full_name(), None),
+            None => dump.push_field("Assembly", String::from("None (netmodule)"), None),
+        }
+
+        dump.push_field("StrongNameSigned", self.strong_name_status(), None);
+
+        let references = self.referenced_assemblies();
+
+        let mut references_dump = Dump::new_from_string(format!("Assembly References ({})", references.len()));
+        references_dump.push_field("", format!("{:<40} {:<16} {:<10} {}", "Name", "Version", "Culture", "PublicKeyToken"), None);
+
+        for reference in references.iter() {
+            references_dump.push_field("", format!(
+                "{:<40} {:<16} {:<10} {}",
+                reference.name,
+                reference.version_as_string(),
+                reference.culture_as_string(),
+                reference.public_key_token_as_string()
+            ), None);
+        }
+
+        dump.push_child(references_dump);
+
+        return dump;
+    }
+
+    #[rustfmt::skip]
+    pub fn dump_assembly(&self) -> Dump {
+        let mut dump = Dump::new(".NET Assembly");
+
+        dump.push_field("RuntimeVersion", self.metadata_root.version.clone(), None);
+
+        if let Some(assembly) = self.assembly() {
+            dump.push_field("Name", assembly.name.clone(), None);
+            dump.push_field("Version", assembly.version_as_string(), None);
+            dump.push_field("Culture", assembly.culture_as_string().to_string(), None);
+            dump.push_field("PublicKeyToken", assembly.public_key_token_as_string(), None);
+            dump.push_field("Flags", format!("{:#x}", assembly.flags), None);
+            dump.push_field("HashAlgId", format!("{:#x}", assembly.hash_algorithm.unwrap_or(0)), None);
+        } else {
+            dump.push_field("Name", String::from("No Assembly manifest (netmodule)"), None);
+        }
+
+        dump.push_field("TargetFramework", self.target_framework().unwrap_or(String::from("Unknown")), None);
+
+        let references = self.referenced_assemblies();
+
+        let mut references_dump = Dump::new_from_string(format!("Referenced Assemblies ({})", references.len()));
+
+        for reference in references.iter() {
+            references_dump.push_field("", reference.full_name(), None);
+        }
+
+        dump.push_child(references_dump);
+
+        return dump;
+    }
+}
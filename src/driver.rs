@@ -0,0 +1,488 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::analysis::x86_capstone;
+use crate::dump::Dump;
+use crate::pe::{compute_checksum, DLLCharacteristicsFlags, MachineType, SectionFlags, Subsystem, PE};
+use crate::x86_64::{Operand, parse_operands};
+
+/*
+ * Kernel driver specific analysis
+ */
+
+/// Modules only kernel-mode images import from
+pub const KERNEL_MODULES: &[&str] = &[
+    "ntoskrnl.exe",
+    "hal.dll",
+    "ndis.sys",
+    "fltmgr.sys",
+    "wdfldr.sys",
+    "ksecdd.sys",
+    "cng.sys",
+    "storport.sys",
+    "netio.sys",
+    "wmilib.sys",
+];
+
+/// Prefixes of the kernel routines, the prefix is always followed by an uppercase letter
+#[rustfmt::skip]
+pub const KERNEL_API_FAMILIES: &[(&str, &str)] = &[
+    ("Zw", "System services"),
+    ("Nt", "System services"),
+    ("Mm", "Memory manager"),
+    ("Io", "I/O manager"),
+    ("Ob", "Object manager"),
+    ("Ps", "Process and thread manager"),
+    ("Ke", "Kernel core"),
+    ("Ex", "Executive support"),
+    ("Rtl", "Runtime library"),
+    ("Se", "Security reference monitor"),
+    ("Cm", "Configuration manager"),
+    ("Po", "Power manager"),
+    ("Hal", "Hardware abstraction layer"),
+    ("Flt", "Filter manager"),
+    ("Wdf", "Kernel-mode driver framework"),
+    ("Ndis", "Network driver interface"),
+];
+
+#[rustfmt::skip]
+pub const IRP_MAJOR_FUNCTIONS: &[&str] = &[
+    "IRP_MJ_CREATE", "IRP_MJ_CREATE_NAMED_PIPE", "IRP_MJ_CLOSE", "IRP_MJ_READ", "IRP_MJ_WRITE",
+    "IRP_MJ_QUERY_INFORMATION", "IRP_MJ_SET_INFORMATION", "IRP_MJ_QUERY_EA", "IRP_MJ_SET_EA",
+    "IRP_MJ_FLUSH_BUFFERS", "IRP_MJ_QUERY_VOLUME_INFORMATION", "IRP_MJ_SET_VOLUME_INFORMATION",
+    "IRP_MJ_DIRECTORY_CONTROL", "IRP_MJ_FILE_SYSTEM_CONTROL", "IRP_MJ_DEVICE_CONTROL",
+    "IRP_MJ_INTERNAL_DEVICE_CONTROL", "IRP_MJ_SHUTDOWN", "IRP_MJ_LOCK_CONTROL", "IRP_MJ_CLEANUP",
+    "IRP_MJ_CREATE_MAILSLOT", "IRP_MJ_QUERY_SECURITY", "IRP_MJ_SET_SECURITY", "IRP_MJ_POWER",
+    "IRP_MJ_SYSTEM_CONTROL", "IRP_MJ_DEVICE_CHANGE", "IRP_MJ_QUERY_QUOTA", "IRP_MJ_SET_QUOTA", "IRP_MJ_PNP",
+];
+
+/// Kernel routines telling how the driver handles its IRPs and what it reaches from kernel mode
+#[rustfmt::skip]
+pub const DRIVER_IMPORT_HEURISTICS: &[(&str, &str)] = &[
+    ("IoCreateDevice", "Creates a device object"),
+    ("IoCreateDeviceSecure", "Creates a device object with an SDDL security descriptor"),
+    ("IoCreateSymbolicLink", "Exposes a device to user mode through a symbolic link"),
+    ("IoRegisterDeviceInterface", "Exposes a device to user mode through a device interface"),
+    ("IofCompleteRequest", "Completes IRPs"),
+    ("IoCompleteRequest", "Completes IRPs"),
+    ("IofCallDriver", "Forwards IRPs to a lower driver"),
+    ("IoCallDriver", "Forwards IRPs to a lower driver"),
+    ("IoAttachDeviceToDeviceStack", "Attaches as a filter to another device stack"),
+    ("IoAttachDeviceToDeviceStackSafe", "Attaches as a filter to another device stack"),
+    ("IoBuildDeviceIoControlRequest", "Sends device control requests to other drivers"),
+    ("IoAllocateMdl", "Describes user buffers (direct I/O or METHOD_NEITHER)"),
+    ("ProbeForRead", "Validates user-mode buffers (METHOD_NEITHER)"),
+    ("ProbeForWrite", "Validates user-mode buffers (METHOD_NEITHER)"),
+    ("MmMapIoSpace", "Maps physical memory"),
+    ("MmMapIoSpaceEx", "Maps physical memory"),
+    ("MmMapLockedPagesSpecifyCache", "Maps locked pages, possibly into user mode"),
+    ("MmGetSystemRoutineAddress", "Resolves kernel routines at run time"),
+    ("ZwMapViewOfSection", "Maps sections, \\Device\\PhysicalMemory among them"),
+    ("PsSetCreateProcessNotifyRoutine", "Monitors process creation"),
+    ("PsSetCreateProcessNotifyRoutineEx", "Monitors process creation"),
+    ("PsSetCreateThreadNotifyRoutine", "Monitors thread creation"),
+    ("PsSetLoadImageNotifyRoutine", "Monitors image loads"),
+    ("ObRegisterCallbacks", "Filters process and thread handle operations"),
+    ("CmRegisterCallback", "Filters registry operations"),
+    ("CmRegisterCallbackEx", "Filters registry operations"),
+    ("FltRegisterFilter", "Registers a file system minifilter"),
+    ("KeStackAttachProcess", "Attaches to the address space of other processes"),
+    ("ZwTerminateProcess", "Terminates processes"),
+    ("WdfVersionBind", "Built on the kernel-mode driver framework (KMDF)"),
+];
+
+/* Offsets of DriverUnload and MajorFunction[0] in DRIVER_OBJECT */
+const DRIVER_UNLOAD_OFFSET_32: u64 = 0x34;
+const MAJOR_FUNCTION_OFFSET_32: u64 = 0x38;
+const DRIVER_UNLOAD_OFFSET_64: u64 = 0x68;
+const MAJOR_FUNCTION_OFFSET_64: u64 = 0x70;
+
+fn kernel_imports(pe: &PE) -> Vec<(String, Vec<String>)> {
+    match pe.hint_name_table {
+        Some(ref hnt) => {
+            return hnt
+                .entries
+                .iter()
+                .map(|dll| (dll.dll_name.clone(), dll.entries.iter().map(|e| e.name.clone()).collect()))
+                .collect();
+        }
+        None => return Vec::new(),
+    }
+}
+
+/// A driver either targets the NATIVE subsystem or links against the kernel
+pub fn is_driver(pe: &PE) -> bool {
+    if pe.get_optional_header().get_subsystem() == Subsystem::Native {
+        return true;
+    }
+
+    return kernel_imports(pe)
+        .iter()
+        .any(|(dll, _)| KERNEL_MODULES.iter().any(|m| m.eq_ignore_ascii_case(dll)));
+}
+
+pub fn api_family(name: &str) -> Option<&'static str> {
+    for (prefix, _) in KERNEL_API_FAMILIES.iter() {
+        if let Some(rest) = name.strip_prefix(prefix) && rest.chars().next().is_some_and(|c| c.is_ascii_uppercase()) {
+            return Some(prefix);
+        }
+    }
+
+    return None;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DriverSection {
+    pub name: String,
+    pub size: u32,
+    pub characteristics: u32,
+}
+
+impl DriverSection {
+    pub fn is_discardable(&self) -> bool {
+        return self.characteristics & SectionFlags::MemDiscardable as u32 != 0;
+    }
+
+    pub fn is_pageable(&self) -> bool {
+        return self.name.starts_with("PAGE") && self.characteristics & SectionFlags::MemNotPaged as u32 == 0;
+    }
+
+    pub fn is_writable_executable(&self) -> bool {
+        let wx = SectionFlags::MemWrite as u32 | SectionFlags::MemExecute as u32;
+
+        return self.characteristics & wx == wx;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DriverReport {
+    pub subsystem: &'static str,
+    pub kernel_modules: Vec<String>,
+    pub sections: Vec<DriverSection>,
+    /// IRP major function index -> candidate dispatch routines
+    pub dispatch_routines: BTreeMap<usize, BTreeSet<u64>>,
+    pub unload_routines: BTreeSet<u64>,
+    pub api_families: BTreeMap<&'static str, Vec<String>>,
+    pub force_integrity: bool,
+    pub wdm_driver: bool,
+    pub embedded_signature: bool,
+    pub checksum: u32,
+    /// None for the memory images, their checksum covers the file they were loaded from
+    pub computed_checksum: Option<u32>,
+    pub signing_requirement: &'static str,
+    /// Imported routine -> what it tells about the driver, see DRIVER_IMPORT_HEURISTICS
+    pub import_heuristics: Vec<(String, &'static str)>,
+    pub warnings: Vec<String>,
+}
+
+/// Tracks the code addresses loaded in registers to recover the routines stored into the DRIVER_OBJECT
+struct DispatchScanner {
+    registers: HashMap<String, u64>,
+    unload_offset: u64,
+    major_function_offset: u64,
+    pointer_size: u64,
+}
+
+impl DispatchScanner {
+    fn scan(&mut self, insn: &capstone::Insn, report: &mut DriverReport, is_code: &dyn Fn(u64) -> bool) {
+        let mnemonic = insn.mnemonic().unwrap_or("");
+        let operands = parse_operands(insn.op_str().unwrap_or(""));
+
+        let [destination, source] = operands.as_slice() else {
+            return;
+        };
+
+        match (mnemonic, destination) {
+            ("lea", Operand::Register(register)) => {
+                /* x64 code addresses are rip-relative */
+                let next = insn.address() + insn.bytes().len() as u64;
+
+                match source.memory().and_then(|m| m.absolute_address(next)) {
+                    Some(address) => self.registers.insert(register.name.clone(), address),
+                    None => self.registers.remove(&register.name),
+                };
+            }
+            ("mov", Operand::Register(register)) => {
+                match source.immediate() {
+                    Some(value) => self.registers.insert(register.name.clone(), value as u64),
+                    None => self.registers.remove(&register.name),
+                };
+            }
+            ("mov", Operand::Memory(memory)) => {
+                /* "qword ptr [rcx + 0x70]", indexed accesses are not DRIVER_OBJECT fields */
+                let Some(offset) = memory.field_of().map(|(_, displacement)| displacement as u64).filter(|offset| *offset != 0) else {
+                    return;
+                };
+
+                let value = match source {
+                    Operand::Register(register) => match self.registers.get(&register.name) {
+                        Some(value) => *value,
+                        None => return,
+                    },
+                    Operand::Immediate(value) => *value as u64,
+                    _ => return,
+                };
+
+                if !is_code(value) {
+                    return;
+                }
+
+                let end = self.major_function_offset + IRP_MAJOR_FUNCTIONS.len() as u64 * self.pointer_size;
+
+                if offset == self.unload_offset {
+                    report.unload_routines.insert(value);
+                } else if offset >= self.major_function_offset && offset < end && (offset - self.major_function_offset).is_multiple_of(self.pointer_size) {
+                    let index = ((offset - self.major_function_offset) / self.pointer_size) as usize;
+                    report.dispatch_routines.entry(index).or_default().insert(value);
+                }
+            }
+            (_, Operand::Register(register)) => {
+                self.registers.remove(&register.name);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn scan_dispatch_routines(pe: &PE, report: &mut DriverReport) {
+    let machine: MachineType = pe.get_nt_header().coff_header.machine.into();
+
+    if machine != MachineType::I386 && machine != MachineType::AMD64 {
+        return;
+    }
+
+    let Some(cs) = x86_capstone(pe.is_32_bits()) else {
+        return;
+    };
+
+    let image_base = pe.get_optional_header().get_image_base();
+
+    let code_ranges: Vec<(u64, u64)> = pe
+        .sections
+        .iter()
+        .filter(|s| s.contains_code())
+        .map(|s| {
+            let start = image_base + s.header.virtual_address as u64;
+            (start, start + s.raw_data().len() as u64)
+        })
+        .collect();
+
+    let is_code = |address: u64| code_ranges.iter().any(|(start, end)| address >= *start && address < *end);
+
+    let mut scanner = match pe.is_32_bits() {
+        true => DispatchScanner {
+            registers: HashMap::new(),
+            unload_offset: DRIVER_UNLOAD_OFFSET_32,
+            major_function_offset: MAJOR_FUNCTION_OFFSET_32,
+            pointer_size: 4,
+        },
+        false => DispatchScanner {
+            registers: HashMap::new(),
+            unload_offset: DRIVER_UNLOAD_OFFSET_64,
+            major_function_offset: MAJOR_FUNCTION_OFFSET_64,
+            pointer_size: 8,
+        },
+    };
+
+    let mut sections: Vec<_> = pe.sections.iter().filter(|s| s.contains_code()).collect();
+    sections.sort_by_key(|s| s.header.virtual_address);
+
+    for section in sections {
+        let code = section.raw_data();
+        let address = image_base + section.header.virtual_address as u64;
+        let mut offset = 0;
+
+        while offset < code.len() {
+            let Ok(instructions) = cs.disasm_all(&code[offset..], address + offset as u64) else {
+                break;
+            };
+
+            let mut consumed = 0;
+
+            for insn in instructions.iter() {
+                scanner.scan(insn, report, &is_code);
+                consumed += insn.bytes().len();
+            }
+
+            scanner.registers.clear();
+            offset += consumed + 1;
+        }
+    }
+}
+
+pub fn analyze_driver(pe: &PE, file_bytes: &[u8]) -> DriverReport {
+    let mut report = DriverReport::default();
+
+    let optional_header = pe.get_optional_header();
+
+    report.subsystem = optional_header.get_subsystem().as_static_str();
+    report.checksum = optional_header.get_checksum();
+    report.computed_checksum = match pe.memory_image {
+        true => None,
+        false => Some(compute_checksum(file_bytes, pe.get_checksum_offset())),
+    };
+    report.force_integrity = optional_header.get_dll_characteristics() & DLLCharacteristicsFlags::ForceIntegrity as u16 != 0;
+    report.wdm_driver = optional_header.get_dll_characteristics() & DLLCharacteristicsFlags::WdmDriver as u16 != 0;
+    report.embedded_signature = optional_header.get_certificate_table_idd().size > 0;
+
+    for (dll, functions) in kernel_imports(pe) {
+        if KERNEL_MODULES.iter().any(|m| m.eq_ignore_ascii_case(&dll)) {
+            report.kernel_modules.push(dll.clone());
+        }
+
+        for function in functions {
+            if let Some((_, heuristic)) = DRIVER_IMPORT_HEURISTICS.iter().find(|(name, _)| *name == function) {
+                report.import_heuristics.push((function.clone(), heuristic));
+            }
+
+            if let Some(family) = api_family(&function) {
+                report.api_families.entry(family).or_default().push(function);
+            }
+        }
+    }
+
+    /* Kernel-mode code signing is only enforced by 64-bit Windows */
+    report.signing_requirement = match pe.is_32_bits() {
+        true => "None enforced on 32-bit Windows, boot-start drivers need an embedded signature for Secure Boot",
+        false => "Microsoft signature (WHQL or attestation) since Windows 10 1607, cross-signed certificates before",
+    };
+
+    report.sections = pe
+        .sections
+        .iter()
+        .map(|s| DriverSection {
+            name: s.header.name.clone(),
+            size: s.header.virtual_size,
+            characteristics: s.header.characteristics,
+        })
+        .collect();
+
+    report.sections.sort_by(|a, b| a.name.cmp(&b.name));
+
+    scan_dispatch_routines(pe, &mut report);
+
+    if optional_header.get_subsystem() != Subsystem::Native {
+        report.warnings.push(format!("Imports the kernel but the subsystem is {}", report.subsystem));
+    }
+
+    match report.sections.iter().find(|s| s.name == "INIT") {
+        Some(init) if !init.is_discardable() => report.warnings.push(String::from("INIT section is not discardable, initialization code stays resident")),
+        Some(_) => {}
+        None => report.warnings.push(String::from("No INIT section, DriverEntry code stays resident")),
+    }
+
+    for section in report.sections.iter().filter(|s| s.is_writable_executable()) {
+        report.warnings.push(format!("Section {} is writable and executable", section.name));
+    }
+
+    match report.computed_checksum {
+        _ if report.checksum == 0 => report.warnings.push(String::from("Checksum is zero, the kernel refuses to load drivers without a valid checksum")),
+        Some(computed) if computed != report.checksum => report.warnings.push(format!("Checksum is {:#x} but the file sums to {:#x}, the kernel refuses to load the driver", report.checksum, computed)),
+        _ => {}
+    }
+
+    if !report.embedded_signature {
+        report.warnings.push(String::from("No embedded signature, kernel-mode code signing is required on 64-bit Windows unless catalog-signed"));
+
+        if report.force_integrity {
+            report.warnings.push(String::from("ForceIntegrity is set without an embedded signature, the loader refuses the image"));
+        }
+    }
+
+    /* A device reachable from user mode whose buffers are taken without probing is the classic vulnerable driver */
+    let imports = |name: &str| report.import_heuristics.iter().any(|(function, _)| function == name);
+
+    if (imports("IoCreateSymbolicLink") || imports("IoRegisterDeviceInterface")) && imports("MmMapIoSpace") {
+        report.warnings.push(String::from("Maps physical memory and exposes a device to user mode, a common read/write primitive of vulnerable drivers"));
+    }
+
+    if imports("IoCreateDevice") && !imports("IoCreateDeviceSecure") && imports("IoCreateSymbolicLink") {
+        report.warnings.push(String::from("Device created without a security descriptor and exposed through a symbolic link, any user may open it"));
+    }
+
+    if report.dispatch_routines.is_empty() && report.unload_routines.is_empty() {
+        report.warnings.push(String::from("No dispatch routine assignment found, the driver may use a framework (WDF, NDIS, minifilter)"));
+    }
+
+    return report;
+}
+
+impl DriverReport {
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Driver Analysis");
+
+        dump.push_field("Subsystem", String::from(self.subsystem), None);
+        dump.push_field("KernelModules", self.kernel_modules.join(", "), None);
+        dump.push_field("ForceIntegrity", format!("{}", self.force_integrity), Some("signature checked at load time"));
+        dump.push_field("WdmDriver", format!("{}", self.wdm_driver), None);
+        dump.push_field("EmbeddedSignature", format!("{}", self.embedded_signature), None);
+
+        match self.computed_checksum {
+            _ if self.checksum == 0 => dump.push_field("Checksum", String::from("0x0 (not set)"), None),
+            Some(computed) if computed == self.checksum => dump.push_field("Checksum", format!("{:#x} (valid)", self.checksum), None),
+            Some(computed) => dump.push_field("Checksum", format!("{:#x} (invalid, the file sums to {:#x})", self.checksum, computed), None),
+            None => dump.push_field("Checksum", format!("{:#x}", self.checksum), None),
+        }
+
+        dump.push_field("SigningRequirement", String::from(self.signing_requirement), None);
+
+        let mut sections_dump = Dump::new_from_string(format!("Sections ({})", self.sections.len()));
+
+        for section in self.sections.iter() {
+            let mut properties = Vec::new();
+
+            if section.is_pageable() { properties.push("pageable"); }
+            if section.is_discardable() { properties.push("discardable"); }
+            if section.is_writable_executable() { properties.push("W+X"); }
+            if properties.is_empty() { properties.push("resident"); }
+
+            sections_dump.push_field("", format!("{:<8} size: {:#x} ({})", section.name, section.size, properties.join(", ")), None);
+        }
+
+        let mut dispatch_dump = Dump::new_from_string(format!("Dispatch Routines ({})", self.dispatch_routines.len()));
+
+        for (index, routines) in self.dispatch_routines.iter() {
+            let routines: Vec<String> = routines.iter().map(|r| format!("{:#x}", r)).collect();
+            dispatch_dump.push_field(IRP_MAJOR_FUNCTIONS[*index], routines.join(", "), None);
+        }
+
+        if !self.unload_routines.is_empty() {
+            let routines: Vec<String> = self.unload_routines.iter().map(|r| format!("{:#x}", r)).collect();
+            dispatch_dump.push_field("DriverUnload", routines.join(", "), None);
+        }
+
+        let mut heuristics_dump = Dump::new_from_string(format!("Import Heuristics ({})", self.import_heuristics.len()));
+
+        for (function, heuristic) in self.import_heuristics.iter() {
+            heuristics_dump.push_field("", format!("{:<32} {}", function, heuristic), None);
+        }
+
+        let mut families_dump = Dump::new("Kernel API Families");
+
+        for (prefix, functions) in self.api_families.iter() {
+            let family = KERNEL_API_FAMILIES.iter().find(|(p, _)| p == prefix).map(|(_, f)| *f).unwrap_or("");
+
+            let mut family_dump = Dump::new_from_string(format!("{} - {} ({})", prefix, family, functions.len()));
+
+            for function in functions.iter() {
+                family_dump.push_field("", function.clone(), None);
+            }
+
+            families_dump.push_child(family_dump);
+        }
+
+        let mut warnings_dump = Dump::new_from_string(format!("Warnings ({})", self.warnings.len()));
+
+        for warning in self.warnings.iter() {
+            warnings_dump.push_field("", warning.clone(), None);
+        }
+
+        dump.push_child(sections_dump);
+        dump.push_child(dispatch_dump);
+        dump.push_child(heuristics_dump);
+        dump.push_child(families_dump);
+        dump.push_child(warnings_dump);
+
+        return dump;
+    }
+}
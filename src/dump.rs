@@ -1,14 +1,45 @@
+use crate::analysis;
+use crate::arm64x::{self, Arm64XReport};
+use crate::authenticode;
+use crate::buildinfo::BuildInfo;
+use crate::carve::CarveReport;
+use crate::capabilities;
+use crate::deps::{DependencyTree, MissingDependencies};
+use crate::cil;
+use crate::disasm;
+use crate::driver;
+use crate::efi;
 use crate::elf::ELF;
-use crate::exec::Exec;
-use crate::args::Args;
-use crate::pe::PE;
+use crate::exec::{Exec, parse_exec, parse_exec_with};
+use crate::extract;
+use crate::format::format_hex_lines;
+use crate::fuzzy::FileFuzzyHashes;
+use crate::hashes::FileHashes;
+use crate::functions;
+use crate::legacy::LegacyExecutable;
+use crate::linkmap::LinkMap;
+use crate::minidump::Minidump;
+use crate::lint::LintReport;
+use crate::mitigations::MitigationsReport;
+use crate::patch;
+use crate::summary::Summary;
+use crate::strings::StringsReport;
+use crate::address::{self, AddressConversions, AddressLocation};
+use crate::symbols;
+use crate::timestamps::TimestampReport;
+use crate::args::{AddrArgs, Args, CarveArgs, DiffArgs, DisasmFormat, MinidumpArgs, OutputFormat, StringsArgs};
+use crate::diff::{ExecDiff, ExportComparison};
+use crate::pe::{dump_entry_point, dump_imports, PE};
 
 use regex::Regex;
+use std::path::Path;
+use serde::{Serialize, Serializer};
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize)]
 pub struct DumpField {
     pub key: &'static str,
     pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub comment: Option<&'static str>,
 }
 
@@ -35,11 +66,32 @@ impl Default for DumpRawData {
     }
 }
 
-#[derive(Clone, Debug, Default)]
+impl DumpRawData {
+    pub fn is_none(&self) -> bool {
+        return matches!(self, DumpRawData::None());
+    }
+}
+
+impl Serialize for DumpRawData {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            DumpRawData::None() => serializer.serialize_none(),
+            DumpRawData::Bytes(bytes) => {
+                let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+                serializer.serialize_str(&hex)
+            },
+            DumpRawData::Code(code) => code.serialize(serializer),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
 pub struct Dump {
     label: String,
     fields: Vec<DumpField>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     children: Vec<Dump>,
+    #[serde(skip_serializing_if = "DumpRawData::is_none")]
     raw_data: DumpRawData,
 }
 
@@ -79,6 +131,18 @@ impl Dump {
         self.raw_data = raw_data;
     }
 
+    /// Keeps the first bytes of the raw data, 0 keeps them all, with a field telling how many are dumped
+    pub fn truncate_raw_bytes(&mut self, max_bytes: usize) {
+        if let DumpRawData::Bytes(ref mut bytes) = self.raw_data {
+            let size = bytes.len();
+
+            if max_bytes > 0 && size > max_bytes {
+                bytes.truncate(max_bytes);
+                self.fields.push(DumpField::new("Data", format!("first {:#x} of {:#x} bytes", max_bytes, size), None));
+            }
+        }
+    }
+
     pub fn iter_fields(&self) -> std::slice::Iter<'_, DumpField> {
         return self.fields.iter();
     }
@@ -115,7 +179,7 @@ impl Dump {
         for field in self.fields.iter() {
             let label = field.key;
 
-            if label.len() == 0 {
+            if label.is_empty() {
                 println!(
                     "{:>width$}{}",
                     "",
@@ -137,151 +201,1012 @@ impl Dump {
                     println!("{:>width$}{}", "", loc, width = fields_indent);
                 }
             },
-            _ => {},
+            DumpRawData::Bytes(bytes) => {
+                for line in format_hex_lines(bytes, 0).iter() {
+                    println!("{:>width$}{}", "", line, width = fields_indent);
+                }
+            },
+            DumpRawData::None() => {},
         }
 
-        if self.children.len() > 0 {
-            println!("");
+        if !self.children.is_empty() {
+            println!();
         }
 
         for child in self.children.iter() {
             child.print(indent_level + 1, indent_size);
-            println!("");
+            println!();
+        }
+    }
+
+    pub fn to_html(&self, html: &mut String, depth: usize) {
+        let heading = (depth + 2).min(6);
+
+        html.push_str("<section>\n");
+        html.push_str(&format!("<h{}>{}</h{}>\n", heading, escape_html(&self.label), heading));
+
+        if !self.fields.is_empty() {
+            html.push_str("<table>\n");
+
+            for field in self.fields.iter() {
+                html.push_str(&format!(
+                    "<tr><th>{}</th><td>{}</td></tr>\n",
+                    escape_html(field.key),
+                    escape_html(&field.value)
+                ));
+            }
+
+            html.push_str("</table>\n");
+        }
+
+        let lines = match &self.raw_data {
+            DumpRawData::Code(code) => code.clone(),
+            DumpRawData::Bytes(bytes) => format_hex_lines(bytes, 0),
+            DumpRawData::None() => Vec::new(),
+        };
+
+        if !lines.is_empty() {
+            html.push_str("<pre>");
+
+            for loc in lines.iter() {
+                html.push_str(&escape_html(loc));
+                html.push('\n');
+            }
+
+            html.push_str("</pre>\n");
+        }
+
+        for child in self.children.iter() {
+            child.to_html(html, depth + 1);
         }
+
+        html.push_str("</section>\n");
     }
 }
 
-pub fn dump_pe(pe: &PE, args: &Args) {
-    if args.pe_dos_header {
-        pe.get_dos_header().dump().print(0, args.padding_size);
+fn escape_html(s: &str) -> String {
+    return s
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;");
+}
+
+const HTML_REPORT_STYLE: &str = "body { font-family: monospace; margin: 2em; } \
+section { margin-left: 1em; } \
+table { border-collapse: collapse; } \
+th { text-align: left; padding-right: 1em; font-weight: normal; color: #555; } \
+pre { background: #f4f4f4; padding: 0.5em; }";
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    file: &'a str,
+    dumps: &'a [Dump],
+}
+
+/*
+ * Writes the dumps either straight to stdout as text, or collects them to emit a single JSON/HTML report
+ */
+pub struct DumpWriter {
+    format: OutputFormat,
+    indent_size: usize,
+    title: String,
+    dumps: Vec<Dump>,
+}
+
+impl DumpWriter {
+    pub fn new(format: OutputFormat, indent_size: usize, title: &str) -> DumpWriter {
+        return DumpWriter {
+            format,
+            indent_size,
+            title: String::from(title),
+            dumps: Vec::new(),
+        };
     }
 
-    if args.pe_nt_header {
-        pe.get_nt_header().dump().print(0, args.padding_size);
+    pub fn write(&mut self, dump: Dump) {
+        match self.format {
+            OutputFormat::Text => dump.print(0, self.indent_size),
+            _ => self.dumps.push(dump),
+        }
     }
 
-    if args.pe_optional_header {
-        pe.get_optional_header().dump().print(0, args.padding_size);
+    /// Writes a list of dumps under a common label
+    pub fn write_list(&mut self, label: String, dumps: Vec<Dump>, separated: bool) {
+        match self.format {
+            OutputFormat::Text => {
+                println!("{}", label);
+                println!();
+
+                for dump in dumps.iter() {
+                    dump.print(0, self.indent_size);
+
+                    if separated {
+                        println!();
+                    }
+                }
+            },
+            _ => {
+                let mut list = Dump::new_from_string(label);
+
+                for dump in dumps {
+                    list.push_child(dump);
+                }
+
+                self.dumps.push(list);
+            },
+        }
     }
 
-    if args.sections {
-        let sections_filter_regex = Regex::new(&args.sections_filter.as_str()).expect("Invalid regular expression");
+    /// Writes lines as is in text, without a label nor indentation, for the listings read by other tools
+    pub fn write_raw(&mut self, label: &str, lines: Vec<String>) {
+        match self.format {
+            OutputFormat::Text => {
+                for line in lines.iter() {
+                    println!("{}", line);
+                }
+            },
+            _ => {
+                let mut dump = Dump::new(label);
+                dump.set_raw_data(DumpRawData::Code(lines));
+                self.dumps.push(dump);
+            },
+        }
+    }
 
-        println!("Sections ({})", pe.get_number_of_sections());
-        println!("");
+    /// Writes a placeholder for data that is missing from the executable
+    pub fn write_missing(&mut self, label: &str, message: &str) {
+        match self.format {
+            OutputFormat::Text => {
+                println!("{}", label);
+                println!("{}", message);
+            },
+            _ => {
+                let mut dump = Dump::new(label);
+                dump.push_field("", String::from(message), None);
+                self.dumps.push(dump);
+            },
+        }
+    }
 
-        for (_, section) in pe.sections.iter() {
-            if sections_filter_regex.is_match(section.header.name.as_str()) {
-                section.dump(pe, args.disasm).print(0, args.padding_size);
-            }
+    pub fn write_separator(&mut self) {
+        if self.format == OutputFormat::Text {
+            println!();
         }
     }
 
-    if args.pe_import {
-        if pe.import_directory_table.is_none() {
-            println!("Import data");
-            println!("No Import Data found in PE");
-        } else {
-            pe.import_directory_table.as_ref().unwrap().dump().print(0, args.padding_size);
+    /// JSON report of the dumps collected with a non-text output format
+    pub fn to_json_string(&self) -> String {
+        let report = JsonReport {
+            file: &self.title,
+            dumps: &self.dumps,
+        };
+
+        return serde_json::to_string_pretty(&report).expect("Cannot serialize dumps to JSON");
+    }
+
+    pub fn finish(self) {
+        match self.format {
+            OutputFormat::Text => {},
+            OutputFormat::Json => println!("{}", self.to_json_string()),
+            OutputFormat::Html => {
+                let mut html = String::new();
+
+                html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+                html.push_str(&format!("<title>execdump - {}</title>\n", escape_html(&self.title)));
+                html.push_str(&format!("<style>{}</style>\n", HTML_REPORT_STYLE));
+                html.push_str("</head>\n<body>\n");
+                html.push_str(&format!("<h1>{}</h1>\n", escape_html(&self.title)));
+
+                for dump in self.dumps.iter() {
+                    dump.to_html(&mut html, 0);
+                }
 
-            for ilt in pe.import_lookup_tables.as_ref().unwrap().iter() {
-                ilt.dump().print(0, args.padding_size);
+                html.push_str("</body>\n</html>");
+
+                println!("{}", html);
+            },
+        }
+    }
+}
+
+fn dump_functions(functions: Result<Vec<functions::DiscoveredFunction>, Box<dyn std::error::Error>>, args: &Args, writer: &mut DumpWriter) {
+    let signatures = match functions {
+        Ok(functions) => functions::hash_functions(&functions),
+        Err(e) => {
+            writer.write_missing("Function Hashes", &e.to_string());
+            return;
+        }
+    };
+
+    if args.function_hashes {
+        writer.write(functions::dump_function_hashes(&signatures));
+    }
+
+    if let Some(ref other_path) = args.compare_functions {
+        let other_functions = parse_exec(other_path).and_then(|other| functions::discover_exec_functions(&other));
+
+        match other_functions {
+            Ok(other_functions) => {
+                let other_signatures = functions::hash_functions(&other_functions);
+                writer.write(functions::FunctionComparison::compare(&signatures, &other_signatures).dump());
             }
+            Err(e) => writer.write_missing("Function Comparison", &e.to_string()),
+        }
+    }
+}
+
+pub fn dump_pe(pe: &PE, args: &Args, writer: &mut DumpWriter) {
+    /* Firmware images run without Windows, the checks built on the Windows loader and APIs do not apply */
+    let is_efi = efi::is_efi(pe);
 
-            println!("");
+    /* TE images keep a TE header in place of the DOS and NT headers, their optional header is synthesized from it */
+    let is_te = pe.te_header.is_some();
 
-            pe.hint_name_table.as_ref().unwrap().dump().print(0, args.padding_size);
+    if args.pe_dos_header {
+        match is_te {
+            true => writer.write_missing("DOS Header", "TE image, the DOS header is stripped"),
+            false => writer.write(pe.get_dos_header().dump()),
+        }
+    }
+
+    if args.dos_stub {
+        match is_te {
+            true => writer.write_missing("DOS Stub", "TE image, the DOS stub is stripped"),
+            false => writer.write(pe.dos_stub.dump(args.disasm)),
+        }
+    }
+
+    if args.pe_nt_header {
+        match is_te {
+            true => writer.write_missing("NT Header", "TE image, the NT headers are stripped (see --te-header)"),
+            false => writer.write(pe.get_nt_header().dump()),
+        }
+    }
+
+    if args.te_header {
+        match pe.te_header {
+            Some(ref te_header) => writer.write(te_header.dump()),
+            None => writer.write_missing("TE Header", "Not a TE image"),
+        }
+    }
+
+    if args.pe_optional_header {
+        writer.write(pe.get_optional_header().dump());
+    }
+
+    if args.entry {
+        writer.write(dump_entry_point(pe, args.disasm, args.entry_instructions));
+    }
+
+    if args.sections {
+        let sections_filter_regex = Regex::new(args.sections_filter.as_str()).expect("Invalid regular expression");
+
+        /* In the order of the section table, with the duplicate and empty names */
+        let sections = pe
+            .sections
+            .iter()
+            .filter(|section| sections_filter_regex.is_match(section.header.name.as_str()))
+            .map(|section| {
+                let mut dump = section.dump(pe, args.sections_data, args.disasm && args.disasm_format == DisasmFormat::Execdump);
+                dump.truncate_raw_bytes(args.max_bytes);
+                dump
+            })
+            .collect();
+
+        writer.write_list(format!("Sections ({})", pe.get_number_of_sections()), sections, false);
+    }
+
+    if args.pe_import {
+        match dump_imports(pe) {
+            Some(imports) => writer.write(imports),
+            None => writer.write_missing("Import data", "No Import Data found in PE"),
         }
     }
 
     if args.pe_import_directory_table {
         if let Some(ref idt) = pe.import_directory_table {
-            idt.dump().print(0, args.padding_size);
+            writer.write(idt.dump());
+        } else {
+            writer.write_missing("Import Directory Table", "No Import Directory Table found in PE");
+        }
+    }
+
+    if args.pe_import_lookup_table {
+        if let Some(ref ilts) = pe.import_lookup_tables {
+            for ilt in ilts.iter() {
+                writer.write(ilt.dump());
+            }
         } else {
-           println!("Import Directory Table");
-           println!("No Import Directory Table found in PE");
+            writer.write_missing("Import Lookup Table", "No Import Lookup Table found in PE");
         }
     }
 
     if args.pe_hint_name_table {
         if let Some(ref hnt) = pe.hint_name_table {
-            hnt.dump().print(0, args.padding_size);
+            writer.write(hnt.dump());
         } else {
-            println!("Hint/Name Table");
-            println!("No Hint/Name Table found in PE");
+            writer.write_missing("Hint/Name Table", "No Hint/Name Table found in PE");
         }
     }
 
     if args.pe_dlls {
         if let Some(ref hnt) = pe.hint_name_table {
-            hnt.dump_dlls().print(0, args.padding_size);
+            writer.write(hnt.dump_dlls());
         } else {
-            println!("DLLs");
-            println!("No DLLs found in PE");
+            writer.write_missing("DLLs", "No DLLs found in PE");
+        }
+    }
+
+    if args.iat {
+        match pe.import_address_table {
+            Some(ref iat) => writer.write(iat.dump(pe)),
+            None => writer.write_missing("Import Address Table", "No Import Address Table found in PE"),
+        }
+    }
+
+    if args.exports {
+        match pe.export_data {
+            Some(ref export_data) => writer.write(export_data.dump()),
+            None => writer.write_missing("Exports", "No Export Table found in PE"),
         }
     }
 
     if args.pe_debug_directory {
-        if let Some(ref dd) = pe.debug_directory {
-            dd.dump().print(0, args.padding_size);
+        if pe.debug_directory.is_some() {
+            for entry in pe.debug_entries.iter() {
+                writer.write(entry.directory.dump());
+
+                if let Some(ref data) = entry.data {
+                    writer.write(data.dump());
+                }
+            }
         } else {
-            println!("Debug");
-            println!("No debug information found in PE");
+            writer.write_missing("Debug", "No debug information found in PE");
+        }
+    }
+
+    if args.fetch_pdb {
+        match pe.pdb_path {
+            Some(_) => writer.write(symbols::dump_pdb(pe)),
+            None => writer.write_missing("PDB", "No PDB could be fetched for this PE"),
         }
     }
 
     if args.pe_exc_table {
         if let Some(ref et) = pe.exception_table {
-            et.dump().print(0, args.padding_size);
+            writer.write(et.dump());
+        } else {
+            writer.write_missing("Exception", "No exception information found in PE");
+        }
+    }
+
+    if args.deps {
+        if is_efi {
+            writer.write_missing("Dependencies", "EFI image, the firmware loads it without resolving DLLs");
+        } else {
+            let exec_path = args.file_path.clone().unwrap_or_default();
+            writer.write(DependencyTree::resolve(pe, &exec_path, &args.deps_path).dump());
+        }
+    }
+
+    if args.efi {
+        if is_efi {
+            writer.write(efi::analyze_efi(pe).dump());
+        } else {
+            writer.write_missing("EFI Image", "Not an EFI image (subsystem is not EFI_APPLICATION, EFI_BOOT_SERVICE_DRIVER, EFI_RUNTIME_DRIVER or EFI_ROM)");
+        }
+    }
+
+    if args.driver {
+        if is_efi {
+            writer.write_missing("Driver Analysis", "EFI image, not a Windows kernel driver (see --efi)");
+        } else if driver::is_driver(pe) {
+            let file_path = args.file_path.clone().unwrap_or_default();
+
+            match std::fs::read(&file_path) {
+                Ok(bytes) => writer.write(driver::analyze_driver(pe, &bytes).dump()),
+                Err(e) => writer.write_missing("Driver Analysis", &e.to_string()),
+            }
+        } else {
+            writer.write_missing("Driver Analysis", "Not a kernel driver (subsystem is not NATIVE and no kernel module is imported)");
+        }
+    }
+
+    if args.dotnet {
+        match pe.dotnet {
+            Some(ref dotnet) => writer.write(dotnet.dump()),
+            None => writer.write_missing(".NET", "No CLR Runtime Header found in PE, the image is native"),
+        }
+    }
+
+    if args.dotnet_metadata {
+        match pe.dotnet {
+            Some(ref dotnet) => writer.write(dotnet.dump_metadata()),
+            None => writer.write_missing(".NET Metadata", "No CLR Runtime Header found in PE, the image is native"),
+        }
+    }
+
+    if args.dotnet_refs {
+        match pe.dotnet {
+            Some(ref dotnet) => writer.write(dotnet.dump_references()),
+            None => writer.write_missing(".NET References", "No CLR Runtime Header found in PE, the image is native"),
+        }
+    }
+
+    if args.dotnet_assembly {
+        if let Some(ref dotnet) = pe.dotnet {
+            writer.write(dotnet.dump_assembly());
         } else {
-            println!("Exception");
-            println!("No exception information found in PE");
+            writer.write_missing(".NET Assembly", "No .NET metadata found in PE");
+        }
+    }
+
+    if let Some(ref query) = args.disasm_cil {
+        match pe.dotnet {
+            Some(ref dotnet) => match cil::find_methods(dotnet, query) {
+                Ok(methods) => {
+                    let dumps = methods.iter().map(|index| cil::disassemble_method(pe, dotnet, *index).dump(dotnet)).collect();
+                    writer.write_list(format!("CIL Disassembly ({})", methods.len()), dumps, true);
+                }
+                Err(e) => writer.write_missing("CIL Disassembly", &e.to_string()),
+            },
+            None => writer.write_missing("CIL Disassembly", "No .NET metadata found in PE"),
+        }
+    }
+
+    if let Some(ref output_dir) = args.extract_resources {
+        match extract::extract_resources(pe, output_dir) {
+            Ok(report) => writer.write(report.dump()),
+            Err(e) => writer.write_missing("Extracted Resources", &e.to_string()),
         }
+    }
 
+    if let Some(ref path) = args.extract_cert {
+        match extract::extract_certificate(pe, path) {
+            Ok(report) => writer.write(report.dump()),
+            Err(e) => writer.write_missing("Extracted Certificate", &e.to_string()),
+        }
+    }
+
+    if let Some(ref output_path) = args.strip_debug {
+        let file_path = args.file_path.clone().unwrap_or_default();
+
+        match patch::strip_debug(pe, &file_path, output_path) {
+            Ok(dump) => writer.write(dump),
+            Err(e) => writer.write_missing("Strip Debug", &e.to_string()),
+        }
+    }
+
+    if let Some(ref image_base) = args.rebase {
+        let file_path = args.file_path.clone().unwrap_or_default();
+
+        match patch::parse_address(image_base).and_then(|image_base| patch::rebase(pe, &file_path, image_base, args.output.as_deref())) {
+            Ok(dump) => writer.write(dump),
+            Err(e) => writer.write_missing("Rebase", &e.to_string()),
+        }
+    }
+
+    if args.fix_checksum {
+        let file_path = args.file_path.clone().unwrap_or_default();
+
+        match patch::fix_file_checksum(pe, &file_path, args.output.as_deref()) {
+            Ok(dump) => writer.write(dump),
+            Err(e) => writer.write_missing("Fix Checksum", &e.to_string()),
+        }
+    }
+
+    if args.remove_signature {
+        let file_path = args.file_path.clone().unwrap_or_default();
+
+        match patch::remove_signature(pe, &file_path, args.output.as_deref()) {
+            Ok(dump) => writer.write(dump),
+            Err(e) => writer.write_missing("Remove Signature", &e.to_string()),
+        }
+    }
+
+    if args.resources {
+        match pe.resource_table {
+            Some(ref rt) => writer.write(rt.dump()),
+            None => writer.write_missing("Resources", "No resources found in PE"),
+        }
+    }
+
+    if args.version_info {
+        match pe.resource_table.as_ref().and_then(|rt| rt.version_info()) {
+            Some(version_info) => writer.write(version_info.dump()),
+            None => writer.write_missing("Version Info", "No version information resource found in PE"),
+        }
+    }
+
+    if args.manifest {
+        match pe.resource_table.as_ref().and_then(|rt| rt.manifest()) {
+            Some(manifest) => match args.output {
+                Some(ref output) => match std::fs::write(output, &manifest.xml) {
+                    Ok(()) => {
+                        let mut dump = manifest.dump(false);
+                        dump.push_field("Output", output.display().to_string(), None);
+                        writer.write(dump);
+                    },
+                    Err(e) => writer.write_missing("Manifest", &format!("Cannot write {}: {}", output.display(), e)),
+                },
+                None => writer.write(manifest.dump(true)),
+            },
+            None => writer.write_missing("Manifest", "No manifest resource found in PE"),
+        }
+    }
+
+    if args.resource_strings {
+        let string_tables = pe.resource_table.as_ref().map(|rt| rt.string_tables()).unwrap_or_default();
+
+        if string_tables.is_empty() {
+            writer.write_missing("String Tables", "No string table resource found in PE");
+        } else {
+            let mut dump = Dump::new_from_string(format!("String Tables ({} languages)", string_tables.len()));
+
+            for string_table in string_tables.iter() {
+                dump.push_child(string_table.dump());
+            }
+
+            writer.write(dump);
+        }
+    }
+
+    if args.resource_stats {
+        if let Some(ref rt) = pe.resource_table {
+            writer.write(rt.dump_stats());
+        } else {
+            writer.write_missing("Resource Statistics", "No resources found in PE");
+        }
+    }
+
+    if args.signature {
+        match authenticode::pe_signature(pe) {
+            Ok(signature) => writer.write(signature.dump()),
+            Err(e) => writer.write_missing("Authenticode Signature", &e.to_string()),
+        }
+    }
+
+    if args.mitigations {
+        writer.write(MitigationsReport::from_pe(pe).dump());
+    }
+
+    if args.timestamps {
+        writer.write(TimestampReport::from_pe(pe).dump());
+    }
+
+    if args.capabilities || args.attack {
+        if is_efi {
+            writer.write_missing("Capabilities", "EFI image, the capabilities are inferred from Windows API imports");
+        } else {
+            writer.write(capabilities::infer_pe_capabilities(pe).dump(args.attack));
+        }
+    }
+
+    if args.tls {
+        if let Some(ref tls) = pe.tls_directory {
+            writer.write(tls.dump(pe, args.disasm));
+        } else {
+            writer.write_missing("TLS Directory", "No TLS Directory found in PE");
+        }
+    }
+
+    if args.load_config {
+        match pe.load_config {
+            Some(ref load_config) => writer.write(load_config.dump()),
+            None => writer.write_missing("Load Config Directory", "No Load Config Directory found in PE"),
+        }
+    }
+
+    if args.cfg {
+        match pe.load_config {
+            Some(ref load_config) if !load_config.guard_cf_functions.is_empty() => writer.write(load_config.dump_guard_cf_functions(pe)),
+            _ => writer.write_missing("Control Flow Guard", "No Control Flow Guard function table found in PE"),
+        }
+    }
+
+    if args.safeseh {
+        match pe.load_config {
+            Some(ref load_config) if load_config.se_handler_table != 0 => writer.write(load_config.dump_se_handlers(pe)),
+            _ if !pe.is_32_bits() => writer.write_missing("SafeSEH", "64-bit image, the exception handlers are found through the exception table"),
+            _ => writer.write_missing("SafeSEH", "No SafeSEH handler table found in PE"),
+        }
+    }
+
+    if args.arm64x {
+        let file_path = args.file_path.clone().unwrap_or_default();
+
+        match std::fs::read(&file_path) {
+            Ok(bytes) if arm64x::is_hybrid(pe) => writer.write(Arm64XReport::from_pe(pe, &bytes).dump()),
+            Ok(_) => writer.write_missing("ARM64X Hybrid Image", "No ARM64X dynamic relocations nor ARM64EC metadata in the load config"),
+            Err(e) => writer.write_missing("ARM64X Hybrid Image", &e.to_string()),
+        }
+    }
+
+    if args.relocs {
+        match pe.base_relocation_table {
+            Some(ref relocations) => writer.write(relocations.dump(pe)),
+            None => writer.write_missing("Base Relocations", "No Base Relocation Table found in PE"),
+        }
+    }
+
+    if args.symbols {
+        match pe.coff_symbol_table {
+            Some(ref symbol_table) => writer.write(symbol_table.dump(&pe.section_names_by_index())),
+            None => writer.write_missing("COFF Symbols", "No COFF symbol table found in PE"),
+        }
+    }
+
+    if args.anti_analysis {
+        writer.write(analysis::detect_pe_anti_analysis(pe).dump());
+    }
+
+    if args.packed {
+        writer.write(analysis::analyze_pe_packing(pe).dump());
+    }
+
+    if args.embedded {
+        writer.write(analysis::find_pe_embedded_files(pe).dump());
+    }
+
+    if args.function_hashes || args.compare_functions.is_some() {
+        dump_functions(functions::discover_pe_functions(pe), args, writer);
     }
 }
 
-pub fn dump_elf(elf: &ELF, args: &Args) {
+pub fn dump_elf(elf: &ELF, args: &Args, writer: &mut DumpWriter) {
     if args.elf_header {
-        elf.headers.elf_header.dump().print(0, args.padding_size);
+        writer.write(elf.headers.elf_header.dump());
     }
 
     if args.elf_program_headers {
         for header in elf.headers.program_headers.iter() {
-            header.dump().print(0, args.padding_size);
-            println!("");
+            writer.write(header.dump());
+            writer.write_separator();
         }
     }
 
     if args.sections {
-        let sections_filter_regex = Regex::new(&args.sections_filter.as_str()).expect("Invalid regular expression");
+        let sections_filter_regex = Regex::new(args.sections_filter.as_str()).expect("Invalid regular expression");
 
-        println!("Sections ({})", elf.sections.len());
-        println!("");
+        /* Non-allocated sections have no address, they come after the others in file order */
+        let mut sections: Vec<_> = elf
+            .sections
+            .values()
+            .filter(|section| sections_filter_regex.is_match(section.name.as_str()))
+            .collect();
 
-        for (_, section) in elf.sections.iter() {
-            if sections_filter_regex.is_match(section.name.as_str()) {
-                section.dump(elf, args.sections_data, args.disasm).print(0, args.padding_size);
-                println!("");
-            }
-        }
+        sections.sort_by_key(|section| (section.header.virtual_address() == 0, section.header.virtual_address(), section.offset(), section.name.clone()));
+
+        let sections = sections
+            .into_iter()
+            .map(|section| {
+                let mut dump = section.dump(elf, args.sections_data, args.disasm && args.disasm_format == DisasmFormat::Execdump);
+                dump.truncate_raw_bytes(args.max_bytes);
+                dump
+            })
+            .collect();
+
+        writer.write_list(format!("Sections ({})", elf.sections.len()), sections, true);
+    }
+
+    if args.elf_symbols {
+        writer.write(elf.dump_symbols());
     }
 
     if args.elf_headers {
-        elf.headers.elf_header.dump().print(0, args.padding_size);
+        writer.write(elf.headers.elf_header.dump());
 
-        println!("");
+        writer.write_separator();
 
         for header in elf.headers.program_headers.iter() {
-            header.dump().print(0, args.padding_size);
-            println!("");
+            writer.write(header.dump());
+            writer.write_separator();
         }
     }
+
+    if args.anti_analysis {
+        writer.write(analysis::detect_elf_anti_analysis(elf).dump());
+    }
+
+    if args.packed {
+        writer.write(analysis::analyze_elf_packing(elf).dump());
+    }
+
+    if args.embedded {
+        writer.write(analysis::find_elf_embedded_files(elf).dump());
+    }
+
+    if args.function_hashes || args.compare_functions.is_some() {
+        dump_functions(functions::discover_elf_functions(elf), args, writer);
+    }
 }
 
-pub fn dump_exec(exec: &Exec, args: &Args) {
+/// Fails after the dump when a check reported failures (--missing-deps)
+pub fn dump_exec(exec: &Exec, args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let title = args.file_path.as_ref().map(|p| p.display().to_string()).unwrap_or_default();
+    let mut writer = DumpWriter::new(args.output_format, args.padding_size, &title);
+
+    if args.summary {
+        let file_path = args.file_path.clone().unwrap_or_default();
+
+        match std::fs::read(&file_path) {
+            Ok(bytes) => writer.write(Summary::from_exec(exec, &bytes).dump()),
+            Err(e) => writer.write_missing("Summary", &e.to_string()),
+        }
+    }
+
+    if args.hashes {
+        let file_path = args.file_path.clone().unwrap_or_default();
+
+        match std::fs::read(&file_path) {
+            Ok(bytes) => writer.write(FileHashes::compute(exec, &bytes).dump()),
+            Err(e) => writer.write_missing("Hashes", &e.to_string()),
+        }
+    }
+
+    if args.fuzzy_hashes {
+        let file_path = args.file_path.clone().unwrap_or_default();
+
+        match std::fs::read(&file_path) {
+            Ok(bytes) => writer.write(FileFuzzyHashes::compute(exec, &bytes).dump()),
+            Err(e) => writer.write_missing("Fuzzy Hashes", &e.to_string()),
+        }
+    }
+
+    if args.buildinfo {
+        let file_path = args.file_path.clone().unwrap_or_default();
+
+        match std::fs::read(&file_path) {
+            Ok(bytes) => {
+                let build_info = BuildInfo::from_exec(exec, &bytes);
+
+                match build_info.is_empty() {
+                    true => writer.write_missing("Build Info", "No Go, Rust or Delphi metadata found"),
+                    false => writer.write(build_info.dump()),
+                }
+            }
+            Err(e) => writer.write_missing("Build Info", &e.to_string()),
+        }
+    }
+
+    if let Some(ref address) = args.whereis {
+        let file_path = args.file_path.clone().unwrap_or_default();
+        let file_size = std::fs::metadata(&file_path).map(|m| m.len()).unwrap_or(0);
+
+        match AddressLocation::locate(exec, address, file_size) {
+            Ok(location) => writer.write(location.dump()),
+            Err(e) => writer.write_missing("Where Is", &e.to_string()),
+        }
+    }
+
+    if args.lint {
+        let file_path = args.file_path.clone().unwrap_or_default();
+
+        match (exec, std::fs::read(&file_path)) {
+            (Exec::PE(pe), _) if pe.te_header.is_some() => writer.write_missing("Lint", "TE image, the structural rules check the PE headers it strips"),
+            (Exec::PE(pe), Ok(bytes)) => writer.write(LintReport::from_pe(pe, &bytes).dump()),
+            (Exec::PE(_), Err(e)) => writer.write_missing("Lint", &e.to_string()),
+            (Exec::ELF(_), _) => writer.write_missing("Lint", "The structural rules only cover PE files"),
+        }
+    }
+
+    if let Some(ref range) = args.hexdump {
+        let file_path = args.file_path.clone().unwrap_or_default();
+
+        match std::fs::read(&file_path).map_err(|e| e.into()).and_then(|bytes| address::dump_hex_range(exec, range, &bytes)) {
+            Ok(dump) => writer.write(dump),
+            Err(e) => writer.write_missing("Hexdump", &e.to_string()),
+        }
+    }
+
     match exec {
-        Exec::PE(pe) => dump_pe(pe, args),
-        Exec::ELF(elf) => dump_elf(elf, args),
+        Exec::PE(pe) => dump_pe(pe, args, &mut writer),
+        Exec::ELF(elf) => dump_elf(elf, args, &mut writer),
+    }
+
+    if args.disasm && args.disasm_format == DisasmFormat::Objdump {
+        let file_name = args.file_path.as_ref().map(|p| p.display().to_string()).unwrap_or_default();
+
+        let listing = match exec {
+            Exec::PE(pe) => disasm::objdump_pe_listing(pe, &file_name),
+            Exec::ELF(elf) => disasm::objdump_elf_listing(elf, &file_name),
+        };
+
+        match listing {
+            Ok(lines) => writer.write_raw("Disassembly", lines),
+            Err(e) => writer.write_missing("Disassembly", &e.to_string()),
+        }
+    }
+
+    if let Some(ref pattern) = args.extract_section {
+        let file_path = args.file_path.clone().unwrap_or_default();
+
+        match extract::extract_sections(exec, &file_path, pattern, &args.output_dir, args.extract_virtual_size) {
+            Ok(report) => writer.write(report.dump()),
+            Err(e) => writer.write_missing("Extracted Sections", &e.to_string()),
+        }
+    }
+
+    let mut errors = Vec::new();
+
+    /* An incompatible DLL fails the command, as a release check would */
+    if let Some(ref reference_path) = args.compare_exports {
+        match (exec, parse_exec_with(reference_path, false)) {
+            (Exec::PE(pe), Ok(Exec::PE(reference))) => {
+                let comparison = ExportComparison::compare(&reference, pe);
+
+                if !comparison.is_compatible() {
+                    errors.push(format!("{} of the reference exports are removed or renumbered", comparison.removed.len() + comparison.ordinal_changes.len()));
+                }
+
+                writer.write(comparison.dump());
+            }
+            (Exec::PE(_), Ok(Exec::ELF(_))) => writer.write_missing("Export Comparison", "The reference is not a PE file"),
+            (Exec::PE(_), Err(e)) => writer.write_missing("Export Comparison", &e.to_string()),
+            (Exec::ELF(_), _) => writer.write_missing("Export Comparison", "Only the exports of PE files are compared"),
+        }
+    }
+
+    if args.missing_deps {
+        let exec_path = args.file_path.clone().unwrap_or_default();
+        let exec_name = exec_path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+
+        match exec {
+            Exec::PE(pe) if efi::is_efi(pe) => writer.write_missing("Missing Dependencies", "EFI image, the firmware loads it without resolving DLLs"),
+            Exec::PE(pe) => {
+                let report = MissingDependencies::from_tree(&DependencyTree::resolve(pe, &exec_path, &args.deps_path), &exec_name);
+                if report.failures() > 0 {
+                    errors.push(format!("{} of the dependencies cannot be resolved", report.failures()));
+                }
+
+                writer.write(report.dump());
+            }
+            Exec::ELF(_) => writer.write_missing("Missing Dependencies", "Only the DLLs of PE files are resolved"),
+        }
+    }
+
+    writer.finish();
+
+    if !errors.is_empty() {
+        return Err(errors.join(", ").into());
+    }
+
+    return Ok(());
+}
+
+pub fn dump_diff(diff: &ExecDiff, args: &DiffArgs) {
+    let title = format!("{} -> {}", args.old_file_path.display(), args.new_file_path.display());
+    let mut writer = DumpWriter::new(args.output_format, args.padding_size, &title);
+
+    writer.write(diff.dump());
+
+    writer.finish();
+}
+
+pub fn dump_link_map(directory: &Path, args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let link_map = LinkMap::from_directory(directory)?;
+
+    let title = directory.display().to_string();
+    let mut writer = DumpWriter::new(args.output_format, args.padding_size, &title);
+
+    writer.write(link_map.dump());
+
+    writer.finish();
+
+    return Ok(());
+}
+
+/// Fails after the report when an address cannot be converted
+pub fn dump_addresses(args: &AddrArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let exec = parse_exec_with(&args.file_path, false)?;
+    let file_size = std::fs::metadata(&args.file_path)?.len();
+
+    let conversions = AddressConversions::from_args(&exec, args, file_size);
+
+    let title = args.file_path.display().to_string();
+    let mut writer = DumpWriter::new(args.output_format, args.padding_size, &title);
+
+    writer.write(conversions.dump());
+
+    writer.finish();
+
+    if conversions.failures() > 0 {
+        return Err(format!("{} of the addresses cannot be converted", conversions.failures()).into());
+    }
+
+    return Ok(());
+}
+
+/// Fails when a module to extract is not in the minidump
+pub fn dump_minidump(args: &MinidumpArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let bytes = std::fs::read(&args.file_path)?;
+    let minidump = Minidump::from_bytes(&bytes)?;
+
+    let title = args.file_path.display().to_string();
+    let mut writer = DumpWriter::new(args.output_format, args.padding_size, &title);
+
+    writer.write(minidump.dump());
+
+    let mut failures = 0;
+
+    for name in args.extract.iter() {
+        match minidump.extract_module_to(&bytes, name, &args.output_dir) {
+            Ok(dump) => writer.write(dump),
+            Err(e) => {
+                writer.write_missing("Extracted Module", &e.to_string());
+                failures += 1;
+            }
+        }
     }
+
+    writer.finish();
+
+    if failures > 0 {
+        return Err(format!("{} of the modules cannot be extracted", failures).into());
+    }
+
+    return Ok(());
+}
+
+pub fn dump_legacy(legacy: &LegacyExecutable, args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let title = args.file_path.as_ref().map(|p| p.display().to_string()).unwrap_or_default();
+    let mut writer = DumpWriter::new(args.output_format, args.padding_size, &title);
+
+    writer.write(legacy.dump());
+    writer.finish();
+
+    return Ok(());
+}
+
+pub fn dump_carve(args: &CarveArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let bytes = std::fs::read(&args.file_path)?;
+    let mut report = CarveReport::scan(&bytes);
+
+    if args.extract {
+        report.extract(&bytes, &args.file_path, &args.output_dir)?;
+    }
+
+    let title = args.file_path.display().to_string();
+    let mut writer = DumpWriter::new(args.output_format, args.padding_size, &title);
+
+    writer.write(report.dump());
+
+    writer.finish();
+
+    return Ok(());
+}
+
+/// Files that are not PE or ELF executables are scanned too, without the section of the strings
+pub fn dump_strings(args: &StringsArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let bytes = std::fs::read(&args.file_path)?;
+    let exec = parse_exec_with(&args.file_path, false).ok();
+
+    let filter = match args.filter {
+        Some(ref filter) => Some(Regex::new(filter)?),
+        None => None,
+    };
+
+    let report = StringsReport::extract(&bytes, exec.as_ref(), args.encoding, args.min_length, filter.as_ref());
+
+    let title = args.file_path.display().to_string();
+    let mut writer = DumpWriter::new(args.output_format, args.padding_size, &title);
+
+    writer.write(report.dump());
+
+    writer.finish();
+
+    return Ok(());
+}
+
+/// Report of the subcommands modifying an executable
+pub fn dump_edit(file_path: &Path, padding_size: usize, output_format: OutputFormat, dump: Dump) -> Result<(), Box<dyn std::error::Error>> {
+    let title = file_path.display().to_string();
+    let mut writer = DumpWriter::new(output_format, padding_size, &title);
+
+    writer.write(dump);
+
+    writer.finish();
+
+    return Ok(());
 }
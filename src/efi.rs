@@ -0,0 +1,285 @@
+use crate::dump::Dump;
+use crate::pe::{BaseRelocationType, CharacteristicsFlag, DLLCharacteristicsFlags, MachineType, SectionFlags, Subsystem, PE};
+
+/*
+ * UEFI image specific analysis (applications, boot service and runtime drivers, option ROMs)
+ */
+
+pub const EFI_ENTRY_POINT_PROTOTYPE: &str = "EFI_STATUS EFIAPI (EFI_HANDLE ImageHandle, EFI_SYSTEM_TABLE *SystemTable)";
+
+/* Section alignment runtime drivers need so the OS can map their pages with their own attributes */
+const RUNTIME_DRIVER_ALIGNMENT: u32 = 0x1000;
+const RUNTIME_DRIVER_ALIGNMENT_AARCH64: u32 = 0x10000;
+
+pub fn is_efi_subsystem(subsystem: Subsystem) -> bool {
+    return matches!(
+        subsystem,
+        Subsystem::EfiApplication | Subsystem::EfiBootServiceDriver | Subsystem::EfiRuntimeDriver | Subsystem::EfiRom
+    );
+}
+
+pub fn is_efi(pe: &PE) -> bool {
+    return is_efi_subsystem(pe.get_optional_header().get_subsystem());
+}
+
+/// Architecture name used by the UEFI specification and the default boot file of the removable media
+pub fn efi_architecture(machine: MachineType) -> Option<(&'static str, Option<&'static str>)> {
+    match machine {
+        MachineType::I386 => return Some(("IA32", Some("\\EFI\\BOOT\\BOOTIA32.EFI"))),
+        MachineType::AMD64 => return Some(("X64", Some("\\EFI\\BOOT\\BOOTX64.EFI"))),
+        MachineType::IA64 => return Some(("IA64", Some("\\EFI\\BOOT\\BOOTIA64.EFI"))),
+        MachineType::ARMNT => return Some(("ARM", Some("\\EFI\\BOOT\\BOOTARM.EFI"))),
+        MachineType::ARM64 => return Some(("AARCH64", Some("\\EFI\\BOOT\\BOOTAA64.EFI"))),
+        MachineType::RISCV32 => return Some(("RISCV32", Some("\\EFI\\BOOT\\BOOTRISCV32.EFI"))),
+        MachineType::RISCV64 => return Some(("RISCV64", Some("\\EFI\\BOOT\\BOOTRISCV64.EFI"))),
+        MachineType::RISCV128 => return Some(("RISCV128", Some("\\EFI\\BOOT\\BOOTRISCV128.EFI"))),
+        MachineType::LOONGARCH32 => return Some(("LOONGARCH32", Some("\\EFI\\BOOT\\BOOTLOONGARCH32.EFI"))),
+        MachineType::LOONGARCH64 => return Some(("LOONGARCH64", Some("\\EFI\\BOOT\\BOOTLOONGARCH64.EFI"))),
+        /* EFI byte code is interpreted, it runs on every architecture but cannot be a default boot file */
+        MachineType::EBC => return Some(("EBC", None)),
+        _ => return None,
+    }
+}
+
+fn image_kind(subsystem: Subsystem) -> &'static str {
+    match subsystem {
+        Subsystem::EfiApplication => return "Application, unloaded when its entry point returns",
+        Subsystem::EfiBootServiceDriver => return "Boot service driver, unloaded at ExitBootServices()",
+        Subsystem::EfiRuntimeDriver => return "Runtime driver, stays mapped for the OS after ExitBootServices()",
+        Subsystem::EfiRom => return "Option ROM image",
+        _ => return "Not an EFI image",
+    }
+}
+
+/// Entry of the .sbat section read by shim to revoke vulnerable bootloaders by generation
+#[derive(Debug, Clone, Default)]
+pub struct SbatEntry {
+    pub component_name: String,
+    pub component_generation: String,
+    pub vendor_name: String,
+    pub vendor_package_name: String,
+    pub vendor_version: String,
+    pub vendor_url: String,
+}
+
+impl SbatEntry {
+    /// "component_name,component_generation,vendor_name,vendor_package_name,vendor_version,vendor_url" lines
+    pub fn parse_section(data: &[u8]) -> Vec<SbatEntry> {
+        let text = String::from_utf8_lossy(data);
+
+        return text
+            .lines()
+            .map(|line| line.trim_matches(|c: char| c == '\0' || c.is_whitespace()))
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let mut fields = line.splitn(6, ',').map(|f| f.trim().to_string());
+
+                return SbatEntry {
+                    component_name: fields.next().unwrap_or_default(),
+                    component_generation: fields.next().unwrap_or_default(),
+                    vendor_name: fields.next().unwrap_or_default(),
+                    vendor_package_name: fields.next().unwrap_or_default(),
+                    vendor_version: fields.next().unwrap_or_default(),
+                    vendor_url: fields.next().unwrap_or_default(),
+                };
+            })
+            .collect();
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EfiSection {
+    pub name: String,
+    pub virtual_address: u32,
+    pub size: u32,
+    pub characteristics: u32,
+}
+
+impl EfiSection {
+    pub fn is_writable_executable(&self) -> bool {
+        let wx = SectionFlags::MemWrite as u32 | SectionFlags::MemExecute as u32;
+
+        return self.characteristics & wx == wx;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EfiReport {
+    pub subsystem: &'static str,
+    pub kind: &'static str,
+    pub architecture: Option<&'static str>,
+    pub boot_file: Option<&'static str>,
+    pub entry_point: u64,
+    pub entry_point_section: Option<String>,
+    pub section_alignment: u32,
+    pub relocations: usize,
+    pub relocs_stripped: bool,
+    pub nx_compat: bool,
+    pub embedded_signature: bool,
+    pub imported_dlls: Vec<String>,
+    pub sections: Vec<EfiSection>,
+    /// None when the image has no .sbat section
+    pub sbat: Option<Vec<SbatEntry>>,
+    pub warnings: Vec<String>,
+}
+
+pub fn analyze_efi(pe: &PE) -> EfiReport {
+    let mut report = EfiReport::default();
+
+    let optional_header = pe.get_optional_header();
+    let subsystem = optional_header.get_subsystem();
+    let machine = MachineType::from(pe.get_nt_header().coff_header.machine);
+    let entry_point = optional_header.get_address_of_entry_point();
+
+    report.subsystem = subsystem.as_static_str();
+    report.kind = image_kind(subsystem);
+
+    if let Some((architecture, boot_file)) = efi_architecture(machine) {
+        report.architecture = Some(architecture);
+        report.boot_file = boot_file;
+    }
+
+    report.entry_point = optional_header.get_image_base() + entry_point as u64;
+    report.entry_point_section = pe.get_section_for_rva(entry_point).filter(|_| entry_point != 0).map(|s| s.header.name.clone());
+    report.section_alignment = optional_header.get_section_alignment();
+    report.relocs_stripped = pe.get_nt_header().coff_header.characteristics & CharacteristicsFlag::RelocsStripped as u16 != 0;
+    report.nx_compat = optional_header.get_dll_characteristics() & DLLCharacteristicsFlags::NXCompat as u16 != 0;
+    report.embedded_signature = optional_header.get_certificate_table_idd().size > 0;
+
+    report.relocations = pe
+        .base_relocation_table
+        .iter()
+        .flat_map(|t| t.blocks.iter())
+        .flat_map(|b| b.entries.iter())
+        .filter(|e| e.get_relocation_type() != BaseRelocationType::Absolute)
+        .count();
+
+    if let Some(ref hnt) = pe.hint_name_table {
+        report.imported_dlls = hnt.entries.iter().map(|dll| dll.dll_name.clone()).collect();
+    }
+
+    report.sections = pe
+        .sections
+        .iter()
+        .map(|s| EfiSection {
+            name: s.header.name.clone(),
+            virtual_address: s.header.virtual_address,
+            size: s.header.virtual_size,
+            characteristics: s.header.characteristics,
+        })
+        .collect();
+
+    report.sbat = pe.get_section(".sbat").map(|s| SbatEntry::parse_section(s.raw_data()));
+
+    if report.architecture.is_none() {
+        report.warnings.push(format!("Machine {:?} is not an architecture supported by UEFI", machine));
+    }
+
+    if entry_point == 0 {
+        report.warnings.push(String::from("No entry point, the firmware has nothing to call after loading the image"));
+    } else if report.entry_point_section.is_none() {
+        report.warnings.push(format!("Entry point {:#x} is outside of the sections", report.entry_point));
+    }
+
+    /* The firmware loads the images wherever it finds free pages, they are relocated unless loaded at their base */
+    if report.relocations == 0 {
+        report.warnings.push(String::from("No base relocations, the image only loads at its preferred base address"));
+    }
+
+    if !report.imported_dlls.is_empty() {
+        report.warnings.push(format!("Imports {}, EFI images get their services from the system table and are not linked against DLLs", report.imported_dlls.join(", ")));
+    }
+
+    /* TE images do not keep their section alignment */
+    if subsystem == Subsystem::EfiRuntimeDriver && pe.te_header.is_none() {
+        let alignment = match machine {
+            MachineType::ARM64 => RUNTIME_DRIVER_ALIGNMENT_AARCH64,
+            _ => RUNTIME_DRIVER_ALIGNMENT,
+        };
+
+        if report.section_alignment < alignment {
+            report.warnings.push(format!("Section alignment {:#x} is below the {:#x} runtime drivers need to be remapped by the OS", report.section_alignment, alignment));
+        }
+    }
+
+    for section in report.sections.iter().filter(|s| s.is_writable_executable()) {
+        report.warnings.push(format!("Section {} is writable and executable, firmware enforcing W^X refuses to load it", section.name));
+    }
+
+    if !report.nx_compat {
+        report.warnings.push(String::from("NX_COMPAT is not set, the image cannot run with the firmware memory protections enabled"));
+    }
+
+    if !report.embedded_signature {
+        report.warnings.push(String::from("No embedded signature, Secure Boot refuses to run the image unless its hash is enrolled in db"));
+    }
+
+    return report;
+}
+
+impl EfiReport {
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("EFI Image");
+
+        dump.push_field("Subsystem", String::from(self.subsystem), None);
+        dump.push_field("Kind", String::from(self.kind), None);
+        dump.push_field("Architecture", String::from(self.architecture.unwrap_or("Unknown")), None);
+
+        if let Some(boot_file) = self.boot_file {
+            dump.push_field("DefaultBootFile", String::from(boot_file), Some("loaded from removable media without a boot entry"));
+        }
+
+        let entry_point_section = self.entry_point_section.clone().unwrap_or_else(|| String::from("outside of the sections"));
+
+        dump.push_field("EntryPoint", format!("{:#x} ({})", self.entry_point, entry_point_section), Some(EFI_ENTRY_POINT_PROTOTYPE));
+        dump.push_field("SectionAlignment", format!("{:#x}", self.section_alignment), None);
+        dump.push_field("Relocations", format!("{}", self.relocations), None);
+        dump.push_field("RelocsStripped", format!("{}", self.relocs_stripped), None);
+        dump.push_field("NxCompat", format!("{}", self.nx_compat), Some("firmware memory protections"));
+        dump.push_field("EmbeddedSignature", format!("{}", self.embedded_signature), Some("Secure Boot"));
+
+        let mut sections_dump = Dump::new_from_string(format!("Sections ({})", self.sections.len()));
+
+        for section in self.sections.iter() {
+            let mut properties = Vec::new();
+
+            if section.characteristics & SectionFlags::MemRead as u32 != 0 { properties.push("R"); }
+            if section.characteristics & SectionFlags::MemWrite as u32 != 0 { properties.push("W"); }
+            if section.characteristics & SectionFlags::MemExecute as u32 != 0 { properties.push("X"); }
+            if section.virtual_address % self.section_alignment.max(1) != 0 { properties.push("misaligned"); }
+
+            sections_dump.push_field("", format!("{:<8} rva: {:#x} size: {:#x} ({})", section.name, section.virtual_address, section.size, properties.join("")), None);
+        }
+
+        dump.push_child(sections_dump);
+
+        if let Some(ref sbat) = self.sbat {
+            let mut sbat_dump = Dump::new_from_string(format!("SBAT ({})", sbat.len()));
+
+            for entry in sbat.iter() {
+                let mut entry_dump = Dump::new(&entry.component_name);
+
+                entry_dump.push_field("Generation", entry.component_generation.clone(), None);
+                entry_dump.push_field("VendorName", entry.vendor_name.clone(), None);
+                entry_dump.push_field("VendorPackageName", entry.vendor_package_name.clone(), None);
+                entry_dump.push_field("VendorVersion", entry.vendor_version.clone(), None);
+                entry_dump.push_field("VendorUrl", entry.vendor_url.clone(), None);
+
+                sbat_dump.push_child(entry_dump);
+            }
+
+            dump.push_child(sbat_dump);
+        }
+
+        let mut warnings_dump = Dump::new_from_string(format!("Warnings ({})", self.warnings.len()));
+
+        for warning in self.warnings.iter() {
+            warnings_dump.push_field("", warning.clone(), None);
+        }
+
+        dump.push_child(warnings_dump);
+
+        return dump;
+    }
+}
@@ -1,9 +1,10 @@
-use crate::{disasm::disasm_elf_code, dump::{Dump, DumpRawData}, reader::{BEReader, LEReader, Reader}};
+use crate::{analysis::describe_section_entropy, demangle::{display_name, label_name}, disasm::disasm_elf_code, dump::{Dump, DumpRawData}, reader::{decode_name, ExecReader, FileReader}};
 
 use strum::IntoEnumIterator;
 use strum_macros::{EnumIter, IntoStaticStr};
 
-use std::{collections::HashMap, fmt::Display, ops::BitAnd, path::PathBuf};
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use std::{collections::HashMap, fmt::Display, path::Path};
 
 pub const ELF_MAGIC: u32 = 0x7f454c46;
 pub const ELF_MAGIC_ARRAY: [u8; 4] = [0x7F, b'E', b'L', b'F'];
@@ -92,7 +93,7 @@ impl From<u8> for ELFOsAbi {
  */
 
 #[repr(u16)]
-#[derive(Clone, Debug, IntoStaticStr)]
+#[derive(Clone, Copy, Debug, EnumIter, IntoStaticStr)]
 #[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
 pub enum ELFTargetISA {
     /// No specific instruction set
@@ -170,6 +171,13 @@ pub enum ELFTargetISA {
     LoongArch = 0x102,
 }
 
+impl ELFTargetISA {
+    /// Instruction set of an e_machine value, None for the values missing from the enum
+    pub fn from_machine(machine: u16) -> Option<ELFTargetISA> {
+        return ELFTargetISA::iter().find(|isa| *isa as u16 == machine);
+    }
+}
+
 /*
  * Elf File Type (e_type in elf header)
  */
@@ -298,7 +306,7 @@ pub struct ELFHeader32 {
 }
 
 impl ELFHeader32 {
-    pub fn from_parser(reader: &mut Reader) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn from_parser<E: ByteOrder>(reader: &mut ExecReader<E>) -> Result<Self, Box<dyn std::error::Error>> {
         let mut header = Self::default();
 
         header.ei_mag = reader.read_n()?;
@@ -418,7 +426,7 @@ pub struct ELFHeader64 {
 }
 
 impl ELFHeader64 {
-    pub fn from_parser(reader: &mut Reader) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn from_parser<E: ByteOrder>(reader: &mut ExecReader<E>) -> Result<Self, Box<dyn std::error::Error>> {
         let mut header = Self::default();
 
         header.ei_mag = reader.read_n()?;
@@ -487,8 +495,8 @@ impl Default for ELFHeader {
 }
 
 impl ELFHeader {
-    pub fn from_parser(reader: &mut Reader) -> Result<Self, Box<dyn std::error::Error>> {
-        let class_byte = reader.peek_at::<4>()?;
+    pub fn from_parser<E: ByteOrder>(reader: &mut ExecReader<E>) -> Result<Self, Box<dyn std::error::Error>> {
+        let class_byte = reader.peek_n::<5>()?[4];
 
         match class_byte {
             1 => Ok(Self::ELFHeader32(ELFHeader32::from_parser(reader)?)),
@@ -497,6 +505,27 @@ impl ELFHeader {
         }
     }
 
+    pub fn entry_point(&self) -> u64 {
+        match self {
+            Self::ELFHeader32(h) => h.e_entry as u64,
+            Self::ELFHeader64(h) => h.e_entry,
+        }
+    }
+
+    pub fn machine(&self) -> u16 {
+        match self {
+            Self::ELFHeader32(h) => h.e_machine,
+            Self::ELFHeader64(h) => h.e_machine,
+        }
+    }
+
+    pub fn file_type(&self) -> ELFFileType {
+        match self {
+            Self::ELFHeader32(h) => ELFFileType::from(h.e_type),
+            Self::ELFHeader64(h) => ELFFileType::from(h.e_type),
+        }
+    }
+
     pub fn program_headers_offset(&self) -> u64 {
         match self {
             Self::ELFHeader32(h) => h.e_phoff as u64,
@@ -539,6 +568,13 @@ impl ELFHeader {
         }
     }
 
+    pub fn is_big_endian(&self) -> bool {
+        match self {
+            Self::ELFHeader32(h) => h.ei_data == ELFEndianness::Big as u8,
+            Self::ELFHeader64(h) => h.ei_data == ELFEndianness::Big as u8,
+        }
+    }
+
     pub fn shstr_index(&self) -> usize {
         match self {
             Self::ELFHeader32(h) => h.e_shstrndx as usize,
@@ -725,7 +761,7 @@ pub struct ELFProgramHeader32 {
 }
 
 impl ELFProgramHeader32 {
-    pub fn from_reader(reader: &mut Reader) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn from_reader<E: ByteOrder>(reader: &mut ExecReader<E>) -> Result<Self, Box<dyn std::error::Error>> {
         let mut header = Self::default();
 
         header.p_type = reader.read_u32()?;
@@ -786,7 +822,7 @@ pub struct ELFProgramHeader64 {
 }
 
 impl ELFProgramHeader64 {
-    pub fn from_reader(reader: &mut Reader) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn from_reader<E: ByteOrder>(reader: &mut ExecReader<E>) -> Result<Self, Box<dyn std::error::Error>> {
         let mut header = Self::default();
 
         header.p_type = reader.read_u32()?;
@@ -1005,7 +1041,6 @@ impl std::fmt::Display for SectionType {
             SectionType::GnuVerneed => write!(f, "SHT_GNU_VERNEED"),
             SectionType::GnuVersym => write!(f, "SHT_GNU_VERSYM"),
             SectionType::Num => write!(f, "SHT_NUM"),
-            _ => write!(f, "OS/Architecture specific")
         }
     }
 }
@@ -1049,7 +1084,7 @@ pub struct ELFSectionHeader32 {
 }
 
 impl ELFSectionHeader32 {
-    pub fn from_reader(reader: &mut Reader) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn from_reader<E: ByteOrder>(reader: &mut ExecReader<E>) -> Result<Self, Box<dyn std::error::Error>> {
         let mut header = Self::default();
 
         header.sh_name = reader.read_u32()?;
@@ -1120,7 +1155,7 @@ pub struct ELFSectionHeader64 {
 }
 
 impl ELFSectionHeader64 {
-    pub fn from_reader(reader: &mut Reader) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn from_reader<E: ByteOrder>(reader: &mut ExecReader<E>) -> Result<Self, Box<dyn std::error::Error>> {
         let mut header = Self::default();
 
         header.sh_name = reader.read_u32()?;
@@ -1172,7 +1207,7 @@ impl ELFSectionHeader {
 
     pub fn flags(&self) -> u64 {
         match &self {
-            ELFSectionHeader::ELFSectionHeader32(h) => (h.sh_flags as u64),
+            ELFSectionHeader::ELFSectionHeader32(h) => h.sh_flags as u64,
             ELFSectionHeader::ELFSectionHeader64(h) => h.sh_flags,
         }
     }
@@ -1229,6 +1264,8 @@ impl ELFSection {
     pub fn dump(&self, elf: &ELF, data: bool, disasm_code: bool) -> Dump {
         let mut dump = Dump::new_from_string(format!("Section ({})", self.name));
 
+        dump.push_field("Entropy", describe_section_entropy(&self.data, false), None);
+
         match &self.header {
             ELFSectionHeader::ELFSectionHeader32(h) => dump.push_child(h.dump()),
             ELFSectionHeader::ELFSectionHeader64(h) => dump.push_child(h.dump()),
@@ -1255,6 +1292,148 @@ impl ELFSection {
     }
 }
 
+/* Symbols */
+
+/*
+ * Symbol binding and type (st_info in symbol table entries)
+ */
+
+pub const STB_LOCAL: u8 = 0;
+pub const STB_GLOBAL: u8 = 1;
+pub const STB_WEAK: u8 = 2;
+
+pub const STT_NOTYPE: u8 = 0;
+pub const STT_OBJECT: u8 = 1;
+pub const STT_FUNC: u8 = 2;
+pub const STT_SECTION: u8 = 3;
+pub const STT_FILE: u8 = 4;
+pub const STT_COMMON: u8 = 5;
+pub const STT_TLS: u8 = 6;
+pub const STT_GNU_IFUNC: u8 = 10;
+
+/// Section index of symbols that are not defined in this file
+pub const SHN_UNDEF: u16 = 0;
+
+#[derive(Clone, Debug, Default)]
+pub struct ELFSymbol {
+    pub name_offset: u32,
+    pub name: String,
+    pub value: u64,
+    pub size: u64,
+    pub info: u8,
+    pub other: u8,
+    pub shndx: u16,
+}
+
+impl ELFSymbol {
+    pub fn from_reader<E: ByteOrder>(reader: &mut ExecReader<E>, class: &ELFClass) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut symbol = ELFSymbol::default();
+
+        /* The name is resolved afterwards, from the string table linked to the symbol table */
+        symbol.name_offset = reader.read_u32()?;
+
+        match class {
+            ELFClass::ELF32 => {
+                symbol.value = reader.read_u32()? as u64;
+                symbol.size = reader.read_u32()? as u64;
+                symbol.info = reader.read_u8()?;
+                symbol.other = reader.read_u8()?;
+                symbol.shndx = reader.read_u16()?;
+            }
+            ELFClass::ELF64 => {
+                symbol.info = reader.read_u8()?;
+                symbol.other = reader.read_u8()?;
+                symbol.shndx = reader.read_u16()?;
+                symbol.value = reader.read_u64()?;
+                symbol.size = reader.read_u64()?;
+            }
+        }
+
+        return Ok(symbol);
+    }
+
+    pub fn entry_size(class: &ELFClass) -> usize {
+        match class {
+            ELFClass::ELF32 => 16,
+            ELFClass::ELF64 => 24,
+        }
+    }
+
+    pub fn binding(&self) -> u8 {
+        return self.info >> 4;
+    }
+
+    pub fn symbol_type(&self) -> u8 {
+        return self.info & 0xF;
+    }
+
+    pub fn is_defined(&self) -> bool {
+        return self.shndx != SHN_UNDEF;
+    }
+
+    /// Global or weak functions and objects, as seen by the dynamic linker
+    pub fn is_exported(&self) -> bool {
+        return self.is_defined() &&
+               (self.binding() == STB_GLOBAL || self.binding() == STB_WEAK) &&
+               (self.symbol_type() == STT_FUNC || self.symbol_type() == STT_OBJECT) &&
+               !self.name.is_empty();
+    }
+
+    pub fn is_imported(&self) -> bool {
+        return !self.is_defined() && self.binding() != STB_LOCAL && !self.name.is_empty();
+    }
+
+    pub fn binding_name(&self) -> &'static str {
+        match self.binding() {
+            STB_LOCAL => return "LOCAL",
+            STB_GLOBAL => return "GLOBAL",
+            STB_WEAK => return "WEAK",
+            _ => return "OTHER",
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self.symbol_type() {
+            STT_NOTYPE => return "NOTYPE",
+            STT_OBJECT => return "OBJECT",
+            STT_FUNC => return "FUNC",
+            STT_SECTION => return "SECTION",
+            STT_FILE => return "FILE",
+            STT_COMMON => return "COMMON",
+            STT_TLS => return "TLS",
+            STT_GNU_IFUNC => return "IFUNC",
+            _ => return "OTHER",
+        }
+    }
+
+    pub fn dump_line(&self) -> String {
+        let section = match self.shndx {
+            SHN_UNDEF => String::from("UND"),
+            shndx => format!("{}", shndx),
+        };
+
+        return format!("{:#018x} {:>8} {:<7} {:<6} {:>5} {}", self.value, self.size, self.type_name(), self.binding_name(), section, display_name(&self.name));
+    }
+}
+
+/*
+ * Dynamic section tags (d_tag in dynamic entries)
+ */
+
+pub const DT_NULL: i64 = 0;
+pub const DT_NEEDED: i64 = 1;
+pub const DT_SONAME: i64 = 14;
+
+fn read_string_at(strtab: &[u8], offset: usize) -> String {
+    match strtab.get(offset..) {
+        Some(s) => {
+            let nul = s.iter().position(|&b| b == 0).unwrap_or(s.len());
+            return String::from_utf8_lossy(&s[..nul]).to_string();
+        }
+        None => return String::new(),
+    }
+}
+
 /* Headers */
 
 #[derive(Clone, Debug, Default)]
@@ -1269,19 +1448,31 @@ pub struct ELFHeaders {
 pub struct ELF {
     pub headers: ELFHeaders,
     pub sections: HashMap<String, ELFSection>,
+    pub dynamic_symbols: Vec<ELFSymbol>,
+    pub symbols: Vec<ELFSymbol>,
+    pub needed_libraries: Vec<String>,
+    pub soname: Option<String>,
 }
 
 impl ELF {
-    fn parse_headers_and_sections(
+    fn parse<E: ByteOrder>(&mut self, reader: &mut ExecReader<E>) -> Result<(), Box<dyn std::error::Error>> {
+        self.parse_headers_and_sections(reader)?;
+        self.parse_dynamic::<E>()?;
+        self.symbols = self.parse_symbol_table::<E>(".symtab", ".strtab")?;
+
+        return Ok(());
+    }
+
+    fn parse_headers_and_sections<E: ByteOrder>(
         &mut self,
-        reader: &mut Reader
+        reader: &mut ExecReader<E>
     ) -> Result<(), Box<dyn std::error::Error>> {
         self.headers.elf_header = ELFHeader::from_parser(reader)?;
 
         let ph_off = self.headers.elf_header.program_headers_offset();
         let ph_num_entries = self.headers.elf_header.program_headers_num_entries();
 
-        reader.set_position(ph_off as usize)?;
+        reader.set_position(ph_off)?;
 
         for _ in 0..ph_num_entries {
             match self.class() {
@@ -1295,7 +1486,7 @@ impl ELF {
         let sh_off = self.headers.elf_header.section_headers_offset();
         let sh_num_entries = self.headers.elf_header.section_headers_num_entries();
 
-        reader.set_position(sh_off as usize)?;
+        reader.set_position(sh_off)?;
 
         let mut sections = Vec::new();
 
@@ -1307,30 +1498,88 @@ impl ELF {
                     ELFSection::new(ELFSectionHeader::ELFSectionHeader64(ELFSectionHeader64::from_reader(reader)?)),
             };
 
-            let old_position = reader.position();
+            /* NOBITS sections (.bss) occupy no space in the file */
+            if section.header.section_type() != SectionType::Nobits {
+                let size = section.size() as usize;
 
-            reader.set_position(section.offset() as usize)?;
-
-            section.data = reader.read_bytes(section.size() as usize)?.to_vec();
+                section.data = reader.with_position(section.offset(), |r| r.read_bytes(size))?;
+            }
 
             sections.push(section);
-
-            reader.set_position(old_position)?;
         }
 
         let shstrtab_sh = &sections[self.get_elf_header().shstr_index()].clone();
 
         for section in sections.iter_mut() {
             let name_offset = section.header.name_offset() as usize;
-            let name = &shstrtab_sh.data[name_offset..];
+            let name = shstrtab_sh.data.get(name_offset..).unwrap_or(&[]);
             let nul = name.iter().position(|&b| b == 0).unwrap_or(name.len());
-            section.name = String::from_utf8_lossy(&name[..nul]).to_string();
+            section.name = decode_name(&name[..nul]).0;
         }
 
         self.sections = sections.into_iter().map(|s| (s.name.clone(), s)).collect();
 
         return Ok(());
     }
+
+    /// Symbols of a symbol table section, named from its string table section
+    fn parse_symbol_table<E: ByteOrder>(&self, symtab_name: &str, strtab_name: &str) -> Result<Vec<ELFSymbol>, Box<dyn std::error::Error>> {
+        let (Some(symtab), Some(strtab)) = (self.sections.get(symtab_name), self.sections.get(strtab_name)) else {
+            return Ok(Vec::new());
+        };
+
+        let class = self.class();
+
+        let mut reader = ExecReader::<E>::from_bytes(&symtab.data);
+        let mut symbols = Vec::new();
+
+        while reader.remaining() >= ELFSymbol::entry_size(&class) as u64 {
+            let mut symbol = ELFSymbol::from_reader(&mut reader, &class)?;
+            symbol.name = read_string_at(&strtab.data, symbol.name_offset as usize);
+
+            symbols.push(symbol);
+        }
+
+        return Ok(symbols);
+    }
+
+    fn parse_dynamic<E: ByteOrder>(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let dynstr = match self.sections.get(".dynstr") {
+            Some(s) => s.data.clone(),
+            None => return Ok(()),
+        };
+
+        let class = self.class();
+
+        self.dynamic_symbols = self.parse_symbol_table::<E>(".dynsym", ".dynstr")?;
+
+        if let Some(dynamic) = self.sections.get(".dynamic") {
+            let mut reader = ExecReader::<E>::from_bytes(&dynamic.data);
+
+            let mut needed = Vec::new();
+            let mut soname = None;
+
+            loop {
+                let (tag, value) = match class {
+                    ELFClass::ELF32 if reader.remaining() >= 8 => (reader.read_i32()? as i64, reader.read_u32()? as u64),
+                    ELFClass::ELF64 if reader.remaining() >= 16 => (reader.read_i64()?, reader.read_u64()?),
+                    _ => break,
+                };
+
+                match tag {
+                    DT_NULL => break,
+                    DT_NEEDED => needed.push(read_string_at(&dynstr, value as usize)),
+                    DT_SONAME => soname = Some(read_string_at(&dynstr, value as usize)),
+                    _ => {}
+                }
+            }
+
+            self.needed_libraries = needed;
+            self.soname = soname;
+        }
+
+        return Ok(());
+    }
 }
 
 impl ELF {
@@ -1344,32 +1593,91 @@ impl ELF {
             ELFHeader::ELFHeader64(_) => ELFClass::ELF64,
         }
     }
+
+    /// Allocated section at a virtual address
+    pub fn get_section_for_address(&self, address: u64) -> Option<&ELFSection> {
+        return self.sections.values().find(|s| {
+            let start = s.header.virtual_address();
+
+            (s.header.flags() & SectionFlags::Alloc as u64) != 0 && start != 0 && address >= start && address < start + s.size()
+        });
+    }
+
+    /// Defined functions of the symbol tables by address, with their demangled name
+    pub fn function_labels(&self) -> HashMap<u64, String> {
+        let mut labels = HashMap::new();
+
+        /* The dynamic symbols come last, .symtab has the same names and the local functions too */
+        for symbol in self.symbols.iter().chain(self.dynamic_symbols.iter()) {
+            if symbol.symbol_type() == STT_FUNC && symbol.is_defined() && symbol.value != 0 && !symbol.name.is_empty() {
+                labels.entry(symbol.value).or_insert_with(|| label_name(&symbol.name));
+            }
+        }
+
+        return labels;
+    }
+
+    pub fn dump_symbols(&self) -> Dump {
+        let mut dump = Dump::new("Symbols");
+
+        for (label, symbols) in [("Dynamic Symbols", &self.dynamic_symbols), ("Symbol Table", &self.symbols)] {
+            /* The null symbol and the unnamed section symbols are left out */
+            let named: Vec<_> = symbols.iter().filter(|s| !s.name.is_empty()).collect();
+
+            let mut symbols_dump = Dump::new_from_string(format!("{} ({})", label, named.len()));
+
+            for symbol in named {
+                symbols_dump.push_field("", symbol.dump_line(), None);
+            }
+
+            dump.push_child(symbols_dump);
+        }
+
+        return dump;
+    }
 }
 
-pub fn parse_elf(file_path: &PathBuf) -> Result<ELF, Box<dyn std::error::Error>> {
+pub fn parse_elf(file_path: &Path) -> Result<ELF, Box<dyn std::error::Error>> {
     if !file_path.exists() {
         return Err("File does not exist".into());
     }
 
-    let file_bytes = std::fs::read(file_path).expect("Unable to open and read file");
+    let mut file = FileReader::open(file_path)?;
 
-    let magic_bytes = &file_bytes[0..4];
+    /* The byte order is given by the identification bytes, before any multi-byte field */
+    let ident = ExecReader::<LittleEndian>::new(&mut file).peek_n::<6>()?;
 
-    if magic_bytes != ELF_MAGIC_ARRAY {
-        return Err("File magic number does not match ELF magic number".into());
+    let mut elf = ELF::default();
+
+    match check_ident(&ident)? {
+        1 => elf.parse::<LittleEndian>(&mut ExecReader::new(&mut file))?,
+        _ => elf.parse::<BigEndian>(&mut ExecReader::new(&mut file))?,
     }
 
-    let e_data = file_bytes[5];
+    return Ok(elf);
+}
 
-    let mut reader = match e_data {
-        1 => Reader::LittleEndian(LEReader::new(&file_bytes)),
-        2 => Reader::BigEndian(BEReader::new(&file_bytes)),
-        _ => { return Err("Unknown value for endianness".into()); }
-    };
+pub fn parse_elf_from_bytes(bytes: &[u8]) -> Result<ELF, Box<dyn std::error::Error>> {
+    let ident = ExecReader::<LittleEndian>::from_bytes(bytes).peek_n::<6>()?;
 
     let mut elf = ELF::default();
 
-    elf.parse_headers_and_sections(&mut reader)?;
+    match check_ident(&ident)? {
+        1 => elf.parse::<LittleEndian>(&mut ExecReader::from_bytes(bytes))?,
+        _ => elf.parse::<BigEndian>(&mut ExecReader::from_bytes(bytes))?,
+    }
 
     return Ok(elf);
 }
+
+/// Checks the magic number, returns the data encoding (1 for little endian, 2 for big endian)
+fn check_ident(ident: &[u8; 6]) -> Result<u8, Box<dyn std::error::Error>> {
+    if ident[0..4] != ELF_MAGIC_ARRAY {
+        return Err("File magic number does not match ELF magic number".into());
+    }
+
+    match ident[5] {
+        1 | 2 => return Ok(ident[5]),
+        _ => return Err("Unknown value for endianness".into()),
+    }
+}
@@ -2,8 +2,9 @@ use std::fs::File;
 use std::io::Read;
 use std::path::PathBuf;
 
-use crate::elf::{ELF_MAGIC_ARRAY, ELF};
-use crate::pe::{DOS_MAGIC_ARRAY, PE};
+use crate::elf::{ELF_MAGIC_ARRAY, ELF, parse_elf, parse_elf_from_bytes};
+use crate::minidump::is_minidump;
+use crate::pe::{DOS_MAGIC_ARRAY, TE_MAGIC_ARRAY, PE, PEReader, parse_pe_from_reader, parse_pe_from_reader_as, parse_pe_memory_image, parse_pe_with};
 
 pub enum ExecType {
     PE,
@@ -16,19 +17,69 @@ pub fn guess_exectype(path: &PathBuf) -> Result<ExecType, Box<dyn std::error::Er
 
     file.read_exact(&mut buffer)?;
 
-    if buffer[0..4] == ELF_MAGIC_ARRAY {
+    return guess_exectype_from_bytes(&buffer);
+}
+
+pub fn guess_exectype_from_bytes(bytes: &[u8]) -> Result<ExecType, Box<dyn std::error::Error>> {
+    if bytes.get(0..4) == Some(&ELF_MAGIC_ARRAY[..]) {
         return Ok(ExecType::ELF);
     }
 
-    if buffer[0..2] == DOS_MAGIC_ARRAY {
+    if bytes.get(0..2) == Some(&DOS_MAGIC_ARRAY[..]) {
+        return Ok(ExecType::PE);
+    }
+
+    /* TE images of the UEFI PEI phase, parsed with the PE ones */
+    if bytes.get(0..2) == Some(&TE_MAGIC_ARRAY[..]) {
         return Ok(ExecType::PE);
     }
 
+    if is_minidump(bytes) {
+        return Err("Minidump file, its modules are listed and extracted by the minidump subcommand".into());
+    }
+
     return Err("Cannot determine the executable type".into());
 }
 
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant, reason = "a single executable is parsed per run, boxing the PE only adds an indirection")]
 pub enum Exec {
     PE(PE),
     ELF(ELF),
 }
+
+pub fn parse_exec(path: &PathBuf) -> Result<Exec, Box<dyn std::error::Error>> {
+    return parse_exec_with(path, true);
+}
+
+/// Without section_data the PE sections are left empty, ELF files are always fully read
+pub fn parse_exec_with(path: &PathBuf, section_data: bool) -> Result<Exec, Box<dyn std::error::Error>> {
+    match guess_exectype(path)? {
+        ExecType::PE => return Ok(Exec::PE(parse_pe_with(path, section_data)?)),
+        ExecType::ELF => return Ok(Exec::ELF(parse_elf(path)?)),
+    }
+}
+
+/// Parses a PE module dumped from process memory, ELF files are only read from their file layout
+pub fn parse_exec_memory_image(path: &PathBuf, section_data: bool) -> Result<Exec, Box<dyn std::error::Error>> {
+    match guess_exectype(path)? {
+        ExecType::PE => return Ok(Exec::PE(parse_pe_memory_image(path, section_data)?)),
+        ExecType::ELF => return Err("Memory images are only supported for PE modules".into()),
+    }
+}
+
+/// Parses a PE module read from process memory (minidumps), with the section data
+pub fn parse_exec_memory_image_from_bytes(bytes: &[u8]) -> Result<Exec, Box<dyn std::error::Error>> {
+    match guess_exectype_from_bytes(bytes)? {
+        ExecType::PE => return Ok(Exec::PE(parse_pe_from_reader_as(&mut PEReader::from_bytes(bytes), true, true)?)),
+        ExecType::ELF => return Err("Memory images are only supported for PE modules".into()),
+    }
+}
+
+/// Parses an executable already in memory, with the PE section data
+pub fn parse_exec_from_bytes(bytes: &[u8]) -> Result<Exec, Box<dyn std::error::Error>> {
+    match guess_exectype_from_bytes(bytes)? {
+        ExecType::PE => return Ok(Exec::PE(parse_pe_from_reader(&mut PEReader::from_bytes(bytes), true)?)),
+        ExecType::ELF => return Ok(Exec::ELF(parse_elf_from_bytes(bytes)?)),
+    }
+}
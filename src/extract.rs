@@ -0,0 +1,324 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+
+use crate::authenticode::AuthenticodeSignature;
+use crate::dump::Dump;
+use crate::elf::SectionType;
+use crate::exec::Exec;
+use crate::pe::PE;
+use crate::resources::{bitmap_file, is_png, ResourceEntry, ResourceId, ResourceTable, ResourceType};
+
+/*
+ * Extraction of the executable content to files
+ */
+
+#[derive(Debug, Clone, Default)]
+pub struct ExtractedFile {
+    pub source: String,
+    pub path: PathBuf,
+    pub size: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExtractionReport {
+    pub label: String,
+    pub files: Vec<ExtractedFile>,
+}
+
+impl ExtractionReport {
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new_from_string(format!("{} ({})", self.label, self.files.len()));
+
+        for file in self.files.iter() {
+            dump.push_field("", format!("{} -> {} ({:#x} bytes)", file.source, file.path.display(), file.size), None);
+        }
+
+        return dump;
+    }
+}
+
+/// Makes a name usable as a file name, ".text" becomes "text"
+pub fn sanitize_file_name(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '_' })
+        .collect();
+
+    let sanitized = sanitized.trim_start_matches('.');
+
+    if sanitized.is_empty() {
+        return String::from("unnamed");
+    }
+
+    return sanitized.to_string();
+}
+
+/// Writes the file in the output directory, suffixing the name when it is already taken by this extraction
+fn write_unique(output_dir: &Path, stem: &str, extension: &str, data: &[u8], taken: &mut HashSet<String>) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let mut file_name = format!("{}.{}", stem, extension);
+    let mut counter = 1;
+
+    while !taken.insert(file_name.to_ascii_lowercase()) {
+        file_name = format!("{}_{}.{}", stem, counter, extension);
+        counter += 1;
+    }
+
+    let path = output_dir.join(file_name);
+    std::fs::write(&path, data)?;
+
+    return Ok(path);
+}
+
+/// Sections matching the pattern, an exact name match wins over the regular expression
+fn select_sections<'a>(names: Vec<&'a String>, pattern: &str) -> Result<Vec<&'a String>, Box<dyn std::error::Error>> {
+    if names.iter().any(|n| n.as_str() == pattern) {
+        return Ok(names.into_iter().filter(|n| n.as_str() == pattern).collect());
+    }
+
+    let regex = Regex::new(pattern)?;
+
+    return Ok(names.into_iter().filter(|n| regex.is_match(n)).collect());
+}
+
+/// Writes the bytes of the matching sections, as stored in the file or as mapped in memory (zero-filled up to the virtual size)
+pub fn extract_sections(exec: &Exec, file_path: &Path, pattern: &str, output_dir: &Path, virtual_size: bool) -> Result<ExtractionReport, Box<dyn std::error::Error>> {
+    /* The parsed sections are cut to their virtual size, the file alignment padding is only in the file */
+    let file_bytes = std::fs::read(file_path)?;
+
+    let file_range = |offset: u64, size: u64| -> &[u8] {
+        let start = (offset as usize).min(file_bytes.len());
+        let end = (offset.saturating_add(size) as usize).min(file_bytes.len());
+        return &file_bytes[start..end];
+    };
+
+    /* (name, virtual address, bytes) */
+    let mut sections: Vec<(String, u64, Vec<u8>)> = Vec::new();
+
+    match exec {
+        Exec::PE(pe) => {
+            /* Every section is extracted when names repeat, write_unique keeps their files apart */
+            let names = select_sections(pe.sections.iter().map(|s| &s.header.name).collect(), pattern)?;
+
+            for section in pe.sections.iter().filter(|s| names.contains(&&s.header.name)) {
+                let header = &section.header;
+                let name = &header.name;
+
+                let data = match virtual_size {
+                    true => {
+                        let mapped_size = header.size_of_raw_data.min(header.virtual_size);
+                        let mut data = file_range(header.ptr_to_raw_data as u64, mapped_size as u64).to_vec();
+                        data.resize(header.virtual_size as usize, 0);
+                        data
+                    }
+                    false => file_range(header.ptr_to_raw_data as u64, header.size_of_raw_data as u64).to_vec(),
+                };
+
+                sections.push((name.clone(), header.virtual_address as u64, data));
+            }
+        }
+        Exec::ELF(elf) => {
+            for name in select_sections(elf.sections.keys().collect(), pattern)? {
+                let section = &elf.sections[name];
+
+                /* NOBITS sections have no bytes in the file */
+                let mut data = match section.header.section_type() {
+                    SectionType::Nobits => Vec::new(),
+                    _ => file_range(section.offset(), section.size()).to_vec(),
+                };
+
+                if virtual_size {
+                    data.resize(section.size() as usize, 0);
+                }
+
+                sections.push((name.clone(), section.header.virtual_address(), data));
+            }
+        }
+    }
+
+    std::fs::create_dir_all(output_dir)?;
+
+    sections.sort_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(&b.0)));
+
+    let mut report = ExtractionReport {
+        label: String::from("Extracted Sections"),
+        files: Vec::new(),
+    };
+
+    let mut taken = HashSet::new();
+
+    for (name, _, data) in sections {
+        let path = write_unique(output_dir, &sanitize_file_name(&name), "bin", &data, &mut taken)?;
+
+        report.files.push(ExtractedFile {
+            source: name,
+            path,
+            size: data.len(),
+        });
+    }
+
+    return Ok(report);
+}
+
+fn resource_id_name(id: &ResourceId) -> String {
+    match id {
+        ResourceId::Id(id) => return format!("{}", id),
+        ResourceId::Name(name) => return sanitize_file_name(name),
+    }
+}
+
+/// File extension and content of a resource, icons, cursors and bitmaps are rebuilt into their file formats
+fn resource_file(table: &ResourceTable, entry: &ResourceEntry) -> (&'static str, Vec<u8>) {
+    let resource_type = match entry.type_id {
+        ResourceId::Id(id) => ResourceType::from(id),
+        ResourceId::Name(_) => ResourceType::Unknown,
+    };
+
+    match resource_type {
+        ResourceType::Bitmap => {
+            if let Some(file) = bitmap_file(&entry.data) {
+                return ("bmp", file);
+            }
+        }
+        ResourceType::GroupIcon => {
+            if let Some(file) = table.group_icon_file(entry) {
+                return ("ico", file);
+            }
+        }
+        ResourceType::GroupCursor => {
+            if let Some(file) = table.group_icon_file(entry) {
+                return ("cur", file);
+            }
+        }
+        ResourceType::Icon | ResourceType::Cursor if is_png(&entry.data) => return ("png", entry.data.clone()),
+        ResourceType::Icon | ResourceType::Cursor => return ("dib", entry.data.clone()),
+        ResourceType::Manifest => return ("manifest", entry.data.clone()),
+        ResourceType::Html => return ("html", entry.data.clone()),
+        _ => {}
+    }
+
+    return ("bin", entry.data.clone());
+}
+
+/// Writes every resource leaf to a "TYPE_name_language" file
+pub fn extract_resources(pe: &PE, output_dir: &Path) -> Result<ExtractionReport, Box<dyn std::error::Error>> {
+    let table = pe.resource_table.as_ref().ok_or("No resources found in PE")?;
+
+    std::fs::create_dir_all(output_dir)?;
+
+    let mut report = ExtractionReport {
+        label: String::from("Extracted Resources"),
+        files: Vec::new(),
+    };
+
+    let mut taken = HashSet::new();
+
+    for entry in table.entries.iter() {
+        let (extension, data) = resource_file(table, entry);
+
+        let stem = format!(
+            "{}_{}_{}",
+            match entry.type_id {
+                ResourceId::Id(_) => entry.type_id.type_name(),
+                ResourceId::Name(ref name) => sanitize_file_name(name),
+            },
+            resource_id_name(&entry.name_id),
+            entry.language
+        );
+
+        let path = write_unique(output_dir, &stem, extension, &data, &mut taken)?;
+
+        report.files.push(ExtractedFile {
+            source: entry.path(),
+            path,
+            size: data.len(),
+        });
+    }
+
+    return Ok(report);
+}
+
+/// Standard base64 with padding, wrapped at 64 columns as PEM expects
+fn base64_lines(data: &[u8]) -> Vec<String> {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[((n >> (18 - i * 6)) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    return encoded.as_bytes().chunks(64).map(|line| String::from_utf8_lossy(line).to_string()).collect();
+}
+
+fn pem_encode(label: &str, data: &[u8]) -> Vec<u8> {
+    let mut pem = format!("-----BEGIN {}-----\n", label);
+
+    for line in base64_lines(data) {
+        pem.push_str(&line);
+        pem.push('\n');
+    }
+
+    pem.push_str(&format!("-----END {}-----\n", label));
+
+    return pem.into_bytes();
+}
+
+/// Writes the Authenticode PKCS#7 blob of the security directory and each embedded X.509 certificate next to it,
+/// as "<name>-<index>.<ext>". The blob and the certificates are PEM encoded when the extension is .pem, DER otherwise
+pub fn extract_certificate(pe: &PE, path: &Path) -> Result<ExtractionReport, Box<dyn std::error::Error>> {
+    let signature = pe
+        .certificate_table
+        .as_ref()
+        .and_then(|table| table.pkcs7_signature())
+        .ok_or("No PKCS#7 signature found in PE")?;
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let extension = path.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or(String::from("der"));
+    let is_pem = extension.eq_ignore_ascii_case("pem");
+    let encode = |label: &str, data: &[u8]| -> Vec<u8> { if is_pem { pem_encode(label, data) } else { data.to_vec() } };
+
+    let blob = encode("PKCS7", &signature.data);
+    std::fs::write(path, &blob)?;
+
+    let mut files = vec![ExtractedFile {
+        source: String::from(signature.get_certificate_type().as_static_str()),
+        path: path.to_path_buf(),
+        size: blob.len(),
+    }];
+
+    /* The blob is still extracted when its certificates can not be parsed */
+    let certificates = AuthenticodeSignature::from_der(&signature.data).map(|s| s.certificates).unwrap_or_default();
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or(String::from("certificate"));
+
+    for (i, certificate) in certificates.iter().enumerate() {
+        let certificate_path = path.with_file_name(format!("{}-{}.{}", stem, i, extension));
+        let data = encode("CERTIFICATE", &certificate.der);
+
+        std::fs::write(&certificate_path, &data)?;
+
+        files.push(ExtractedFile {
+            source: format!("X.509 {}", certificate.common_name),
+            path: certificate_path,
+            size: data.len(),
+        });
+    }
+
+    return Ok(ExtractionReport {
+        label: String::from("Extracted Certificate"),
+        files,
+    });
+}
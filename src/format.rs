@@ -1,9 +1,123 @@
-use std::time::{Duration, SystemTime};
-use chrono::prelude::{DateTime, Utc};
+use std::sync::RwLock;
+use chrono::format::{Item, StrftimeItems};
+use chrono::prelude::{DateTime, Local, SecondsFormat, Utc};
+use strum::IntoEnumIterator;
 
+use crate::args::TimeZone;
+
+/* Timestamps are printed in ISO-8601 unless a strftime format is given with --time-format, in the --timezone time zone */
+struct TimeFormat {
+    format: Option<String>,
+    timezone: TimeZone,
+}
+
+static TIME_FORMAT: RwLock<TimeFormat> = RwLock::new(TimeFormat { format: None, timezone: TimeZone::Utc });
+
+pub fn set_time_format(format: Option<&str>, timezone: TimeZone) -> Result<(), Box<dyn std::error::Error>> {
+    /* chrono panics when formatting with an invalid specifier, they are rejected here instead */
+    if let Some(format) = format && StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(format!("Invalid time format \"{}\"", format).into());
+    }
+
+    let mut time_format = TIME_FORMAT.write().map_err(|_| "Time format lock poisoned")?;
+
+    time_format.format = format.map(String::from);
+    time_format.timezone = timezone;
+
+    return Ok(());
+}
+
+/// Human-readable form of a timestamp in seconds since the Unix epoch
 pub fn format_u32_as_ctime(ctime: u32) -> String {
-    let time = SystemTime::UNIX_EPOCH + Duration::from_secs(ctime as u64);
-    let dt: DateTime<Utc> = time.into();
+    return format_unix_time(ctime as i64);
+}
+
+/// Same as format_u32_as_ctime for the dates outside of the 32-bit range (certificate validity)
+pub fn format_unix_time(seconds: i64) -> String {
+    let Some(dt) = DateTime::<Utc>::from_timestamp(seconds, 0) else {
+        return String::from("invalid");
+    };
+
+    let time_format = TIME_FORMAT.read().unwrap_or_else(|e| e.into_inner());
+
+    match (&time_format.format, time_format.timezone) {
+        (Some(format), TimeZone::Utc) => return dt.format(format).to_string(),
+        (Some(format), TimeZone::Local) => return dt.with_timezone(&Local).format(format).to_string(),
+        (None, TimeZone::Utc) => return dt.to_rfc3339_opts(SecondsFormat::Secs, true),
+        (None, TimeZone::Local) => return dt.with_timezone(&Local).to_rfc3339_opts(SecondsFormat::Secs, true),
+    }
+}
+
+/// Raw value of a timestamp followed by its human-readable form, "0x5f5e1000 (2020-09-13T12:26:40Z)"
+pub fn format_timestamp(ctime: u32) -> String {
+    return format!("{:#x} ({})", ctime, format_u32_as_ctime(ctime));
+}
+
+/// Hex dump lines of 16 bytes, prefixed with the address of their first byte and followed by their ASCII
+pub fn format_hex_lines(data: &[u8], address: u64) -> Vec<String> {
+    return format_hex_lines_with_gutter(data, |index| format!("{:08X}", address + index));
+}
+
+/// Hex dump lines of 16 bytes, prefixed with the gutter of the index of their first byte in the data
+pub fn format_hex_lines_with_gutter(data: &[u8], gutter: impl Fn(u64) -> String) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for (i, chunk) in data.chunks(16).enumerate() {
+        let mut line = format!("{}  ", gutter((i * 16) as u64));
+
+        for (j, byte) in chunk.iter().enumerate() {
+            line.push_str(&format!("{:02X} ", byte));
+
+            if j == 7 {
+                line.push(' ');
+            }
+        }
+
+        for j in chunk.len()..16 {
+            line.push_str(if j == 7 { "    " } else { "   " });
+        }
+
+        line.push(' ');
+        line.extend(chunk.iter().map(|b| if b.is_ascii_graphic() || *b == b' ' { *b as char } else { '.' }));
+
+        lines.push(line);
+    }
+
+    return lines;
+}
+
+/// Flag enums decoded by decode_flags, every variant is a bit of the value or the value of a field of several bits
+pub trait FlagEnum: IntoEnumIterator + Into<&'static str> + Copy {
+    /// Prefix of the names in the Windows SDK headers ("IMAGE_SCN_"), printed before the variant names
+    const PREFIX: &'static str = "";
+
+    fn bits(self) -> u64;
+
+    /// Bits of the field the variant is a value of, the variant itself for the single bit flags
+    fn mask(self) -> u64 {
+        return self.bits();
+    }
+}
+
+/// Names of the flags set in the value joined with '|', "IMAGE_SCN_CNT_CODE | IMAGE_SCN_MEM_EXECUTE"
+pub fn decode_flags<F: FlagEnum>(value: u64) -> String {
+    let flags: Vec<String> = F::iter()
+        .filter(|&flag| flag.bits() != 0 && (value & flag.mask()) == flag.bits())
+        .map(|flag| {
+            let name: &'static str = flag.into();
+            format!("{}{}", F::PREFIX, name)
+        })
+        .collect();
+
+    return flags.join(" | ");
+}
+
+/// Raw value followed by the names of its flags, the raw value alone when no flag is set
+pub fn format_flags<F: FlagEnum>(value: u64) -> String {
+    let names = decode_flags::<F>(value);
 
-    return format!("{}", dt.format("%d/%m/%Y %H:%M"));
+    match names.is_empty() {
+        true => return format!("{:#x}", value),
+        false => return format!("{:#x} ({})", value, names),
+    }
 }
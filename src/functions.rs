@@ -0,0 +1,421 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ops::Range;
+
+use capstone::prelude::*;
+
+use crate::analysis::x86_capstone;
+use crate::disasm::is_padding_instruction;
+use crate::dump::Dump;
+use crate::elf::{ELFClass, ELFTargetISA, ELF};
+use crate::exec::Exec;
+use crate::pe::{ExcFunctionEntry, MachineType, PE};
+use crate::x86_64::direct_target;
+
+/*
+ * Function discovery and per-function similarity hashing, used to match functions between two binaries
+ */
+
+/* Functions with fewer instructions are too generic to be matched meaningfully */
+pub const MIN_HASHED_INSTRUCTIONS: usize = 5;
+
+/* Number of MinHash slots, split into LSH bands to find candidate pairs */
+pub const MINHASH_SIZE: usize = 64;
+pub const MINHASH_BANDS: usize = 16;
+const MINHASH_ROWS: usize = MINHASH_SIZE / MINHASH_BANDS;
+
+/* Length of the mnemonic n-grams fed to the MinHash */
+pub const SHINGLE_SIZE: usize = 3;
+
+/* Below this similarity, two functions are considered unrelated */
+pub const CHANGED_SIMILARITY_THRESHOLD: f64 = 0.5;
+
+#[derive(Debug, Clone)]
+pub struct DiscoveredFunction {
+    pub address: u64,
+    pub size: u64,
+    pub mnemonics: Vec<String>,
+}
+
+struct DecodedInstruction {
+    address: u64,
+    size: u64,
+    mnemonic: String,
+    call_target: Option<u64>,
+    is_padding: bool,
+}
+
+/// Linear sweep of a code region, resuming after undecodable bytes
+fn decode_region(cs: &Capstone, code: &[u8], address: u64) -> Vec<DecodedInstruction> {
+    let mut decoded = Vec::new();
+    let mut offset = 0;
+
+    while offset < code.len() {
+        let instructions = match cs.disasm_all(&code[offset..], address + offset as u64) {
+            Ok(instructions) => instructions,
+            Err(_) => break,
+        };
+
+        let mut consumed = 0;
+
+        for insn in instructions.iter() {
+            let mnemonic = insn.mnemonic().unwrap_or("").to_string();
+
+            let call_target = match mnemonic.as_str() {
+                "call" => insn.op_str().and_then(direct_target),
+                _ => None,
+            };
+
+            decoded.push(DecodedInstruction {
+                address: insn.address(),
+                size: insn.bytes().len() as u64,
+                is_padding: is_padding_instruction(insn),
+                mnemonic,
+                call_target,
+            });
+
+            consumed += insn.bytes().len();
+        }
+
+        offset += consumed + 1;
+    }
+
+    return decoded;
+}
+
+/// Splits the code regions into functions, starting from the seeds and every direct call target
+pub fn discover_functions(cs: &Capstone, regions: &[(u64, &[u8])], seeds: &[u64]) -> Vec<DiscoveredFunction> {
+    let mut functions = Vec::new();
+
+    for (region_address, code) in regions.iter() {
+        let region: Range<u64> = *region_address..*region_address + code.len() as u64;
+        let decoded = decode_region(cs, code, *region_address);
+
+        let boundaries: HashSet<u64> = decoded.iter().map(|insn| insn.address).collect();
+
+        let starts: BTreeSet<u64> = seeds
+            .iter()
+            .copied()
+            .chain(decoded.iter().filter_map(|insn| insn.call_target))
+            .filter(|address| region.contains(address) && boundaries.contains(address))
+            .collect();
+
+        let starts: Vec<u64> = starts.into_iter().collect();
+
+        for (i, start) in starts.iter().enumerate() {
+            let end = starts.get(i + 1).copied().unwrap_or(region.end);
+
+            /* The linear sweep yields instructions sorted by address */
+            let body = &decoded[decoded.partition_point(|insn| insn.address < *start)..decoded.partition_point(|insn| insn.address < end)];
+
+            /* Trailing padding between functions is not part of the function */
+            let last = match body.iter().rposition(|insn| !insn.is_padding) {
+                Some(last) => last,
+                None => continue,
+            };
+
+            functions.push(DiscoveredFunction {
+                address: *start,
+                size: body[last].address + body[last].size - start,
+                mnemonics: body[..=last].iter().map(|insn| insn.mnemonic.clone()).collect(),
+            });
+        }
+    }
+
+    return functions;
+}
+
+pub fn discover_pe_functions(pe: &PE) -> Result<Vec<DiscoveredFunction>, Box<dyn std::error::Error>> {
+    let machine: MachineType = pe.get_nt_header().coff_header.machine.into();
+
+    if machine != MachineType::I386 && machine != MachineType::AMD64 {
+        return Err("Function discovery is only supported for x86 executables".into());
+    }
+
+    let cs = x86_capstone(pe.is_32_bits()).ok_or("Cannot initialize the disassembler")?;
+
+    let image_base = pe.get_optional_header().get_image_base();
+
+    let mut seeds = vec![image_base + pe.get_optional_header().get_address_of_entry_point() as u64];
+
+    if let Some(ref exception_table) = pe.exception_table {
+        for entry in exception_table.entries.iter() {
+            if let ExcFunctionEntry::X64(e) = entry {
+                seeds.push(image_base + e.begin_address as u64);
+            }
+        }
+    }
+
+    if let Some(ref export_data) = pe.export_data {
+        for entry in export_data.entries.iter().filter(|e| e.forwarder.is_none()) {
+            seeds.push(image_base + entry.rva as u64);
+        }
+    }
+
+    if let Some(ref tls) = pe.tls_directory {
+        seeds.extend(tls.callbacks.iter().copied());
+    }
+
+    let mut sections: Vec<_> = pe.sections.iter().filter(|s| s.contains_code()).collect();
+    sections.sort_by_key(|s| s.header.virtual_address);
+
+    let regions: Vec<(u64, &[u8])> = sections
+        .iter()
+        .map(|s| (image_base + s.header.virtual_address as u64, s.raw_data()))
+        .collect();
+
+    return Ok(discover_functions(&cs, &regions, &seeds));
+}
+
+pub fn discover_elf_functions(elf: &ELF) -> Result<Vec<DiscoveredFunction>, Box<dyn std::error::Error>> {
+    let machine = elf.get_elf_header().machine();
+
+    if machine != ELFTargetISA::X86 as u16 && machine != ELFTargetISA::AMDX86_64 as u16 {
+        return Err("Function discovery is only supported for x86 executables".into());
+    }
+
+    let cs = x86_capstone(matches!(elf.class(), ELFClass::ELF32)).ok_or("Cannot initialize the disassembler")?;
+
+    let seeds = vec![elf.get_elf_header().entry_point()];
+
+    let mut sections: Vec<_> = elf.sections.values().filter(|s| s.contains_code()).collect();
+    sections.sort_by_key(|s| s.header.virtual_address());
+
+    let regions: Vec<(u64, &[u8])> = sections
+        .iter()
+        .map(|s| (s.header.virtual_address(), s.data.as_slice()))
+        .collect();
+
+    return Ok(discover_functions(&cs, &regions, &seeds));
+}
+
+pub fn discover_exec_functions(exec: &Exec) -> Result<Vec<DiscoveredFunction>, Box<dyn std::error::Error>> {
+    match exec {
+        Exec::PE(pe) => return discover_pe_functions(pe),
+        Exec::ELF(elf) => return discover_elf_functions(elf),
+    }
+}
+
+/*
+ * Hashing
+ */
+
+fn fnv1a(data: &[u8], mut hash: u64) -> u64 {
+    for byte in data.iter() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    return hash;
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+
+fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+
+    return x ^ (x >> 31);
+}
+
+#[derive(Debug, Clone)]
+pub struct FunctionSignature {
+    pub address: u64,
+    pub size: u64,
+    pub instruction_count: usize,
+    /// Hash of the whole mnemonic sequence, equal for functions differing only by operands
+    pub exact_hash: u64,
+    /// MinHash of the mnemonic n-grams, approximates the Jaccard similarity of two functions
+    pub minhash: [u64; MINHASH_SIZE],
+}
+
+impl FunctionSignature {
+    pub fn from_function(function: &DiscoveredFunction) -> FunctionSignature {
+        let mut exact_hash = FNV_OFFSET_BASIS;
+
+        for mnemonic in function.mnemonics.iter() {
+            exact_hash = fnv1a(mnemonic.as_bytes(), exact_hash);
+            exact_hash = fnv1a(b";", exact_hash);
+        }
+
+        let mut minhash = [u64::MAX; MINHASH_SIZE];
+
+        for shingle in function.mnemonics.windows(SHINGLE_SIZE.min(function.mnemonics.len())) {
+            let shingle_hash = fnv1a(shingle.join(" ").as_bytes(), FNV_OFFSET_BASIS);
+
+            for (seed, slot) in minhash.iter_mut().enumerate() {
+                *slot = (*slot).min(splitmix64(shingle_hash ^ seed as u64));
+            }
+        }
+
+        return FunctionSignature {
+            address: function.address,
+            size: function.size,
+            instruction_count: function.mnemonics.len(),
+            exact_hash,
+            minhash,
+        };
+    }
+
+    pub fn similarity(&self, other: &FunctionSignature) -> f64 {
+        let equal = self.minhash.iter().zip(other.minhash.iter()).filter(|(a, b)| a == b).count();
+
+        return equal as f64 / MINHASH_SIZE as f64;
+    }
+
+    fn band_hash(&self, band: usize) -> u64 {
+        let rows = &self.minhash[band * MINHASH_ROWS..(band + 1) * MINHASH_ROWS];
+
+        return rows.iter().fold(band as u64, |hash, row| splitmix64(hash ^ row));
+    }
+}
+
+pub fn hash_functions(functions: &[DiscoveredFunction]) -> Vec<FunctionSignature> {
+    return functions.iter().map(FunctionSignature::from_function).collect();
+}
+
+pub fn dump_function_hashes(signatures: &[FunctionSignature]) -> Dump {
+    let mut dump = Dump::new_from_string(format!("Function Hashes ({})", signatures.len()));
+
+    for signature in signatures.iter() {
+        dump.push_field(
+            "",
+            format!(
+                "{:#x} size: {:#x} instructions: {} hash: {:016x}",
+                signature.address, signature.size, signature.instruction_count, signature.exact_hash
+            ),
+            None,
+        );
+    }
+
+    return dump;
+}
+
+/*
+ * Comparison
+ */
+
+#[derive(Debug, Clone, Default)]
+pub struct FunctionComparison {
+    pub identical: Vec<(u64, u64)>,
+    pub changed: Vec<(u64, u64, f64)>,
+    pub new: Vec<u64>,
+    pub removed: Vec<u64>,
+    pub ignored: usize,
+}
+
+impl FunctionComparison {
+    /// Matches identical functions by exact hash first, then pairs the remaining ones by decreasing similarity
+    pub fn compare(old: &[FunctionSignature], new: &[FunctionSignature]) -> FunctionComparison {
+        let mut comparison = FunctionComparison::default();
+
+        let total = old.len() + new.len();
+
+        let old: Vec<&FunctionSignature> = old.iter().filter(|s| s.instruction_count >= MIN_HASHED_INSTRUCTIONS).collect();
+        let new: Vec<&FunctionSignature> = new.iter().filter(|s| s.instruction_count >= MIN_HASHED_INSTRUCTIONS).collect();
+
+        comparison.ignored = total - old.len() - new.len();
+
+        let mut old_matched = vec![false; old.len()];
+        let mut new_matched = vec![false; new.len()];
+
+        let mut new_by_hash: HashMap<u64, Vec<usize>> = HashMap::new();
+
+        for (j, signature) in new.iter().enumerate() {
+            new_by_hash.entry(signature.exact_hash).or_default().push(j);
+        }
+
+        for (i, signature) in old.iter().enumerate() {
+            if let Some(candidates) = new_by_hash.get_mut(&signature.exact_hash) && !candidates.is_empty() {
+                let j = candidates.remove(0);
+
+                old_matched[i] = true;
+                new_matched[j] = true;
+                comparison.identical.push((signature.address, new[j].address));
+            }
+        }
+
+        /* Locality sensitive hashing: only functions sharing a band are compared */
+        let mut buckets: HashMap<u64, Vec<usize>> = HashMap::new();
+
+        for (j, signature) in new.iter().enumerate().filter(|(j, _)| !new_matched[*j]) {
+            for band in 0..MINHASH_BANDS {
+                buckets.entry(signature.band_hash(band)).or_default().push(j);
+            }
+        }
+
+        let mut pairs: Vec<(f64, usize, usize)> = Vec::new();
+
+        for (i, signature) in old.iter().enumerate().filter(|(i, _)| !old_matched[*i]) {
+            let mut candidates: HashSet<usize> = HashSet::new();
+
+            for band in 0..MINHASH_BANDS {
+                if let Some(bucket) = buckets.get(&signature.band_hash(band)) {
+                    candidates.extend(bucket.iter().copied());
+                }
+            }
+
+            for j in candidates {
+                let similarity = signature.similarity(new[j]);
+
+                if similarity >= CHANGED_SIMILARITY_THRESHOLD {
+                    pairs.push((similarity, i, j));
+                }
+            }
+        }
+
+        pairs.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+        for (similarity, i, j) in pairs {
+            if old_matched[i] || new_matched[j] {
+                continue;
+            }
+
+            old_matched[i] = true;
+            new_matched[j] = true;
+            comparison.changed.push((old[i].address, new[j].address, similarity));
+        }
+
+        comparison.changed.sort_by_key(|c| c.0);
+
+        comparison.removed = old.iter().zip(old_matched.iter()).filter(|(_, m)| !**m).map(|(s, _)| s.address).collect();
+        comparison.new = new.iter().zip(new_matched.iter()).filter(|(_, m)| !**m).map(|(s, _)| s.address).collect();
+
+        return comparison;
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Function Comparison");
+
+        dump.push_field("Identical", format!("{}", self.identical.len()), None);
+        dump.push_field("Changed", format!("{}", self.changed.len()), None);
+        dump.push_field("New", format!("{}", self.new.len()), None);
+        dump.push_field("Removed", format!("{}", self.removed.len()), None);
+        dump.push_field("Ignored", format!("{}", self.ignored), Some("functions too small to be compared"));
+
+        let mut changed_dump = Dump::new_from_string(format!("Changed ({})", self.changed.len()));
+
+        for (old_address, new_address, similarity) in self.changed.iter() {
+            changed_dump.push_field("", format!("{:#x} -> {:#x} (similarity: {:.2})", old_address, new_address, similarity), None);
+        }
+
+        let mut new_dump = Dump::new_from_string(format!("New ({})", self.new.len()));
+
+        for address in self.new.iter() {
+            new_dump.push_field("", format!("{:#x}", address), None);
+        }
+
+        let mut removed_dump = Dump::new_from_string(format!("Removed ({})", self.removed.len()));
+
+        for address in self.removed.iter() {
+            removed_dump.push_field("", format!("{:#x}", address), None);
+        }
+
+        dump.push_child(changed_dump);
+        dump.push_child(new_dump);
+        dump.push_child(removed_dump);
+
+        return dump;
+    }
+}
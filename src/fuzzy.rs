@@ -0,0 +1,323 @@
+use crate::dump::Dump;
+use crate::elf::SectionFlags as ELFSectionFlags;
+use crate::exec::Exec;
+use crate::pe::SectionFlags;
+
+/*
+ * Similarity hashes of the file and of its executable sections
+ * ssdeep (context triggered piecewise hashing) as the ssdeep tool prints it, TLSH as the T1 digests of the reference implementation
+ */
+
+/* ssdeep */
+
+const SSDEEP_ROLLING_WINDOW: usize = 7;
+const SSDEEP_MIN_BLOCK_SIZE: u32 = 3;
+const SSDEEP_HASH_PRIME: u32 = 0x01000193;
+const SSDEEP_HASH_INIT: u32 = 0x28021967;
+const SSDEEP_SPAMSUM_LENGTH: usize = 64;
+const SSDEEP_BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Rolling hash of the last bytes, its value triggers the ends of the pieces
+#[derive(Default)]
+struct RollingHash {
+    window: [u8; SSDEEP_ROLLING_WINDOW],
+    h1: u32,
+    h2: u32,
+    h3: u32,
+    n: usize,
+}
+
+impl RollingHash {
+    fn update(&mut self, c: u8) -> u32 {
+        let c32 = c as u32;
+
+        self.h2 = self.h2.wrapping_sub(self.h1).wrapping_add(SSDEEP_ROLLING_WINDOW as u32 * c32);
+        self.h1 = self.h1.wrapping_add(c32).wrapping_sub(self.window[self.n % SSDEEP_ROLLING_WINDOW] as u32);
+        self.window[self.n % SSDEEP_ROLLING_WINDOW] = c;
+        self.n += 1;
+        self.h3 = (self.h3 << 5) ^ c32;
+
+        return self.h1.wrapping_add(self.h2).wrapping_add(self.h3);
+    }
+}
+
+/// Signature of a piece size, the triggers are counted without the character of the data after the last one
+struct SsdeepSignature {
+    characters: Vec<u8>,
+    triggers: usize,
+}
+
+impl SsdeepSignature {
+    fn new(max_length: usize) -> SsdeepSignature {
+        return SsdeepSignature { characters: vec![0; max_length], triggers: 0 };
+    }
+
+    /// Once the signature is full its last character is overwritten, the hash keeps going
+    fn trigger(&mut self, hash: &mut u32) {
+        self.characters[self.triggers] = SSDEEP_BASE64[(*hash % 64) as usize];
+
+        if self.triggers < self.characters.len() - 1 {
+            *hash = SSDEEP_HASH_INIT;
+            self.triggers += 1;
+        }
+    }
+
+    fn finish(&mut self, hash: u32) {
+        self.characters[self.triggers] = SSDEEP_BASE64[(hash % 64) as usize];
+    }
+
+    fn signature(&self) -> String {
+        return self.characters.iter().take_while(|c| **c != 0).map(|c| *c as char).collect();
+    }
+}
+
+fn ssdeep_signatures(data: &[u8], block_size: u32) -> (SsdeepSignature, SsdeepSignature) {
+    let mut roll = RollingHash::default();
+    let mut rolled = 0;
+
+    let mut first = SsdeepSignature::new(SSDEEP_SPAMSUM_LENGTH);
+    let mut second = SsdeepSignature::new(SSDEEP_SPAMSUM_LENGTH / 2);
+    let mut first_hash = SSDEEP_HASH_INIT;
+    let mut second_hash = SSDEEP_HASH_INIT;
+
+    for &c in data.iter() {
+        rolled = roll.update(c);
+
+        first_hash = first_hash.wrapping_mul(SSDEEP_HASH_PRIME) ^ c as u32;
+        second_hash = second_hash.wrapping_mul(SSDEEP_HASH_PRIME) ^ c as u32;
+
+        if rolled % block_size == block_size - 1 {
+            first.trigger(&mut first_hash);
+        }
+
+        if rolled % (block_size * 2) == block_size * 2 - 1 {
+            second.trigger(&mut second_hash);
+        }
+    }
+
+    /* The data after the last trigger ends the signatures */
+    if rolled != 0 {
+        first.finish(first_hash);
+        second.finish(second_hash);
+    }
+
+    return (first, second);
+}
+
+/// "<block size>:<signature>:<signature of the double block size>"
+pub fn ssdeep(data: &[u8]) -> String {
+    let mut block_size = SSDEEP_MIN_BLOCK_SIZE;
+
+    while (block_size as u64) * (SSDEEP_SPAMSUM_LENGTH as u64) < data.len() as u64 {
+        block_size *= 2;
+    }
+
+    loop {
+        let (first, second) = ssdeep_signatures(data, block_size);
+
+        /* Too few pieces to compare, the block size is halved */
+        if block_size > SSDEEP_MIN_BLOCK_SIZE && first.triggers < SSDEEP_SPAMSUM_LENGTH / 2 {
+            block_size /= 2;
+            continue;
+        }
+
+        return format!("{}:{}:{}", block_size, first.signature(), second.signature());
+    }
+}
+
+/* TLSH */
+
+/* Pearson hashing permutation of the reference implementation */
+const TLSH_PEARSON_TABLE: [u8; 256] = [
+    1, 87, 49, 12, 176, 178, 102, 166, 121, 193, 6, 84, 249, 230, 44, 163,
+    14, 197, 213, 181, 161, 85, 218, 80, 64, 239, 24, 226, 236, 142, 38, 200,
+    110, 177, 104, 103, 141, 253, 255, 50, 77, 101, 81, 18, 45, 96, 31, 222,
+    25, 107, 190, 70, 86, 237, 240, 34, 72, 242, 20, 214, 244, 227, 149, 235,
+    97, 234, 57, 22, 60, 250, 82, 175, 208, 5, 127, 199, 111, 62, 135, 248,
+    174, 169, 211, 58, 66, 154, 106, 195, 245, 171, 17, 187, 182, 179, 0, 243,
+    132, 56, 148, 75, 128, 133, 158, 100, 130, 126, 91, 13, 153, 246, 216, 219,
+    119, 68, 223, 78, 83, 88, 201, 99, 122, 11, 92, 32, 136, 114, 52, 10,
+    138, 30, 48, 183, 156, 35, 61, 26, 143, 74, 251, 94, 129, 162, 63, 152,
+    170, 7, 115, 167, 241, 206, 3, 150, 55, 59, 151, 220, 90, 53, 23, 131,
+    125, 173, 15, 238, 79, 95, 89, 16, 105, 137, 225, 224, 217, 160, 37, 123,
+    118, 73, 2, 157, 46, 116, 9, 145, 134, 228, 207, 212, 202, 215, 69, 229,
+    27, 188, 67, 124, 168, 252, 42, 4, 29, 108, 21, 247, 19, 205, 39, 203,
+    233, 40, 186, 147, 198, 192, 155, 33, 164, 191, 98, 204, 165, 180, 117, 76,
+    140, 36, 210, 172, 41, 54, 159, 8, 185, 232, 113, 196, 231, 47, 146, 120,
+    51, 65, 28, 144, 254, 221, 93, 189, 194, 139, 112, 43, 71, 109, 184, 209,
+];
+
+/* 128 buckets of 2 bits, the digest has 32 bytes of body */
+const TLSH_BUCKETS: usize = 128;
+const TLSH_CODE_SIZE: usize = TLSH_BUCKETS / 4;
+const TLSH_WINDOW_SIZE: usize = 5;
+const TLSH_MIN_DATA_LENGTH: usize = 50;
+
+fn tlsh_mapping(salt: u8, i: u8, j: u8, k: u8) -> u8 {
+    let mut h = TLSH_PEARSON_TABLE[salt as usize];
+    h = TLSH_PEARSON_TABLE[(h ^ i) as usize];
+    h = TLSH_PEARSON_TABLE[(h ^ j) as usize];
+    h = TLSH_PEARSON_TABLE[(h ^ k) as usize];
+    return h;
+}
+
+/// Logarithmic bucket of the data length
+fn tlsh_length_capturing(length: usize) -> u8 {
+    let log = (length as f64).ln();
+
+    let value = match length {
+        0..=656 => (log / 0.4054651).floor(),
+        657..=3199 => (log / 0.26236426 - 8.72777).floor(),
+        _ => (log / 0.095310180 - 62.5472).floor(),
+    };
+
+    return (value as u32 & 0xFF) as u8;
+}
+
+fn swap_nibbles(value: u8) -> u8 {
+    return value.rotate_left(4);
+}
+
+/// "T1" followed by 70 hex digits, None for less than 50 bytes or data too uniform to fill half of the buckets
+pub fn tlsh(data: &[u8]) -> Option<String> {
+    if data.len() < TLSH_MIN_DATA_LENGTH {
+        return None;
+    }
+
+    let mut buckets = [0u32; 256];
+    let mut checksum = 0u8;
+
+    /* Triplets of the sliding window of 5 bytes, each salted to its own bucket */
+    for i in TLSH_WINDOW_SIZE - 1..data.len() {
+        let (w0, w1, w2, w3, w4) = (data[i], data[i - 1], data[i - 2], data[i - 3], data[i - 4]);
+
+        checksum = tlsh_mapping(0, w0, w1, checksum);
+
+        buckets[tlsh_mapping(2, w0, w1, w2) as usize] += 1;
+        buckets[tlsh_mapping(3, w0, w1, w3) as usize] += 1;
+        buckets[tlsh_mapping(5, w0, w2, w3) as usize] += 1;
+        buckets[tlsh_mapping(7, w0, w2, w4) as usize] += 1;
+        buckets[tlsh_mapping(11, w0, w1, w4) as usize] += 1;
+        buckets[tlsh_mapping(13, w0, w3, w4) as usize] += 1;
+    }
+
+    let mut sorted = buckets[..TLSH_BUCKETS].to_vec();
+    sorted.sort_unstable();
+
+    let (q1, q2, q3) = (sorted[TLSH_BUCKETS / 4 - 1], sorted[TLSH_BUCKETS / 2 - 1], sorted[TLSH_BUCKETS - TLSH_BUCKETS / 4 - 1]);
+
+    if q3 == 0 || buckets[..TLSH_BUCKETS].iter().filter(|count| **count > 0).count() <= TLSH_BUCKETS / 2 {
+        return None;
+    }
+
+    let mut code = [0u8; TLSH_CODE_SIZE];
+
+    for (i, byte) in code.iter_mut().enumerate() {
+        for j in 0..4 {
+            let count = buckets[4 * i + j];
+
+            let quartile = match count {
+                count if count > q3 => 3,
+                count if count > q2 => 2,
+                count if count > q1 => 1,
+                _ => 0,
+            };
+
+            *byte |= quartile << (j * 2);
+        }
+    }
+
+    let q1_ratio = ((q1 as u64 * 100 / q3 as u64) % 16) as u8;
+    let q2_ratio = ((q2 as u64 * 100 / q3 as u64) % 16) as u8;
+
+    let mut digest = vec![swap_nibbles(checksum), swap_nibbles(tlsh_length_capturing(data.len())), (q1_ratio << 4) | q2_ratio];
+    digest.extend(code.iter().rev());
+
+    return Some(format!("T1{}", digest.iter().map(|b| format!("{:02X}", b)).collect::<String>()));
+}
+
+/* Report */
+
+#[derive(Debug, Clone, Default)]
+pub struct FuzzyHashes {
+    pub ssdeep: String,
+    pub tlsh: Option<String>,
+}
+
+impl FuzzyHashes {
+    pub fn compute(data: &[u8]) -> FuzzyHashes {
+        return FuzzyHashes { ssdeep: ssdeep(data), tlsh: tlsh(data) };
+    }
+
+    pub fn dump(&self, label: String) -> Dump {
+        let mut dump = Dump::new_from_string(label);
+
+        dump.push_field("SSDEEP", self.ssdeep.clone(), None);
+
+        match self.tlsh {
+            Some(ref tlsh) => dump.push_field("TLSH", tlsh.clone(), None),
+            None => dump.push_field("TLSH", String::from("None (less than 50 bytes or too uniform)"), None),
+        }
+
+        return dump;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FileFuzzyHashes {
+    pub file: FuzzyHashes,
+    /// Executable sections in address order, the hashes cover the bytes stored in the file
+    pub sections: Vec<(String, FuzzyHashes)>,
+}
+
+impl FileFuzzyHashes {
+    pub fn compute(exec: &Exec, file_bytes: &[u8]) -> FileFuzzyHashes {
+        let mut hashes = FileFuzzyHashes::default();
+
+        hashes.file = FuzzyHashes::compute(file_bytes);
+
+        match exec {
+            Exec::PE(pe) => {
+                let sections = pe
+                    .sections
+                    .iter()
+                    .filter(|s| s.contains_code() || (s.header.characteristics & SectionFlags::MemExecute as u32) != 0);
+
+                for section in sections {
+                    hashes.sections.push((section.header.name.clone(), FuzzyHashes::compute(section.raw_data())));
+                }
+            }
+            Exec::ELF(elf) => {
+                let mut sections: Vec<_> = elf
+                    .sections
+                    .values()
+                    .filter(|s| (s.header.flags() & ELFSectionFlags::ExecInstr as u64) != 0)
+                    .collect();
+
+                sections.sort_by_key(|s| (s.header.virtual_address(), s.name.clone()));
+
+                for section in sections {
+                    hashes.sections.push((section.name.clone(), FuzzyHashes::compute(&section.data)));
+                }
+            }
+        }
+
+        return hashes;
+    }
+
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Fuzzy Hashes");
+
+        dump.push_child(self.file.dump(String::from("File")));
+
+        let mut sections_dump = Dump::new_from_string(format!("Executable Sections ({})", self.sections.len()));
+
+        for (name, hashes) in self.sections.iter() {
+            sections_dump.push_child(hashes.dump(name.clone()));
+        }
+
+        dump.push_child(sections_dump);
+
+        return dump;
+    }
+}
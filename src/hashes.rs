@@ -0,0 +1,164 @@
+use md5::Md5;
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+
+use crate::dump::Dump;
+use crate::exec::Exec;
+use crate::ordinals::ordinal_name;
+use crate::patch::rich_header_range;
+use crate::pe::PE;
+
+/*
+ * Cryptographic hashes of the file and of the raw data of its sections
+ */
+
+fn to_hex(digest: &[u8]) -> String {
+    return digest.iter().map(|b| format!("{:02x}", b)).collect();
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Hashes {
+    pub md5: String,
+    pub sha1: String,
+    pub sha256: String,
+}
+
+impl Hashes {
+    pub fn compute(data: &[u8]) -> Hashes {
+        return Hashes {
+            md5: to_hex(&Md5::digest(data)),
+            sha1: to_hex(&Sha1::digest(data)),
+            sha256: to_hex(&Sha256::digest(data)),
+        };
+    }
+
+    pub fn dump(&self, label: String) -> Dump {
+        let mut dump = Dump::new_from_string(label);
+
+        dump.push_field("MD5", self.md5.clone(), None);
+        dump.push_field("SHA1", self.sha1.clone(), None);
+        dump.push_field("SHA256", self.sha256.clone(), None);
+
+        return dump;
+    }
+}
+
+/// Rich header hash used to cluster the samples built by the same toolchain: MD5 of the decoded Rich header,
+/// from the "DanS" marker to the last entry, the "Rich" marker and the XOR key excluded (as pefile computes it)
+pub fn rich_header_hash(file_bytes: &[u8], nt_header_offset: usize) -> Option<String> {
+    let range = rich_header_range(file_bytes, nt_header_offset)?;
+    let key = &file_bytes[range.end - 4..range.end];
+
+    let decoded: Vec<u8> = file_bytes[range.start..range.end - 8]
+        .iter()
+        .enumerate()
+        .map(|(i, b)| b ^ key[i % 4])
+        .collect();
+
+    return Some(to_hex(&Md5::digest(&decoded)));
+}
+
+/* Extensions pefile removes from the DLL names, and the DLLs whose ordinals it translates to names */
+const IMPHASH_STRIPPED_EXTENSIONS: [&str; 3] = ["ocx", "sys", "dll"];
+const IMPHASH_ORDINAL_DLLS: [&str; 3] = ["ws2_32.dll", "wsock32.dll", "oleaut32.dll"];
+
+/// Import hash used to cluster the samples importing the same functions: MD5 of the "dll.function" entries in import order,
+/// lowercased and separated by commas, the other ordinals named "ord<N>" (as pefile computes it)
+pub fn import_hash(pe: &PE) -> Option<String> {
+    let (Some(idt), Some(ilts), Some(hnt)) = (&pe.import_directory_table, &pe.import_lookup_tables, &pe.hint_name_table) else {
+        return None;
+    };
+
+    let mut entries = Vec::new();
+
+    for (ilt, hnd) in ilts.iter().zip(hnt.entries.iter()).take(idt.len()) {
+        let dll_name = hnd.dll_name.to_ascii_lowercase();
+
+        let library = match dll_name.rsplit_once('.') {
+            Some((stem, extension)) if IMPHASH_STRIPPED_EXTENSIONS.contains(&extension) => stem,
+            _ => dll_name.as_str(),
+        };
+
+        let mut names = hnd.entries.iter();
+
+        for entry in ilt.entries.iter() {
+            let function = match entry.by_ordinal {
+                true => match ordinal_name(&dll_name, entry.ordinal_number).filter(|_| IMPHASH_ORDINAL_DLLS.contains(&dll_name.as_str())) {
+                    Some(name) => String::from(name),
+                    None => format!("ord{}", entry.ordinal_number),
+                },
+                false => match names.next() {
+                    Some(hne) if !hne.name.is_empty() => hne.name.clone(),
+                    _ => continue,
+                },
+            };
+
+            entries.push(format!("{}.{}", library, function).to_ascii_lowercase());
+        }
+    }
+
+    if entries.is_empty() {
+        return None;
+    }
+
+    return Some(to_hex(&Md5::digest(entries.join(",").as_bytes())));
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FileHashes {
+    pub file: Hashes,
+    /// None for ELF and for the PE without a Rich header
+    pub rich_header: Option<String>,
+    /// Sections in address order, the hashes cover the bytes stored in the file
+    pub sections: Vec<(String, Hashes)>,
+}
+
+impl FileHashes {
+    pub fn compute(exec: &Exec, file_bytes: &[u8]) -> FileHashes {
+        let mut hashes = FileHashes::default();
+
+        hashes.file = Hashes::compute(file_bytes);
+
+        match exec {
+            Exec::PE(pe) => {
+                hashes.rich_header = rich_header_hash(file_bytes, pe.get_dos_header().e_lfanew as usize);
+
+                for section in pe.sections.iter() {
+                    hashes.sections.push((section.header.name.clone(), Hashes::compute(section.raw_data())));
+                }
+            }
+            Exec::ELF(elf) => {
+                let mut sections: Vec<_> = elf.sections.values().filter(|s| !s.name.is_empty()).collect();
+                sections.sort_by_key(|s| (s.header.virtual_address() == 0, s.header.virtual_address(), s.offset(), s.name.clone()));
+
+                for section in sections {
+                    hashes.sections.push((section.name.clone(), Hashes::compute(&section.data)));
+                }
+            }
+        }
+
+        return hashes;
+    }
+
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Hashes");
+
+        let mut file_dump = self.file.dump(String::from("File"));
+
+        if let Some(ref rich_header) = self.rich_header {
+            file_dump.push_field("RichHeader", rich_header.clone(), None);
+        }
+
+        dump.push_child(file_dump);
+
+        let mut sections_dump = Dump::new_from_string(format!("Sections ({})", self.sections.len()));
+
+        for (name, hashes) in self.sections.iter() {
+            sections_dump.push_child(hashes.dump(name.clone()));
+        }
+
+        dump.push_child(sections_dump);
+
+        return dump;
+    }
+}
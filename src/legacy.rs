@@ -0,0 +1,719 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use strum_macros::IntoStaticStr;
+
+use crate::dump::Dump;
+use crate::pe::{PEReader, DOS_MAGIC_ARRAY};
+use crate::reader::decode_name;
+
+/*
+ * Legacy executables whose DOS header points to something else than a PE: the 16-bit NE of Windows 3.x and OS/2 1.x,
+ * and the LE/LX of the VxDs and OS/2 2.x. Only the headers, the segments (objects) and the imports are read
+ */
+
+/* Offset of e_lfanew in the DOS header */
+const E_LFANEW_OFFSET: u64 = 0x3C;
+
+/* Guards against a corrupted table */
+const MAX_LEGACY_TABLE_ENTRIES: u32 = 0x10000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+pub enum LegacyFormat {
+    NE,
+    LE,
+    LX,
+}
+
+/// Format and header offset of a legacy executable, None for the PE and the plain DOS programs
+pub fn detect_legacy_format(bytes: &[u8]) -> Option<(LegacyFormat, u64)> {
+    if bytes.get(0..2) != Some(&DOS_MAGIC_ARRAY[..]) {
+        return None;
+    }
+
+    let mut cursor = PEReader::from_bytes(bytes);
+    cursor.set_position(E_LFANEW_OFFSET).ok()?;
+
+    let header_offset = cursor.read_u32().ok()? as u64;
+
+    cursor.set_position(header_offset).ok()?;
+
+    match &cursor.read_n::<2>().ok()? {
+        b"NE" => return Some((LegacyFormat::NE, header_offset)),
+        b"LE" => return Some((LegacyFormat::LE, header_offset)),
+        b"LX" => return Some((LegacyFormat::LX, header_offset)),
+        _ => return None,
+    }
+}
+
+/// Length-prefixed strings of the name tables, the bytes that are not printable UTF-8 are escaped and reported in the
+/// warnings
+fn read_pascal_string(cursor: &mut PEReader, warnings: &mut Vec<String>) -> Result<String, Box<dyn std::error::Error>> {
+    let offset = cursor.position();
+    let length = cursor.read_u8()?;
+    let (name, escaped) = decode_name(&cursor.read_bytes(length as usize)?);
+
+    if escaped {
+        warnings.push(format!("{}: Name at {:#x} is not printable UTF-8, non-printable bytes are escaped", name, offset));
+    }
+
+    return Ok(name);
+}
+
+/// Resident and non-resident name tables: a name and its ordinal until an empty name
+fn read_name_table(cursor: &mut PEReader, end: u64, warnings: &mut Vec<String>) -> Result<Vec<(String, u16)>, Box<dyn std::error::Error>> {
+    let mut names = Vec::new();
+
+    while cursor.position() < end && names.len() < MAX_LEGACY_TABLE_ENTRIES as usize {
+        let name = read_pascal_string(cursor, warnings)?;
+
+        if name.is_empty() {
+            break;
+        }
+
+        names.push((name, cursor.read_u16()?));
+    }
+
+    return Ok(names);
+}
+
+fn target_os_name(target_os: u16) -> &'static str {
+    match target_os {
+        1 => return "OS/2",
+        2 => return "Windows",
+        3 => return "European MS-DOS 4.x",
+        4 => return "Windows 386",
+        5 => return "Borland Operating System Services",
+        _ => return "Unknown",
+    }
+}
+
+fn format_names(dump: &mut Dump, names: &[(String, u16)]) {
+    for (name, ordinal) in names.iter() {
+        dump.push_field("", format!("@{:<5} {}", ordinal, name), None);
+    }
+}
+
+/*
+ * NE (New Executable), 16-bit Windows and OS/2
+ * http://bytepointer.com/resources/win16_ne_exe_format_win3.0.htm
+ */
+
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum NESegmentFlags {
+    Data = 0x0001,
+    Moveable = 0x0010,
+    Preload = 0x0040,
+    ReadOnly = 0x0080,
+    RelocInfo = 0x0100,
+    Discardable = 0x1000,
+}
+
+const NE_SEGMENT_FLAGS: [NESegmentFlags; 6] = [
+    NESegmentFlags::Data,
+    NESegmentFlags::Moveable,
+    NESegmentFlags::Preload,
+    NESegmentFlags::ReadOnly,
+    NESegmentFlags::RelocInfo,
+    NESegmentFlags::Discardable,
+];
+
+/* Set in the flags of the DLLs */
+const NE_FLAG_LIBRARY: u16 = 0x8000;
+
+/* Relocation record types, the low bits of the flags byte */
+const NE_RELOCATION_IMPORT_ORDINAL: u8 = 1;
+const NE_RELOCATION_IMPORT_NAME: u8 = 2;
+
+#[derive(Debug, Clone, Default)]
+pub struct NEHeader {
+    pub linker_version: u8,
+    pub linker_revision: u8,
+    pub entry_table_offset: u16,
+    pub entry_table_length: u16,
+    pub flags: u16,
+    pub auto_data_segment: u16,
+    pub heap_size: u16,
+    pub stack_size: u16,
+    pub initial_ip: u16,
+    pub initial_cs: u16,
+    pub initial_sp: u16,
+    pub initial_ss: u16,
+    pub segment_count: u16,
+    pub module_reference_count: u16,
+    pub non_resident_names_size: u16,
+    pub segment_table_offset: u16,
+    pub resource_table_offset: u16,
+    pub resident_names_offset: u16,
+    pub module_reference_offset: u16,
+    pub imported_names_offset: u16,
+    /// From the start of the file, the other offsets are relative to the NE header
+    pub non_resident_names_offset: u32,
+    pub movable_entry_count: u16,
+    pub alignment_shift: u16,
+    pub resource_segment_count: u16,
+    pub target_os: u8,
+    pub other_flags: u8,
+    pub expected_windows_version: u16,
+}
+
+impl NEHeader {
+    pub fn from_parser(cursor: &mut PEReader) -> Result<NEHeader, Box<dyn std::error::Error>> {
+        let mut header = NEHeader::default();
+
+        cursor.skip(2)?; // "NE"
+        header.linker_version = cursor.read_u8()?;
+        header.linker_revision = cursor.read_u8()?;
+        header.entry_table_offset = cursor.read_u16()?;
+        header.entry_table_length = cursor.read_u16()?;
+        cursor.skip(4)?; // CRC
+        header.flags = cursor.read_u16()?;
+        header.auto_data_segment = cursor.read_u16()?;
+        header.heap_size = cursor.read_u16()?;
+        header.stack_size = cursor.read_u16()?;
+        header.initial_ip = cursor.read_u16()?;
+        header.initial_cs = cursor.read_u16()?;
+        header.initial_sp = cursor.read_u16()?;
+        header.initial_ss = cursor.read_u16()?;
+        header.segment_count = cursor.read_u16()?;
+        header.module_reference_count = cursor.read_u16()?;
+        header.non_resident_names_size = cursor.read_u16()?;
+        header.segment_table_offset = cursor.read_u16()?;
+        header.resource_table_offset = cursor.read_u16()?;
+        header.resident_names_offset = cursor.read_u16()?;
+        header.module_reference_offset = cursor.read_u16()?;
+        header.imported_names_offset = cursor.read_u16()?;
+        header.non_resident_names_offset = cursor.read_u32()?;
+        header.movable_entry_count = cursor.read_u16()?;
+        header.alignment_shift = cursor.read_u16()?;
+        header.resource_segment_count = cursor.read_u16()?;
+        header.target_os = cursor.read_u8()?;
+        header.other_flags = cursor.read_u8()?;
+        cursor.skip(6)?; // Gangload area and minimum code swap size
+        header.expected_windows_version = cursor.read_u16()?;
+
+        return Ok(header);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NESegment {
+    pub file_offset: u64,
+    /// Bytes in the file, 0x10000 when the length field is zero
+    pub length: u32,
+    pub flags: u16,
+    pub min_alloc: u32,
+}
+
+impl NESegment {
+    pub fn is_data(&self) -> bool {
+        return self.flags & NESegmentFlags::Data as u16 != 0;
+    }
+
+    fn flags_as_string(&self) -> String {
+        let names: Vec<&'static str> = NE_SEGMENT_FLAGS
+            .iter()
+            .filter(|flag| self.flags & **flag as u16 != 0)
+            .map(|flag| flag.into())
+            .collect();
+
+        return names.join(" | ");
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NEImport {
+    Ordinal(u16),
+    Name(String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NEExecutable {
+    pub header_offset: u64,
+    pub header: NEHeader,
+    pub module_name: String,
+    pub description: String,
+    pub segments: Vec<NESegment>,
+    pub imported_modules: Vec<String>,
+    /// Module index -> the functions the relocations import from it
+    pub imports: BTreeMap<usize, Vec<NEImport>>,
+    pub resident_names: Vec<(String, u16)>,
+    pub non_resident_names: Vec<(String, u16)>,
+    pub warnings: Vec<String>,
+}
+
+impl NEExecutable {
+    pub fn from_parser(cursor: &mut PEReader, header_offset: u64) -> Result<NEExecutable, Box<dyn std::error::Error>> {
+        let mut ne = NEExecutable { header_offset, ..NEExecutable::default() };
+
+        cursor.set_position(header_offset)?;
+        ne.header = NEHeader::from_parser(cursor)?;
+
+        cursor.set_position(header_offset + ne.header.segment_table_offset as u64)?;
+
+        for _ in 0..ne.header.segment_count {
+            let sector = cursor.read_u16()?;
+            let length = cursor.read_u16()?;
+            let flags = cursor.read_u16()?;
+            let min_alloc = cursor.read_u16()?;
+
+            ne.segments.push(NESegment {
+                file_offset: (sector as u64) << ne.header.alignment_shift.min(16),
+                length: if length == 0 && sector != 0 { 0x10000 } else { length as u32 },
+                flags,
+                min_alloc: if min_alloc == 0 { 0x10000 } else { min_alloc as u32 },
+            });
+        }
+
+        let imported_names = header_offset + ne.header.imported_names_offset as u64;
+
+        for index in 0..ne.header.module_reference_count as u64 {
+            cursor.set_position(header_offset + ne.header.module_reference_offset as u64 + index * 2)?;
+
+            let name_offset = cursor.read_u16()?;
+
+            cursor.set_position(imported_names + name_offset as u64)?;
+            ne.imported_modules.push(read_pascal_string(cursor, &mut ne.warnings)?);
+        }
+
+        /* The imported functions are only known from the relocations patching their addresses in the segments */
+        for segment in ne.segments.iter().filter(|s| s.file_offset != 0 && s.flags & NESegmentFlags::RelocInfo as u16 != 0) {
+            cursor.set_position(segment.file_offset + segment.length as u64)?;
+
+            let count = cursor.read_u16()?;
+
+            for _ in 0..count {
+                cursor.skip(1)?; // Source type
+                let flags = cursor.read_u8()?;
+                cursor.skip(2)?; // Offset in the segment
+
+                let module_index = cursor.read_u16()? as usize;
+                let value = cursor.read_u16()?;
+
+                let import = match flags & 0x3 {
+                    NE_RELOCATION_IMPORT_ORDINAL => NEImport::Ordinal(value),
+                    NE_RELOCATION_IMPORT_NAME => {
+                        let position = cursor.position();
+
+                        cursor.set_position(imported_names + value as u64)?;
+                        let name = read_pascal_string(cursor, &mut ne.warnings)?;
+                        cursor.set_position(position)?;
+
+                        NEImport::Name(name)
+                    }
+                    _ => continue,
+                };
+
+                let functions = ne.imports.entry(module_index).or_default();
+
+                if !functions.contains(&import) {
+                    functions.push(import);
+                }
+            }
+        }
+
+        for functions in ne.imports.values_mut() {
+            functions.sort();
+        }
+
+        let resident_names = header_offset + ne.header.resident_names_offset as u64;
+
+        cursor.set_position(resident_names)?;
+        ne.resident_names = read_name_table(cursor, header_offset + ne.header.module_reference_offset as u64, &mut ne.warnings)?;
+
+        /* The first entries of the name tables are the module name and its description */
+        if !ne.resident_names.is_empty() {
+            ne.module_name = ne.resident_names.remove(0).0;
+        }
+
+        if ne.header.non_resident_names_offset != 0 {
+            let start = ne.header.non_resident_names_offset as u64;
+
+            cursor.set_position(start)?;
+            ne.non_resident_names = read_name_table(cursor, start + ne.header.non_resident_names_size as u64, &mut ne.warnings)?;
+
+            if !ne.non_resident_names.is_empty() {
+                ne.description = ne.non_resident_names.remove(0).0;
+            }
+        }
+
+        return Ok(ne);
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("NE Executable");
+
+        dump.push_field("HeaderOffset", format!("{:#x}", self.header_offset), None);
+        dump.push_field("ModuleName", self.module_name.clone(), None);
+        dump.push_field("Description", self.description.clone(), None);
+        dump.push_field("TargetOS", format!("{} ({})", self.header.target_os, target_os_name(self.header.target_os as u16)), None);
+        dump.push_field("LinkerVersion", format!("{}.{}", self.header.linker_version, self.header.linker_revision), None);
+        dump.push_field("ExpectedWindowsVersion", format!("{}.{}", self.header.expected_windows_version >> 8, self.header.expected_windows_version & 0xFF), None);
+        dump.push_field("Flags", format!("{:#x} ({})", self.header.flags, if self.header.flags & NE_FLAG_LIBRARY != 0 { "library" } else { "program" }), None);
+        dump.push_field("EntryPoint", format!("segment {} + {:#x}", self.header.initial_cs, self.header.initial_ip), None);
+        dump.push_field("Stack", format!("segment {} + {:#x} size: {:#x}", self.header.initial_ss, self.header.initial_sp, self.header.stack_size), None);
+        dump.push_field("HeapSize", format!("{:#x}", self.header.heap_size), None);
+        dump.push_field("AutoDataSegment", format!("{}", self.header.auto_data_segment), None);
+        dump.push_field("AlignmentShift", format!("{}", self.header.alignment_shift), None);
+
+        let mut segments_dump = Dump::new_from_string(format!("Segments ({})", self.segments.len()));
+
+        for (index, segment) in self.segments.iter().enumerate() {
+            let kind = if segment.is_data() { "DATA" } else { "CODE" };
+
+            segments_dump.push_field("", format!("#{:<3} {}  offset: {:#08x} length: {:#06x} alloc: {:#06x}  {}", index + 1, kind, segment.file_offset, segment.length, segment.min_alloc, segment.flags_as_string()), None);
+        }
+
+        let mut imports_dump = Dump::new_from_string(format!("Imported Modules ({})", self.imported_modules.len()));
+
+        for (index, module) in self.imported_modules.iter().enumerate() {
+            let functions = self.imports.get(&(index + 1)).map(|f| f.as_slice()).unwrap_or(&[]);
+
+            let mut module_dump = Dump::new_from_string(format!("{} ({} functions)", module, functions.len()));
+
+            for function in functions.iter() {
+                match function {
+                    NEImport::Ordinal(ordinal) => module_dump.push_field("", format!("{}.{}", module, ordinal), None),
+                    NEImport::Name(name) => module_dump.push_field("", name.clone(), None),
+                }
+            }
+
+            imports_dump.push_child(module_dump);
+        }
+
+        let mut resident_dump = Dump::new_from_string(format!("Resident Names ({})", self.resident_names.len()));
+        format_names(&mut resident_dump, &self.resident_names);
+
+        let mut non_resident_dump = Dump::new_from_string(format!("Non-Resident Names ({})", self.non_resident_names.len()));
+        format_names(&mut non_resident_dump, &self.non_resident_names);
+
+        dump.push_child(segments_dump);
+        dump.push_child(imports_dump);
+        dump.push_child(resident_dump);
+        dump.push_child(non_resident_dump);
+
+        for warning in self.warnings.iter() {
+            dump.push_field("Warning", warning.clone(), None);
+        }
+
+        return dump;
+    }
+}
+
+/*
+ * LE/LX (Linear Executable), VxDs of Windows 3.x/9x and 32-bit OS/2
+ * http://www.textfiles.com/programming/FORMATS/lxexe.txt
+ */
+
+/* Operating system of the VxDs */
+const LE_OS_WINDOWS_386: u16 = 4;
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum LEObjectFlags {
+    Readable = 0x0001,
+    Writable = 0x0002,
+    Executable = 0x0004,
+    Resource = 0x0008,
+    Discardable = 0x0010,
+    Shared = 0x0020,
+    Preload = 0x0040,
+    Big = 0x2000,
+}
+
+const LE_OBJECT_FLAGS: [LEObjectFlags; 8] = [
+    LEObjectFlags::Readable,
+    LEObjectFlags::Writable,
+    LEObjectFlags::Executable,
+    LEObjectFlags::Resource,
+    LEObjectFlags::Discardable,
+    LEObjectFlags::Shared,
+    LEObjectFlags::Preload,
+    LEObjectFlags::Big,
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct LEHeader {
+    pub byte_order: u8,
+    pub word_order: u8,
+    pub format_level: u32,
+    pub cpu_type: u16,
+    pub os_type: u16,
+    pub module_version: u32,
+    pub module_flags: u32,
+    pub page_count: u32,
+    pub eip_object: u32,
+    pub eip: u32,
+    pub esp_object: u32,
+    pub esp: u32,
+    pub page_size: u32,
+    pub object_table_offset: u32,
+    pub object_count: u32,
+    pub resident_names_offset: u32,
+    pub entry_table_offset: u32,
+    pub import_module_table_offset: u32,
+    pub import_module_count: u32,
+    /// From the start of the file, the other offsets are relative to the LE header
+    pub data_pages_offset: u32,
+    pub non_resident_names_offset: u32,
+    pub non_resident_names_size: u32,
+}
+
+impl LEHeader {
+    pub fn from_parser(cursor: &mut PEReader) -> Result<LEHeader, Box<dyn std::error::Error>> {
+        let mut header = LEHeader::default();
+
+        cursor.skip(2)?; // "LE" or "LX"
+        header.byte_order = cursor.read_u8()?;
+        header.word_order = cursor.read_u8()?;
+        header.format_level = cursor.read_u32()?;
+        header.cpu_type = cursor.read_u16()?;
+        header.os_type = cursor.read_u16()?;
+        header.module_version = cursor.read_u32()?;
+        header.module_flags = cursor.read_u32()?;
+        header.page_count = cursor.read_u32()?;
+        header.eip_object = cursor.read_u32()?;
+        header.eip = cursor.read_u32()?;
+        header.esp_object = cursor.read_u32()?;
+        header.esp = cursor.read_u32()?;
+        header.page_size = cursor.read_u32()?;
+        cursor.skip(4 * 5)?; // Page offset shift or last page size, fixup and loader section sizes and checksums
+        header.object_table_offset = cursor.read_u32()?;
+        header.object_count = cursor.read_u32()?;
+        cursor.skip(4 * 4)?; // Object page, object iterated pages and resource tables, resource count
+        header.resident_names_offset = cursor.read_u32()?;
+        header.entry_table_offset = cursor.read_u32()?;
+        cursor.skip(4 * 4)?; // Module directives, fixup page and fixup record tables
+        header.import_module_table_offset = cursor.read_u32()?;
+        header.import_module_count = cursor.read_u32()?;
+        cursor.skip(4 * 2)?; // Import procedure name and per-page checksum tables
+        header.data_pages_offset = cursor.read_u32()?;
+        cursor.skip(4)?; // Preload pages
+        header.non_resident_names_offset = cursor.read_u32()?;
+        header.non_resident_names_size = cursor.read_u32()?;
+
+        return Ok(header);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LEObject {
+    pub virtual_size: u32,
+    pub base_address: u32,
+    pub flags: u32,
+    pub page_table_index: u32,
+    pub page_count: u32,
+}
+
+impl LEObject {
+    fn flags_as_string(&self) -> String {
+        let names: Vec<&'static str> = LE_OBJECT_FLAGS
+            .iter()
+            .filter(|flag| self.flags & **flag as u32 != 0)
+            .map(|flag| flag.into())
+            .collect();
+
+        return names.join(" | ");
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LEExecutable {
+    pub format: LegacyFormat,
+    pub header_offset: u64,
+    pub header: LEHeader,
+    pub module_name: String,
+    pub description: String,
+    pub objects: Vec<LEObject>,
+    pub imported_modules: Vec<String>,
+    pub resident_names: Vec<(String, u16)>,
+    pub non_resident_names: Vec<(String, u16)>,
+    pub warnings: Vec<String>,
+}
+
+impl LEExecutable {
+    pub fn from_parser(cursor: &mut PEReader, format: LegacyFormat, header_offset: u64) -> Result<LEExecutable, Box<dyn std::error::Error>> {
+        cursor.set_position(header_offset)?;
+
+        let header = LEHeader::from_parser(cursor)?;
+
+        if header.byte_order != 0 || header.word_order != 0 {
+            return Err("Big-endian LE/LX executables are not supported".into());
+        }
+
+        let mut le = LEExecutable {
+            format,
+            header_offset,
+            header,
+            module_name: String::new(),
+            description: String::new(),
+            objects: Vec::new(),
+            imported_modules: Vec::new(),
+            resident_names: Vec::new(),
+            non_resident_names: Vec::new(),
+            warnings: Vec::new(),
+        };
+
+        cursor.set_position(header_offset + le.header.object_table_offset as u64)?;
+
+        for _ in 0..le.header.object_count.min(MAX_LEGACY_TABLE_ENTRIES) {
+            let object = LEObject {
+                virtual_size: cursor.read_u32()?,
+                base_address: cursor.read_u32()?,
+                flags: cursor.read_u32()?,
+                page_table_index: cursor.read_u32()?,
+                page_count: cursor.read_u32()?,
+            };
+
+            cursor.skip(4)?; // Reserved
+
+            le.objects.push(object);
+        }
+
+        /* The imported procedures are only named by the fixup records, the modules are listed on their own */
+        cursor.set_position(header_offset + le.header.import_module_table_offset as u64)?;
+
+        for _ in 0..le.header.import_module_count.min(MAX_LEGACY_TABLE_ENTRIES) {
+            le.imported_modules.push(read_pascal_string(cursor, &mut le.warnings)?);
+        }
+
+        /* The entry table follows the resident names */
+        let resident_names_end = match le.header.entry_table_offset > le.header.resident_names_offset {
+            true => header_offset + le.header.entry_table_offset as u64,
+            false => cursor.length(),
+        };
+
+        cursor.set_position(header_offset + le.header.resident_names_offset as u64)?;
+        le.resident_names = read_name_table(cursor, resident_names_end, &mut le.warnings)?;
+
+        if !le.resident_names.is_empty() {
+            le.module_name = le.resident_names.remove(0).0;
+        }
+
+        if le.header.non_resident_names_offset != 0 {
+            let start = le.header.non_resident_names_offset as u64;
+
+            cursor.set_position(start)?;
+            le.non_resident_names = read_name_table(cursor, start + le.header.non_resident_names_size as u64, &mut le.warnings)?;
+
+            if !le.non_resident_names.is_empty() {
+                le.description = le.non_resident_names.remove(0).0;
+            }
+        }
+
+        return Ok(le);
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let format: &'static str = self.format.into();
+        let mut dump = Dump::new_from_string(format!("{} Executable", format));
+
+        let cpu = match self.header.cpu_type {
+            1 => "80286",
+            2 => "80386",
+            3 => "80486",
+            _ => "Unknown",
+        };
+
+        dump.push_field("HeaderOffset", format!("{:#x}", self.header_offset), None);
+        dump.push_field("ModuleName", self.module_name.clone(), None);
+        dump.push_field("Description", self.description.clone(), None);
+        match self.header.os_type {
+            LE_OS_WINDOWS_386 => dump.push_field("TargetOS", format!("{} ({}, virtual device driver)", self.header.os_type, target_os_name(self.header.os_type)), None),
+            os_type => dump.push_field("TargetOS", format!("{} ({})", os_type, target_os_name(os_type)), None),
+        }
+
+        dump.push_field("CPU", format!("{} ({})", self.header.cpu_type, cpu), None);
+        dump.push_field("FormatLevel", format!("{}", self.header.format_level), None);
+        dump.push_field("ModuleVersion", format!("{:#x}", self.header.module_version), None);
+        dump.push_field("ModuleFlags", format!("{:#x}", self.header.module_flags), None);
+        dump.push_field("EntryPoint", format!("object {} + {:#x}", self.header.eip_object, self.header.eip), None);
+        dump.push_field("Stack", format!("object {} + {:#x}", self.header.esp_object, self.header.esp), None);
+        dump.push_field("PageSize", format!("{:#x}", self.header.page_size), None);
+        dump.push_field("Pages", format!("{} at {:#x}", self.header.page_count, self.header.data_pages_offset), None);
+
+        let mut objects_dump = Dump::new_from_string(format!("Objects ({})", self.objects.len()));
+
+        for (index, object) in self.objects.iter().enumerate() {
+            objects_dump.push_field("", format!("#{:<3} base: {:#010x} size: {:#08x} pages: {} (from {})  {}", index + 1, object.base_address, object.virtual_size, object.page_count, object.page_table_index, object.flags_as_string()), None);
+        }
+
+        let mut imports_dump = Dump::new_from_string(format!("Imported Modules ({})", self.imported_modules.len()));
+
+        for module in self.imported_modules.iter() {
+            imports_dump.push_field("", module.clone(), None);
+        }
+
+        let mut resident_dump = Dump::new_from_string(format!("Resident Names ({})", self.resident_names.len()));
+        format_names(&mut resident_dump, &self.resident_names);
+
+        let mut non_resident_dump = Dump::new_from_string(format!("Non-Resident Names ({})", self.non_resident_names.len()));
+        format_names(&mut non_resident_dump, &self.non_resident_names);
+
+        dump.push_child(objects_dump);
+        dump.push_child(imports_dump);
+        dump.push_child(resident_dump);
+        dump.push_child(non_resident_dump);
+
+        for warning in self.warnings.iter() {
+            dump.push_field("Warning", warning.clone(), None);
+        }
+
+        return dump;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum LegacyExecutable {
+    NE(NEExecutable),
+    LE(LEExecutable),
+}
+
+impl LegacyExecutable {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Option<LegacyExecutable>, Box<dyn std::error::Error>> {
+        let Some((format, header_offset)) = detect_legacy_format(bytes) else {
+            return Ok(None);
+        };
+
+        let mut cursor = PEReader::from_bytes(bytes);
+
+        match format {
+            LegacyFormat::NE => return Ok(Some(LegacyExecutable::NE(NEExecutable::from_parser(&mut cursor, header_offset)?))),
+            LegacyFormat::LE | LegacyFormat::LX => return Ok(Some(LegacyExecutable::LE(LEExecutable::from_parser(&mut cursor, format, header_offset)?))),
+        }
+    }
+
+    /// None for the files that are not NE or LE/LX executables, only their headers are read to tell
+    pub fn from_file(path: &Path) -> Result<Option<LegacyExecutable>, Box<dyn std::error::Error>> {
+        let mut file = File::open(path)?;
+        let mut dos_header = [0u8; E_LFANEW_OFFSET as usize + 4];
+
+        if file.read_exact(&mut dos_header).is_err() || dos_header[0..2] != DOS_MAGIC_ARRAY {
+            return Ok(None);
+        }
+
+        let mut signature = [0u8; 2];
+
+        file.seek(SeekFrom::Start(u32::from_le_bytes(dos_header[E_LFANEW_OFFSET as usize..].try_into()?) as u64))?;
+
+        if file.read_exact(&mut signature).is_err() || !matches!(&signature, b"NE" | b"LE" | b"LX") {
+            return Ok(None);
+        }
+
+        return LegacyExecutable::from_bytes(&std::fs::read(path)?);
+    }
+
+    pub fn dump(&self) -> Dump {
+        match self {
+            LegacyExecutable::NE(ne) => return ne.dump(),
+            LegacyExecutable::LE(le) => return le.dump(),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+pub mod pe;
+pub mod elf;
+pub mod dump;
+pub mod args;
+pub mod disasm;
+pub mod tui;
+pub mod format;
+pub mod exec;
+pub mod reader;
+pub mod demangle;
+pub mod x86_64;
+pub mod char_utils;
+pub mod analysis;
+pub mod resources;
+pub mod dotnet;
+pub mod cil;
+pub mod capabilities;
+pub mod diff;
+pub mod functions;
+pub mod deps;
+pub mod linkmap;
+pub mod driver;
+pub mod efi;
+pub mod extract;
+pub mod patch;
+pub mod repl;
+pub mod hashes;
+pub mod server;
+pub mod symbols;
+pub mod summary;
+pub mod address;
+pub mod authenticode;
+pub mod apiset;
+pub mod ordinals;
+pub mod mitigations;
+pub mod lint;
+pub mod timestamps;
+pub mod packers;
+pub mod minidump;
+pub mod carve;
+pub mod arm64x;
+pub mod legacy;
+pub mod fuzzy;
+pub mod toolchain;
+pub mod buildinfo;
+pub mod strings;
@@ -0,0 +1,290 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use crate::deps::pe_imported_dlls;
+use crate::dump::Dump;
+use crate::elf::{ELF, STB_WEAK};
+use crate::exec::{guess_exectype, parse_exec_with, Exec};
+use crate::pe::PE;
+
+/*
+ * Cross-check of the exports and imports of all the binaries found in a directory
+ */
+
+#[derive(Debug, Clone)]
+pub struct ModuleImport {
+    /// PE imports are bound to a DLL, ELF imports are resolved against every needed library
+    pub library: Option<String>,
+    pub symbol: String,
+    pub weak: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ModuleSymbols {
+    pub path: PathBuf,
+    /// Names the module can be imported by (file name, export name or SONAME)
+    pub names: Vec<String>,
+    /// Exported symbols, ordinals are written "#n" and PE exports are listed under both forms
+    pub exports: BTreeSet<String>,
+    pub export_ordinals: BTreeMap<String, String>,
+    pub imports: Vec<ModuleImport>,
+    pub needed_libraries: Vec<String>,
+}
+
+impl ModuleSymbols {
+    fn from_pe(path: &Path, pe: &PE) -> ModuleSymbols {
+        let mut module = ModuleSymbols::default();
+
+        module.path = path.to_path_buf();
+        module.names.push(file_name(path));
+
+        if let Some(ref export_data) = pe.export_data {
+            if !export_data.dll_name.is_empty() {
+                module.names.push(export_data.dll_name.clone());
+            }
+
+            for entry in export_data.entries.iter() {
+                let ordinal = format!("#{}", entry.ordinal);
+
+                if let Some(ref name) = entry.name {
+                    module.exports.insert(name.clone());
+                    module.export_ordinals.insert(ordinal.clone(), name.clone());
+                }
+
+                module.exports.insert(ordinal);
+            }
+        }
+
+        for dll in pe_imported_dlls(pe) {
+            module.needed_libraries.push(dll.name.clone());
+
+            for function in dll.functions.iter() {
+                module.imports.push(ModuleImport {
+                    library: Some(dll.name.clone()),
+                    symbol: function.to_string(),
+                    weak: false,
+                });
+            }
+        }
+
+        return module;
+    }
+
+    fn from_elf(path: &Path, elf: &ELF) -> ModuleSymbols {
+        let mut module = ModuleSymbols::default();
+
+        module.path = path.to_path_buf();
+        module.names.push(file_name(path));
+
+        if let Some(ref soname) = elf.soname {
+            module.names.push(soname.clone());
+        }
+
+        for symbol in elf.dynamic_symbols.iter() {
+            if symbol.is_exported() {
+                module.exports.insert(symbol.name.clone());
+            } else if symbol.is_imported() {
+                module.imports.push(ModuleImport {
+                    library: None,
+                    symbol: symbol.name.clone(),
+                    weak: symbol.binding() == STB_WEAK,
+                });
+            }
+        }
+
+        module.needed_libraries = elf.needed_libraries.clone();
+
+        return module;
+    }
+
+    pub fn name(&self) -> String {
+        return file_name(&self.path);
+    }
+
+    fn is_named(&self, name: &str) -> bool {
+        return self.names.iter().any(|n| n.eq_ignore_ascii_case(name));
+    }
+
+    /// Matches an export name or an ordinal written "#n"
+    fn exports_symbol(&self, symbol: &str) -> bool {
+        return self.exports.contains(symbol);
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    return path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(dir)?.flatten().map(|e| e.path()).collect();
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+
+    return Ok(());
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LinkMap {
+    pub modules: Vec<ModuleSymbols>,
+    pub skipped: Vec<(PathBuf, String)>,
+    /// (importer, exporter) -> symbols
+    pub links: BTreeMap<(String, String), Vec<String>>,
+    /// module -> exports nobody in the directory imports
+    pub dead_exports: BTreeMap<String, Vec<String>>,
+    /// (importer, library or None, symbol)
+    pub unresolved_imports: Vec<(String, Option<String>, String)>,
+}
+
+impl LinkMap {
+    /// Parses every PE/ELF file found in the directory (recursively) and links their imports to their exports
+    pub fn from_directory(dir: &Path) -> Result<LinkMap, Box<dyn std::error::Error>> {
+        let mut link_map = LinkMap::default();
+
+        let mut files = Vec::new();
+        collect_files(dir, &mut files)?;
+
+        for file in files {
+            /* Files that are not executables are silently ignored */
+            if guess_exectype(&file).is_err() {
+                continue;
+            }
+
+            match parse_exec_with(&file, false) {
+                Ok(Exec::PE(pe)) => link_map.modules.push(ModuleSymbols::from_pe(&file, &pe)),
+                Ok(Exec::ELF(elf)) => link_map.modules.push(ModuleSymbols::from_elf(&file, &elf)),
+                Err(e) => link_map.skipped.push((file, e.to_string())),
+            }
+        }
+
+        link_map.link();
+
+        return Ok(link_map);
+    }
+
+    fn link(&mut self) {
+        let mut used: BTreeSet<(usize, String)> = BTreeSet::new();
+
+        for importer in self.modules.iter() {
+            for import in importer.imports.iter() {
+                let providers: Vec<usize> = match import.library {
+                    Some(ref library) => self.modules.iter().enumerate().filter(|(_, m)| m.is_named(library)).map(|(i, _)| i).collect(),
+                    None => self
+                        .modules
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, m)| importer.needed_libraries.iter().any(|lib| m.is_named(lib)))
+                        .map(|(i, _)| i)
+                        .collect(),
+                };
+
+                let resolved = providers.iter().find(|i| self.modules[**i].exports_symbol(&import.symbol));
+
+                match resolved {
+                    Some(i) => {
+                        let exporter = &self.modules[*i];
+
+                        let symbol = exporter.export_ordinals.get(&import.symbol).unwrap_or(&import.symbol).clone();
+
+                        used.insert((*i, symbol.clone()));
+
+                        self.links.entry((importer.name(), exporter.name())).or_default().push(symbol);
+                    }
+                    None => {
+                        /* Libraries outside of the directory cannot be checked, ELF imports may come from any of them */
+                        let all_present = match import.library {
+                            Some(_) => !providers.is_empty(),
+                            None => importer.needed_libraries.iter().all(|lib| self.modules.iter().any(|m| m.is_named(lib))),
+                        };
+
+                        if all_present && !import.weak {
+                            self.unresolved_imports.push((importer.name(), import.library.clone(), import.symbol.clone()));
+                        }
+                    }
+                }
+            }
+        }
+
+        for (i, module) in self.modules.iter().enumerate() {
+            let dead: Vec<String> = module
+                .exports
+                .iter()
+                .filter(|e| !e.starts_with('#') || !module.export_ordinals.contains_key(*e))
+                .filter(|e| !used.contains(&(i, (*e).clone())))
+                .cloned()
+                .collect();
+
+            if !dead.is_empty() {
+                self.dead_exports.insert(module.name(), dead);
+            }
+        }
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Link Map");
+
+        dump.push_field("Modules", format!("{}", self.modules.len()), None);
+        dump.push_field("Links", format!("{}", self.links.len()), None);
+        dump.push_field("DeadExports", format!("{}", self.dead_exports.values().map(|v| v.len()).sum::<usize>()), None);
+        dump.push_field("UnresolvedImports", format!("{}", self.unresolved_imports.len()), None);
+
+        let mut modules_dump = Dump::new_from_string(format!("Modules ({})", self.modules.len()));
+
+        for module in self.modules.iter() {
+            modules_dump.push_field("", format!("{} (exports: {}, imports: {})", module.path.display(), module.exports.len(), module.imports.len()), None);
+        }
+
+        for (path, error) in self.skipped.iter() {
+            modules_dump.push_field("Skipped", format!("{} ({})", path.display(), error), None);
+        }
+
+        let mut matrix_dump = Dump::new("Cross-Reference Matrix");
+        let mut importers: BTreeMap<&String, Dump> = BTreeMap::new();
+
+        for ((importer, exporter), symbols) in self.links.iter() {
+            let importer_dump = importers.entry(importer).or_insert_with(|| Dump::new_from_string(importer.clone()));
+            importer_dump.push_field("->", format!("{} ({} symbols)", exporter, symbols.len()), None);
+        }
+
+        for (_, importer_dump) in importers.into_iter() {
+            matrix_dump.push_child(importer_dump);
+        }
+
+        let mut dead_dump = Dump::new("Dead Exports");
+
+        for (module, exports) in self.dead_exports.iter() {
+            let mut module_dump = Dump::new_from_string(format!("{} ({})", module, exports.len()));
+
+            for export in exports.iter() {
+                module_dump.push_field("", export.clone(), None);
+            }
+
+            dead_dump.push_child(module_dump);
+        }
+
+        let mut unresolved_dump = Dump::new_from_string(format!("Unresolved Imports ({})", self.unresolved_imports.len()));
+
+        for (importer, library, symbol) in self.unresolved_imports.iter() {
+            let target = match library {
+                Some(library) => format!("{}!{}", library, symbol),
+                None => symbol.clone(),
+            };
+
+            unresolved_dump.push_field("", format!("{}: {}", importer, target), None);
+        }
+
+        dump.push_child(modules_dump);
+        dump.push_child(matrix_dump);
+        dump.push_child(dead_dump);
+        dump.push_child(unresolved_dump);
+
+        return dump;
+    }
+}
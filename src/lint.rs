@@ -0,0 +1,381 @@
+use strum_macros::IntoStaticStr;
+
+use crate::dump::Dump;
+use crate::pe::{compute_checksum, SectionFlags, PE, SECTION_HEADER_SIZE};
+
+/*
+ * Structural anomalies of PE files: what the loader tolerates but no linker produces, and what breaks the tools
+ * The section table and the data directories are read from the file bytes, the parsed PE merges the sections of same name
+ */
+
+/* Older Windows loaders refuse images with more sections */
+const MAX_LOADER_SECTIONS: usize = 96;
+
+/* Section alignment of the images the loader maps page by page */
+const PAGE_SIZE: u32 = 0x1000;
+
+/* File alignment bounds of the specification, for the section alignments of at least a page */
+const MIN_FILE_ALIGNMENT: u32 = 0x200;
+const MAX_FILE_ALIGNMENT: u32 = 0x10000;
+
+/* Virtual size from which a section without raw data is worth a look, the loader zero-fills all of it */
+const LARGE_VIRTUAL_SIZE: u32 = 0x100000;
+
+/* Offsets in the optional header of NumberOfRvaAndSizes */
+const PE32_NUMBER_OF_RVA_AND_SIZES_OFFSET: u64 = 92;
+const PE64_NUMBER_OF_RVA_AND_SIZES_OFFSET: u64 = 108;
+
+pub const DATA_DIRECTORY_NAMES: [&str; 16] = [
+    "ExportTable", "ImportTable", "ResourceTable", "ExceptionTable", "CertificateTable", "BaseRelocationTable", "Debug", "Architecture",
+    "GlobalPtr", "TLSTable", "LoadConfigTable", "BoundImport", "ImportAddressTable", "DelayImportDescriptor", "CLRRuntimeHeader", "Reserved",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum LintSeverity {
+    /// The loader rejects the image or maps something else than the tools see
+    Error,
+    Warning,
+    /// Unusual but harmless
+    Info,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum LintCode {
+    SectionTableTruncated,
+    SectionTableOutsideHeaders,
+    TooManySections,
+    DuplicateSectionName,
+    NonPrintableSectionName,
+    SectionsOverlapVirtual,
+    SectionsOverlapRaw,
+    SectionPastEndOfFile,
+    EmptyRawDataLargeVirtualSize,
+    WritableExecutableSection,
+    SectionVirtualAddressMisaligned,
+    SectionRawDataMisaligned,
+    NonstandardSectionAlignment,
+    NonstandardFileAlignment,
+    SizeOfImageMismatch,
+    SizeOfHeadersMismatch,
+    EntryPointOutsideSections,
+    EntryPointNotExecutable,
+    NumberOfRvaAndSizes,
+    DataDirectoryOutsideImage,
+    ChecksumMismatch,
+}
+
+impl LintCode {
+    pub fn severity(&self) -> LintSeverity {
+        match self {
+            LintCode::SectionTableTruncated
+            | LintCode::SectionsOverlapVirtual
+            | LintCode::SectionPastEndOfFile
+            | LintCode::EntryPointOutsideSections => return LintSeverity::Error,
+            LintCode::DuplicateSectionName
+            | LintCode::NonPrintableSectionName
+            | LintCode::SectionRawDataMisaligned
+            | LintCode::NumberOfRvaAndSizes
+            | LintCode::ChecksumMismatch => return LintSeverity::Info,
+            _ => return LintSeverity::Warning,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LintFinding {
+    pub code: LintCode,
+    pub severity: LintSeverity,
+    pub message: String,
+}
+
+/// Section header fields as stored in the file
+#[derive(Debug, Clone, Default)]
+struct RawSectionHeader {
+    name: String,
+    virtual_size: u32,
+    virtual_address: u32,
+    size_of_raw_data: u32,
+    ptr_to_raw_data: u32,
+    characteristics: u32,
+}
+
+fn read_u16(bytes: &[u8], offset: u64) -> Option<u16> {
+    let offset = usize::try_from(offset).ok()?;
+
+    return bytes.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]]));
+}
+
+fn read_u32(bytes: &[u8], offset: u64) -> Option<u32> {
+    let offset = usize::try_from(offset).ok()?;
+
+    return bytes.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+}
+
+fn align_up(value: u64, alignment: u64) -> u64 {
+    return value.div_ceil(alignment.max(1)) * alignment.max(1);
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LintReport {
+    pub findings: Vec<LintFinding>,
+}
+
+impl LintReport {
+    fn push(&mut self, code: LintCode, message: String) {
+        self.findings.push(LintFinding { code, severity: code.severity(), message });
+    }
+
+    pub fn from_pe(pe: &PE, file_bytes: &[u8]) -> LintReport {
+        let mut report = LintReport::default();
+
+        let optional_header = pe.get_optional_header();
+        let section_alignment = optional_header.get_section_alignment();
+        let file_alignment = optional_header.get_file_alignment();
+        let size_of_headers = optional_header.get_size_of_headers();
+        let file_size = file_bytes.len() as u64;
+
+        /* Section table */
+        let number_of_sections = pe.get_number_of_sections();
+        let table_offset = pe.get_section_table_offset();
+        let table_end = table_offset + number_of_sections as u64 * SECTION_HEADER_SIZE;
+
+        let mut sections = Vec::new();
+
+        for i in 0..number_of_sections as u64 {
+            let offset = table_offset + i * SECTION_HEADER_SIZE;
+
+            let Some(raw_name) = file_bytes.get(offset as usize..(offset + 8) as usize) else {
+                report.push(LintCode::SectionTableTruncated, format!("{} section headers claimed, the file ends after {}", number_of_sections, i));
+                break;
+            };
+
+            let (Some(virtual_size), Some(virtual_address), Some(size_of_raw_data), Some(ptr_to_raw_data), Some(characteristics)) = (
+                read_u32(file_bytes, offset + 8),
+                read_u32(file_bytes, offset + 12),
+                read_u32(file_bytes, offset + 16),
+                read_u32(file_bytes, offset + 20),
+                read_u32(file_bytes, offset + 36),
+            ) else {
+                report.push(LintCode::SectionTableTruncated, format!("{} section headers claimed, the file ends after {}", number_of_sections, i));
+                break;
+            };
+
+            let name_end = raw_name.iter().position(|b| *b == 0).unwrap_or(8);
+
+            if raw_name[..name_end].iter().any(|b| !b.is_ascii_graphic()) {
+                report.push(LintCode::NonPrintableSectionName, format!("section {} has a name that is not printable ASCII", i));
+            }
+
+            sections.push(RawSectionHeader {
+                name: String::from_utf8_lossy(&raw_name[..name_end]).to_string(),
+                virtual_size,
+                virtual_address,
+                size_of_raw_data,
+                ptr_to_raw_data,
+                characteristics,
+            });
+        }
+
+        if number_of_sections > MAX_LOADER_SECTIONS {
+            report.push(LintCode::TooManySections, format!("{} sections, older loaders refuse more than {}", number_of_sections, MAX_LOADER_SECTIONS));
+        }
+
+        if table_end > size_of_headers as u64 {
+            report.push(LintCode::SectionTableOutsideHeaders, format!("the section table ends at {:#x}, past SizeOfHeaders {:#x}", table_end, size_of_headers));
+        }
+
+        for (i, section) in sections.iter().enumerate() {
+            if sections[..i].iter().any(|other| other.name == section.name) {
+                report.push(LintCode::DuplicateSectionName, format!("{} is the name of several sections, the tools keying the sections by name only see one", section.name));
+            }
+        }
+
+        /* A section without raw data takes its virtual size alone, the others at least their raw size */
+        let virtual_extent = |section: &RawSectionHeader| -> u64 {
+            let size = match section.virtual_size {
+                0 => section.size_of_raw_data,
+                size => size,
+            };
+
+            return align_up(size as u64, section_alignment as u64);
+        };
+
+        let mut by_address: Vec<&RawSectionHeader> = sections.iter().collect();
+        by_address.sort_by_key(|section| section.virtual_address);
+
+        for pair in by_address.windows(2) {
+            let end = pair[0].virtual_address as u64 + virtual_extent(pair[0]);
+
+            if end > pair[1].virtual_address as u64 {
+                report.push(LintCode::SectionsOverlapVirtual, format!("{} ends at {:#x} in memory, past the start of {} at {:#x}", pair[0].name, end, pair[1].name, pair[1].virtual_address));
+            }
+        }
+
+        let mut by_offset: Vec<&RawSectionHeader> = sections.iter().filter(|s| s.size_of_raw_data != 0).collect();
+        by_offset.sort_by_key(|section| section.ptr_to_raw_data);
+
+        for pair in by_offset.windows(2) {
+            let end = pair[0].ptr_to_raw_data as u64 + pair[0].size_of_raw_data as u64;
+
+            if end > pair[1].ptr_to_raw_data as u64 {
+                report.push(LintCode::SectionsOverlapRaw, format!("the raw data of {} ends at {:#x}, past the start of {} at {:#x}", pair[0].name, end, pair[1].name, pair[1].ptr_to_raw_data));
+            }
+        }
+
+        for section in sections.iter() {
+            let raw_end = section.ptr_to_raw_data as u64 + section.size_of_raw_data as u64;
+            let executable = (section.characteristics & (SectionFlags::MemExecute as u32 | SectionFlags::CntCode as u32)) != 0;
+            let writable = (section.characteristics & SectionFlags::MemWrite as u32) != 0;
+
+            if section.size_of_raw_data != 0 && raw_end > file_size {
+                report.push(LintCode::SectionPastEndOfFile, format!("the raw data of {} ends at {:#x}, past the end of the file at {:#x}", section.name, raw_end, file_size));
+            }
+
+            /* Expected from .bss, suspicious from code that has to be unpacked into it */
+            if section.size_of_raw_data == 0 && (section.virtual_size >= LARGE_VIRTUAL_SIZE || executable) && section.virtual_size != 0 {
+                report.push(LintCode::EmptyRawDataLargeVirtualSize, format!("{} has no raw data but a virtual size of {:#x}{}", section.name, section.virtual_size, if executable { " and is executable" } else { "" }));
+            }
+
+            if executable && writable {
+                report.push(LintCode::WritableExecutableSection, format!("{} is both writable and executable", section.name));
+            }
+
+            if section_alignment != 0 && !section.virtual_address.is_multiple_of(section_alignment) {
+                report.push(LintCode::SectionVirtualAddressMisaligned, format!("{} starts at {:#x}, not a multiple of SectionAlignment {:#x}", section.name, section.virtual_address, section_alignment));
+            }
+
+            /* The loader rounds the pointer down to a multiple of 512, whatever the file alignment */
+            if file_alignment != 0 && section.size_of_raw_data != 0 && !section.ptr_to_raw_data.is_multiple_of(file_alignment) {
+                report.push(LintCode::SectionRawDataMisaligned, format!("the raw data of {} starts at {:#x}, not a multiple of FileAlignment {:#x}", section.name, section.ptr_to_raw_data, file_alignment));
+            }
+        }
+
+        /* Alignments */
+        if !section_alignment.is_power_of_two() || section_alignment < file_alignment {
+            report.push(LintCode::NonstandardSectionAlignment, format!("SectionAlignment {:#x} is not a power of two of at least FileAlignment {:#x}", section_alignment, file_alignment));
+        } else if section_alignment != PAGE_SIZE {
+            report.push(LintCode::NonstandardSectionAlignment, format!("SectionAlignment {:#x} is not the page size {:#x}", section_alignment, PAGE_SIZE));
+        }
+
+        /* Below the page size both alignments must be equal, the image is then mapped as a whole */
+        if !file_alignment.is_power_of_two() {
+            report.push(LintCode::NonstandardFileAlignment, format!("FileAlignment {:#x} is not a power of two", file_alignment));
+        } else if section_alignment < PAGE_SIZE && file_alignment != section_alignment {
+            report.push(LintCode::NonstandardFileAlignment, format!("FileAlignment {:#x} differs from SectionAlignment {:#x}, below the page size", file_alignment, section_alignment));
+        } else if section_alignment >= PAGE_SIZE && !(MIN_FILE_ALIGNMENT..=MAX_FILE_ALIGNMENT).contains(&file_alignment) {
+            report.push(LintCode::NonstandardFileAlignment, format!("FileAlignment {:#x} is outside of {:#x}-{:#x}", file_alignment, MIN_FILE_ALIGNMENT, MAX_FILE_ALIGNMENT));
+        }
+
+        /* Sizes */
+        let expected_size_of_image = by_address
+            .last()
+            .map(|section| section.virtual_address as u64 + virtual_extent(section))
+            .unwrap_or(align_up(size_of_headers as u64, section_alignment as u64));
+
+        if optional_header.get_size_of_image() as u64 != align_up(expected_size_of_image, section_alignment as u64) {
+            report.push(LintCode::SizeOfImageMismatch, format!("SizeOfImage is {:#x}, the sections end at {:#x}", optional_header.get_size_of_image(), expected_size_of_image));
+        }
+
+        let first_raw_data = by_offset.first().map(|section| section.ptr_to_raw_data);
+
+        if file_alignment != 0 && !size_of_headers.is_multiple_of(file_alignment) {
+            report.push(LintCode::SizeOfHeadersMismatch, format!("SizeOfHeaders {:#x} is not a multiple of FileAlignment {:#x}", size_of_headers, file_alignment));
+        }
+
+        if let Some(first_raw_data) = first_raw_data.filter(|offset| size_of_headers > *offset) {
+            report.push(LintCode::SizeOfHeadersMismatch, format!("SizeOfHeaders {:#x} covers the raw data of the first section at {:#x}", size_of_headers, first_raw_data));
+        }
+
+        /* Entry point, DLLs may have none */
+        let entry_point = optional_header.get_address_of_entry_point();
+
+        if entry_point != 0 {
+            match sections.iter().find(|s| entry_point >= s.virtual_address && (entry_point as u64) < s.virtual_address as u64 + virtual_extent(s)) {
+                None if entry_point < size_of_headers => report.push(LintCode::EntryPointOutsideSections, format!("the entry point {:#x} is inside the headers", entry_point)),
+                None => report.push(LintCode::EntryPointOutsideSections, format!("the entry point {:#x} is outside of the sections", entry_point)),
+                Some(section) if (section.characteristics & (SectionFlags::MemExecute as u32 | SectionFlags::CntCode as u32)) == 0 => {
+                    report.push(LintCode::EntryPointNotExecutable, format!("the entry point {:#x} is in {}, a section that is not executable", entry_point, section.name))
+                }
+                Some(_) => {}
+            }
+        }
+
+        /* Data directories */
+        let number_of_rva_and_sizes_offset = match pe.is_32_bits() {
+            true => PE32_NUMBER_OF_RVA_AND_SIZES_OFFSET,
+            false => PE64_NUMBER_OF_RVA_AND_SIZES_OFFSET,
+        };
+
+        let number_of_rva_and_sizes = read_u32(file_bytes, pe.get_optional_header_offset() + number_of_rva_and_sizes_offset).unwrap_or(0);
+
+        if number_of_rva_and_sizes != DATA_DIRECTORY_NAMES.len() as u32 {
+            report.push(LintCode::NumberOfRvaAndSizes, format!("NumberOfRvaAndSizes is {}, not {}", number_of_rva_and_sizes, DATA_DIRECTORY_NAMES.len()));
+        }
+
+        let size_of_image = optional_header.get_size_of_image() as u64;
+
+        for (index, name) in DATA_DIRECTORY_NAMES.iter().enumerate().take(number_of_rva_and_sizes as usize) {
+            let offset = pe.get_data_directory_offset(index);
+
+            let (Some(address), Some(size)) = (read_u32(file_bytes, offset), read_u32(file_bytes, offset + 4)) else {
+                break;
+            };
+
+            if address == 0 && size == 0 {
+                continue;
+            }
+
+            /* The certificate table is the only directory given by file offset, it is never mapped */
+            let (end, limit, space) = match *name {
+                "CertificateTable" => (address as u64 + size as u64, file_size, "file"),
+                _ => (address as u64 + size as u64, size_of_image, "image"),
+            };
+
+            if end > limit {
+                report.push(LintCode::DataDirectoryOutsideImage, format!("{} ends at {:#x}, past the end of the {} at {:#x}", name, end, space, limit));
+            }
+        }
+
+        /* Only drivers and the DLLs loaded at boot are checked by the loader */
+        let checksum = optional_header.get_checksum();
+
+        if checksum != 0 && read_u16(file_bytes, 0).is_some() {
+            let computed = compute_checksum(file_bytes, pe.get_checksum_offset());
+
+            if computed != checksum {
+                report.push(LintCode::ChecksumMismatch, format!("CheckSum is {:#x}, the file sums to {:#x}", checksum, computed));
+            }
+        }
+
+        report.findings.sort_by_key(|finding| finding.severity);
+
+        return report;
+    }
+
+    pub fn count(&self, severity: LintSeverity) -> usize {
+        return self.findings.iter().filter(|f| f.severity == severity).count();
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new_from_string(format!("Lint ({} findings)", self.findings.len()));
+
+        dump.push_field("Errors", format!("{}", self.count(LintSeverity::Error)), None);
+        dump.push_field("Warnings", format!("{}", self.count(LintSeverity::Warning)), None);
+        dump.push_field("Infos", format!("{}", self.count(LintSeverity::Info)), None);
+
+        let mut findings_dump = Dump::new("Findings");
+
+        for finding in self.findings.iter() {
+            let severity: &'static str = finding.severity.into();
+
+            findings_dump.push_field(finding.code.into(), format!("[{}] {}", severity, finding.message), None);
+        }
+
+        dump.push_child(findings_dump);
+
+        return dump;
+    }
+}
@@ -1,38 +1,77 @@
-use crate::dump::dump_exec;
-use crate::pe::parse_pe;
-use crate::elf::parse_elf;
-use crate::args::Args;
-use crate::exec::{ExecType, guess_exectype, Exec};
+use execdump::dump::{dump_exec, dump_diff, dump_edit, dump_link_map, dump_addresses, dump_carve, dump_legacy, dump_minidump, dump_strings};
+use execdump::args::{Args, Command};
+use execdump::exec::{parse_exec, parse_exec_memory_image, parse_exec_with, Exec};
+use execdump::legacy::LegacyExecutable;
+use execdump::symbols::{self, SymbolStore};
+use execdump::{demangle, diff, format, patch, repl, server, tui};
 
 use clap::Parser;
 
-pub mod pe;
-pub mod elf;
-pub mod dump;
-pub mod args;
-pub mod disasm;
-pub mod tui;
-pub mod format;
-pub mod exec;
-pub mod reader;
-pub mod demangle;
-pub mod x86_64;
-pub mod char_utils;
-
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
-    let exectype = guess_exectype(&args.file_path)?;
+    demangle::set_demangle_names(!args.no_demangle);
+    format::set_time_format(args.time_format.as_deref(), args.timezone)?;
+
+    if let Some(Command::Diff(diff_args)) = &args.command {
+        let old_exec = parse_exec(&diff_args.old_file_path)?;
+        let new_exec = parse_exec(&diff_args.new_file_path)?;
+
+        let exec_diff = diff::diff(&old_exec, &new_exec)?;
+
+        if diff_args.tui {
+            return tui::diff_main(&diff_args.old_file_path, old_exec, &diff_args.new_file_path, exec_diff);
+        } else {
+            dump_diff(&exec_diff, diff_args);
+        }
+
+        return Ok(());
+    }
+
+    match &args.command {
+        Some(Command::Patch(a)) => return dump_edit(&a.file_path, a.padding_size, a.output_format, patch::patch(a)?),
+        Some(Command::AddSection(a)) => return dump_edit(&a.file_path, a.padding_size, a.output_format, patch::add_section(a)?),
+        Some(Command::AddImport(a)) => return dump_edit(&a.file_path, a.padding_size, a.output_format, patch::add_import(a)?),
+        Some(Command::RenameSection(a)) => return dump_edit(&a.file_path, a.padding_size, a.output_format, patch::rename_section(a)?),
+        Some(Command::Unmap(a)) => return dump_edit(&a.file_path, a.padding_size, a.output_format, patch::unmap(a)?),
+        Some(Command::RebuildIat(a)) => return dump_edit(&a.file_path, a.padding_size, a.output_format, patch::rebuild_iat(a)?),
+        Some(Command::Normalize(a)) => return dump_edit(&a.file_path, a.padding_size, a.output_format, patch::normalize(a)?),
+        Some(Command::Minidump(a)) => return dump_minidump(a),
+        Some(Command::Carve(a)) => return dump_carve(a),
+        Some(Command::Strings(a)) => return dump_strings(a),
+        Some(Command::Addr(a)) => return dump_addresses(a),
+        Some(Command::Repl(a)) => return repl::run(a),
+        Some(Command::Serve(a)) => return server::run(a),
+        _ => {}
+    }
 
-    let exec = match exectype {
-        ExecType::PE => Exec::PE(parse_pe(&args.file_path)?),
-        ExecType::ELF => Exec::ELF(parse_elf(&args.file_path)?),
+    if let Some(ref directory) = args.link_map {
+        return dump_link_map(directory, &args);
+    }
+
+    let file_path = args.file_path.clone().ok_or("Missing executable file path")?;
+
+    /* NE and LE/LX executables only get their own view, the PE and ELF dumps do not apply */
+    if let Some(legacy) = LegacyExecutable::from_file(&file_path)? {
+        return dump_legacy(&legacy, &args);
+    }
+
+    let mut exec = match args.memory_image {
+        true => parse_exec_memory_image(&file_path, args.needs_section_data())?,
+        false => parse_exec_with(&file_path, args.needs_section_data())?,
     };
 
+    if let (true, Exec::PE(pe)) = (args.fetch_pdb, &mut exec) {
+        /* The executable is still dumped without its symbols */
+        if let Err(e) = symbols::fetch_pdb(pe, &SymbolStore::new(&args.symbol_server, args.symbol_cache.as_deref())) {
+            eprintln!("Warning: cannot fetch the PDB: {}", e);
+        }
+    }
+
     if args.tui {
-        return tui::main(&args.file_path, exec);
+        return tui::main(&file_path, exec);
     } else {
-        dump_exec(&exec, &args);
+        dump_exec(&exec, &args)?;
     }
 
 
@@ -0,0 +1,537 @@
+use std::path::Path;
+
+use strum_macros::IntoStaticStr;
+
+use crate::dump::Dump;
+use crate::exec::{parse_exec_memory_image_from_bytes, Exec};
+use crate::extract::sanitize_file_name;
+use crate::format::format_timestamp;
+use crate::pe::PEReader;
+use crate::summary::Summary;
+
+/*
+ * Minidump (.dmp) files written by MiniDumpWriteDump, WER and the debuggers
+ * https://learn.microsoft.com/en-us/windows/win32/api/minidumpapiset/
+ */
+
+pub const MINIDUMP_SIGNATURE: [u8; 4] = *b"MDMP";
+
+const MINIDUMP_MODULE_SIZE: u64 = 108;
+const MINIDUMP_THREAD_SIZE: u64 = 48;
+const MINIDUMP_MEMORY_DESCRIPTOR_SIZE: u64 = 16;
+
+/* Broken counts must not make the parser allocate for millions of entries */
+const MAX_STREAMS: u32 = 0x1000;
+const MAX_ENTRIES: u64 = 0x100000;
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum MinidumpStreamType {
+    Unused = 0,
+    ThreadList = 3,
+    ModuleList = 4,
+    MemoryList = 5,
+    Exception = 6,
+    SystemInfo = 7,
+    ThreadExList = 8,
+    Memory64List = 9,
+    CommentA = 10,
+    CommentW = 11,
+    HandleData = 12,
+    FunctionTable = 13,
+    UnloadedModuleList = 14,
+    MiscInfo = 15,
+    MemoryInfoList = 16,
+    ThreadInfoList = 17,
+    HandleOperationList = 18,
+    Token = 19,
+    Unknown = 0xffffffff,
+}
+
+impl From<u32> for MinidumpStreamType {
+    fn from(value: u32) -> Self {
+        match value {
+            0 => MinidumpStreamType::Unused,
+            3 => MinidumpStreamType::ThreadList,
+            4 => MinidumpStreamType::ModuleList,
+            5 => MinidumpStreamType::MemoryList,
+            6 => MinidumpStreamType::Exception,
+            7 => MinidumpStreamType::SystemInfo,
+            8 => MinidumpStreamType::ThreadExList,
+            9 => MinidumpStreamType::Memory64List,
+            10 => MinidumpStreamType::CommentA,
+            11 => MinidumpStreamType::CommentW,
+            12 => MinidumpStreamType::HandleData,
+            13 => MinidumpStreamType::FunctionTable,
+            14 => MinidumpStreamType::UnloadedModuleList,
+            15 => MinidumpStreamType::MiscInfo,
+            16 => MinidumpStreamType::MemoryInfoList,
+            17 => MinidumpStreamType::ThreadInfoList,
+            18 => MinidumpStreamType::HandleOperationList,
+            19 => MinidumpStreamType::Token,
+            _ => MinidumpStreamType::Unknown,
+        }
+    }
+}
+
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum ProcessorArchitecture {
+    Intel = 0,
+    Arm = 5,
+    Ia64 = 6,
+    Amd64 = 9,
+    Arm64 = 12,
+    Unknown = 0xffff,
+}
+
+impl From<u16> for ProcessorArchitecture {
+    fn from(value: u16) -> Self {
+        match value {
+            0 => ProcessorArchitecture::Intel,
+            5 => ProcessorArchitecture::Arm,
+            6 => ProcessorArchitecture::Ia64,
+            9 => ProcessorArchitecture::Amd64,
+            12 => ProcessorArchitecture::Arm64,
+            _ => ProcessorArchitecture::Unknown,
+        }
+    }
+}
+
+impl ProcessorArchitecture {
+    /// Offsets of the instruction and stack pointers in the CONTEXT record of the architecture, and their size
+    fn context_registers(&self) -> Option<(u64, u64, usize)> {
+        match self {
+            ProcessorArchitecture::Intel => return Some((0xb8, 0xc4, 4)),
+            ProcessorArchitecture::Amd64 => return Some((0xf8, 0x98, 8)),
+            ProcessorArchitecture::Arm64 => return Some((0x108, 0x100, 8)),
+            _ => return None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MinidumpHeader {
+    pub version: u32,
+    pub number_of_streams: u32,
+    pub stream_directory_rva: u32,
+    pub checksum: u32,
+    pub time_date_stamp: u32,
+    pub flags: u64,
+}
+
+impl MinidumpHeader {
+    pub fn from_parser(cursor: &mut PEReader) -> Result<MinidumpHeader, Box<dyn std::error::Error>> {
+        let mut header = MinidumpHeader::default();
+
+        if cursor.read_n::<4>()? != MINIDUMP_SIGNATURE {
+            return Err("Invalid minidump signature".into());
+        }
+
+        header.version = cursor.read_u32()?;
+        header.number_of_streams = cursor.read_u32()?;
+        header.stream_directory_rva = cursor.read_u32()?;
+        header.checksum = cursor.read_u32()?;
+        header.time_date_stamp = cursor.read_u32()?;
+        header.flags = cursor.read_u64()?;
+
+        return Ok(header);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MinidumpStream {
+    pub stream_type: u32,
+    pub data_size: u32,
+    pub rva: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MinidumpSystemInfo {
+    pub processor_architecture: u16,
+    pub number_of_processors: u8,
+    pub major_version: u32,
+    pub minor_version: u32,
+    pub build_number: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MinidumpModule {
+    pub base_of_image: u64,
+    pub size_of_image: u32,
+    pub checksum: u32,
+    pub time_date_stamp: u32,
+    /// Full path of the module in the dumped process
+    pub name: String,
+}
+
+impl MinidumpModule {
+    /// "C:\Windows\System32\ntdll.dll" becomes "ntdll.dll"
+    pub fn file_name(&self) -> &str {
+        return self.name.rsplit(['\\', '/']).next().unwrap_or(&self.name);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MinidumpThread {
+    pub thread_id: u32,
+    pub suspend_count: u32,
+    pub priority: u32,
+    pub teb: u64,
+    pub stack_start: u64,
+    pub stack_size: u32,
+    pub context_size: u32,
+    /// Read from the CONTEXT record of the architectures the system info tells
+    pub instruction_pointer: Option<u64>,
+    pub stack_pointer: Option<u64>,
+}
+
+/// Range of the process memory saved in the dump, at the file offset rva
+#[derive(Debug, Clone, Default)]
+pub struct MinidumpMemoryRange {
+    pub start: u64,
+    pub size: u64,
+    pub rva: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Minidump {
+    pub header: MinidumpHeader,
+    pub streams: Vec<MinidumpStream>,
+    pub system_info: Option<MinidumpSystemInfo>,
+    pub modules: Vec<MinidumpModule>,
+    pub threads: Vec<MinidumpThread>,
+    pub memory_ranges: Vec<MinidumpMemoryRange>,
+    pub warnings: Vec<String>,
+}
+
+pub fn is_minidump(bytes: &[u8]) -> bool {
+    return bytes.get(0..4) == Some(&MINIDUMP_SIGNATURE[..]);
+}
+
+impl Minidump {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Minidump, Box<dyn std::error::Error>> {
+        let mut cursor = PEReader::from_bytes(bytes);
+        let mut minidump = Minidump::default();
+
+        minidump.header = MinidumpHeader::from_parser(&mut cursor)?;
+
+        cursor.set_position(minidump.header.stream_directory_rva as u64)?;
+
+        for _ in 0..minidump.header.number_of_streams.min(MAX_STREAMS) {
+            minidump.streams.push(MinidumpStream {
+                stream_type: cursor.read_u32()?,
+                data_size: cursor.read_u32()?,
+                rva: cursor.read_u32()?,
+            });
+        }
+
+        /* The system info comes first, the thread contexts depend on the architecture */
+        let mut streams = minidump.streams.clone();
+        streams.sort_by_key(|stream| MinidumpStreamType::from(stream.stream_type) != MinidumpStreamType::SystemInfo);
+
+        for stream in streams.iter() {
+            let stream_type = MinidumpStreamType::from(stream.stream_type);
+
+            let parsed = cursor
+                .sub_reader(stream.rva as u64, stream.data_size as u64)
+                .map_err(|e| e.into())
+                .and_then(|mut data| match stream_type {
+                    MinidumpStreamType::SystemInfo => minidump.parse_system_info(&mut data),
+                    MinidumpStreamType::ModuleList => minidump.parse_module_list(&mut data, bytes),
+                    MinidumpStreamType::ThreadList => minidump.parse_thread_list(&mut data, bytes),
+                    MinidumpStreamType::MemoryList => minidump.parse_memory_list(&mut data),
+                    MinidumpStreamType::Memory64List => minidump.parse_memory64_list(&mut data),
+                    _ => Ok(()),
+                });
+
+            if let Err(e) = parsed {
+                let name: &'static str = stream_type.into();
+                minidump.warnings.push(format!("{} stream at {:#x}: {}", name, stream.rva, e));
+            }
+        }
+
+        minidump.memory_ranges.sort_by_key(|range| range.start);
+
+        return Ok(minidump);
+    }
+
+    fn parse_system_info(&mut self, cursor: &mut PEReader) -> Result<(), Box<dyn std::error::Error>> {
+        let mut system_info = MinidumpSystemInfo::default();
+
+        system_info.processor_architecture = cursor.read_u16()?;
+        cursor.skip(4)?;
+        system_info.number_of_processors = cursor.read_u8()?;
+        cursor.skip(1)?;
+        system_info.major_version = cursor.read_u32()?;
+        system_info.minor_version = cursor.read_u32()?;
+        system_info.build_number = cursor.read_u32()?;
+
+        self.system_info = Some(system_info);
+
+        return Ok(());
+    }
+
+    fn parse_module_list(&mut self, cursor: &mut PEReader, bytes: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        let count = cursor.read_u32()? as u64;
+
+        for i in 0..count.min(MAX_ENTRIES) {
+            cursor.set_position(4 + i * MINIDUMP_MODULE_SIZE)?;
+
+            let mut module = MinidumpModule::default();
+
+            module.base_of_image = cursor.read_u64()?;
+            module.size_of_image = cursor.read_u32()?;
+            module.checksum = cursor.read_u32()?;
+            module.time_date_stamp = cursor.read_u32()?;
+
+            let name_rva = cursor.read_u32()?;
+
+            module.name = read_minidump_string(bytes, name_rva).unwrap_or_else(|| format!("<name at {:#x}>", name_rva));
+
+            self.modules.push(module);
+        }
+
+        return Ok(());
+    }
+
+    fn parse_thread_list(&mut self, cursor: &mut PEReader, bytes: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        let count = cursor.read_u32()? as u64;
+
+        let registers = self
+            .system_info
+            .as_ref()
+            .and_then(|info| ProcessorArchitecture::from(info.processor_architecture).context_registers());
+
+        for i in 0..count.min(MAX_ENTRIES) {
+            cursor.set_position(4 + i * MINIDUMP_THREAD_SIZE)?;
+
+            let mut thread = MinidumpThread::default();
+
+            thread.thread_id = cursor.read_u32()?;
+            thread.suspend_count = cursor.read_u32()?;
+            cursor.skip(4)?;
+            thread.priority = cursor.read_u32()?;
+            thread.teb = cursor.read_u64()?;
+            thread.stack_start = cursor.read_u64()?;
+            thread.stack_size = cursor.read_u32()?;
+            cursor.skip(4)?;
+            thread.context_size = cursor.read_u32()?;
+
+            let context_rva = cursor.read_u32()? as u64;
+
+            if let Some((ip_offset, sp_offset, size)) = registers {
+                let read_register = |offset: u64| -> Option<u64> {
+                    let start = usize::try_from(context_rva + offset).ok()?;
+                    let register = bytes.get(start..start + size).filter(|_| offset + size as u64 <= thread.context_size as u64)?;
+
+                    return Some(register.iter().rev().fold(0u64, |value, byte| (value << 8) | *byte as u64));
+                };
+
+                thread.instruction_pointer = read_register(ip_offset);
+                thread.stack_pointer = read_register(sp_offset);
+            }
+
+            self.threads.push(thread);
+        }
+
+        return Ok(());
+    }
+
+    fn parse_memory_list(&mut self, cursor: &mut PEReader) -> Result<(), Box<dyn std::error::Error>> {
+        let count = cursor.read_u32()? as u64;
+
+        for i in 0..count.min(MAX_ENTRIES) {
+            cursor.set_position(4 + i * MINIDUMP_MEMORY_DESCRIPTOR_SIZE)?;
+
+            let start = cursor.read_u64()?;
+            let size = cursor.read_u32()? as u64;
+            let rva = cursor.read_u32()? as u64;
+
+            self.memory_ranges.push(MinidumpMemoryRange { start, size, rva });
+        }
+
+        return Ok(());
+    }
+
+    /// Full memory dumps store the ranges back to back from a base offset
+    fn parse_memory64_list(&mut self, cursor: &mut PEReader) -> Result<(), Box<dyn std::error::Error>> {
+        let count = cursor.read_u64()?;
+        let mut rva = cursor.read_u64()?;
+
+        for _ in 0..count.min(MAX_ENTRIES) {
+            let start = cursor.read_u64()?;
+            let size = cursor.read_u64()?;
+
+            self.memory_ranges.push(MinidumpMemoryRange { start, size, rva });
+
+            rva = rva.saturating_add(size);
+        }
+
+        return Ok(());
+    }
+
+    /// Module whose file name or full path matches, without case
+    pub fn find_module(&self, name: &str) -> Option<&MinidumpModule> {
+        return self
+            .modules
+            .iter()
+            .find(|module| module.file_name().eq_ignore_ascii_case(name) || module.name.eq_ignore_ascii_case(name));
+    }
+
+    /// Number of bytes of the process memory from address the dump saved
+    pub fn saved_size(&self, address: u64, size: u64) -> u64 {
+        let end = address.saturating_add(size);
+
+        return self
+            .memory_ranges
+            .iter()
+            .map(|range| range.start.saturating_add(range.size).min(end).saturating_sub(range.start.max(address)))
+            .sum();
+    }
+
+    /// Memory of the process from address, zero-filled where the dump did not save it, and the number of bytes found
+    pub fn read_memory(&self, bytes: &[u8], address: u64, size: u64) -> (Vec<u8>, u64) {
+        let mut memory = vec![0u8; size as usize];
+        let mut found = 0;
+        let end = address.saturating_add(size);
+
+        for range in self.memory_ranges.iter() {
+            let start = range.start.max(address);
+            let stop = range.start.saturating_add(range.size).min(end);
+
+            if start >= stop {
+                continue;
+            }
+
+            let source = range.rva + (start - range.start);
+            let length = (stop - start).min((bytes.len() as u64).saturating_sub(source));
+
+            if length == 0 {
+                continue;
+            }
+
+            let destination = (start - address) as usize;
+
+            memory[destination..destination + length as usize].copy_from_slice(&bytes[source as usize..(source + length) as usize]);
+            found += length;
+        }
+
+        return (memory, found);
+    }
+
+    /// Image of the module as mapped in the process, to parse as a memory image
+    pub fn extract_module(&self, bytes: &[u8], module: &MinidumpModule) -> (Vec<u8>, u64) {
+        return self.read_memory(bytes, module.base_of_image, module.size_of_image as u64);
+    }
+
+    /// Writes the image of the module to the output directory and parses it back as a memory image
+    #[rustfmt::skip]
+    pub fn extract_module_to(&self, bytes: &[u8], name: &str, output_dir: &Path) -> Result<Dump, Box<dyn std::error::Error>> {
+        let module = self.find_module(name).ok_or_else(|| format!("No module {} in the minidump", name))?;
+        let (image, saved) = self.extract_module(bytes, module);
+
+        std::fs::create_dir_all(output_dir)?;
+
+        let path = output_dir.join(sanitize_file_name(module.file_name()));
+        std::fs::write(&path, &image)?;
+
+        let mut dump = Dump::new_from_string(format!("Extracted Module ({})", module.file_name()));
+
+        dump.push_field("Output", path.display().to_string(), None);
+        dump.push_field("BaseOfImage", format!("{:#x}", module.base_of_image), None);
+        dump.push_field("SizeOfImage", format!("{:#x}", module.size_of_image), None);
+        dump.push_field("Saved", format!("{:#x} bytes ({:.1}%)", saved, saved as f64 * 100.0 / (module.size_of_image.max(1) as f64)), None);
+
+        /* The pages the dump did not save are zero-filled, the headers alone are enough to parse */
+        match parse_exec_memory_image_from_bytes(&image) {
+            Ok(Exec::PE(pe)) => dump.push_child(Summary::from_pe(&pe, &image).dump()),
+            Ok(Exec::ELF(_)) => dump.push_field("Warning", String::from("The module is not a PE image"), None),
+            Err(e) => dump.push_field("Warning", format!("Cannot parse the module: {}", e), None),
+        }
+
+        return Ok(dump);
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Minidump");
+
+        dump.push_field("Version", format!("{:#x}", self.header.version & 0xffff), None);
+        dump.push_field("NumberOfStreams", format!("{}", self.header.number_of_streams), None);
+        dump.push_field("TimeDateStamp", format_timestamp(self.header.time_date_stamp), None);
+        dump.push_field("Flags", format!("{:#x}", self.header.flags), None);
+
+        for warning in self.warnings.iter() {
+            dump.push_field("Warning", warning.clone(), None);
+        }
+
+        if let Some(ref system_info) = self.system_info {
+            let mut system_info_dump = Dump::new("System Info");
+
+            let architecture: &'static str = ProcessorArchitecture::from(system_info.processor_architecture).into();
+
+            system_info_dump.push_field("ProcessorArchitecture", format!("{:#x} ({})", system_info.processor_architecture, architecture), None);
+            system_info_dump.push_field("NumberOfProcessors", format!("{}", system_info.number_of_processors), None);
+            system_info_dump.push_field("Version", format!("{}.{}.{}", system_info.major_version, system_info.minor_version, system_info.build_number), None);
+
+            dump.push_child(system_info_dump);
+        }
+
+        let mut streams_dump = Dump::new_from_string(format!("Streams ({})", self.streams.len()));
+
+        for stream in self.streams.iter() {
+            let stream_type: &'static str = MinidumpStreamType::from(stream.stream_type).into();
+
+            streams_dump.push_field("", format!("{:<22} {:#x} rva: {:#x} size: {:#x}", stream_type, stream.stream_type, stream.rva, stream.data_size), None);
+        }
+
+        dump.push_child(streams_dump);
+
+        let mut modules_dump = Dump::new_from_string(format!("Modules ({})", self.modules.len()));
+
+        for module in self.modules.iter() {
+            let saved = self.saved_size(module.base_of_image, module.size_of_image as u64);
+
+            modules_dump.push_field("", format!("{:#018x} size: {:#x} saved: {:#x} {}", module.base_of_image, module.size_of_image, saved, module.name), None);
+        }
+
+        dump.push_child(modules_dump);
+
+        let mut threads_dump = Dump::new_from_string(format!("Threads ({})", self.threads.len()));
+
+        for thread in self.threads.iter() {
+            let registers = match (thread.instruction_pointer, thread.stack_pointer) {
+                (Some(ip), Some(sp)) => format!(" ip: {:#x} sp: {:#x}", ip, sp),
+                _ => format!(" context: {:#x} bytes", thread.context_size),
+            };
+
+            threads_dump.push_field("", format!("{:<6} teb: {:#x} stack: {:#x} size: {:#x} suspended: {}{}", thread.thread_id, thread.teb, thread.stack_start, thread.stack_size, thread.suspend_count, registers), None);
+        }
+
+        dump.push_child(threads_dump);
+
+        let mut memory_dump = Dump::new_from_string(format!("Memory Ranges ({}, {:#x} bytes)", self.memory_ranges.len(), self.memory_ranges.iter().map(|range| range.size).sum::<u64>()));
+
+        for range in self.memory_ranges.iter() {
+            memory_dump.push_field("", format!("{:#018x} - {:#018x} size: {:#x} rva: {:#x}", range.start, range.start.saturating_add(range.size), range.size, range.rva), None);
+        }
+
+        dump.push_child(memory_dump);
+
+        return dump;
+    }
+}
+
+/// MINIDUMP_STRING: a byte length followed by the UTF-16 characters
+fn read_minidump_string(bytes: &[u8], rva: u32) -> Option<String> {
+    let mut cursor = PEReader::from_bytes(bytes);
+
+    cursor.set_position(rva as u64).ok()?;
+
+    let length = cursor.read_u32().ok()?;
+
+    return cursor.read_utf16(length as usize / 2).ok();
+}
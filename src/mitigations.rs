@@ -0,0 +1,185 @@
+use strum_macros::IntoStaticStr;
+
+use crate::authenticode::pe_signature;
+use crate::dump::Dump;
+use crate::format::format_flags;
+use crate::pe::{
+    CharacteristicsFlag, DLLCharacteristicsFlags, DebugEntryData, ExDllCharacteristicsFlags, GuardFlags, MachineType, PE,
+};
+
+/*
+ * Security mitigations of a PE image (checksec), from the optional header, the load config and the debug directory
+ */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum MitigationStatus {
+    Pass,
+    Fail,
+    /// The mitigation does not exist for the architecture of the image
+    #[strum(serialize = "N/A")]
+    NotApplicable,
+}
+
+#[derive(Debug, Clone)]
+pub struct MitigationCheck {
+    pub name: &'static str,
+    pub status: MitigationStatus,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MitigationsReport {
+    pub dll_characteristics: u16,
+    /// From the IMAGE_DEBUG_TYPE_EX_DLLCHARACTERISTICS debug entry, the optional header has no room left for them
+    pub ex_dll_characteristics: Option<u32>,
+    pub checks: Vec<MitigationCheck>,
+}
+
+fn check(name: &'static str, passed: bool, detail: String) -> MitigationCheck {
+    let status = match passed {
+        true => MitigationStatus::Pass,
+        false => MitigationStatus::Fail,
+    };
+
+    return MitigationCheck { name, status, detail };
+}
+
+fn not_applicable(name: &'static str, detail: &str) -> MitigationCheck {
+    return MitigationCheck { name, status: MitigationStatus::NotApplicable, detail: String::from(detail) };
+}
+
+impl MitigationsReport {
+    pub fn from_pe(pe: &PE) -> MitigationsReport {
+        let mut report = MitigationsReport::default();
+
+        let dll_characteristics = pe.get_optional_header().get_dll_characteristics();
+        report.dll_characteristics = dll_characteristics;
+
+        let has_dll_characteristic = |flag: DLLCharacteristicsFlags| (dll_characteristics & flag as u16) != 0;
+
+        let relocs_stripped = (pe.get_nt_header().coff_header.characteristics & CharacteristicsFlag::RelocsStripped as u16) != 0;
+
+        /* The loader cannot move an image without relocations, whatever DYNAMIC_BASE says */
+        report.checks.push(match (has_dll_characteristic(DLLCharacteristicsFlags::DynamicBase), relocs_stripped) {
+            (true, false) => check("ASLR", true, String::from("DYNAMIC_BASE set")),
+            (true, true) => check("ASLR", false, String::from("DYNAMIC_BASE set but the relocations are stripped")),
+            (false, _) => check("ASLR", false, String::from("DYNAMIC_BASE not set")),
+        });
+
+        report.checks.push(match pe.is_32_bits() {
+            true => not_applicable("HighEntropyVA", "32-bit image"),
+            false => match has_dll_characteristic(DLLCharacteristicsFlags::HighEntropyVA) {
+                true => check("HighEntropyVA", true, String::from("HIGH_ENTROPY_VA set")),
+                false => check("HighEntropyVA", false, String::from("HIGH_ENTROPY_VA not set, ASLR uses 32-bit addresses")),
+            },
+        });
+
+        report.checks.push(match has_dll_characteristic(DLLCharacteristicsFlags::NXCompat) {
+            true => check("DEP", true, String::from("NX_COMPAT set")),
+            false => check("DEP", false, String::from("NX_COMPAT not set")),
+        });
+
+        let load_config = pe.load_config.as_ref();
+        let guard_flags = load_config.map(|config| config.guard_flags).unwrap_or(0);
+
+        /* The flag only requests CFG, the checks come with the instrumentation recorded in the load config */
+        report.checks.push(match (has_dll_characteristic(DLLCharacteristicsFlags::GuardCf), (guard_flags & GuardFlags::CfInstrumented as u32) != 0) {
+            (true, true) => check("CFG", true, String::from("GUARD_CF set and CF_INSTRUMENTED")),
+            (true, false) => check("CFG", false, String::from("GUARD_CF set but the code is not instrumented")),
+            (false, _) => check("CFG", false, String::from("GUARD_CF not set")),
+        });
+
+        /* 64-bit exception handling is table based, SafeSEH only concerns 32-bit images */
+        report.checks.push(match (pe.is_32_bits(), has_dll_characteristic(DLLCharacteristicsFlags::NoSeh), load_config) {
+            (false, _, _) => not_applicable("SafeSEH", "64-bit image, table based exception handling"),
+            (true, true, _) => check("SafeSEH", true, String::from("NO_SEH set, no exception handler can be called")),
+            (true, false, Some(config)) if config.se_handler_table != 0 => check("SafeSEH", true, format!("{} registered handlers", config.se_handler_count)),
+            (true, false, _) => check("SafeSEH", false, String::from("no SEHandlerTable in the load config")),
+        });
+
+        let gs_objects = pe.debug_entries.iter().find_map(|entry| match entry.data {
+            Some(DebugEntryData::VcFeature(ref feature)) => Some(feature.gs),
+            _ => None,
+        });
+
+        report.checks.push(match (load_config.map(|config| config.security_cookie).unwrap_or(0), gs_objects) {
+            (0, _) => check("/GS", false, String::from("no security cookie in the load config")),
+            (_, _) if (guard_flags & GuardFlags::SecurityCookieUnused as u32) != 0 => check("/GS", false, String::from("SECURITY_COOKIE_UNUSED set")),
+            (cookie, Some(gs)) => check("/GS", true, format!("security cookie at {:#x}, {} objects built with /GS", cookie, gs)),
+            (cookie, None) => check("/GS", true, format!("security cookie at {:#x}", cookie)),
+        });
+
+        report.ex_dll_characteristics = pe.debug_entries.iter().find_map(|entry| match entry.data {
+            Some(DebugEntryData::ExDllCharacteristics(characteristics)) => Some(characteristics),
+            _ => None,
+        });
+
+        let machine = MachineType::from(pe.get_nt_header().coff_header.machine);
+
+        /* Shadow stacks are an x86 feature, the policy bits only refine CET_COMPAT */
+        report.checks.push(match report.ex_dll_characteristics {
+            _ if machine != MachineType::I386 && machine != MachineType::AMD64 => not_applicable("CETCompat", "CET shadow stacks only exist on x86"),
+            Some(characteristics) if (characteristics & ExDllCharacteristicsFlags::CetCompat as u32) != 0 => {
+                let mut detail = String::from("CET_COMPAT set");
+
+                for (flag, policy) in [
+                    (ExDllCharacteristicsFlags::CetCompatStrictMode, "strict mode"),
+                    (ExDllCharacteristicsFlags::CetSetContextIpValidationRelaxedMode, "relaxed SetThreadContext IP validation"),
+                    (ExDllCharacteristicsFlags::CetDynamicApisAllowInProc, "dynamic APIs allowed in process only"),
+                ] {
+                    if (characteristics & flag as u32) != 0 {
+                        detail.push_str(&format!(", {}", policy));
+                    }
+                }
+
+                check("CETCompat", true, detail)
+            }
+            Some(_) => check("CETCompat", false, String::from("CET_COMPAT not set")),
+            None => check("CETCompat", false, String::from("no extended DLL characteristics in the debug directory")),
+        });
+
+        /* The signature is parsed, not verified against the image digest */
+        report.checks.push(match (&pe.certificate_table, pe_signature(pe)) {
+            (_, Ok(signature)) => match signature.signer.and_then(|i| signature.certificates.get(i)) {
+                Some(certificate) => check("Authenticode", true, format!("signed by {}", certificate.common_name)),
+                None => check("Authenticode", true, String::from("signed, signer certificate not embedded")),
+            },
+            (Some(_), Err(e)) => check("Authenticode", false, format!("invalid signature: {}", e)),
+            (None, Err(_)) => check("Authenticode", false, String::from("not signed")),
+        });
+
+        return report;
+    }
+
+    pub fn count(&self, status: MitigationStatus) -> usize {
+        return self.checks.iter().filter(|c| c.status == status).count();
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Mitigations");
+
+        dump.push_field("DllCharacteristics", format_flags::<DLLCharacteristicsFlags>(self.dll_characteristics as u64), None);
+
+        match self.ex_dll_characteristics {
+            Some(characteristics) => dump.push_field("ExDllCharacteristics", format_flags::<ExDllCharacteristicsFlags>(characteristics as u64), None),
+            None => dump.push_field("ExDllCharacteristics", String::from("not present"), None),
+        }
+
+        dump.push_field("Passed", format!("{}", self.count(MitigationStatus::Pass)), None);
+        dump.push_field("Failed", format!("{}", self.count(MitigationStatus::Fail)), None);
+
+        let mut checks_dump = Dump::new_from_string(format!("Checks ({})", self.checks.len()));
+
+        for check in self.checks.iter() {
+            let status: &'static str = check.status.into();
+
+            checks_dump.push_field("", format!("{:<14} {:<5} {}", check.name, status, check.detail), None);
+        }
+
+        dump.push_child(checks_dump);
+
+        return dump;
+    }
+}
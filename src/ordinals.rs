@@ -0,0 +1,164 @@
+/*
+ * Names of the functions imported by ordinal from the system DLLs whose ordinals never changed across Windows versions
+ * Winsock 1.1 ordinals are shared by ws2_32 and wsock32, the others come from the export tables of the DLLs
+ */
+
+const WINSOCK_ORDINALS: &[(u16, &str)] = &[
+    (1, "accept"),
+    (2, "bind"),
+    (3, "closesocket"),
+    (4, "connect"),
+    (5, "getpeername"),
+    (6, "getsockname"),
+    (7, "getsockopt"),
+    (8, "htonl"),
+    (9, "htons"),
+    (10, "ioctlsocket"),
+    (11, "inet_addr"),
+    (12, "inet_ntoa"),
+    (13, "listen"),
+    (14, "ntohl"),
+    (15, "ntohs"),
+    (16, "recv"),
+    (17, "recvfrom"),
+    (18, "select"),
+    (19, "send"),
+    (20, "sendto"),
+    (21, "setsockopt"),
+    (22, "shutdown"),
+    (23, "socket"),
+    (51, "gethostbyaddr"),
+    (52, "gethostbyname"),
+    (53, "getprotobyname"),
+    (54, "getprotobynumber"),
+    (55, "getservbyname"),
+    (56, "getservbyport"),
+    (57, "gethostname"),
+    (101, "WSAAsyncSelect"),
+    (102, "WSAAsyncGetHostByAddr"),
+    (103, "WSAAsyncGetHostByName"),
+    (104, "WSAAsyncGetProtoByNumber"),
+    (105, "WSAAsyncGetProtoByName"),
+    (106, "WSAAsyncGetServByPort"),
+    (107, "WSAAsyncGetServByName"),
+    (108, "WSACancelAsyncRequest"),
+    (109, "WSASetBlockingHook"),
+    (110, "WSAUnhookBlockingHook"),
+    (111, "WSAGetLastError"),
+    (112, "WSASetLastError"),
+    (113, "WSACancelBlockingCall"),
+    (114, "WSAIsBlocking"),
+    (115, "WSAStartup"),
+    (116, "WSACleanup"),
+    (151, "__WSAFDIsSet"),
+    (500, "WEP"),
+];
+
+const OLEAUT32_ORDINALS: &[(u16, &str)] = &[
+    (2, "SysAllocString"),
+    (3, "SysReAllocString"),
+    (4, "SysAllocStringLen"),
+    (5, "SysReAllocStringLen"),
+    (6, "SysFreeString"),
+    (7, "SysStringLen"),
+    (8, "VariantInit"),
+    (9, "VariantClear"),
+    (10, "VariantCopy"),
+    (11, "VariantCopyInd"),
+    (12, "VariantChangeType"),
+    (13, "VariantTimeToDosDateTime"),
+    (14, "DosDateTimeToVariantTime"),
+    (15, "SafeArrayCreate"),
+    (16, "SafeArrayDestroy"),
+    (17, "SafeArrayGetDim"),
+    (18, "SafeArrayGetElemsize"),
+    (19, "SafeArrayGetUBound"),
+    (20, "SafeArrayGetLBound"),
+    (21, "SafeArrayLock"),
+    (22, "SafeArrayUnlock"),
+    (23, "SafeArrayAccessData"),
+    (24, "SafeArrayUnaccessData"),
+    (25, "SafeArrayGetElement"),
+    (26, "SafeArrayPutElement"),
+    (27, "SafeArrayCopy"),
+    (28, "DispGetParam"),
+    (29, "DispGetIDsOfNames"),
+    (30, "DispInvoke"),
+    (31, "CreateDispTypeInfo"),
+    (32, "CreateStdDispatch"),
+    (33, "RegisterActiveObject"),
+    (34, "RevokeActiveObject"),
+    (35, "GetActiveObject"),
+    (36, "SafeArrayAllocDescriptor"),
+    (37, "SafeArrayAllocData"),
+    (38, "SafeArrayDestroyDescriptor"),
+    (39, "SafeArrayDestroyData"),
+    (40, "SafeArrayRedim"),
+    (41, "SafeArrayAllocDescriptorEx"),
+    (42, "SafeArrayCreateEx"),
+    (43, "SafeArrayCreateVectorEx"),
+    (44, "SafeArraySetRecordInfo"),
+    (45, "SafeArrayGetRecordInfo"),
+    (147, "VariantChangeTypeEx"),
+    (148, "SafeArrayPtrOfIndex"),
+    (149, "SysStringByteLen"),
+    (150, "SysAllocStringByteLen"),
+    (161, "LoadTypeLib"),
+    (162, "LoadRegTypeLib"),
+    (163, "RegisterTypeLib"),
+    (164, "QueryPathOfRegTypeLib"),
+    (183, "LoadTypeLibEx"),
+    (184, "SystemTimeToVariantTime"),
+    (185, "VariantTimeToSystemTime"),
+    (186, "UnRegisterTypeLib"),
+    (200, "GetErrorInfo"),
+    (201, "SetErrorInfo"),
+    (202, "CreateErrorInfo"),
+    (411, "SafeArrayCreateVector"),
+];
+
+const COMCTL32_ORDINALS: &[(u16, &str)] = &[
+    (2, "MenuHelp"),
+    (3, "ShowHideMenuCtl"),
+    (4, "GetEffectiveClientRect"),
+    (5, "DrawStatusTextA"),
+    (6, "CreateStatusWindowA"),
+    (7, "CreateToolbar"),
+    (8, "CreateMappedBitmap"),
+    (13, "MakeDragList"),
+    (14, "LBItemFromPt"),
+    (15, "DrawInsert"),
+    (16, "CreateUpDownControl"),
+    (17, "InitCommonControls"),
+    (410, "SetWindowSubclass"),
+    (411, "GetWindowSubclass"),
+    (412, "RemoveWindowSubclass"),
+    (413, "DefSubclassProc"),
+];
+
+/* DLL name without its extension, lowercase */
+const ORDINAL_DATABASE: &[(&str, &[(u16, &str)])] = &[
+    ("ws2_32", WINSOCK_ORDINALS),
+    ("wsock32", WINSOCK_ORDINALS),
+    ("oleaut32", OLEAUT32_ORDINALS),
+    ("comctl32", COMCTL32_ORDINALS),
+];
+
+/// "WS2_32.dll" becomes "ws2_32"
+pub fn dll_stem(dll_name: &str) -> String {
+    let lower = dll_name.to_ascii_lowercase();
+
+    match lower.strip_suffix(".dll") {
+        Some(stem) => return String::from(stem),
+        None => return lower,
+    }
+}
+
+/// Name of the function a system DLL exports under the ordinal, None for the DLLs and ordinals unknown to the bundled database
+pub fn ordinal_name(dll_name: &str, ordinal: u16) -> Option<&'static str> {
+    let stem = dll_stem(dll_name);
+
+    let (_, ordinals) = ORDINAL_DATABASE.iter().find(|(dll, _)| *dll == stem)?;
+
+    return ordinals.iter().find(|(o, _)| *o == ordinal).map(|(_, name)| *name);
+}
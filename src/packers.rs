@@ -0,0 +1,152 @@
+use strum_macros::IntoStaticStr;
+
+use crate::analysis::{shannon_entropy, HIGH_ENTROPY_THRESHOLD, MIN_ENTROPY_SAMPLE_SIZE};
+use crate::pe::PE;
+
+/*
+ * Identification of the common packers and protectors of PE images
+ * Entry point signatures are hex bytes, "??" matches any byte
+ */
+
+struct PackerSignature {
+    name: &'static str,
+    /// Names of the sections the packer adds, compared without case
+    section_names: &'static [&'static str],
+    entry_point_patterns: &'static [&'static str],
+}
+
+const PACKER_SIGNATURES: &[PackerSignature] = &[
+    PackerSignature {
+        name: "UPX",
+        section_names: &["UPX0", "UPX1", "UPX2", "UPX3"],
+        entry_point_patterns: &[
+            /* pushad; mov esi, packed; lea edi, [esi - offset] */
+            "60 BE ?? ?? ?? ?? 8D BE ?? ?? ?? ??",
+            /* push rbx; push rsi; push rdi; push rbp; lea rsi, [rip + packed]; lea rdi, [rsi - offset] */
+            "53 56 57 55 48 8D 35 ?? ?? ?? ?? 48 8D BE",
+        ],
+    },
+    PackerSignature {
+        name: "ASPack",
+        section_names: &[".aspack", ".adata"],
+        entry_point_patterns: &[
+            "60 E8 03 00 00 00 E9 EB 04 5D 45 55 C3 E8 01 00 00 00",
+        ],
+    },
+    PackerSignature {
+        name: "Themida",
+        section_names: &[".themida", ".winlice"],
+        entry_point_patterns: &[
+            /* mov eax, 0; pushad; or eax, eax; jz; call $+5; pop eax; add eax */
+            "B8 ?? ?? ?? ?? 60 0B C0 74 ?? E8 00 00 00 00 58 05",
+        ],
+    },
+    PackerSignature {
+        name: "MPRESS",
+        section_names: &[".MPRESS1", ".MPRESS2"],
+        entry_point_patterns: &[
+            "60 E8 00 00 00 00 58 05 ?? ?? ?? ?? 8B 30 03 F0 2B C0 8B FE 66 AD C1 E0 0C",
+        ],
+    },
+    PackerSignature {
+        name: "PECompact",
+        section_names: &["PEC2", "PEC2TO", "PEC2MO", "PECompact2", "pec1"],
+        entry_point_patterns: &[
+            /* mov eax, handler; push eax; push fs:[0]; mov fs:[0], esp; xor eax, eax; mov [eax], ecx; "PECompact2" */
+            "B8 ?? ?? ?? ?? 50 64 FF 35 00 00 00 00 64 89 25 00 00 00 00 33 C0 89 08 50 45 43 6F 6D 70 61 63 74 32",
+        ],
+    },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum PackerEvidence {
+    SectionName,
+    EntryPointSignature,
+    /// Only supports the other evidences, compressed data alone does not tell the packer
+    EntropyProfile,
+}
+
+#[derive(Debug, Clone)]
+pub struct PackerMatch {
+    pub name: &'static str,
+    pub evidences: Vec<PackerEvidence>,
+}
+
+impl PackerMatch {
+    /// "UPX (section name, entry point signature)"
+    pub fn describe(&self) -> String {
+        let evidences: Vec<String> = self
+            .evidences
+            .iter()
+            .map(|evidence| {
+                let evidence: &'static str = evidence.into();
+                evidence.to_ascii_lowercase().replace('_', " ")
+            })
+            .collect();
+
+        return format!("{} ({})", self.name, evidences.join(", "));
+    }
+}
+
+fn matches_pattern(data: &[u8], pattern: &str) -> bool {
+    let mut length = 0;
+
+    for (i, token) in pattern.split_whitespace().enumerate() {
+        let Some(&byte) = data.get(i) else {
+            return false;
+        };
+
+        if token != "??" && u8::from_str_radix(token, 16).ok() != Some(byte) {
+            return false;
+        }
+
+        length += 1;
+    }
+
+    return length > 0;
+}
+
+/// Packers and protectors whose section names or entry point code are found in the image, in the order of the signatures
+pub fn detect_pe_packers(pe: &PE) -> Vec<PackerMatch> {
+    let entry_point = pe.get_optional_header().get_address_of_entry_point();
+
+    let entry_point_bytes = pe
+        .get_section_for_rva(entry_point)
+        .filter(|_| entry_point != 0)
+        .and_then(|section| section.raw_data().get((entry_point - section.header.virtual_address) as usize..))
+        .unwrap_or(&[]);
+
+    /* The resources stay uncompressed for the shell to read the icons and version information */
+    let high_entropy = pe
+        .sections
+        .iter()
+        .filter(|section| section.header.name != ".rsrc")
+        .any(|section| section.raw_data().len() >= MIN_ENTROPY_SAMPLE_SIZE && shannon_entropy(section.raw_data()) > HIGH_ENTROPY_THRESHOLD);
+
+    let mut matches = Vec::new();
+
+    for signature in PACKER_SIGNATURES.iter() {
+        let mut evidences = Vec::new();
+
+        if pe.sections.iter().any(|section| signature.section_names.iter().any(|name| name.eq_ignore_ascii_case(&section.header.name))) {
+            evidences.push(PackerEvidence::SectionName);
+        }
+
+        if signature.entry_point_patterns.iter().any(|pattern| matches_pattern(entry_point_bytes, pattern)) {
+            evidences.push(PackerEvidence::EntryPointSignature);
+        }
+
+        if evidences.is_empty() {
+            continue;
+        }
+
+        if high_entropy {
+            evidences.push(PackerEvidence::EntropyProfile);
+        }
+
+        matches.push(PackerMatch { name: signature.name, evidences });
+    }
+
+    return matches;
+}
@@ -0,0 +1,1549 @@
+use std::ops::Range;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use strum::IntoEnumIterator;
+
+use crate::args::{AddImportArgs, AddSectionArgs, NormalizeArgs, PatchArgs, RebuildIatArgs, RenameSectionArgs, UnmapArgs};
+use crate::deps::ImportRef;
+use crate::dump::Dump;
+use crate::elf::SectionType;
+use crate::exec::{parse_exec, Exec};
+use crate::pe::{
+    compute_checksum, parse_pe, BaseRelocationType, CharacteristicsFlag, DebugDirectory, CODEVIEW_NB10_PATH_OFFSET, CODEVIEW_NB10_SIGNATURE, CODEVIEW_RSDS_PATH_OFFSET, CODEVIEW_RSDS_SIGNATURE, DebugType, PEArchitecture, SectionFlags,
+    DATA_DIRECTORY_BOUND_IMPORT, DATA_DIRECTORY_CERTIFICATE, DATA_DIRECTORY_DEBUG, DATA_DIRECTORY_IMPORT,
+    DATA_DIRECTORY_IMPORT_ADDRESS_TABLE, DEBUG_DIRECTORY_ENTRY_SIZE, OPTIONAL_HEADER_SIZE_OF_CODE_OFFSET,
+    OPTIONAL_HEADER_SIZE_OF_HEADERS_OFFSET, OPTIONAL_HEADER_SIZE_OF_IMAGE_OFFSET,
+    OPTIONAL_HEADER_SIZE_OF_INITIALIZED_DATA_OFFSET, OPTIONAL_HEADER_SIZE_OF_UNINITIALIZED_DATA_OFFSET, PEReader, PE,
+    SECTION_HEADER_SIZE,
+};
+
+/*
+ * Structure-aware modifications of executables
+ */
+
+/// Accepts "0x1000", "1000h" and decimal values
+pub fn parse_address(value: &str) -> Result<u64, Box<dyn std::error::Error>> {
+    let value = value.trim();
+
+    if let Some(hex) = value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        return Ok(u64::from_str_radix(hex, 16)?);
+    }
+
+    if let Some(hex) = value.strip_suffix('h').or_else(|| value.strip_suffix('H')) {
+        return Ok(u64::from_str_radix(hex, 16)?);
+    }
+
+    return Ok(value.parse::<u64>()?);
+}
+
+/// "9090EB05", "90 90 eb 05" or "90:90:EB:05"
+pub fn parse_hex_bytes(value: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let digits: Vec<char> = value.chars().filter(|c| !c.is_whitespace() && *c != ':').collect();
+
+    if digits.is_empty() || !digits.len().is_multiple_of(2) {
+        return Err("Expected an even number of hex digits".into());
+    }
+
+    let mut bytes = Vec::with_capacity(digits.len() / 2);
+
+    for pair in digits.chunks(2) {
+        let byte: String = pair.iter().collect();
+        bytes.push(u8::from_str_radix(&byte, 16).map_err(|_| format!("Invalid hex byte \"{}\"", byte))?);
+    }
+
+    return Ok(bytes);
+}
+
+/// Where a patch lands in the file
+#[derive(Debug, Clone, Default)]
+pub struct PatchTarget {
+    pub offset: u64,
+    pub virtual_address: Option<u64>,
+    /// Section name, "(headers)" or "(overlay)"
+    pub location: String,
+}
+
+struct FileSection {
+    name: String,
+    offset: u64,
+    /// Bytes in the file
+    size: u64,
+    virtual_address: u64,
+    /// Bytes of the file mapped in memory, the alignment padding past the virtual size is not
+    mapped_size: u64,
+}
+
+fn file_sections(exec: &Exec) -> Vec<FileSection> {
+    match exec {
+        Exec::PE(pe) => {
+            return pe
+                .sections
+                .iter()
+                .map(|s| FileSection {
+                    name: s.header.name.clone(),
+                    offset: s.header.ptr_to_raw_data as u64,
+                    size: s.header.size_of_raw_data as u64,
+                    virtual_address: s.header.virtual_address as u64,
+                    mapped_size: match s.header.virtual_size {
+                        0 => s.header.size_of_raw_data as u64,
+                        virtual_size => s.header.size_of_raw_data.min(virtual_size) as u64,
+                    },
+                })
+                .collect();
+        }
+        Exec::ELF(elf) => {
+            return elf
+                .sections
+                .values()
+                .filter(|s| s.header.section_type() != SectionType::Nobits)
+                .map(|s| FileSection {
+                    name: s.name.clone(),
+                    offset: s.offset(),
+                    size: s.size(),
+                    virtual_address: s.header.virtual_address(),
+                    /* Non-allocated ELF sections are not mapped */
+                    mapped_size: if s.header.virtual_address() != 0 { s.size() } else { 0 },
+                })
+                .collect();
+        }
+    }
+}
+
+/// Checks the patched range lies in the file, within a single section when it touches one
+pub fn resolve_target(exec: &Exec, address: u64, is_virtual: bool, length: usize, file_size: u64) -> Result<PatchTarget, Box<dyn std::error::Error>> {
+    let sections = file_sections(exec);
+    let length = length as u64;
+
+    if is_virtual {
+        /* A RVA for PE, a virtual address for ELF */
+        let section = sections
+            .iter()
+            .find(|s| address >= s.virtual_address && address < s.virtual_address + s.mapped_size)
+            .ok_or_else(|| format!("Address {:#x} is not backed by section data in the file", address))?;
+
+        let end = section.virtual_address + section.mapped_size;
+
+        if address + length > end {
+            return Err(format!("Patch at {:#x} overflows section {} by {} bytes", address, section.name, address + length - end).into());
+        }
+
+        return Ok(PatchTarget {
+            offset: section.offset + (address - section.virtual_address),
+            virtual_address: Some(address),
+            location: section.name.clone(),
+        });
+    }
+
+    if address + length > file_size {
+        return Err(format!("Patch at offset {:#x} goes past the end of the file ({:#x} bytes)", address, file_size).into());
+    }
+
+    match sections.iter().find(|s| address >= s.offset && address < s.offset + s.size) {
+        Some(section) => {
+            let end = section.offset + section.size;
+
+            if address + length > end {
+                return Err(format!("Patch at offset {:#x} overflows section {} by {} bytes", address, section.name, address + length - end).into());
+            }
+
+            let in_memory = address < section.offset + section.mapped_size;
+
+            return Ok(PatchTarget {
+                offset: address,
+                virtual_address: in_memory.then(|| section.virtual_address + (address - section.offset)),
+                location: section.name.clone(),
+            });
+        }
+        None => {
+            let first_section = sections.iter().filter(|s| s.size > 0).map(|s| s.offset).min().unwrap_or(file_size);
+
+            if address < first_section && address + length > first_section {
+                return Err(format!("Patch at offset {:#x} overflows the headers into the first section", address).into());
+            }
+
+            let location = if address < first_section { "(headers)" } else { "(overlay)" };
+
+            return Ok(PatchTarget {
+                offset: address,
+                virtual_address: None,
+                location: String::from(location),
+            });
+        }
+    }
+}
+
+/// Recomputes the CheckSum field of a PE in place, returns the old and new values
+pub fn fix_checksum(pe: &PE, file_bytes: &mut [u8]) -> Result<(u32, u32), Box<dyn std::error::Error>> {
+    let offset = pe.get_checksum_offset() as usize;
+
+    let field = file_bytes.get_mut(offset..offset + 4).ok_or("CheckSum field lies outside of the file")?;
+    let old = u32::from_le_bytes([field[0], field[1], field[2], field[3]]);
+
+    let new = compute_checksum(file_bytes, offset as u64);
+    file_bytes[offset..offset + 4].copy_from_slice(&new.to_le_bytes());
+
+    return Ok((old, new));
+}
+
+/// Writes the recomputed CheckSum, nothing is written when it is already valid
+pub fn fix_file_checksum(pe: &PE, file_path: &Path, output_path: Option<&Path>) -> Result<Dump, Box<dyn std::error::Error>> {
+    let mut file_bytes = std::fs::read(file_path)?;
+
+    let (old_checksum, new_checksum) = fix_checksum(pe, &mut file_bytes)?;
+
+    let mut dump = Dump::new("Fix Checksum");
+
+    dump.push_field("CheckSum", format!("{:#x} -> {:#x}", old_checksum, new_checksum), None);
+
+    if old_checksum == new_checksum && output_path.is_none() {
+        dump.push_field("Output", String::from("unchanged, the checksum is already valid"), None);
+        return Ok(dump);
+    }
+
+    let (output, backup) = write_output(file_path, output_path, &file_bytes)?;
+
+    dump.push_field("Output", output.display().to_string(), None);
+
+    if let Some(backup) = backup {
+        dump.push_field("Backup", backup.display().to_string(), None);
+    }
+
+    return Ok(dump);
+}
+
+/// Copies the file next to itself with a ".bak" extension appended, never overwriting a previous backup
+pub fn backup_file(file_path: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let mut backup = PathBuf::from(format!("{}.bak", file_path.display()));
+    let mut counter = 1;
+
+    while backup.exists() {
+        backup = PathBuf::from(format!("{}.bak{}", file_path.display(), counter));
+        counter += 1;
+    }
+
+    std::fs::copy(file_path, &backup)?;
+
+    return Ok(backup);
+}
+
+/// Writes the modified bytes to the output path if given, else in place after backing up the original file
+pub fn write_output(file_path: &Path, output_path: Option<&Path>, file_bytes: &[u8]) -> Result<(PathBuf, Option<PathBuf>), Box<dyn std::error::Error>> {
+    match output_path {
+        Some(output_path) => {
+            std::fs::write(output_path, file_bytes)?;
+            return Ok((output_path.to_path_buf(), None));
+        }
+        None => {
+            let backup = backup_file(file_path)?;
+            std::fs::write(file_path, file_bytes)?;
+            return Ok((file_path.to_path_buf(), Some(backup)));
+        }
+    }
+}
+
+fn format_bytes(bytes: &[u8]) -> String {
+    return bytes.iter().map(|b| format!("{:02X}", b)).collect::<Vec<String>>().join(" ");
+}
+
+pub fn patch(args: &PatchArgs) -> Result<Dump, Box<dyn std::error::Error>> {
+    let exec = parse_exec(&args.file_path)?;
+
+    let address = parse_address(&args.at)?;
+    let bytes = parse_hex_bytes(&args.bytes)?;
+
+    let mut file_bytes = std::fs::read(&args.file_path)?;
+
+    let target = resolve_target(&exec, address, args.rva, bytes.len(), file_bytes.len() as u64)?;
+
+    let start = target.offset as usize;
+    let old_bytes = file_bytes[start..start + bytes.len()].to_vec();
+
+    let backup = match args.backup {
+        true => Some(backup_file(&args.file_path)?),
+        false => None,
+    };
+
+    file_bytes[start..start + bytes.len()].copy_from_slice(&bytes);
+
+    let checksum = match (&exec, args.fix_checksum) {
+        (Exec::PE(pe), true) => Some(fix_checksum(pe, &mut file_bytes)?),
+        _ => None,
+    };
+
+    std::fs::write(&args.file_path, &file_bytes)?;
+
+    let mut dump = Dump::new("Patch");
+
+    dump.push_field("FileOffset", format!("{:#x}", target.offset), None);
+
+    if let Some(virtual_address) = target.virtual_address {
+        dump.push_field("VirtualAddress", format!("{:#x}", virtual_address), Some("RVA for PE"));
+    }
+
+    dump.push_field("Location", target.location, None);
+    dump.push_field("Size", format!("{}", bytes.len()), None);
+    dump.push_field("OldBytes", format_bytes(&old_bytes), None);
+    dump.push_field("NewBytes", format_bytes(&bytes), None);
+
+    if let Some((old, new)) = checksum {
+        dump.push_field("CheckSum", format!("{:#x} -> {:#x}", old, new), None);
+    }
+
+    if let Some(backup) = backup {
+        dump.push_field("Backup", backup.display().to_string(), None);
+    }
+
+    return Ok(dump);
+}
+
+/*
+ * Debug information stripping
+ */
+
+/* "DanS" and "Rich" markers delimiting the Rich header, the first one is XOR-ed with the checksum key */
+const RICH_HEADER_START: u32 = 0x536e6144;
+const RICH_HEADER_END: &[u8] = b"Rich";
+
+/// File range of the Rich header, the undocumented linker footprint between the DOS stub and the NT header
+pub fn rich_header_range(file_bytes: &[u8], nt_header_offset: usize) -> Option<Range<usize>> {
+    let stub = file_bytes.get(..nt_header_offset.min(file_bytes.len()))?;
+
+    let end = stub.windows(4).rposition(|w| w == RICH_HEADER_END)?;
+    let key = u32::from_le_bytes(stub.get(end + 4..end + 8)?.try_into().ok()?);
+
+    let mut position = end;
+
+    while position >= 4 {
+        position -= 4;
+
+        let value = u32::from_le_bytes(stub[position..position + 4].try_into().ok()?);
+
+        if value ^ key == RICH_HEADER_START {
+            return Some(position..end + 8);
+        }
+    }
+
+    return None;
+}
+
+/// Every entry of the debug directory as (file offset, entry), only the first one is kept by the parser
+pub fn debug_entries(pe: &PE, file_bytes: &[u8]) -> Result<Vec<(u64, DebugDirectory)>, Box<dyn std::error::Error>> {
+    let debug_idd = pe.get_optional_header().get_debug_idd();
+
+    let mut entries = Vec::new();
+
+    if debug_idd.virtual_address == 0 || debug_idd.size == 0 {
+        return Ok(entries);
+    }
+
+    let Some(offset) = pe.convert_rva_to_file_offset(debug_idd.virtual_address) else {
+        return Ok(entries);
+    };
+
+    let mut cursor = PEReader::from_bytes(file_bytes);
+
+    for i in 0..(debug_idd.size as u64 / DEBUG_DIRECTORY_ENTRY_SIZE) {
+        let entry_offset = offset + i * DEBUG_DIRECTORY_ENTRY_SIZE;
+
+        cursor.set_position(entry_offset)?;
+        entries.push((entry_offset, DebugDirectory::from_parser(&mut cursor)?));
+    }
+
+    return Ok(entries);
+}
+
+fn zero_range(file_bytes: &mut [u8], start: u64, size: u64) -> usize {
+    let start = (start as usize).min(file_bytes.len());
+    let end = (start + size as usize).min(file_bytes.len());
+
+    file_bytes[start..end].fill(0);
+
+    return end - start;
+}
+
+/// Writes a copy of the PE without its debug directory, debug data (PDB path included) and Rich header
+pub fn strip_debug(pe: &PE, file_path: &Path, output_path: &Path) -> Result<Dump, Box<dyn std::error::Error>> {
+    let mut file_bytes = std::fs::read(file_path)?;
+
+    let entries = debug_entries(pe, &file_bytes)?;
+
+    let mut debug_data_size = 0;
+
+    for (entry_offset, entry) in entries.iter() {
+        if entry.pointer_to_raw_data != 0 {
+            debug_data_size += zero_range(&mut file_bytes, entry.pointer_to_raw_data as u64, entry.size_of_data as u64);
+        }
+
+        zero_range(&mut file_bytes, *entry_offset, DEBUG_DIRECTORY_ENTRY_SIZE);
+    }
+
+    zero_range(&mut file_bytes, pe.get_data_directory_offset(DATA_DIRECTORY_DEBUG), 8);
+
+    let rich_header = rich_header_range(&file_bytes, pe.get_dos_header().e_lfanew as usize);
+
+    if let Some(ref range) = rich_header {
+        file_bytes[range.clone()].fill(0);
+    }
+
+    /* Characteristics is the last field of the COFF header */
+    let characteristics_offset = pe.get_coff_header_offset() as usize + 18;
+    let characteristics = pe.get_nt_header().coff_header.characteristics | CharacteristicsFlag::DebugStripped as u16;
+    file_bytes[characteristics_offset..characteristics_offset + 2].copy_from_slice(&characteristics.to_le_bytes());
+
+    let (old_checksum, new_checksum) = fix_checksum(pe, &mut file_bytes)?;
+
+    std::fs::write(output_path, &file_bytes)?;
+
+    let mut dump = Dump::new("Strip Debug");
+
+    dump.push_field("Output", output_path.display().to_string(), None);
+    dump.push_field("DebugEntries", format!("{}", entries.len()), None);
+    dump.push_field("DebugDataSize", format!("{:#x} ({} bytes)", debug_data_size, debug_data_size), Some("includes the PDB path"));
+
+    match rich_header {
+        Some(range) => dump.push_field("RichHeader", format!("{:#x}..{:#x} ({} bytes)", range.start, range.end, range.len()), None),
+        None => dump.push_field("RichHeader", String::from("not found"), None),
+    }
+
+    dump.push_field("CheckSum", format!("{:#x} -> {:#x}", old_checksum, new_checksum), None);
+
+    return Ok(dump);
+}
+
+/*
+ * Timestamp and path normalization for reproducible builds
+ */
+
+/* Type, Name and Language levels */
+const RESOURCE_TREE_DEPTH: usize = 3;
+
+fn write_u32(file_bytes: &mut [u8], offset: u64, value: u32) -> bool {
+    let offset = offset as usize;
+
+    match file_bytes.get_mut(offset..offset + 4) {
+        Some(field) => {
+            field.copy_from_slice(&value.to_le_bytes());
+            return true;
+        }
+        None => return false,
+    }
+}
+
+/// Sets the TimeDateStamp of every resource directory, returns the number of directories
+fn normalize_resource_directories(file_bytes: &mut [u8], base_offset: u64, directory_offset: u32, depth: usize, visited: &mut HashSet<u32>, timestamp: u32) -> usize {
+    /* Malformed (or malicious) trees can loop back on themselves */
+    if depth >= RESOURCE_TREE_DEPTH || !visited.insert(directory_offset) {
+        return 0;
+    }
+
+    let directory = base_offset as usize + directory_offset as usize;
+
+    if !write_u32(file_bytes, directory as u64 + 4, timestamp) {
+        return 0;
+    }
+
+    let read_u16 = |offset: usize| file_bytes.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as usize).unwrap_or(0);
+    let entries = read_u16(directory + 12) + read_u16(directory + 14);
+
+    let mut count = 1;
+
+    for i in 0..entries {
+        let entry = directory + 16 + i * 8 + 4;
+
+        let Some(offset) = file_bytes.get(entry..entry + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])) else {
+            break;
+        };
+
+        if offset & 0x80000000 != 0 {
+            count += normalize_resource_directories(file_bytes, base_offset, offset & 0x7fffffff, depth + 1, visited, timestamp);
+        }
+    }
+
+    return count;
+}
+
+/// Timestamp given on the command line, else SOURCE_DATE_EPOCH, else zero
+fn normalization_timestamp(args: &NormalizeArgs) -> Result<u32, Box<dyn std::error::Error>> {
+    let epoch = match args.timestamp {
+        Some(timestamp) => timestamp,
+        None => match std::env::var("SOURCE_DATE_EPOCH") {
+            Ok(value) => value.trim().parse::<u64>().map_err(|_| format!("Invalid SOURCE_DATE_EPOCH \"{}\"", value))?,
+            Err(_) => 0,
+        },
+    };
+
+    return Ok(u32::try_from(epoch).map_err(|_| format!("Timestamp {} does not fit the 32-bit PE timestamps", epoch))?);
+}
+
+/// Sets the COFF, export, debug and resource timestamps, blanks the PDB path and recomputes the checksum
+pub fn normalize(args: &NormalizeArgs) -> Result<Dump, Box<dyn std::error::Error>> {
+    let Exec::PE(pe) = parse_exec(&args.file_path)? else {
+        return Err("Only PE files can be normalized".into());
+    };
+
+    let timestamp = normalization_timestamp(args)?;
+
+    let mut file_bytes = std::fs::read(&args.file_path)?;
+
+    write_u32(&mut file_bytes, pe.get_coff_header_offset() + 4, timestamp);
+
+    let mut export_timestamp = false;
+
+    if pe.export_data.is_some() && let Some(offset) = pe.convert_rva_to_file_offset(pe.get_optional_header().get_export_table_idd().virtual_address) {
+        export_timestamp = write_u32(&mut file_bytes, offset + 4, timestamp);
+    }
+
+    let entries = debug_entries(&pe, &file_bytes)?;
+    let mut pdb_paths = 0;
+
+    for (entry_offset, entry) in entries.iter() {
+        write_u32(&mut file_bytes, entry_offset + 4, timestamp);
+
+        if DebugType::from(entry.debug_type) != DebugType::CodeView {
+            continue;
+        }
+
+        let start = entry.pointer_to_raw_data as usize;
+        let end = (start + entry.size_of_data as usize).min(file_bytes.len());
+
+        if let Some(data) = file_bytes.get_mut(start..end) {
+            let path_offset = match data.get(..4) {
+                Some(signature) if signature == CODEVIEW_RSDS_SIGNATURE => CODEVIEW_RSDS_PATH_OFFSET,
+                Some(signature) if signature == CODEVIEW_NB10_SIGNATURE => CODEVIEW_NB10_PATH_OFFSET,
+                _ => continue,
+            };
+
+            if data.len() > path_offset {
+                data[path_offset..].fill(0);
+                pdb_paths += 1;
+            }
+        }
+    }
+
+    let mut resource_directories = 0;
+
+    if pe.resource_table.is_some() && let Some(offset) = pe.convert_rva_to_file_offset(pe.get_optional_header().get_resource_table_idd().virtual_address) {
+        resource_directories = normalize_resource_directories(&mut file_bytes, offset, 0, 0, &mut HashSet::new(), timestamp);
+    }
+
+    let (old_checksum, new_checksum) = fix_checksum(&pe, &mut file_bytes)?;
+
+    let output_path = args.output.clone().unwrap_or(args.file_path.clone());
+    std::fs::write(&output_path, &file_bytes)?;
+
+    let mut dump = Dump::new("Normalize");
+
+    dump.push_field("Output", output_path.display().to_string(), None);
+    dump.push_field("Timestamp", crate::format::format_timestamp(timestamp), None);
+    dump.push_field("ExportDirectory", format!("{}", export_timestamp), None);
+    dump.push_field("DebugEntries", format!("{}", entries.len()), None);
+    dump.push_field("PdbPaths", format!("{}", pdb_paths), Some("blanked"));
+    dump.push_field("ResourceDirectories", format!("{}", resource_directories), None);
+    dump.push_field("CheckSum", format!("{:#x} -> {:#x}", old_checksum, new_checksum), None);
+
+    return Ok(dump);
+}
+
+/*
+ * Rebasing
+ */
+
+/* The loader maps images on 64K boundaries */
+const IMAGE_BASE_ALIGNMENT: u64 = 0x10000;
+
+/// Moves the preferred ImageBase and applies the base relocations to the fields depending on it
+pub fn rebase(pe: &PE, file_path: &Path, new_image_base: u64, output_path: Option<&Path>) -> Result<Dump, Box<dyn std::error::Error>> {
+    let old_image_base = pe.get_optional_header().get_image_base();
+
+    if new_image_base == 0 || !new_image_base.is_multiple_of(IMAGE_BASE_ALIGNMENT) {
+        return Err(format!("Image base {:#x} must be a non-zero multiple of {:#x}", new_image_base, IMAGE_BASE_ALIGNMENT).into());
+    }
+
+    if pe.is_32_bits() && new_image_base > u32::MAX as u64 {
+        return Err(format!("Image base {:#x} does not fit a PE32 image", new_image_base).into());
+    }
+
+    let relocations = pe.base_relocation_table.as_ref().ok_or("No base relocations found in PE, the image cannot be rebased")?;
+
+    let mut file_bytes = std::fs::read(file_path)?;
+
+    let delta = new_image_base.wrapping_sub(old_image_base);
+
+    let mut applied = 0;
+    let mut unsupported: Vec<String> = Vec::new();
+
+    for block in relocations.blocks.iter() {
+        for (i, entry) in block.entries.iter().enumerate() {
+            let relocation_type = entry.get_relocation_type();
+
+            if relocation_type == BaseRelocationType::Absolute {
+                continue;
+            }
+
+            let rva = block.page_rva + entry.offset as u32;
+
+            let width = match relocation_type {
+                BaseRelocationType::High | BaseRelocationType::Low | BaseRelocationType::HighAdj => 2,
+                BaseRelocationType::HighLow => 4,
+                BaseRelocationType::Dir64 => 8,
+                _ => {
+                    unsupported.push(format!("{:#x} ({})", rva, relocation_type.as_static_str()));
+                    continue;
+                }
+            };
+
+            let offset = pe
+                .convert_rva_to_file_offset(rva)
+                .map(|o| o as usize)
+                .filter(|o| o + width <= file_bytes.len())
+                .ok_or_else(|| format!("Relocation at RVA {:#x} is not backed by file data", rva))?;
+
+            let field = &mut file_bytes[offset..offset + width];
+
+            match relocation_type {
+                BaseRelocationType::High => {
+                    let value = u16::from_le_bytes([field[0], field[1]]).wrapping_add((delta >> 16) as u16);
+                    field.copy_from_slice(&value.to_le_bytes());
+                }
+                BaseRelocationType::Low => {
+                    let value = u16::from_le_bytes([field[0], field[1]]).wrapping_add(delta as u16);
+                    field.copy_from_slice(&value.to_le_bytes());
+                }
+                BaseRelocationType::HighAdj => {
+                    /* The low half of the 32-bit value is stored in the next slot, rounding applies to the high half */
+                    let low = block.entries.get(i + 1).map(|e| e.offset as i16 as i32).unwrap_or(0);
+                    let value = ((u16::from_le_bytes([field[0], field[1]]) as i32) << 16).wrapping_add(low).wrapping_add(delta as i32).wrapping_add(0x8000);
+                    field.copy_from_slice(&((value >> 16) as u16).to_le_bytes());
+                }
+                BaseRelocationType::HighLow => {
+                    let value = u32::from_le_bytes([field[0], field[1], field[2], field[3]]).wrapping_add(delta as u32);
+                    field.copy_from_slice(&value.to_le_bytes());
+                }
+                _ => {
+                    let value = u64::from_le_bytes(field[..8].try_into()?).wrapping_add(delta);
+                    field.copy_from_slice(&value.to_le_bytes());
+                }
+            }
+
+            applied += 1;
+        }
+    }
+
+    let image_base_offset = pe.get_image_base_offset() as usize;
+
+    match pe.get_architecture() {
+        PEArchitecture::PE32 => file_bytes[image_base_offset..image_base_offset + 4].copy_from_slice(&(new_image_base as u32).to_le_bytes()),
+        PEArchitecture::PE64 => file_bytes[image_base_offset..image_base_offset + 8].copy_from_slice(&new_image_base.to_le_bytes()),
+    }
+
+    let (old_checksum, new_checksum) = fix_checksum(pe, &mut file_bytes)?;
+
+    let (output, backup) = write_output(file_path, output_path, &file_bytes)?;
+
+    let mut dump = Dump::new("Rebase");
+
+    dump.push_field("Output", output.display().to_string(), None);
+    dump.push_field("ImageBase", format!("{:#x} -> {:#x}", old_image_base, new_image_base), None);
+    dump.push_field("Delta", format!("{:#x}", delta), None);
+    dump.push_field("Relocations", format!("{}", applied), Some("applied"));
+    dump.push_field("CheckSum", format!("{:#x} -> {:#x}", old_checksum, new_checksum), None);
+
+    if let Some(backup) = backup {
+        dump.push_field("Backup", backup.display().to_string(), None);
+    }
+
+    let mut unsupported_dump = Dump::new_from_string(format!("Unsupported Relocations ({})", unsupported.len()));
+
+    for relocation in unsupported.iter() {
+        unsupported_dump.push_field("", relocation.clone(), None);
+    }
+
+    if !unsupported.is_empty() {
+        dump.push_child(unsupported_dump);
+    }
+
+    return Ok(dump);
+}
+
+/*
+ * Sections editing
+ */
+
+/* Executable images have no string table for longer section names */
+const MAX_SECTION_NAME_LENGTH: usize = 8;
+
+fn align_up(value: u64, alignment: u64) -> u64 {
+    if alignment == 0 {
+        return value;
+    }
+
+    return value.div_ceil(alignment) * alignment;
+}
+
+fn read_u32_at(file_bytes: &[u8], offset: u64) -> u32 {
+    let offset = offset as usize;
+    return u32::from_le_bytes(file_bytes[offset..offset + 4].try_into().unwrap_or([0; 4]));
+}
+
+fn section_name_bytes(name: &str) -> Result<[u8; MAX_SECTION_NAME_LENGTH], Box<dyn std::error::Error>> {
+    if name.is_empty() || name.len() > MAX_SECTION_NAME_LENGTH || !name.is_ascii() {
+        return Err(format!("Section name \"{}\" must be 1 to {} ASCII characters", name, MAX_SECTION_NAME_LENGTH).into());
+    }
+
+    let mut bytes = [0; MAX_SECTION_NAME_LENGTH];
+    bytes[..name.len()].copy_from_slice(name.as_bytes());
+
+    return Ok(bytes);
+}
+
+/// Accepts a number (0x60000020) or flag names joined with '|' (CNT_CODE|MEM_EXECUTE|MEM_READ)
+pub fn parse_section_characteristics(value: &str) -> Result<u32, Box<dyn std::error::Error>> {
+    if let Ok(characteristics) = parse_address(value) {
+        return Ok(u32::try_from(characteristics)?);
+    }
+
+    let mut characteristics = 0;
+
+    for name in value.split('|').map(|n| n.trim()) {
+        let name = name.strip_prefix("IMAGE_SCN_").unwrap_or(name);
+
+        let flag = SectionFlags::iter()
+            .find(|f| <&'static str>::from(*f).eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("Unknown section flag \"{}\"", name))?;
+
+        characteristics |= flag as u32;
+    }
+
+    return Ok(characteristics);
+}
+
+/// File offset of the header of the first section with the name, the sections are kept in the order of the section table
+fn find_section_header(pe: &PE, name: &str) -> Option<u64> {
+    let index = pe.get_section_index(name)? as u64;
+
+    return Some(pe.get_section_table_offset() + index * SECTION_HEADER_SIZE);
+}
+
+pub fn rename_section(args: &RenameSectionArgs) -> Result<Dump, Box<dyn std::error::Error>> {
+    let Exec::PE(pe) = parse_exec(&args.file_path)? else {
+        return Err("Only PE sections can be renamed".into());
+    };
+
+    let new_name = section_name_bytes(&args.new_name)?;
+
+    let mut file_bytes = std::fs::read(&args.file_path)?;
+
+    let header = find_section_header(&pe, &args.name).ok_or_else(|| format!("No section named \"{}\"", args.name))? as usize;
+
+    file_bytes[header..header + MAX_SECTION_NAME_LENGTH].copy_from_slice(&new_name);
+
+    let (old_checksum, new_checksum) = fix_checksum(&pe, &mut file_bytes)?;
+
+    let (output, backup) = write_output(&args.file_path, args.output.as_deref(), &file_bytes)?;
+
+    let mut dump = Dump::new("Rename Section");
+
+    dump.push_field("Output", output.display().to_string(), None);
+    dump.push_field("Section", format!("{} -> {}", args.name, args.new_name), None);
+    dump.push_field("CheckSum", format!("{:#x} -> {:#x}", old_checksum, new_checksum), None);
+
+    if let Some(backup) = backup {
+        dump.push_field("Backup", backup.display().to_string(), None);
+    }
+
+    return Ok(dump);
+}
+
+/// Where a section appended by append_section landed
+struct AppendedSection {
+    virtual_address: u64,
+    pointer_to_raw_data: u64,
+    size_of_raw_data: u64,
+    size_of_image: u64,
+    /// Bytes the overlay was moved by, 0 when there is no overlay
+    overlay_moved: u64,
+}
+
+/// Virtual address of a section appended after the last one
+fn next_section_address(pe: &PE) -> u64 {
+    let end_of_sections = pe
+        .sections
+        .iter()
+        .map(|s| s.header.virtual_address as u64 + (s.header.virtual_size.max(s.header.size_of_raw_data)) as u64)
+        .max()
+        .unwrap_or(pe.get_optional_header().get_size_of_headers() as u64);
+
+    return align_up(end_of_sections, pe.get_optional_header().get_section_alignment() as u64);
+}
+
+/// Appends a section header and its data after the last section, the overlay is moved after it
+fn append_section(pe: &PE, file_bytes: &mut Vec<u8>, name: [u8; MAX_SECTION_NAME_LENGTH], characteristics: u32, data: &[u8], virtual_size: u64) -> Result<AppendedSection, Box<dyn std::error::Error>> {
+    let optional_header = pe.get_optional_header();
+    let file_alignment = optional_header.get_file_alignment() as u64;
+    let section_alignment = optional_header.get_section_alignment() as u64;
+
+    /* The new header goes after the last one, it must not run into the first section data */
+    let header_offset = pe.get_section_table_offset() + pe.get_number_of_sections() as u64 * SECTION_HEADER_SIZE;
+    let headers_end = header_offset + SECTION_HEADER_SIZE;
+
+    let first_raw_data = pe.sections.iter().filter(|s| s.header.size_of_raw_data > 0).map(|s| s.header.ptr_to_raw_data as u64).min().unwrap_or(file_bytes.len() as u64);
+
+    if headers_end > first_raw_data {
+        return Err("No room left for a new section header before the first section data".into());
+    }
+
+    if file_bytes[header_offset as usize..headers_end as usize].iter().any(|b| *b != 0) {
+        return Err("The space after the section table is in use (bound imports?), refusing to overwrite it".into());
+    }
+
+    let size_of_headers = optional_header.get_size_of_headers() as u64;
+    let new_size_of_headers = align_up(headers_end, file_alignment).max(size_of_headers);
+
+    let virtual_address = next_section_address(pe);
+
+    /* The overlay starts where the last section data ends */
+    let overlay_start = pe.sections.iter().map(|s| s.header.ptr_to_raw_data as u64 + s.header.size_of_raw_data as u64).max().unwrap_or(new_size_of_headers).min(file_bytes.len() as u64);
+
+    let pointer_to_raw_data = match data.is_empty() {
+        true => 0,
+        false => align_up(overlay_start, file_alignment),
+    };
+
+    let size_of_raw_data = align_up(data.len() as u64, file_alignment);
+
+    let mut header = Vec::with_capacity(SECTION_HEADER_SIZE as usize);
+    header.extend_from_slice(&name);
+    header.extend_from_slice(&(virtual_size as u32).to_le_bytes());
+    header.extend_from_slice(&(virtual_address as u32).to_le_bytes());
+    header.extend_from_slice(&(size_of_raw_data as u32).to_le_bytes());
+    header.extend_from_slice(&(pointer_to_raw_data as u32).to_le_bytes());
+    header.extend_from_slice(&[0; 12]);
+    header.extend_from_slice(&characteristics.to_le_bytes());
+
+    file_bytes[header_offset as usize..headers_end as usize].copy_from_slice(&header);
+
+    let mut inserted = 0;
+    let mut overlay_moved = 0;
+
+    if !data.is_empty() {
+        let overlay = file_bytes.split_off(overlay_start as usize);
+
+        file_bytes.resize(pointer_to_raw_data as usize, 0);
+        file_bytes.extend_from_slice(data);
+        file_bytes.resize((pointer_to_raw_data + size_of_raw_data) as usize, 0);
+
+        inserted = file_bytes.len() as u64 - overlay_start;
+
+        if !overlay.is_empty() {
+            overlay_moved = inserted;
+        }
+
+        file_bytes.extend_from_slice(&overlay);
+    }
+
+    /* The certificate table is referenced by file offset */
+    let certificate_directory = pe.get_data_directory_offset(DATA_DIRECTORY_CERTIFICATE);
+    let certificate_offset = read_u32_at(file_bytes, certificate_directory) as u64;
+
+    if inserted > 0 && certificate_offset >= overlay_start {
+        write_u32(file_bytes, certificate_directory, (certificate_offset + inserted) as u32);
+    }
+
+    let number_of_sections = pe.get_number_of_sections() as u16 + 1;
+    let number_of_sections_offset = pe.get_coff_header_offset() as usize + 2;
+    file_bytes[number_of_sections_offset..number_of_sections_offset + 2].copy_from_slice(&number_of_sections.to_le_bytes());
+
+    let optional_header_offset = pe.get_optional_header_offset();
+    let size_of_image = align_up(virtual_address + virtual_size, section_alignment);
+
+    write_u32(file_bytes, optional_header_offset + OPTIONAL_HEADER_SIZE_OF_IMAGE_OFFSET, size_of_image as u32);
+    write_u32(file_bytes, optional_header_offset + OPTIONAL_HEADER_SIZE_OF_HEADERS_OFFSET, new_size_of_headers as u32);
+
+    /* The size fields sum the sections of each kind */
+    for (flag, field, size) in [
+        (SectionFlags::CntCode, OPTIONAL_HEADER_SIZE_OF_CODE_OFFSET, size_of_raw_data),
+        (SectionFlags::CntInitializedData, OPTIONAL_HEADER_SIZE_OF_INITIALIZED_DATA_OFFSET, size_of_raw_data),
+        (SectionFlags::CntUninitializedData, OPTIONAL_HEADER_SIZE_OF_UNINITIALIZED_DATA_OFFSET, align_up(virtual_size, file_alignment)),
+    ] {
+        if characteristics & flag as u32 != 0 {
+            let value = read_u32_at(file_bytes, optional_header_offset + field);
+            write_u32(file_bytes, optional_header_offset + field, value.wrapping_add(size as u32));
+        }
+    }
+
+    return Ok(AppendedSection {
+        virtual_address,
+        pointer_to_raw_data,
+        size_of_raw_data,
+        size_of_image,
+        overlay_moved,
+    });
+}
+
+pub fn add_section(args: &AddSectionArgs) -> Result<Dump, Box<dyn std::error::Error>> {
+    let Exec::PE(pe) = parse_exec(&args.file_path)? else {
+        return Err("Sections can only be added to PE files".into());
+    };
+
+    let name = section_name_bytes(&args.name)?;
+    let characteristics = parse_section_characteristics(&args.characteristics)?;
+
+    let data = match args.data {
+        Some(ref path) => std::fs::read(path)?,
+        None => Vec::new(),
+    };
+
+    let virtual_size = (args.virtual_size.unwrap_or(data.len() as u32) as u64).max(data.len() as u64).max(1);
+
+    let mut file_bytes = std::fs::read(&args.file_path)?;
+
+    let section = append_section(&pe, &mut file_bytes, name, characteristics, &data, virtual_size)?;
+
+    let (old_checksum, new_checksum) = fix_checksum(&pe, &mut file_bytes)?;
+
+    let (output, backup) = write_output(&args.file_path, args.output.as_deref(), &file_bytes)?;
+
+    let mut dump = Dump::new("Add Section");
+
+    dump.push_field("Output", output.display().to_string(), None);
+    dump.push_field("Name", args.name.clone(), None);
+    dump.push_field("Characteristics", format!("{:#x}", characteristics), None);
+    dump.push_field("VirtualAddress", format!("{:#x}", section.virtual_address), None);
+    dump.push_field("VirtualSize", format!("{:#x}", virtual_size), None);
+    dump.push_field("PointerToRawData", format!("{:#x}", section.pointer_to_raw_data), None);
+    dump.push_field("SizeOfRawData", format!("{:#x}", section.size_of_raw_data), None);
+    dump.push_field("SizeOfImage", format!("{:#x}", section.size_of_image), None);
+
+    if section.overlay_moved > 0 {
+        dump.push_field("Overlay", format!("moved by {:#x} bytes", section.overlay_moved), None);
+    }
+
+    dump.push_field("CheckSum", format!("{:#x} -> {:#x}", old_checksum, new_checksum), None);
+
+    if let Some(backup) = backup {
+        dump.push_field("Backup", backup.display().to_string(), None);
+    }
+
+    return Ok(dump);
+}
+
+/*
+ * Memory dump un-mapping
+ */
+
+/// Converts a PE dumped from memory (sections at their virtual addresses) back to the file layout
+pub fn unmap(args: &UnmapArgs) -> Result<Dump, Box<dyn std::error::Error>> {
+    let dump_bytes = std::fs::read(&args.file_path)?;
+
+    /* The raw pointers of a dump describe the original file, only the headers can be trusted */
+    let mut pe = PE::new();
+    let section_headers = pe.parse_headers(&mut PEReader::from_bytes(&dump_bytes))?;
+
+    let optional_header = pe.get_optional_header();
+    let file_alignment = (optional_header.get_file_alignment() as u64).max(1);
+    let section_alignment = (optional_header.get_section_alignment() as u64).max(1);
+    let size_of_headers = (optional_header.get_size_of_headers() as u64).min(dump_bytes.len() as u64);
+
+    let mut order: Vec<usize> = (0..section_headers.len()).collect();
+    order.sort_by_key(|i| section_headers[*i].virtual_address);
+
+    let mut file_bytes = dump_bytes[..size_of_headers as usize].to_vec();
+    file_bytes.resize(align_up(size_of_headers, file_alignment) as usize, 0);
+
+    let mut dump = Dump::new("Unmap");
+    let mut sections_dump = Dump::new_from_string(format!("Sections ({})", section_headers.len()));
+
+    for (position, index) in order.iter().enumerate() {
+        let header = &section_headers[*index];
+
+        let start = header.virtual_address as u64;
+
+        /* A zero virtual size spans up to the next section */
+        let virtual_size = match header.virtual_size {
+            0 => order.get(position + 1).map(|next| section_headers[*next].virtual_address as u64).unwrap_or(align_up(start + 1, section_alignment)) - start,
+            size => size as u64,
+        };
+
+        let end = (start + virtual_size).min(dump_bytes.len() as u64);
+        let mut data = dump_bytes.get(start as usize..end as usize).unwrap_or(&[]);
+
+        /* Uninitialized data has no bytes in the file as long as nothing was written to it */
+        let uninitialized = header.characteristics & SectionFlags::CntUninitializedData as u32 != 0;
+
+        if uninitialized && data.iter().all(|b| *b == 0) {
+            data = &[];
+        }
+
+        let pointer_to_raw_data = match data.is_empty() {
+            true => 0,
+            false => file_bytes.len() as u64,
+        };
+
+        let size_of_raw_data = align_up(data.len() as u64, file_alignment);
+
+        file_bytes.extend_from_slice(data);
+        file_bytes.resize((file_bytes.len() as u64 + size_of_raw_data - data.len() as u64) as usize, 0);
+
+        let header_offset = pe.get_section_table_offset() + *index as u64 * SECTION_HEADER_SIZE;
+        write_u32(&mut file_bytes, header_offset + 16, size_of_raw_data as u32);
+        write_u32(&mut file_bytes, header_offset + 20, pointer_to_raw_data as u32);
+
+        sections_dump.push_field("", format!("{:<8} va: {:#x} raw: {:#x} -> {:#x} size: {:#x} -> {:#x}", header.name, header.virtual_address, header.ptr_to_raw_data, pointer_to_raw_data, header.size_of_raw_data, size_of_raw_data), None);
+    }
+
+    let (old_checksum, new_checksum) = fix_checksum(&pe, &mut file_bytes)?;
+
+    let (output, backup) = write_output(&args.file_path, args.output.as_deref(), &file_bytes)?;
+
+    dump.push_field("Output", output.display().to_string(), None);
+    dump.push_field("DumpSize", format!("{:#x}", dump_bytes.len()), None);
+    dump.push_field("FileSize", format!("{:#x}", file_bytes.len()), None);
+    dump.push_field("CheckSum", format!("{:#x} -> {:#x}", old_checksum, new_checksum), None);
+
+    if let Some(backup) = backup {
+        dump.push_field("Backup", backup.display().to_string(), None);
+    }
+
+    dump.push_child(sections_dump);
+
+    return Ok(dump);
+}
+
+/*
+ * Import directory rebuilding
+ */
+
+/* Size of an IMAGE_IMPORT_DESCRIPTOR */
+const IMPORT_DESCRIPTOR_SIZE: u64 = 20;
+
+/* Bit set in the thunks importing by ordinal */
+const PE32_ORDINAL_FLAG: u64 = 0x80000000;
+const PE64_ORDINAL_FLAG: u64 = 0x8000000000000000;
+
+/// A DLL of a rebuilt import directory
+struct ImportDescriptor {
+    dll_name: String,
+    functions: Vec<ImportRef>,
+    /// RVA of the existing import address table, a new one is laid out with the directory when missing
+    first_thunk: Option<u64>,
+}
+
+/// Lays out the descriptors, lookup tables, address tables and names of an import directory meant to be loaded at section_address
+/// The raw existing descriptors are kept first, returns the data and the thunks of each new descriptor
+fn build_import_directory(existing_descriptors: &[u8], descriptors: &[ImportDescriptor], section_address: u64, is_32_bits: bool) -> (Vec<u8>, Vec<Vec<u64>>) {
+    let (thunk_size, ordinal_flag) = match is_32_bits {
+        true => (4, PE32_ORDINAL_FLAG),
+        false => (8, PE64_ORDINAL_FLAG),
+    };
+
+    let thunks_size = |d: &ImportDescriptor| (d.functions.len() as u64 + 1) * thunk_size;
+
+    let lookup_tables_offset = existing_descriptors.len() as u64 + (descriptors.len() as u64 + 1) * IMPORT_DESCRIPTOR_SIZE;
+    let address_tables_offset = lookup_tables_offset + descriptors.iter().map(thunks_size).sum::<u64>();
+    let names_offset = address_tables_offset + descriptors.iter().filter(|d| d.first_thunk.is_none()).map(thunks_size).sum::<u64>();
+
+    let mut names: Vec<u8> = Vec::new();
+    let mut all_thunks = Vec::with_capacity(descriptors.len());
+
+    let mut descriptor_table: Vec<u8> = existing_descriptors.to_vec();
+    let mut lookup_tables: Vec<u8> = Vec::new();
+    let mut address_tables: Vec<u8> = Vec::new();
+
+    for descriptor in descriptors.iter() {
+        let name_rva = section_address + names_offset + names.len() as u64;
+        names.extend_from_slice(descriptor.dll_name.as_bytes());
+        names.push(0);
+
+        let mut thunks = Vec::with_capacity(descriptor.functions.len());
+
+        for function in descriptor.functions.iter() {
+            match function {
+                ImportRef::Ordinal(ordinal) => thunks.push(ordinal_flag | *ordinal as u64),
+                ImportRef::Name(name) => {
+                    /* Hint/Name entries are 2-byte aligned */
+                    if !names.len().is_multiple_of(2) {
+                        names.push(0);
+                    }
+
+                    thunks.push(section_address + names_offset + names.len() as u64);
+
+                    names.extend_from_slice(&0u16.to_le_bytes());
+                    names.extend_from_slice(name.as_bytes());
+                    names.push(0);
+                }
+            }
+        }
+
+        let lookup_table_rva = section_address + lookup_tables_offset + lookup_tables.len() as u64;
+
+        let first_thunk = match descriptor.first_thunk {
+            Some(first_thunk) => first_thunk,
+            None => section_address + address_tables_offset + address_tables.len() as u64,
+        };
+
+        for thunk in thunks.iter().chain(std::iter::once(&0)) {
+            lookup_tables.extend_from_slice(&thunk.to_le_bytes()[..thunk_size as usize]);
+
+            if descriptor.first_thunk.is_none() {
+                address_tables.extend_from_slice(&thunk.to_le_bytes()[..thunk_size as usize]);
+            }
+        }
+
+        descriptor_table.extend_from_slice(&(lookup_table_rva as u32).to_le_bytes());
+        descriptor_table.extend_from_slice(&0u32.to_le_bytes());
+        descriptor_table.extend_from_slice(&0u32.to_le_bytes());
+        descriptor_table.extend_from_slice(&(name_rva as u32).to_le_bytes());
+        descriptor_table.extend_from_slice(&(first_thunk as u32).to_le_bytes());
+
+        all_thunks.push(thunks);
+    }
+
+    descriptor_table.resize(lookup_tables_offset as usize, 0);
+
+    let mut data = descriptor_table;
+    data.extend_from_slice(&lookup_tables);
+    data.extend_from_slice(&address_tables);
+    data.extend_from_slice(&names);
+
+    return (data, all_thunks);
+}
+
+fn read_thunk(pe: &PE, file_bytes: &[u8], rva: u64) -> Option<u64> {
+    let offset = pe.convert_rva_to_file_offset(rva as u32)? as usize;
+
+    match pe.is_32_bits() {
+        true => return Some(u32::from_le_bytes(file_bytes.get(offset..offset + 4)?.try_into().ok()?) as u64),
+        false => return Some(u64::from_le_bytes(file_bytes.get(offset..offset + 8)?.try_into().ok()?)),
+    }
+}
+
+fn write_thunk(pe: &PE, file_bytes: &mut [u8], rva: u64, value: u64) -> bool {
+    let Some(offset) = pe.convert_rva_to_file_offset(rva as u32) else {
+        return false;
+    };
+
+    let size = if pe.is_32_bits() { 4 } else { 8 };
+
+    let Some(slot) = file_bytes.get_mut(offset as usize..offset as usize + size) else {
+        return false;
+    };
+
+    slot.copy_from_slice(&value.to_le_bytes()[..size]);
+
+    return true;
+}
+
+/// Absolute address of every export of the modules, "path=base" or "path" to use the preferred ImageBase
+fn module_exports(modules: &[String]) -> Result<HashMap<u64, (String, ImportRef)>, Box<dyn std::error::Error>> {
+    let mut exports = HashMap::new();
+
+    for module in modules.iter() {
+        let (path, base) = match module.rsplit_once('=') {
+            Some((path, base)) => (PathBuf::from(path), Some(parse_address(base)?)),
+            None => (PathBuf::from(module), None),
+        };
+
+        let module_pe = parse_pe(&path).map_err(|e| format!("Cannot parse module {}: {}", path.display(), e))?;
+
+        let dll_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let base = base.unwrap_or(module_pe.get_optional_header().get_image_base());
+
+        let Some(ref export_data) = module_pe.export_data else {
+            continue;
+        };
+
+        /* Forwarded exports point to a string, the loader resolves them in another module */
+        for entry in export_data.entries.iter().filter(|e| e.forwarder.is_none() && e.rva != 0) {
+            let function = match entry.name {
+                Some(ref name) => ImportRef::Name(name.clone()),
+                None => ImportRef::Ordinal(entry.ordinal as u16),
+            };
+
+            /* Aliases share an address, the first named one wins */
+            let address = base + entry.rva as u64;
+
+            match exports.get(&address) {
+                Some((_, ImportRef::Name(_))) => {}
+                _ => {
+                    exports.insert(address, (dll_name.clone(), function));
+                }
+            }
+        }
+    }
+
+    return Ok(exports);
+}
+
+/// End of the import address table starting at start: resolved addresses separated by single null thunks
+fn import_address_table_end(pe: &PE, file_bytes: &[u8], exports: &HashMap<u64, (String, ImportRef)>, start: u64) -> u64 {
+    let thunk_size = if pe.is_32_bits() { 4 } else { 8 };
+
+    let mut end = start;
+    let mut rva = start;
+
+    while let Some(thunk) = read_thunk(pe, file_bytes, rva) {
+        match thunk {
+            0 if rva > end => break,
+            0 => {}
+            thunk if exports.contains_key(&thunk) => end = rva + thunk_size,
+            _ => break,
+        }
+
+        rva += thunk_size;
+    }
+
+    return end;
+}
+
+/// Longest run of resolved addresses in the sections, packers often leave the IAT data directory empty
+fn find_import_address_table(pe: &PE, file_bytes: &[u8], exports: &HashMap<u64, (String, ImportRef)>) -> Option<(u64, u64)> {
+    let thunk_size = if pe.is_32_bits() { 4 } else { 8 };
+
+    let mut best: Option<(u64, u64, usize)> = None;
+
+    for section in pe.sections.iter() {
+        let start = section.header.virtual_address as u64;
+        let end = start + section.header.data_size() as u64;
+
+        let mut rva = start;
+
+        while rva + thunk_size <= end {
+            if !read_thunk(pe, file_bytes, rva).is_some_and(|t| exports.contains_key(&t)) {
+                rva += thunk_size;
+                continue;
+            }
+
+            let run_end = import_address_table_end(pe, file_bytes, exports, rva);
+            let resolved = (rva..run_end)
+                .step_by(thunk_size as usize)
+                .filter(|r| read_thunk(pe, file_bytes, *r).is_some_and(|t| exports.contains_key(&t)))
+                .count();
+
+            /* Sections are not ordered, the lowest address breaks ties */
+            if best.is_none_or(|(best_rva, _, count)| resolved > count || (resolved == count && rva < best_rva)) {
+                best = Some((rva, run_end - rva, resolved));
+            }
+
+            rva = run_end;
+        }
+    }
+
+    return best.map(|(rva, size, _)| (rva, size));
+}
+
+/// Rewrites the import directory of a dumped PE, whose import address table holds the addresses of the loaded functions
+pub fn rebuild_iat(args: &RebuildIatArgs) -> Result<Dump, Box<dyn std::error::Error>> {
+    let mut file_bytes = std::fs::read(&args.file_path)?;
+
+    /* The import directory of a dump is usually destroyed, only the headers and the sections are parsed */
+    let mut pe = PE::new();
+    pe.parse_headers_and_sections(&mut PEReader::from_bytes(&file_bytes), false)?;
+
+    let name = section_name_bytes(&args.section_name)?;
+    let exports = module_exports(&args.modules)?;
+
+    if exports.is_empty() {
+        return Err("The modules export no functions".into());
+    }
+
+    let thunk_size = if pe.is_32_bits() { 4 } else { 8 };
+
+    let iat_directory = pe.get_optional_header().get_import_address_table_idd();
+
+    let (iat_rva, iat_size) = match args.iat {
+        Some(ref iat) => {
+            let iat_rva = parse_address(iat)?;
+
+            match args.iat_size {
+                Some(ref size) => (iat_rva, parse_address(size)?),
+                None => (iat_rva, import_address_table_end(&pe, &file_bytes, &exports, iat_rva) - iat_rva),
+            }
+        }
+        None if iat_directory.virtual_address != 0 && iat_directory.size != 0 => (iat_directory.virtual_address as u64, iat_directory.size as u64),
+        None => find_import_address_table(&pe, &file_bytes, &exports).ok_or("No import address table found, pass its RVA with --iat")?,
+    };
+
+    /* Consecutive addresses of the same module make a descriptor, null thunks and unresolved addresses end it */
+    let mut descriptors: Vec<ImportDescriptor> = Vec::new();
+    let mut unresolved: Vec<(u64, u64)> = Vec::new();
+    let mut current: Option<ImportDescriptor> = None;
+
+    for rva in (iat_rva..iat_rva + iat_size).step_by(thunk_size as usize) {
+        let thunk = read_thunk(&pe, &file_bytes, rva).ok_or_else(|| format!("Import address table slot {:#x} is not in the file", rva))?;
+
+        let resolved = match thunk {
+            0 => None,
+            thunk => match exports.get(&thunk) {
+                Some(export) => Some(export),
+                None => {
+                    unresolved.push((rva, thunk));
+                    None
+                }
+            },
+        };
+
+        match (resolved, current.as_mut()) {
+            (Some((dll_name, function)), Some(descriptor)) if descriptor.dll_name == *dll_name => descriptor.functions.push(function.clone()),
+            (Some((dll_name, function)), _) => {
+                descriptors.extend(current.take());
+
+                current = Some(ImportDescriptor {
+                    dll_name: dll_name.clone(),
+                    functions: vec![function.clone()],
+                    first_thunk: Some(rva),
+                });
+            }
+            (None, _) => descriptors.extend(current.take()),
+        }
+    }
+
+    descriptors.extend(current.take());
+
+    if descriptors.is_empty() {
+        return Err(format!("No address of the import address table ({:#x}, {:#x} bytes) resolves to an export of the modules", iat_rva, iat_size).into());
+    }
+
+    let section_address = next_section_address(&pe);
+    let (data, thunks) = build_import_directory(&[], &descriptors, section_address, pe.is_32_bits());
+
+    /* On disk the import address table holds the same thunks as the lookup tables */
+    for (descriptor, thunks) in descriptors.iter().zip(thunks.iter()) {
+        let first_thunk = descriptor.first_thunk.unwrap_or_default();
+
+        for (i, thunk) in thunks.iter().enumerate() {
+            write_thunk(&pe, &mut file_bytes, first_thunk + i as u64 * thunk_size, *thunk);
+        }
+    }
+
+    let characteristics = SectionFlags::CntInitializedData as u32 | SectionFlags::MemRead as u32;
+    let section = append_section(&pe, &mut file_bytes, name, characteristics, &data, data.len() as u64)?;
+
+    let import_directory_size = (descriptors.len() as u64 + 1) * IMPORT_DESCRIPTOR_SIZE;
+
+    /* Bound imports describe the previous directory */
+    for (index, rva, size) in [
+        (DATA_DIRECTORY_IMPORT, section.virtual_address, import_directory_size),
+        (DATA_DIRECTORY_IMPORT_ADDRESS_TABLE, iat_rva, iat_size),
+        (DATA_DIRECTORY_BOUND_IMPORT, 0, 0),
+    ] {
+        let directory_offset = pe.get_data_directory_offset(index);
+        write_u32(&mut file_bytes, directory_offset, rva as u32);
+        write_u32(&mut file_bytes, directory_offset + 4, size as u32);
+    }
+
+    let (old_checksum, new_checksum) = fix_checksum(&pe, &mut file_bytes)?;
+
+    let (output, backup) = write_output(&args.file_path, args.output.as_deref(), &file_bytes)?;
+
+    let mut dump = Dump::new("Rebuild IAT");
+
+    dump.push_field("Output", output.display().to_string(), None);
+    dump.push_field("Exports", format!("{}", exports.len()), None);
+    dump.push_field("ImportAddressTable", format!("address: {:#x} sz: {:#x}", iat_rva, iat_size), None);
+    dump.push_field("Section", format!("{} ({:#x})", args.section_name, section.virtual_address), None);
+    dump.push_field("ImportTable", format!("address: {:#x} sz: {:#x}", section.virtual_address, import_directory_size), None);
+    dump.push_field("CheckSum", format!("{:#x} -> {:#x}", old_checksum, new_checksum), None);
+
+    if let Some(backup) = backup {
+        dump.push_field("Backup", backup.display().to_string(), None);
+    }
+
+    let mut imports_dump = Dump::new_from_string(format!("Imports ({})", descriptors.len()));
+
+    for descriptor in descriptors.iter() {
+        let mut descriptor_dump = Dump::new_from_string(format!("{} (first thunk: {:#x})", descriptor.dll_name, descriptor.first_thunk.unwrap_or_default()));
+
+        for function in descriptor.functions.iter() {
+            descriptor_dump.push_field("", function.to_string(), None);
+        }
+
+        imports_dump.push_child(descriptor_dump);
+    }
+
+    let mut unresolved_dump = Dump::new_from_string(format!("Unresolved Thunks ({})", unresolved.len()));
+
+    for (rva, thunk) in unresolved.iter() {
+        unresolved_dump.push_field("", format!("{:#x}: {:#x}", rva, thunk), None);
+    }
+
+    dump.push_child(imports_dump);
+    dump.push_child(unresolved_dump);
+
+    return Ok(dump);
+}
+
+/// Raw descriptors of the import directory, without the null terminator
+fn import_descriptors(pe: &PE, file_bytes: &[u8]) -> Vec<u8> {
+    let import_directory = pe.get_optional_header().get_import_table_idd();
+
+    let Some(mut offset) = pe.convert_rva_to_file_offset(import_directory.virtual_address).filter(|_| import_directory.virtual_address != 0) else {
+        return Vec::new();
+    };
+
+    let mut descriptors = Vec::new();
+
+    while let Some(descriptor) = file_bytes.get(offset as usize..(offset + IMPORT_DESCRIPTOR_SIZE) as usize) {
+        if descriptor.iter().all(|b| *b == 0) {
+            break;
+        }
+
+        descriptors.extend_from_slice(descriptor);
+        offset += IMPORT_DESCRIPTOR_SIZE;
+    }
+
+    return descriptors;
+}
+
+/// "Name" imports by name, "#12" by ordinal
+fn parse_import_ref(value: &str) -> Result<ImportRef, Box<dyn std::error::Error>> {
+    match value.strip_prefix('#') {
+        Some(ordinal) => return Ok(ImportRef::Ordinal(ordinal.parse::<u16>().map_err(|_| format!("Invalid ordinal \"{}\"", value))?)),
+        None if value.is_empty() => return Err("Empty function name".into()),
+        None => return Ok(ImportRef::Name(value.to_string())),
+    }
+}
+
+/// Copies the import directory to a new section with a new descriptor for the DLL, the loader then loads it with the PE
+pub fn add_import(args: &AddImportArgs) -> Result<Dump, Box<dyn std::error::Error>> {
+    let Exec::PE(pe) = parse_exec(&args.file_path)? else {
+        return Err("Imports can only be added to PE files".into());
+    };
+
+    let name = section_name_bytes(&args.section_name)?;
+
+    let functions = args.functions.iter().map(|f| parse_import_ref(f)).collect::<Result<Vec<ImportRef>, _>>()?;
+
+    if let Some(ref hint_name_table) = pe.hint_name_table && hint_name_table.entries.iter().any(|e| e.dll_name.eq_ignore_ascii_case(&args.dll)) {
+        return Err(format!("{} is already imported", args.dll).into());
+    }
+
+    let mut file_bytes = std::fs::read(&args.file_path)?;
+
+    let existing_descriptors = import_descriptors(&pe, &file_bytes);
+
+    /* Bound imports describe the previous directory, they usually sit right after the section table */
+    let bound_import_directory = pe.get_data_directory_offset(DATA_DIRECTORY_BOUND_IMPORT);
+    let bound_import_offset = read_u32_at(&file_bytes, bound_import_directory) as u64;
+    let bound_import_size = read_u32_at(&file_bytes, bound_import_directory + 4) as u64;
+
+    if bound_import_offset != 0 && bound_import_offset < pe.get_optional_header().get_size_of_headers() as u64 {
+        zero_range(&mut file_bytes, bound_import_offset, bound_import_size);
+    }
+
+    write_u32(&mut file_bytes, bound_import_directory, 0);
+    write_u32(&mut file_bytes, bound_import_directory + 4, 0);
+
+    let descriptor = ImportDescriptor {
+        dll_name: args.dll.clone(),
+        functions,
+        first_thunk: None,
+    };
+
+    let section_address = next_section_address(&pe);
+    let (data, _) = build_import_directory(&existing_descriptors, std::slice::from_ref(&descriptor), section_address, pe.is_32_bits());
+
+    /* The loader writes the resolved addresses to the new import address table */
+    let characteristics = SectionFlags::CntInitializedData as u32 | SectionFlags::MemRead as u32 | SectionFlags::MemWrite as u32;
+    let section = append_section(&pe, &mut file_bytes, name, characteristics, &data, data.len() as u64)?;
+
+    let number_of_descriptors = existing_descriptors.len() as u64 / IMPORT_DESCRIPTOR_SIZE + 1;
+    let import_directory_size = (number_of_descriptors + 1) * IMPORT_DESCRIPTOR_SIZE;
+
+    let import_directory = pe.get_data_directory_offset(DATA_DIRECTORY_IMPORT);
+    write_u32(&mut file_bytes, import_directory, section.virtual_address as u32);
+    write_u32(&mut file_bytes, import_directory + 4, import_directory_size as u32);
+
+    let (old_checksum, new_checksum) = fix_checksum(&pe, &mut file_bytes)?;
+
+    let (output, backup) = write_output(&args.file_path, args.output.as_deref(), &file_bytes)?;
+
+    let mut dump = Dump::new("Add Import");
+
+    dump.push_field("Output", output.display().to_string(), None);
+    dump.push_field("Dll", args.dll.clone(), None);
+    dump.push_field("Section", format!("{} ({:#x})", args.section_name, section.virtual_address), None);
+    dump.push_field("ImportTable", format!("address: {:#x} sz: {:#x}", section.virtual_address, import_directory_size), None);
+    dump.push_field("Descriptors", format!("{}", number_of_descriptors), None);
+
+    if bound_import_offset != 0 {
+        dump.push_field("BoundImports", String::from("removed"), None);
+    }
+
+    dump.push_field("CheckSum", format!("{:#x} -> {:#x}", old_checksum, new_checksum), None);
+
+    if let Some(backup) = backup {
+        dump.push_field("Backup", backup.display().to_string(), None);
+    }
+
+    let mut functions_dump = Dump::new_from_string(format!("Functions ({})", descriptor.functions.len()));
+
+    for function in descriptor.functions.iter() {
+        functions_dump.push_field("", function.to_string(), None);
+    }
+
+    dump.push_child(functions_dump);
+
+    return Ok(dump);
+}
+
+/*
+ * Signature removal
+ */
+
+/// Clears the security data directory and drops the certificates, truncating the file when they were appended at its end
+pub fn remove_signature(pe: &PE, file_path: &Path, output_path: Option<&Path>) -> Result<Dump, Box<dyn std::error::Error>> {
+    let mut file_bytes = std::fs::read(file_path)?;
+
+    /* The security directory holds a file offset, not a RVA */
+    let directory_offset = pe.get_data_directory_offset(DATA_DIRECTORY_CERTIFICATE);
+    let certificate_offset = read_u32_at(&file_bytes, directory_offset) as u64;
+    let certificate_size = read_u32_at(&file_bytes, directory_offset + 4) as u64;
+
+    if certificate_offset == 0 || certificate_size == 0 {
+        return Err("No signature found in PE".into());
+    }
+
+    let file_size = file_bytes.len() as u64;
+    let certificate_end = certificate_offset.saturating_add(certificate_size);
+
+    if certificate_offset > file_size {
+        return Err(format!("The certificate table ({:#x}) lies past the end of the file ({:#x} bytes)", certificate_offset, file_size).into());
+    }
+
+    /* Signing tools only pad the table to 8 bytes, anything after it is kept */
+    let truncated = file_bytes[certificate_end.min(file_size) as usize..].iter().all(|b| *b == 0);
+
+    match truncated {
+        true => file_bytes.truncate(certificate_offset as usize),
+        false => {
+            zero_range(&mut file_bytes, certificate_offset, certificate_size);
+        }
+    }
+
+    write_u32(&mut file_bytes, directory_offset, 0);
+    write_u32(&mut file_bytes, directory_offset + 4, 0);
+
+    let (old_checksum, new_checksum) = fix_checksum(pe, &mut file_bytes)?;
+
+    let (output, backup) = write_output(file_path, output_path, &file_bytes)?;
+
+    let mut dump = Dump::new("Remove Signature");
+
+    dump.push_field("Output", output.display().to_string(), None);
+    dump.push_field("Certificates", format!("{}", pe.certificate_table.as_ref().map(|t| t.certificates.len()).unwrap_or_default()), None);
+    dump.push_field("CertificateTable", format!("offset: {:#x} sz: {:#x}", certificate_offset, certificate_size), None);
+
+    match truncated {
+        true => dump.push_field("FileSize", format!("{:#x} -> {:#x}", file_size, file_bytes.len()), None),
+        false => dump.push_field("FileSize", format!("{:#x} (data follows the certificates, zeroed instead of truncated)", file_size), None),
+    }
+
+    dump.push_field("CheckSum", format!("{:#x} -> {:#x}", old_checksum, new_checksum), None);
+
+    if let Some(backup) = backup {
+        dump.push_field("Backup", backup.display().to_string(), None);
+    }
+
+    return Ok(dump);
+}
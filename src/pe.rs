@@ -1,21 +1,31 @@
-use byteorder::{LittleEndian, ReadBytesExt};
+use byteorder::LittleEndian;
 use std::error::Error;
-use std::io;
-use std::path::PathBuf;
-use std::{collections::HashMap, io::Read};
+use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, HashMap};
 
-use strum::IntoEnumIterator;
 use strum_macros::{EnumIter, IntoStaticStr};
 
-use crate::demangle::{demangle_msvc, is_mangled_symbol};
-use crate::disasm::disasm_pe_code;
+use crate::demangle::{display_name, label_name};
+use crate::address::{AddressKind, AddressLocation};
+use crate::analysis::describe_section_entropy;
+use crate::disasm::{disasm_dos_stub, disasm_pe_code, disasm_pe_instructions, disasm_pe_until_return};
 use crate::dump::*;
-use crate::format::format_u32_as_ctime;
+use crate::format::{decode_flags, format_flags, format_hex_lines, format_timestamp, FlagEnum};
+use crate::hashes::import_hash;
+use crate::dotnet::DotNetData;
+use crate::patch::rich_header_range;
+use crate::reader::{decode_name, ExecReader, FileReader};
+use crate::resources::ResourceTable;
+use crate::apiset::display_with_host;
+use crate::ordinals::{dll_stem, ordinal_name};
 
 /*
  * https://learn.microsoft.com/en-us/windows/win32/debug/pe-format
  */
 
+/// PE files are little-endian whatever the machine
+pub type PEReader<'a> = ExecReader<'a, LittleEndian>;
+
 /*
  * MS-DOS Header present in every PE file
  */
@@ -23,6 +33,7 @@ use crate::format::format_u32_as_ctime;
 /* Magic number for MS-DOS executable */
 pub const DOS_MAGIC: u16 = 0x5a4d;
 pub const DOS_MAGIC_ARRAY: [u8; 2] = [b'M', b'Z'];
+pub const DOS_HEADER_SIZE: u32 = 0x40;
 
 #[derive(Default, Clone, Debug)]
 #[repr(C)]
@@ -53,17 +64,40 @@ impl DOSHeader {
         return DOSHeader::default();
     }
 
-    fn from_parser(cursor: &mut io::Cursor<&Vec<u8>>) -> Result<DOSHeader, Box<dyn Error>> {
+    fn from_parser(cursor: &mut PEReader) -> Result<DOSHeader, Box<dyn Error>> {
         let mut header: DOSHeader = DOSHeader::new();
-        header.e_magic = cursor.read_u16::<LittleEndian>()?;
+        header.e_magic = cursor.read_u16()?;
 
         if header.e_magic != DOS_MAGIC {
             return Err("Invalid DOS magic number".into());
         }
 
-        cursor.set_position(0x3C);
+        header.e_cblp = cursor.read_u16()?;
+        header.e_cp = cursor.read_u16()?;
+        header.e_crlc = cursor.read_u16()?;
+        header.e_cparhdr = cursor.read_u16()?;
+        header.e_minalloc = cursor.read_u16()?;
+        header.e_maxalloc = cursor.read_u16()?;
+        header.e_ss = cursor.read_u16()?;
+        header.e_sp = cursor.read_u16()?;
+        header.e_csum = cursor.read_u16()?;
+        header.e_ip = cursor.read_u16()?;
+        header.e_cs = cursor.read_u16()?;
+        header.e_lfarlc = cursor.read_u16()?;
+        header.e_ovno = cursor.read_u16()?;
+
+        for word in header.e_res.iter_mut() {
+            *word = cursor.read_u16()?;
+        }
+
+        header.e_oemid = cursor.read_u16()?;
+        header.e_oeminfo = cursor.read_u16()?;
+
+        for word in header.e_res2.iter_mut() {
+            *word = cursor.read_u16()?;
+        }
 
-        header.e_lfanew = cursor.read_u32::<LittleEndian>()?;
+        header.e_lfanew = cursor.read_u32()?;
 
         return Ok(header);
     }
@@ -96,6 +130,114 @@ impl DOSHeader {
     }
 }
 
+/*
+ * MS-DOS stub, the real-mode program run in place of the PE under MS-DOS
+ */
+
+/* Stub written by the Microsoft linkers: prints the message with int 21h/09h and exits with int 21h/4Ch */
+const STANDARD_DOS_STUB_CODE: [u8; 14] = [0x0e, 0x1f, 0xba, 0x0e, 0x00, 0xb4, 0x09, 0xcd, 0x21, 0xb8, 0x01, 0x4c, 0xcd, 0x21];
+const STANDARD_DOS_STUB_MESSAGE: &str = "This program cannot be run in DOS mode.";
+
+/* Guards against a corrupted e_lfanew */
+const MAX_DOS_STUB_SIZE: u32 = 0x10000;
+
+#[derive(Default, Clone, Debug)]
+pub struct DosStub {
+    /// File offset of the stub, the end of the DOS header (e_cparhdr paragraphs)
+    pub offset: u32,
+    /// Bytes up to the Rich header, or up to the NT header without one
+    pub data: Vec<u8>,
+    /// File offset of the initial CS:IP
+    pub entry_offset: u32,
+}
+
+impl DosStub {
+    pub fn from_parser(
+        cursor: &mut PEReader,
+        dos_header: &DOSHeader,
+    ) -> Result<DosStub, Box<dyn std::error::Error>> {
+        let mut stub = DosStub::default();
+
+        let end = dos_header.e_lfanew.min(MAX_DOS_STUB_SIZE).min(cursor.length() as u32);
+
+        stub.offset = (dos_header.e_cparhdr as u32 * 16).max(DOS_HEADER_SIZE).min(end);
+        stub.entry_offset = stub.offset + dos_header.e_cs as u32 * 16 + dos_header.e_ip as u32;
+
+        let mut header_and_stub = vec![0; end as usize];
+        cursor.set_position(0)?;
+        cursor.read_exact(&mut header_and_stub)?;
+
+        let stub_end = match rich_header_range(&header_and_stub, end as usize) {
+            Some(range) if range.start >= stub.offset as usize => range.start,
+            _ => end as usize,
+        };
+
+        stub.data = header_and_stub[stub.offset as usize..stub_end].to_vec();
+
+        return Ok(stub);
+    }
+
+    /// The stub of the Microsoft linkers, anything else was put there on purpose
+    pub fn is_standard(&self) -> bool {
+        return self.data.starts_with(&STANDARD_DOS_STUB_CODE) && self.message().is_some_and(|m| m.starts_with(STANDARD_DOS_STUB_MESSAGE));
+    }
+
+    /// "$"-terminated string printed by int 21h/09h, found through the "mov dx, offset" of the stub (DS is the stub
+    /// segment), or the first printable one
+    pub fn message(&self) -> Option<String> {
+        let dx_offset = self.data.windows(3).filter(|w| w[0] == 0xba).map(|w| u16::from_le_bytes([w[1], w[2]]) as usize).find(|offset| {
+            self.data.get(*offset..).is_some_and(|text| text.iter().position(|&b| b == b'$').is_some_and(|end| text[..end].iter().all(|b| b.is_ascii_graphic() || b.is_ascii_whitespace())))
+        });
+
+        let start = match dx_offset {
+            Some(offset) => offset,
+            None => {
+                let end = self.data.iter().position(|&b| b == b'$')?;
+                self.data[..end].iter().rposition(|&b| !(b.is_ascii_graphic() || b.is_ascii_whitespace())).map(|p| p + 1).unwrap_or(0)
+            }
+        };
+
+        let end = start + self.data[start..].iter().position(|&b| b == b'$')?;
+        let message = String::from_utf8_lossy(&self.data[start..end]).trim().to_string();
+
+        return (!message.is_empty()).then_some(message);
+    }
+
+    /// Offset of the entry point in the stub, None when CS:IP points outside of it
+    pub fn entry_in_stub(&self) -> Option<usize> {
+        let entry = self.entry_offset.checked_sub(self.offset)? as usize;
+
+        return (entry < self.data.len()).then_some(entry);
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self, disasm: bool) -> Dump {
+        let mut dump = Dump::new("DOS Stub");
+
+        dump.push_field("Offset", format!("{:#x}", self.offset), None);
+        dump.push_field("Size", format!("{:#x} ({} bytes)", self.data.len(), self.data.len()), None);
+
+        match self.entry_in_stub() {
+            Some(_) => dump.push_field("EntryPoint", format!("{:#x}", self.entry_offset), None),
+            None => dump.push_field("EntryPoint", format!("{:#x} (outside of the stub)", self.entry_offset), None),
+        }
+
+        dump.push_field("Standard", String::from(if self.is_standard() { "Yes" } else { "No (custom stub)" }), None);
+        dump.push_field("Message", self.message().unwrap_or(String::from("None")), None);
+
+        /* Addresses are the IP of the instructions, relative to the load module that starts after the header */
+        match (disasm, self.entry_in_stub()) {
+            (true, Some(entry)) => match disasm_dos_stub(&self.data[entry..], (self.entry_offset - self.offset) as u64) {
+                Ok(lines) => dump.set_raw_data(DumpRawData::Code(lines)),
+                Err(e) => dump.push_field("Disassembly", e.to_string(), None),
+            },
+            _ => dump.set_raw_data(DumpRawData::Code(format_hex_lines(&self.data, self.offset as u64))),
+        }
+
+        return dump;
+    }
+}
+
 /*
  * Machine Types (machine field in COFF Header)
  */
@@ -197,6 +339,7 @@ pub enum CharacteristicsFlag {
     LargeAddressAware = 0x0020, // Application can handle > 2-GB addresses.
     UnusedFlag = 0x0040,       // This flag is reserved for future use.
     BytesReversedLo = 0x0080, // Little endian: the least significant bit (LSB) precedes the most significant bit (MSB) in memory. This flag is deprecated and should be zero.
+    #[strum(serialize = "32BIT_MACHINE")]
     Bit32Machine = 0x0100,    // Machine is based on a 32-bit-word architecture.
     DebugStripped = 0x0200,   // Debugging information is removed from the image file.
     RemovableRunFromSwap = 0x0400, // If the image is on removable media, fully load it and copy it to the swap file.
@@ -207,6 +350,14 @@ pub enum CharacteristicsFlag {
     BytesReversedHi = 0x8000, // Big endian: the MSB precedes the LSB in memory. This flag is deprecated and should be zero.
 }
 
+impl FlagEnum for CharacteristicsFlag {
+    const PREFIX: &'static str = "IMAGE_FILE_";
+
+    fn bits(self) -> u64 {
+        return self as u64;
+    }
+}
+
 /*
  * COFF Header
  */
@@ -224,27 +375,22 @@ pub struct COFFHeader {
 }
 
 impl COFFHeader {
-    fn from_parser(cursor: &mut io::Cursor<&Vec<u8>>) -> Result<COFFHeader, Box<dyn Error>> {
+    fn from_parser(cursor: &mut PEReader) -> Result<COFFHeader, Box<dyn Error>> {
         let mut header: COFFHeader = COFFHeader::default();
 
-        header.machine = cursor.read_u16::<LittleEndian>()?;
-        header.number_of_sections = cursor.read_u16::<LittleEndian>()?;
-        header.time_date_stamp = cursor.read_u32::<LittleEndian>()?;
-        header.pointer_to_symbol_table = cursor.read_u32::<LittleEndian>()?;
-        header.number_of_symbols = cursor.read_u32::<LittleEndian>()?;
-        header.size_of_optional_header = cursor.read_u16::<LittleEndian>()?;
-        header.characteristics = cursor.read_u16::<LittleEndian>()?;
+        header.machine = cursor.read_u16()?;
+        header.number_of_sections = cursor.read_u16()?;
+        header.time_date_stamp = cursor.read_u32()?;
+        header.pointer_to_symbol_table = cursor.read_u32()?;
+        header.number_of_symbols = cursor.read_u32()?;
+        header.size_of_optional_header = cursor.read_u16()?;
+        header.characteristics = cursor.read_u16()?;
 
         return Ok(header);
     }
 
     pub fn characteristics_as_string(&self) -> String {
-        let flags: Vec<&'static str> = CharacteristicsFlag::iter()
-            .filter(|&flag| (flag as u16 & self.characteristics) != 0)
-            .map(|flag| flag.into())
-            .collect();
-
-        return flags.join(" | ");
+        return decode_flags::<CharacteristicsFlag>(self.characteristics as u64);
     }
 
     #[rustfmt::skip]
@@ -253,11 +399,11 @@ impl COFFHeader {
 
         dump.push_field("Machine", format!("{:#x} ({:#?})", self.machine, MachineType::from(self.machine)), None);
         dump.push_field("NumberOfSections", format!("{:#x}", self.number_of_sections), None);
-        dump.push_field("TimeDateStamp", format!("{:#x} ({})", self.time_date_stamp, format_u32_as_ctime(self.time_date_stamp)), None);
+        dump.push_field("TimeDateStamp", format_timestamp(self.time_date_stamp), None);
         dump.push_field("PointerToSymbolTable", format!("{:#x}", self.pointer_to_symbol_table), None);
         dump.push_field("NumberOfSymbols", format!("{:#x}", self.number_of_symbols), None);
         dump.push_field("SizeOfOptionalHeader", format!("{:#x}", self.size_of_optional_header), None);
-        dump.push_field("Characteristics", format!("{:#x} ({})", self.characteristics, self.characteristics_as_string()), None);
+        dump.push_field("Characteristics", format_flags::<CharacteristicsFlag>(self.characteristics as u64), None);
 
         return dump;
     }
@@ -273,9 +419,9 @@ pub struct NTHeader {
 }
 
 impl NTHeader {
-    fn from_parser(cursor: &mut io::Cursor<&Vec<u8>>) -> Result<NTHeader, Box<dyn Error>> {
+    fn from_parser(cursor: &mut PEReader) -> Result<NTHeader, Box<dyn Error>> {
         let mut header: NTHeader = NTHeader::default();
-        header.signature = cursor.read_u32::<LittleEndian>()?;
+        header.signature = cursor.read_u32()?;
 
         if header.signature != NT_PE_SIGNATURE {
             return Err("Invalid PE signature in NT Header".into());
@@ -314,12 +460,12 @@ impl ImageDataDirectory {
     }
 
     pub fn from_parser(
-        cursor: &mut io::Cursor<&Vec<u8>>,
+        cursor: &mut PEReader,
     ) -> Result<ImageDataDirectory, Box<dyn std::error::Error>> {
         let mut idd = ImageDataDirectory::new();
 
-        idd.virtual_address = cursor.read_u32::<LittleEndian>()?;
-        idd.size = cursor.read_u32::<LittleEndian>()?;
+        idd.virtual_address = cursor.read_u32()?;
+        idd.size = cursor.read_u32()?;
 
         return Ok(idd);
     }
@@ -396,20 +542,24 @@ pub enum DLLCharacteristicsFlags {
     NoIsolation = 0x0200,         // Isolation aware, but do not isolate the image.
     NoSeh = 0x0400, // Does not use structured exception (SE) handling. No SE handler may be called in this image.
     NoBind = 0x0800, // Do not bind the image.
+    #[strum(serialize = "APPCONTAINER")]
     AppContainer = 0x1000, // Image must execute in an AppContainer.
     WdmDriver = 0x2000, // A WDM driver.
     GuardCf = 0x4000, // Image supports Control Flow Guard.
     TerminalServerAware = 0x8000, // Terminal Server
 }
 
+impl FlagEnum for DLLCharacteristicsFlags {
+    const PREFIX: &'static str = "IMAGE_DLLCHARACTERISTICS_";
+
+    fn bits(self) -> u64 {
+        return self as u64;
+    }
+}
+
 impl DLLCharacteristicsFlags {
     pub fn flags_as_string(characteristics: u16) -> String {
-        let flags: Vec<&'static str> = DLLCharacteristicsFlags::iter()
-            .filter(|&flag| (flag as u16 & characteristics) != 0)
-            .map(|flag| flag.into())
-            .collect();
-
-        return flags.join(" | ");
+        return decode_flags::<DLLCharacteristicsFlags>(characteristics as u64);
     }
 }
 
@@ -484,39 +634,39 @@ impl OptionalHeader32 {
         return OptionalHeader32::default();
     }
 
-    fn from_parser(cursor: &mut io::Cursor<&Vec<u8>>) -> Result<OptionalHeader32, Box<dyn Error>> {
+    fn from_parser(cursor: &mut PEReader) -> Result<OptionalHeader32, Box<dyn Error>> {
         let mut header: OptionalHeader32 = OptionalHeader32::new();
 
-        header.magic = cursor.read_u16::<LittleEndian>()?;
+        header.magic = cursor.read_u16()?;
         header.major_linker_version = cursor.read_u8()?;
         header.minor_linker_version = cursor.read_u8()?;
-        header.size_of_code = cursor.read_u32::<LittleEndian>()?;
-        header.size_of_initialized_data = cursor.read_u32::<LittleEndian>()?;
-        header.size_of_uninitialized_data = cursor.read_u32::<LittleEndian>()?;
-        header.address_of_entry_point = cursor.read_u32::<LittleEndian>()?;
-        header.base_of_code = cursor.read_u32::<LittleEndian>()?;
-        header.base_of_data = cursor.read_u32::<LittleEndian>()?;
-        header.image_base = cursor.read_u32::<LittleEndian>()?;
-        header.section_alignment = cursor.read_u32::<LittleEndian>()?;
-        header.file_alignement = cursor.read_u32::<LittleEndian>()?;
-        header.major_operating_system_version = cursor.read_u16::<LittleEndian>()?;
-        header.minor_operating_system_version = cursor.read_u16::<LittleEndian>()?;
-        header.major_image_version = cursor.read_u16::<LittleEndian>()?;
-        header.minor_image_version = cursor.read_u16::<LittleEndian>()?;
-        header.major_subsystem_version = cursor.read_u16::<LittleEndian>()?;
-        header.minor_subsystem_version = cursor.read_u16::<LittleEndian>()?;
-        header.win32_version_value = cursor.read_u32::<LittleEndian>()?; /* reserved field */
-        header.size_of_image = cursor.read_u32::<LittleEndian>()?;
-        header.size_of_headers = cursor.read_u32::<LittleEndian>()?;
-        header.checksum = cursor.read_u32::<LittleEndian>()?;
-        header.subsystem = cursor.read_u16::<LittleEndian>()?;
-        header.dll_characteristics = cursor.read_u16::<LittleEndian>()?;
-        header.size_of_stack_reserve = cursor.read_u32::<LittleEndian>()?;
-        header.size_of_stack_commit = cursor.read_u32::<LittleEndian>()?;
-        header.size_of_heap_reserve = cursor.read_u32::<LittleEndian>()?;
-        header.size_of_heap_commit = cursor.read_u32::<LittleEndian>()?;
-        header.loader_flags = cursor.read_u32::<LittleEndian>()?; /* reserved_field */
-        header.number_of_rva_and_sizes = cursor.read_u32::<LittleEndian>()?;
+        header.size_of_code = cursor.read_u32()?;
+        header.size_of_initialized_data = cursor.read_u32()?;
+        header.size_of_uninitialized_data = cursor.read_u32()?;
+        header.address_of_entry_point = cursor.read_u32()?;
+        header.base_of_code = cursor.read_u32()?;
+        header.base_of_data = cursor.read_u32()?;
+        header.image_base = cursor.read_u32()?;
+        header.section_alignment = cursor.read_u32()?;
+        header.file_alignement = cursor.read_u32()?;
+        header.major_operating_system_version = cursor.read_u16()?;
+        header.minor_operating_system_version = cursor.read_u16()?;
+        header.major_image_version = cursor.read_u16()?;
+        header.minor_image_version = cursor.read_u16()?;
+        header.major_subsystem_version = cursor.read_u16()?;
+        header.minor_subsystem_version = cursor.read_u16()?;
+        header.win32_version_value = cursor.read_u32()?; /* reserved field */
+        header.size_of_image = cursor.read_u32()?;
+        header.size_of_headers = cursor.read_u32()?;
+        header.checksum = cursor.read_u32()?;
+        header.subsystem = cursor.read_u16()?;
+        header.dll_characteristics = cursor.read_u16()?;
+        header.size_of_stack_reserve = cursor.read_u32()?;
+        header.size_of_stack_commit = cursor.read_u32()?;
+        header.size_of_heap_reserve = cursor.read_u32()?;
+        header.size_of_heap_commit = cursor.read_u32()?;
+        header.loader_flags = cursor.read_u32()?; /* reserved_field */
+        header.number_of_rva_and_sizes = cursor.read_u32()?;
         header.export_table = ImageDataDirectory::from_parser(cursor)?;
         header.import_table = ImageDataDirectory::from_parser(cursor)?;
         header.resource_table = ImageDataDirectory::from_parser(cursor)?;
@@ -570,8 +720,8 @@ impl OptionalHeader32 {
         windows_specific_dump.push_field("SizeOfImage", format!("{:#x}", self.size_of_image), None);
         windows_specific_dump.push_field("SizeOfHeaders", format!("{:#x}", self.size_of_headers), None);
         windows_specific_dump.push_field("Checksum", format!("{:#x}", self.checksum), None);
-        windows_specific_dump.push_field("Subsystem", format!("{:#x} ({})", self.subsystem, Subsystem::from(self.subsystem).as_static_str()), None);
-        windows_specific_dump.push_field("DLLCharacteristics", format!("{:#x} ({})", self.dll_characteristics, DLLCharacteristicsFlags::flags_as_string(self.dll_characteristics)), None);
+        windows_specific_dump.push_field("Subsystem", format!("{:#x} (IMAGE_SUBSYSTEM_{})", self.subsystem, Subsystem::from(self.subsystem).as_static_str()), None);
+        windows_specific_dump.push_field("DLLCharacteristics", format_flags::<DLLCharacteristicsFlags>(self.dll_characteristics as u64), None);
         windows_specific_dump.push_field("SizeOfStackReserve", format!("{:#x}", self.size_of_stack_reserve), None);
         windows_specific_dump.push_field("SizeOfStackCommit", format!("{:#x}", self.size_of_stack_commit), None);
         windows_specific_dump.push_field("SizeOfHeapReserve", format!("{:#x}", self.size_of_heap_reserve), None);
@@ -666,38 +816,38 @@ impl OptionalHeader64 {
         return OptionalHeader64::default();
     }
 
-    fn from_parser(cursor: &mut io::Cursor<&Vec<u8>>) -> Result<OptionalHeader64, Box<dyn Error>> {
+    fn from_parser(cursor: &mut PEReader) -> Result<OptionalHeader64, Box<dyn Error>> {
         let mut header: OptionalHeader64 = OptionalHeader64::new();
 
-        header.magic = cursor.read_u16::<LittleEndian>()?;
+        header.magic = cursor.read_u16()?;
         header.major_linker_version = cursor.read_u8()?;
         header.minor_linker_version = cursor.read_u8()?;
-        header.size_of_code = cursor.read_u32::<LittleEndian>()?;
-        header.size_of_initialized_data = cursor.read_u32::<LittleEndian>()?;
-        header.size_of_uninitialized_data = cursor.read_u32::<LittleEndian>()?;
-        header.address_of_entry_point = cursor.read_u32::<LittleEndian>()?;
-        header.base_of_code = cursor.read_u32::<LittleEndian>()?;
-        header.image_base = cursor.read_u64::<LittleEndian>()?;
-        header.section_alignment = cursor.read_u32::<LittleEndian>()?;
-        header.file_alignement = cursor.read_u32::<LittleEndian>()?;
-        header.major_operating_system_version = cursor.read_u16::<LittleEndian>()?;
-        header.minor_operating_system_version = cursor.read_u16::<LittleEndian>()?;
-        header.major_image_version = cursor.read_u16::<LittleEndian>()?;
-        header.minor_image_version = cursor.read_u16::<LittleEndian>()?;
-        header.major_subsystem_version = cursor.read_u16::<LittleEndian>()?;
-        header.minor_subsystem_version = cursor.read_u16::<LittleEndian>()?;
-        header.win32_version_value = cursor.read_u32::<LittleEndian>()?; /* reserved field */
-        header.size_of_image = cursor.read_u32::<LittleEndian>()?;
-        header.size_of_headers = cursor.read_u32::<LittleEndian>()?;
-        header.checksum = cursor.read_u32::<LittleEndian>()?;
-        header.subsystem = cursor.read_u16::<LittleEndian>()?;
-        header.dll_characteristics = cursor.read_u16::<LittleEndian>()?;
-        header.size_of_stack_reserve = cursor.read_u64::<LittleEndian>()?;
-        header.size_of_stack_commit = cursor.read_u64::<LittleEndian>()?;
-        header.size_of_heap_reserve = cursor.read_u64::<LittleEndian>()?;
-        header.size_of_heap_commit = cursor.read_u64::<LittleEndian>()?;
-        header.loader_flags = cursor.read_u32::<LittleEndian>()?; /* reserved_field */
-        header.number_of_rva_and_sizes = cursor.read_u32::<LittleEndian>()?;
+        header.size_of_code = cursor.read_u32()?;
+        header.size_of_initialized_data = cursor.read_u32()?;
+        header.size_of_uninitialized_data = cursor.read_u32()?;
+        header.address_of_entry_point = cursor.read_u32()?;
+        header.base_of_code = cursor.read_u32()?;
+        header.image_base = cursor.read_u64()?;
+        header.section_alignment = cursor.read_u32()?;
+        header.file_alignement = cursor.read_u32()?;
+        header.major_operating_system_version = cursor.read_u16()?;
+        header.minor_operating_system_version = cursor.read_u16()?;
+        header.major_image_version = cursor.read_u16()?;
+        header.minor_image_version = cursor.read_u16()?;
+        header.major_subsystem_version = cursor.read_u16()?;
+        header.minor_subsystem_version = cursor.read_u16()?;
+        header.win32_version_value = cursor.read_u32()?; /* reserved field */
+        header.size_of_image = cursor.read_u32()?;
+        header.size_of_headers = cursor.read_u32()?;
+        header.checksum = cursor.read_u32()?;
+        header.subsystem = cursor.read_u16()?;
+        header.dll_characteristics = cursor.read_u16()?;
+        header.size_of_stack_reserve = cursor.read_u64()?;
+        header.size_of_stack_commit = cursor.read_u64()?;
+        header.size_of_heap_reserve = cursor.read_u64()?;
+        header.size_of_heap_commit = cursor.read_u64()?;
+        header.loader_flags = cursor.read_u32()?; /* reserved_field */
+        header.number_of_rva_and_sizes = cursor.read_u32()?;
         header.export_table = ImageDataDirectory::from_parser(cursor)?;
         header.import_table = ImageDataDirectory::from_parser(cursor)?;
         header.resource_table = ImageDataDirectory::from_parser(cursor)?;
@@ -750,8 +900,8 @@ impl OptionalHeader64 {
         windows_specific_fields_dump.push_field("SizeOfImage", format!("{:#x}", self.size_of_image), None);
         windows_specific_fields_dump.push_field("SizeOfHeaders", format!("{:#x}", self.size_of_headers), None);
         windows_specific_fields_dump.push_field("Checksum", format!("{:#x}", self.checksum), None);
-        windows_specific_fields_dump.push_field("Subsystem", format!("{:#x} ({})", self.subsystem, Subsystem::from(self.subsystem).as_static_str()), None);
-        windows_specific_fields_dump.push_field("DLLCharacteristics", format!("{:#x} ({})", self.dll_characteristics, DLLCharacteristicsFlags::flags_as_string(self.dll_characteristics)), None);
+        windows_specific_fields_dump.push_field("Subsystem", format!("{:#x} (IMAGE_SUBSYSTEM_{})", self.subsystem, Subsystem::from(self.subsystem).as_static_str()), None);
+        windows_specific_fields_dump.push_field("DLLCharacteristics", format_flags::<DLLCharacteristicsFlags>(self.dll_characteristics as u64), None);
         windows_specific_fields_dump.push_field("SizeOfStackReserve", format!("{:#x}", self.size_of_stack_reserve), None);
         windows_specific_fields_dump.push_field("SizeOfStackCommit", format!("{:#x}", self.size_of_stack_commit), None);
         windows_specific_fields_dump.push_field("SizeOfHeapReserve", format!("{:#x}", self.size_of_heap_reserve), None);
@@ -806,6 +956,77 @@ impl OptionalHeader {
         }
     }
 
+    pub fn get_address_of_entry_point(&self) -> u32 {
+        match self {
+            Self::PE32(h) => h.address_of_entry_point,
+            Self::PE64(h) => h.address_of_entry_point,
+        }
+    }
+
+    pub fn get_image_base(&self) -> u64 {
+        match self {
+            Self::PE32(h) => h.image_base as u64,
+            Self::PE64(h) => h.image_base,
+        }
+    }
+
+    /// (major, minor)
+    pub fn get_linker_version(&self) -> (u8, u8) {
+        match self {
+            Self::PE32(h) => (h.major_linker_version, h.minor_linker_version),
+            Self::PE64(h) => (h.major_linker_version, h.minor_linker_version),
+        }
+    }
+
+    pub fn get_section_alignment(&self) -> u32 {
+        match self {
+            Self::PE32(h) => h.section_alignment,
+            Self::PE64(h) => h.section_alignment,
+        }
+    }
+
+    pub fn get_size_of_image(&self) -> u32 {
+        match self {
+            Self::PE32(h) => h.size_of_image,
+            Self::PE64(h) => h.size_of_image,
+        }
+    }
+
+    pub fn get_file_alignment(&self) -> u32 {
+        match self {
+            Self::PE32(h) => h.file_alignement,
+            Self::PE64(h) => h.file_alignement,
+        }
+    }
+
+    pub fn get_size_of_headers(&self) -> u32 {
+        match self {
+            Self::PE32(h) => h.size_of_headers,
+            Self::PE64(h) => h.size_of_headers,
+        }
+    }
+
+    pub fn get_subsystem(&self) -> Subsystem {
+        match self {
+            Self::PE32(h) => h.subsystem.into(),
+            Self::PE64(h) => h.subsystem.into(),
+        }
+    }
+
+    pub fn get_dll_characteristics(&self) -> u16 {
+        match self {
+            Self::PE32(h) => h.dll_characteristics,
+            Self::PE64(h) => h.dll_characteristics,
+        }
+    }
+
+    pub fn get_checksum(&self) -> u32 {
+        match self {
+            Self::PE32(h) => h.checksum,
+            Self::PE64(h) => h.checksum,
+        }
+    }
+
     pub fn get_export_table_idd(&self) -> &ImageDataDirectory {
         match self {
             Self::PE32(h) => &h.export_table,
@@ -927,24 +1148,40 @@ pub enum SectionFlags {
     Reserved400 = 0x00000400, // Reserved for future use.
     LnkRemove = 0x00000800, // The section will not become part of the image. This is valid only for object files.
     LnkComdat = 0x00001000, // The section contains COMDAT data. For more information, see COMDAT Sections (Object Only). This is valid only for object files.
+    #[strum(serialize = "GPREL")]
     GPRel = 0x00008000,     // The section contains data referenced through the global pointer (GP).
     MemPurgeable = 0x00020000, // Reserved for future use.
     MemLocked = 0x00040000, // Reserved for future use.
     MemPreload = 0x00080000, // Reserved for future use.
+    #[strum(serialize = "ALIGN_1BYTES")]
     Align1Bytes = 0x00100000, // Align data on a 1-byte boundary. Valid only for object files.
+    #[strum(serialize = "ALIGN_2BYTES")]
     Align2Bytes = 0x00200000, // Align data on a 2-byte boundary. Valid only for object files.
+    #[strum(serialize = "ALIGN_4BYTES")]
     Align4Bytes = 0x00300000, // Align data on a 4-byte boundary. Valid only for object files.
+    #[strum(serialize = "ALIGN_8BYTES")]
     Align8Bytes = 0x00400000, // Align data on an 8-byte boundary. Valid only for object files.
+    #[strum(serialize = "ALIGN_16BYTES")]
     Align16Bytes = 0x00500000, // Align data on a 16-byte boundary. Valid only for object files.
+    #[strum(serialize = "ALIGN_32BYTES")]
     Align32Bytes = 0x00600000, // Align data on a 32-byte boundary. Valid only for object files.
+    #[strum(serialize = "ALIGN_64BYTES")]
     Align64Bytes = 0x00700000, // Align data on a 64-byte boundary. Valid only for object files.
+    #[strum(serialize = "ALIGN_128BYTES")]
     Align128Bytes = 0x00800000, // Align data on a 128-byte boundary. Valid only for object files.
+    #[strum(serialize = "ALIGN_256BYTES")]
     Align256Bytes = 0x00900000, // Align data on a 256-byte boundary. Valid only for object files.
+    #[strum(serialize = "ALIGN_512BYTES")]
     Align512Bytes = 0x00A00000, // Align data on a 512-byte boundary. Valid only for object files.
+    #[strum(serialize = "ALIGN_1024BYTES")]
     Align1024Bytes = 0x00B00000, // Align data on a 1024-byte boundary. Valid only for object files.
+    #[strum(serialize = "ALIGN_2048BYTES")]
     Align2048Bytes = 0x00C00000, // Align data on a 2048-byte boundary. Valid only for object files.
+    #[strum(serialize = "ALIGN_4096BYTES")]
     Align4096Bytes = 0x00D00000, // Align data on a 4096-byte boundary. Valid only for object files.
+    #[strum(serialize = "ALIGN_8192BYTES")]
     Align8192Bytes = 0x00E00000, // Align data on an 8192-byte boundary. Valid only for object files.
+    #[strum(serialize = "LNK_NRELOC_OVFL")]
     LnkNRelocOVFL = 0x01000000,  // The section contains extended relocations.
     MemDiscardable = 0x02000000, // The section can be discarded as needed.
     MemNotCached = 0x04000000,   // The section cannot be cached.
@@ -955,14 +1192,28 @@ pub enum SectionFlags {
     MemWrite = 0x80000000,       // The section can be written to.
 }
 
+/* Alignment field of the section characteristics, object files only */
+pub const SECTION_ALIGN_MASK: u32 = 0x00F00000;
+
+impl FlagEnum for SectionFlags {
+    const PREFIX: &'static str = "IMAGE_SCN_";
+
+    fn bits(self) -> u64 {
+        return self as u64;
+    }
+
+    /* The alignments are the values of a 4 bits field, not flags */
+    fn mask(self) -> u64 {
+        match self.bits() & SECTION_ALIGN_MASK as u64 {
+            0 => return self.bits(),
+            _ => return SECTION_ALIGN_MASK as u64,
+        }
+    }
+}
+
 impl SectionFlags {
     pub fn flags_as_string(section_flags: u32) -> String {
-        let flags: Vec<&'static str> = SectionFlags::iter()
-            .filter(|&flag| (flag as u32 & section_flags) != 0)
-            .map(|flag| flag.into())
-            .collect();
-
-        return flags.join(" | ");
+        return decode_flags::<SectionFlags>(section_flags as u64);
     }
 }
 
@@ -974,6 +1225,10 @@ impl SectionFlags {
 #[repr(C)]
 pub struct SectionHeader {
     pub name: String,
+    /// Name bytes as stored in the header, NUL padded
+    pub raw_name: [u8; 8],
+    /// The name has bytes that are not printable UTF-8, they are escaped in `name`
+    pub name_escaped: bool,
     pub virtual_size: u32,
     pub virtual_address: u32,
     pub size_of_raw_data: u32,
@@ -991,48 +1246,30 @@ impl SectionHeader {
     }
 
     fn from_parser(
-        cursor: &mut io::Cursor<&Vec<u8>>,
+        cursor: &mut PEReader,
     ) -> Result<SectionHeader, Box<dyn std::error::Error>> {
         let mut header = SectionHeader::new();
 
-        let first_name_byte = cursor.read_u8()?;
+        cursor.read_exact(&mut header.raw_name)?;
 
-        if first_name_byte == 0x2F as u8 {
-            // "/"
-            todo!("Need to implement section header name finding in string table");
-        } else if first_name_byte == 0x0 as u8 {
-            // "\0"
+        /* Long names are written "/n", an offset in the COFF string table which images do not keep */
+        if header.raw_name[0] == 0x0 {
             header.name = "empty".to_string();
-            cursor.set_position(cursor.position() + 39);
-
-            return Ok(header);
         } else {
-            let mut name_buffer: Vec<u8> = Vec::new();
-
-            name_buffer.push(first_name_byte);
-
-            for _ in 0..7 {
-                let c = cursor.read_u8()?;
+            let length = header.raw_name.iter().position(|c| *c == 0x0).unwrap_or(header.raw_name.len());
 
-                if c == '\0' as u8 {
-                    continue;
-                }
-
-                name_buffer.push(c);
-            }
-
-            header.name = String::from_utf8(name_buffer).expect("Invalid section name found in PE");
+            (header.name, header.name_escaped) = decode_name(&header.raw_name[..length]);
         }
 
-        header.virtual_size = cursor.read_u32::<LittleEndian>()?;
-        header.virtual_address = cursor.read_u32::<LittleEndian>()?;
-        header.size_of_raw_data = cursor.read_u32::<LittleEndian>()?;
-        header.ptr_to_raw_data = cursor.read_u32::<LittleEndian>()?;
-        header.pointer_to_relocations = cursor.read_u32::<LittleEndian>()?;
-        header.pointer_to_line_numbers = cursor.read_u32::<LittleEndian>()?;
-        header.number_of_relocations = cursor.read_u16::<LittleEndian>()?;
-        header.number_of_line_numbers = cursor.read_u16::<LittleEndian>()?;
-        header.characteristics = cursor.read_u32::<LittleEndian>()?;
+        header.virtual_size = cursor.read_u32()?;
+        header.virtual_address = cursor.read_u32()?;
+        header.size_of_raw_data = cursor.read_u32()?;
+        header.ptr_to_raw_data = cursor.read_u32()?;
+        header.pointer_to_relocations = cursor.read_u32()?;
+        header.pointer_to_line_numbers = cursor.read_u32()?;
+        header.number_of_relocations = cursor.read_u16()?;
+        header.number_of_line_numbers = cursor.read_u16()?;
+        header.characteristics = cursor.read_u32()?;
 
         return Ok(header);
     }
@@ -1051,6 +1288,11 @@ impl SectionHeader {
 
         dump.push_field("Name", self.name.clone(), None);
 
+        if self.name_escaped {
+            dump.push_field("RawName", self.raw_name.iter().map(|b| format!("{:02x}", b)).collect::<Vec<String>>().join(" "), None);
+            dump.push_field("Warning", String::from("Name is not printable UTF-8, non-printable bytes are escaped"), None);
+        }
+
         dump.push_field("VirtualSize", format!("{:#x}", self.virtual_size), None);
         dump.push_field("VirtualAddress", format!("{:#x}", self.virtual_address), None);
         dump.push_field("SizeOfRawData", format!("{:#x}", self.size_of_raw_data), None);
@@ -1059,7 +1301,7 @@ impl SectionHeader {
         dump.push_field("PointerToLineNumbers", format!("{:#x}", self.pointer_to_line_numbers), None);
         dump.push_field("NumberOfRelocations", format!("{:#x}", self.number_of_relocations), None);
         dump.push_field("NumberOfLineNumbers", format!("{:#x}", self.number_of_line_numbers), None);
-        dump.push_field("Characteristics", format!("{:#x} ({})", self.characteristics, SectionFlags::flags_as_string(self.characteristics)), None);
+        dump.push_field("Characteristics", format_flags::<SectionFlags>(self.characteristics as u64), None);
 
         return dump;
     }
@@ -1099,8 +1341,8 @@ impl std::fmt::Debug for Section {
 impl Section {
     pub fn new(header: SectionHeader, data: Vec<u8>) -> Section {
         return Section {
-            header: header,
-            data: data,
+            header,
+            data,
         };
     }
 
@@ -1108,24 +1350,31 @@ impl Section {
         return (self.header.characteristics & (SectionFlags::CntCode as u32)) > 0;
     }
 
-    pub fn dump(&self, pe: &PE, disasm_code: bool) -> Dump {
+    /// Bytes backed by the file, without the zero-filled virtual tail
+    pub fn raw_data(&self) -> &[u8] {
+        let raw_size = (self.header.size_of_raw_data as usize).min(self.data.len());
+        return &self.data[..raw_size];
+    }
+
+    pub fn dump(&self, pe: &PE, data: bool, disasm_code: bool) -> Dump {
         let mut dump = Dump::new_from_string(format!("Section ({})", self.header.name));
 
+        dump.push_field("Entropy", describe_section_entropy(self.raw_data(), self.header.name == ".rsrc"), None);
         dump.push_child(self.header.dump());
 
         if disasm_code {
             if (self.header.characteristics & SectionFlags::CntCode as u32) > 0 {
-                let res = disasm_pe_code(&pe, &self.data, self.header.virtual_address as u64);
+                let res = disasm_pe_code(pe, &self.data, self.header.virtual_address as u64);
 
                 if let Ok(code) = res {
                     dump.set_raw_data(DumpRawData::Code(code));
-                } else {
+                } else if data {
                     dump.set_raw_data(DumpRawData::Bytes(self.data.clone()));
                 }
-            } else {
+            } else if data {
                 dump.set_raw_data(DumpRawData::Bytes(self.data.clone()));
             }
-        } else {
+        } else if data {
             dump.set_raw_data(DumpRawData::Bytes(self.data.clone()));
         }
 
@@ -1133,6 +1382,20 @@ impl Section {
     }
 }
 
+/*
+ * Section ranges, for the RVA to file offset lookups
+ */
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct SectionRange {
+    pub virtual_address: u32,
+    /// Size of the section in memory, aligned on the section alignment
+    pub mapped_size: u32,
+    pub ptr_to_raw_data: u32,
+    /// Bytes of the mapped size backed by the file
+    pub file_size: u32,
+}
+
 /*
  * Import Directory Table
  * https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#the-idata-section
@@ -1150,15 +1413,15 @@ pub struct ImportDirectoryTableEntry {
 
 impl ImportDirectoryTableEntry {
     pub fn from_parser(
-        cursor: &mut io::Cursor<&Vec<u8>>,
+        cursor: &mut PEReader,
     ) -> Result<ImportDirectoryTableEntry, Box<dyn std::error::Error>> {
         let mut idt = ImportDirectoryTableEntry::default();
 
-        idt.import_lookup_table_rva = cursor.read_u32::<LittleEndian>()?;
-        idt.time_date_stamp = cursor.read_u32::<LittleEndian>()?;
-        idt.forwarder_chain = cursor.read_u32::<LittleEndian>()?;
-        idt.name_rva = cursor.read_u32::<LittleEndian>()?;
-        idt.import_address_table_rva = cursor.read_u32::<LittleEndian>()?;
+        idt.import_lookup_table_rva = cursor.read_u32()?;
+        idt.time_date_stamp = cursor.read_u32()?;
+        idt.forwarder_chain = cursor.read_u32()?;
+        idt.name_rva = cursor.read_u32()?;
+        idt.import_address_table_rva = cursor.read_u32()?;
 
         return Ok(idt);
     }
@@ -1172,12 +1435,21 @@ impl ImportDirectoryTableEntry {
                self.import_address_table_rva == 0;
     }
 
+    /// Zero until the image is bound, -1 for the new-style bindings described by the Bound Import Table
+    pub fn format_time_date_stamp(&self) -> String {
+        match self.time_date_stamp {
+            0 => return String::from("0x0 (not bound)"),
+            u32::MAX => return format!("{:#x} (bound)", self.time_date_stamp),
+            _ => return format_timestamp(self.time_date_stamp),
+        }
+    }
+
     #[rustfmt::skip]
     pub fn dump(&self) -> Dump {
         let mut dump = Dump::new("Import Directory Table Entry");
 
         dump.push_field("ImportLookupTableRva", format!("{:#x}", self.import_lookup_table_rva), None);
-        dump.push_field("TimeDateStamp", format!("{:#x}", self.time_date_stamp), None);
+        dump.push_field("TimeDateStamp", self.format_time_date_stamp(), None);
         dump.push_field("ForwarderChain", format!("{:#x}", self.forwarder_chain), None);
         dump.push_field("NameRva", format!("{:#x}", self.name_rva), None);
         dump.push_field("ImportAddressTableRva", format!("{:#x}", self.import_address_table_rva), None);
@@ -1186,29 +1458,46 @@ impl ImportDirectoryTableEntry {
     }
 }
 
+/* Size of an import descriptor in the file */
+pub const IMPORT_DIRECTORY_ENTRY_SIZE: u64 = 20;
+
+/* Ordinals are 16 bits, no DLL can provide more functions */
+const MAX_IMPORT_LOOKUP_ENTRIES: usize = 0x10000;
+
 #[derive(Default, Clone, Debug)]
 pub struct ImportDirectoryTable {
     pub entries: Vec<ImportDirectoryTableEntry>,
+    /// Malformed or truncated import data, parsing stops or skips the entry instead of failing
+    pub warnings: Vec<String>,
 }
 
 impl ImportDirectoryTable {
+    /// Reads the descriptors up to the null one, never past the size of the directory or the end of the file
     pub fn from_parser(
-        cursor: &mut io::Cursor<&Vec<u8>>,
+        cursor: &mut PEReader,
+        directory_size: u32,
     ) -> Result<ImportDirectoryTable, Box<dyn std::error::Error>> {
         let mut idt = ImportDirectoryTable::default();
 
+        let max_entries = directory_size as u64 / IMPORT_DIRECTORY_ENTRY_SIZE;
+
         loop {
-            let entry = ImportDirectoryTableEntry::from_parser(cursor)?;
+            let Ok(entry) = ImportDirectoryTableEntry::from_parser(cursor) else {
+                idt.warnings.push(format!("Import directory truncated by the end of the file after {} descriptors", idt.entries.len()));
+                break;
+            };
 
             if entry.is_zeroed_out() {
                 break;
             }
 
-            idt.entries.push(entry);
-
-            if idt.entries.len() > 256 {
+            /* Some linkers do not count the null descriptor in the size, only a non-null one past it is an error */
+            if idt.entries.len() as u64 >= max_entries {
+                idt.warnings.push(format!("Import directory has no null descriptor within its size ({:#x}), stopped after {} descriptors", directory_size, idt.entries.len()));
                 break;
             }
+
+            idt.entries.push(entry);
         }
 
         return Ok(idt);
@@ -1218,6 +1507,10 @@ impl ImportDirectoryTable {
         return self.entries.len();
     }
 
+    pub fn is_empty(&self) -> bool {
+        return self.entries.is_empty();
+    }
+
     #[rustfmt::skip]
     pub fn dump(&self) -> Dump {
         let mut dump = Dump::new("Import Directory");
@@ -1226,16 +1519,30 @@ impl ImportDirectoryTable {
             dump.push_child(entry.dump());
         }
 
+        for warning in self.warnings.iter() {
+            dump.push_field("Warning", warning.clone(), None);
+        }
+
         return dump;
     }
 }
 
+/* Import lookup entry fields, the ordinal flag is the most significant bit in both formats */
+const PE32_IMPORT_ORDINAL_FLAG: u64 = 0x80000000;
+const PE64_IMPORT_ORDINAL_FLAG: u64 = 0x8000000000000000;
+const IMPORT_ORDINAL_MASK: u64 = 0xFFFF;
+const IMPORT_HINT_NAME_RVA_MASK: u64 = 0x7FFFFFFF;
+
 #[derive(Default, Clone, Debug)]
 #[repr(C)]
 pub struct ImportLookupTableEntry {
+    /// Entry as stored, 32 or 64 bits
+    pub raw: u64,
     pub by_ordinal: bool,
     pub ordinal_number: u16,
     pub hint_name_table_rva: u32,
+    /// Bits the specification requires to be zero are set (16-30/62 for ordinals, 31-62 for PE32+ names)
+    pub reserved_bits_set: bool,
 }
 
 impl ImportLookupTableEntry {
@@ -1244,39 +1551,38 @@ impl ImportLookupTableEntry {
     }
 
     pub fn from_parser(
-        cursor: &mut io::Cursor<&Vec<u8>>,
+        cursor: &mut PEReader,
         is_32_bits: bool,
     ) -> Result<ImportLookupTableEntry, Box<dyn std::error::Error>> {
         let mut entry = ImportLookupTableEntry::new();
 
-        if is_32_bits {
-            let data = cursor.read_u32::<LittleEndian>()?;
-            entry.by_ordinal = (data & 0x80000000) > 0;
-
-            if entry.by_ordinal {
-                entry.ordinal_number = (data & 0xFFFF) as u16;
-            } else {
-                entry.hint_name_table_rva = (data & 0x7FFFFFF) as u32;
+        let ordinal_flag = match is_32_bits {
+            true => {
+                entry.raw = cursor.read_u32()? as u64;
+                PE32_IMPORT_ORDINAL_FLAG
             }
-        } else {
-            let data = cursor.read_u64::<LittleEndian>()?;
-            entry.by_ordinal = (data & 0x8000000000000000) > 0;
-
-            if entry.by_ordinal {
-                entry.ordinal_number = (data & 0xFFFF) as u16;
-            } else {
-                entry.hint_name_table_rva = (data & 0x7FFFFFF) as u32;
+            false => {
+                entry.raw = cursor.read_u64()?;
+                PE64_IMPORT_ORDINAL_FLAG
             }
+        };
+
+        entry.by_ordinal = (entry.raw & ordinal_flag) != 0;
+
+        if entry.by_ordinal {
+            entry.ordinal_number = (entry.raw & IMPORT_ORDINAL_MASK) as u16;
+            entry.reserved_bits_set = (entry.raw & !(ordinal_flag | IMPORT_ORDINAL_MASK)) != 0;
+        } else {
+            entry.hint_name_table_rva = (entry.raw & IMPORT_HINT_NAME_RVA_MASK) as u32;
+            entry.reserved_bits_set = (entry.raw & !IMPORT_HINT_NAME_RVA_MASK) != 0;
         }
 
         return Ok(entry);
     }
 
-    #[rustfmt::skip]
+    /// The null entry ending the table, any set bit makes an entry for the loader
     pub fn is_zeroed_out(&self) -> bool {
-        return self.by_ordinal == false &&
-               self.ordinal_number == 0 &&
-               self.hint_name_table_rva == 0;
+        return self.raw == 0;
     }
 
     #[rustfmt::skip]
@@ -1285,7 +1591,8 @@ impl ImportLookupTableEntry {
 
         let flag_str = if self.by_ordinal { "Ordinal" } else { "Name" };
 
-        dump.push_field("Ordinal/Name Flag", format!("{}", flag_str), None);
+        dump.push_field("Raw", format!("{:#x}", self.raw), None);
+        dump.push_field("Ordinal/Name Flag", flag_str.to_string(), None);
 
         if self.by_ordinal {
             dump.push_field("OrdinalNumber", format!("{:#x}", self.ordinal_number), None);
@@ -1293,6 +1600,10 @@ impl ImportLookupTableEntry {
             dump.push_field("HintNameTableRva", format!("{:#x}", self.hint_name_table_rva), None);
         }
 
+        if self.reserved_bits_set {
+            dump.push_field("Warning", String::from("reserved bits set"), None);
+        }
+
         return dump;
     }
 }
@@ -1300,27 +1611,33 @@ impl ImportLookupTableEntry {
 #[derive(Default, Clone, Debug)]
 pub struct ImportLookupTable {
     pub entries: Vec<ImportLookupTableEntry>,
+    /// The table ran into the end of the file, or past the number of possible ordinals, before its null entry
+    pub truncated: bool,
 }
 
 impl ImportLookupTable {
     pub fn from_parser(
-        cursor: &mut io::Cursor<&Vec<u8>>,
+        cursor: &mut PEReader,
         is_32_bit: bool,
     ) -> Result<ImportLookupTable, Box<dyn std::error::Error>> {
         let mut ilt = ImportLookupTable::default();
 
         loop {
-            let entry = ImportLookupTableEntry::from_parser(cursor, is_32_bit)?;
+            let Ok(entry) = ImportLookupTableEntry::from_parser(cursor, is_32_bit) else {
+                ilt.truncated = true;
+                break;
+            };
 
             if entry.is_zeroed_out() {
                 break;
             }
 
-            ilt.entries.push(entry);
-
-            if ilt.entries.len() > 256 {
+            if ilt.entries.len() >= MAX_IMPORT_LOOKUP_ENTRIES {
+                ilt.truncated = true;
                 break;
             }
+
+            ilt.entries.push(entry);
         }
 
         return Ok(ilt);
@@ -1330,6 +1647,10 @@ impl ImportLookupTable {
         return self.entries.len();
     }
 
+    pub fn is_empty(&self) -> bool {
+        return self.entries.is_empty();
+    }
+
     pub fn dump(&self) -> Dump {
         let mut dump = Dump::new("Import Lookup Table");
 
@@ -1346,6 +1667,10 @@ impl ImportLookupTable {
 pub struct HintNameEntry {
     pub hint: u16,
     pub name: String,
+    /// Name bytes as stored in the file, before escaping and demangling
+    pub raw_name: Vec<u8>,
+    /// The name has bytes that are not printable UTF-8, they are escaped in `name`
+    pub name_escaped: bool,
     pub pad: bool,
 }
 
@@ -1355,37 +1680,17 @@ impl HintNameEntry {
     }
 
     pub fn from_parser(
-        cursor: &mut io::Cursor<&Vec<u8>>,
+        cursor: &mut PEReader,
     ) -> Result<HintNameEntry, Box<dyn std::error::Error>> {
         let mut entry = HintNameEntry::new();
 
-        entry.hint = cursor.read_u16::<LittleEndian>()?;
-
-        let mut name_buffer: Vec<u8> = Vec::new();
-
-        loop {
-            let c = cursor.read_u8()?;
-
-            if c == 0x0 {
-                break;
-            }
-
-            name_buffer.push(c);
-        }
-
-        if (cursor.position() % 2) != 0 {
-            cursor.read_u8()?;
-            entry.pad = true;
-        } else {
-            entry.pad = false;
-        }
+        entry.hint = cursor.read_u16()?;
+        entry.raw_name = cursor.read_cstr()?;
 
-        let name = String::from_utf8(name_buffer).expect("Invalid name found in Hint/Name Table");
+        /* The pad byte of the last entry may be missing from a truncated file */
+        entry.pad = !cursor.position().is_multiple_of(2) && cursor.read_u8().is_ok();
 
-        entry.name = match is_mangled_symbol(name.as_str()) {
-            true => demangle_msvc(name.as_str()).unwrap(),
-            false => name,
-        };
+        (entry.name, entry.name_escaped) = decode_name(&entry.raw_name);
 
         return Ok(entry);
     }
@@ -1394,28 +1699,17 @@ impl HintNameEntry {
 #[derive(Default, Clone, Debug)]
 pub struct HintNameData {
     pub dll_name: String,
+    /// DLL name bytes as stored in the file
+    pub raw_dll_name: Vec<u8>,
     pub entries: Vec<HintNameEntry>,
 }
 
 impl HintNameData {
+    /// Reads the NUL-terminated DLL name, undecoded
     pub fn parse_dll_name(
-        cursor: &mut io::Cursor<&Vec<u8>>,
-    ) -> Result<String, Box<dyn std::error::Error>> {
-        let mut name_buffer = Vec::new();
-
-        loop {
-            let c = cursor.read_u8()?;
-
-            if c == 0x0 {
-                break;
-            }
-
-            name_buffer.push(c);
-        }
-
-        return Ok(
-            String::from_utf8(name_buffer).expect("Invalid name found in Hint/Name Table for DLL")
-        );
+        cursor: &mut PEReader,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        return Ok(cursor.read_cstr()?);
     }
 }
 
@@ -1432,7 +1726,7 @@ impl HintNameTable {
             let mut dll_dump = Dump::new(&entry.dll_name);
 
             for hne in entry.entries.iter() {
-                dll_dump.push_field("", hne.name.to_string(), None);
+                dll_dump.push_field("", display_name(&hne.name), None);
             }
 
             dump.push_child(dll_dump);
@@ -1445,120 +1739,613 @@ impl HintNameTable {
         let mut dump = Dump::new("DLLS");
 
         for entry in self.entries.iter() {
-            dump.push_field("", entry.dll_name.clone(), None);
+            dump.push_field("", display_with_host(&entry.dll_name), None);
         }
 
         return dump;
     }
 }
 
-/*
- * Export Directory Table
- * https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#the-edata-section-image-only
- */
+/// Imports grouped by DLL: the descriptor, then the functions in lookup table order with their hint or ordinal and Import Address Table slot
+pub fn dump_imports(pe: &PE) -> Option<Dump> {
+    let (Some(idt), Some(ilts), Some(hnt)) = (&pe.import_directory_table, &pe.import_lookup_tables, &pe.hint_name_table) else {
+        return None;
+    };
 
-#[derive(Debug, Clone, Default)]
-#[repr(C)]
-pub struct ExportDirectoryTable {
-    pub export_flags: u32,
-    pub time_date_stamp: u32,
-    pub major_version: u16,
-    pub minor_version: u16,
-    pub name_rva: u32,
-    pub ordinal_base: u32,
-    pub address_table_entries: u32,
-    pub number_of_name_pointers: u32,
-    pub export_address_table_rva: u32,
-    pub name_pointer_rva: u32,
-    pub ordinal_table_rva: u32,
-}
+    let mut dump = Dump::new_from_string(format!("Imports ({} DLLs)", idt.len()));
 
-impl ExportDirectoryTable {
-    pub fn from_parser(
-        cursor: &mut io::Cursor<&Vec<u8>>,
-    ) -> Result<ExportDirectoryTable, Box<dyn std::error::Error>> {
-        let mut edt = ExportDirectoryTable::default();
+    if let Some(imphash) = import_hash(pe) {
+        dump.push_field("Imphash", imphash, None);
+    }
 
-        edt.export_flags = cursor.read_u32::<LittleEndian>()?;
-        edt.time_date_stamp = cursor.read_u32::<LittleEndian>()?;
-        edt.major_version = cursor.read_u16::<LittleEndian>()?;
-        edt.minor_version = cursor.read_u16::<LittleEndian>()?;
-        edt.name_rva = cursor.read_u32::<LittleEndian>()?;
-        edt.ordinal_base = cursor.read_u32::<LittleEndian>()?;
-        edt.address_table_entries = cursor.read_u32::<LittleEndian>()?;
-        edt.number_of_name_pointers = cursor.read_u32::<LittleEndian>()?;
-        edt.export_address_table_rva = cursor.read_u32::<LittleEndian>()?;
-        edt.name_pointer_rva = cursor.read_u32::<LittleEndian>()?;
-        edt.ordinal_table_rva = cursor.read_u32::<LittleEndian>()?;
+    let thunk_size = if pe.is_32_bits() { 4 } else { 8 };
 
-        return Ok(edt);
-    }
+    /* The lookup tables and Hint/Name entries are kept aligned with the descriptors when parsing */
+    for ((descriptor, ilt), hnd) in idt.entries.iter().zip(ilts.iter()).zip(hnt.entries.iter()) {
+        let mut dll_dump = Dump::new_from_string(format!("{} ({} functions)", hnd.dll_name, ilt.len()));
 
-    #[rustfmt::skip]
-    pub fn dump(&self) -> Dump {
-        let mut dump = Dump::new("Export Directory Table");
+        dll_dump.push_field("TimeDateStamp", descriptor.format_time_date_stamp(), None);
 
-        dump.push_field("ExportFlags", format!("{:#x}", self.export_flags), None);
-        dump.push_field("TimeDateStamp", format!("{:#x} ({})", self.time_date_stamp, format_u32_as_ctime(self.time_date_stamp)), None);
-        dump.push_field("MajorVersion", format!("{:#x}", self.major_version), None);
-        dump.push_field("MinorVersion", format!("{:#x}", self.minor_version), None);
-        dump.push_field("NameRva", format!("{:#x}", self.name_rva), None);
-        dump.push_field("OrdinalBase", format!("{:#x}", self.ordinal_base), None);
-        dump.push_field("AddressTableEntries", format!("{:#x}", self.address_table_entries), None);
-        dump.push_field("NumberOfNamePointers", format!("{:#x}", self.number_of_name_pointers), None);
-        dump.push_field("ExportAddressTableRva", format!("{:#x}", self.export_address_table_rva), None);
-        dump.push_field("NamePointerRva", format!("{:#x}", self.name_pointer_rva), None);
-        dump.push_field("OrdinalTableRva", format!("{:#x}", self.ordinal_table_rva), None);
+        if descriptor.forwarder_chain != 0 && descriptor.forwarder_chain != u32::MAX {
+            dll_dump.push_field("ForwarderChain", format!("{:#x}", descriptor.forwarder_chain), None);
+        }
 
-        return dump;
-    }
-}
+        dll_dump.push_field("ImportAddressTableRva", format!("{:#x}", descriptor.import_address_table_rva), None);
 
-#[derive(Debug, Clone, Default)]
-#[repr(C)]
-pub struct ExportAddressTableEntry {
-    pub export_rva: u32,
-    pub forwarder_rva: u32,
-}
+        let mut names = hnd.entries.iter();
 
-impl ExportAddressTableEntry {
-    pub fn from_parser(
-        cursor: &mut io::Cursor<&Vec<u8>>,
-    ) -> Result<ExportAddressTableEntry, Box<dyn std::error::Error>> {
-        let mut entry = ExportAddressTableEntry::default();
+        for (i, entry) in ilt.entries.iter().enumerate() {
+            let slot = descriptor.import_address_table_rva as u64 + i as u64 * thunk_size;
+
+            /* The Hint/Name entries only exist for the functions imported by name */
+            let function = match entry.by_ordinal {
+                true => match ordinal_name(&hnd.dll_name, entry.ordinal_number) {
+                    Some(name) => format!("ordinal {:<3} {}!{}", entry.ordinal_number, dll_stem(&hnd.dll_name), name),
+                    None => format!("ordinal {}", entry.ordinal_number),
+                },
+                false => match names.next() {
+                    Some(hne) if !hne.name.is_empty() => format!("hint {:<6} {}", format!("{:#x}", hne.hint), display_name(&hne.name)),
+                    _ => format!("unreadable Hint/Name entry at {:#x}", entry.hint_name_table_rva),
+                },
+            };
 
-        entry.export_rva = cursor.read_u32::<LittleEndian>()?;
-        entry.forwarder_rva = cursor.read_u32::<LittleEndian>()?;
+            dll_dump.push_field("", format!("{:#010x}  {}", slot, function), None);
+        }
 
-        return Ok(entry);
+        dump.push_child(dll_dump);
     }
-}
 
-type ExportAddressTable = Vec<ExportAddressTableEntry>;
+    for warning in idt.warnings.iter() {
+        dump.push_field("Warning", warning.clone(), None);
+    }
 
-type ExportNamePointerTable = Vec<u32>;
+    return Some(dump);
+}
 
-type ExportOrdinalTable = Vec<u16>;
+/*
+ * Import Address Table
+ * https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#import-address-table
+ */
 
-type ExportNameTable = Vec<String>;
+#[derive(Default, Clone, Debug)]
+pub struct ImportAddressTableSlot {
+    pub rva: u32,
+    /// Thunk as stored, a copy of the lookup entry on disk or the function address for a bound import
+    pub thunk: ImportLookupTableEntry,
+    /// Hint/Name entry the thunk points to, when it is not bound nor an ordinal
+    pub hint_name: Option<HintNameEntry>,
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct ImportAddressTable {
+    pub rva: u32,
+    pub size: u32,
+    /// Every slot of the directory, the null thunks ending the DLLs included
+    pub slots: Vec<ImportAddressTableSlot>,
+    pub warnings: Vec<String>,
+}
+
+impl ImportAddressTable {
+    /// Number of slots holding a function, the null thunks ending the DLLs left out
+    pub fn functions_count(&self) -> usize {
+        return self.slots.iter().filter(|slot| !slot.thunk.is_zeroed_out()).count();
+    }
+
+    /// DLL and function of the slot from the import descriptor whose address table holds it, the names coming from the
+    /// lookup table and Hint/Name entries
+    fn descriptor_function(pe: &PE, rva: u32) -> Option<(String, String)> {
+        let (idt, ilts, hnt) = (pe.import_directory_table.as_ref()?, pe.import_lookup_tables.as_ref()?, pe.hint_name_table.as_ref()?);
+
+        let thunk_size = if pe.is_32_bits() { 4 } else { 8 };
+
+        for ((descriptor, ilt), hnd) in idt.entries.iter().zip(ilts.iter()).zip(hnt.entries.iter()) {
+            let Some(offset) = rva.checked_sub(descriptor.import_address_table_rva) else {
+                continue;
+            };
+
+            let index = (offset / thunk_size) as usize;
+
+            if offset % thunk_size != 0 || index >= ilt.len() {
+                continue;
+            }
+
+            let entry = &ilt.entries[index];
+
+            /* The Hint/Name entries only exist for the functions imported by name */
+            let function = match entry.by_ordinal {
+                true => ordinal_function(&hnd.dll_name, entry.ordinal_number),
+                false => {
+                    let name_index = ilt.entries[..index].iter().filter(|e| !e.by_ordinal).count();
+
+                    match hnd.entries.get(name_index) {
+                        Some(hne) if !hne.name.is_empty() => display_name(&hne.name),
+                        _ => format!("unreadable Hint/Name entry at {:#x}", entry.hint_name_table_rva),
+                    }
+                }
+            };
+
+            return Some((hnd.dll_name.clone(), function));
+        }
+
+        return None;
+    }
+
+    /// Every slot with the function it is resolved to, from the import descriptors else from the thunk itself
+    #[rustfmt::skip]
+    pub fn dump(&self, pe: &PE) -> Dump {
+        let mut dump = Dump::new_from_string(format!("Import Address Table ({} functions)", self.functions_count()));
+
+        dump.push_field("Rva", format!("{:#x}", self.rva), None);
+        dump.push_field("Size", format!("{:#x}", self.size), None);
+
+        let thunk_width = if pe.is_32_bits() { 10 } else { 18 };
+
+        for slot in self.slots.iter().filter(|slot| !slot.thunk.is_zeroed_out()) {
+            let function = match (Self::descriptor_function(pe, slot.rva), &slot.hint_name) {
+                (Some((dll_name, function)), _) => format!("{}!{}", dll_name, function),
+                (None, Some(hne)) => display_name(&hne.name),
+                (None, None) if slot.thunk.by_ordinal => format!("#{}", slot.thunk.ordinal_number),
+                (None, None) => String::from("no import descriptor"),
+            };
+
+            dump.push_field("", format!("{:#010x}  {:#0width$x}  {}", slot.rva, slot.thunk.raw, function, width = thunk_width), None);
+        }
+
+        for warning in self.warnings.iter() {
+            dump.push_field("Warning", warning.clone(), None);
+        }
+
+        return dump;
+    }
+}
+
+/// "send (#19)" for the ordinals of the bundled database, "#19" for the others
+fn ordinal_function(dll_name: &str, ordinal: u16) -> String {
+    match ordinal_name(dll_name, ordinal) {
+        Some(name) => return format!("{} (#{})", name, ordinal),
+        None => return format!("#{}", ordinal),
+    }
+}
+
+/*
+ * Delay-Load Import Tables
+ * https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#delay-load-import-tables-image-only
+ */
+
+/* Size of a delay-load descriptor in the file */
+pub const DELAY_IMPORT_DESCRIPTOR_SIZE: u64 = 32;
+
+/* Descriptors without this attribute (Visual C++ 6) hold virtual addresses instead of RVAs */
+const DELAY_IMPORT_RVA_BASED: u32 = 0x1;
+
+#[derive(Default, Clone, Debug)]
+pub struct DelayImportDescriptor {
+    pub attributes: u32,
+    pub name_rva: u32,
+    pub module_handle_rva: u32,
+    pub delay_import_address_table_rva: u32,
+    pub delay_import_name_table_rva: u32,
+    pub bound_delay_import_table_rva: u32,
+    pub unload_delay_import_table_rva: u32,
+    pub time_date_stamp: u32,
+}
+
+impl DelayImportDescriptor {
+    pub fn from_parser(
+        cursor: &mut PEReader,
+    ) -> Result<DelayImportDescriptor, Box<dyn std::error::Error>> {
+        let mut descriptor = DelayImportDescriptor::default();
+
+        descriptor.attributes = cursor.read_u32()?;
+        descriptor.name_rva = cursor.read_u32()?;
+        descriptor.module_handle_rva = cursor.read_u32()?;
+        descriptor.delay_import_address_table_rva = cursor.read_u32()?;
+        descriptor.delay_import_name_table_rva = cursor.read_u32()?;
+        descriptor.bound_delay_import_table_rva = cursor.read_u32()?;
+        descriptor.unload_delay_import_table_rva = cursor.read_u32()?;
+        descriptor.time_date_stamp = cursor.read_u32()?;
+
+        return Ok(descriptor);
+    }
+
+    pub fn is_zeroed_out(&self) -> bool {
+        return self.name_rva == 0 && self.delay_import_address_table_rva == 0 && self.delay_import_name_table_rva == 0;
+    }
+
+    /// Converts the virtual addresses of the old descriptors to RVAs
+    pub fn to_rva(&self, address: u32, image_base: u64) -> u32 {
+        match self.attributes & DELAY_IMPORT_RVA_BASED {
+            0 if address != 0 => return (address as u64).wrapping_sub(image_base) as u32,
+            _ => return address,
+        }
+    }
+}
+
+/// Delay-load descriptors, with the lookup tables and Hint/Name entries aligned with them like the regular imports
+#[derive(Default, Clone, Debug)]
+pub struct DelayImportTable {
+    pub descriptors: Vec<DelayImportDescriptor>,
+    pub lookup_tables: Vec<ImportLookupTable>,
+    pub hint_name_table: HintNameTable,
+    pub warnings: Vec<String>,
+}
+
+/*
+ * Bound Import Table, written by the binding tools in the headers
+ */
+
+#[derive(Default, Clone, Debug)]
+pub struct BoundImportDescriptor {
+    /// Timestamp of the DLL the imports were bound to
+    pub time_date_stamp: u32,
+    pub module_name: String,
+    /// DLLs the bound module forwards some of its exports to, bound at the same time
+    pub forwarder_refs: Vec<(u32, String)>,
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct BoundImportTable {
+    pub descriptors: Vec<BoundImportDescriptor>,
+}
+
+/* Size of a bound import descriptor and of a forwarder reference */
+const BOUND_IMPORT_DESCRIPTOR_SIZE: u64 = 8;
+
+impl BoundImportTable {
+    /// Module names are stored at offsets from the start of the table
+    pub fn from_parser(
+        cursor: &mut PEReader,
+        size: u32,
+    ) -> Result<BoundImportTable, Box<dyn std::error::Error>> {
+        let mut table = BoundImportTable::default();
+
+        let start = cursor.position();
+        let end = start + size as u64;
+
+        let read_name = |cursor: &mut PEReader, offset: u16| -> Result<String, Box<dyn std::error::Error>> {
+            let position = cursor.position();
+            cursor.set_position(start + offset as u64)?;
+            let name = decode_name(&cursor.read_cstr()?).0;
+            cursor.set_position(position)?;
+
+            return Ok(name);
+        };
+
+        while cursor.position() + BOUND_IMPORT_DESCRIPTOR_SIZE <= end {
+            let time_date_stamp = cursor.read_u32()?;
+            let name_offset = cursor.read_u16()?;
+            let forwarder_count = cursor.read_u16()?;
+
+            if time_date_stamp == 0 && name_offset == 0 {
+                break;
+            }
+
+            let mut descriptor = BoundImportDescriptor {
+                time_date_stamp,
+                module_name: read_name(cursor, name_offset)?,
+                forwarder_refs: Vec::new(),
+            };
+
+            for _ in 0..forwarder_count {
+                if cursor.position() + BOUND_IMPORT_DESCRIPTOR_SIZE > end {
+                    break;
+                }
+
+                let forwarder_time_date_stamp = cursor.read_u32()?;
+                let forwarder_name_offset = cursor.read_u16()?;
+                let _reserved = cursor.read_u16()?;
+
+                descriptor.forwarder_refs.push((forwarder_time_date_stamp, read_name(cursor, forwarder_name_offset)?));
+            }
+
+            table.descriptors.push(descriptor);
+        }
+
+        return Ok(table);
+    }
+
+    /// Timestamp the imports of a DLL were bound to
+    pub fn find(&self, dll_name: &str) -> Option<u32> {
+        return self
+            .descriptors
+            .iter()
+            .flat_map(|d| std::iter::once((d.time_date_stamp, &d.module_name)).chain(d.forwarder_refs.iter().map(|(t, n)| (*t, n))))
+            .find(|(_, name)| name.eq_ignore_ascii_case(dll_name))
+            .map(|(time_date_stamp, _)| time_date_stamp);
+    }
+}
+
+/*
+ * Export Directory Table
+ * https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#the-edata-section-image-only
+ */
+
+#[derive(Debug, Clone, Default)]
+#[repr(C)]
+pub struct ExportDirectoryTable {
+    pub export_flags: u32,
+    pub time_date_stamp: u32,
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub name_rva: u32,
+    pub ordinal_base: u32,
+    pub address_table_entries: u32,
+    pub number_of_name_pointers: u32,
+    pub export_address_table_rva: u32,
+    pub name_pointer_rva: u32,
+    pub ordinal_table_rva: u32,
+}
+
+impl ExportDirectoryTable {
+    pub fn from_parser(
+        cursor: &mut PEReader,
+    ) -> Result<ExportDirectoryTable, Box<dyn std::error::Error>> {
+        let mut edt = ExportDirectoryTable::default();
+
+        edt.export_flags = cursor.read_u32()?;
+        edt.time_date_stamp = cursor.read_u32()?;
+        edt.major_version = cursor.read_u16()?;
+        edt.minor_version = cursor.read_u16()?;
+        edt.name_rva = cursor.read_u32()?;
+        edt.ordinal_base = cursor.read_u32()?;
+        edt.address_table_entries = cursor.read_u32()?;
+        edt.number_of_name_pointers = cursor.read_u32()?;
+        edt.export_address_table_rva = cursor.read_u32()?;
+        edt.name_pointer_rva = cursor.read_u32()?;
+        edt.ordinal_table_rva = cursor.read_u32()?;
+
+        return Ok(edt);
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Export Directory Table");
+
+        dump.push_field("ExportFlags", format!("{:#x}", self.export_flags), None);
+        dump.push_field("TimeDateStamp", format_timestamp(self.time_date_stamp), None);
+        dump.push_field("MajorVersion", format!("{:#x}", self.major_version), None);
+        dump.push_field("MinorVersion", format!("{:#x}", self.minor_version), None);
+        dump.push_field("NameRva", format!("{:#x}", self.name_rva), None);
+        dump.push_field("OrdinalBase", format!("{:#x}", self.ordinal_base), None);
+        dump.push_field("AddressTableEntries", format!("{:#x}", self.address_table_entries), None);
+        dump.push_field("NumberOfNamePointers", format!("{:#x}", self.number_of_name_pointers), None);
+        dump.push_field("ExportAddressTableRva", format!("{:#x}", self.export_address_table_rva), None);
+        dump.push_field("NamePointerRva", format!("{:#x}", self.name_pointer_rva), None);
+        dump.push_field("OrdinalTableRva", format!("{:#x}", self.ordinal_table_rva), None);
+
+        return dump;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+#[repr(C)]
+pub struct ExportAddressTableEntry {
+    pub export_rva: u32,
+    pub forwarder_rva: u32,
+}
+
+impl ExportAddressTableEntry {
+    /// An entry is a forwarder when its RVA points inside the export section itself
+    pub fn from_parser(
+        cursor: &mut PEReader,
+        export_section: &std::ops::Range<u32>,
+    ) -> Result<ExportAddressTableEntry, Box<dyn std::error::Error>> {
+        let mut entry = ExportAddressTableEntry::default();
+
+        let rva = cursor.read_u32()?;
+
+        if export_section.contains(&rva) {
+            entry.forwarder_rva = rva;
+        } else {
+            entry.export_rva = rva;
+        }
+
+        return Ok(entry);
+    }
+
+    pub fn is_forwarder(&self) -> bool {
+        return self.forwarder_rva != 0;
+    }
+}
+
+type ExportAddressTable = Vec<ExportAddressTableEntry>;
+
+type ExportNamePointerTable = Vec<u32>;
+
+type ExportOrdinalTable = Vec<u16>;
+
+type ExportNameTable = Vec<String>;
+
+/* Upper bound on the number of exports, protects against corrupted directories */
+pub const MAX_EXPORTS: u32 = 0x10000;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportEntry {
+    pub ordinal: u32,
+    pub rva: u32,
+    pub name: Option<String>,
+    pub forwarder: Option<String>,
+    /// Public symbol of the PDB at the export RVA, once the PDB symbols are attached
+    pub symbol: Option<String>,
+}
+
+impl ExportEntry {
+    /// Name used to identify the export, falls back on the ordinal for exports by ordinal only
+    pub fn display_name(&self) -> String {
+        match self.name {
+            Some(ref name) => return name.clone(),
+            None => return format!("#{}", self.ordinal),
+        }
+    }
+
+    /// DLL and function of a forwarder, "NTDLL.RtlAllocateHeap" -> ("NTDLL", "RtlAllocateHeap"), the function is "#27" when forwarded by ordinal
+    pub fn forwarder_target(&self) -> Option<(&str, &str)> {
+        return self.forwarder.as_deref().and_then(|f| f.rsplit_once('.')).filter(|(dll, function)| !dll.is_empty() && !function.is_empty());
+    }
+}
 
 #[derive(Default, Clone, Debug)]
 pub struct ExportData {
     pub export_directory_table: ExportDirectoryTable,
+    pub dll_name: String,
     pub export_address_table: ExportAddressTable,
     pub export_name_pointer_table: ExportNamePointerTable,
     pub export_ordinal_table: ExportOrdinalTable,
     pub export_name_table: ExportNameTable,
+    pub entries: Vec<ExportEntry>,
 }
 
 impl ExportData {
     pub fn from_parser(
-        cursor: &mut io::Cursor<&Vec<u8>>,
+        cursor: &mut PEReader,
+        pe: &PE,
     ) -> Result<ExportData, Box<dyn std::error::Error>> {
         let mut export_data = ExportData::default();
 
+        let export_table_idd = pe.get_optional_header().get_export_table_idd();
+        let export_section = export_table_idd.virtual_address..export_table_idd.virtual_address + export_table_idd.size;
+
+        export_data.export_directory_table = ExportDirectoryTable::from_parser(cursor)?;
+
+        let edt = &export_data.export_directory_table;
+
+        if edt.address_table_entries > MAX_EXPORTS || edt.number_of_name_pointers > MAX_EXPORTS {
+            return Err("Invalid Export Directory Table, too many entries".into());
+        }
+
+        if let Some(name_offset) = pe.convert_rva_to_file_offset(edt.name_rva) {
+            cursor.set_position(name_offset)?;
+            export_data.dll_name = ExportData::parse_name(cursor)?;
+        }
+
+        let eat_offset = pe
+            .convert_rva_to_file_offset(edt.export_address_table_rva)
+            .ok_or("Cannot find file offset for Export Address Table")?;
+
+        cursor.set_position(eat_offset)?;
+
+        for _ in 0..edt.address_table_entries {
+            export_data
+                .export_address_table
+                .push(ExportAddressTableEntry::from_parser(cursor, &export_section)?);
+        }
+
+        if edt.number_of_name_pointers > 0 {
+            let npt_offset = pe
+                .convert_rva_to_file_offset(edt.name_pointer_rva)
+                .ok_or("Cannot find file offset for Export Name Pointer Table")?;
+
+            cursor.set_position(npt_offset)?;
+
+            for _ in 0..edt.number_of_name_pointers {
+                export_data.export_name_pointer_table.push(cursor.read_u32()?);
+            }
+
+            let ot_offset = pe
+                .convert_rva_to_file_offset(edt.ordinal_table_rva)
+                .ok_or("Cannot find file offset for Export Ordinal Table")?;
+
+            cursor.set_position(ot_offset)?;
+
+            for _ in 0..edt.number_of_name_pointers {
+                export_data.export_ordinal_table.push(cursor.read_u16()?);
+            }
+
+            for name_rva in export_data.export_name_pointer_table.iter() {
+                let name = match pe.convert_rva_to_file_offset(*name_rva) {
+                    Some(name_offset) => {
+                        cursor.set_position(name_offset)?;
+                        ExportData::parse_name(cursor)?
+                    }
+                    None => String::new(),
+                };
+
+                export_data.export_name_table.push(name);
+            }
+        }
+
+        let ordinal_base = export_data.export_directory_table.ordinal_base;
+
+        for (index, eat_entry) in export_data.export_address_table.iter().enumerate() {
+            /* Unused slots of the Export Address Table are zeroed */
+            if eat_entry.export_rva == 0 && eat_entry.forwarder_rva == 0 {
+                continue;
+            }
+
+            let name = export_data
+                .export_ordinal_table
+                .iter()
+                .position(|ordinal| *ordinal as usize == index)
+                .map(|name_index| export_data.export_name_table[name_index].clone());
+
+            /* A forwarder whose string cannot be read is still not code, its RVA must not pass for the function address */
+            let forwarder = if eat_entry.is_forwarder() {
+                match pe.convert_rva_to_file_offset(eat_entry.forwarder_rva) {
+                    Some(forwarder_offset) => {
+                        cursor.set_position(forwarder_offset)?;
+                        Some(ExportData::parse_name(cursor)?)
+                    }
+                    None => Some(format!("(unreadable forwarder at {:#x})", eat_entry.forwarder_rva)),
+                }
+            } else {
+                None
+            };
+
+            export_data.entries.push(ExportEntry {
+                ordinal: ordinal_base + index as u32,
+                rva: if eat_entry.is_forwarder() { eat_entry.forwarder_rva } else { eat_entry.export_rva },
+                name,
+                forwarder,
+                symbol: None,
+            });
+        }
+
         return Ok(export_data);
     }
+
+    pub fn parse_name(
+        cursor: &mut PEReader,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        return Ok(decode_name(&cursor.read_cstr()?).0);
+    }
+
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new_from_string(format!("Exports ({})", self.entries.len()));
+
+        let edt = &self.export_directory_table;
+        let named = self.entries.iter().filter(|e| e.name.is_some()).count();
+
+        dump.push_field("DllName", self.dll_name.clone(), None);
+        dump.push_field("TimeDateStamp", format_timestamp(edt.time_date_stamp), None);
+        dump.push_field("Version", format!("{}.{}", edt.major_version, edt.minor_version), None);
+        dump.push_field("OrdinalBase", format!("{}", edt.ordinal_base), None);
+        dump.push_field("Named", format!("{} of {}", named, self.entries.len()), None);
+
+        /* Forwarded exports are resolved by the loader in another DLL, they have no code here */
+        let forwarded = self.entries.iter().filter(|e| e.forwarder.is_some()).count();
+
+        if forwarded > 0 {
+            let mut target_dlls: Vec<String> = self.entries.iter().filter_map(|e| e.forwarder_target()).map(|(dll, _)| dll.to_ascii_uppercase()).collect();
+            target_dlls.sort();
+            target_dlls.dedup();
+
+            dump.push_field("Forwarded", format!("{} to {}", forwarded, target_dlls.join(", ")), None);
+        }
+
+        for entry in self.entries.iter() {
+            let symbol = match entry.symbol {
+                Some(ref symbol) if entry.name.as_ref() != Some(symbol) => format!(" ({})", label_name(symbol)),
+                _ => String::new(),
+            };
+
+            match entry.forwarder {
+                Some(ref forwarder) => dump.push_field("", format!("{:>5}  {:<10}  {} -> {}", entry.ordinal, "forwarder", display_name(&entry.display_name()), forwarder), None),
+                None => dump.push_field("", format!("{:>5}  {:#010x}  {}{}", entry.ordinal, entry.rva, display_name(&entry.display_name()), symbol), None),
+            }
+        }
+
+        return dump;
+    }
 }
 
 /*
@@ -1582,6 +2369,10 @@ pub enum DebugType {
     Borland = 9, // Reserved for Borland.
     Reserved10 = 10, // Reserved.
     CLSid = 11, // Reserved.
+    VcFeature = 12, // Number of objects built with the security features of the compiler (/GS, /sdl, guardN).
+    Pogo = 13, // Section contributions of the profile guided optimization data.
+    Iltcg = 14, // Incremental link-time code generation.
+    Mpx = 15, // Intel MPX.
     Repro = 16, // PE determinism or reproducibility.
     EmbeddedAtPtrd = 17, // Debugging information is embedded in the PE file at location specified by PointerToRawData.
     StoresCryptoHashCnt = 19, // Stores crypto hash for the content of the symbol file used to build the PE/COFF file.
@@ -1603,6 +2394,10 @@ impl From<u32> for DebugType {
             v if v == DebugType::Borland as u32 => DebugType::Borland,
             v if v == DebugType::Reserved10 as u32 => DebugType::Reserved10,
             v if v == DebugType::CLSid as u32 => DebugType::CLSid,
+            v if v == DebugType::VcFeature as u32 => DebugType::VcFeature,
+            v if v == DebugType::Pogo as u32 => DebugType::Pogo,
+            v if v == DebugType::Iltcg as u32 => DebugType::Iltcg,
+            v if v == DebugType::Mpx as u32 => DebugType::Mpx,
             v if v == DebugType::Repro as u32 => DebugType::Repro,
             v if v == DebugType::EmbeddedAtPtrd as u32 => DebugType::EmbeddedAtPtrd,
             v if v == DebugType::StoresCryptoHashCnt as u32 => DebugType::StoresCryptoHashCnt,
@@ -1637,18 +2432,18 @@ impl DebugDirectory {
     }
 
     pub fn from_parser(
-        cursor: &mut io::Cursor<&Vec<u8>>,
+        cursor: &mut PEReader,
     ) -> Result<DebugDirectory, Box<dyn std::error::Error>> {
         let mut dd = DebugDirectory::new();
 
-        dd.characteristics = cursor.read_u32::<LittleEndian>()?;
-        dd.time_date_stamp = cursor.read_u32::<LittleEndian>()?;
-        dd.major_version = cursor.read_u16::<LittleEndian>()?;
-        dd.minor_version = cursor.read_u16::<LittleEndian>()?;
-        dd.debug_type = cursor.read_u32::<LittleEndian>()?;
-        dd.size_of_data = cursor.read_u32::<LittleEndian>()?;
-        dd.address_of_raw_data = cursor.read_u32::<LittleEndian>()?;
-        dd.pointer_to_raw_data = cursor.read_u32::<LittleEndian>()?;
+        dd.characteristics = cursor.read_u32()?;
+        dd.time_date_stamp = cursor.read_u32()?;
+        dd.major_version = cursor.read_u16()?;
+        dd.minor_version = cursor.read_u16()?;
+        dd.debug_type = cursor.read_u32()?;
+        dd.size_of_data = cursor.read_u32()?;
+        dd.address_of_raw_data = cursor.read_u32()?;
+        dd.pointer_to_raw_data = cursor.read_u32()?;
 
         return Ok(dd);
     }
@@ -1658,7 +2453,7 @@ impl DebugDirectory {
         let mut dump = Dump::new("Debug Directory");
 
         dump.push_field("Characteristics", format!("{:#x}", self.characteristics), None);
-        dump.push_field("TimeDateStamp", format!("{:#x} ({})", self.time_date_stamp, format_u32_as_ctime(self.time_date_stamp)), None);
+        dump.push_field("TimeDateStamp", format_timestamp(self.time_date_stamp), None);
         dump.push_field("MajorVersion", format!("{:#x}", self.major_version), None);
         dump.push_field("MinorVersion", format!("{:#x}", self.minor_version), None);
         dump.push_field("DebugType", format!("{:#x} ({})",self.debug_type,DebugType::from(self.debug_type).as_static_str()), None);
@@ -1671,27 +2466,495 @@ impl DebugDirectory {
 }
 
 /*
- * Exception Table
- * https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#the-pdata-section
+ * CodeView debug data, the RSDS record identifies the PDB matching the executable
  */
 
-/// 32-bit MIPS images
-#[derive(Debug, Clone, Copy, Default)]
-pub struct Mips32ExcFunctionEntry {
-    pub begin_address: u32,
-    pub end_address: u32,
-    pub exception_handler: u32,
-    pub handler_data: u32,
-    pub prolog_end_address: u32,
+/* "RSDS" signature, GUID and age precede the PDB path in CodeView 7.0 debug data */
+pub const CODEVIEW_RSDS_SIGNATURE: [u8; 4] = *b"RSDS";
+pub const CODEVIEW_RSDS_PATH_OFFSET: usize = 24;
+
+/* "NB10" signature, offset, timestamp and age precede the PDB path in the PDB 2.0 debug data of the older linkers */
+pub const CODEVIEW_NB10_SIGNATURE: [u8; 4] = *b"NB10";
+pub const CODEVIEW_NB10_PATH_OFFSET: usize = 16;
+
+#[derive(Default, Clone, Debug)]
+pub struct CodeViewRecord {
+    /// RSDS or NB10
+    pub signature: [u8; 4],
+    /// Zero for NB10, the PDB is identified by its timestamp
+    pub guid: [u8; 16],
+    /// NB10 only, written in the PDB too
+    pub timestamp: u32,
+    pub age: u32,
+    pub pdb_path: String,
 }
 
-impl Mips32ExcFunctionEntry {
-    #[rustfmt::skip]
-    pub fn dump(&self) -> Dump {
-        let mut dump = Dump::new("Function Entry");
+impl CodeViewRecord {
+    /// The cursor spans the debug data, None for the other CodeView formats (NB09, NB11 embed the debug information)
+    pub fn from_parser(
+        cursor: &mut PEReader,
+    ) -> Result<Option<CodeViewRecord>, Box<dyn std::error::Error>> {
+        let mut record = CodeViewRecord::default();
 
-        dump.push_field("BeginAddress", format!("{:#x}", self.begin_address), None);
-        dump.push_field("EndAddress", format!("{:#x}", self.end_address), None);
+        record.signature = cursor.read_n::<4>()?;
+
+        match record.signature {
+            CODEVIEW_RSDS_SIGNATURE => {
+                record.guid = cursor.read_n::<16>()?;
+                record.age = cursor.read_u32()?;
+            }
+            CODEVIEW_NB10_SIGNATURE => {
+                /* Offset of the debug information, always zero as it lives in the PDB */
+                cursor.read_u32()?;
+                record.timestamp = cursor.read_u32()?;
+                record.age = cursor.read_u32()?;
+            }
+            _ => return Ok(None),
+        }
+
+        /* The path is NUL terminated, unless the debug data is cut */
+        let path = cursor.read_bytes(cursor.remaining() as usize)?;
+        let path = path.split(|b| *b == 0).next().unwrap_or_default();
+
+        record.pdb_path = String::from_utf8_lossy(path).to_string();
+
+        return Ok(Some(record));
+    }
+
+    pub fn is_rsds(&self) -> bool {
+        return self.signature == CODEVIEW_RSDS_SIGNATURE;
+    }
+
+    /// Registry format, the first three fields are stored little endian
+    pub fn guid_string(&self) -> String {
+        let g = &self.guid;
+
+        return format!(
+            "{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{}",
+            u32::from_le_bytes([g[0], g[1], g[2], g[3]]),
+            u16::from_le_bytes([g[4], g[5]]),
+            u16::from_le_bytes([g[6], g[7]]),
+            g[8],
+            g[9],
+            g[10..].iter().map(|b| format!("{:02X}", b)).collect::<String>()
+        );
+    }
+
+    /// Directory of the PDB on a symbol server, the GUID without dashes (the timestamp for NB10) followed by the age in hexadecimal
+    pub fn symbol_server_key(&self) -> String {
+        match self.is_rsds() {
+            true => return format!("{}{:X}", self.guid_string().replace('-', ""), self.age),
+            false => return format!("{:08X}{:X}", self.timestamp, self.age),
+        }
+    }
+
+    /// File name of the PDB, the path is the one of the machine that built the executable
+    pub fn pdb_name(&self) -> String {
+        return self.pdb_path.rsplit(['\\', '/']).next().unwrap_or_default().to_string();
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("CodeView");
+
+        dump.push_field("Signature", String::from_utf8_lossy(&self.signature).to_string(), None);
+
+        match self.is_rsds() {
+            true => dump.push_field("Guid", self.guid_string(), None),
+            false => dump.push_field("Timestamp", format_timestamp(self.timestamp), None),
+        }
+
+        dump.push_field("Age", format!("{}", self.age), None);
+        dump.push_field("PdbPath", self.pdb_path.clone(), None);
+        dump.push_field("SymbolServerKey", format!("{}/{}", self.pdb_name(), self.symbol_server_key()), None);
+
+        return dump;
+    }
+}
+
+/*
+ * Debug data of the POGO, REPRO and VC_FEATURE entries
+ */
+
+/* Guards against a corrupted POGO entry */
+const MAX_POGO_ENTRIES: usize = 0x10000;
+
+/// Section contribution of the profile guided optimization data, named after the COFF section it comes from (.text$mn, .rdata$zzzdbg...)
+#[derive(Debug, Clone, Default)]
+pub struct PogoEntry {
+    pub rva: u32,
+    pub size: u32,
+    pub name: String,
+}
+
+/// Number of objects built with each of the security features of the compiler
+#[derive(Debug, Clone, Default)]
+pub struct VcFeature {
+    pub pre_vc11: u32,
+    pub c_cpp: u32,
+    pub gs: u32,
+    pub sdl: u32,
+    pub guard_n: u32,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum ExDllCharacteristicsFlags {
+    CetCompat = 0x01,                          // Image is compatible with the CET shadow stack.
+    CetCompatStrictMode = 0x02,                // The shadow stack is enforced in strict mode.
+    CetSetContextIpValidationRelaxedMode = 0x04, // Relaxed validation of the instruction pointer set by SetThreadContext.
+    CetDynamicApisAllowInProc = 0x08,          // The dynamic code APIs can only be used out of process.
+    CetReserved1 = 0x10,                       // Reserved for CET policy downlevel only.
+    CetReserved2 = 0x20,                       // Reserved for CET policy downlevel only.
+    ForwardCfiCompat = 0x40,                   // Image is compatible with the forward control flow integrity.
+    HotpatchCompatible = 0x80,                 // Image can be hotpatched.
+}
+
+impl FlagEnum for ExDllCharacteristicsFlags {
+    const PREFIX: &'static str = "IMAGE_DLLCHARACTERISTICS_EX_";
+
+    fn bits(self) -> u64 {
+        return self as u64;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum DebugEntryData {
+    CodeView(CodeViewRecord),
+    Pogo {
+        /// "LTCG", "PGI", "PGO" or "PGU"
+        signature: String,
+        entries: Vec<PogoEntry>,
+    },
+    /// Hash of the build inputs, the time stamps of the image hold its first bytes
+    Repro(Vec<u8>),
+    VcFeature(VcFeature),
+    /// Extended DLL characteristics, see ExDllCharacteristicsFlags
+    ExDllCharacteristics(u32),
+}
+
+impl DebugEntryData {
+    /// The cursor spans the debug data of the entry, None for the types without a decoder
+    pub fn from_parser(
+        cursor: &mut PEReader,
+        debug_type: DebugType,
+    ) -> Result<Option<DebugEntryData>, Box<dyn std::error::Error>> {
+        match debug_type {
+            DebugType::CodeView => return Ok(CodeViewRecord::from_parser(cursor)?.map(DebugEntryData::CodeView)),
+            DebugType::Pogo => {
+                let signature = cursor.read_u32()?.to_be_bytes();
+                let signature = String::from_utf8_lossy(&signature).trim_end_matches('\0').to_string();
+
+                let mut entries = Vec::new();
+
+                while cursor.remaining() >= 8 && entries.len() < MAX_POGO_ENTRIES {
+                    let rva = cursor.read_u32()?;
+                    let size = cursor.read_u32()?;
+
+                    let mut name = Vec::new();
+
+                    while cursor.remaining() > 0 {
+                        match cursor.read_u8()? {
+                            0 => break,
+                            b => name.push(b),
+                        }
+                    }
+
+                    /* Entries are 4-byte aligned, the name is padded with zeroes */
+                    let position = cursor.position();
+                    cursor.set_position(((position + 3) & !3).min(cursor.length()))?;
+
+                    entries.push(PogoEntry { rva, size, name: decode_name(&name).0 });
+                }
+
+                return Ok(Some(DebugEntryData::Pogo { signature, entries }));
+            }
+            /* An empty entry means the hash only lives in the time stamps */
+            DebugType::Repro => {
+                if cursor.remaining() < 4 {
+                    return Ok(Some(DebugEntryData::Repro(Vec::new())));
+                }
+
+                let length = (cursor.read_u32()? as u64).min(cursor.remaining());
+
+                return Ok(Some(DebugEntryData::Repro(cursor.read_bytes(length as usize)?)));
+            }
+            DebugType::VcFeature => {
+                return Ok(Some(DebugEntryData::VcFeature(VcFeature {
+                    pre_vc11: cursor.read_u32()?,
+                    c_cpp: cursor.read_u32()?,
+                    gs: cursor.read_u32()?,
+                    sdl: cursor.read_u32()?,
+                    guard_n: cursor.read_u32()?,
+                })));
+            }
+            DebugType::ExDLLCharacteristics => return Ok(Some(DebugEntryData::ExDllCharacteristics(cursor.read_u32()?))),
+            _ => return Ok(None),
+        }
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        match self {
+            DebugEntryData::CodeView(codeview) => return codeview.dump(),
+            DebugEntryData::Pogo { signature, entries } => {
+                let mut dump = Dump::new_from_string(format!("POGO ({} entries)", entries.len()));
+
+                dump.push_field("Signature", signature.clone(), None);
+
+                for entry in entries.iter() {
+                    dump.push_field("", format!("{:#010x} {:#010x} {}", entry.rva, entry.size, entry.name), None);
+                }
+
+                return dump;
+            }
+            DebugEntryData::Repro(hash) => {
+                let mut dump = Dump::new("Repro");
+
+                match hash.is_empty() {
+                    true => dump.push_field("Hash", String::from("None (the time stamps hold the hash)"), None),
+                    false => dump.push_field("Hash", hash.iter().map(|b| format!("{:02x}", b)).collect(), None),
+                }
+
+                return dump;
+            }
+            DebugEntryData::VcFeature(feature) => {
+                let mut dump = Dump::new("VC Feature");
+
+                dump.push_field("PreVC11", format!("{}", feature.pre_vc11), None);
+                dump.push_field("C/C++", format!("{}", feature.c_cpp), None);
+                dump.push_field("/GS", format!("{}", feature.gs), None);
+                dump.push_field("/sdl", format!("{}", feature.sdl), None);
+                dump.push_field("guardN", format!("{}", feature.guard_n), None);
+
+                return dump;
+            }
+            DebugEntryData::ExDllCharacteristics(characteristics) => {
+                let mut dump = Dump::new("Extended DLL Characteristics");
+
+                dump.push_field("Characteristics", format_flags::<ExDllCharacteristicsFlags>(*characteristics as u64), None);
+
+                return dump;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DebugEntry {
+    pub directory: DebugDirectory,
+    pub data: Option<DebugEntryData>,
+}
+
+/*
+ * Base Relocation Table
+ * https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#the-reloc-section-image-only
+ */
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum BaseRelocationType {
+    Absolute = 0,     // The base relocation is skipped, used to pad a block.
+    High = 1,         // Adds the high 16 bits of the difference to the 16-bit field at offset.
+    Low = 2,          // Adds the low 16 bits of the difference to the 16-bit field at offset.
+    HighLow = 3,      // Applies all 32 bits of the difference to the 32-bit field at offset.
+    HighAdj = 4,      // High 16 bits adjusted with the low 16 bits stored in the next slot.
+    ArmMov32 = 5,     // MIPS jump address, ARM MOVW/MOVT pair or RISC-V high 20 bits depending on the machine.
+    Reserved = 6,     // Reserved, must be zero.
+    ThumbMov32 = 7,   // Thumb MOVW/MOVT pair or RISC-V low 12 bits (I-format) depending on the machine.
+    RiscvLow12S = 8,  // RISC-V low 12 bits (S-format) or LoongArch address depending on the machine.
+    MipsJmpAddr16 = 9, // MIPS16 jump instruction.
+    Dir64 = 10,       // Applies the difference to the 64-bit field at offset.
+    Unknown = 0xff,
+}
+
+impl From<u8> for BaseRelocationType {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => BaseRelocationType::Absolute,
+            1 => BaseRelocationType::High,
+            2 => BaseRelocationType::Low,
+            3 => BaseRelocationType::HighLow,
+            4 => BaseRelocationType::HighAdj,
+            5 => BaseRelocationType::ArmMov32,
+            6 => BaseRelocationType::Reserved,
+            7 => BaseRelocationType::ThumbMov32,
+            8 => BaseRelocationType::RiscvLow12S,
+            9 => BaseRelocationType::MipsJmpAddr16,
+            10 => BaseRelocationType::Dir64,
+            _ => BaseRelocationType::Unknown,
+        }
+    }
+}
+
+impl BaseRelocationType {
+    pub fn as_static_str(&self) -> &'static str {
+        return self.into();
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BaseRelocationEntry {
+    /// High 4 bits of the entry
+    pub relocation_type: u8,
+    /// Low 12 bits of the entry, from the page RVA (or the raw 16 bits following a HIGHADJ entry)
+    pub offset: u16,
+}
+
+impl BaseRelocationEntry {
+    pub fn get_relocation_type(&self) -> BaseRelocationType {
+        return BaseRelocationType::from(self.relocation_type);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BaseRelocationBlock {
+    pub page_rva: u32,
+    pub block_size: u32,
+    pub entries: Vec<BaseRelocationEntry>,
+}
+
+impl BaseRelocationBlock {
+    pub fn from_parser(
+        cursor: &mut PEReader,
+    ) -> Result<BaseRelocationBlock, Box<dyn std::error::Error>> {
+        let mut block = BaseRelocationBlock::default();
+
+        block.page_rva = cursor.read_u32()?;
+        block.block_size = cursor.read_u32()?;
+
+        for _ in 0..(block.block_size.saturating_sub(8) / 2) {
+            let entry = cursor.read_u16()?;
+
+            block.entries.push(BaseRelocationEntry {
+                relocation_type: (entry >> 12) as u8,
+                offset: entry & 0xfff,
+            });
+        }
+
+        return Ok(block);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BaseRelocationTable {
+    pub blocks: Vec<BaseRelocationBlock>,
+}
+
+impl BaseRelocationTable {
+    pub fn from_parser(
+        cursor: &mut PEReader,
+        size: u32,
+    ) -> Result<BaseRelocationTable, Box<dyn std::error::Error>> {
+        let mut table = BaseRelocationTable::default();
+
+        let position = cursor.position();
+        let mut reader = cursor.sub_reader(position, size as u64)?;
+
+        while reader.remaining() >= 8 {
+            let block_start = reader.position();
+
+            /* The directory size bounds the reads, a block cut by it ends the table */
+            let Ok(mut block) = BaseRelocationBlock::from_parser(&mut reader) else {
+                break;
+            };
+
+            /* A block smaller than its header would never advance */
+            if block.block_size < 8 {
+                break;
+            }
+
+            /* The 16 bits following a HIGHADJ entry are its parameter, not an entry */
+            for i in 1..block.entries.len() {
+                if block.entries[i - 1].get_relocation_type() == BaseRelocationType::HighAdj {
+                    let raw = ((block.entries[i].relocation_type as u16) << 12) | block.entries[i].offset;
+                    block.entries[i] = BaseRelocationEntry { relocation_type: BaseRelocationType::Absolute as u8, offset: raw };
+                }
+            }
+
+            let block_end = block_start + block.block_size as u64;
+
+            table.blocks.push(block);
+
+            if reader.set_position(block_end).is_err() {
+                break;
+            }
+        }
+
+        return Ok(table);
+    }
+
+    pub fn number_of_relocations(&self) -> usize {
+        return self
+            .blocks
+            .iter()
+            .map(|b| b.entries.iter().filter(|e| e.get_relocation_type() != BaseRelocationType::Absolute).count())
+            .sum();
+    }
+
+    pub fn dump(&self, pe: &PE) -> Dump {
+        let mut dump = Dump::new_from_string(format!("Base Relocations ({} blocks, {} relocations)", self.blocks.len(), self.number_of_relocations()));
+
+        dump.push_field("Types", format_relocation_counts(self.blocks.iter().flat_map(|b| b.entries.iter())), None);
+
+        for block in self.blocks.iter() {
+            let section = pe.get_section_for_rva(block.page_rva).map(|s| s.header.name.as_str()).unwrap_or("no section");
+
+            let mut block_dump = Dump::new_from_string(format!("Page {:#x} ({}, {} entries)", block.page_rva, section, block.entries.len()));
+
+            block_dump.push_field("Types", format_relocation_counts(block.entries.iter()), None);
+
+            /* The ABSOLUTE entries pad the blocks and hold the HIGHADJ parameters, they relocate nothing */
+            for entry in block.entries.iter().filter(|e| e.get_relocation_type() != BaseRelocationType::Absolute) {
+                block_dump.push_field("", format!("{:#010x}  {}", block.page_rva + entry.offset as u32, entry.get_relocation_type().as_static_str()), None);
+            }
+
+            dump.push_child(block_dump);
+        }
+
+        return dump;
+    }
+}
+
+/// "DIR64: 12, ABSOLUTE: 1", in the order of the relocation types
+fn format_relocation_counts<'a>(entries: impl Iterator<Item = &'a BaseRelocationEntry>) -> String {
+    let mut counts: BTreeMap<u8, usize> = BTreeMap::new();
+
+    for entry in entries {
+        *counts.entry(entry.relocation_type).or_default() += 1;
+    }
+
+    return counts
+        .iter()
+        .map(|(relocation_type, count)| format!("{}: {}", BaseRelocationType::from(*relocation_type).as_static_str(), count))
+        .collect::<Vec<String>>()
+        .join(", ");
+}
+
+/*
+ * Exception Table
+ * https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#the-pdata-section
+ */
+
+/// 32-bit MIPS images
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Mips32ExcFunctionEntry {
+    pub begin_address: u32,
+    pub end_address: u32,
+    pub exception_handler: u32,
+    pub handler_data: u32,
+    pub prolog_end_address: u32,
+}
+
+impl Mips32ExcFunctionEntry {
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Function Entry");
+
+        dump.push_field("BeginAddress", format!("{:#x}", self.begin_address), None);
+        dump.push_field("EndAddress", format!("{:#x}", self.end_address), None);
         dump.push_field("ExceptionHandler", format!("{:#x}", self.exception_handler), None);
         dump.push_field("HandlerData", format!("{:#x}", self.handler_data), None);
         dump.push_field("PrologEndAddress", format!("{:#x}", self.prolog_end_address), None);
@@ -1700,23 +2963,211 @@ impl Mips32ExcFunctionEntry {
     }
 }
 
+/*
+ * x64 unwind information, pointed to by the function entries of the exception table
+ * https://learn.microsoft.com/en-us/cpp/build/exception-handling-x64
+ */
+
+const X64_REGISTERS: [&str; 16] = ["rax", "rcx", "rdx", "rbx", "rsp", "rbp", "rsi", "rdi", "r8", "r9", "r10", "r11", "r12", "r13", "r14", "r15"];
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum UnwindFlags {
+    Ehandler = 0x1,  // The function has an exception handler, called when looking for the functions that handle the exception.
+    Uhandler = 0x2,  // The function has a termination handler, called when unwinding an exception.
+    Chaininfo = 0x4, // The unwind info is the continuation of the one of a previous function entry.
+}
+
+impl FlagEnum for UnwindFlags {
+    fn bits(self) -> u64 {
+        return self as u64;
+    }
+}
+
+impl UnwindFlags {
+    pub fn flags_as_string(unwind_flags: u8) -> String {
+        return decode_flags::<UnwindFlags>(unwind_flags as u64);
+    }
+}
+
+/// Operation of the prolog, the codes are stored in the reverse order of the prolog instructions
+#[derive(Debug, Clone, Copy)]
+pub enum UnwindOperation {
+    PushNonvol(u8),
+    AllocLarge(u32),
+    AllocSmall(u32),
+    SetFpreg,
+    SaveNonvol(u8, u32),
+    SaveNonvolFar(u8, u32),
+    /// Version 2 only, describes the epilogs
+    Epilog(u8, u8),
+    SaveXmm128(u8, u32),
+    SaveXmm128Far(u8, u32),
+    /// The frame pushed by the processor for an interrupt or an exception, with an error code or not
+    PushMachframe(bool),
+    Unknown(u8, u8),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct UnwindCode {
+    /// Offset of the end of the prolog instruction from the start of the prolog
+    pub code_offset: u8,
+    pub operation: UnwindOperation,
+}
+
+impl UnwindCode {
+    pub fn dump_line(&self) -> String {
+        let operation = match self.operation {
+            UnwindOperation::PushNonvol(register) => format!("PUSH_NONVOL {}", X64_REGISTERS[register as usize]),
+            UnwindOperation::AllocLarge(size) => format!("ALLOC_LARGE {:#x}", size),
+            UnwindOperation::AllocSmall(size) => format!("ALLOC_SMALL {:#x}", size),
+            UnwindOperation::SetFpreg => String::from("SET_FPREG"),
+            UnwindOperation::SaveNonvol(register, offset) => format!("SAVE_NONVOL {}, [rsp + {:#x}]", X64_REGISTERS[register as usize], offset),
+            UnwindOperation::SaveNonvolFar(register, offset) => format!("SAVE_NONVOL_FAR {}, [rsp + {:#x}]", X64_REGISTERS[register as usize], offset),
+            UnwindOperation::Epilog(size, flags) => format!("EPILOG size {:#x}, flags {:#x}", size, flags),
+            UnwindOperation::SaveXmm128(register, offset) => format!("SAVE_XMM128 xmm{}, [rsp + {:#x}]", register, offset),
+            UnwindOperation::SaveXmm128Far(register, offset) => format!("SAVE_XMM128_FAR xmm{}, [rsp + {:#x}]", register, offset),
+            UnwindOperation::PushMachframe(error_code) => format!("PUSH_MACHFRAME{}", if error_code { " with error code" } else { "" }),
+            UnwindOperation::Unknown(code, info) => format!("UNKNOWN {} (info {})", code, info),
+        };
+
+        return format!("{:#04x}: {}", self.code_offset, operation);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct UnwindInfo {
+    pub version: u8,
+    pub flags: u8,
+    pub size_of_prolog: u8,
+    pub count_of_codes: u8,
+    /// Zero when the function uses no frame pointer
+    pub frame_register: u8,
+    /// Scaled by 16, the offset of the frame pointer from rsp
+    pub frame_offset: u8,
+    pub codes: Vec<UnwindCode>,
+    /// EHANDLER or UHANDLER, the language specific handler (__C_specific_handler, __CxxFrameHandler3...)
+    pub exception_handler: Option<u32>,
+    /// CHAININFO, the function entry whose unwind info continues this one
+    pub chained_entry: Option<X64ExcFunctionEntry>,
+}
+
+impl UnwindInfo {
+    pub fn from_parser(
+        cursor: &mut PEReader,
+    ) -> Result<UnwindInfo, Box<dyn std::error::Error>> {
+        let mut info = UnwindInfo::default();
+
+        let version_and_flags = cursor.read_u8()?;
+        info.version = version_and_flags & 0x7;
+        info.flags = version_and_flags >> 3;
+        info.size_of_prolog = cursor.read_u8()?;
+        info.count_of_codes = cursor.read_u8()?;
+
+        let frame = cursor.read_u8()?;
+        info.frame_register = frame & 0xf;
+        info.frame_offset = frame >> 4;
+
+        let mut slots = Vec::with_capacity(info.count_of_codes as usize);
+
+        for _ in 0..info.count_of_codes {
+            slots.push(cursor.read_u16()?);
+        }
+
+        /* The codes array is padded to an even number of slots */
+        if info.count_of_codes % 2 == 1 {
+            cursor.read_u16()?;
+        }
+
+        let mut i = 0;
+
+        while i < slots.len() {
+            let code_offset = (slots[i] & 0xff) as u8;
+            let code = ((slots[i] >> 8) & 0xf) as u8;
+            let op_info = (slots[i] >> 12) as u8;
+
+            let slot = |n: usize| -> u32 { slots.get(i + n).copied().unwrap_or(0) as u32 };
+            let far = |n: usize| -> u32 { slot(n) | (slot(n + 1) << 16) };
+
+            let (operation, used) = match code {
+                0 => (UnwindOperation::PushNonvol(op_info), 1),
+                1 if op_info == 0 => (UnwindOperation::AllocLarge(slot(1) * 8), 2),
+                1 => (UnwindOperation::AllocLarge(far(1)), 3),
+                2 => (UnwindOperation::AllocSmall(op_info as u32 * 8 + 8), 1),
+                3 => (UnwindOperation::SetFpreg, 1),
+                4 => (UnwindOperation::SaveNonvol(op_info, slot(1) * 8), 2),
+                5 => (UnwindOperation::SaveNonvolFar(op_info, far(1)), 3),
+                6 if info.version >= 2 => (UnwindOperation::Epilog(code_offset, op_info), 2),
+                8 => (UnwindOperation::SaveXmm128(op_info, slot(1) * 16), 2),
+                9 => (UnwindOperation::SaveXmm128Far(op_info, far(1)), 3),
+                10 => (UnwindOperation::PushMachframe(op_info != 0), 1),
+                _ => (UnwindOperation::Unknown(code, op_info), 1),
+            };
+
+            info.codes.push(UnwindCode { code_offset, operation });
+
+            i += used;
+        }
+
+        if (info.flags & UnwindFlags::Chaininfo as u8) != 0 {
+            info.chained_entry = Some(X64ExcFunctionEntry::from_parser(cursor)?);
+        } else if (info.flags & (UnwindFlags::Ehandler as u8 | UnwindFlags::Uhandler as u8)) != 0 {
+            info.exception_handler = Some(cursor.read_u32()?);
+        }
+
+        return Ok(info);
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Unwind Info");
+
+        dump.push_field("Version", format!("{}", self.version), None);
+        dump.push_field("Flags", format_flags::<UnwindFlags>(self.flags as u64), None);
+        dump.push_field("SizeOfProlog", format!("{:#x}", self.size_of_prolog), None);
+        dump.push_field("CountOfCodes", format!("{}", self.count_of_codes), None);
+
+        match self.frame_register {
+            0 => dump.push_field("FrameRegister", String::from("none"), None),
+            register => dump.push_field("FrameRegister", format!("{} (rsp + {:#x})", X64_REGISTERS[register as usize], self.frame_offset as u32 * 16), None),
+        }
+
+        if let Some(handler) = self.exception_handler {
+            dump.push_field("ExceptionHandler", format!("{:#x}", handler), None);
+        }
+
+        if let Some(ref chained) = self.chained_entry {
+            dump.push_field("ChainedFunction", format!("{:#x}-{:#x} (unwind info {:#x})", chained.begin_address, chained.end_address, chained.unwind_information), None);
+        }
+
+        for code in self.codes.iter() {
+            dump.push_field("", code.dump_line(), None);
+        }
+
+        return dump;
+    }
+}
+
 /// x64 and Itanium platforms
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct X64ExcFunctionEntry {
     pub begin_address: u32,
     pub end_address: u32,
     pub unwind_information: u32,
+    /// Decoded for x64 only, see parse_exception_table
+    pub unwind_info: Option<Box<UnwindInfo>>,
 }
 
 impl X64ExcFunctionEntry {
     pub fn from_parser(
-        cursor: &mut io::Cursor<&Vec<u8>>,
+        cursor: &mut PEReader,
     ) -> Result<X64ExcFunctionEntry, Box<dyn std::error::Error>> {
         let mut entry = X64ExcFunctionEntry::default();
 
-        entry.begin_address = cursor.read_u32::<LittleEndian>()?;
-        entry.end_address = cursor.read_u32::<LittleEndian>()?;
-        entry.unwind_information = cursor.read_u32::<LittleEndian>()?;
+        entry.begin_address = cursor.read_u32()?;
+        entry.end_address = cursor.read_u32()?;
+        entry.unwind_information = cursor.read_u32()?;
 
         return Ok(entry);
     }
@@ -1729,6 +3180,10 @@ impl X64ExcFunctionEntry {
         dump.push_field("EndAddress", format!("{:#x}", self.end_address), None);
         dump.push_field("UnwindInformation", format!("{:#x}", self.unwind_information), None);
 
+        if let Some(ref unwind_info) = self.unwind_info {
+            dump.push_child(unwind_info.dump());
+        }
+
         return dump;
     }
 }
@@ -1743,96 +3198,1716 @@ pub struct OtherExcFunctionEntry {
     pub flag_exception: bool,
 }
 
-impl OtherExcFunctionEntry {
-    #[rustfmt::skip]
-    pub fn dump(&self) -> Dump {
-        let mut dump = Dump::new("Function Entry");
+impl OtherExcFunctionEntry {
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Function Entry");
+
+        dump.push_field("BeginAddress", format!("{:#x}", self.begin_address), None);
+        dump.push_field("PrologLength", format!("{:#x}", self.prolog_length), None);
+        dump.push_field("FunctionLength", format!("{:#x}", self.function_length), None);
+        dump.push_field("32-bit Flag", format!("{}", self.flag_32bit), None);
+        dump.push_field("Exception Flag", format!("{}", self.flag_exception), None);
+
+        return dump;
+    }
+}
+
+/// ARM64, ARM64EC and ARM64X platforms, the unwind data is either packed in the entry or the RVA of an .xdata record
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Arm64ExcFunctionEntry {
+    pub begin_address: u32,
+    pub unwind_data: u32,
+}
+
+impl Arm64ExcFunctionEntry {
+    pub fn from_parser(
+        cursor: &mut PEReader,
+    ) -> Result<Arm64ExcFunctionEntry, Box<dyn std::error::Error>> {
+        let mut entry = Arm64ExcFunctionEntry::default();
+
+        entry.begin_address = cursor.read_u32()?;
+        entry.unwind_data = cursor.read_u32()?;
+
+        return Ok(entry);
+    }
+
+    /// 0 for an .xdata RVA, 1 for packed unwind data, 2 for packed unwind data of a function fragment without prolog
+    pub fn flag(&self) -> u32 {
+        return self.unwind_data & 0x3;
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Function Entry");
+
+        dump.push_field("BeginAddress", format!("{:#x}", self.begin_address), None);
+
+        let packed = self.unwind_data;
+
+        match self.flag() {
+            0 => dump.push_field("UnwindData", format!("{:#x}", packed), None),
+            flag => {
+                let form = match flag {
+                    1 => "packed",
+                    2 => "packed fragment",
+                    _ => "reserved",
+                };
+
+                dump.push_field("Flag", format!("{} ({})", flag, form), None);
+                dump.push_field("FunctionLength", format!("{:#x}", ((packed >> 2) & 0x7ff) * 4), None);
+                dump.push_field("RegF", format!("{}", (packed >> 13) & 0x7), None);
+                dump.push_field("RegI", format!("{}", (packed >> 16) & 0xf), None);
+                dump.push_field("H", format!("{}", (packed >> 20) & 0x1), None);
+                dump.push_field("CR", format!("{}", (packed >> 21) & 0x3), None);
+                dump.push_field("FrameSize", format!("{:#x}", ((packed >> 23) & 0x1ff) * 16), None);
+            }
+        }
+
+        return dump;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ExcFunctionEntry {
+    Mips32(Mips32ExcFunctionEntry),
+    X64(X64ExcFunctionEntry),
+    Arm64(Arm64ExcFunctionEntry),
+    Other(OtherExcFunctionEntry),
+}
+
+impl Default for ExcFunctionEntry {
+    fn default() -> Self {
+        return ExcFunctionEntry::X64(X64ExcFunctionEntry::default());
+    }
+}
+
+impl ExcFunctionEntry {
+    pub fn from_parser(
+        cursor: &mut PEReader,
+        machine_type: MachineType,
+    ) -> Result<ExcFunctionEntry, Box<dyn std::error::Error>> {
+        match machine_type {
+            MachineType::AMD64 | MachineType::I386 => Ok(ExcFunctionEntry::X64(
+                X64ExcFunctionEntry::from_parser(cursor)?,
+            )),
+            MachineType::ARM64 | MachineType::ARM64EC | MachineType::ARM64X => Ok(ExcFunctionEntry::Arm64(
+                Arm64ExcFunctionEntry::from_parser(cursor)?,
+            )),
+            _ => Err("Cannot parse Exception Function Entry, unsupported platform".into()),
+            /* TODO: implement other machine types */
+        }
+    }
+
+    pub fn is_supported(machine_type: MachineType) -> bool {
+        return matches!(
+            machine_type,
+            MachineType::AMD64 | MachineType::I386 | MachineType::ARM64 | MachineType::ARM64EC | MachineType::ARM64X
+        );
+    }
+
+    /// Size of the entry in the table
+    #[allow(clippy::len_without_is_empty, reason = "an entry always has a size")]
+    pub fn len(&self) -> usize {
+        match self {
+            ExcFunctionEntry::Mips32(_) => 20,
+            ExcFunctionEntry::X64(_) => 12,
+            ExcFunctionEntry::Arm64(_) => 8,
+            ExcFunctionEntry::Other(_) => 8,
+        }
+    }
+
+    pub fn dump(&self) -> Dump {
+        match self {
+            ExcFunctionEntry::Mips32(e) => e.dump(),
+            ExcFunctionEntry::X64(e) => e.dump(),
+            ExcFunctionEntry::Arm64(e) => e.dump(),
+            ExcFunctionEntry::Other(e) => e.dump(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExceptionTable {
+    pub entries: Vec<ExcFunctionEntry>,
+    pub warnings: Vec<String>,
+}
+
+impl ExceptionTable {
+    pub fn from_parser(
+        cursor: &mut PEReader,
+        size: usize,
+        machine_type: MachineType,
+    ) -> Result<ExceptionTable, Box<dyn std::error::Error>> {
+        let mut et = ExceptionTable::default();
+
+        let mut parsed_sz = 0_usize;
+
+        /* The function entries of the other platforms are not decoded, the rest of the PE is still parsed */
+        if !ExcFunctionEntry::is_supported(machine_type) {
+            et.warnings.push(format!("Function entries of {:?} images are not decoded, {:#x} bytes skipped", machine_type, size));
+            return Ok(et);
+        }
+
+        while parsed_sz < size {
+            let entry = ExcFunctionEntry::from_parser(cursor, machine_type)?;
+            parsed_sz += entry.len();
+            et.entries.push(entry);
+        }
+
+        return Ok(et);
+    }
+
+    pub fn dump(&self) -> Dump {
+        let mut dump =
+            Dump::new(format!("Exception Table ({} entries)", self.entries.len()).as_str());
+
+        for entry in self.entries.iter() {
+            dump.push_child(entry.dump());
+        }
+
+        for warning in self.warnings.iter() {
+            dump.push_field("Warning", warning.clone(), None);
+        }
+
+        return dump;
+    }
+}
+
+/*
+ * Entry point
+ */
+
+/// Location of AddressOfEntryPoint with the anomalies of its section, and the first instructions at the entry with disasm
+#[rustfmt::skip]
+pub fn dump_entry_point(pe: &PE, disasm: bool, instructions: usize) -> Dump {
+    let mut dump = Dump::new("Entry Point");
+
+    let entry_point = pe.get_optional_header().get_address_of_entry_point();
+    let image_base = pe.get_optional_header().get_image_base();
+    let is_dll = (pe.get_nt_header().coff_header.characteristics & CharacteristicsFlag::DLL as u16) != 0;
+
+    dump.push_field("AddressOfEntryPoint", format!("{:#x}", entry_point), None);
+
+    /* DLLs without initialization have no entry point, executables always need one */
+    if entry_point == 0 {
+        match is_dll {
+            true => dump.push_field("Note", String::from("no entry point, the DLL has no initialization routine"), None),
+            false => dump.push_field("Warning", String::from("no entry point, execution starts at the image base"), None),
+        }
+
+        return dump;
+    }
+
+    dump.push_field("VirtualAddress", format!("{:#x}", image_base + entry_point as u64), None);
+
+    let location = match AddressLocation::locate_pe(pe, Some(AddressKind::Rva), entry_point as u64, u64::MAX) {
+        Ok(location) => location,
+        Err(e) => {
+            dump.push_field("Warning", e.to_string(), None);
+            return dump;
+        }
+    };
+
+    dump.push_field("Location", format!("{} + {:#x}", location.region, location.region_offset), None);
+
+    match location.offset {
+        Some(offset) => dump.push_field("FileOffset", format!("{:#x}", offset), None),
+        None => dump.push_field("FileOffset", String::from("none (zero-filled memory)"), None),
+    }
+
+    dump.push_field("Permissions", location.permissions.clone().unwrap_or_default(), None);
+
+    if let Some((name, distance)) = location.nearest_symbol {
+        match distance {
+            0 => dump.push_field("Symbol", name, None),
+            _ => dump.push_field("Symbol", format!("{} + {:#x}", name, distance), None),
+        }
+    }
+
+    match pe.get_section_for_rva(entry_point) {
+        None => dump.push_field("Warning", String::from("the entry point is inside the headers"), None),
+        Some(section) => {
+            let characteristics = section.header.characteristics;
+
+            if !section.contains_code() && (characteristics & SectionFlags::MemExecute as u32) == 0 {
+                dump.push_field("Warning", format!("the entry point is in {}, a section that is not executable", section.header.name), None);
+            }
+
+            if (characteristics & SectionFlags::MemWrite as u32) != 0 {
+                dump.push_field("Warning", format!("the entry point is in {}, a writable section", section.header.name), None);
+            }
+
+            if location.offset.is_none() {
+                dump.push_field("Warning", String::from("the entry point is past the raw data of its section, the code is written at runtime"), None);
+            }
+        }
+    }
+
+    /* Only the code backed by the raw data of a section is disassembled */
+    if let (true, Some(section), Some(_)) = (disasm, pe.get_section_for_rva(entry_point), location.offset) {
+        let code = section.raw_data().get((entry_point - section.header.virtual_address) as usize..).unwrap_or_default();
+
+        match disasm_pe_instructions(pe, code, image_base + entry_point as u64, instructions) {
+            Ok(lines) if !lines.is_empty() => dump.set_raw_data(DumpRawData::Code(lines)),
+            Ok(_) => dump.push_field("Error", String::from("No instruction could be decoded at the entry point"), None),
+            Err(e) => dump.push_field("Error", e.to_string(), None),
+        }
+    }
+
+    return dump;
+}
+
+/*
+ * TLS Directory
+ * https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#the-tls-section
+ */
+
+/* Upper bound on the number of TLS callbacks read, the array is only null-terminated */
+const MAX_TLS_CALLBACKS: usize = 256;
+
+/* Upper bound on the number of instructions of a disassembled callback, when no return ends it */
+const MAX_TLS_CALLBACK_INSTRUCTIONS: usize = 256;
+
+#[derive(Debug, Clone, Default)]
+pub struct TlsDirectory {
+    pub raw_data_start_va: u64,
+    pub raw_data_end_va: u64,
+    pub address_of_index: u64,
+    pub address_of_callbacks: u64,
+    pub size_of_zero_fill: u32,
+    pub characteristics: u32,
+    pub callbacks: Vec<u64>,
+}
+
+impl TlsDirectory {
+    pub fn from_parser(
+        cursor: &mut PEReader,
+        is_32_bits: bool,
+    ) -> Result<TlsDirectory, Box<dyn std::error::Error>> {
+        let mut tls = TlsDirectory::default();
+
+        if is_32_bits {
+            tls.raw_data_start_va = cursor.read_u32()? as u64;
+            tls.raw_data_end_va = cursor.read_u32()? as u64;
+            tls.address_of_index = cursor.read_u32()? as u64;
+            tls.address_of_callbacks = cursor.read_u32()? as u64;
+        } else {
+            tls.raw_data_start_va = cursor.read_u64()?;
+            tls.raw_data_end_va = cursor.read_u64()?;
+            tls.address_of_index = cursor.read_u64()?;
+            tls.address_of_callbacks = cursor.read_u64()?;
+        }
+
+        tls.size_of_zero_fill = cursor.read_u32()?;
+        tls.characteristics = cursor.read_u32()?;
+
+        return Ok(tls);
+    }
+
+    /// Reads the null-terminated array of callback VAs
+    pub fn parse_callbacks(
+        &mut self,
+        cursor: &mut PEReader,
+        is_32_bits: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for _ in 0..MAX_TLS_CALLBACKS {
+            let callback = match is_32_bits {
+                true => cursor.read_u32()? as u64,
+                false => cursor.read_u64()?,
+            };
+
+            if callback == 0 {
+                break;
+            }
+
+            self.callbacks.push(callback);
+        }
+
+        return Ok(());
+    }
+
+    /// Callbacks with the section holding them and their symbol, followed by their disassembly up to the first return when disasm is set
+    #[rustfmt::skip]
+    pub fn dump(&self, pe: &PE, disasm: bool) -> Dump {
+        let mut dump = Dump::new("TLS Directory");
+
+        dump.push_field("RawDataStartVA", format!("{:#x}", self.raw_data_start_va), None);
+        dump.push_field("RawDataEndVA", format!("{:#x}", self.raw_data_end_va), None);
+        dump.push_field("AddressOfIndex", format!("{:#x}", self.address_of_index), None);
+        dump.push_field("AddressOfCallbacks", format!("{:#x}", self.address_of_callbacks), None);
+        dump.push_field("SizeOfZeroFill", format!("{:#x}", self.size_of_zero_fill), None);
+        dump.push_field("Characteristics", format!("{:#x}", self.characteristics), None);
+
+        let mut callbacks_dump = Dump::new_from_string(format!("Callbacks ({})", self.callbacks.len()));
+
+        for callback in self.callbacks.iter() {
+            let location = match AddressLocation::locate_pe(pe, Some(AddressKind::VirtualAddress), *callback, u64::MAX) {
+                Ok(location) => location,
+                Err(_) => {
+                    callbacks_dump.push_field("", format!("{:#x}  outside of the image", callback), None);
+                    continue;
+                }
+            };
+
+            let symbol = match location.nearest_symbol {
+                Some((ref name, 0)) => format!("  {}", name),
+                Some((ref name, distance)) => format!("  {} + {:#x}", name, distance),
+                None => String::new(),
+            };
+
+            callbacks_dump.push_field("", format!("{:#x}  rva {:#x}  {} + {:#x} ({}){}", callback, location.rva.unwrap_or(0), location.region, location.region_offset, location.permissions.unwrap_or_default(), symbol), None);
+
+            if disasm {
+                let mut callback_dump = Dump::new_from_string(format!("Callback {:#x}", callback));
+
+                match disasm_pe_until_return(pe, *callback, MAX_TLS_CALLBACK_INSTRUCTIONS) {
+                    Ok(lines) => callback_dump.set_raw_data(DumpRawData::Code(lines)),
+                    Err(e) => callback_dump.push_field("Error", e.to_string(), None),
+                }
+
+                callbacks_dump.push_child(callback_dump);
+            }
+        }
+
+        dump.push_child(callbacks_dump);
+
+        return dump;
+    }
+}
+
+/*
+ * Load Configuration Directory
+ * https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#load-configuration-structure-image-only
+ */
+
+/* Size of the structure up to GuardEHContinuationCount, the last field read */
+const LOAD_CONFIG_SIZE_32: usize = 0xAC;
+const LOAD_CONFIG_SIZE_64: usize = 0x118;
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum GuardFlags {
+    CfInstrumented = 0x00000100,                 // Module performs control flow integrity checks using system-supplied support.
+    CfwInstrumented = 0x00000200,                // Module performs control flow and write integrity checks.
+    CfFunctionTablePresent = 0x00000400,         // Module contains valid control flow target metadata.
+    SecurityCookieUnused = 0x00000800,           // Module does not make use of the /GS security cookie.
+    ProtectDelayloadIat = 0x00001000,            // Module supports read only delay load IAT.
+    DelayloadIatInItsOwnSection = 0x00002000,    // Delayload import table in its own .didat section (with nothing else in it) that can be freely reprotected.
+    CfExportSuppressionInfoPresent = 0x00004000, // Module contains suppressed export information.
+    CfEnableExportSuppression = 0x00008000,      // Module enables suppression of exports.
+    CfLongjumpTablePresent = 0x00010000,         // Module contains longjmp target information.
+    RfInstrumented = 0x00020000,                 // Module contains return flow instrumentation and metadata.
+    RfEnable = 0x00040000,                       // Module requests that the OS enable return flow protection.
+    RfStrict = 0x00080000,                       // Module requests that the OS enable return flow protection in strict mode.
+    RetpolinePresent = 0x00100000,               // Module was built with retpoline support.
+    EhContinuationTablePresent = 0x00400000,     // Module contains EH continuation target information.
+    XfgEnabled = 0x00800000,                     // Module was built with XFG.
+    CastguardPresent = 0x01000000,               // Module has CastGuard instrumentation present.
+    MemcpyPresent = 0x02000000,                  // Module has Guarded Memcpy instrumentation present.
+}
+
+impl FlagEnum for GuardFlags {
+    fn bits(self) -> u64 {
+        return self as u64;
+    }
+}
+
+/* Number of extra bytes after each RVA of the GuardCFFunctionTable, in the upper bits of GuardFlags */
+pub const GUARD_CF_FUNCTION_TABLE_SIZE_MASK: u32 = 0xF0000000;
+pub const GUARD_CF_FUNCTION_TABLE_SIZE_SHIFT: u32 = 28;
+
+impl GuardFlags {
+    pub fn flags_as_string(guard_flags: u32) -> String {
+        return decode_flags::<GuardFlags>(guard_flags as u64);
+    }
+}
+
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum DependentLoadFlags {
+    LoadLibrarySearchDllLoadDir = 0x0100,
+    LoadLibrarySearchApplicationDir = 0x0200,
+    LoadLibrarySearchUserDirs = 0x0400,
+    LoadLibrarySearchSystem32 = 0x0800,
+    LoadLibrarySearchDefaultDirs = 0x1000,
+    LoadLibrarySearchSystem32NoForwarder = 0x4000,
+}
+
+impl FlagEnum for DependentLoadFlags {
+    fn bits(self) -> u64 {
+        return self as u64;
+    }
+}
+
+impl DependentLoadFlags {
+    pub fn flags_as_string(dependent_load_flags: u16) -> String {
+        return decode_flags::<DependentLoadFlags>(dependent_load_flags as u64);
+    }
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum GuardCFFunctionFlags {
+    FidSuppressed = 0x01,       // Call target is explicitly suppressed (do not treat it as valid for purposes of CFG)
+    ExportSuppressed = 0x02,    // Call target is export suppressed.
+    FidLangexcpthandler = 0x04, // Call target is a language exception handler.
+    FidXfg = 0x08,              // Call target supports XFG.
+}
+
+impl FlagEnum for GuardCFFunctionFlags {
+    fn bits(self) -> u64 {
+        return self as u64;
+    }
+}
+
+impl GuardCFFunctionFlags {
+    pub fn flags_as_string(function_flags: u8) -> String {
+        return decode_flags::<GuardCFFunctionFlags>(function_flags as u64);
+    }
+}
+
+/* Upper bound on the number of GuardCFFunctionTable entries read, the count is not checked by anything else */
+const MAX_GUARD_CF_FUNCTIONS: u64 = 0x100000;
+
+/* Upper bound on the number of SEHandlerTable entries read */
+const MAX_SE_HANDLERS: u64 = 0x10000;
+
+/// Entry of the GuardCFFunctionTable, a valid indirect call target
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GuardCFFunction {
+    pub rva: u32,
+    /// First of the extra bytes of the entry, when GuardFlags gives a stride
+    pub flags: u8,
+}
+
+/// Names of the functions by RVA from the pdb symbols, the exports taking precedence
+fn function_names(pe: &PE) -> HashMap<u32, String> {
+    let mut names: HashMap<u32, String> = pe.pdb_symbols.iter().map(|(rva, name)| (*rva, label_name(name))).collect();
+
+    for entry in pe.export_data.iter().flat_map(|e| e.entries.iter()).filter(|e| e.forwarder.is_none()) {
+        names.insert(entry.rva, entry.display_name());
+    }
+
+    return names;
+}
+
+/// Both variants are read into the same structure, the pointer sized fields of the 32-bit one are widened.
+/// The structure grew with the Windows versions, the fields past Size are absent and left to zero
+#[derive(Debug, Clone, Default)]
+pub struct LoadConfigDirectory {
+    pub is_32_bits: bool,
+    pub size: u32,
+    pub time_date_stamp: u32,
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub global_flags_clear: u32,
+    pub global_flags_set: u32,
+    pub critical_section_default_timeout: u32,
+    pub de_commit_free_block_threshold: u64,
+    pub de_commit_total_free_threshold: u64,
+    pub lock_prefix_table: u64,
+    pub maximum_allocation_size: u64,
+    pub virtual_memory_threshold: u64,
+    pub process_affinity_mask: u64,
+    pub process_heap_flags: u32,
+    pub csd_version: u16,
+    pub dependent_load_flags: u16,
+    pub edit_list: u64,
+    pub security_cookie: u64,
+    pub se_handler_table: u64,
+    pub se_handler_count: u64,
+    pub guard_cf_check_function_pointer: u64,
+    pub guard_cf_dispatch_function_pointer: u64,
+    pub guard_cf_function_table: u64,
+    pub guard_cf_function_count: u64,
+    pub guard_flags: u32,
+    pub code_integrity_flags: u16,
+    pub code_integrity_catalog: u16,
+    pub code_integrity_catalog_offset: u32,
+    pub guard_address_taken_iat_entry_table: u64,
+    pub guard_address_taken_iat_entry_count: u64,
+    pub guard_long_jump_target_table: u64,
+    pub guard_long_jump_target_count: u64,
+    pub dynamic_value_reloc_table: u64,
+    pub chpe_metadata_pointer: u64,
+    pub guard_rf_failure_routine: u64,
+    pub guard_rf_failure_routine_function_pointer: u64,
+    pub guard_rf_verify_stack_pointer_function_pointer: u64,
+    pub enclave_configuration_pointer: u64,
+    pub volatile_metadata_pointer: u64,
+    pub guard_eh_continuation_table: u64,
+    pub guard_eh_continuation_count: u64,
+    pub dynamic_value_reloc_table_offset: u32,
+    /// One-based index of the section holding the dynamic value relocation table, zero when given by its VA
+    pub dynamic_value_reloc_table_section: u16,
+    pub guard_cf_functions: Vec<GuardCFFunction>,
+    /// RVAs of the SafeSEH handlers, 32-bit images only
+    pub se_handlers: Vec<u32>,
+    pub dynamic_relocations: Option<DynamicRelocationTable>,
+    /// ARM64EC code map and entry points, ARM64X and ARM64EC images only
+    pub arm64ec_metadata: Option<Arm64ECMetadata>,
+}
+
+impl LoadConfigDirectory {
+    pub fn from_parser(
+        cursor: &mut PEReader,
+        is_32_bits: bool,
+    ) -> Result<LoadConfigDirectory, Box<dyn std::error::Error>> {
+        let size = cursor.read_u32()?;
+
+        /* Copy the part of the structure given by Size, the missing fields read as zero */
+        let full_size = if is_32_bits { LOAD_CONFIG_SIZE_32 } else { LOAD_CONFIG_SIZE_64 };
+        let mut data = vec![0u8; full_size];
+        let available = (size as usize).clamp(4, full_size) - 4;
+
+        cursor.read_exact(&mut data[4..4 + available])?;
+
+        let mut reader = PEReader::from_bytes(&data);
+        reader.set_position(4)?;
+
+        let read_pointer = |reader: &mut PEReader| -> Result<u64, Box<dyn std::error::Error>> {
+            match is_32_bits {
+                true => return Ok(reader.read_u32()? as u64),
+                false => return Ok(reader.read_u64()?),
+            }
+        };
+
+        let mut config = LoadConfigDirectory { is_32_bits, size, ..LoadConfigDirectory::default() };
+
+        config.time_date_stamp = reader.read_u32()?;
+        config.major_version = reader.read_u16()?;
+        config.minor_version = reader.read_u16()?;
+        config.global_flags_clear = reader.read_u32()?;
+        config.global_flags_set = reader.read_u32()?;
+        config.critical_section_default_timeout = reader.read_u32()?;
+        config.de_commit_free_block_threshold = read_pointer(&mut reader)?;
+        config.de_commit_total_free_threshold = read_pointer(&mut reader)?;
+        config.lock_prefix_table = read_pointer(&mut reader)?;
+        config.maximum_allocation_size = read_pointer(&mut reader)?;
+        config.virtual_memory_threshold = read_pointer(&mut reader)?;
+
+        /* The two fields are swapped between the variants */
+        if is_32_bits {
+            config.process_heap_flags = reader.read_u32()?;
+            config.process_affinity_mask = reader.read_u32()? as u64;
+        } else {
+            config.process_affinity_mask = reader.read_u64()?;
+            config.process_heap_flags = reader.read_u32()?;
+        }
+
+        config.csd_version = reader.read_u16()?;
+        config.dependent_load_flags = reader.read_u16()?;
+        config.edit_list = read_pointer(&mut reader)?;
+        config.security_cookie = read_pointer(&mut reader)?;
+        config.se_handler_table = read_pointer(&mut reader)?;
+        config.se_handler_count = read_pointer(&mut reader)?;
+        config.guard_cf_check_function_pointer = read_pointer(&mut reader)?;
+        config.guard_cf_dispatch_function_pointer = read_pointer(&mut reader)?;
+        config.guard_cf_function_table = read_pointer(&mut reader)?;
+        config.guard_cf_function_count = read_pointer(&mut reader)?;
+        config.guard_flags = reader.read_u32()?;
+        config.code_integrity_flags = reader.read_u16()?;
+        config.code_integrity_catalog = reader.read_u16()?;
+        config.code_integrity_catalog_offset = reader.read_u32()?;
+        reader.read_u32()?; // CodeIntegrity.Reserved
+        config.guard_address_taken_iat_entry_table = read_pointer(&mut reader)?;
+        config.guard_address_taken_iat_entry_count = read_pointer(&mut reader)?;
+        config.guard_long_jump_target_table = read_pointer(&mut reader)?;
+        config.guard_long_jump_target_count = read_pointer(&mut reader)?;
+        config.dynamic_value_reloc_table = read_pointer(&mut reader)?;
+        config.chpe_metadata_pointer = read_pointer(&mut reader)?;
+        config.guard_rf_failure_routine = read_pointer(&mut reader)?;
+        config.guard_rf_failure_routine_function_pointer = read_pointer(&mut reader)?;
+        config.dynamic_value_reloc_table_offset = reader.read_u32()?;
+        config.dynamic_value_reloc_table_section = reader.read_u16()?;
+        reader.read_u16()?; // Reserved2
+        config.guard_rf_verify_stack_pointer_function_pointer = read_pointer(&mut reader)?;
+        reader.read_u32()?; // HotPatchTableOffset
+        reader.read_u32()?; // Reserved3
+        config.enclave_configuration_pointer = read_pointer(&mut reader)?;
+        config.volatile_metadata_pointer = read_pointer(&mut reader)?;
+        config.guard_eh_continuation_table = read_pointer(&mut reader)?;
+        config.guard_eh_continuation_count = read_pointer(&mut reader)?;
+
+        return Ok(config);
+    }
+
+    /// Whether Size covers the structure up to the given end offsets of the 32 and 64-bit variants
+    fn covers(&self, end_32: u32, end_64: u32) -> bool {
+        return self.size >= if self.is_32_bits { end_32 } else { end_64 };
+    }
+
+    /// Extra bytes after each RVA of the GuardCFFunctionTable (the GFIDS flags)
+    pub fn guard_cf_function_stride(&self) -> u32 {
+        return (self.guard_flags & GUARD_CF_FUNCTION_TABLE_SIZE_MASK) >> GUARD_CF_FUNCTION_TABLE_SIZE_SHIFT;
+    }
+
+    /// Reads the GuardCFFunctionTable, each entry is an RVA followed by the number of extra bytes given by the stride
+    pub fn parse_guard_cf_functions(
+        &mut self,
+        cursor: &mut PEReader,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let stride = self.guard_cf_function_stride();
+
+        for _ in 0..self.guard_cf_function_count.min(MAX_GUARD_CF_FUNCTIONS) {
+            let mut function = GuardCFFunction { rva: cursor.read_u32()?, flags: 0 };
+
+            for i in 0..stride {
+                let extra = cursor.read_u8()?;
+
+                if i == 0 {
+                    function.flags = extra;
+                }
+            }
+
+            self.guard_cf_functions.push(function);
+        }
+
+        return Ok(());
+    }
+
+    /// The table is sorted by RVA, the loader refuses the handlers missing from it
+    pub fn parse_se_handlers(
+        &mut self,
+        cursor: &mut PEReader,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for _ in 0..self.se_handler_count.min(MAX_SE_HANDLERS) {
+            self.se_handlers.push(cursor.read_u32()?);
+        }
+
+        return Ok(());
+    }
+
+    /// Valid indirect call targets, named with the exports and the pdb symbols, or sized with the .pdata entry they start
+    #[rustfmt::skip]
+    pub fn dump_guard_cf_functions(&self, pe: &PE) -> Dump {
+        let mut dump = Dump::new_from_string(format!("Control Flow Guard ({} functions)", self.guard_cf_functions.len()));
+
+        dump.push_field("GuardFlags", format_flags::<GuardFlags>(self.guard_flags as u64), None);
+        dump.push_field("GuardCFFunctionTable", format!("{:#x}", self.guard_cf_function_table), None);
+        dump.push_field("GuardCFFunctionCount", format!("{}", self.guard_cf_function_count), None);
+        dump.push_field("Stride", format!("{} extra bytes", self.guard_cf_function_stride()), None);
+
+        let names = function_names(pe);
+
+        let pdata_sizes: HashMap<u32, u32> = pe
+            .exception_table
+            .iter()
+            .flat_map(|t| t.entries.iter())
+            .filter_map(|entry| match entry {
+                ExcFunctionEntry::X64(e) => Some((e.begin_address, e.end_address.saturating_sub(e.begin_address))),
+                _ => None,
+            })
+            .collect();
+
+        for function in self.guard_cf_functions.iter() {
+            let mut line = format!("{:#010x}", function.rva);
+
+            if let Some(name) = names.get(&function.rva) {
+                line.push_str(&format!("  {}", name));
+            } else if let Some(size) = pdata_sizes.get(&function.rva) {
+                line.push_str(&format!("  (.pdata function, {:#x} bytes)", size));
+            }
+
+            if function.flags != 0 {
+                line.push_str(&format!("  [{}]", GuardCFFunctionFlags::flags_as_string(function.flags)));
+            }
+
+            dump.push_field("", line, None);
+        }
+
+        return dump;
+    }
+
+    /// Registered safe exception handlers, named with the exports and the pdb symbols
+    #[rustfmt::skip]
+    pub fn dump_se_handlers(&self, pe: &PE) -> Dump {
+        let mut dump = Dump::new_from_string(format!("SafeSEH ({} handlers)", self.se_handlers.len()));
+
+        dump.push_field("SEHandlerTable", format!("{:#x}", self.se_handler_table), None);
+        dump.push_field("SEHandlerCount", format!("{}", self.se_handler_count), None);
+
+        let names = function_names(pe);
+
+        for rva in self.se_handlers.iter() {
+            let location = match pe.get_section_for_rva(*rva) {
+                Some(section) => format!("{} + {:#x}", section.header.name, rva - section.header.virtual_address),
+                None => String::from("outside of the sections"),
+            };
+
+            match names.get(rva) {
+                Some(name) => dump.push_field("", format!("{:#010x}  {}  {}", rva, location, name), None),
+                None => dump.push_field("", format!("{:#010x}  {}", rva, location), None),
+            }
+        }
+
+        return dump;
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Load Config Directory");
+
+        dump.push_field("Size", format!("{:#x}", self.size), None);
+        dump.push_field("TimeDateStamp", format_timestamp(self.time_date_stamp), None);
+        dump.push_field("Version", format!("{}.{}", self.major_version, self.minor_version), None);
+        dump.push_field("GlobalFlagsClear", format!("{:#x}", self.global_flags_clear), None);
+        dump.push_field("GlobalFlagsSet", format!("{:#x}", self.global_flags_set), None);
+        dump.push_field("CriticalSectionDefaultTimeout", format!("{:#x}", self.critical_section_default_timeout), None);
+        dump.push_field("DeCommitFreeBlockThreshold", format!("{:#x}", self.de_commit_free_block_threshold), None);
+        dump.push_field("DeCommitTotalFreeThreshold", format!("{:#x}", self.de_commit_total_free_threshold), None);
+        dump.push_field("LockPrefixTable", format!("{:#x}", self.lock_prefix_table), None);
+        dump.push_field("MaximumAllocationSize", format!("{:#x}", self.maximum_allocation_size), None);
+        dump.push_field("VirtualMemoryThreshold", format!("{:#x}", self.virtual_memory_threshold), None);
+        dump.push_field("ProcessAffinityMask", format!("{:#x}", self.process_affinity_mask), None);
+        dump.push_field("ProcessHeapFlags", format!("{:#x}", self.process_heap_flags), None);
+        dump.push_field("CSDVersion", format!("{:#x}", self.csd_version), None);
+        dump.push_field("DependentLoadFlags", format_flags::<DependentLoadFlags>(self.dependent_load_flags as u64), None);
+        dump.push_field("EditList", format!("{:#x}", self.edit_list), None);
+        dump.push_field("SecurityCookie", format!("{:#x}", self.security_cookie), None);
+
+        if self.covers(0x48, 0x70) {
+            dump.push_field("SEHandlerTable", format!("{:#x}", self.se_handler_table), None);
+            dump.push_field("SEHandlerCount", format!("{}", self.se_handler_count), None);
+        }
+
+        if self.covers(0x5C, 0x94) {
+            dump.push_field("GuardCFCheckFunctionPointer", format!("{:#x}", self.guard_cf_check_function_pointer), None);
+            dump.push_field("GuardCFDispatchFunctionPointer", format!("{:#x}", self.guard_cf_dispatch_function_pointer), None);
+            dump.push_field("GuardCFFunctionTable", format!("{:#x}", self.guard_cf_function_table), None);
+            dump.push_field("GuardCFFunctionCount", format!("{}", self.guard_cf_function_count), None);
+            dump.push_field("GuardFlags", format_flags::<GuardFlags>(self.guard_flags as u64), None);
+        }
+
+        if self.covers(0x68, 0xA0) {
+            dump.push_field("CodeIntegrity", format!("flags: {:#x} catalog: {:#x} catalog offset: {:#x}", self.code_integrity_flags, self.code_integrity_catalog, self.code_integrity_catalog_offset), None);
+        }
+
+        if self.covers(0x78, 0xC0) {
+            dump.push_field("GuardAddressTakenIatEntryTable", format!("{:#x}", self.guard_address_taken_iat_entry_table), None);
+            dump.push_field("GuardAddressTakenIatEntryCount", format!("{}", self.guard_address_taken_iat_entry_count), None);
+            dump.push_field("GuardLongJumpTargetTable", format!("{:#x}", self.guard_long_jump_target_table), None);
+            dump.push_field("GuardLongJumpTargetCount", format!("{}", self.guard_long_jump_target_count), None);
+        }
+
+        if self.covers(0x88, 0xE0) {
+            dump.push_field("DynamicValueRelocTable", format!("{:#x}", self.dynamic_value_reloc_table), None);
+            dump.push_field("CHPEMetadataPointer", format!("{:#x}", self.chpe_metadata_pointer), None);
+            dump.push_field("GuardRFFailureRoutine", format!("{:#x}", self.guard_rf_failure_routine), None);
+            dump.push_field("GuardRFFailureRoutineFunctionPointer", format!("{:#x}", self.guard_rf_failure_routine_function_pointer), None);
+        }
+
+        if self.covers(0x94, 0xF0) {
+            dump.push_field("DynamicValueRelocTableOffset", format!("{:#x}", self.dynamic_value_reloc_table_offset), None);
+            dump.push_field("DynamicValueRelocTableSection", format!("{}", self.dynamic_value_reloc_table_section), None);
+            dump.push_field("GuardRFVerifyStackPointerFunctionPointer", format!("{:#x}", self.guard_rf_verify_stack_pointer_function_pointer), None);
+        }
+
+        if self.covers(0xA4, 0x108) {
+            dump.push_field("EnclaveConfigurationPointer", format!("{:#x}", self.enclave_configuration_pointer), None);
+            dump.push_field("VolatileMetadataPointer", format!("{:#x}", self.volatile_metadata_pointer), None);
+        }
+
+        if self.covers(0xAC, 0x118) {
+            dump.push_field("GuardEHContinuationTable", format!("{:#x}", self.guard_eh_continuation_table), None);
+            dump.push_field("GuardEHContinuationCount", format!("{}", self.guard_eh_continuation_count), None);
+        }
+
+        return dump;
+    }
+}
+
+/*
+ * Dynamic Value Relocation Table, the ARM64X fixups turn the native ARM64 view of a hybrid image into its ARM64EC one
+ * https://learn.microsoft.com/en-us/windows/arm/arm64x-pe
+ */
+
+const DYNAMIC_RELOCATION_TABLE_VERSION_1: u32 = 1;
+
+/* Guards against a corrupted table */
+const MAX_DYNAMIC_RELOCATIONS: usize = 256;
+const MAX_ARM64X_FIXUPS: usize = 0x10000;
+
+#[repr(u64)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum DynamicRelocationSymbol {
+    Unknown = 0,
+    GuardRfPrologue = 1,
+    GuardRfEpilogue = 2,
+    GuardImportControlTransfer = 3,
+    GuardIndirControlTransfer = 4,
+    GuardSwitchableBranch = 5,
+    Arm64X = 6,
+    FunctionOverride = 7,
+    Arm64KernelImportCallTransfer = 8,
+}
+
+impl From<u64> for DynamicRelocationSymbol {
+    fn from(value: u64) -> Self {
+        match value {
+            1 => DynamicRelocationSymbol::GuardRfPrologue,
+            2 => DynamicRelocationSymbol::GuardRfEpilogue,
+            3 => DynamicRelocationSymbol::GuardImportControlTransfer,
+            4 => DynamicRelocationSymbol::GuardIndirControlTransfer,
+            5 => DynamicRelocationSymbol::GuardSwitchableBranch,
+            6 => DynamicRelocationSymbol::Arm64X,
+            7 => DynamicRelocationSymbol::FunctionOverride,
+            8 => DynamicRelocationSymbol::Arm64KernelImportCallTransfer,
+            _ => DynamicRelocationSymbol::Unknown,
+        }
+    }
+}
+
+impl DynamicRelocationSymbol {
+    pub fn as_static_str(&self) -> &'static str {
+        return self.into();
+    }
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum Arm64XFixupType {
+    ZeroFill = 0,
+    Value = 1,
+    Delta = 2,
+}
+
+impl Arm64XFixupType {
+    pub fn as_static_str(&self) -> &'static str {
+        return self.into();
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Arm64XFixup {
+    pub rva: u32,
+    pub fixup_type: Arm64XFixupType,
+    /// Bytes written, a delta is always added to a pointer
+    pub size: u8,
+    /// Value written, or added for a delta
+    pub value: i64,
+}
+
+impl Arm64XFixup {
+    /// Writes the fixup to a buffer mapped at RVA 0, the fixups past its end are ignored
+    pub fn apply(&self, image: &mut [u8]) {
+        let start = self.rva as usize;
+        let Some(bytes) = image.get_mut(start..start + self.size as usize) else {
+            return;
+        };
+
+        match self.fixup_type {
+            Arm64XFixupType::ZeroFill => bytes.fill(0),
+            Arm64XFixupType::Value => bytes.copy_from_slice(&self.value.to_le_bytes()[..self.size as usize]),
+            Arm64XFixupType::Delta => {
+                let mut current = [0u8; 8];
+                current[..bytes.len()].copy_from_slice(bytes);
+
+                let patched = u64::from_le_bytes(current).wrapping_add(self.value as u64);
+                bytes.copy_from_slice(&patched.to_le_bytes()[..self.size as usize]);
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self.fixup_type {
+            Arm64XFixupType::ZeroFill => return format!("{:#010x}  zero {} bytes", self.rva, self.size),
+            Arm64XFixupType::Value => return format!("{:#010x}  set {} bytes to {:#x}", self.rva, self.size, self.value),
+            Arm64XFixupType::Delta if self.value < 0 => return format!("{:#010x}  subtract {:#x}", self.rva, self.value.unsigned_abs()),
+            Arm64XFixupType::Delta => return format!("{:#010x}  add {:#x}", self.rva, self.value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DynamicRelocation {
+    pub symbol: u64,
+    pub size: u32,
+    pub arm64x_fixups: Vec<Arm64XFixup>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DynamicRelocationTable {
+    pub version: u32,
+    pub size: u32,
+    /// Only the version 1 entries are decoded
+    pub relocations: Vec<DynamicRelocation>,
+}
+
+impl DynamicRelocationTable {
+    pub fn from_parser(
+        cursor: &mut PEReader,
+        is_32_bits: bool,
+    ) -> Result<DynamicRelocationTable, Box<dyn std::error::Error>> {
+        let mut table = DynamicRelocationTable::default();
+
+        table.version = cursor.read_u32()?;
+        table.size = cursor.read_u32()?;
+
+        if table.version != DYNAMIC_RELOCATION_TABLE_VERSION_1 {
+            return Ok(table);
+        }
+
+        let end = cursor.position() + table.size as u64;
+
+        while cursor.position() < end && table.relocations.len() < MAX_DYNAMIC_RELOCATIONS {
+            let symbol = match is_32_bits {
+                true => cursor.read_u32()? as u64,
+                false => cursor.read_u64()?,
+            };
+
+            let size = cursor.read_u32()?;
+            let fixups_end = cursor.position() + size as u64;
+
+            let mut relocation = DynamicRelocation { symbol, size, ..DynamicRelocation::default() };
+
+            if DynamicRelocationSymbol::from(symbol) == DynamicRelocationSymbol::Arm64X {
+                relocation.arm64x_fixups = parse_arm64x_fixups(cursor, fixups_end)?;
+            }
+
+            table.relocations.push(relocation);
+
+            cursor.set_position(fixups_end)?;
+        }
+
+        return Ok(table);
+    }
+
+    pub fn arm64x_fixups(&self) -> impl Iterator<Item = &Arm64XFixup> {
+        return self.relocations.iter().flat_map(|relocation| relocation.arm64x_fixups.iter());
+    }
+}
+
+/// Blocks laid out like the base relocations: a page RVA, the block size, then 16-bit records made of a 12-bit offset,
+/// a 2-bit type and a 2-bit size, followed by the value for the VALUE and DELTA records
+fn parse_arm64x_fixups(cursor: &mut PEReader, end: u64) -> Result<Vec<Arm64XFixup>, Box<dyn std::error::Error>> {
+    let mut fixups = Vec::new();
+
+    while cursor.position() + 8 <= end && fixups.len() < MAX_ARM64X_FIXUPS {
+        let block_start = cursor.position();
+        let page_rva = cursor.read_u32()?;
+        let block_size = cursor.read_u32()?;
+
+        if block_size < 8 {
+            break;
+        }
+
+        let block_end = (block_start + block_size as u64).min(end);
+
+        while cursor.position() + 2 <= block_end {
+            let record = cursor.read_u16()?;
+
+            /* Padding of the block to a 32-bit boundary */
+            if record == 0 && cursor.position() == block_end {
+                break;
+            }
+
+            let rva = page_rva + (record & 0xFFF) as u32;
+            let size_bits = (record >> 14) as u8;
+
+            let fixup = match (record >> 12) & 0x3 {
+                0 => Arm64XFixup { rva, fixup_type: Arm64XFixupType::ZeroFill, size: 1 << size_bits, value: 0 },
+                1 => {
+                    let size = 1u8 << size_bits;
+                    let value = match size {
+                        1 => cursor.read_u8()? as i64,
+                        2 => cursor.read_u16()? as i64,
+                        4 => cursor.read_u32()? as i64,
+                        _ => cursor.read_u64()? as i64,
+                    };
+
+                    Arm64XFixup { rva, fixup_type: Arm64XFixupType::Value, size, value }
+                }
+                2 => {
+                    /* The size bits are the sign and the scale of the 16-bit delta */
+                    let scale = if size_bits & 0x2 != 0 { 8 } else { 4 };
+                    let delta = cursor.read_u16()? as i64 * scale;
+
+                    Arm64XFixup { rva, fixup_type: Arm64XFixupType::Delta, size: 8, value: if size_bits & 0x1 != 0 { -delta } else { delta } }
+                }
+                _ => return Err(format!("Invalid ARM64X fixup type at RVA {:#x}", rva).into()),
+            };
+
+            fixups.push(fixup);
+        }
+
+        cursor.set_position(block_end)?;
+    }
+
+    return Ok(fixups);
+}
+
+/*
+ * ARM64EC metadata, pointed by the CHPEMetadataPointer of the load config of the ARM64X and ARM64EC images
+ */
+
+/* Guards against a corrupted code map */
+const MAX_CHPE_CODE_RANGES: u32 = 0x10000;
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum ChpeRangeType {
+    Arm64 = 0,
+    #[strum(serialize = "ARM64EC")]
+    Arm64EC = 1,
+    Amd64 = 2,
+    Unknown = 3,
+}
+
+impl ChpeRangeType {
+    pub fn as_static_str(&self) -> &'static str {
+        return self.into();
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChpeCodeRange {
+    pub start: u32,
+    pub length: u32,
+    pub range_type: ChpeRangeType,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Arm64ECMetadata {
+    pub version: u32,
+    pub code_map: u32,
+    pub code_map_count: u32,
+    pub code_ranges_to_entry_points: u32,
+    pub redirection_metadata: u32,
+    /// Entry point of the other view, the ARM64EC one for the native loader
+    pub alternate_entry_point: u32,
+    pub auxiliary_iat: u32,
+    pub code_ranges_to_entry_points_count: u32,
+    pub redirection_metadata_count: u32,
+    pub extra_rfe_table: u32,
+    pub extra_rfe_table_size: u32,
+    pub auxiliary_iat_copy: u32,
+    pub code_ranges: Vec<ChpeCodeRange>,
+}
+
+impl Arm64ECMetadata {
+    pub fn from_parser(cursor: &mut PEReader) -> Result<Arm64ECMetadata, Box<dyn std::error::Error>> {
+        let mut metadata = Arm64ECMetadata::default();
+
+        metadata.version = cursor.read_u32()?;
+        metadata.code_map = cursor.read_u32()?;
+        metadata.code_map_count = cursor.read_u32()?;
+        metadata.code_ranges_to_entry_points = cursor.read_u32()?;
+        metadata.redirection_metadata = cursor.read_u32()?;
+        cursor.skip(5 * 4)?; // __os_arm64x_dispatch_call_no_redirect to __os_arm64x_check_icall_cfg
+        metadata.alternate_entry_point = cursor.read_u32()?;
+        metadata.auxiliary_iat = cursor.read_u32()?;
+        metadata.code_ranges_to_entry_points_count = cursor.read_u32()?;
+        metadata.redirection_metadata_count = cursor.read_u32()?;
+        cursor.skip(2 * 4)?; // GetX64InformationFunctionPointer, SetX64InformationFunctionPointer
+        metadata.extra_rfe_table = cursor.read_u32()?;
+        metadata.extra_rfe_table_size = cursor.read_u32()?;
+        cursor.skip(4)?; // __os_arm64x_dispatch_fptr
+        metadata.auxiliary_iat_copy = cursor.read_u32()?;
+
+        return Ok(metadata);
+    }
+
+    /// Reads the code map, the two low bits of the start RVA give the range type
+    pub fn parse_code_ranges(&mut self, cursor: &mut PEReader) -> Result<(), Box<dyn std::error::Error>> {
+        for _ in 0..self.code_map_count.min(MAX_CHPE_CODE_RANGES) {
+            let start = cursor.read_u32()?;
+            let length = cursor.read_u32()?;
+
+            let range_type = match start & 0x3 {
+                0 => ChpeRangeType::Arm64,
+                1 => ChpeRangeType::Arm64EC,
+                2 => ChpeRangeType::Amd64,
+                _ => ChpeRangeType::Unknown,
+            };
+
+            self.code_ranges.push(ChpeCodeRange { start: start & !0x3, length, range_type });
+        }
+
+        return Ok(());
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("ARM64EC Metadata");
+
+        dump.push_field("Version", format!("{}", self.version), None);
+        dump.push_field("CodeMap", format!("{:#x} ({} ranges)", self.code_map, self.code_map_count), None);
+        dump.push_field("CodeRangesToEntryPoints", format!("{:#x} ({} entries)", self.code_ranges_to_entry_points, self.code_ranges_to_entry_points_count), None);
+        dump.push_field("RedirectionMetadata", format!("{:#x} ({} entries)", self.redirection_metadata, self.redirection_metadata_count), None);
+        dump.push_field("AlternateEntryPoint", format!("{:#x}", self.alternate_entry_point), None);
+        dump.push_field("AuxiliaryIAT", format!("{:#x}", self.auxiliary_iat), None);
+        dump.push_field("AuxiliaryIATCopy", format!("{:#x}", self.auxiliary_iat_copy), None);
+        dump.push_field("ExtraRFETable", format!("{:#x} sz: {:#x}", self.extra_rfe_table, self.extra_rfe_table_size), None);
+
+        let mut ranges_dump = Dump::new_from_string(format!("Code Ranges ({})", self.code_ranges.len()));
+
+        for range in self.code_ranges.iter() {
+            ranges_dump.push_field("", format!("{:#010x} - {:#010x}  {}", range.start, range.start as u64 + range.length as u64, range.range_type.as_static_str()), None);
+        }
+
+        dump.push_child(ranges_dump);
+
+        return dump;
+    }
+}
+
+/*
+ * Attribute Certificate Table
+ * https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#the-attribute-certificate-table-image-only
+ */
+
+/* Guards against a corrupted table made of empty entries */
+const MAX_CERTIFICATES: usize = 64;
+
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum CertificateType {
+    Unknown = 0x0,
+    X509 = 0x1,
+    PkcsSignedData = 0x2,
+    Reserved1 = 0x3,
+    TsStackSigned = 0x4,
+}
+
+impl CertificateType {
+    pub fn as_static_str(&self) -> &'static str {
+        return self.into();
+    }
+}
+
+impl From<u16> for CertificateType {
+    fn from(value: u16) -> Self {
+        match value {
+            0x1 => CertificateType::X509,
+            0x2 => CertificateType::PkcsSignedData,
+            0x3 => CertificateType::Reserved1,
+            0x4 => CertificateType::TsStackSigned,
+            _ => CertificateType::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WinCertificate {
+    pub length: u32,
+    pub revision: u16,
+    pub certificate_type: u16,
+    pub data: Vec<u8>,
+}
+
+impl WinCertificate {
+    pub fn from_parser(
+        cursor: &mut PEReader,
+    ) -> Result<WinCertificate, Box<dyn std::error::Error>> {
+        let mut certificate = WinCertificate::default();
+
+        certificate.length = cursor.read_u32()?;
+        certificate.revision = cursor.read_u16()?;
+        certificate.certificate_type = cursor.read_u16()?;
+
+        let mut data = vec![0; (certificate.length as usize).saturating_sub(8)];
+        cursor.read_exact(&mut data)?;
+
+        certificate.data = data;
+
+        return Ok(certificate);
+    }
+
+    pub fn get_certificate_type(&self) -> CertificateType {
+        return CertificateType::from(self.certificate_type);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CertificateTable {
+    pub certificates: Vec<WinCertificate>,
+}
+
+impl CertificateTable {
+    /// The table is not mapped in memory, its data directory holds a file offset instead of an RVA
+    pub fn from_parser(
+        cursor: &mut PEReader,
+        offset: u32,
+        size: u32,
+    ) -> Result<CertificateTable, Box<dyn std::error::Error>> {
+        let mut table = CertificateTable::default();
+
+        let end = offset as u64 + size as u64;
+        let mut position = offset as u64;
+
+        while position + 8 <= end && table.certificates.len() < MAX_CERTIFICATES {
+            cursor.set_position(position)?;
+
+            let certificate = WinCertificate::from_parser(cursor)?;
+
+            if certificate.length < 8 {
+                break;
+            }
+
+            /* Entries are 8-byte aligned */
+            position += (certificate.length as u64 + 7) & !7;
+
+            table.certificates.push(certificate);
+        }
+
+        return Ok(table);
+    }
+
+    /// The Authenticode signature, a DER-encoded PKCS#7 SignedData
+    pub fn pkcs7_signature(&self) -> Option<&WinCertificate> {
+        return self
+            .certificates
+            .iter()
+            .find(|c| c.get_certificate_type() == CertificateType::PkcsSignedData);
+    }
+}
+
+/*
+ * COFF Symbol Table, deprecated for images but still written by MinGW and for object files
+ * https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#coff-symbol-table
+ */
+
+pub const COFF_SYMBOL_SIZE: u64 = 18;
+
+/* Guards against a corrupted NumberOfSymbols */
+const MAX_COFF_SYMBOLS: u32 = 0x100000;
+
+/* Special section numbers of the symbols */
+const IMAGE_SYM_UNDEFINED: i16 = 0;
+const IMAGE_SYM_ABSOLUTE: i16 = -1;
+const IMAGE_SYM_DEBUG: i16 = -2;
+
+/* Complex type of the function symbols, in the high byte of the type */
+const IMAGE_SYM_DTYPE_FUNCTION: u16 = 0x20;
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum StorageClass {
+    EndOfFunction = 0xff,
+    Null = 0,
+    Automatic = 1,
+    External = 2,
+    Static = 3,
+    Register = 4,
+    ExternalDef = 5,
+    Label = 6,
+    UndefinedLabel = 7,
+    MemberOfStruct = 8,
+    Argument = 9,
+    StructTag = 10,
+    MemberOfUnion = 11,
+    UnionTag = 12,
+    TypeDefinition = 13,
+    UndefinedStatic = 14,
+    EnumTag = 15,
+    MemberOfEnum = 16,
+    RegisterParam = 17,
+    BitField = 18,
+    Block = 100,
+    Function = 101,
+    EndOfStruct = 102,
+    File = 103,
+    Section = 104,
+    WeakExternal = 105,
+    ClrToken = 107,
+    Unknown = 0xfe,
+}
+
+impl StorageClass {
+    pub fn as_static_str(&self) -> &'static str {
+        return self.into();
+    }
+}
+
+impl From<u8> for StorageClass {
+    fn from(value: u8) -> Self {
+        match value {
+            0xff => StorageClass::EndOfFunction,
+            0 => StorageClass::Null,
+            1 => StorageClass::Automatic,
+            2 => StorageClass::External,
+            3 => StorageClass::Static,
+            4 => StorageClass::Register,
+            5 => StorageClass::ExternalDef,
+            6 => StorageClass::Label,
+            7 => StorageClass::UndefinedLabel,
+            8 => StorageClass::MemberOfStruct,
+            9 => StorageClass::Argument,
+            10 => StorageClass::StructTag,
+            11 => StorageClass::MemberOfUnion,
+            12 => StorageClass::UnionTag,
+            13 => StorageClass::TypeDefinition,
+            14 => StorageClass::UndefinedStatic,
+            15 => StorageClass::EnumTag,
+            16 => StorageClass::MemberOfEnum,
+            17 => StorageClass::RegisterParam,
+            18 => StorageClass::BitField,
+            100 => StorageClass::Block,
+            101 => StorageClass::Function,
+            102 => StorageClass::EndOfStruct,
+            103 => StorageClass::File,
+            104 => StorageClass::Section,
+            105 => StorageClass::WeakExternal,
+            107 => StorageClass::ClrToken,
+            _ => StorageClass::Unknown,
+        }
+    }
+}
+
+/// Auxiliary records following a symbol, decoded from the storage class and the type of the symbol
+#[derive(Debug, Clone)]
+pub enum CoffAuxRecord {
+    FunctionDefinition {
+        tag_index: u32,
+        total_size: u32,
+        pointer_to_line_number: u32,
+        pointer_to_next_function: u32,
+    },
+    /// .bf and .ef symbols
+    FunctionBoundary {
+        line_number: u16,
+        pointer_to_next_function: u32,
+    },
+    WeakExternal {
+        tag_index: u32,
+        characteristics: u32,
+    },
+    /// Name of the source file, spread over all the records of the .file symbol
+    File(String),
+    SectionDefinition {
+        length: u32,
+        number_of_relocations: u16,
+        number_of_line_numbers: u16,
+        checksum: u32,
+        number: u16,
+        selection: u8,
+    },
+    Raw(Vec<u8>),
+}
+
+impl CoffAuxRecord {
+    pub fn dump_line(&self) -> String {
+        match self {
+            CoffAuxRecord::FunctionDefinition { tag_index, total_size, pointer_to_line_number, pointer_to_next_function } => format!(
+                "Function: TagIndex {}, TotalSize {:#x}, PointerToLineNumber {:#x}, PointerToNextFunction {:#x}",
+                tag_index, total_size, pointer_to_line_number, pointer_to_next_function
+            ),
+            CoffAuxRecord::FunctionBoundary { line_number, pointer_to_next_function } => {
+                format!("Boundary: LineNumber {}, PointerToNextFunction {:#x}", line_number, pointer_to_next_function)
+            }
+            CoffAuxRecord::WeakExternal { tag_index, characteristics } => {
+                let search: &'static str = match characteristics {
+                    1 => "NOLIBRARY",
+                    2 => "LIBRARY",
+                    3 => "ALIAS",
+                    4 => "ANTI_DEPENDENCY",
+                    _ => "UNKNOWN",
+                };
+
+                format!("Weak External: TagIndex {}, Characteristics {} ({:#x})", tag_index, search, characteristics)
+            }
+            CoffAuxRecord::File(name) => format!("File: {}", name),
+            CoffAuxRecord::SectionDefinition { length, number_of_relocations, number_of_line_numbers, checksum, number, selection } => format!(
+                "Section: Length {:#x}, Relocations {}, LineNumbers {}, Checksum {:#010x}, Number {}, Selection {}",
+                length, number_of_relocations, number_of_line_numbers, checksum, number, selection
+            ),
+            CoffAuxRecord::Raw(data) => format!("Raw: {}", data.iter().map(|b| format!("{:02x}", b)).collect::<Vec<String>>().join(" ")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CoffSymbol {
+    /// Index of the symbol in the table, the auxiliary records take indices too
+    pub index: u32,
+    pub name: String,
+    pub value: u32,
+    /// 1-based section index, or one of IMAGE_SYM_UNDEFINED, IMAGE_SYM_ABSOLUTE and IMAGE_SYM_DEBUG
+    pub section_number: i16,
+    pub symbol_type: u16,
+    pub storage_class: u8,
+    pub number_of_aux_symbols: u8,
+    pub aux_records: Vec<CoffAuxRecord>,
+}
+
+impl CoffSymbol {
+    pub fn from_parser(
+        cursor: &mut PEReader,
+        string_table: &[u8],
+    ) -> Result<CoffSymbol, Box<dyn std::error::Error>> {
+        let mut symbol = CoffSymbol::default();
+
+        let mut short_name = [0u8; 8];
+        cursor.read_exact(&mut short_name)?;
+
+        /* Long names are an offset in the string table, behind 4 zero bytes */
+        symbol.name = match short_name[..4] == [0; 4] {
+            true => read_coff_string(string_table, u32::from_le_bytes(short_name[4..].try_into()?) as usize),
+            false => {
+                let nul = short_name.iter().position(|&b| b == 0).unwrap_or(8);
+                decode_name(&short_name[..nul]).0
+            }
+        };
+
+        symbol.value = cursor.read_u32()?;
+        symbol.section_number = cursor.read_i16()?;
+        symbol.symbol_type = cursor.read_u16()?;
+        symbol.storage_class = cursor.read_u8()?;
+        symbol.number_of_aux_symbols = cursor.read_u8()?;
+
+        return Ok(symbol);
+    }
+
+    pub fn get_storage_class(&self) -> StorageClass {
+        return StorageClass::from(self.storage_class);
+    }
+
+    pub fn is_function(&self) -> bool {
+        return (self.symbol_type & 0x30) == IMAGE_SYM_DTYPE_FUNCTION;
+    }
+
+    /// Decodes the auxiliary records of the symbol, each one is the size of a symbol
+    pub fn parse_aux_records(&mut self, records: &[u8]) {
+        let u16_at = |record: &[u8], offset: usize| u16::from_le_bytes([record[offset], record[offset + 1]]);
+        let u32_at = |record: &[u8], offset: usize| u32::from_le_bytes(record[offset..offset + 4].try_into().unwrap_or_default());
+
+        if self.get_storage_class() == StorageClass::File {
+            let nul = records.iter().position(|&b| b == 0).unwrap_or(records.len());
+            self.aux_records.push(CoffAuxRecord::File(decode_name(&records[..nul]).0));
+            return;
+        }
+
+        /* Weak externals are undefined external symbols, the WEAK_EXTERNAL class is only used by some compilers */
+        let is_weak_external = self.get_storage_class() == StorageClass::WeakExternal
+            || (self.get_storage_class() == StorageClass::External && self.section_number == IMAGE_SYM_UNDEFINED);
+
+        for record in records.chunks_exact(COFF_SYMBOL_SIZE as usize) {
+            let aux = match self.get_storage_class() {
+                _ if is_weak_external => CoffAuxRecord::WeakExternal {
+                    tag_index: u32_at(record, 0),
+                    characteristics: u32_at(record, 4),
+                },
+                StorageClass::External if self.is_function() && self.section_number > 0 => CoffAuxRecord::FunctionDefinition {
+                    tag_index: u32_at(record, 0),
+                    total_size: u32_at(record, 4),
+                    pointer_to_line_number: u32_at(record, 8),
+                    pointer_to_next_function: u32_at(record, 12),
+                },
+                StorageClass::Function => CoffAuxRecord::FunctionBoundary {
+                    line_number: u16_at(record, 4),
+                    pointer_to_next_function: u32_at(record, 12),
+                },
+                /* Section symbols are the static symbols named after their section, with a zero value */
+                StorageClass::Static if self.value == 0 && self.section_number > 0 => CoffAuxRecord::SectionDefinition {
+                    length: u32_at(record, 0),
+                    number_of_relocations: u16_at(record, 4),
+                    number_of_line_numbers: u16_at(record, 6),
+                    checksum: u32_at(record, 8),
+                    number: u16_at(record, 12),
+                    selection: record[14],
+                },
+                _ => CoffAuxRecord::Raw(record.to_vec()),
+            };
+
+            self.aux_records.push(aux);
+        }
+    }
 
-        dump.push_field("BeginAddress", format!("{:#x}", self.begin_address), None);
-        dump.push_field("PrologLength", format!("{:#x}", self.prolog_length), None);
-        dump.push_field("FunctionLength", format!("{:#x}", self.function_length), None);
-        dump.push_field("32-bit Flag", format!("{}", self.flag_32bit), None);
-        dump.push_field("Exception Flag", format!("{}", self.flag_exception), None);
+    pub fn section_as_string(&self, section_names: &[String]) -> String {
+        match self.section_number {
+            IMAGE_SYM_UNDEFINED => return String::from("UNDEF"),
+            IMAGE_SYM_ABSOLUTE => return String::from("ABS"),
+            IMAGE_SYM_DEBUG => return String::from("DEBUG"),
+            number if number > 0 => match section_names.get(number as usize - 1) {
+                Some(name) => return name.clone(),
+                None => return format!("{}", number),
+            },
+            number => return format!("{}", number),
+        }
+    }
 
-        return dump;
+    pub fn dump_line(&self, section_names: &[String]) -> String {
+        let storage_class = match self.get_storage_class() {
+            StorageClass::Unknown => format!("{:#x}", self.storage_class),
+            storage_class => String::from(storage_class.as_static_str()),
+        };
+
+        return format!(
+            "{:>5} {:#010x} {:<8} {:<8} {:<16} {}",
+            self.index,
+            self.value,
+            self.section_as_string(section_names),
+            if self.is_function() { "function" } else { "" },
+            storage_class,
+            display_name(&self.name)
+        );
     }
 }
 
-#[derive(Debug, Clone)]
-pub enum ExcFunctionEntry {
-    Mips32(Mips32ExcFunctionEntry),
-    X64(X64ExcFunctionEntry),
-    Other(OtherExcFunctionEntry),
+/// Null-terminated string of the string table, its offsets count the 4 bytes of its size
+fn read_coff_string(string_table: &[u8], offset: usize) -> String {
+    match string_table.get(offset..) {
+        Some(s) => {
+            let nul = s.iter().position(|&b| b == 0).unwrap_or(s.len());
+            return decode_name(&s[..nul]).0;
+        }
+        None => return String::new(),
+    }
 }
 
-impl Default for ExcFunctionEntry {
-    fn default() -> Self {
-        return ExcFunctionEntry::X64(X64ExcFunctionEntry::default());
-    }
+#[derive(Debug, Clone, Default)]
+pub struct CoffSymbolTable {
+    pub symbols: Vec<CoffSymbol>,
+    /// Size of the string table following the symbols, including its own 4 bytes
+    pub string_table_size: u32,
 }
 
-impl ExcFunctionEntry {
+impl CoffSymbolTable {
+    /// The table is not mapped in memory, the COFF header gives its file offset. The string table follows the symbols
     pub fn from_parser(
-        cursor: &mut io::Cursor<&Vec<u8>>,
-        machine_type: MachineType,
-    ) -> Result<ExcFunctionEntry, Box<dyn std::error::Error>> {
-        match machine_type {
-            MachineType::AMD64 | MachineType::I386 => Ok(ExcFunctionEntry::X64(
-                X64ExcFunctionEntry::from_parser(cursor)?,
-            )),
-            _ => Err("Cannot parse Exception Function Entry, unsupported platform".into()),
-            /* TODO: implement other machine types */
+        cursor: &mut PEReader,
+        offset: u32,
+        number_of_symbols: u32,
+    ) -> Result<CoffSymbolTable, Box<dyn std::error::Error>> {
+        let mut table = CoffSymbolTable::default();
+
+        let string_table_offset = offset as u64 + number_of_symbols as u64 * COFF_SYMBOL_SIZE;
+        let mut string_table = Vec::new();
+
+        if string_table_offset + 4 <= cursor.length() {
+            cursor.set_position(string_table_offset)?;
+
+            table.string_table_size = cursor.read_u32()?;
+
+            let end = (string_table_offset + table.string_table_size as u64).min(cursor.length());
+
+            string_table = vec![0; end.saturating_sub(string_table_offset) as usize];
+            cursor.set_position(string_table_offset)?;
+            cursor.read_exact(&mut string_table)?;
         }
-    }
 
-    pub fn len(&self) -> usize {
-        match self {
-            ExcFunctionEntry::Mips32(_) => 20,
-            ExcFunctionEntry::X64(_) => 12,
-            ExcFunctionEntry::Other(_) => 8,
+        let mut index = 0;
+
+        while index < number_of_symbols {
+            cursor.set_position(offset as u64 + index as u64 * COFF_SYMBOL_SIZE)?;
+
+            let mut symbol = CoffSymbol::from_parser(cursor, &string_table)?;
+            symbol.index = index;
+
+            let aux_count = (symbol.number_of_aux_symbols as u32).min(number_of_symbols - index - 1);
+
+            let mut records = vec![0; aux_count as usize * COFF_SYMBOL_SIZE as usize];
+            cursor.read_exact(&mut records)?;
+            symbol.parse_aux_records(&records);
+
+            index += 1 + aux_count;
+
+            table.symbols.push(symbol);
         }
+
+        return Ok(table);
     }
 
-    pub fn dump(&self) -> Dump {
-        match self {
-            ExcFunctionEntry::Mips32(e) => e.dump(),
-            ExcFunctionEntry::X64(e) => e.dump(),
-            ExcFunctionEntry::Other(e) => e.dump(),
+    pub fn dump(&self, section_names: &[String]) -> Dump {
+        let mut dump = Dump::new_from_string(format!("COFF Symbols ({})", self.symbols.len()));
+
+        dump.push_field("", format!("{:>5} {:<10} {:<8} {:<8} {:<16} {}", "Index", "Value", "Section", "Type", "StorageClass", "Name"), None);
+
+        for symbol in self.symbols.iter() {
+            dump.push_field("", symbol.dump_line(section_names), None);
+
+            for aux in symbol.aux_records.iter() {
+                dump.push_field("", format!("{:>5} {}", "", aux.dump_line()), None);
+            }
         }
+
+        return dump;
     }
 }
 
-#[derive(Debug, Clone, Default)]
-pub struct ExceptionTable {
-    pub entries: Vec<ExcFunctionEntry>,
-}
+/*
+ * TE (Terse Executable) Header, the PE headers of the UEFI PEI modules stripped down to what the firmware needs
+ * https://uefi.org/specs/PI/1.8/V1_TE_Image.html
+ */
 
-impl ExceptionTable {
-    pub fn from_parser(
-        cursor: &mut io::Cursor<&Vec<u8>>,
-        size: usize,
-        machine_type: MachineType,
-    ) -> Result<ExceptionTable, Box<dyn std::error::Error>> {
-        let mut et = ExceptionTable::default();
+pub const TE_MAGIC_ARRAY: [u8; 2] = [b'V', b'Z'];
+pub const TE_HEADER_SIZE: u64 = 40;
+
+#[derive(Default, Clone, Debug)]
+#[repr(C)]
+pub struct TeHeader {
+    pub signature: u16,
+    pub machine: u16,
+    pub number_of_sections: u8,
+    pub subsystem: u8,
+    /// Size of the headers removed from the original PE, file offsets are shifted by it minus the TE header
+    pub stripped_size: u16,
+    pub address_of_entry_point: u32,
+    pub base_of_code: u32,
+    pub image_base: u64,
+    pub base_relocation_table: ImageDataDirectory,
+    pub debug: ImageDataDirectory,
+}
 
-        let mut parsed_sz = 0 as usize;
+impl TeHeader {
+    pub fn from_parser(cursor: &mut PEReader) -> Result<TeHeader, Box<dyn Error>> {
+        let mut header = TeHeader::default();
 
-        while parsed_sz < size {
-            let entry = ExcFunctionEntry::from_parser(cursor, machine_type)?;
-            parsed_sz += entry.len();
-            et.entries.push(entry);
+        if cursor.read_n::<2>()? != TE_MAGIC_ARRAY {
+            return Err("Invalid TE signature".into());
         }
 
-        return Ok(et);
+        header.signature = u16::from_le_bytes(TE_MAGIC_ARRAY);
+        header.machine = cursor.read_u16()?;
+        header.number_of_sections = cursor.read_u8()?;
+        header.subsystem = cursor.read_u8()?;
+        header.stripped_size = cursor.read_u16()?;
+        header.address_of_entry_point = cursor.read_u32()?;
+        header.base_of_code = cursor.read_u32()?;
+        header.image_base = cursor.read_u64()?;
+        header.base_relocation_table = ImageDataDirectory::from_parser(cursor)?;
+        header.debug = ImageDataDirectory::from_parser(cursor)?;
+
+        return Ok(header);
+    }
+
+    /// Difference between the file offsets of the original PE, kept in the section and debug headers, and the TE file ones
+    pub fn stripped_offset(&self) -> u32 {
+        return (self.stripped_size as u32).saturating_sub(TE_HEADER_SIZE as u32);
+    }
+
+    /// The 64-bit machines had a PE32+ optional header before it was stripped
+    pub fn is_32_bits(&self) -> bool {
+        return !matches!(
+            MachineType::from(self.machine),
+            MachineType::AMD64 | MachineType::ARM64 | MachineType::IA64 | MachineType::RISCV64 | MachineType::LOONGARCH64
+        );
     }
 
+    #[rustfmt::skip]
     pub fn dump(&self) -> Dump {
-        let mut dump =
-            Dump::new(format!("Exception Table ({} entries)", self.entries.len()).as_str());
+        let mut dump = Dump::new("TE Header");
 
-        for entry in self.entries.iter() {
-            dump.push_child(entry.dump());
-        }
+        dump.push_field("Signature", format!("{:#x} (VZ)", self.signature), None);
+        dump.push_field("Machine", format!("{:#x} ({:#?})", self.machine, MachineType::from(self.machine)), None);
+        dump.push_field("NumberOfSections", format!("{:#x}", self.number_of_sections), None);
+        dump.push_field("Subsystem", format!("{:#x} (IMAGE_SUBSYSTEM_{})", self.subsystem, Subsystem::from(self.subsystem as u16).as_static_str()), None);
+        dump.push_field("StrippedSize", format!("{:#x}", self.stripped_size), None);
+        dump.push_field("AddressOfEntryPoint", format!("{:#x}", self.address_of_entry_point), None);
+        dump.push_field("BaseOfCode", format!("{:#x}", self.base_of_code), None);
+        dump.push_field("ImageBase", format!("{:#x}", self.image_base), None);
+        dump.push_field("BaseRelocationTable", format!("address: {:#x} sz: {:#x}", self.base_relocation_table.virtual_address, self.base_relocation_table.size), None);
+        dump.push_field("Debug", format!("address: {:#x} sz: {:#x}", self.debug.virtual_address, self.debug.size), None);
 
         return dump;
     }
@@ -1861,12 +4936,38 @@ pub enum PEArchitecture {
 #[derive(Default, Debug)]
 pub struct PE {
     pub header: PEHeader,
-    pub sections: HashMap<String, Section>,
+    pub dos_stub: DosStub,
+    /// In the order of the section table, packed and handcrafted images can have duplicate or empty names
+    pub sections: Vec<Section>,
+    /// Sections sorted by address, see index_sections
+    pub section_ranges: Vec<SectionRange>,
     pub import_directory_table: Option<ImportDirectoryTable>,
     pub import_lookup_tables: Option<Vec<ImportLookupTable>>,
     pub hint_name_table: Option<HintNameTable>,
+    pub delay_import_table: Option<DelayImportTable>,
+    pub bound_import_table: Option<BoundImportTable>,
+    pub export_data: Option<ExportData>,
     pub debug_directory: Option<DebugDirectory>,
+    /// Every entry of the debug directory with its decoded data
+    pub debug_entries: Vec<DebugEntry>,
+    /// RSDS record of the first CodeView entry of the debug directory
+    pub codeview: Option<CodeViewRecord>,
+    /// Matching PDB and its public symbols by RVA, only loaded on demand (see set_pdb_symbols)
+    pub pdb_path: Option<PathBuf>,
+    pub pdb_symbols: BTreeMap<u32, String>,
     pub exception_table: Option<ExceptionTable>,
+    pub resource_table: Option<ResourceTable>,
+    pub dotnet: Option<DotNetData>,
+    pub tls_directory: Option<TlsDirectory>,
+    pub load_config: Option<LoadConfigDirectory>,
+    pub certificate_table: Option<CertificateTable>,
+    pub base_relocation_table: Option<BaseRelocationTable>,
+    pub coff_symbol_table: Option<CoffSymbolTable>,
+    pub import_address_table: Option<ImportAddressTable>,
+    /// The input is a module dumped from process memory, laid out at its virtual addresses
+    pub memory_image: bool,
+    /// Header of a TE image, its PE headers are synthesized from it
+    pub te_header: Option<TeHeader>,
 }
 
 impl PE {
@@ -1908,161 +5009,591 @@ impl PE {
         return self.header.nt.coff_header.number_of_sections as usize;
     }
 
-    pub fn convert_rva_to_file_offset(&self, rva: u32) -> Option<u64> {
-        for section in self.sections.values() {
-            let start = section.header.virtual_address;
-            let end = start + section.header.virtual_size;
+    /// File offset of the CheckSum field, right after the signature, the COFF header and the first optional header fields
+    pub fn get_checksum_offset(&self) -> u64 {
+        return self.get_optional_header_offset() + OPTIONAL_HEADER_CHECKSUM_OFFSET;
+    }
+
+    /// File offset of the COFF header, after the "PE\0\0" signature
+    pub fn get_coff_header_offset(&self) -> u64 {
+        return self.header.dos.e_lfanew as u64 + 4;
+    }
+
+    pub fn get_optional_header_offset(&self) -> u64 {
+        return self.get_coff_header_offset() + 20;
+    }
+
+    /// File offset of the section table, right after the optional header
+    pub fn get_section_table_offset(&self) -> u64 {
+        return self.get_optional_header_offset() + self.get_size_of_optional_header();
+    }
+
+    /// File offset of the ImageBase field of the optional header
+    pub fn get_image_base_offset(&self) -> u64 {
+        let image_base_offset = match self.get_architecture() {
+            PEArchitecture::PE32 => PE32_IMAGE_BASE_OFFSET,
+            PEArchitecture::PE64 => PE64_IMAGE_BASE_OFFSET,
+        };
+
+        return self.get_optional_header_offset() + image_base_offset;
+    }
+
+    /// File offset of the given entry of the optional header data directories
+    pub fn get_data_directory_offset(&self, index: usize) -> u64 {
+        let data_directories_offset = match self.get_architecture() {
+            PEArchitecture::PE32 => PE32_DATA_DIRECTORIES_OFFSET,
+            PEArchitecture::PE64 => PE64_DATA_DIRECTORIES_OFFSET,
+        };
+
+        return self.get_optional_header_offset() + data_directories_offset + index as u64 * 8;
+    }
+
+    /// Builds the sorted ranges used by convert_rva_to_file_offset, to call again when the sections change
+    pub fn index_sections(&mut self) {
+        let section_alignment = (self.get_optional_header().get_section_alignment() as u64).max(1);
+
+        self.section_ranges = self
+            .sections
+            .iter()
+            .map(|section| {
+                /* The loader maps the virtual size (the raw size when zero) rounded up to the section alignment */
+                let size = match section.header.virtual_size {
+                    0 => section.header.size_of_raw_data,
+                    virtual_size => virtual_size,
+                } as u64;
+
+                let mapped_size = size.div_ceil(section_alignment) * section_alignment;
+
+                SectionRange {
+                    virtual_address: section.header.virtual_address,
+                    mapped_size: mapped_size.min(u32::MAX as u64) as u32,
+                    ptr_to_raw_data: section.header.ptr_to_raw_data,
+                    file_size: (section.header.size_of_raw_data as u64).min(mapped_size) as u32,
+                }
+            })
+            .collect();
+
+        self.section_ranges.sort_by_key(|range| range.virtual_address);
+    }
+
+    /// First section with the name, the later ones of a duplicated name are only reached by index
+    pub fn get_section(&self, name: &str) -> Option<&Section> {
+        return self.sections.iter().find(|s| s.header.name == name);
+    }
+
+    pub fn has_section(&self, name: &str) -> bool {
+        return self.get_section(name).is_some();
+    }
+
+    /// Index of the first section with the name in the section table
+    pub fn get_section_index(&self, name: &str) -> Option<usize> {
+        return self.sections.iter().position(|s| s.header.name == name);
+    }
+
+    /// Section mapped at a RVA, in its virtual or raw size whichever is larger
+    pub fn get_section_for_rva(&self, rva: u32) -> Option<&Section> {
+        return self.sections.iter().find(|s| {
+            let size = s.header.virtual_size.max(s.header.size_of_raw_data) as u64;
+
+            rva >= s.header.virtual_address && (rva as u64) < s.header.virtual_address as u64 + size
+        });
+    }
+
+    /// File offset of a RVA, None when it is outside of the sections or in their zero-filled part
+    pub fn convert_rva_to_file_offset(&self, rva: u32) -> Option<u64> {
+        let index = self.section_ranges.partition_point(|range| range.virtual_address <= rva);
+
+        let range = self.section_ranges.get(index.checked_sub(1)?)?;
+        let offset_in_section = rva - range.virtual_address;
+
+        if offset_in_section >= range.mapped_size || offset_in_section >= range.file_size {
+            return None;
+        }
+
+        return Some(range.ptr_to_raw_data as u64 + offset_in_section as u64);
+    }
+
+    /// The raw data of the headers, of the overlay and past the mapped size of a section has no RVA
+    pub fn convert_file_offset_to_rva(&self, offset: u64) -> Option<u32> {
+        let range = self
+            .section_ranges
+            .iter()
+            .find(|range| offset >= range.ptr_to_raw_data as u64 && offset < range.ptr_to_raw_data as u64 + range.file_size as u64)?;
+
+        return Some(range.virtual_address + (offset - range.ptr_to_raw_data as u64) as u32);
+    }
+
+    /// Parses the DOS, NT and optional headers, returns the section headers in table order
+    pub fn parse_headers(
+        &mut self,
+        cursor: &mut PEReader,
+    ) -> Result<Vec<SectionHeader>, Box<dyn std::error::Error>> {
+        let dos_header = DOSHeader::from_parser(cursor)?;
+
+        self.dos_stub = DosStub::from_parser(cursor, &dos_header)?;
+
+        cursor.set_position(dos_header.e_lfanew as u64)?;
+
+        let nt_header = NTHeader::from_parser(cursor)?;
+
+        let start_of_optional_position = cursor.position();
+
+        let optional_magic: u16 = cursor.read_u16()?;
+        cursor.set_position(start_of_optional_position)?;
+
+        match optional_magic {
+            PE_FORMAT_32_MAGIC => {
+                let optional_header: OptionalHeader32 = OptionalHeader32::from_parser(cursor)?;
+
+                self.header = PEHeader {
+                    dos: dos_header,
+                    nt: nt_header,
+                    optional: OptionalHeader::PE32(optional_header),
+                };
+            }
+            PE_FORMAT_64_MAGIC => {
+                let optional_header: OptionalHeader64 = OptionalHeader64::from_parser(cursor)?;
+
+                self.header = PEHeader {
+                    dos: dos_header,
+                    nt: nt_header,
+                    optional: OptionalHeader::PE64(optional_header),
+                };
+            }
+            _ => {
+                return Err("Invalid PE optional header magic".into());
+            }
+        }
+
+        let end_of_optional_position = cursor.position();
+        let optional_size = end_of_optional_position - start_of_optional_position;
+
+        cursor.set_position(end_of_optional_position + (self.get_size_of_optional_header() - optional_size))?;
+
+        let mut section_headers = Vec::with_capacity(self.get_number_of_sections());
+
+        for _ in 0..self.get_number_of_sections() {
+            section_headers.push(SectionHeader::from_parser(cursor)?);
+        }
+
+        return Ok(section_headers);
+    }
+
+    /// Parses a TE header and its section table. The PE headers are synthesized from the fields the TE header keeps,
+    /// the alignments, the timestamps and the other data directories are lost with the stripping
+    pub fn parse_te_headers(
+        &mut self,
+        cursor: &mut PEReader,
+    ) -> Result<Vec<SectionHeader>, Box<dyn std::error::Error>> {
+        let te_header = TeHeader::from_parser(cursor)?;
+
+        if (te_header.stripped_size as u64) < TE_HEADER_SIZE {
+            return Err(format!("Invalid TE StrippedSize {:#x}", te_header.stripped_size).into());
+        }
+
+        let mut section_headers = Vec::with_capacity(te_header.number_of_sections as usize);
+
+        for _ in 0..te_header.number_of_sections {
+            let mut section_header = SectionHeader::from_parser(cursor)?;
+
+            /* The raw pointers are the ones of the original PE, before the headers were stripped */
+            section_header.ptr_to_raw_data = section_header.ptr_to_raw_data.saturating_sub(te_header.stripped_offset());
+
+            section_headers.push(section_header);
+        }
+
+        let size_of_image = section_headers
+            .iter()
+            .map(|header| header.virtual_address as u64 + header.virtual_size.max(header.size_of_raw_data) as u64)
+            .max()
+            .unwrap_or(0)
+            .max(te_header.stripped_size as u64)
+            .min(u32::MAX as u64) as u32;
+
+        let mut nt_header = NTHeader::default();
+
+        nt_header.coff_header.machine = te_header.machine;
+        nt_header.coff_header.number_of_sections = te_header.number_of_sections as u16;
+
+        let optional_header = match te_header.is_32_bits() {
+            true => OptionalHeader::PE32(OptionalHeader32 {
+                magic: PE_FORMAT_32_MAGIC,
+                address_of_entry_point: te_header.address_of_entry_point,
+                base_of_code: te_header.base_of_code,
+                image_base: te_header.image_base as u32,
+                size_of_image,
+                size_of_headers: te_header.stripped_size as u32,
+                subsystem: te_header.subsystem as u16,
+                number_of_rva_and_sizes: 16,
+                base_relocation_table: te_header.base_relocation_table.clone(),
+                debug: te_header.debug.clone(),
+                ..Default::default()
+            }),
+            false => OptionalHeader::PE64(OptionalHeader64 {
+                magic: PE_FORMAT_64_MAGIC,
+                address_of_entry_point: te_header.address_of_entry_point,
+                base_of_code: te_header.base_of_code,
+                image_base: te_header.image_base,
+                size_of_image,
+                size_of_headers: te_header.stripped_size as u32,
+                subsystem: te_header.subsystem as u16,
+                number_of_rva_and_sizes: 16,
+                base_relocation_table: te_header.base_relocation_table.clone(),
+                debug: te_header.debug.clone(),
+                ..Default::default()
+            }),
+        };
+
+        self.header = PEHeader {
+            dos: DOSHeader::default(),
+            nt: nt_header,
+            optional: optional_header,
+        };
+
+        self.te_header = Some(te_header);
+
+        return Ok(section_headers);
+    }
+
+    /// In a memory image the sections sit at their virtual address: the raw pointers become the virtual addresses and
+    /// the raw sizes the mapped sizes, as far as the dump goes
+    fn reinterpret_memory_layout(&self, section_headers: &mut [SectionHeader], image_length: u64) {
+        let section_alignment = (self.get_optional_header().get_section_alignment() as u64).max(1);
+
+        for header in section_headers.iter_mut() {
+            let size = match header.virtual_size {
+                0 => header.size_of_raw_data,
+                virtual_size => virtual_size,
+            } as u64;
+
+            let mapped_size = size.div_ceil(section_alignment) * section_alignment;
+
+            header.ptr_to_raw_data = header.virtual_address;
+            header.size_of_raw_data = mapped_size.min(image_length.saturating_sub(header.virtual_address as u64)).min(u32::MAX as u64) as u32;
+        }
+    }
+
+    /// Parses the headers and the section table, the content of the sections is only read with section_data
+    pub fn parse_headers_and_sections(
+        &mut self,
+        cursor: &mut PEReader,
+        section_data: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut section_headers = match cursor.peek_n::<2>()? == TE_MAGIC_ARRAY {
+            true => self.parse_te_headers(cursor)?,
+            false => self.parse_headers(cursor)?,
+        };
+
+        if self.memory_image {
+            self.reinterpret_memory_layout(&mut section_headers, cursor.length());
+        }
+
+        for section_header in section_headers {
+            let section_data = match section_data {
+                true => {
+                    let mut data: Vec<u8> = vec![0; section_header.data_size()];
+
+                    /* Only SizeOfRawData bytes are backed by the file, the rest is zero-filled like the loader does (.bss) */
+                    let file_size = (section_header.size_of_raw_data as u64)
+                        .min(cursor.length().saturating_sub(section_header.ptr_to_raw_data as u64))
+                        .min(data.len() as u64) as usize;
+
+                    cursor.set_position(section_header.ptr_to_raw_data as u64)?;
+                    cursor.read_exact(&mut data[..file_size])?;
+
+                    data
+                }
+                false => Vec::new(),
+            };
+
+            self.sections.push(Section {
+                header: section_header,
+                data: section_data,
+            });
+        }
+
+        self.index_sections();
+
+        return Ok(());
+    }
+
+    /// DLL name, lookup table and Hint/Name entries of an import descriptor, regular or delay-load. The Hint/Name
+    /// addresses of the lookup table are relative to address_base, the image base for the VA-based delay-load tables
+    fn parse_imported_dll(
+        &self,
+        cursor: &mut PEReader,
+        index: usize,
+        name_rva: u32,
+        ilt_rva: u32,
+        address_base: u64,
+        warnings: &mut Vec<String>,
+    ) -> Result<(HintNameData, ImportLookupTable), Box<dyn std::error::Error>> {
+        let mut hnd = HintNameData::default();
+
+        match self.convert_rva_to_file_offset(name_rva) {
+            Some(dll_name_offset) => {
+                cursor.set_position(dll_name_offset)?;
+
+                match HintNameData::parse_dll_name(cursor) {
+                    Ok(raw_dll_name) => {
+                        let escaped;
+                        (hnd.dll_name, escaped) = decode_name(&raw_dll_name);
+                        hnd.raw_dll_name = raw_dll_name;
+
+                        if escaped {
+                            warnings.push(format!("{}: DLL name is not printable UTF-8, non-printable bytes are escaped", hnd.dll_name));
+                        }
+                    }
+                    Err(_) => warnings.push(format!("Descriptor {}: DLL name at {:#x} truncated by the end of the file", index, name_rva)),
+                }
+            }
+            None => warnings.push(format!("Descriptor {}: DLL name RVA {:#x} is outside of the sections", index, name_rva)),
+        }
+
+        let Some(ilt_offset) = self.convert_rva_to_file_offset(ilt_rva) else {
+            warnings.push(format!("{}: Import Lookup Table RVA {:#x} is outside of the sections", hnd.dll_name, ilt_rva));
+            return Ok((hnd, ImportLookupTable::default()));
+        };
+
+        cursor.set_position(ilt_offset)?;
+
+        let mut ilt = ImportLookupTable::from_parser(cursor, self.is_32_bits())?;
+
+        for ilt_entry in ilt.entries.iter_mut().filter(|e| !e.by_ordinal && address_base != 0) {
+            ilt_entry.hint_name_table_rva = (ilt_entry.hint_name_table_rva as u64).wrapping_sub(address_base) as u32;
+        }
+
+        if ilt.truncated {
+            warnings.push(format!("{}: Import Lookup Table at {:#x} has no null entry, stopped after {} entries", hnd.dll_name, ilt_rva, ilt.len()));
+        }
+
+        for ilt_entry in ilt.entries.iter() {
+            if ilt_entry.reserved_bits_set {
+                warnings.push(format!("{}: Import Lookup Table entry {:#x} has reserved bits set", hnd.dll_name, ilt_entry.raw));
+            }
+
+            if ilt_entry.by_ordinal {
+                continue;
+            }
+
+            /* Placeholders keep the names in lookup table order */
+            let Some(hne_offset) = self.convert_rva_to_file_offset(ilt_entry.hint_name_table_rva) else {
+                warnings.push(format!("{}: Hint/Name RVA {:#x} is outside of the sections", hnd.dll_name, ilt_entry.hint_name_table_rva));
+                hnd.entries.push(HintNameEntry::default());
+                continue;
+            };
+
+            cursor.set_position(hne_offset)?;
+
+            match HintNameEntry::from_parser(cursor) {
+                Ok(hne) => {
+                    if hne.name_escaped {
+                        warnings.push(format!("{}: Hint/Name entry at {:#x} is not printable UTF-8, non-printable bytes are escaped", hnd.dll_name, ilt_entry.hint_name_table_rva));
+                    }
 
-            if rva >= start && rva < end {
-                let offset_in_section = (rva - start) as u64;
-                return Some(section.header.ptr_to_raw_data as u64 + offset_in_section);
+                    hnd.entries.push(hne);
+                }
+                Err(_) => {
+                    warnings.push(format!("{}: Hint/Name entry at {:#x} truncated by the end of the file", hnd.dll_name, ilt_entry.hint_name_table_rva));
+                    hnd.entries.push(HintNameEntry::default());
+                }
             }
         }
 
-        return None;
+        return Ok((hnd, ilt));
     }
 
-    pub fn parse_headers_and_sections(
+    pub fn parse_import_data(
         &mut self,
-        cursor: &mut io::Cursor<&Vec<u8>>,
+        cursor: &mut PEReader,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let dos_header = DOSHeader::from_parser(cursor)?;
+        let import_table_idd = self.get_optional_header().get_import_table_idd();
+        let itd_file_offset = self.convert_rva_to_file_offset(import_table_idd.virtual_address);
 
-        cursor.set_position(dos_header.e_lfanew as u64);
+        if let Some(file_offset) = itd_file_offset.filter(|_| import_table_idd.virtual_address != 0) {
+            cursor.set_position(file_offset)?;
 
-        let nt_header = NTHeader::from_parser(cursor)?;
+            let mut import_directory_table = ImportDirectoryTable::from_parser(cursor, import_table_idd.size)?;
+            let mut hint_name_table = HintNameTable::default();
 
-        let optional_magic: u16 = cursor.read_u16::<LittleEndian>()?;
-        cursor.set_position(cursor.position() - 2);
+            let mut import_lookup_tables = Vec::new();
 
-        let start_of_optional_position = cursor.position();
+            /* Every descriptor gets a lookup table and a Hint/Name entry, possibly empty, to keep them aligned */
+            for (i, idt) in import_directory_table.entries.iter().enumerate() {
+                /* Some linkers leave the lookup table out, the address table holds the same thunks on disk */
+                let ilt_rva = match idt.import_lookup_table_rva {
+                    0 => idt.import_address_table_rva,
+                    rva => rva,
+                };
 
-        match optional_magic {
-            PE_FORMAT_32_MAGIC => {
-                let optional_header: OptionalHeader32 = OptionalHeader32::from_parser(cursor)?;
+                let (hnd, ilt) = self.parse_imported_dll(cursor, i, idt.name_rva, ilt_rva, 0, &mut import_directory_table.warnings)?;
 
-                self.header = PEHeader {
-                    dos: dos_header,
-                    nt: nt_header,
-                    optional: OptionalHeader::PE32(optional_header),
-                };
+                hint_name_table.entries.push(hnd);
+                import_lookup_tables.push(ilt);
             }
-            PE_FORMAT_64_MAGIC => {
-                let optional_header: OptionalHeader64 = OptionalHeader64::from_parser(cursor)?;
 
-                self.header = PEHeader {
-                    dos: dos_header,
-                    nt: nt_header,
-                    optional: OptionalHeader::PE64(optional_header),
-                };
-            }
-            _ => {
-                return Err("Invalid PE optional header magic".into());
-            }
+            self.import_directory_table = Some(import_directory_table);
+            self.import_lookup_tables = Some(import_lookup_tables);
+            self.hint_name_table = Some(hint_name_table);
         }
 
-        let end_of_optional_position = cursor.position();
-        let optional_size = end_of_optional_position - start_of_optional_position;
+        return Ok(());
+    }
 
-        cursor
-            .set_position(cursor.position() + (self.get_size_of_optional_header() - optional_size));
+    /// Slots of the Import Address Table directory, read independently of the import descriptors which may point
+    /// elsewhere or to a stripped lookup table
+    pub fn parse_import_address_table(
+        &mut self,
+        cursor: &mut PEReader,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let iat_idd = self.get_optional_header().get_import_address_table_idd().clone();
 
-        for _ in 0..self.get_number_of_sections() {
-            let section_header = SectionHeader::from_parser(cursor)?;
+        if iat_idd.virtual_address == 0 || iat_idd.size == 0 {
+            return Ok(());
+        }
 
-            let previous_position = cursor.position();
+        let Some(file_offset) = self.convert_rva_to_file_offset(iat_idd.virtual_address) else {
+            return Ok(());
+        };
+
+        let thunk_size: u32 = if self.is_32_bits() { 4 } else { 8 };
+        let image_base = self.get_optional_header().get_image_base();
 
-            let mut section_data: Vec<u8> = vec![0; section_header.data_size()];
+        let mut iat = ImportAddressTable {
+            rva: iat_idd.virtual_address,
+            size: iat_idd.size,
+            ..Default::default()
+        };
 
-            cursor.set_position(section_header.ptr_to_raw_data as u64);
-            cursor.read_exact(&mut section_data)?;
+        let slots_count = ((iat_idd.size / thunk_size) as usize).min(MAX_IMPORT_LOOKUP_ENTRIES);
 
-            self.sections.insert(
-                section_header.name.clone(),
-                Section {
-                    header: section_header,
-                    data: section_data,
-                },
-            );
+        for i in 0..slots_count {
+            let rva = iat_idd.virtual_address + i as u32 * thunk_size;
 
-            cursor.set_position(previous_position);
+            /* Reading the Hint/Name entries moves the cursor away from the table */
+            let thunk = cursor
+                .set_position(file_offset + (i as u32 * thunk_size) as u64)
+                .ok()
+                .and_then(|_| ImportLookupTableEntry::from_parser(cursor, self.is_32_bits()).ok());
+
+            let Some(thunk) = thunk else {
+                iat.warnings.push(format!("Import Address Table truncated by the end of the file after {} slots", iat.slots.len()));
+                break;
+            };
+
+            /* Bound thunks hold addresses in the image, the others the RVA of their Hint/Name entry */
+            let hint_name = match thunk.by_ordinal || thunk.is_zeroed_out() || thunk.raw >= image_base {
+                true => None,
+                false => self.convert_rva_to_file_offset(thunk.hint_name_table_rva).and_then(|offset| {
+                    cursor.set_position(offset).ok()?;
+                    HintNameEntry::from_parser(cursor).ok().filter(|hne| !hne.name.is_empty())
+                }),
+            };
+
+            iat.slots.push(ImportAddressTableSlot { rva, thunk, hint_name });
         }
 
+        self.import_address_table = Some(iat);
+
         return Ok(());
     }
 
-    pub fn parse_import_data(
+    pub fn parse_delay_import_data(
         &mut self,
-        cursor: &mut io::Cursor<&Vec<u8>>,
+        cursor: &mut PEReader,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let import_table_idd = self.get_optional_header().get_import_table_idd();
-        let itd_file_offset = self.convert_rva_to_file_offset(import_table_idd.virtual_address);
+        let delay_import_idd = self.get_optional_header().get_delay_import_descriptor_idd();
 
-        if let Some(file_offset) = itd_file_offset {
-            cursor.set_position(file_offset);
+        if delay_import_idd.virtual_address == 0 {
+            return Ok(());
+        }
 
-            let import_directory_table = ImportDirectoryTable::from_parser(cursor)?;
-            let mut hint_name_table = HintNameTable::default();
+        let Some(file_offset) = self.convert_rva_to_file_offset(delay_import_idd.virtual_address) else {
+            return Ok(());
+        };
 
-            let mut import_lookup_tables = Vec::new();
+        let image_base = self.get_optional_header().get_image_base();
+        let max_descriptors = (delay_import_idd.size as u64 / DELAY_IMPORT_DESCRIPTOR_SIZE).max(1);
+
+        let mut table = DelayImportTable::default();
+
+        cursor.set_position(file_offset)?;
 
-            for idt in import_directory_table.entries.iter() {
-                let ilt_offset = self
-                    .convert_rva_to_file_offset(idt.import_lookup_table_rva)
-                    .expect("Cannot find file offset for Import Lookup Table");
-                cursor.set_position(ilt_offset);
+        /* Like the regular import directory, the size may or may not count the null descriptor */
+        loop {
+            let Ok(descriptor) = DelayImportDescriptor::from_parser(cursor) else {
+                table.warnings.push(format!("Delay import directory truncated by the end of the file after {} descriptors", table.descriptors.len()));
+                break;
+            };
 
-                let ilt = ImportLookupTable::from_parser(cursor, self.is_32_bits())?;
+            if descriptor.is_zeroed_out() {
+                break;
+            }
 
-                let mut hnd = HintNameData::default();
+            if table.descriptors.len() as u64 >= max_descriptors {
+                table.warnings.push(format!("Delay import directory has no null descriptor within its size ({:#x}), stopped after {} descriptors", delay_import_idd.size, table.descriptors.len()));
+                break;
+            }
 
-                let dll_name_offset = self
-                    .convert_rva_to_file_offset(idt.name_rva)
-                    .expect("Cannot find file offset_for_dll_name");
+            table.descriptors.push(descriptor);
+        }
 
-                cursor.set_position(dll_name_offset);
+        for (i, descriptor) in table.descriptors.iter().enumerate() {
+            let name_rva = descriptor.to_rva(descriptor.name_rva, image_base);
+            let int_rva = descriptor.to_rva(descriptor.delay_import_name_table_rva, image_base);
 
-                hnd.dll_name = HintNameData::parse_dll_name(cursor)?;
+            /* The name table of the old descriptors points to the Hint/Name entries by VA too */
+            let address_base = if descriptor.attributes & DELAY_IMPORT_RVA_BASED == 0 { image_base } else { 0 };
 
-                for ilt_entry in ilt.entries.iter() {
-                    if ilt_entry.by_ordinal {
-                        continue;
-                    }
+            let (hnd, ilt) = self.parse_imported_dll(cursor, i, name_rva, int_rva, address_base, &mut table.warnings)?;
 
-                    let ilt_offset = self
-                        .convert_rva_to_file_offset(ilt_entry.hint_name_table_rva)
-                        .expect("Cannot find file offset for Hint/Name table entry");
+            table.hint_name_table.entries.push(hnd);
+            table.lookup_tables.push(ilt);
+        }
 
-                    cursor.set_position(ilt_offset);
+        self.delay_import_table = Some(table);
 
-                    hnd.entries.push(HintNameEntry::from_parser(cursor)?);
-                }
+        return Ok(());
+    }
 
-                hint_name_table.entries.push(hnd);
+    pub fn parse_bound_import_table(
+        &mut self,
+        cursor: &mut PEReader,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let bound_import_idd = self.get_optional_header().get_bound_import_idd();
 
-                import_lookup_tables.push(ilt);
-            }
+        if bound_import_idd.virtual_address == 0 || bound_import_idd.size == 0 {
+            return Ok(());
+        }
 
-            self.import_directory_table = Some(import_directory_table);
-            self.import_lookup_tables = Some(import_lookup_tables);
-            self.hint_name_table = Some(hint_name_table);
+        /* The table is in the headers, mapped at the same offsets as in the file */
+        let file_offset = match (bound_import_idd.virtual_address as u64) < self.get_optional_header().get_size_of_headers() as u64 {
+            true => Some(bound_import_idd.virtual_address as u64),
+            false => self.convert_rva_to_file_offset(bound_import_idd.virtual_address),
+        };
+
+        if let Some(file_offset) = file_offset {
+            cursor.set_position(file_offset)?;
+
+            /* Stale or garbage binding data is not worth failing the whole parse */
+            self.bound_import_table = BoundImportTable::from_parser(cursor, bound_import_idd.size).ok();
         }
 
         return Ok(());
     }
 
-    #[allow(dead_code)]
     pub fn parse_export_data(
         &mut self,
-        cursor: &mut io::Cursor<&Vec<u8>>,
+        cursor: &mut PEReader,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let export_table_idd = self.get_optional_header().get_export_table_idd();
-        let etd_offset = self.convert_rva_to_file_offset(export_table_idd.virtual_address);
 
-        if let Some(file_offset) = etd_offset {
-            cursor.set_position(file_offset);
+        if export_table_idd.virtual_address > 0 && let Some(file_offset) = self.convert_rva_to_file_offset(export_table_idd.virtual_address) {
+            cursor.set_position(file_offset)?;
 
-            let edt = ExportDirectoryTable::from_parser(cursor)?;
+            let export_data = ExportData::from_parser(cursor, self)?;
+
+            self.export_data = Some(export_data);
         }
 
         return Ok(());
@@ -2070,7 +5601,7 @@ impl PE {
 
     pub fn parse_debug_directory(
         &mut self,
-        cursor: &mut io::Cursor<&Vec<u8>>,
+        cursor: &mut PEReader,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let debug_va = self.get_optional_header().get_debug_idd().virtual_address;
 
@@ -2078,20 +5609,60 @@ impl PE {
             let debug_fo = self.convert_rva_to_file_offset(debug_va);
 
             if let Some(dfo) = debug_fo {
-                cursor.set_position(dfo as u64);
+                cursor.set_position(dfo)?;
 
                 let debug_directory = DebugDirectory::from_parser(cursor)?;
 
                 self.debug_directory = Some(debug_directory);
+
+                let entries = self.get_optional_header().get_debug_idd().size as u64 / DEBUG_DIRECTORY_ENTRY_SIZE;
+
+                for i in 0..entries.min(MAX_DEBUG_DIRECTORY_ENTRIES) {
+                    cursor.set_position(dfo + i * DEBUG_DIRECTORY_ENTRY_SIZE)?;
+
+                    let entry = DebugDirectory::from_parser(cursor)?;
+
+                    /* In a memory image the data is found at its address, the entries without one are not mapped */
+                    let data_offset = match (self.memory_image, &self.te_header) {
+                        (true, _) => entry.address_of_raw_data,
+                        (false, Some(te_header)) => entry.pointer_to_raw_data.saturating_sub(te_header.stripped_offset()),
+                        (false, None) => entry.pointer_to_raw_data,
+                    };
+
+                    /* A broken debug data only loses its decoding */
+                    let data = cursor
+                        .sub_reader(data_offset as u64, entry.size_of_data as u64)
+                        .ok()
+                        .filter(|_| data_offset != 0)
+                        .and_then(|mut data| DebugEntryData::from_parser(&mut data, DebugType::from(entry.debug_type)).ok().flatten());
+
+                    if let (None, Some(DebugEntryData::CodeView(codeview))) = (&self.codeview, &data) {
+                        self.codeview = Some(codeview.clone());
+                    }
+
+                    self.debug_entries.push(DebugEntry { directory: entry, data });
+                }
             }
         }
 
         return Ok(());
     }
 
+    /// Attaches the PDB public symbols, they name the exports and the functions in the disassembly
+    pub fn set_pdb_symbols(&mut self, path: PathBuf, symbols: BTreeMap<u32, String>) {
+        if let Some(ref mut export_data) = self.export_data {
+            for entry in export_data.entries.iter_mut().filter(|e| e.forwarder.is_none()) {
+                entry.symbol = symbols.get(&entry.rva).cloned();
+            }
+        }
+
+        self.pdb_path = Some(path);
+        self.pdb_symbols = symbols;
+    }
+
     pub fn parse_exception_table(
         &mut self,
-        cursor: &mut io::Cursor<&Vec<u8>>,
+        cursor: &mut PEReader,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let exception_va = self
             .get_optional_header()
@@ -2102,7 +5673,7 @@ impl PE {
             let exception_fo = self.convert_rva_to_file_offset(exception_va);
 
             if let Some(efo) = exception_fo {
-                cursor.set_position(efo as u64);
+                cursor.set_position(efo)?;
 
                 let exception_table = ExceptionTable::from_parser(
                     cursor,
@@ -2111,31 +5682,356 @@ impl PE {
                 )?;
 
                 self.exception_table = Some(exception_table);
+                self.parse_unwind_info(cursor)?;
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// Follows the unwind information of the x64 function entries, the other architectures have their own formats
+    fn parse_unwind_info(
+        &mut self,
+        cursor: &mut PEReader,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if MachineType::from(self.get_nt_header().coff_header.machine) != MachineType::AMD64 {
+            return Ok(());
+        }
+
+        let Some(mut exception_table) = self.exception_table.take() else {
+            return Ok(());
+        };
+
+        for entry in exception_table.entries.iter_mut() {
+            let ExcFunctionEntry::X64(entry) = entry else {
+                continue;
+            };
+
+            /* The low bit marks an RVA to another function entry instead of an unwind info */
+            if (entry.unwind_information & 1) != 0 {
+                continue;
+            }
+
+            if let Some(ufo) = self.convert_rva_to_file_offset(entry.unwind_information) {
+                cursor.set_position(ufo)?;
+
+                /* A broken unwind info only loses its decoding */
+                entry.unwind_info = UnwindInfo::from_parser(cursor).ok().map(Box::new);
+            }
+        }
+
+        self.exception_table = Some(exception_table);
+
+        return Ok(());
+    }
+
+    pub fn parse_resource_table(
+        &mut self,
+        cursor: &mut PEReader,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let resource_va = self.get_optional_header().get_resource_table_idd().virtual_address;
+
+        if resource_va > 0 && let Some(rfo) = self.convert_rva_to_file_offset(resource_va) {
+            self.resource_table = Some(ResourceTable::from_parser(cursor, rfo, self));
+        }
+
+        return Ok(());
+    }
+
+    pub fn parse_tls_directory(
+        &mut self,
+        cursor: &mut PEReader,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let tls_va = self.get_optional_header().get_tls_table_idd().virtual_address;
+
+        if tls_va > 0 && let Some(tfo) = self.convert_rva_to_file_offset(tls_va) {
+            cursor.set_position(tfo)?;
+
+            let mut tls_directory = TlsDirectory::from_parser(cursor, self.is_32_bits())?;
+
+            let image_base = self.get_optional_header().get_image_base();
+            let callbacks_rva = tls_directory.address_of_callbacks.wrapping_sub(image_base) as u32;
+
+            if tls_directory.address_of_callbacks != 0 && let Some(cfo) = self.convert_rva_to_file_offset(callbacks_rva) {
+                cursor.set_position(cfo)?;
+                tls_directory.parse_callbacks(cursor, self.is_32_bits())?;
+            }
+
+            self.tls_directory = Some(tls_directory);
+        }
+
+        return Ok(());
+    }
+
+    pub fn parse_load_config(
+        &mut self,
+        cursor: &mut PEReader,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let load_config_va = self.get_optional_header().get_load_config_table_idd().virtual_address;
+
+        if load_config_va > 0 && let Some(lfo) = self.convert_rva_to_file_offset(load_config_va) {
+            cursor.set_position(lfo)?;
+
+            let mut load_config = LoadConfigDirectory::from_parser(cursor, self.is_32_bits())?;
+
+            let image_base = self.get_optional_header().get_image_base();
+            let table_rva = load_config.guard_cf_function_table.wrapping_sub(image_base) as u32;
+
+            if load_config.guard_cf_function_table != 0 && let Some(gfo) = self.convert_rva_to_file_offset(table_rva) {
+                cursor.set_position(gfo)?;
+                load_config.parse_guard_cf_functions(cursor)?;
+            }
+
+            let handlers_rva = load_config.se_handler_table.wrapping_sub(image_base) as u32;
+
+            if load_config.is_32_bits && load_config.se_handler_table != 0 && let Some(sfo) = self.convert_rva_to_file_offset(handlers_rva) {
+                cursor.set_position(sfo)?;
+                load_config.parse_se_handlers(cursor)?;
+            }
+
+            /* A broken hybrid metadata only loses its decoding, like the debug data */
+            if let Some(dfo) = self.get_dynamic_relocation_table_rva(cursor, &load_config).and_then(|rva| self.convert_rva_to_file_offset(rva)) {
+                load_config.dynamic_relocations = cursor
+                    .set_position(dfo)
+                    .ok()
+                    .and_then(|_| DynamicRelocationTable::from_parser(cursor, load_config.is_32_bits).ok());
+            }
+
+            let metadata_rva = load_config.chpe_metadata_pointer.wrapping_sub(image_base) as u32;
+
+            if !load_config.is_32_bits && load_config.chpe_metadata_pointer != 0 && let Some(mfo) = self.convert_rva_to_file_offset(metadata_rva) {
+                cursor.set_position(mfo)?;
+
+                let mut metadata = Arm64ECMetadata::from_parser(cursor)?;
+
+                if let Some(cfo) = self.convert_rva_to_file_offset(metadata.code_map) {
+                    cursor.set_position(cfo)?;
+                    metadata.parse_code_ranges(cursor)?;
+                }
+
+                load_config.arm64ec_metadata = Some(metadata);
             }
+
+            self.load_config = Some(load_config);
+        }
+
+        return Ok(());
+    }
+
+    /// Given by an offset in a section of the table, or by its VA in the older images
+    fn get_dynamic_relocation_table_rva(&self, cursor: &mut PEReader, load_config: &LoadConfigDirectory) -> Option<u32> {
+        if load_config.dynamic_value_reloc_table_section != 0 {
+            let header_offset = self.get_section_table_offset() + (load_config.dynamic_value_reloc_table_section as u64 - 1) * SECTION_HEADER_SIZE;
+
+            /* VirtualAddress follows the name and VirtualSize */
+            cursor.set_position(header_offset + 12).ok()?;
+
+            return cursor.read_u32().ok()?.checked_add(load_config.dynamic_value_reloc_table_offset);
+        }
+
+        if load_config.dynamic_value_reloc_table != 0 {
+            return Some(load_config.dynamic_value_reloc_table.wrapping_sub(self.get_optional_header().get_image_base()) as u32);
+        }
+
+        return None;
+    }
+
+    pub fn parse_clr_data(
+        &mut self,
+        cursor: &mut PEReader,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let clr_va = self.get_optional_header().get_clr_runtime_header_idd().virtual_address;
+
+        if clr_va > 0 && let Some(cfo) = self.convert_rva_to_file_offset(clr_va) {
+            cursor.set_position(cfo)?;
+
+            self.dotnet = Some(DotNetData::from_parser(cursor, self));
+        }
+
+        return Ok(());
+    }
+
+    pub fn parse_base_relocation_table(
+        &mut self,
+        cursor: &mut PEReader,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let relocation_idd = self.get_optional_header().get_base_relocation_table_idd();
+
+        let (relocation_va, relocation_size) = (relocation_idd.virtual_address, relocation_idd.size);
+
+        if relocation_va > 0 && relocation_size > 0 && let Some(rfo) = self.convert_rva_to_file_offset(relocation_va) {
+            cursor.set_position(rfo)?;
+
+            self.base_relocation_table = Some(BaseRelocationTable::from_parser(cursor, relocation_size)?);
+        }
+
+        return Ok(());
+    }
+
+    pub fn parse_certificate_table(
+        &mut self,
+        cursor: &mut PEReader,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        /* The certificates are never mapped, their directory holds a file offset */
+        if self.memory_image {
+            return Ok(());
+        }
+
+        let certificate_idd = self.get_optional_header().get_certificate_table_idd();
+
+        let (offset, size) = (certificate_idd.virtual_address, certificate_idd.size);
+
+        if offset > 0 && size > 0 && (offset as u64 + size as u64) <= cursor.length() {
+            self.certificate_table = Some(CertificateTable::from_parser(cursor, offset, size)?);
+        }
+
+        return Ok(());
+    }
+
+    pub fn parse_coff_symbol_table(
+        &mut self,
+        cursor: &mut PEReader,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.memory_image {
+            return Ok(());
+        }
+
+        let coff_header = &self.get_nt_header().coff_header;
+
+        let (offset, count) = (coff_header.pointer_to_symbol_table, coff_header.number_of_symbols.min(MAX_COFF_SYMBOLS));
+
+        if offset > 0 && count > 0 && (offset as u64 + count as u64 * COFF_SYMBOL_SIZE) <= cursor.length() {
+            self.coff_symbol_table = Some(CoffSymbolTable::from_parser(cursor, offset, count)?);
         }
 
         return Ok(());
     }
+
+    /// Section names in the order of the section table, the COFF symbols refer to them by their 1-based index
+    pub fn section_names_by_index(&self) -> Vec<String> {
+        return self.sections.iter().map(|s| s.header.name.clone()).collect();
+    }
+}
+
+/*
+ * Image checksum
+ */
+
+/* Offsets of the fields shared by the PE32 and PE32+ optional headers */
+pub const OPTIONAL_HEADER_SIZE_OF_CODE_OFFSET: u64 = 4;
+pub const OPTIONAL_HEADER_SIZE_OF_INITIALIZED_DATA_OFFSET: u64 = 8;
+pub const OPTIONAL_HEADER_SIZE_OF_UNINITIALIZED_DATA_OFFSET: u64 = 12;
+pub const OPTIONAL_HEADER_SIZE_OF_IMAGE_OFFSET: u64 = 56;
+pub const OPTIONAL_HEADER_SIZE_OF_HEADERS_OFFSET: u64 = 60;
+const OPTIONAL_HEADER_CHECKSUM_OFFSET: u64 = 64;
+
+pub const SECTION_HEADER_SIZE: u64 = 40;
+
+/* Offsets of the ImageBase field, 32 bits in PE32 (after BaseOfData) and 64 bits in PE32+ */
+const PE32_IMAGE_BASE_OFFSET: u64 = 28;
+const PE64_IMAGE_BASE_OFFSET: u64 = 24;
+
+/* Offsets of the data directories in the optional headers */
+const PE32_DATA_DIRECTORIES_OFFSET: u64 = 96;
+const PE64_DATA_DIRECTORIES_OFFSET: u64 = 112;
+
+/* Indices of the data directories */
+pub const DATA_DIRECTORY_EXPORT: usize = 0;
+pub const DATA_DIRECTORY_IMPORT: usize = 1;
+pub const DATA_DIRECTORY_RESOURCE: usize = 2;
+pub const DATA_DIRECTORY_CERTIFICATE: usize = 4;
+pub const DATA_DIRECTORY_DEBUG: usize = 6;
+pub const DATA_DIRECTORY_BOUND_IMPORT: usize = 11;
+pub const DATA_DIRECTORY_IMPORT_ADDRESS_TABLE: usize = 12;
+
+pub const DEBUG_DIRECTORY_ENTRY_SIZE: u64 = 28;
+
+/* Upper bound on the number of debug directory entries looked at, protects against corrupted directories */
+pub const MAX_DEBUG_DIRECTORY_ENTRIES: u64 = 64;
+
+/// Checksum as computed by CheckSumMappedFile: 16-bit one's complement sum of the file, skipping the CheckSum field, plus the file size
+pub fn compute_checksum(file_bytes: &[u8], checksum_offset: u64) -> u32 {
+    let mut sum: u64 = 0;
+
+    for (i, word) in file_bytes.chunks(2).enumerate() {
+        let offset = i as u64 * 2;
+
+        if offset >= checksum_offset && offset < checksum_offset + 4 {
+            continue;
+        }
+
+        let value = match word {
+            [low, high] => u16::from_le_bytes([*low, *high]),
+            [low] => *low as u16,
+            _ => 0,
+        };
+
+        sum += value as u64;
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+
+    sum = (sum & 0xffff) + (sum >> 16);
+
+    return (sum as u32).wrapping_add(file_bytes.len() as u32);
 }
 
 /*
  * Main parse method that reads from a file, tests if it's a PE file or not, parses and returns the parsed PE
  */
-pub fn parse_pe(file_path: &PathBuf) -> Result<PE, Box<dyn std::error::Error>> {
+pub fn parse_pe(file_path: &Path) -> Result<PE, Box<dyn std::error::Error>> {
+    return parse_pe_with(file_path, true);
+}
+
+/*
+ * The file is read on demand, only the headers, the data directories and, with section_data, the sections content are loaded
+ */
+pub fn parse_pe_with(file_path: &Path, section_data: bool) -> Result<PE, Box<dyn std::error::Error>> {
+    if !file_path.exists() {
+        return Err("File does not exist".into());
+    }
+
+    let mut file = FileReader::open(file_path)?;
+
+    return parse_pe_from_reader(&mut PEReader::new(&mut file), section_data);
+}
+
+/*
+ * Parses a module dumped from process memory, its sections are read at their virtual address instead of their raw pointer
+ */
+pub fn parse_pe_memory_image(file_path: &Path, section_data: bool) -> Result<PE, Box<dyn std::error::Error>> {
     if !file_path.exists() {
         return Err("File does not exist".into());
     }
 
-    let file_bytes = std::fs::read(file_path).expect("Unable to open file");
-    let mut cursor = io::Cursor::new(&file_bytes);
+    let mut file = FileReader::open(file_path)?;
+
+    return parse_pe_from_reader_as(&mut PEReader::new(&mut file), section_data, true);
+}
+
+pub fn parse_pe_from_reader(cursor: &mut PEReader, section_data: bool) -> Result<PE, Box<dyn std::error::Error>> {
+    return parse_pe_from_reader_as(cursor, section_data, false);
+}
 
+pub fn parse_pe_from_reader_as(cursor: &mut PEReader, section_data: bool, memory_image: bool) -> Result<PE, Box<dyn std::error::Error>> {
     let mut pe: PE = PE::new();
 
-    pe.parse_headers_and_sections(&mut cursor)?;
-    pe.parse_import_data(&mut cursor)?;
-    pe.parse_export_data(&mut cursor)?;
-    pe.parse_debug_directory(&mut cursor)?;
-    pe.parse_exception_table(&mut cursor)?;
+    pe.memory_image = memory_image;
+
+    pe.parse_headers_and_sections(cursor, section_data)?;
+    pe.parse_import_data(cursor)?;
+    pe.parse_import_address_table(cursor)?;
+    pe.parse_delay_import_data(cursor)?;
+    pe.parse_bound_import_table(cursor)?;
+    pe.parse_export_data(cursor)?;
+    pe.parse_debug_directory(cursor)?;
+    pe.parse_exception_table(cursor)?;
+    pe.parse_resource_table(cursor)?;
+    pe.parse_clr_data(cursor)?;
+    pe.parse_tls_directory(cursor)?;
+    pe.parse_load_config(cursor)?;
+    pe.parse_certificate_table(cursor)?;
+    pe.parse_base_relocation_table(cursor)?;
+    pe.parse_coff_symbol_table(cursor)?;
 
     return Ok(pe);
 }
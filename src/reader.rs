@@ -1,9 +1,16 @@
 use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::marker::PhantomData;
+use std::path::Path;
+
+use byteorder::ByteOrder;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ReaderError {
     UnexpectedEof,
     InvalidPosition,
+    Io(io::ErrorKind),
 }
 
 impl fmt::Display for ReaderError {
@@ -11,444 +18,357 @@ impl fmt::Display for ReaderError {
         match self {
             ReaderError::UnexpectedEof => write!(f, "Unexpected end of file"),
             ReaderError::InvalidPosition => write!(f, "Invalid position"),
+            ReaderError::Io(kind) => write!(f, "I/O error ({})", kind),
         }
     }
 }
 
 impl std::error::Error for ReaderError {}
 
-pub type ReaderResult<T> = Result<T, ReaderError>;
-
-/// Little Endian Reader
-#[derive(Debug)]
-pub struct LEReader<'a> {
-    data: &'a [u8],
-    position: usize,
-}
-
-impl<'a> LEReader<'a> {
-    pub fn new(data: &'a [u8]) -> LEReader<'a> {
-        return LEReader {
-            data,
-            position: 0,
-        };
-    }
-
-    #[inline]
-    pub fn read_bytes(&mut self, n: usize) -> ReaderResult<&[u8]> {
-        if self.position + n > self.data.len() {
-            return Err(ReaderError::UnexpectedEof);
+impl From<io::Error> for ReaderError {
+    fn from(error: io::Error) -> ReaderError {
+        match error.kind() {
+            io::ErrorKind::UnexpectedEof => return ReaderError::UnexpectedEof,
+            kind => return ReaderError::Io(kind),
         }
-
-        let bytes = &self.data[self.position..self.position + n];
-        self.position += n;
-
-        return Ok(bytes);
     }
+}
 
-    #[inline]
-    fn peek_bytes(&self, n: usize) -> ReaderResult<&[u8]> {
-        if self.position + n > self.data.len() {
-            return Err(ReaderError::UnexpectedEof);
-        }
+pub type ReaderResult<T> = Result<T, ReaderError>;
 
-        return Ok(&self.data[self.position..self.position + n]);
-    }
+/*
+ * Seekable readers, the PE parser only reads the regions it needs from them
+ */
 
-    #[inline]
-    pub fn read_u8(&mut self) -> ReaderResult<u8> {
-        let bytes = self.read_bytes(1)?;
-        return Ok(bytes[0]);
+/// Position helpers shared by files and in-memory buffers, a failed seek shows up as a failed read
+pub trait SeekRead: Read + Seek {
+    fn position(&mut self) -> u64 {
+        return self.stream_position().unwrap_or(0);
     }
 
-    #[inline]
-    pub fn read_i8(&mut self) -> ReaderResult<i8> {
-        return Ok(self.read_u8()? as i8);
+    fn set_position(&mut self, position: u64) {
+        let _ = self.seek(SeekFrom::Start(position));
     }
 
-    #[inline]
-    pub fn read_u16(&mut self) -> ReaderResult<u16> {
-        let bytes = self.read_bytes(2)?;
-        return Ok(u16::from_le_bytes([bytes[0], bytes[1]]));
-    }
+    /// Total size of the underlying data
+    fn length(&mut self) -> u64 {
+        let position = SeekRead::position(self);
+        let length = self.seek(SeekFrom::End(0)).unwrap_or(0);
 
-    #[inline]
-    pub fn read_i16(&mut self) -> ReaderResult<i16> {
-        let bytes = self.read_bytes(2)?;
-        return Ok(i16::from_le_bytes([bytes[0], bytes[1]]));
-    }
+        SeekRead::set_position(self, position);
 
-    #[inline]
-    pub fn read_u32(&mut self) -> ReaderResult<u32> {
-        let bytes = self.read_bytes(4)?;
-        return Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+        return length;
     }
+}
 
-    #[inline]
-    pub fn read_i32(&mut self) -> ReaderResult<i32> {
-        let bytes = self.read_bytes(4)?;
-        return Ok(i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
-    }
+impl<T: Read + Seek> SeekRead for T {}
 
-    #[inline]
-    pub fn read_u64(&mut self) -> ReaderResult<u64> {
-        let bytes = self.read_bytes(8)?;
-        return Ok(u64::from_le_bytes([
-            bytes[0], bytes[1], bytes[2], bytes[3],
-            bytes[4], bytes[5], bytes[6], bytes[7],
-        ]));
-    }
+/// Buffered file reader tracking its position, seeking inside the buffer does not hit the disk
+#[derive(Debug)]
+pub struct FileReader {
+    reader: BufReader<File>,
+    position: u64,
+    length: u64,
+}
 
-    #[inline]
-    pub fn read_i64(&mut self) -> ReaderResult<i64> {
-        let bytes = self.read_bytes(8)?;
-        return Ok(i64::from_le_bytes([
-            bytes[0], bytes[1], bytes[2], bytes[3],
-            bytes[4], bytes[5], bytes[6], bytes[7],
-        ]));
-    }
+impl FileReader {
+    pub fn open(path: &Path) -> io::Result<FileReader> {
+        let file = File::open(path)?;
+        let length = file.metadata()?.len();
 
-    #[inline]
-    pub fn read_n<const N: usize>(&mut self) -> ReaderResult<[u8; N]> {
-        let bytes = self.read_bytes(N)?;
-        let mut arr = [0u8; N];
-        arr.copy_from_slice(bytes);
-        return Ok(arr);
+        return Ok(FileReader {
+            reader: BufReader::new(file),
+            position: 0,
+            length,
+        });
     }
+}
 
-    #[inline]
-    pub fn peek(&self) -> ReaderResult<u8> {
-        if self.position >= self.data.len() {
-            return Err(ReaderError::UnexpectedEof);
-        }
+impl Read for FileReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        self.position += read as u64;
 
-        return Ok(self.data[self.position]);
+        return Ok(read);
     }
+}
 
-    #[inline]
-    pub fn peek_n<const N: usize>(&self) -> ReaderResult<[u8; N]> {
-        let bytes = self.peek_bytes(N)?;
-        let mut arr = [0u8; N];
-        arr.copy_from_slice(bytes);
-        return Ok(arr);
-    }
+impl Seek for FileReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => offset as i128,
+            SeekFrom::End(offset) => self.length as i128 + offset as i128,
+            SeekFrom::Current(offset) => self.position as i128 + offset as i128,
+        };
 
-    #[inline]
-    pub fn peek_at<const N: usize>(&self) -> ReaderResult<u8> {
-        if (self.position + N) >= self.data.len() {
-            return Err(ReaderError::UnexpectedEof);
+        if target < 0 || target > i64::MAX as i128 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid seek position"));
         }
 
-        return Ok(self.data[self.position + N]);
-    }
+        self.reader.seek_relative((target - self.position as i128) as i64)?;
+        self.position = target as u64;
 
-    #[inline]
-    pub fn position(&self) -> usize {
-        return self.position;
+        return Ok(self.position);
     }
 
-    #[inline]
-    pub fn set_position(&mut self, pos: usize) -> ReaderResult<()> {
-        if pos > self.data.len() {
-            return Err(ReaderError::InvalidPosition);
-        }
-
-        self.position = pos;
-
-        return Ok(());
+    fn stream_position(&mut self) -> io::Result<u64> {
+        return Ok(self.position);
     }
+}
 
-    #[inline]
-    pub fn remaining(&self) -> usize {
-        return self.data.len() - self.position;
-    }
+/*
+ * Executable reader, the same bounds-checked reads for both byte orders over files and in-memory buffers
+ */
+
+enum ReaderSource<'a> {
+    Borrowed(&'a mut dyn SeekRead),
+    Bytes(&'a [u8]),
 }
 
-/// Big Endian Reader
-#[derive(Debug)]
-pub struct BEReader<'a> {
-    data: &'a [u8],
-    position: usize,
+/// Reads a window of a source with the byte order of the executable, positions are relative to the window
+pub struct ExecReader<'a, E: ByteOrder> {
+    source: ReaderSource<'a>,
+    /// Absolute offsets of the window in the source
+    start: u64,
+    end: u64,
+    position: u64,
+    byte_order: PhantomData<E>,
 }
 
-impl<'a> BEReader<'a> {
-    pub fn new(data: &'a [u8]) -> BEReader<'a> {
-        return BEReader {
-            data,
+impl<'a, E: ByteOrder> ExecReader<'a, E> {
+    pub fn new(source: &'a mut dyn SeekRead) -> ExecReader<'a, E> {
+        let end = source.length();
+
+        return ExecReader {
+            source: ReaderSource::Borrowed(source),
+            start: 0,
+            end,
             position: 0,
+            byte_order: PhantomData,
         };
     }
 
-    #[inline]
-    pub fn read_bytes(&mut self, n: usize) -> ReaderResult<&[u8]> {
-        if self.position + n > self.data.len() {
-            return Err(ReaderError::UnexpectedEof);
-        }
-
-        let bytes = &self.data[self.position..self.position + n];
-        self.position += n;
-
-        return Ok(bytes);
+    pub fn from_bytes(data: &'a [u8]) -> ExecReader<'a, E> {
+        return ExecReader {
+            source: ReaderSource::Bytes(data),
+            start: 0,
+            end: data.len() as u64,
+            position: 0,
+            byte_order: PhantomData,
+        };
     }
 
-    #[inline]
-    fn peek_bytes(&self, n: usize) -> ReaderResult<&[u8]> {
-        if self.position + n > self.data.len() {
-            return Err(ReaderError::UnexpectedEof);
+    /// Reader limited to `size` bytes from `offset`, cut at the end of this one
+    pub fn sub_reader(&mut self, offset: u64, size: u64) -> ReaderResult<ExecReader<'_, E>> {
+        if offset > self.length() {
+            return Err(ReaderError::InvalidPosition);
         }
 
-        return Ok(&self.data[self.position..self.position + n]);
-    }
+        let start = self.start + offset;
+        let end = start.saturating_add(size).min(self.end);
 
-    #[inline]
-    pub fn read_u8(&mut self) -> ReaderResult<u8> {
-        let bytes = self.read_bytes(1)?;
-        return Ok(bytes[0]);
-    }
+        let source = match self.source {
+            ReaderSource::Borrowed(ref mut source) => ReaderSource::Borrowed(&mut **source),
+            ReaderSource::Bytes(data) => ReaderSource::Bytes(data),
+        };
 
-    #[inline]
-    pub fn read_i8(&mut self) -> ReaderResult<i8> {
-        return Ok(self.read_u8()? as i8);
+        return Ok(ExecReader {
+            source,
+            start,
+            end,
+            position: start,
+            byte_order: PhantomData,
+        });
     }
 
     #[inline]
-    pub fn read_u16(&mut self) -> ReaderResult<u16> {
-        let bytes = self.read_bytes(2)?;
-        return Ok(u16::from_be_bytes([bytes[0], bytes[1]]));
+    pub fn position(&self) -> u64 {
+        return self.position - self.start;
     }
 
     #[inline]
-    pub fn read_i16(&mut self) -> ReaderResult<i16> {
-        let bytes = self.read_bytes(2)?;
-        return Ok(i16::from_be_bytes([bytes[0], bytes[1]]));
-    }
+    pub fn set_position(&mut self, position: u64) -> ReaderResult<()> {
+        if position > self.length() {
+            return Err(ReaderError::InvalidPosition);
+        }
 
-    #[inline]
-    pub fn read_u32(&mut self) -> ReaderResult<u32> {
-        let bytes = self.read_bytes(4)?;
-        return Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
-    }
+        self.position = self.start + position;
 
-    #[inline]
-    pub fn read_i32(&mut self) -> ReaderResult<i32> {
-        let bytes = self.read_bytes(4)?;
-        return Ok(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+        return Ok(());
     }
 
     #[inline]
-    pub fn read_u64(&mut self) -> ReaderResult<u64> {
-        let bytes = self.read_bytes(8)?;
-        return Ok(u64::from_be_bytes([
-            bytes[0], bytes[1], bytes[2], bytes[3],
-            bytes[4], bytes[5], bytes[6], bytes[7],
-        ]));
+    pub fn skip(&mut self, n: u64) -> ReaderResult<()> {
+        return self.set_position(self.position().saturating_add(n));
     }
 
-    #[inline]
-    pub fn read_i64(&mut self) -> ReaderResult<i64> {
-        let bytes = self.read_bytes(8)?;
-        return Ok(i64::from_be_bytes([
-            bytes[0], bytes[1], bytes[2], bytes[3],
-            bytes[4], bytes[5], bytes[6], bytes[7],
-        ]));
-    }
+    /// Runs the reads at `position`, the current position is restored afterwards
+    pub fn with_position<T>(&mut self, position: u64, read: impl FnOnce(&mut Self) -> ReaderResult<T>) -> ReaderResult<T> {
+        let saved_position = self.position;
 
-    #[inline]
-    pub fn read_n<const N: usize>(&mut self) -> ReaderResult<[u8; N]> {
-        let bytes = self.read_bytes(N)?;
-        let mut arr = [0u8; N];
-        arr.copy_from_slice(bytes);
-        return Ok(arr);
+        self.set_position(position)?;
+
+        let result = read(self);
+
+        self.position = saved_position;
+
+        return result;
     }
 
     #[inline]
-    pub fn peek(&self) -> ReaderResult<u8> {
-        if self.position >= self.data.len() {
-            return Err(ReaderError::UnexpectedEof);
-        }
-        return Ok(self.data[self.position]);
+    pub fn length(&self) -> u64 {
+        return self.end - self.start;
     }
 
     #[inline]
-    pub fn peek_n<const N: usize>(&self) -> ReaderResult<[u8; N]> {
-        let bytes = self.peek_bytes(N)?;
-        let mut arr = [0u8; N];
-        arr.copy_from_slice(bytes);
-        return Ok(arr);
+    pub fn remaining(&self) -> u64 {
+        return self.end - self.position;
     }
 
-    #[inline]
-    pub fn peek_at<const N: usize>(&self) -> ReaderResult<u8> {
-        if (self.position + N) >= self.data.len() {
+    pub fn read_exact(&mut self, buffer: &mut [u8]) -> ReaderResult<()> {
+        if buffer.len() as u64 > self.remaining() {
             return Err(ReaderError::UnexpectedEof);
         }
 
-        return Ok(self.data[self.position + N]);
-    }
-
-    #[inline]
-    pub fn position(&self) -> usize {
-        return self.position;
-    }
-
-    #[inline]
-    pub fn set_position(&mut self, pos: usize) -> ReaderResult<()> {
-        if pos > self.data.len() {
-            return Err(ReaderError::InvalidPosition);
+        let position = self.position;
+
+        match self.source {
+            ReaderSource::Borrowed(ref mut source) => {
+                /* Sub-readers share the source, it is only seeked when another reader moved it */
+                if source.position() != position {
+                    source.seek(SeekFrom::Start(position))?;
+                }
+
+                source.read_exact(buffer)?;
+            }
+            ReaderSource::Bytes(data) => {
+                let start = position as usize;
+                buffer.copy_from_slice(&data[start..start + buffer.len()]);
+            }
         }
 
-        self.position = pos;
+        self.position += buffer.len() as u64;
 
         return Ok(());
     }
 
-    #[inline]
-    pub fn remaining(&self) -> usize {
-        return self.data.len() - self.position;
-    }
-}
+    pub fn read_bytes(&mut self, n: usize) -> ReaderResult<Vec<u8>> {
+        if n as u64 > self.remaining() {
+            return Err(ReaderError::UnexpectedEof);
+        }
 
-/// Reader enum that supports both endianness
-#[derive(Debug)]
-pub enum Reader<'a> {
-    LittleEndian(LEReader<'a>),
-    BigEndian(BEReader<'a>),
-}
+        let mut bytes = vec![0; n];
+        self.read_exact(&mut bytes)?;
 
-impl<'a> Reader<'a> {
-    pub fn new_le(data: &'a [u8]) -> Reader<'a> {
-        return Reader::LittleEndian(LEReader::new(data));
+        return Ok(bytes);
     }
 
-    pub fn new_be(data: &'a [u8]) -> Reader<'a> {
-        return Reader::BigEndian(BEReader::new(data));
+    #[inline]
+    pub fn read_n<const N: usize>(&mut self) -> ReaderResult<[u8; N]> {
+        let mut bytes = [0; N];
+        self.read_exact(&mut bytes)?;
+
+        return Ok(bytes);
     }
 
     #[inline]
-    pub fn read_bytes(&mut self, n: usize) -> ReaderResult<&[u8]> {
-        match self {
-            Reader::LittleEndian(r) => r.read_bytes(n),
-            Reader::BigEndian(r) => r.read_bytes(n),
-        }
+    pub fn peek_n<const N: usize>(&mut self) -> ReaderResult<[u8; N]> {
+        let position = self.position();
+
+        return self.with_position(position, |reader| reader.read_n());
     }
 
     #[inline]
     pub fn read_u8(&mut self) -> ReaderResult<u8> {
-        match self {
-            Reader::LittleEndian(r) => r.read_u8(),
-            Reader::BigEndian(r) => r.read_u8(),
-        }
+        return Ok(self.read_n::<1>()?[0]);
     }
 
     #[inline]
     pub fn read_i8(&mut self) -> ReaderResult<i8> {
-        match self {
-            Reader::LittleEndian(r) => r.read_i8(),
-            Reader::BigEndian(r) => r.read_i8(),
-        }
+        return Ok(self.read_u8()? as i8);
     }
 
     #[inline]
     pub fn read_u16(&mut self) -> ReaderResult<u16> {
-        match self {
-            Reader::LittleEndian(r) => r.read_u16(),
-            Reader::BigEndian(r) => r.read_u16(),
-        }
+        return Ok(E::read_u16(&self.read_n::<2>()?));
     }
 
     #[inline]
     pub fn read_i16(&mut self) -> ReaderResult<i16> {
-        match self {
-            Reader::LittleEndian(r) => r.read_i16(),
-            Reader::BigEndian(r) => r.read_i16(),
-        }
+        return Ok(E::read_i16(&self.read_n::<2>()?));
     }
 
     #[inline]
     pub fn read_u32(&mut self) -> ReaderResult<u32> {
-        match self {
-            Reader::LittleEndian(r) => r.read_u32(),
-            Reader::BigEndian(r) => r.read_u32(),
-        }
+        return Ok(E::read_u32(&self.read_n::<4>()?));
     }
 
     #[inline]
     pub fn read_i32(&mut self) -> ReaderResult<i32> {
-        match self {
-            Reader::LittleEndian(r) => r.read_i32(),
-            Reader::BigEndian(r) => r.read_i32(),
-        }
+        return Ok(E::read_i32(&self.read_n::<4>()?));
     }
 
     #[inline]
     pub fn read_u64(&mut self) -> ReaderResult<u64> {
-        match self {
-            Reader::LittleEndian(r) => r.read_u64(),
-            Reader::BigEndian(r) => r.read_u64(),
-        }
+        return Ok(E::read_u64(&self.read_n::<8>()?));
     }
 
     #[inline]
     pub fn read_i64(&mut self) -> ReaderResult<i64> {
-        match self {
-            Reader::LittleEndian(r) => r.read_i64(),
-            Reader::BigEndian(r) => r.read_i64(),
-        }
+        return Ok(E::read_i64(&self.read_n::<8>()?));
     }
 
-    #[inline]
-    pub fn read_n<const N: usize>(&mut self) -> ReaderResult<[u8; N]> {
-        match self {
-            Reader::LittleEndian(r) => r.read_n(),
-            Reader::BigEndian(r) => r.read_n(),
-        }
-    }
+    /// Reads up to the NUL terminator, which is consumed but not returned
+    pub fn read_cstr(&mut self) -> ReaderResult<Vec<u8>> {
+        let mut bytes = Vec::new();
 
-    #[inline]
-    pub fn peek(&self) -> ReaderResult<u8> {
-        match self {
-            Reader::LittleEndian(r) => r.peek(),
-            Reader::BigEndian(r) => r.peek(),
+        loop {
+            match self.read_u8()? {
+                0x0 => return Ok(bytes),
+                c => bytes.push(c),
+            }
         }
     }
 
-    #[inline]
-    pub fn peek_n<const N: usize>(&self) -> ReaderResult<[u8; N]> {
-        match self {
-            Reader::LittleEndian(r) => r.peek_n(),
-            Reader::BigEndian(r) => r.peek_n(),
-        }
-    }
+    /// Reads `length` UTF-16 code units, unpaired surrogates are replaced
+    pub fn read_utf16(&mut self, length: usize) -> ReaderResult<String> {
+        let bytes = self.read_bytes(length * 2)?;
+        let units: Vec<u16> = bytes.chunks_exact(2).map(E::read_u16).collect();
 
-    #[inline]
-    pub fn peek_at<const N: usize>(&self) -> ReaderResult<u8> {
-        match self {
-            Reader::LittleEndian(r) => r.peek_at::<N>(),
-            Reader::BigEndian(r) => r.peek_at::<N>(),
-        }
+        return Ok(String::from_utf16_lossy(&units));
     }
+}
 
-    #[inline]
-    pub fn position(&self) -> usize {
-        match self {
-            Reader::LittleEndian(r) => r.position(),
-            Reader::BigEndian(r) => r.position(),
+/*
+ * Names read from the file
+ */
+
+/// Decodes a name without failing, bytes that are not printable UTF-8 are escaped as "\xNN"
+/// Returns the name and whether some bytes had to be escaped
+pub fn decode_name(bytes: &[u8]) -> (String, bool) {
+    let mut name = String::with_capacity(bytes.len());
+    let mut escaped = false;
+
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            if c.is_control() {
+                let mut buffer = [0; 4];
+
+                for byte in c.encode_utf8(&mut buffer).bytes() {
+                    name.push_str(&format!("\\x{:02x}", byte));
+                }
+
+                escaped = true;
+            } else {
+                name.push(c);
+            }
         }
-    }
 
-    #[inline]
-    pub fn set_position(&mut self, pos: usize) -> ReaderResult<()> {
-        match self {
-            Reader::LittleEndian(r) => r.set_position(pos),
-            Reader::BigEndian(r) => r.set_position(pos),
+        for byte in chunk.invalid() {
+            name.push_str(&format!("\\x{:02x}", byte));
+            escaped = true;
         }
     }
 
-    #[inline]
-    pub fn remaining(&self) -> usize {
-        match self {
-            Reader::LittleEndian(r) => r.remaining(),
-            Reader::BigEndian(r) => r.remaining(),
-        }
-    }
+    return (name, escaped);
 }
@@ -0,0 +1,546 @@
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use crate::args::ReplArgs;
+use crate::demangle::display_name;
+use crate::deps::pe_imported_dlls;
+use crate::disasm::{disasm_elf_instructions, disasm_pe_instructions};
+use crate::dump::{Dump, DumpRawData};
+use crate::elf::{ELF, ELFClass, ELFTargetISA, SectionType, SectionFlags as ELFSectionFlags};
+use crate::exec::{parse_exec, parse_exec_from_bytes, Exec};
+use crate::format::format_hex_lines;
+use crate::patch::parse_address;
+use crate::pe::{MachineType, SectionFlags, PE};
+
+/*
+ * Interactive query mode, the executable is parsed once and queried until the prompt is closed
+ */
+
+pub const DEFAULT_DISASM_COUNT: usize = 20;
+pub const DEFAULT_HEX_LENGTH: usize = 256;
+
+const PROMPT: &str = "execdump> ";
+
+const COMMANDS: [(&str, &str); 12] = [
+    ("info", "Format, architecture, entry point and image base"),
+    ("headers", "Dumps the headers"),
+    ("sections", "Lists the sections"),
+    ("imports [filter]", "Lists the imports, filtered by library or function name"),
+    ("exports [filter]", "Lists the exports, filtered by name"),
+    ("disasm <address> [count]", "Disassembles count instructions (20 by default) at a virtual address or a RVA"),
+    ("hex <section> [offset] [length]", "Dumps length bytes (256 by default) of a section, from an offset in the section"),
+    ("hex <address> [length]", "Dumps length bytes (256 by default) at a virtual address or a RVA"),
+    ("rva2off <rva>", "Converts a RVA (a virtual address for ELF) to a file offset"),
+    ("off2rva <offset>", "Converts a file offset to a RVA (a virtual address for ELF)"),
+    ("help", "Lists the commands"),
+    ("quit", "Leaves the prompt (also exit or end of input)"),
+];
+
+fn contains_ignore_case(value: &str, filter: &str) -> bool {
+    return value.to_ascii_lowercase().contains(&filter.to_ascii_lowercase());
+}
+
+fn parse_count(value: Option<&&str>, default: usize) -> Result<usize, Box<dyn std::error::Error>> {
+    match value {
+        Some(value) => return Ok(parse_address(value)? as usize),
+        None => return Ok(default),
+    }
+}
+
+fn hex_dump(label: String, data: &[u8], address: u64) -> Dump {
+    let mut dump = Dump::new_from_string(label);
+    dump.set_raw_data(DumpRawData::Code(format_hex_lines(data, address)));
+
+    return dump;
+}
+
+pub struct Session {
+    pub path: PathBuf,
+    pub exec: Exec,
+}
+
+impl Session {
+    /// Parses the executable with its section data, every query is answered from the parsed model
+    pub fn open(path: &Path) -> Result<Session, Box<dyn std::error::Error>> {
+        let path = path.to_path_buf();
+        let exec = parse_exec(&path)?;
+
+        return Ok(Session { path, exec });
+    }
+
+    /// Same as open for an executable already in memory, path only names it
+    pub fn from_bytes(path: PathBuf, bytes: &[u8]) -> Result<Session, Box<dyn std::error::Error>> {
+        let exec = parse_exec_from_bytes(bytes)?;
+
+        return Ok(Session { path, exec });
+    }
+
+    /// Runs a single query, the error is the message to show at the prompt
+    pub fn execute(&self, line: &str) -> Result<Dump, Box<dyn std::error::Error>> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        let Some((command, args)) = words.split_first() else {
+            return Err("Empty command".into());
+        };
+
+        return self.query(command, args);
+    }
+
+    /// Runs a command with its arguments already split, see COMMANDS
+    pub fn query(&self, command: &str, args: &[&str]) -> Result<Dump, Box<dyn std::error::Error>> {
+        match (command, &self.exec) {
+            ("help", _) => return Ok(help_dump()),
+            ("info", Exec::PE(pe)) => return Ok(pe_info(pe)),
+            ("info", Exec::ELF(elf)) => return Ok(elf_info(elf)),
+            ("headers", Exec::PE(pe)) => return Ok(pe_headers(pe)),
+            ("headers", Exec::ELF(elf)) => return Ok(elf_headers(elf)),
+            ("sections", Exec::PE(pe)) => return Ok(pe_sections(pe)),
+            ("sections", Exec::ELF(elf)) => return Ok(elf_sections(elf)),
+            ("imports", Exec::PE(pe)) => return Ok(pe_imports(pe, args.first().copied())),
+            ("imports", Exec::ELF(elf)) => return Ok(elf_imports(elf, args.first().copied())),
+            ("exports", Exec::PE(pe)) => return Ok(pe_exports(pe, args.first().copied())),
+            ("exports", Exec::ELF(elf)) => return Ok(elf_exports(elf, args.first().copied())),
+            ("disasm", _) => return self.disasm(args),
+            ("hex", _) => return self.hex(args),
+            ("rva2off", _) => return self.rva_to_offset(args),
+            ("off2rva", _) => return self.offset_to_rva(args),
+            _ => return Err(format!("Unknown command \"{}\", type help to list the commands", command).into()),
+        }
+    }
+
+    /// Bytes mapped at the address up to the end of their section, with the virtual address of the first one
+    fn bytes_at(&self, address: u64) -> Result<(&[u8], u64), Box<dyn std::error::Error>> {
+        match &self.exec {
+            Exec::PE(pe) => {
+                let image_base = pe.get_optional_header().get_image_base();
+                let rva = pe_rva(pe, address);
+
+                let section = pe
+                    .sections
+                    .iter()
+                    .find(|s| rva >= s.header.virtual_address as u64 && rva < (s.header.virtual_address as u64 + pe_section_size(s)))
+                    .ok_or_else(|| format!("{:#x} is not inside a section", address))?;
+
+                let data = section.raw_data();
+                let start = ((rva - section.header.virtual_address as u64) as usize).min(data.len());
+
+                return Ok((&data[start..], image_base + rva));
+            }
+            Exec::ELF(elf) => {
+                let section = elf
+                    .sections
+                    .values()
+                    .find(|s| s.header.virtual_address() != 0 && address >= s.header.virtual_address() && address < s.header.virtual_address() + s.size())
+                    .ok_or_else(|| format!("{:#x} is not inside a section", address))?;
+
+                let start = ((address - section.header.virtual_address()) as usize).min(section.data.len());
+
+                return Ok((&section.data[start..], address));
+            }
+        }
+    }
+
+    fn disasm(&self, args: &[&str]) -> Result<Dump, Box<dyn std::error::Error>> {
+        let address = parse_address(args.first().ok_or("Usage: disasm <address> [count]")?)?;
+        let count = parse_count(args.get(1), DEFAULT_DISASM_COUNT)?;
+
+        if let Exec::ELF(elf) = &self.exec {
+            let machine = elf.get_elf_header().machine();
+
+            if machine != ELFTargetISA::X86 as u16 && machine != ELFTargetISA::AMDX86_64 as u16 {
+                return Err(format!("Only x86 code can be disassembled (e_machine is {:#x})", machine).into());
+            }
+        }
+
+        let (code, address) = self.bytes_at(address)?;
+
+        let lines = match &self.exec {
+            Exec::PE(pe) => disasm_pe_instructions(pe, code, address, count)?,
+            Exec::ELF(elf) => disasm_elf_instructions(elf, code, address, count)?,
+        };
+
+        if lines.is_empty() {
+            return Err(format!("No instruction could be decoded at {:#x}", address).into());
+        }
+
+        let mut dump = Dump::new_from_string(format!("Disassembly at {:#x} ({} instructions)", address, lines.len()));
+        dump.set_raw_data(DumpRawData::Code(lines));
+
+        return Ok(dump);
+    }
+
+    fn hex(&self, args: &[&str]) -> Result<Dump, Box<dyn std::error::Error>> {
+        let target = args.first().ok_or("Usage: hex <section> [offset] [length] or hex <address> [length]")?;
+
+        let section_data = match &self.exec {
+            Exec::PE(pe) => pe.get_section(target).map(|s| (s.raw_data(), pe.get_optional_header().get_image_base() + s.header.virtual_address as u64)),
+            Exec::ELF(elf) => elf.sections.get(*target).map(|s| (s.data.as_slice(), s.header.virtual_address())),
+        };
+
+        /* A section name takes precedence, addresses are only parsed when no section matches */
+        if let Some((data, address)) = section_data {
+            let offset = parse_count(args.get(1), 0)?;
+            let length = parse_count(args.get(2), DEFAULT_HEX_LENGTH)?;
+
+            if offset >= data.len() {
+                return Err(format!("Offset {:#x} is past the end of {} ({:#x} bytes in the file)", offset, target, data.len()).into());
+            }
+
+            let end = offset.saturating_add(length).min(data.len());
+
+            return Ok(hex_dump(format!("{} [{:#x}..{:#x}]", target, offset, end), &data[offset..end], address + offset as u64));
+        }
+
+        let address = parse_address(target).map_err(|_| format!("No section named \"{}\"", target))?;
+        let length = parse_count(args.get(1), DEFAULT_HEX_LENGTH)?;
+
+        let (data, address) = self.bytes_at(address)?;
+
+        if data.is_empty() {
+            return Err(format!("{:#x} is in the zero-filled part of its section", address).into());
+        }
+
+        let data = &data[..length.min(data.len())];
+
+        return Ok(hex_dump(format!("{:#x} ({} bytes)", address, data.len()), data, address));
+    }
+
+    fn rva_to_offset(&self, args: &[&str]) -> Result<Dump, Box<dyn std::error::Error>> {
+        let rva = parse_address(args.first().ok_or("Usage: rva2off <rva>")?)?;
+
+        let mut dump = Dump::new("Address");
+
+        match &self.exec {
+            Exec::PE(pe) => {
+                let rva = pe_rva(pe, rva);
+
+                let offset = match rva < pe.get_optional_header().get_size_of_headers() as u64 {
+                    true => Some(rva),
+                    false => u32::try_from(rva).ok().and_then(|rva| pe.convert_rva_to_file_offset(rva)),
+                };
+
+                let offset = offset.ok_or_else(|| format!("RVA {:#x} is not backed by the file", rva))?;
+
+                dump.push_field("Rva", format!("{:#x}", rva), None);
+                dump.push_field("VirtualAddress", format!("{:#x}", pe.get_optional_header().get_image_base() + rva), None);
+                dump.push_field("Offset", format!("{:#x}", offset), None);
+                dump.push_field("Section", pe_section_name(pe, rva), None);
+            }
+            Exec::ELF(elf) => {
+                let section = elf
+                    .sections
+                    .values()
+                    .filter(|s| s.header.virtual_address() != 0 && s.header.section_type() != SectionType::Nobits)
+                    .find(|s| rva >= s.header.virtual_address() && rva < s.header.virtual_address() + s.size())
+                    .ok_or_else(|| format!("Address {:#x} is not backed by the file", rva))?;
+
+                dump.push_field("VirtualAddress", format!("{:#x}", rva), None);
+                dump.push_field("Offset", format!("{:#x}", section.offset() + rva - section.header.virtual_address()), None);
+                dump.push_field("Section", section.name.clone(), None);
+            }
+        }
+
+        return Ok(dump);
+    }
+
+    fn offset_to_rva(&self, args: &[&str]) -> Result<Dump, Box<dyn std::error::Error>> {
+        let offset = parse_address(args.first().ok_or("Usage: off2rva <offset>")?)?;
+
+        let mut dump = Dump::new("Address");
+
+        match &self.exec {
+            Exec::PE(pe) => {
+                let rva = match pe.convert_file_offset_to_rva(offset) {
+                    Some(rva) => rva as u64,
+                    None if offset < pe.get_optional_header().get_size_of_headers() as u64 => offset,
+                    None => return Err(format!("Offset {:#x} is not mapped", offset).into()),
+                };
+
+                dump.push_field("Offset", format!("{:#x}", offset), None);
+                dump.push_field("Rva", format!("{:#x}", rva), None);
+                dump.push_field("VirtualAddress", format!("{:#x}", pe.get_optional_header().get_image_base() + rva), None);
+                dump.push_field("Section", pe_section_name(pe, rva), None);
+            }
+            Exec::ELF(elf) => {
+                let section = elf
+                    .sections
+                    .values()
+                    .filter(|s| s.header.virtual_address() != 0 && s.header.section_type() != SectionType::Nobits)
+                    .find(|s| offset >= s.offset() && offset < s.offset() + s.size())
+                    .ok_or_else(|| format!("Offset {:#x} is not mapped", offset))?;
+
+                dump.push_field("Offset", format!("{:#x}", offset), None);
+                dump.push_field("VirtualAddress", format!("{:#x}", section.header.virtual_address() + offset - section.offset()), None);
+                dump.push_field("Section", section.name.clone(), None);
+            }
+        }
+
+        return Ok(dump);
+    }
+}
+
+/* PE queries */
+
+/// Addresses below the ImageBase are taken as RVAs
+fn pe_rva(pe: &PE, address: u64) -> u64 {
+    let image_base = pe.get_optional_header().get_image_base();
+
+    return if address >= image_base { address - image_base } else { address };
+}
+
+fn pe_section_size(section: &crate::pe::Section) -> u64 {
+    return section.header.virtual_size.max(section.header.size_of_raw_data) as u64;
+}
+
+fn pe_section_name(pe: &PE, rva: u64) -> String {
+    return pe
+        .sections
+        .iter()
+        .find(|s| rva >= s.header.virtual_address as u64 && rva < s.header.virtual_address as u64 + pe_section_size(s))
+        .map(|s| s.header.name.clone())
+        .unwrap_or_else(|| String::from("headers"));
+}
+
+fn pe_info(pe: &PE) -> Dump {
+    let image_base = pe.get_optional_header().get_image_base();
+    let machine = pe.get_nt_header().coff_header.machine;
+
+    let mut dump = Dump::new("Info");
+
+    dump.push_field("Format", String::from(if pe.is_32_bits() { "PE32" } else { "PE32+" }), None);
+    dump.push_field("Machine", format!("{:#x} ({:#?})", machine, MachineType::from(machine)), None);
+    dump.push_field("ImageBase", format!("{:#x}", image_base), None);
+    dump.push_field("EntryPoint", format!("{:#x}", image_base + pe.get_optional_header().get_address_of_entry_point() as u64), None);
+    dump.push_field("Sections", format!("{}", pe.sections.len()), None);
+    dump.push_field("ImportedDlls", format!("{}", pe_imported_dlls(pe).len()), None);
+    dump.push_field("Exports", format!("{}", pe.export_data.as_ref().map(|e| e.entries.len()).unwrap_or(0)), None);
+
+    return dump;
+}
+
+fn pe_headers(pe: &PE) -> Dump {
+    let mut dump = Dump::new("Headers");
+
+    dump.push_child(pe.get_dos_header().dump());
+    dump.push_child(pe.get_nt_header().dump());
+    dump.push_child(pe.get_optional_header().dump());
+
+    return dump;
+}
+
+fn pe_sections(pe: &PE) -> Dump {
+    let mut dump = Dump::new_from_string(format!("Sections ({})", pe.sections.len()));
+
+    for section in pe.sections.iter() {
+        let header = &section.header;
+
+        dump.push_field("", format!(
+            "{:<8}  Rva {:#010x}  VirtualSize {:#010x}  Offset {:#010x}  RawSize {:#010x}  {}",
+            header.name, header.virtual_address, header.virtual_size, header.ptr_to_raw_data, header.size_of_raw_data,
+            SectionFlags::flags_as_string(header.characteristics)), None);
+    }
+
+    return dump;
+}
+
+fn pe_imports(pe: &PE, filter: Option<&str>) -> Dump {
+    let mut dlls_dumps = Vec::new();
+
+    for dll in pe_imported_dlls(pe) {
+        /* A DLL matching the filter is listed whole, otherwise only its matching functions are */
+        let dll_matches = filter.is_none_or(|f| contains_ignore_case(&dll.name, f));
+
+        let functions: Vec<String> = dll
+            .functions
+            .iter()
+            .map(|f| f.to_string())
+            .filter(|f| dll_matches || filter.is_some_and(|filter| contains_ignore_case(f, filter)))
+            .collect();
+
+        if !dll_matches && functions.is_empty() {
+            continue;
+        }
+
+        let mut dll_dump = Dump::new_from_string(format!("{} ({})", dll.name, functions.len()));
+
+        for function in functions {
+            dll_dump.push_field("", function, None);
+        }
+
+        dlls_dumps.push(dll_dump);
+    }
+
+    let mut dump = Dump::new_from_string(format!("Imports ({} DLLs)", dlls_dumps.len()));
+
+    for dll_dump in dlls_dumps {
+        dump.push_child(dll_dump);
+    }
+
+    return dump;
+}
+
+fn pe_exports(pe: &PE, filter: Option<&str>) -> Dump {
+    let image_base = pe.get_optional_header().get_image_base();
+
+    let entries: Vec<_> = match pe.export_data {
+        Some(ref export_data) => export_data
+            .entries
+            .iter()
+            .filter(|e| filter.is_none_or(|f| contains_ignore_case(&display_name(&e.display_name()), f)))
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let mut dump = Dump::new_from_string(format!("Exports ({})", entries.len()));
+
+    for entry in entries {
+        let target = match entry.forwarder {
+            Some(ref forwarder) => format!("-> {}", forwarder),
+            None => format!("{:#x}", image_base + entry.rva as u64),
+        };
+
+        dump.push_field("", format!("{:>5}  {:<18}  {}", entry.ordinal, target, display_name(&entry.display_name())), None);
+    }
+
+    return dump;
+}
+
+/* ELF queries */
+
+fn elf_info(elf: &ELF) -> Dump {
+    let header = elf.get_elf_header();
+
+    let mut dump = Dump::new("Info");
+
+    dump.push_field("Format", String::from(if matches!(elf.class(), ELFClass::ELF32) { "ELF32" } else { "ELF64" }), None);
+    dump.push_field("Endianness", String::from(if header.is_big_endian() { "Big" } else { "Little" }), None);
+    dump.push_field("Machine", format!("{:#x}", header.machine()), None);
+    dump.push_field("EntryPoint", format!("{:#x}", header.entry_point()), None);
+    dump.push_field("Sections", format!("{}", elf.sections.len()), None);
+    dump.push_field("NeededLibraries", format!("{}", elf.needed_libraries.len()), None);
+    dump.push_field("DynamicSymbols", format!("{}", elf.dynamic_symbols.len()), None);
+
+    return dump;
+}
+
+fn elf_headers(elf: &ELF) -> Dump {
+    let mut dump = Dump::new("Headers");
+
+    dump.push_child(elf.headers.elf_header.dump());
+
+    for header in elf.headers.program_headers.iter() {
+        dump.push_child(header.dump());
+    }
+
+    return dump;
+}
+
+fn elf_sections(elf: &ELF) -> Dump {
+    let mut sections: Vec<_> = elf.sections.values().collect();
+    sections.sort_by_key(|s| (s.header.virtual_address() == 0, s.header.virtual_address(), s.offset(), s.name.clone()));
+
+    let mut dump = Dump::new_from_string(format!("Sections ({})", sections.len()));
+
+    for section in sections {
+        dump.push_field("", format!(
+            "{:<20}  Address {:#010x}  Offset {:#010x}  Size {:#010x}  {}",
+            section.name, section.header.virtual_address(), section.offset(), section.size(),
+            ELFSectionFlags::flags_as_string(section.header.flags())), None);
+    }
+
+    return dump;
+}
+
+fn elf_imports(elf: &ELF, filter: Option<&str>) -> Dump {
+    let matches = |name: &str| filter.is_none_or(|f| contains_ignore_case(name, f));
+
+    /* ELF imports are not bound to a library, the filter applies to the libraries and the symbols separately */
+    let libraries: Vec<&String> = elf.needed_libraries.iter().filter(|l| matches(l)).collect();
+    let symbols: Vec<&String> = elf.dynamic_symbols.iter().filter(|s| s.is_imported()).map(|s| &s.name).filter(|s| matches(&display_name(s))).collect();
+
+    let mut libraries_dump = Dump::new_from_string(format!("Needed Libraries ({})", libraries.len()));
+
+    for library in libraries {
+        libraries_dump.push_field("", library.clone(), None);
+    }
+
+    let mut symbols_dump = Dump::new_from_string(format!("Imported Symbols ({})", symbols.len()));
+
+    for symbol in symbols {
+        symbols_dump.push_field("", display_name(symbol), None);
+    }
+
+    let mut dump = Dump::new("Imports");
+
+    dump.push_child(libraries_dump);
+    dump.push_child(symbols_dump);
+
+    return dump;
+}
+
+fn elf_exports(elf: &ELF, filter: Option<&str>) -> Dump {
+    let symbols: Vec<_> = elf
+        .dynamic_symbols
+        .iter()
+        .filter(|s| s.is_exported() && filter.is_none_or(|f| contains_ignore_case(&display_name(&s.name), f)))
+        .collect();
+
+    let mut dump = Dump::new_from_string(format!("Exports ({})", symbols.len()));
+
+    for symbol in symbols {
+        dump.push_field("", format!("{:#018x}  {}", symbol.value, display_name(&symbol.name)), None);
+    }
+
+    return dump;
+}
+
+fn help_dump() -> Dump {
+    let mut dump = Dump::new("Commands");
+
+    for (command, description) in COMMANDS.iter() {
+        dump.push_field(command, String::from(*description), None);
+    }
+
+    return dump;
+}
+
+/// Reads queries from stdin until quit or the end of the input, the prompt is only shown on a terminal
+pub fn run(args: &ReplArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let session = Session::open(&args.file_path)?;
+
+    let interactive = std::io::stdin().is_terminal();
+
+    if interactive {
+        println!("{} loaded, type help to list the commands", session.path.display());
+    }
+
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    loop {
+        if interactive {
+            print!("{}", PROMPT);
+            std::io::stdout().flush()?;
+        }
+
+        let Some(line) = lines.next() else {
+            break;
+        };
+
+        let line = line?;
+        let line = line.trim();
+
+        match line {
+            "" => continue,
+            "quit" | "exit" => break,
+            _ => {}
+        }
+
+        match session.execute(line) {
+            Ok(dump) => dump.print(0, args.padding_size),
+            Err(e) => eprintln!("Error: {}", e),
+        }
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,1364 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use strum::IntoEnumIterator;
+use strum_macros::{EnumIter, IntoStaticStr};
+
+use crate::dump::*;
+use crate::format::format_timestamp;
+use crate::pe::{PEReader, PE};
+
+/*
+ * Resource Directory
+ * https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#the-rsrc-section
+ */
+
+/* Type, Name and Language levels */
+const RESOURCE_TREE_DEPTH: usize = 3;
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum ResourceType {
+    Unknown = 0,
+    Cursor = 1,
+    Bitmap = 2,
+    Icon = 3,
+    Menu = 4,
+    Dialog = 5,
+    String = 6,
+    Fontdir = 7,
+    Font = 8,
+    Accelerator = 9,
+    Rcdata = 10,
+    Messagetable = 11,
+    GroupCursor = 12,
+    GroupIcon = 14,
+    Version = 16,
+    Dlginclude = 17,
+    Plugplay = 19,
+    Vxd = 20,
+    Anicursor = 21,
+    Aniicon = 22,
+    Html = 23,
+    Manifest = 24,
+}
+
+impl From<u32> for ResourceType {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => ResourceType::Cursor,
+            2 => ResourceType::Bitmap,
+            3 => ResourceType::Icon,
+            4 => ResourceType::Menu,
+            5 => ResourceType::Dialog,
+            6 => ResourceType::String,
+            7 => ResourceType::Fontdir,
+            8 => ResourceType::Font,
+            9 => ResourceType::Accelerator,
+            10 => ResourceType::Rcdata,
+            11 => ResourceType::Messagetable,
+            12 => ResourceType::GroupCursor,
+            14 => ResourceType::GroupIcon,
+            16 => ResourceType::Version,
+            17 => ResourceType::Dlginclude,
+            19 => ResourceType::Plugplay,
+            20 => ResourceType::Vxd,
+            21 => ResourceType::Anicursor,
+            22 => ResourceType::Aniicon,
+            23 => ResourceType::Html,
+            24 => ResourceType::Manifest,
+            _ => ResourceType::Unknown,
+        }
+    }
+}
+
+impl ResourceType {
+    pub fn as_static_str(&self) -> &'static str {
+        return self.into();
+    }
+}
+
+/// A directory entry is identified either by an integer ID or by a UTF-16 string
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ResourceId {
+    Id(u32),
+    Name(String),
+}
+
+impl Default for ResourceId {
+    fn default() -> ResourceId {
+        return ResourceId::Id(0);
+    }
+}
+
+impl fmt::Display for ResourceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceId::Id(id) => write!(f, "{}", id),
+            ResourceId::Name(name) => write!(f, "\"{}\"", name),
+        }
+    }
+}
+
+impl ResourceId {
+    /// Human readable name of a resource type, falling back to the raw ID
+    pub fn type_name(&self) -> String {
+        match self {
+            ResourceId::Id(id) => match ResourceType::from(*id) {
+                ResourceType::Unknown => format!("{}", id),
+                rt => rt.as_static_str().to_string(),
+            },
+            ResourceId::Name(name) => format!("\"{}\"", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+#[repr(C)]
+pub struct ResourceDirectoryTable {
+    pub characteristics: u32,
+    pub time_date_stamp: u32,
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub number_of_name_entries: u16,
+    pub number_of_id_entries: u16,
+}
+
+impl ResourceDirectoryTable {
+    pub fn from_parser(
+        cursor: &mut PEReader,
+    ) -> Result<ResourceDirectoryTable, Box<dyn std::error::Error>> {
+        let mut rdt = ResourceDirectoryTable::default();
+
+        rdt.characteristics = cursor.read_u32()?;
+        rdt.time_date_stamp = cursor.read_u32()?;
+        rdt.major_version = cursor.read_u16()?;
+        rdt.minor_version = cursor.read_u16()?;
+        rdt.number_of_name_entries = cursor.read_u16()?;
+        rdt.number_of_id_entries = cursor.read_u16()?;
+
+        return Ok(rdt);
+    }
+
+    pub fn number_of_entries(&self) -> usize {
+        return self.number_of_name_entries as usize + self.number_of_id_entries as usize;
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Resource Directory Table");
+
+        dump.push_field("Characteristics", format!("{:#x}", self.characteristics), None);
+        dump.push_field("TimeDateStamp", format_timestamp(self.time_date_stamp), None);
+        dump.push_field("MajorVersion", format!("{:#x}", self.major_version), None);
+        dump.push_field("MinorVersion", format!("{:#x}", self.minor_version), None);
+        dump.push_field("NumberOfNameEntries", format!("{}", self.number_of_name_entries), None);
+        dump.push_field("NumberOfIDEntries", format!("{}", self.number_of_id_entries), None);
+
+        return dump;
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct ResourceDataEntry {
+    pub data_rva: u32,
+    pub size: u32,
+    pub code_page: u32,
+    pub reserved: u32,
+}
+
+impl ResourceDataEntry {
+    pub fn from_parser(
+        cursor: &mut PEReader,
+    ) -> Result<ResourceDataEntry, Box<dyn std::error::Error>> {
+        let mut rde = ResourceDataEntry::default();
+
+        rde.data_rva = cursor.read_u32()?;
+        rde.size = cursor.read_u32()?;
+        rde.code_page = cursor.read_u32()?;
+        rde.reserved = cursor.read_u32()?;
+
+        return Ok(rde);
+    }
+}
+
+/// A leaf of the resource tree, flattened with the IDs of the three directory levels leading to it
+#[derive(Clone, Default)]
+pub struct ResourceEntry {
+    pub type_id: ResourceId,
+    pub name_id: ResourceId,
+    pub language: u32,
+    pub data_entry: ResourceDataEntry,
+    pub data: Vec<u8>,
+}
+
+impl std::fmt::Debug for ResourceEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return f
+            .debug_struct("ResourceEntry")
+            .field("type_id", &self.type_id)
+            .field("name_id", &self.name_id)
+            .field("language", &self.language)
+            .field("data_entry", &self.data_entry)
+            .finish();
+    }
+}
+
+impl ResourceEntry {
+    /// Short "TYPE/name/language" path used to reference the entry in reports
+    pub fn path(&self) -> String {
+        return format!("{}/{}/{}", self.type_id.type_name(), self.name_id, self.language);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ResourceTable {
+    pub root: ResourceDirectoryTable,
+    pub entries: Vec<ResourceEntry>,
+    /// Directories that could not be read, the walk stops at them and keeps the entries found before
+    pub warnings: Vec<String>,
+}
+
+impl ResourceTable {
+    /// Parses the resource tree found at the given file offset, following the data entries through the PE sections.
+    /// A damaged tree never fails the parsing of the PE, the directories that cannot be read are reported as warnings
+    pub fn from_parser(
+        cursor: &mut PEReader,
+        base_offset: u64,
+        pe: &PE,
+    ) -> ResourceTable {
+        let mut table = ResourceTable::default();
+
+        let root = cursor.set_position(base_offset).map_err(|e| e.into()).and_then(|_| ResourceDirectoryTable::from_parser(cursor));
+
+        match root {
+            Ok(root) => table.root = root,
+            Err(e) => {
+                table.warnings.push(format!("Root resource directory at {:#x} cannot be read: {}", base_offset, e));
+                return table;
+            }
+        }
+
+        let mut visited = HashSet::new();
+        let mut ids = Vec::new();
+
+        if let Err(e) = ResourceTable::parse_directory(cursor, base_offset, 0, &mut ids, &mut visited, pe, &mut table.entries, &mut table.warnings) {
+            table.warnings.push(format!("Root resource directory: {}, stopped after {} entries", e, table.entries.len()));
+        }
+
+        return table;
+    }
+
+    #[allow(clippy::too_many_arguments, reason = "the state of the walk is threaded through the recursion")]
+    fn parse_directory(
+        cursor: &mut PEReader,
+        base_offset: u64,
+        directory_offset: u32,
+        ids: &mut Vec<ResourceId>,
+        visited: &mut HashSet<u32>,
+        pe: &PE,
+        entries: &mut Vec<ResourceEntry>,
+        warnings: &mut Vec<String>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        /* Malformed (or malicious) trees can loop back on themselves */
+        if !visited.insert(directory_offset) || ids.len() >= RESOURCE_TREE_DEPTH {
+            return Ok(());
+        }
+
+        cursor.set_position(base_offset + directory_offset as u64)?;
+
+        let directory = ResourceDirectoryTable::from_parser(cursor)?;
+
+        for i in 0..directory.number_of_entries() {
+            cursor.set_position(base_offset + directory_offset as u64 + 16 + i as u64 * 8)?;
+
+            let name_or_id = cursor.read_u32()?;
+            let offset = cursor.read_u32()?;
+
+            let id = if (name_or_id & 0x80000000) != 0 {
+                cursor.set_position(base_offset + (name_or_id & 0x7fffffff) as u64)?;
+                ResourceId::Name(ResourceTable::parse_name(cursor)?)
+            } else {
+                ResourceId::Id(name_or_id)
+            };
+
+            ids.push(id);
+
+            if (offset & 0x80000000) != 0 {
+                /* Only the subtree that cannot be read is dropped, the walk goes on with the next entry */
+                let found = entries.len();
+
+                if let Err(e) = ResourceTable::parse_directory(cursor, base_offset, offset & 0x7fffffff, ids, visited, pe, entries, warnings) {
+                    warnings.push(format!("Resource directory at {:#x}: {}, stopped after {} entries", offset & 0x7fffffff, e, entries.len() - found));
+                }
+            } else {
+                cursor.set_position(base_offset + offset as u64)?;
+
+                let data_entry = ResourceDataEntry::from_parser(cursor)?;
+
+                let mut entry = ResourceEntry::default();
+                entry.type_id = ids.first().cloned().unwrap_or_default();
+                entry.name_id = ids.get(1).cloned().unwrap_or_default();
+                entry.language = match ids.get(2) {
+                    Some(ResourceId::Id(language)) => *language,
+                    _ => 0,
+                };
+                entry.data_entry = data_entry;
+
+                /* Data past the end of a truncated file is left empty */
+                if let Some(data_offset) = pe.convert_rva_to_file_offset(data_entry.data_rva).filter(|o| *o <= cursor.length()) {
+                    let available = cursor.length().saturating_sub(data_offset);
+                    let mut data = vec![0; (data_entry.size as u64).min(available) as usize];
+
+                    cursor.set_position(data_offset)?;
+                    cursor.read_exact(&mut data)?;
+
+                    entry.data = data;
+                }
+
+                entries.push(entry);
+            }
+
+            ids.pop();
+        }
+
+        return Ok(());
+    }
+
+    fn parse_name(cursor: &mut PEReader) -> Result<String, Box<dyn std::error::Error>> {
+        let length = cursor.read_u16()?;
+
+        return Ok(cursor.read_utf16(length as usize)?);
+    }
+}
+
+/// Names (or IDs) of a resource type with their entries, one per language
+pub type ResourceNames<'a> = Vec<(&'a ResourceId, Vec<&'a ResourceEntry>)>;
+
+impl ResourceTable {
+    /// Type -> name -> language tree of the entries, in the order of the resource directories
+    pub fn tree(&self) -> Vec<(&ResourceId, ResourceNames<'_>)> {
+        let mut types: Vec<(&ResourceId, ResourceNames)> = Vec::new();
+
+        for entry in self.entries.iter() {
+            let type_index = match types.iter().position(|(id, _)| **id == entry.type_id) {
+                Some(index) => index,
+                None => {
+                    types.push((&entry.type_id, Vec::new()));
+                    types.len() - 1
+                },
+            };
+
+            let names = &mut types[type_index].1;
+
+            match names.iter_mut().find(|(id, _)| **id == entry.name_id) {
+                Some((_, languages)) => languages.push(entry),
+                None => names.push((&entry.name_id, vec![entry])),
+            }
+        }
+
+        return types;
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new_from_string(format!("Resources ({} entries)", self.entries.len()));
+
+        dump.push_field("TimeDateStamp", format_timestamp(self.root.time_date_stamp), None);
+        dump.push_field("Version", format!("{}.{}", self.root.major_version, self.root.minor_version), None);
+
+        for warning in self.warnings.iter() {
+            dump.push_field("Warning", warning.clone(), None);
+        }
+
+        for (type_id, names) in self.tree().iter() {
+            let mut type_dump = Dump::new_from_string(format!("{} ({})", type_id.type_name(), names.len()));
+
+            for (name_id, languages) in names.iter() {
+                let mut name_dump = Dump::new_from_string(format!("{}", name_id));
+
+                for entry in languages.iter() {
+                    name_dump.push_field("", format!(
+                        "{:#06x} {:<28} rva {:#010x}  size {:#x}  code page {}",
+                        entry.language,
+                        language_name(entry.language),
+                        entry.data_entry.data_rva,
+                        entry.data_entry.size,
+                        entry.data_entry.code_page
+                    ), None);
+                }
+
+                type_dump.push_child(name_dump);
+            }
+
+            dump.push_child(type_dump);
+        }
+
+        return dump;
+    }
+}
+
+/*
+ * Languages
+ * https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-lcid
+ */
+
+fn primary_language_name(primary: u32) -> Option<&'static str> {
+    let name = match primary {
+        0x00 => "Neutral",
+        0x01 => "Arabic",
+        0x02 => "Bulgarian",
+        0x03 => "Catalan",
+        0x04 => "Chinese",
+        0x05 => "Czech",
+        0x06 => "Danish",
+        0x07 => "German",
+        0x08 => "Greek",
+        0x09 => "English",
+        0x0a => "Spanish",
+        0x0b => "Finnish",
+        0x0c => "French",
+        0x0d => "Hebrew",
+        0x0e => "Hungarian",
+        0x0f => "Icelandic",
+        0x10 => "Italian",
+        0x11 => "Japanese",
+        0x12 => "Korean",
+        0x13 => "Dutch",
+        0x14 => "Norwegian",
+        0x15 => "Polish",
+        0x16 => "Portuguese",
+        0x18 => "Romanian",
+        0x19 => "Russian",
+        0x1a => "Croatian/Serbian/Bosnian",
+        0x1b => "Slovak",
+        0x1c => "Albanian",
+        0x1d => "Swedish",
+        0x1e => "Thai",
+        0x1f => "Turkish",
+        0x20 => "Urdu",
+        0x21 => "Indonesian",
+        0x22 => "Ukrainian",
+        0x23 => "Belarusian",
+        0x24 => "Slovenian",
+        0x25 => "Estonian",
+        0x26 => "Latvian",
+        0x27 => "Lithuanian",
+        0x29 => "Persian",
+        0x2a => "Vietnamese",
+        0x2b => "Armenian",
+        0x2c => "Azerbaijani",
+        0x2d => "Basque",
+        0x2f => "Macedonian",
+        0x36 => "Afrikaans",
+        0x37 => "Georgian",
+        0x39 => "Hindi",
+        0x3e => "Malay",
+        0x3f => "Kazakh",
+        0x41 => "Swahili",
+        0x43 => "Uzbek",
+        0x45 => "Bengali",
+        0x7f => "Invariant",
+        _ => return None,
+    };
+
+    return Some(name);
+}
+
+fn locale_tag(langid: u32) -> Option<&'static str> {
+    let tag = match langid {
+        0x0401 => "ar-SA",
+        0x0404 => "zh-TW",
+        0x0405 => "cs-CZ",
+        0x0407 => "de-DE",
+        0x0408 => "el-GR",
+        0x0409 => "en-US",
+        0x040c => "fr-FR",
+        0x040d => "he-IL",
+        0x040e => "hu-HU",
+        0x0410 => "it-IT",
+        0x0411 => "ja-JP",
+        0x0412 => "ko-KR",
+        0x0413 => "nl-NL",
+        0x0415 => "pl-PL",
+        0x0416 => "pt-BR",
+        0x0418 => "ro-RO",
+        0x0419 => "ru-RU",
+        0x041d => "sv-SE",
+        0x041e => "th-TH",
+        0x041f => "tr-TR",
+        0x0421 => "id-ID",
+        0x0422 => "uk-UA",
+        0x0429 => "fa-IR",
+        0x042a => "vi-VN",
+        0x0439 => "hi-IN",
+        0x0804 => "zh-CN",
+        0x0809 => "en-GB",
+        0x0816 => "pt-PT",
+        0x0c0a => "es-ES",
+        _ => return None,
+    };
+
+    return Some(tag);
+}
+
+/// Human readable name of a LANGID, e.g. "Russian (ru-RU)"
+pub fn language_name(langid: u32) -> String {
+    match langid {
+        0x0000 => return String::from("Neutral"),
+        0x0400 => return String::from("Process Default"),
+        0x0800 => return String::from("System Default"),
+        _ => {},
+    }
+
+    match (primary_language_name(langid & 0x3ff), locale_tag(langid)) {
+        (Some(name), Some(tag)) => return format!("{} ({})", name, tag),
+        (Some(name), None) => return format!("{} (sublanguage {:#x})", name, langid >> 10),
+        _ => return String::from("Unknown"),
+    }
+}
+
+/*
+ * Statistics
+ */
+
+/* Number of entries listed in the largest entries table */
+const LARGEST_ENTRIES_COUNT: usize = 10;
+
+#[derive(Debug, Clone, Default)]
+struct ResourceStat {
+    count: usize,
+    size: u64,
+}
+
+impl ResourceTable {
+    pub fn total_size(&self) -> u64 {
+        return self.entries.iter().map(|e| e.data_entry.size as u64).sum();
+    }
+
+    fn stat_rows(stats: Vec<(String, ResourceStat)>, total_size: u64) -> Vec<String> {
+        let mut stats = stats;
+        stats.sort_by(|a, b| b.1.size.cmp(&a.1.size).then(a.0.cmp(&b.0)));
+
+        return stats
+            .iter()
+            .map(|(name, stat)| {
+                let percent = if total_size > 0 { stat.size as f64 * 100.0 / total_size as f64 } else { 0.0 };
+                format!("{:<32} {:>7} {:>12} {:>6.1}%", name, stat.count, stat.size, percent)
+            })
+            .collect();
+    }
+
+    #[rustfmt::skip]
+    pub fn dump_stats(&self) -> Dump {
+        let mut dump = Dump::new("Resource Statistics");
+
+        let total_size = self.total_size();
+
+        dump.push_field("Entries", format!("{}", self.entries.len()), None);
+        dump.push_field("TotalSize", format!("{:#x} ({} bytes)", total_size, total_size), None);
+
+        let mut by_type: Vec<(String, ResourceStat)> = Vec::new();
+        let mut by_language: Vec<(String, ResourceStat)> = Vec::new();
+
+        for entry in self.entries.iter() {
+            let type_name = entry.type_id.type_name();
+            let language = format!("{:#06x} {}", entry.language, language_name(entry.language));
+
+            for (stats, key) in [(&mut by_type, type_name), (&mut by_language, language)] {
+                let index = match stats.iter().position(|(k, _)| *k == key) {
+                    Some(index) => index,
+                    None => {
+                        stats.push((key, ResourceStat::default()));
+                        stats.len() - 1
+                    },
+                };
+
+                stats[index].1.count += 1;
+                stats[index].1.size += entry.data_entry.size as u64;
+            }
+        }
+
+        let mut types_dump = Dump::new_from_string(format!("Types ({})", by_type.len()));
+        types_dump.push_field("", format!("{:<32} {:>7} {:>12} {:>7}", "Type", "Count", "Size", "Share"), None);
+
+        for row in ResourceTable::stat_rows(by_type, total_size) {
+            types_dump.push_field("", row, None);
+        }
+
+        dump.push_child(types_dump);
+
+        let mut languages_dump = Dump::new_from_string(format!("Languages ({})", by_language.len()));
+        languages_dump.push_field("", format!("{:<32} {:>7} {:>12} {:>7}", "Language", "Count", "Size", "Share"), None);
+
+        for row in ResourceTable::stat_rows(by_language, total_size) {
+            languages_dump.push_field("", row, None);
+        }
+
+        dump.push_child(languages_dump);
+
+        let mut largest: Vec<&ResourceEntry> = self.entries.iter().collect();
+        largest.sort_by_key(|e| std::cmp::Reverse(e.data_entry.size));
+
+        let mut largest_dump = Dump::new("Largest Entries");
+
+        for entry in largest.iter().take(LARGEST_ENTRIES_COUNT) {
+            largest_dump.push_field("", format!("{:<32} {:>12}", entry.path(), entry.data_entry.size), None);
+        }
+
+        dump.push_child(largest_dump);
+
+        return dump;
+    }
+}
+
+/*
+ * Reconstruction of the resources into standalone files
+ * https://learn.microsoft.com/en-us/windows/win32/menurc/resource-file-formats
+ */
+
+const BITMAP_FILE_HEADER_SIZE: u32 = 14;
+const BITMAP_CORE_HEADER_SIZE: u32 = 12;
+const BITMAP_INFO_HEADER_SIZE: u32 = 40;
+const BI_BITFIELDS: u32 = 3;
+
+const ICON_DIR_SIZE: usize = 6;
+const ICON_DIR_ENTRY_SIZE: usize = 16;
+const GROUP_ICON_DIR_ENTRY_SIZE: usize = 14;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+fn read_u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    return data.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]]));
+}
+
+fn read_u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    return data.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+}
+
+/// RT_BITMAP holds a DIB, a .bmp file prepends the BITMAPFILEHEADER pointing past the color table
+pub fn bitmap_file(dib: &[u8]) -> Option<Vec<u8>> {
+    let header_size = read_u32_at(dib, 0)?;
+
+    let color_table_size = match header_size {
+        BITMAP_CORE_HEADER_SIZE => {
+            let bit_count = read_u16_at(dib, 10)? as u32;
+            if bit_count <= 8 { (1 << bit_count) * 3 } else { 0 }
+        }
+        _ if header_size >= BITMAP_INFO_HEADER_SIZE => {
+            let bit_count = read_u16_at(dib, 14)? as u32;
+            let compression = read_u32_at(dib, 16)?;
+            let colors_used = read_u32_at(dib, 32)?;
+
+            let colors = match colors_used {
+                0 if bit_count <= 8 => 1 << bit_count,
+                n => n,
+            };
+
+            /* The masks follow a BITMAPINFOHEADER, newer headers embed them */
+            let masks = if compression == BI_BITFIELDS && header_size == BITMAP_INFO_HEADER_SIZE { 12 } else { 0 };
+
+            colors * 4 + masks
+        }
+        _ => return None,
+    };
+
+    let file_size = BITMAP_FILE_HEADER_SIZE + dib.len() as u32;
+    let pixels_offset = BITMAP_FILE_HEADER_SIZE + header_size + color_table_size;
+
+    let mut file = Vec::with_capacity(file_size as usize);
+    file.extend_from_slice(b"BM");
+    file.extend_from_slice(&file_size.to_le_bytes());
+    file.extend_from_slice(&[0; 4]);
+    file.extend_from_slice(&pixels_offset.to_le_bytes());
+    file.extend_from_slice(dib);
+
+    return Some(file);
+}
+
+/// Individual RT_ICON images are either a DIB or a PNG
+pub fn is_png(data: &[u8]) -> bool {
+    return data.starts_with(PNG_SIGNATURE);
+}
+
+impl ResourceTable {
+    /// Rebuilds the .ico (or .cur) file of a RT_GROUP_ICON (or RT_GROUP_CURSOR) entry from the images it references
+    pub fn group_icon_file(&self, group: &ResourceEntry) -> Option<Vec<u8>> {
+        let is_cursor = group.type_id == ResourceId::Id(ResourceType::GroupCursor as u32);
+
+        let image_type = match is_cursor {
+            true => ResourceType::Cursor,
+            false => ResourceType::Icon,
+        };
+
+        let count = read_u16_at(&group.data, 4)? as usize;
+
+        let mut directory = Vec::new();
+        let mut images: Vec<&[u8]> = Vec::new();
+
+        directory.extend_from_slice(&[0, 0]);
+        directory.extend_from_slice(&(if is_cursor { 2u16 } else { 1u16 }).to_le_bytes());
+        directory.extend_from_slice(&(count as u16).to_le_bytes());
+
+        let mut offset = (ICON_DIR_SIZE + count * ICON_DIR_ENTRY_SIZE) as u32;
+
+        for i in 0..count {
+            let entry = group.data.get(ICON_DIR_SIZE + i * GROUP_ICON_DIR_ENTRY_SIZE..ICON_DIR_SIZE + (i + 1) * GROUP_ICON_DIR_ENTRY_SIZE)?;
+            let id = read_u16_at(entry, 12)? as u32;
+
+            /* The image shares the language of the group when possible */
+            let image = self
+                .entries
+                .iter()
+                .filter(|e| e.type_id == ResourceId::Id(image_type as u32) && e.name_id == ResourceId::Id(id))
+                .min_by_key(|e| e.language != group.language)?;
+
+            if is_cursor {
+                /* Cursor images start with their hotspot, the .cur directory entry carries it instead */
+                let hotspot = image.data.get(0..4)?;
+                let data = &image.data[4..];
+
+                let width = read_u16_at(entry, 0)?;
+                let height = read_u16_at(entry, 2)? / 2;
+
+                directory.push(width as u8);
+                directory.push(height as u8);
+                directory.extend_from_slice(&[0, 0]);
+                directory.extend_from_slice(hotspot);
+                directory.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                directory.extend_from_slice(&offset.to_le_bytes());
+
+                offset += data.len() as u32;
+                images.push(data);
+            } else {
+                directory.extend_from_slice(&entry[0..8]);
+                directory.extend_from_slice(&(image.data.len() as u32).to_le_bytes());
+                directory.extend_from_slice(&offset.to_le_bytes());
+
+                offset += image.data.len() as u32;
+                images.push(&image.data);
+            }
+        }
+
+        for image in images {
+            directory.extend_from_slice(image);
+        }
+
+        return Some(directory);
+    }
+}
+
+/*
+ * Version information (RT_VERSION)
+ * https://learn.microsoft.com/en-us/windows/win32/menurc/vs-versioninfo
+ */
+
+const VS_FIXEDFILEINFO_SIGNATURE: u32 = 0xFEEF04BD;
+const VS_FIXEDFILEINFO_SIZE: usize = 52;
+
+/* wLength, wValueLength and wType precede the key of every block */
+const VERSION_BLOCK_HEADER_SIZE: usize = 6;
+
+/* Keys of the StringFileInfo tables documented by Microsoft, printed as fields */
+const VERSION_STRING_KEYS: [&str; 12] = [
+    "Comments",
+    "CompanyName",
+    "FileDescription",
+    "FileVersion",
+    "InternalName",
+    "LegalCopyright",
+    "LegalTrademarks",
+    "OriginalFilename",
+    "PrivateBuild",
+    "ProductName",
+    "ProductVersion",
+    "SpecialBuild",
+];
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum FileFlags {
+    Debug = 0x01,
+    Prerelease = 0x02,
+    Patched = 0x04,
+    PrivateBuild = 0x08,
+    InfoInferred = 0x10,
+    SpecialBuild = 0x20,
+}
+
+impl FileFlags {
+    pub fn flags_as_string(file_flags: u32) -> String {
+        let flags: Vec<&'static str> = FileFlags::iter()
+            .filter(|&flag| (flag as u32 & file_flags) != 0)
+            .map(|flag| flag.into())
+            .collect();
+
+        return flags.join(" | ");
+    }
+}
+
+fn file_os_name(file_os: u32) -> &'static str {
+    match file_os {
+        0x00000000 => return "UNKNOWN",
+        0x00000004 => return "WINDOWS32",
+        0x00010000 => return "DOS",
+        0x00010001 => return "DOS_WINDOWS16",
+        0x00010004 => return "DOS_WINDOWS32",
+        0x00020000 => return "OS216",
+        0x00030000 => return "OS232",
+        0x00040000 => return "NT",
+        0x00040004 => return "NT_WINDOWS32",
+        _ => return "",
+    }
+}
+
+fn file_type_name(file_type: u32) -> &'static str {
+    match file_type {
+        0 => return "UNKNOWN",
+        1 => return "APP",
+        2 => return "DLL",
+        3 => return "DRV",
+        4 => return "FONT",
+        5 => return "VXD",
+        7 => return "STATIC_LIB",
+        _ => return "",
+    }
+}
+
+pub fn code_page_name(code_page: u16) -> &'static str {
+    match code_page {
+        0 => return "7-bit ASCII",
+        932 => return "Japanese (Shift JIS)",
+        936 => return "Simplified Chinese (GBK)",
+        949 => return "Korean",
+        950 => return "Traditional Chinese (Big5)",
+        1200 => return "Unicode",
+        1250 => return "Central European",
+        1251 => return "Cyrillic",
+        1252 => return "Western European",
+        1253 => return "Greek",
+        1254 => return "Turkish",
+        1255 => return "Hebrew",
+        1256 => return "Arabic",
+        65001 => return "UTF-8",
+        _ => return "Unknown code page",
+    }
+}
+
+/// VS_VERSIONINFO and its children are nested blocks: header, null-terminated UTF-16 key, value and children, each 32-bit aligned
+struct VersionBlock<'a> {
+    key: String,
+    value: &'a [u8],
+    /// wType is 1 for the UTF-16 values, whose wValueLength counts characters instead of bytes
+    is_text: bool,
+    children: Vec<VersionBlock<'a>>,
+}
+
+impl<'a> VersionBlock<'a> {
+    /// Parses the block at offset, the offsets are relative to the start of the resource which is 32-bit aligned
+    fn parse(data: &'a [u8], offset: usize) -> Option<(VersionBlock<'a>, usize)> {
+        let length = read_u16_at(data, offset)? as usize;
+        let value_length = read_u16_at(data, offset + 2)? as usize;
+        let is_text = read_u16_at(data, offset + 4)? == 1;
+
+        if length < VERSION_BLOCK_HEADER_SIZE {
+            return None;
+        }
+
+        let end = (offset + length).min(data.len());
+
+        let mut position = offset + VERSION_BLOCK_HEADER_SIZE;
+        let mut key = Vec::new();
+
+        while let Some(unit) = read_u16_at(data, position).filter(|_| position < end) {
+            position += 2;
+
+            if unit == 0 {
+                break;
+            }
+
+            key.push(unit);
+        }
+
+        position = (position + 3) & !3;
+
+        let value_size = if is_text { value_length * 2 } else { value_length };
+        let value = data.get(position..(position + value_size).min(end)).unwrap_or(&[]);
+
+        position = (position + value_size + 3) & !3;
+
+        let mut children = Vec::new();
+
+        while position + VERSION_BLOCK_HEADER_SIZE <= end {
+            let Some((child, child_end)) = VersionBlock::parse(&data[..end], position) else {
+                break;
+            };
+
+            children.push(child);
+            position = (child_end + 3) & !3;
+        }
+
+        let block = VersionBlock { key: String::from_utf16_lossy(&key), value, is_text, children };
+
+        return Some((block, end));
+    }
+
+    fn text(&self) -> String {
+        let units: Vec<u16> = self.value.chunks_exact(2).map(|b| u16::from_le_bytes([b[0], b[1]])).take_while(|u| *u != 0).collect();
+
+        return String::from_utf16_lossy(&units);
+    }
+}
+
+/// Strings of a StringFileInfo table, keyed by the language and code page of its name ("040904b0")
+#[derive(Debug, Clone, Default)]
+pub struct VersionStringTable {
+    pub language: u16,
+    pub code_page: u16,
+    pub strings: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VersionInfo {
+    /// VS_FIXEDFILEINFO, absent from some resources
+    pub file_version: Option<[u16; 4]>,
+    pub product_version: Option<[u16; 4]>,
+    pub file_flags_mask: u32,
+    pub file_flags: u32,
+    pub file_os: u32,
+    pub file_type: u32,
+    pub file_subtype: u32,
+    pub string_tables: Vec<VersionStringTable>,
+    /// Language and code page pairs of the VarFileInfo Translation value
+    pub translations: Vec<(u16, u16)>,
+}
+
+fn version_from_dwords(ms: u32, ls: u32) -> [u16; 4] {
+    return [(ms >> 16) as u16, ms as u16, (ls >> 16) as u16, ls as u16];
+}
+
+fn format_version(version: &[u16; 4]) -> String {
+    return format!("{}.{}.{}.{}", version[0], version[1], version[2], version[3]);
+}
+
+impl VersionInfo {
+    pub fn from_data(data: &[u8]) -> Option<VersionInfo> {
+        let (root, _) = VersionBlock::parse(data, 0)?;
+
+        if root.key != "VS_VERSION_INFO" {
+            return None;
+        }
+
+        let mut info = VersionInfo::default();
+
+        if root.value.len() >= VS_FIXEDFILEINFO_SIZE && read_u32_at(root.value, 0) == Some(VS_FIXEDFILEINFO_SIGNATURE) {
+            let fixed = |index: usize| read_u32_at(root.value, index * 4).unwrap_or(0);
+
+            info.file_version = Some(version_from_dwords(fixed(2), fixed(3)));
+            info.product_version = Some(version_from_dwords(fixed(4), fixed(5)));
+            info.file_flags_mask = fixed(6);
+            info.file_flags = fixed(7);
+            info.file_os = fixed(8);
+            info.file_type = fixed(9);
+            info.file_subtype = fixed(10);
+        }
+
+        for child in root.children.iter() {
+            match child.key.as_str() {
+                "StringFileInfo" => {
+                    for table in child.children.iter() {
+                        let id = u32::from_str_radix(&table.key, 16).unwrap_or(0);
+
+                        info.string_tables.push(VersionStringTable {
+                            language: (id >> 16) as u16,
+                            code_page: id as u16,
+                            strings: table.children.iter().map(|s| (s.key.clone(), if s.is_text { s.text() } else { String::new() })).collect(),
+                        });
+                    }
+                },
+                "VarFileInfo" => {
+                    for var in child.children.iter().filter(|v| v.key == "Translation") {
+                        info.translations.extend(var.value.chunks_exact(4).map(|b| (u16::from_le_bytes([b[0], b[1]]), u16::from_le_bytes([b[2], b[3]]))));
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        return Some(info);
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Version Info");
+
+        if let Some(ref file_version) = self.file_version {
+            dump.push_field("FileVersion", format_version(file_version), None);
+        }
+
+        if let Some(ref product_version) = self.product_version {
+            dump.push_field("ProductVersion", format_version(product_version), None);
+        }
+
+        if self.file_version.is_some() {
+            let file_flags = self.file_flags & self.file_flags_mask;
+
+            match FileFlags::flags_as_string(file_flags).as_str() {
+                "" => dump.push_field("FileFlags", format!("{:#x}", file_flags), None),
+                names => dump.push_field("FileFlags", format!("{:#x} ({})", file_flags, names), None),
+            }
+
+            dump.push_field("FileOS", format!("{:#x} {}", self.file_os, file_os_name(self.file_os)), None);
+            dump.push_field("FileType", format!("{:#x} {}", self.file_type, file_type_name(self.file_type)), None);
+            dump.push_field("FileSubtype", format!("{:#x}", self.file_subtype), None);
+        }
+
+        let translations: Vec<String> = self
+            .translations
+            .iter()
+            .map(|(language, code_page)| format!("{} ({})", language_name(*language as u32), code_page_name(*code_page)))
+            .collect();
+
+        if !translations.is_empty() {
+            dump.push_field("Translations", translations.join(", "), None);
+        }
+
+        for table in self.string_tables.iter() {
+            let mut table_dump = Dump::new_from_string(format!(
+                "StringFileInfo {:04x}{:04x} ({}, {})",
+                table.language,
+                table.code_page,
+                language_name(table.language as u32),
+                code_page_name(table.code_page)
+            ));
+
+            for (key, value) in table.strings.iter() {
+                match VERSION_STRING_KEYS.iter().find(|k| **k == key) {
+                    Some(key) => table_dump.push_field(key, value.clone(), None),
+                    None => table_dump.push_field("", format!("{}: {}", key, value), None),
+                }
+            }
+
+            dump.push_child(table_dump);
+        }
+
+        return dump;
+    }
+}
+
+impl ResourceTable {
+    /// Version information of the first RT_VERSION entry that can be decoded
+    pub fn version_info(&self) -> Option<VersionInfo> {
+        return self
+            .entries
+            .iter()
+            .filter(|e| e.type_id == ResourceId::Id(ResourceType::Version as u32))
+            .find_map(|e| VersionInfo::from_data(&e.data));
+    }
+}
+
+/*
+ * Application manifest (RT_MANIFEST)
+ * https://learn.microsoft.com/en-us/windows/win32/sbscs/application-manifests
+ */
+
+/// Opening or self-closing XML tag, with the namespace prefix dropped from its name ("asmv3:trustInfo")
+struct ManifestTag<'a> {
+    name: &'a str,
+    attributes: Vec<(&'a str, &'a str)>,
+    is_closing: bool,
+    is_self_closing: bool,
+}
+
+/// Minimal scanner of the manifest tags, enough for the attributes read here (no entities, CDATA or DTD)
+fn manifest_tags(xml: &str) -> Vec<ManifestTag<'_>> {
+    let mut tags = Vec::new();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+
+        /* Comments may hold tags */
+        if let Some(comment) = rest.strip_prefix("!--") {
+            rest = comment.find("-->").map(|end| &comment[end + 3..]).unwrap_or("");
+            continue;
+        }
+
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+
+        let content = &rest[..end];
+        rest = &rest[end + 1..];
+
+        if content.starts_with('?') || content.starts_with('!') {
+            continue;
+        }
+
+        let is_closing = content.starts_with('/');
+        let is_self_closing = content.ends_with('/');
+        let content = content.trim_start_matches('/').trim_end_matches('/');
+
+        let name_end = content.find(|c: char| c.is_whitespace()).unwrap_or(content.len());
+        let name = &content[..name_end];
+        let name = name.rsplit(':').next().unwrap_or(name);
+
+        let mut attributes = Vec::new();
+        let mut attributes_rest = &content[name_end..];
+
+        while let Some(equal) = attributes_rest.find('=') {
+            let key = attributes_rest[..equal].trim();
+            let value_rest = attributes_rest[equal + 1..].trim_start();
+
+            let Some(quote) = value_rest.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+                break;
+            };
+
+            let Some(value_end) = value_rest[1..].find(quote) else {
+                break;
+            };
+
+            attributes.push((key.rsplit(':').next().unwrap_or(key), &value_rest[1..1 + value_end]));
+            attributes_rest = &value_rest[value_end + 2..];
+        }
+
+        tags.push(ManifestTag { name, attributes, is_closing, is_self_closing });
+    }
+
+    return tags;
+}
+
+impl<'a> ManifestTag<'a> {
+    fn attribute(&self, key: &str) -> Option<&'a str> {
+        return self.attributes.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+    }
+}
+
+/// Name, version, architecture and public key token of an assemblyIdentity element
+#[derive(Debug, Clone, Default)]
+pub struct AssemblyIdentity {
+    pub name: String,
+    pub version: String,
+    pub processor_architecture: String,
+    pub public_key_token: String,
+}
+
+impl AssemblyIdentity {
+    fn from_tag(tag: &ManifestTag) -> AssemblyIdentity {
+        return AssemblyIdentity {
+            name: tag.attribute("name").unwrap_or_default().to_string(),
+            version: tag.attribute("version").unwrap_or_default().to_string(),
+            processor_architecture: tag.attribute("processorArchitecture").unwrap_or_default().to_string(),
+            public_key_token: tag.attribute("publicKeyToken").unwrap_or_default().to_string(),
+        };
+    }
+
+    fn describe(&self) -> String {
+        let mut description = format!("{} {}", self.name, self.version);
+
+        if !self.processor_architecture.is_empty() {
+            description.push_str(&format!(" ({})", self.processor_architecture));
+        }
+
+        if !self.public_key_token.is_empty() {
+            description.push_str(&format!(" token {}", self.public_key_token));
+        }
+
+        return description;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Manifest {
+    /// ID of the RT_MANIFEST entry: 1 for processes, 2 and 3 for the isolation aware DLLs
+    pub resource_id: ResourceId,
+    pub xml: String,
+    pub identity: Option<AssemblyIdentity>,
+    pub requested_execution_level: Option<String>,
+    pub ui_access: Option<String>,
+    pub dependencies: Vec<AssemblyIdentity>,
+}
+
+/// Manifests are UTF-8 with an optional BOM, UTF-16 ones are found in the wild
+fn decode_manifest(data: &[u8]) -> String {
+    if let Some(utf16) = data.strip_prefix(&[0xFF, 0xFE]) {
+        let units: Vec<u16> = utf16.chunks_exact(2).map(|b| u16::from_le_bytes([b[0], b[1]])).collect();
+        return String::from_utf16_lossy(&units);
+    }
+
+    let data = data.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(data);
+
+    return String::from_utf8_lossy(data).trim_end_matches('\0').to_string();
+}
+
+impl Manifest {
+    pub fn from_entry(entry: &ResourceEntry) -> Manifest {
+        let mut manifest = Manifest {
+            resource_id: entry.name_id.clone(),
+            xml: decode_manifest(&entry.data),
+            ..Manifest::default()
+        };
+
+        let mut depth_in_dependency = 0;
+
+        for tag in manifest_tags(&manifest.xml) {
+            match (tag.name, tag.is_closing) {
+                ("dependentAssembly", false) if !tag.is_self_closing => depth_in_dependency += 1,
+                ("dependentAssembly", true) => depth_in_dependency -= 1,
+                ("assemblyIdentity", false) if depth_in_dependency > 0 => manifest.dependencies.push(AssemblyIdentity::from_tag(&tag)),
+                ("assemblyIdentity", false) if manifest.identity.is_none() => manifest.identity = Some(AssemblyIdentity::from_tag(&tag)),
+                ("requestedExecutionLevel", false) => {
+                    manifest.requested_execution_level = tag.attribute("level").map(String::from);
+                    manifest.ui_access = tag.attribute("uiAccess").map(String::from);
+                },
+                _ => {},
+            }
+        }
+
+        return manifest;
+    }
+
+    /// XML lines are printed after the fields, unless the XML has been written to a file
+    #[rustfmt::skip]
+    pub fn dump(&self, with_xml: bool) -> Dump {
+        let mut dump = Dump::new("Manifest");
+
+        dump.push_field("ResourceId", format!("{}", self.resource_id), None);
+
+        if let Some(ref identity) = self.identity {
+            dump.push_field("Identity", identity.describe(), None);
+        }
+
+        /* Without a trustInfo section, the process runs with the rights of its parent */
+        dump.push_field("RequestedExecutionLevel", self.requested_execution_level.clone().unwrap_or(String::from("None (asInvoker)")), None);
+
+        if let Some(ref ui_access) = self.ui_access {
+            dump.push_field("UIAccess", ui_access.clone(), None);
+        }
+
+        let mut dependencies_dump = Dump::new_from_string(format!("Dependencies ({})", self.dependencies.len()));
+
+        for dependency in self.dependencies.iter() {
+            dependencies_dump.push_field("", dependency.describe(), None);
+        }
+
+        dump.push_child(dependencies_dump);
+
+        if with_xml {
+            let mut xml_dump = Dump::new("XML");
+            xml_dump.set_raw_data(DumpRawData::Code(self.xml.lines().map(String::from).collect()));
+            dump.push_child(xml_dump);
+        }
+
+        return dump;
+    }
+}
+
+impl ResourceTable {
+    /// Manifest of the RT_MANIFEST entry with the lowest ID, the process manifest when there is one
+    pub fn manifest(&self) -> Option<Manifest> {
+        return self
+            .entries
+            .iter()
+            .filter(|e| e.type_id == ResourceId::Id(ResourceType::Manifest as u32))
+            .min_by_key(|e| match e.name_id {
+                ResourceId::Id(id) => id,
+                ResourceId::Name(_) => u32::MAX,
+            })
+            .map(Manifest::from_entry);
+    }
+}
+
+/*
+ * String tables (RT_STRING)
+ * https://learn.microsoft.com/en-us/windows/win32/menurc/stringtable-resource
+ */
+
+/* Each RT_STRING entry is a block of 16 strings, block N holding the IDs (N - 1) * 16 to N * 16 - 1 */
+const STRINGS_PER_BLOCK: u32 = 16;
+
+/// Strings of the RT_STRING blocks of one language, sorted by ID
+#[derive(Debug, Clone, Default)]
+pub struct StringTable {
+    pub language: u32,
+    pub strings: Vec<(u32, String)>,
+}
+
+impl StringTable {
+    /// Strings of a block, each one is a length prefixed UTF-16 string and the empty ones are not defined
+    fn parse_block(block_id: u32, data: &[u8]) -> Vec<(u32, String)> {
+        let mut strings = Vec::new();
+        let mut offset = 0;
+
+        for i in 0..STRINGS_PER_BLOCK {
+            let Some(length) = read_u16_at(data, offset) else {
+                break;
+            };
+
+            offset += 2;
+
+            let units: Vec<u16> = data
+                .get(offset..(offset + length as usize * 2).min(data.len()))
+                .unwrap_or(&[])
+                .chunks_exact(2)
+                .map(|b| u16::from_le_bytes([b[0], b[1]]))
+                .collect();
+
+            offset += length as usize * 2;
+
+            if length > 0 {
+                strings.push(((block_id - 1) * STRINGS_PER_BLOCK + i, String::from_utf16_lossy(&units)));
+            }
+        }
+
+        return strings;
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new_from_string(format!("String Table {:#06x} {} ({} strings)", self.language, language_name(self.language), self.strings.len()));
+
+        for (id, string) in self.strings.iter() {
+            dump.push_field("", format!("{:>6}  {:?}", id, string), None);
+        }
+
+        return dump;
+    }
+}
+
+impl ResourceTable {
+    /// RT_STRING blocks decoded and merged per language, in the order the languages are found
+    pub fn string_tables(&self) -> Vec<StringTable> {
+        let mut tables: Vec<StringTable> = Vec::new();
+
+        for entry in self.entries.iter().filter(|e| e.type_id == ResourceId::Id(ResourceType::String as u32)) {
+            /* Blocks are numbered from 1, named blocks do not map to string IDs */
+            let ResourceId::Id(block_id) = entry.name_id else {
+                continue;
+            };
+
+            if block_id == 0 {
+                continue;
+            }
+
+            let index = match tables.iter().position(|t| t.language == entry.language) {
+                Some(index) => index,
+                None => {
+                    tables.push(StringTable { language: entry.language, strings: Vec::new() });
+                    tables.len() - 1
+                },
+            };
+
+            tables[index].strings.extend(StringTable::parse_block(block_id, &entry.data));
+        }
+
+        for table in tables.iter_mut() {
+            table.strings.sort_by_key(|(id, _)| *id);
+        }
+
+        return tables;
+    }
+}
@@ -0,0 +1,420 @@
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use regex::Regex;
+use serde_json::json;
+use tiny_http::{Header, Method, Request, Response};
+
+use crate::args::{Args, OutputFormat, ServeArgs};
+use crate::dump::{dump_elf, dump_pe, Dump, DumpWriter};
+use crate::exec::Exec;
+use crate::hashes::FileHashes;
+use crate::repl::Session;
+
+/*
+ * HTTP/JSON server, executables are uploaded (or loaded from the root directory) once and queried by id,
+ * the id being the SHA-256 of the file
+ *
+ * GET    /                      Lists the endpoints
+ * GET    /files                 Lists the loaded files
+ * POST   /files?name=<name>     Loads the executable sent as the request body
+ * POST   /files?path=<path>     Loads an executable of the root directory (--root)
+ * GET    /files/<id>            Summary of the executable
+ * DELETE /files/<id>            Unloads the executable
+ * GET    /files/<id>/hashes     MD5, SHA-1 and SHA-256 of the file and its sections
+ * GET    /files/<id>/dump       Report of the dump flags given in flags=pe-import,sections,...
+ * GET    /files/<id>/<query>    Query of the repl (imports, disasm, hex, rva2off...), see QUERY_PARAMETERS
+ */
+
+/* Upper bound on the size of an uploaded executable */
+pub const MAX_UPLOAD_SIZE: usize = 512 * 1024 * 1024;
+
+/* Flags of the dump endpoint, the ones writing files or reading other files are left out */
+const DUMP_FLAGS: &[&str] = &[
+    "pe-dos-header",
+    "dos-stub",
+    "pe-nt-header",
+    "te-header",
+    "pe-optional-header",
+    "entry",
+    "pe-import",
+    "pe-import-directory-table",
+    "pe-import-lookup-table",
+    "pe-hint-name-table",
+    "pe-dlls",
+    "iat",
+    "exports",
+    "pe-debug-directory",
+    "pe-exc-table",
+    "tls",
+    "load-config",
+    "cfg",
+    "safeseh",
+    "arm64x",
+    "relocs",
+    "symbols",
+    "resources",
+    "version-info",
+    "manifest",
+    "resource-strings",
+    "resource-stats",
+    "signature",
+    "mitigations",
+    "timestamps",
+    "lint",
+    "driver",
+    "efi",
+    "dotnet",
+    "dotnet-metadata",
+    "dotnet-refs",
+    "dotnet-assembly",
+    "elf-headers",
+    "elf-header",
+    "elf-program-headers",
+    "elf-symbols",
+    "sections",
+    "sections-data",
+    "disasm",
+    "packed",
+    "anti-analysis",
+    "capabilities",
+    "attack",
+    "embedded",
+    "function-hashes",
+];
+
+/* Repl queries and the query string parameters giving their arguments, in order */
+const QUERY_PARAMETERS: [(&str, &[&str]); 9] = [
+    ("info", &[]),
+    ("headers", &[]),
+    ("sections", &[]),
+    ("imports", &["filter"]),
+    ("exports", &["filter"]),
+    ("disasm", &["address", "count"]),
+    ("hex", &["section", "offset", "length"]),
+    ("rva2off", &["rva"]),
+    ("off2rva", &["offset"]),
+];
+
+struct LoadedFile {
+    name: String,
+    size: usize,
+    hashes: FileHashes,
+    session: Session,
+}
+
+impl LoadedFile {
+    fn format(&self) -> &'static str {
+        match &self.session.exec {
+            Exec::PE(pe) => return if pe.is_32_bits() { "PE32" } else { "PE32+" },
+            Exec::ELF(_) => return "ELF",
+        }
+    }
+
+    fn summary(&self) -> serde_json::Value {
+        return json!({
+            "id": self.hashes.file.sha256,
+            "name": self.name,
+            "size": self.size,
+            "format": self.format(),
+        });
+    }
+}
+
+struct Reply {
+    status: u16,
+    body: String,
+}
+
+impl Reply {
+    fn json(value: serde_json::Value) -> Reply {
+        return Reply { status: 200, body: serde_json::to_string_pretty(&value).expect("Cannot serialize reply to JSON") };
+    }
+
+    fn dump(dump: &Dump) -> Reply {
+        return Reply { status: 200, body: serde_json::to_string_pretty(dump).expect("Cannot serialize dump to JSON") };
+    }
+
+    fn error(status: u16, message: impl std::fmt::Display) -> Reply {
+        return Reply { status, body: serde_json::to_string_pretty(&json!({ "error": message.to_string() })).unwrap() };
+    }
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = match bytes[i] {
+            b'%' => bytes.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()),
+            _ => None,
+        };
+
+        match (escaped, bytes[i]) {
+            (Some(byte), _) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (None, b'+') => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (None, byte) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    return String::from_utf8_lossy(&decoded).to_string();
+}
+
+/// Splits the url in its path segments and its query string parameters
+fn parse_url(url: &str) -> (Vec<String>, BTreeMap<String, String>) {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+
+    let segments = path.split('/').filter(|s| !s.is_empty()).map(percent_decode).collect();
+
+    let parameters = query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let (key, value) = p.split_once('=').unwrap_or((p, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect();
+
+    return (segments, parameters);
+}
+
+fn endpoints() -> serde_json::Value {
+    let queries: Vec<String> = QUERY_PARAMETERS
+        .iter()
+        .map(|(query, parameters)| {
+            let parameters: Vec<String> = parameters.iter().map(|p| format!("{}=", p)).collect();
+            format!("GET /files/<id>/{}?{}", query, parameters.join("&"))
+        })
+        .collect();
+
+    return json!({
+        "files": [
+            "GET /files",
+            "POST /files?name=<name> (the executable is the request body)",
+            "POST /files?path=<path> (relative to the root directory)",
+            "GET /files/<id>",
+            "DELETE /files/<id>",
+            "GET /files/<id>/hashes",
+            "GET /files/<id>/dump?flags=<flag>,<flag>&sections-filter=<regex>&max-bytes=<count>",
+        ],
+        "queries": queries,
+        "dump_flags": DUMP_FLAGS,
+    });
+}
+
+pub struct Server {
+    files: BTreeMap<String, LoadedFile>,
+    root: Option<PathBuf>,
+}
+
+impl Server {
+    pub fn new(root: Option<PathBuf>) -> Server {
+        return Server { files: BTreeMap::new(), root };
+    }
+
+    fn load(&mut self, name: String, bytes: Vec<u8>) -> Reply {
+        let session = match Session::from_bytes(PathBuf::from(&name), &bytes) {
+            Ok(session) => session,
+            Err(e) => return Reply::error(422, format!("Cannot parse {}: {}", name, e)),
+        };
+
+        let file = LoadedFile {
+            name,
+            size: bytes.len(),
+            hashes: FileHashes::compute(&session.exec, &bytes),
+            session,
+        };
+
+        let reply = Reply::json(file.summary());
+
+        self.files.insert(file.hashes.file.sha256.clone(), file);
+
+        return reply;
+    }
+
+    fn upload(&mut self, request: &mut Request, parameters: &BTreeMap<String, String>) -> Reply {
+        if let Some(path) = parameters.get("path") {
+            return self.load_path(path);
+        }
+
+        if request.body_length().is_some_and(|length| length > MAX_UPLOAD_SIZE) {
+            return Reply::error(413, format!("The executable is larger than {} bytes", MAX_UPLOAD_SIZE));
+        }
+
+        let mut bytes = Vec::new();
+
+        if let Err(e) = request.as_reader().take(MAX_UPLOAD_SIZE as u64 + 1).read_to_end(&mut bytes) {
+            return Reply::error(400, format!("Cannot read the request body: {}", e));
+        }
+
+        if bytes.len() > MAX_UPLOAD_SIZE {
+            return Reply::error(413, format!("The executable is larger than {} bytes", MAX_UPLOAD_SIZE));
+        }
+
+        if bytes.is_empty() {
+            return Reply::error(400, "The request body is empty, send the executable or a path parameter");
+        }
+
+        let name = parameters.get("name").cloned().unwrap_or_else(|| String::from("upload"));
+
+        return self.load(name, bytes);
+    }
+
+    /// Only files inside the root directory can be loaded, paths escaping it through ".." or links are refused
+    fn load_path(&mut self, path: &str) -> Reply {
+        let Some(ref root) = self.root else {
+            return Reply::error(403, "Loading files by path needs the server to be started with --root");
+        };
+
+        let resolved = match (root.canonicalize(), root.join(path).canonicalize()) {
+            (Ok(root), Ok(resolved)) if resolved.starts_with(&root) && resolved.is_file() => resolved,
+            _ => return Reply::error(404, format!("No file {} in the root directory", path)),
+        };
+
+        match std::fs::read(&resolved) {
+            Ok(bytes) => return self.load(file_name(&resolved), bytes),
+            Err(e) => return Reply::error(500, format!("Cannot read {}: {}", path, e)),
+        }
+    }
+
+    fn dump(&self, file: &LoadedFile, parameters: &BTreeMap<String, String>) -> Reply {
+        let mut argv = vec![String::from("execdump"), String::from("--output-format"), String::from("json")];
+
+        for flag in parameters.get("flags").map(|f| f.as_str()).unwrap_or_default().split(',').filter(|f| !f.is_empty()) {
+            if !DUMP_FLAGS.contains(&flag) {
+                return Reply::error(400, format!("Unknown dump flag \"{}\", the flags are {}", flag, DUMP_FLAGS.join(", ")));
+            }
+
+            argv.push(format!("--{}", flag));
+        }
+
+        if let Some(filter) = parameters.get("sections-filter") {
+            /* Invalid expressions are reported here, the dump expects a valid one */
+            if let Err(e) = Regex::new(filter) {
+                return Reply::error(400, format!("Invalid sections-filter: {}", e));
+            }
+
+            argv.push(String::from("--sections-filter"));
+            argv.push(filter.clone());
+        }
+
+        if let Some(max_bytes) = parameters.get("max-bytes") {
+            argv.push(String::from("--max-bytes"));
+            argv.push(max_bytes.clone());
+        }
+
+        argv.push(file.name.clone());
+
+        let args = match Args::try_parse_from(argv) {
+            Ok(args) => args,
+            Err(e) => return Reply::error(400, e.to_string().trim()),
+        };
+
+        let mut writer = DumpWriter::new(OutputFormat::Json, args.padding_size, &file.name);
+
+        match &file.session.exec {
+            Exec::PE(pe) => dump_pe(pe, &args, &mut writer),
+            Exec::ELF(elf) => dump_elf(elf, &args, &mut writer),
+        }
+
+        return Reply { status: 200, body: writer.to_json_string() };
+    }
+
+    fn query(&self, file: &LoadedFile, query: &str, parameters: &BTreeMap<String, String>) -> Reply {
+        let Some((_, names)) = QUERY_PARAMETERS.iter().find(|(q, _)| *q == query) else {
+            return Reply::error(404, format!("Unknown query \"{}\"", query));
+        };
+
+        let parameter = |name: &str| parameters.get(name).map(|v| v.as_str());
+
+        let args: Vec<&str> = match (query, parameter("address"), parameter("section")) {
+            /* hex takes either an address and a length, or a section, an offset and a length */
+            ("hex", Some(address), _) => [Some(address), parameter("length")].into_iter().flatten().collect(),
+            ("hex", None, Some(section)) => [Some(section), Some(parameter("offset").unwrap_or("0")), parameter("length")].into_iter().flatten().collect(),
+            ("hex", None, None) => return Reply::error(400, "hex needs a section or an address parameter"),
+            /* The arguments are positional, a missing one ends them */
+            _ => names.iter().map_while(|name| parameter(name)).collect(),
+        };
+
+        match file.session.query(query, &args) {
+            Ok(dump) => return Reply::dump(&dump),
+            Err(e) => return Reply::error(400, e),
+        }
+    }
+
+    fn handle(&mut self, request: &mut Request) -> Reply {
+        let (segments, parameters) = parse_url(request.url());
+        let segments: Vec<&str> = segments.iter().map(|s| s.as_str()).collect();
+
+        match (request.method(), segments.as_slice()) {
+            (Method::Get, []) => return Reply::json(endpoints()),
+            (Method::Get, ["files"]) => return Reply::json(serde_json::Value::Array(self.files.values().map(|f| f.summary()).collect())),
+            (Method::Post, ["files"]) => return self.upload(request, &parameters),
+            (method, ["files", id, rest @ ..]) => {
+                let Some(file) = self.files.get(*id) else {
+                    return Reply::error(404, format!("No file with id {}", id));
+                };
+
+                match (method, rest) {
+                    (Method::Get, []) => {
+                        let mut summary = file.summary();
+
+                        summary["info"] = serde_json::to_value(file.session.query("info", &[]).unwrap_or_default()).unwrap();
+
+                        return Reply::json(summary);
+                    }
+                    (Method::Delete, []) => {
+                        let summary = file.summary();
+                        self.files.remove(*id);
+
+                        return Reply::json(summary);
+                    }
+                    (Method::Get, ["hashes"]) => return Reply::dump(&file.hashes.dump()),
+                    (Method::Get, ["dump"]) => return self.dump(file, &parameters),
+                    (Method::Get, [query]) => return self.query(file, query, &parameters),
+                    _ => return Reply::error(404, format!("No endpoint {} {}", request.method(), request.url())),
+                }
+            }
+            _ => return Reply::error(404, format!("No endpoint {} {}", request.method(), request.url())),
+        }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    return path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+}
+
+/// Serves requests one at a time until the process is stopped
+pub fn run(args: &ServeArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let http = tiny_http::Server::http(&args.listen).map_err(|e| format!("Cannot listen on {}: {}", args.listen, e))?;
+
+    let mut server = Server::new(args.root.clone());
+
+    eprintln!("Listening on http://{}", args.listen);
+
+    let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+
+    for mut request in http.incoming_requests() {
+        let reply = server.handle(&mut request);
+
+        let response = Response::from_string(reply.body).with_status_code(reply.status).with_header(content_type.clone());
+
+        if let Err(e) = request.respond(response) {
+            eprintln!("Cannot send the response: {}", e);
+        }
+    }
+
+    return Ok(());
+}
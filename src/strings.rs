@@ -0,0 +1,220 @@
+use regex::Regex;
+use strum_macros::IntoStaticStr;
+
+use crate::args::StringsEncoding;
+use crate::dump::Dump;
+use crate::elf::{SectionFlags as ELFSectionFlags, SectionType};
+use crate::exec::Exec;
+
+/*
+ * Printable ASCII and UTF-16LE strings of any file, attributed to the section of the executable holding them
+ */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+pub enum StringEncoding {
+    #[strum(serialize = "ascii")]
+    Ascii,
+    #[strum(serialize = "utf-16le")]
+    Utf16Le,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExtractedString {
+    pub file_offset: u64,
+    pub encoding: StringEncoding,
+    pub value: String,
+    /// None outside of the mapped sections
+    pub virtual_address: Option<u64>,
+}
+
+/// Range of the file backing a section, "(headers)", "(overlay)" and "(unmapped)" fill the gaps between them
+#[derive(Debug, Clone)]
+struct FileRegion {
+    name: String,
+    start: u64,
+    end: u64,
+    /// Virtual address of the start of the region
+    virtual_address: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StringsRegion {
+    pub name: String,
+    pub strings: Vec<ExtractedString>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StringsReport {
+    /// In the order of the file
+    pub regions: Vec<StringsRegion>,
+}
+
+/// Same characters as GNU strings: the printable ASCII and the tabulation
+fn is_printable(byte: u8) -> bool {
+    return byte == b'\t' || (0x20..0x7F).contains(&byte);
+}
+
+fn extract_ascii(bytes: &[u8], min_length: usize, strings: &mut Vec<ExtractedString>) {
+    let mut start = 0;
+
+    for (position, byte) in bytes.iter().chain(std::iter::once(&0)).enumerate() {
+        if is_printable(*byte) {
+            continue;
+        }
+
+        if position - start >= min_length {
+            strings.push(ExtractedString {
+                file_offset: start as u64,
+                encoding: StringEncoding::Ascii,
+                value: String::from_utf8_lossy(&bytes[start..position]).to_string(),
+                virtual_address: None,
+            });
+        }
+
+        start = position + 1;
+    }
+}
+
+/// Printable ASCII characters followed by a NUL byte, at any alignment
+fn extract_utf16(bytes: &[u8], min_length: usize, strings: &mut Vec<ExtractedString>) {
+    let mut position = 0;
+
+    while position + 1 < bytes.len() {
+        let mut end = position;
+
+        while end + 1 < bytes.len() && is_printable(bytes[end]) && bytes[end + 1] == 0 {
+            end += 2;
+        }
+
+        if (end - position) / 2 >= min_length {
+            strings.push(ExtractedString {
+                file_offset: position as u64,
+                encoding: StringEncoding::Utf16Le,
+                value: bytes[position..end].iter().step_by(2).map(|b| *b as char).collect(),
+                virtual_address: None,
+            });
+
+            position = end;
+        } else {
+            position += 1;
+        }
+    }
+}
+
+/// Sections backed by the file, sorted by file offset
+fn file_regions(exec: &Exec) -> Vec<FileRegion> {
+    let mut regions: Vec<FileRegion> = match exec {
+        Exec::PE(pe) => {
+            let image_base = pe.get_optional_header().get_image_base();
+
+            pe.sections
+                .iter()
+                .filter(|section| section.header.size_of_raw_data > 0)
+                .map(|section| FileRegion {
+                    name: section.header.name.clone(),
+                    start: section.header.ptr_to_raw_data as u64,
+                    end: section.header.ptr_to_raw_data as u64 + section.header.size_of_raw_data as u64,
+                    virtual_address: Some(image_base + section.header.virtual_address as u64),
+                })
+                .collect()
+        }
+        Exec::ELF(elf) => elf
+            .sections
+            .values()
+            .filter(|section| section.header.section_type() != SectionType::Nobits && section.size() > 0 && section.offset() > 0)
+            .map(|section| {
+                let is_mapped = (section.header.flags() & ELFSectionFlags::Alloc as u64) != 0 && section.header.virtual_address() != 0;
+
+                return FileRegion {
+                    name: section.name.clone(),
+                    start: section.offset(),
+                    end: section.offset() + section.size(),
+                    virtual_address: is_mapped.then(|| section.header.virtual_address()),
+                };
+            })
+            .collect(),
+    };
+
+    regions.sort_by_key(|region| region.start);
+
+    return regions;
+}
+
+impl StringsReport {
+    /// The strings outside of the sections of an executable are grouped in "(file)" when the file is not one
+    pub fn extract(bytes: &[u8], exec: Option<&Exec>, encoding: StringsEncoding, min_length: usize, filter: Option<&Regex>) -> StringsReport {
+        let mut strings = Vec::new();
+        let min_length = min_length.max(1);
+
+        if encoding != StringsEncoding::Utf16 {
+            extract_ascii(bytes, min_length, &mut strings);
+        }
+
+        if encoding != StringsEncoding::Ascii {
+            extract_utf16(bytes, min_length, &mut strings);
+        }
+
+        if let Some(filter) = filter {
+            strings.retain(|string| filter.is_match(&string.value));
+        }
+
+        strings.sort_by_key(|string| string.file_offset);
+
+        let regions = exec.map(file_regions).unwrap_or_default();
+        let sections_end = regions.iter().map(|region| region.end).max().unwrap_or(0);
+
+        let mut report = StringsReport::default();
+
+        for mut string in strings.into_iter() {
+            let region = regions.iter().find(|region| string.file_offset >= region.start && string.file_offset < region.end);
+
+            let name = match region {
+                Some(region) => {
+                    string.virtual_address = region.virtual_address.map(|va| va + string.file_offset - region.start);
+                    region.name.clone()
+                }
+                None if exec.is_none() => String::from("(file)"),
+                None if regions.first().is_none_or(|first| string.file_offset < first.start) => String::from("(headers)"),
+                None if string.file_offset >= sections_end => String::from("(overlay)"),
+                None => String::from("(unmapped)"),
+            };
+
+            match report.regions.last_mut() {
+                Some(last) if last.name == name => last.strings.push(string),
+                _ => report.regions.push(StringsRegion { name, strings: vec![string] }),
+            }
+        }
+
+        return report;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.regions.iter().map(|region| region.strings.len()).sum();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new_from_string(format!("Strings ({})", self.len()));
+
+        for region in self.regions.iter() {
+            let mut region_dump = Dump::new_from_string(format!("{} ({})", region.name, region.strings.len()));
+
+            for string in region.strings.iter() {
+                let encoding: &'static str = string.encoding.into();
+
+                match string.virtual_address {
+                    Some(virtual_address) => region_dump.push_field("", format!("{:#x} {} va {:#x}: {}", string.file_offset, encoding, virtual_address, string.value), None),
+                    None => region_dump.push_field("", format!("{:#x} {}: {}", string.file_offset, encoding, string.value), None),
+                }
+            }
+
+            dump.push_child(region_dump);
+        }
+
+        return dump;
+    }
+}
@@ -0,0 +1,245 @@
+use crate::analysis::{analyze_elf_packing, analyze_pe_packing, PackedVerdict};
+use crate::deps::pe_imported_dlls;
+use crate::dump::Dump;
+use crate::efi;
+use crate::elf::{ELFClass, ELFTargetISA, SectionFlags as ELFSectionFlags, ELF};
+use crate::exec::Exec;
+use crate::fuzzy::FuzzyHashes;
+use crate::hashes::{import_hash, rich_header_hash, Hashes};
+use crate::packers::{detect_pe_packers, PackerMatch};
+use crate::toolchain::{detect_elf_toolchain, detect_pe_toolchain, Toolchain};
+use crate::pe::{MachineType, SectionFlags, PE};
+
+/*
+ * One-screen overview of an executable
+ */
+
+#[derive(Debug, Clone, Default)]
+pub struct Summary {
+    pub format: String,
+    pub architecture: String,
+    /// Windows subsystem for PE, object file type for ELF
+    pub kind: String,
+    pub image_base: Option<u64>,
+    pub entry_point: u64,
+    /// False for the DLLs and shared objects without an entry point
+    pub has_entry_point: bool,
+    /// Section holding the entry point, None when it points outside of the sections
+    pub entry_point_section: Option<String>,
+    pub entry_point_in_code: bool,
+    /// Bytes of the executable sections and their number
+    pub code_size: u64,
+    pub code_sections: usize,
+    pub file_size: u64,
+    /// None for the formats without such a notion (no Authenticode or CLR header in ELF)
+    pub signed: Option<bool>,
+    pub managed: Option<bool>,
+    pub packed: Option<PackedVerdict>,
+    /// Packers and protectors identified by their signatures
+    pub packers: Vec<PackerMatch>,
+    /// Compiler and linker guessed from the headers, the sections and the imports
+    pub toolchain: Option<Toolchain>,
+    pub sections: usize,
+    pub imported_libraries: usize,
+    pub imported_functions: usize,
+    pub exports: usize,
+    pub resources: Option<usize>,
+    pub hashes: Hashes,
+    pub fuzzy_hashes: FuzzyHashes,
+    /// MD5 of the imported functions, None for ELF and the PE without imports
+    pub imphash: Option<String>,
+    /// MD5 of the decoded Rich header, None when the linker did not write one
+    pub rich_header_hash: Option<String>,
+}
+
+fn yes_no(value: bool) -> String {
+    return String::from(if value { "Yes" } else { "No" });
+}
+
+impl Summary {
+    pub fn from_exec(exec: &Exec, file_bytes: &[u8]) -> Summary {
+        match exec {
+            Exec::PE(pe) => return Summary::from_pe(pe, file_bytes),
+            Exec::ELF(elf) => return Summary::from_elf(elf, file_bytes),
+        }
+    }
+
+    pub fn from_pe(pe: &PE, file_bytes: &[u8]) -> Summary {
+        let mut summary = Summary::default();
+
+        let optional_header = pe.get_optional_header();
+        let machine = pe.get_nt_header().coff_header.machine;
+        let entry_point = optional_header.get_address_of_entry_point();
+
+        summary.format = String::from(match (&pe.te_header, pe.is_32_bits()) {
+            (Some(_), _) => "TE",
+            (None, true) => "PE32",
+            (None, false) => "PE32+",
+        });
+        summary.architecture = format!("{:?}", MachineType::from(machine));
+        summary.kind = String::from(optional_header.get_subsystem().as_static_str());
+        summary.image_base = Some(optional_header.get_image_base());
+        summary.entry_point = optional_header.get_image_base() + entry_point as u64;
+        summary.has_entry_point = entry_point != 0;
+
+        /* DLLs without an entry point have a zero AddressOfEntryPoint */
+        if let Some(section) = pe.get_section_for_rva(entry_point).filter(|_| entry_point != 0) {
+            summary.entry_point_section = Some(section.header.name.clone());
+            summary.entry_point_in_code = section.contains_code() || (section.header.characteristics & SectionFlags::MemExecute as u32) != 0;
+        }
+
+        for section in pe.sections.iter() {
+            if section.contains_code() || (section.header.characteristics & SectionFlags::MemExecute as u32) != 0 {
+                summary.code_size += section.header.virtual_size.max(section.header.size_of_raw_data) as u64;
+                summary.code_sections += 1;
+            }
+        }
+
+        let imported_dlls = pe_imported_dlls(pe);
+
+        summary.file_size = file_bytes.len() as u64;
+        summary.signed = Some(pe.certificate_table.as_ref().is_some_and(|t| !t.certificates.is_empty()));
+        /* No CLR in the firmware */
+        summary.managed = match efi::is_efi(pe) {
+            true => None,
+            false => Some(pe.dotnet.is_some() || optional_header.get_clr_runtime_header_idd().virtual_address != 0),
+        };
+        summary.packed = Some(analyze_pe_packing(pe).verdict);
+        summary.packers = detect_pe_packers(pe);
+        summary.toolchain = detect_pe_toolchain(pe, file_bytes);
+        summary.sections = pe.sections.len();
+        summary.imported_libraries = imported_dlls.len();
+        summary.imported_functions = imported_dlls.iter().map(|dll| dll.functions.len()).sum();
+        summary.exports = pe.export_data.as_ref().map(|e| e.entries.len()).unwrap_or(0);
+        summary.resources = Some(pe.resource_table.as_ref().map(|r| r.entries.len()).unwrap_or(0));
+        summary.hashes = Hashes::compute(file_bytes);
+        summary.fuzzy_hashes = FuzzyHashes::compute(file_bytes);
+        summary.imphash = import_hash(pe);
+        summary.rich_header_hash = rich_header_hash(file_bytes, pe.get_dos_header().e_lfanew as usize);
+
+        return summary;
+    }
+
+    pub fn from_elf(elf: &ELF, file_bytes: &[u8]) -> Summary {
+        let mut summary = Summary::default();
+
+        let header = elf.get_elf_header();
+
+        summary.format = format!(
+            "{} ({} endian)",
+            if matches!(elf.class(), ELFClass::ELF32) { "ELF32" } else { "ELF64" },
+            if header.is_big_endian() { "big" } else { "little" }
+        );
+
+        summary.architecture = match ELFTargetISA::from_machine(header.machine()) {
+            Some(isa) => format!("{:?}", isa),
+            None => format!("{:#x}", header.machine()),
+        };
+
+        summary.kind = format!("{:?}", header.file_type());
+        summary.entry_point = header.entry_point();
+        summary.has_entry_point = header.entry_point() != 0;
+
+        if let Some(section) = elf.get_section_for_address(header.entry_point()).filter(|_| header.entry_point() != 0) {
+            summary.entry_point_section = Some(section.name.clone());
+            summary.entry_point_in_code = (section.header.flags() & ELFSectionFlags::ExecInstr as u64) != 0;
+        }
+
+        for section in elf.sections.values() {
+            if (section.header.flags() & ELFSectionFlags::ExecInstr as u64) != 0 {
+                summary.code_size += section.size();
+                summary.code_sections += 1;
+            }
+        }
+
+        summary.file_size = file_bytes.len() as u64;
+        summary.packed = Some(analyze_elf_packing(elf).verdict);
+        summary.toolchain = detect_elf_toolchain(elf);
+        summary.sections = elf.sections.values().filter(|s| !s.name.is_empty()).count();
+        summary.imported_libraries = elf.needed_libraries.len();
+        summary.imported_functions = elf.dynamic_symbols.iter().filter(|s| s.is_imported()).count();
+        summary.exports = elf.dynamic_symbols.iter().filter(|s| s.is_exported()).count();
+        summary.hashes = Hashes::compute(file_bytes);
+        summary.fuzzy_hashes = FuzzyHashes::compute(file_bytes);
+
+        return summary;
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Summary");
+
+        dump.push_field("Format", self.format.clone(), None);
+        dump.push_field("Architecture", self.architecture.clone(), None);
+        dump.push_field("Type", self.kind.clone(), None);
+
+        if let Some(image_base) = self.image_base {
+            dump.push_field("ImageBase", format!("{:#x}", image_base), None);
+        }
+
+        let entry_point_origin = match (&self.entry_point_section, self.entry_point_in_code) {
+            (Some(section), true) => section.clone(),
+            (Some(section), false) => format!("{}, not executable", section),
+            (None, _) => String::from("outside of the sections"),
+        };
+
+        match self.has_entry_point {
+            true => dump.push_field("EntryPoint", format!("{:#x} ({})", self.entry_point, entry_point_origin), None),
+            false => dump.push_field("EntryPoint", String::from("None"), None),
+        }
+
+        let code_ratio = if self.file_size > 0 { self.code_size as f64 * 100.0 / self.file_size as f64 } else { 0.0 };
+
+        dump.push_field("Code", format!("{:#x} bytes in {} sections ({:.1}% of the file size)", self.code_size, self.code_sections, code_ratio), None);
+
+        if let Some(signed) = self.signed {
+            dump.push_field("Signed", yes_no(signed), None);
+        }
+
+        if let Some(packed) = self.packed {
+            let verdict: &'static str = packed.into();
+            dump.push_field("Packed", String::from(verdict), None);
+        }
+
+        if !self.packers.is_empty() {
+            dump.push_field("Packer", self.packers.iter().map(|packer| packer.describe()).collect::<Vec<String>>().join(", "), None);
+        }
+
+        if let Some(ref toolchain) = self.toolchain {
+            dump.push_field("Toolchain", toolchain.describe(), None);
+        }
+
+        if let Some(managed) = self.managed {
+            dump.push_field("Managed", yes_no(managed), None);
+        }
+
+        dump.push_field("Sections", format!("{}", self.sections), None);
+        dump.push_field("Imports", format!("{} functions from {} libraries", self.imported_functions, self.imported_libraries), None);
+        dump.push_field("Exports", format!("{}", self.exports), None);
+
+        if let Some(resources) = self.resources {
+            dump.push_field("Resources", format!("{}", resources), None);
+        }
+
+        dump.push_field("FileSize", format!("{:#x}", self.file_size), None);
+        dump.push_field("MD5", self.hashes.md5.clone(), None);
+        dump.push_field("SHA1", self.hashes.sha1.clone(), None);
+        dump.push_field("SHA256", self.hashes.sha256.clone(), None);
+
+        dump.push_field("SSDEEP", self.fuzzy_hashes.ssdeep.clone(), None);
+
+        if let Some(ref tlsh) = self.fuzzy_hashes.tlsh {
+            dump.push_field("TLSH", tlsh.clone(), None);
+        }
+
+        if let Some(ref imphash) = self.imphash {
+            dump.push_field("Imphash", imphash.clone(), None);
+        }
+
+        if let Some(ref rich_header_hash) = self.rich_header_hash {
+            dump.push_field("RichHeaderHash", rich_header_hash.clone(), None);
+        }
+
+        return dump;
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use pdb::FallibleIterator;
+
+use crate::demangle::display_name;
+use crate::dump::Dump;
+use crate::pe::{CodeViewRecord, PE};
+
+/*
+ * PDB retrieval from symbol servers, following the layout of symsrv: <server>/<pdb name>/<GUID><age>/<pdb name>
+ */
+
+pub const MICROSOFT_SYMBOL_SERVER: &str = "https://msdl.microsoft.com/download/symbols";
+
+/* Upper bound on the size of a downloaded PDB */
+pub const MAX_PDB_SIZE: u64 = 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Default)]
+pub struct SymbolStore {
+    /// URLs or symbol store directories, searched in order
+    pub servers: Vec<String>,
+    pub cache_dir: PathBuf,
+}
+
+/// srv*<cache>*<server>*<server> entries of _NT_SYMBOL_PATH, the cache is optional
+fn parse_nt_symbol_path(symbol_path: &str) -> (Vec<String>, Option<PathBuf>) {
+    let mut servers = Vec::new();
+    let mut cache = None;
+
+    for entry in symbol_path.split(';').map(|e| e.trim()) {
+        let Some(rest) = entry.strip_prefix("srv*").or_else(|| entry.strip_prefix("SRV*")) else {
+            continue;
+        };
+
+        let parts: Vec<&str> = rest.split('*').filter(|p| !p.is_empty()).collect();
+
+        match parts.as_slice() {
+            [] => {}
+            [server] => servers.push(server.to_string()),
+            [local, remote @ ..] => {
+                cache.get_or_insert_with(|| PathBuf::from(local));
+                servers.extend(remote.iter().map(|s| s.to_string()));
+            }
+        }
+    }
+
+    return (servers, cache);
+}
+
+fn is_url(server: &str) -> bool {
+    return server.starts_with("http://") || server.starts_with("https://");
+}
+
+impl SymbolStore {
+    /// The servers and cache given on the command line, else the ones of _NT_SYMBOL_PATH, else the Microsoft symbol
+    /// server and the user cache directory
+    pub fn new(servers: &[String], cache_dir: Option<&Path>) -> SymbolStore {
+        let (env_servers, env_cache) = match std::env::var("_NT_SYMBOL_PATH") {
+            Ok(symbol_path) => parse_nt_symbol_path(&symbol_path),
+            Err(_) => (Vec::new(), None),
+        };
+
+        let servers = match (servers.is_empty(), env_servers.is_empty()) {
+            (false, _) => servers.to_vec(),
+            (true, false) => env_servers,
+            (true, true) => vec![String::from(MICROSOFT_SYMBOL_SERVER)],
+        };
+
+        let cache_dir = cache_dir
+            .map(|p| p.to_path_buf())
+            .or(env_cache)
+            .unwrap_or_else(|| dirs::cache_dir().unwrap_or_else(std::env::temp_dir).join("execdump").join("symbols"));
+
+        return SymbolStore { servers, cache_dir };
+    }
+
+    /// Path of the PDB in the cache, it is downloaded from the first server that has it when missing
+    pub fn fetch(&self, codeview: &CodeViewRecord) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let name = codeview.pdb_name();
+
+        if name.is_empty() || name == "." || name == ".." {
+            return Err(format!("Invalid PDB name in the CodeView record: \"{}\"", codeview.pdb_path).into());
+        }
+
+        let key = codeview.symbol_server_key();
+        let cached = self.cache_dir.join(&name).join(&key).join(&name);
+
+        if cached.is_file() {
+            return Ok(cached);
+        }
+
+        let mut errors = Vec::new();
+
+        for server in self.servers.iter() {
+            let data = match is_url(server) {
+                true => download(&format!("{}/{}/{}/{}", server.trim_end_matches('/'), name, key, name)),
+                false => std::fs::read(Path::new(server).join(&name).join(&key).join(&name)).map_err(|e| e.into()),
+            };
+
+            match data {
+                Ok(data) => {
+                    std::fs::create_dir_all(cached.parent().unwrap())?;
+
+                    /* Written aside first, an interrupted download must not be taken for a cached PDB */
+                    let partial = cached.with_extension("partial");
+                    std::fs::write(&partial, data)?;
+                    std::fs::rename(&partial, &cached)?;
+
+                    return Ok(cached);
+                }
+                Err(e) => errors.push(format!("{}: {}", server, e)),
+            }
+        }
+
+        return Err(format!("{}/{} not found ({})", name, key, errors.join(", ")).into());
+    }
+}
+
+fn download(url: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let response = ureq::get(url).call()?;
+
+    let mut data = Vec::new();
+    response.into_reader().take(MAX_PDB_SIZE + 1).read_to_end(&mut data)?;
+
+    if data.len() as u64 > MAX_PDB_SIZE {
+        return Err(format!("the PDB is larger than {} bytes", MAX_PDB_SIZE).into());
+    }
+
+    return Ok(data);
+}
+
+/// Reads the public symbols by RVA, the PDB must have the GUID (or the NB10 timestamp) of the CodeView record
+pub fn load_pdb_symbols(path: &Path, codeview: &CodeViewRecord) -> Result<BTreeMap<u32, String>, Box<dyn std::error::Error>> {
+    let mut pdb = pdb::PDB::open(File::open(path)?)?;
+
+    let information = pdb.pdb_information()?;
+
+    /* PDB 2.0 files have no GUID, their signature is the timestamp of the NB10 record */
+    if codeview.is_rsds() {
+        let guid = information.guid.to_string();
+
+        if !guid.eq_ignore_ascii_case(&codeview.guid_string()) {
+            return Err(format!("{} does not match the executable (GUID {} instead of {})", path.display(), guid, codeview.guid_string()).into());
+        }
+    } else if information.signature != codeview.timestamp {
+        return Err(format!("{} does not match the executable (signature {:#x} instead of {:#x})", path.display(), information.signature, codeview.timestamp).into());
+    }
+
+    let address_map = pdb.address_map()?;
+    let global_symbols = pdb.global_symbols()?;
+
+    let mut symbols = BTreeMap::new();
+    let mut iter = global_symbols.iter();
+
+    while let Some(symbol) = iter.next()? {
+        if let Ok(pdb::SymbolData::Public(public)) = symbol.parse() {
+            /* The first name is kept when several symbols share an address */
+            if let Some(rva) = public.offset.to_rva(&address_map) {
+                symbols.entry(rva.0).or_insert_with(|| public.name.to_string().to_string());
+            }
+        }
+    }
+
+    return Ok(symbols);
+}
+
+/// Fetches the PDB matching the executable and attaches its symbols to it
+pub fn fetch_pdb(pe: &mut PE, store: &SymbolStore) -> Result<(), Box<dyn std::error::Error>> {
+    let codeview = pe.codeview.as_ref().ok_or("No CodeView (RSDS) record in the debug directory")?;
+
+    let path = store.fetch(codeview)?;
+    let symbols = load_pdb_symbols(&path, codeview)?;
+
+    pe.set_pdb_symbols(path, symbols);
+
+    return Ok(());
+}
+
+pub fn dump_pdb(pe: &PE) -> Dump {
+    let mut dump = Dump::new("PDB");
+
+    if let Some(ref path) = pe.pdb_path {
+        dump.push_field("Path", path.display().to_string(), None);
+    }
+
+    if let Some(ref codeview) = pe.codeview {
+        match codeview.is_rsds() {
+            true => dump.push_field("Guid", codeview.guid_string(), None),
+            false => dump.push_field("Signature", format!("{:#x}", codeview.timestamp), None),
+        }
+
+        dump.push_field("Age", format!("{}", codeview.age), None);
+    }
+
+    dump.push_field("PublicSymbols", format!("{}", pe.pdb_symbols.len()), None);
+
+    let exports: Vec<_> = pe
+        .export_data
+        .iter()
+        .flat_map(|e| e.entries.iter())
+        .filter_map(|e| e.symbol.as_ref().map(|symbol| (e, symbol)))
+        .collect();
+
+    let mut exports_dump = Dump::new_from_string(format!("Symbolized Exports ({})", exports.len()));
+
+    for (entry, symbol) in exports {
+        exports_dump.push_field("", format!("{:>5} {} {:#x} {}", entry.ordinal, entry.display_name(), entry.rva, display_name(symbol)), None);
+    }
+
+    dump.push_child(exports_dump);
+
+    return dump;
+}
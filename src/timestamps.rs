@@ -0,0 +1,155 @@
+use chrono::Utc;
+
+use crate::authenticode::pe_signature;
+use crate::dump::Dump;
+use crate::format::{format_timestamp, format_unix_time};
+use crate::pe::{DebugType, PE};
+
+/*
+ * Consistency of the timestamps of a PE image (timestomping)
+ * The linker writes the same time in the COFF header, the export directory and the debug directory entries,
+ * a tool rewriting the COFF header alone leaves the others behind
+ */
+
+/* Release of Windows NT 3.1, the first system loading PE images */
+const FIRST_PE_TIMESTAMP: i64 = 743731200;
+
+#[derive(Debug, Clone)]
+pub struct TimestampSource {
+    pub name: &'static str,
+    /// Type of the debug directory entries
+    pub kind: Option<&'static str>,
+    /// Seconds since the Unix epoch, 32-bit for the image headers
+    pub time: i64,
+}
+
+impl TimestampSource {
+    /// "Debug (CODE_VIEW)" for the debug directory entries
+    pub fn label(&self) -> String {
+        match self.kind {
+            Some(kind) => return format!("{} ({})", self.name, kind),
+            None => return String::from(self.name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TimestampReport {
+    pub coff: u32,
+    /// The COFF timestamp of reproducible builds (/Brepro) is a hash of the image, not a time
+    pub reproducible: bool,
+    pub sources: Vec<TimestampSource>,
+    pub signing_time: Option<i64>,
+    pub anomalies: Vec<String>,
+}
+
+impl TimestampReport {
+    pub fn from_pe(pe: &PE) -> TimestampReport {
+        let mut report = TimestampReport::default();
+
+        report.coff = pe.get_nt_header().coff_header.time_date_stamp;
+        report.reproducible = pe.debug_entries.iter().any(|entry| DebugType::from(entry.directory.debug_type) == DebugType::Repro);
+
+        if let Some(ref export_data) = pe.export_data {
+            report.sources.push(TimestampSource { name: "Export", kind: None, time: export_data.export_directory_table.time_date_stamp as i64 });
+        }
+
+        for entry in pe.debug_entries.iter() {
+            report.sources.push(TimestampSource { name: "Debug", kind: Some(DebugType::from(entry.directory.debug_type).as_static_str()), time: entry.directory.time_date_stamp as i64 });
+        }
+
+        if let Some(ref resource_table) = pe.resource_table {
+            report.sources.push(TimestampSource { name: "Resource", kind: None, time: resource_table.root.time_date_stamp as i64 });
+        }
+
+        report.signing_time = pe_signature(pe).ok().and_then(|signature| signature.signing_time);
+
+        report.check();
+
+        return report;
+    }
+
+    fn check(&mut self) {
+        let now = Utc::now().timestamp();
+
+        let plausibility = |time: i64| -> Option<&'static str> {
+            if time < FIRST_PE_TIMESTAMP {
+                return Some("before the first PE loader");
+            }
+
+            if time > now {
+                return Some("in the future");
+            }
+
+            return None;
+        };
+
+        if self.coff == 0 {
+            self.anomalies.push(String::from("the COFF TimeDateStamp is zeroed"));
+        } else if let (false, Some(reason)) = (self.reproducible, plausibility(self.coff as i64)) {
+            self.anomalies.push(format!("the COFF TimeDateStamp {} is {}", format_timestamp(self.coff), reason));
+        }
+
+        /* Zero is what most linkers write in the directories they do not date, only the values are compared */
+        for source in self.sources.iter().filter(|source| source.time != 0) {
+            if source.time != self.coff as i64 {
+                self.anomalies.push(format!("the {} timestamp {} differs from the COFF TimeDateStamp", source.label(), format_timestamp(source.time as u32)));
+            } else if let (false, Some(reason)) = (self.reproducible, plausibility(source.time)) {
+                self.anomalies.push(format!("the {} timestamp {} is {}", source.label(), format_timestamp(source.time as u32), reason));
+            }
+        }
+
+        /* The signing time comes from the clock of the signer, it can only follow the link */
+        if let Some(signing_time) = self.signing_time {
+            if let Some(reason) = plausibility(signing_time) {
+                self.anomalies.push(format!("the signing time {} is {}", format_unix_time(signing_time), reason));
+            }
+
+            if !self.reproducible && self.coff != 0 && signing_time < self.coff as i64 {
+                self.anomalies.push(format!("the image is signed at {}, before its COFF TimeDateStamp {}", format_unix_time(signing_time), format_timestamp(self.coff)));
+            }
+        }
+    }
+
+    #[rustfmt::skip]
+    pub fn dump(&self) -> Dump {
+        let mut dump = Dump::new("Timestamps");
+
+        dump.push_field("COFF", format_timestamp(self.coff), None);
+
+        for source in self.sources.iter() {
+            let time = match source.time {
+                0 => String::from("not set"),
+                time => format_timestamp(time as u32),
+            };
+
+            match source.kind {
+                Some(kind) => dump.push_field(source.name, format!("{} {}", time, kind), None),
+                None => dump.push_field(source.name, time, None),
+            }
+        }
+
+        if let Some(signing_time) = self.signing_time {
+            dump.push_field("SigningTime", format_unix_time(signing_time), None);
+        }
+
+        if self.reproducible {
+            dump.push_field("Reproducible", String::from("true, the timestamps are hashes of the image"), None);
+        }
+
+        match self.anomalies.is_empty() {
+            true => dump.push_field("Verdict", String::from("consistent"), None),
+            false => dump.push_field("Verdict", format!("{} anomalies", self.anomalies.len()), None),
+        }
+
+        let mut anomalies_dump = Dump::new_from_string(format!("Anomalies ({})", self.anomalies.len()));
+
+        for anomaly in self.anomalies.iter() {
+            anomalies_dump.push_field("", anomaly.clone(), None);
+        }
+
+        dump.push_child(anomalies_dump);
+
+        return dump;
+    }
+}
@@ -0,0 +1,233 @@
+use crate::deps::pe_imported_dlls;
+use crate::elf::ELF;
+use crate::patch::rich_header_range;
+use crate::pe::PE;
+
+/*
+ * Likely compiler and linker of an executable, from the linker version, the Rich header, the section layout and the runtime imports
+ * Every toolchain is reported with the evidences it was identified from, none of them is proof on its own
+ */
+
+/* Written by the Go linker at the start of the code and in the build information */
+const GO_BUILD_ID_MARKER: &[u8] = b"\xff Go build ID: \"";
+const GO_BUILD_INFO_MARKER: &[u8] = b"\xff Go buildinf:";
+
+/* Sections of the Borland linkers, ".itext" holds the initialization code since Delphi 2009 */
+const DELPHI_SECTION_NAMES: [&str; 4] = ["CODE", "DATA", "BSS", ".itext"];
+
+/* Runtime DLLs telling the C runtime the image was built against */
+const RUNTIME_IMPORTS: &[(&str, &str)] = &[
+    ("vcruntime", "vcruntime"),
+    ("msvcp", "msvcp"),
+    ("ucrtbase", "ucrt"),
+    ("api-ms-win-crt-", "ucrt"),
+    /* The system C runtime of MinGW, before the versioned ones of Visual C++ (msvcr100.dll) */
+    ("msvcrt", "msvcrt"),
+    ("msvcr", "msvcr"),
+    ("libgcc_s_", "libgcc"),
+    ("libstdc++", "libstdc++"),
+    ("libwinpthread", "winpthreads"),
+    ("borlndmm", "borlndmm"),
+];
+
+#[derive(Debug, Clone)]
+pub struct Toolchain {
+    pub name: String,
+    pub evidences: Vec<String>,
+}
+
+impl Toolchain {
+    /// "MSVC 14.29, Visual Studio 2019 (Rich header linker build 30159, linker version 14.29, runtime vcruntime)"
+    pub fn describe(&self) -> String {
+        match self.evidences.is_empty() {
+            true => return self.name.clone(),
+            false => return format!("{} ({})", self.name, self.evidences.join(", ")),
+        }
+    }
+}
+
+/// Visual Studio release shipping the linker version
+fn visual_studio_release(major: u8, minor: u8) -> Option<&'static str> {
+    match (major, minor) {
+        (6, _) => return Some("Visual C++ 6.0"),
+        (7, 0) => return Some("Visual Studio .NET 2002"),
+        (7, _) => return Some("Visual Studio .NET 2003"),
+        (8, _) => return Some("Visual Studio 2005"),
+        (9, _) => return Some("Visual Studio 2008"),
+        (10, _) => return Some("Visual Studio 2010"),
+        (11, _) => return Some("Visual Studio 2012"),
+        (12, _) => return Some("Visual Studio 2013"),
+        (14, 0..=9) => return Some("Visual Studio 2015"),
+        (14, 10..=19) => return Some("Visual Studio 2017"),
+        (14, 20..=29) => return Some("Visual Studio 2019"),
+        (14, _) => return Some("Visual Studio 2022"),
+        _ => return None,
+    }
+}
+
+/// Build number of the linker, the last tool of the Rich header as it appends itself after the objects
+fn rich_header_linker_build(file_bytes: &[u8], nt_header_offset: usize) -> Option<u16> {
+    let range = rich_header_range(file_bytes, nt_header_offset)?;
+    let key = u32::from_le_bytes(file_bytes[range.end - 4..range.end].try_into().ok()?);
+
+    /* "DanS" and three padding values precede the (product << 16 | build, count) pairs */
+    let entries = file_bytes.get(range.start + 16..range.end - 8)?;
+
+    return entries
+        .chunks_exact(8)
+        .map(|entry| u32::from_le_bytes(entry[..4].try_into().unwrap()) ^ key)
+        .rfind(|comp_id| (comp_id >> 16) > 1)
+        .map(|comp_id| (comp_id & 0xFFFF) as u16);
+}
+
+fn contains(data: &[u8], marker: &[u8]) -> bool {
+    return data.windows(marker.len()).any(|window| window == marker);
+}
+
+fn runtime_evidences(pe: &PE) -> Vec<String> {
+    let mut runtimes: Vec<&'static str> = Vec::new();
+
+    for dll in pe_imported_dlls(pe).iter() {
+        let name = dll.name.to_ascii_lowercase();
+
+        if let Some((_, runtime)) = RUNTIME_IMPORTS.iter().find(|(prefix, _)| name.starts_with(prefix)) && !runtimes.contains(runtime) {
+            runtimes.push(runtime);
+        }
+    }
+
+    return runtimes.iter().map(|runtime| format!("runtime {}", runtime)).collect();
+}
+
+pub fn detect_pe_toolchain(pe: &PE, file_bytes: &[u8]) -> Option<Toolchain> {
+    let (major, minor) = pe.get_optional_header().get_linker_version();
+    let linker_version = format!("linker version {}.{:02}", major, minor);
+
+    let runtimes = runtime_evidences(pe);
+    let has_runtime = |runtime: &str| runtimes.iter().any(|evidence| *evidence == format!("runtime {}", runtime));
+
+    if pe.dotnet.is_some() {
+        return Some(Toolchain { name: String::from(".NET compiler"), evidences: vec![String::from("CLR header"), linker_version] });
+    }
+
+    /* The Go linker writes 3.0 and no Rich header */
+    let go_markers = pe.sections.iter().any(|section| contains(section.raw_data(), GO_BUILD_ID_MARKER) || contains(section.raw_data(), GO_BUILD_INFO_MARKER));
+
+    if go_markers {
+        let mut evidences = vec![String::from("Go build ID")];
+
+        if major == 3 {
+            evidences.push(linker_version);
+        }
+
+        return Some(Toolchain { name: String::from("Go"), evidences });
+    }
+
+    let delphi_sections: Vec<&str> = DELPHI_SECTION_NAMES.iter().copied().filter(|name| pe.has_section(name)).collect();
+
+    if !delphi_sections.is_empty() || (major, minor) == (2, 25) || has_runtime("borlndmm") {
+        let mut evidences = Vec::new();
+
+        if !delphi_sections.is_empty() {
+            evidences.push(format!("sections {}", delphi_sections.join(", ")));
+        }
+
+        if (major, minor) == (2, 25) {
+            evidences.push(linker_version.clone());
+        }
+
+        /* GNU ld and MSVC never write these sections, a lone linker version is not enough */
+        if delphi_sections.len() >= 2 || evidences.len() >= 2 || has_runtime("borlndmm") {
+            evidences.extend(runtimes.iter().cloned());
+            return Some(Toolchain { name: String::from("Delphi / C++Builder"), evidences });
+        }
+    }
+
+    /* Only the Microsoft linker writes a Rich header */
+    if let Some(build) = rich_header_linker_build(file_bytes, pe.get_dos_header().e_lfanew as usize) {
+        let name = match visual_studio_release(major, minor) {
+            Some(release) => format!("MSVC {}.{:02}, {}", major, minor, release),
+            None => String::from("MSVC"),
+        };
+
+        let mut evidences = vec![format!("Rich header linker build {}", build), linker_version];
+        evidences.extend(runtimes);
+
+        return Some(Toolchain { name, evidences });
+    }
+
+    /* GNU ld writes its own version, 2.xx */
+    if major == 2 {
+        let mut evidences = vec![linker_version];
+
+        if pe.coff_symbol_table.is_some() {
+            evidences.push(String::from("COFF symbol table"));
+        }
+
+        evidences.extend(runtimes);
+
+        return Some(Toolchain { name: String::from("MinGW-GCC (GNU ld)"), evidences });
+    }
+
+    /* lld-link writes 14.0 without a Rich header, with the MinGW runtime for llvm-mingw */
+    if (major, minor) == (14, 0) {
+        let name = match has_runtime("msvcrt") && !has_runtime("vcruntime") || has_runtime("libstdc++") || has_runtime("winpthreads") {
+            true => "Clang (LLD, MinGW runtime)",
+            false => "Clang / LLD",
+        };
+
+        let mut evidences = vec![format!("{} without a Rich header", linker_version)];
+        evidences.extend(runtimes);
+
+        return Some(Toolchain { name: String::from(name), evidences });
+    }
+
+    return None;
+}
+
+/// From the .comment section the compilers and linkers append their version to, and the Go sections
+pub fn detect_elf_toolchain(elf: &ELF) -> Option<Toolchain> {
+    if elf.sections.contains_key(".go.buildinfo") || elf.sections.contains_key(".note.go.buildid") {
+        let section = if elf.sections.contains_key(".go.buildinfo") { ".go.buildinfo" } else { ".note.go.buildid" };
+
+        return Some(Toolchain { name: String::from("Go"), evidences: vec![format!("section {}", section)] });
+    }
+
+    let comment = elf.sections.get(".comment")?;
+
+    let entries: Vec<String> = comment
+        .data
+        .split(|b| *b == 0)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| String::from_utf8_lossy(entry).trim().to_string())
+        .collect();
+
+    let compiler = entries.iter().find_map(|entry| {
+        if let Some(version) = entry.strip_prefix("GCC: ") {
+            return Some(format!("GCC {}", version));
+        }
+
+        if let Some(position) = entry.find("clang version ") {
+            return Some(format!("Clang {}", entry[position + "clang version ".len()..].split_whitespace().next().unwrap_or_default()));
+        }
+
+        if let Some(version) = entry.strip_prefix("rustc version ") {
+            return Some(format!("Rust {}", version.split_whitespace().next().unwrap_or_default()));
+        }
+
+        return None;
+    });
+
+    let linker = entries.iter().find_map(|entry| {
+        let version = entry.strip_prefix("Linker: ")?;
+        return Some(version.split(" (").next().unwrap_or(version).to_string());
+    });
+
+    let name = match (compiler, linker) {
+        (Some(compiler), Some(linker)) => format!("{}, {}", compiler, linker),
+        (Some(compiler), None) => compiler,
+        (None, Some(linker)) => linker,
+        (None, None) => return None,
+    };
+
+    return Some(Toolchain { name, evidences: vec![String::from(".comment section")] });
+}
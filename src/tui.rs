@@ -5,7 +5,6 @@ use ratatui::{
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
     widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
-    layout::{Size},
 };
 
 use crossterm::{
@@ -17,11 +16,12 @@ use crossterm::{
 };
 
 use serde::{Deserialize, Serialize};
-use std::{error::Error, io, path::PathBuf, cmp::min};
+use std::{error::Error, io, path::{Path, PathBuf}, cmp::min};
 
-use crate::{char_utils, dump::{Dump, DumpRawData}, x86_64::starts_with_type_qualifier};
+use crate::dump::{Dump, DumpRawData};
+use crate::diff::ExecDiff;
 use crate::exec::Exec;
-use crate::x86_64::{is_x86_64_register, is_type_qualifier};
+use crate::x86_64::{Register, memory_size, parse_immediate};
 
 #[derive(Clone, Debug)]
 struct Theme {
@@ -110,10 +110,8 @@ impl KeyBindings {
     fn load() -> Self {
         if let Some(home) = dirs::home_dir() {
             let config_path = home.join(".execdumprc");
-            if let Ok(contents) = std::fs::read_to_string(config_path) {
-                if let Ok(bindings) = toml::from_str(&contents) {
-                    return bindings;
-                }
+            if let Ok(contents) = std::fs::read_to_string(config_path) && let Ok(bindings) = toml::from_str(&contents) {
+                return bindings;
             }
         }
 
@@ -132,12 +130,20 @@ enum ExplorerItem {
     ELFProgramHeaders,
     Sections,
     Section(String),
+    /// Index in the section table, the names of a PE can repeat
+    PESection(usize, String),
     PEDataDirectories,
     PEImportTable,
     PEExportTable,
     PEResourceTable,
     PEExceptionTable,
     PEDebugDirectory,
+    Diff,
+    DiffHeaders,
+    DiffSections,
+    DiffImports,
+    DiffExports,
+    DiffResources,
 }
 
 impl ExplorerItem {
@@ -151,12 +157,19 @@ impl ExplorerItem {
             ExplorerItem::ELFProgramHeaders=> "Program Headers".to_string(),
             ExplorerItem::Sections => "Sections/".to_string(),
             ExplorerItem::Section(name) => format!("  {}", name),
+            ExplorerItem::PESection(_, name) => format!("  {}", name),
             ExplorerItem::PEDataDirectories => "Data Directories/".to_string(),
             ExplorerItem::PEImportTable => "  Import Table".to_string(),
             ExplorerItem::PEExportTable => "  Export Table".to_string(),
             ExplorerItem::PEResourceTable => "  Resource Table".to_string(),
             ExplorerItem::PEExceptionTable => "  Exception Table".to_string(),
             ExplorerItem::PEDebugDirectory => "  Debug Directory".to_string(),
+            ExplorerItem::Diff => "Diff/".to_string(),
+            ExplorerItem::DiffHeaders => "  Headers".to_string(),
+            ExplorerItem::DiffSections => "  Sections".to_string(),
+            ExplorerItem::DiffImports => "  Imports".to_string(),
+            ExplorerItem::DiffExports => "  Exports".to_string(),
+            ExplorerItem::DiffResources => "  Resources".to_string(),
         }
     }
 }
@@ -173,14 +186,12 @@ enum ViewType {
     PEResourceTable,
     PEExceptionTable,
     PEDebugDirectory,
+    Diff(Dump),
 }
 
 impl ViewType {
     fn should_scroll(&self) -> bool {
-        match self {
-            ViewType::Welcome => false,
-            _ => true,
-        }
+        !matches!(self, ViewType::Welcome)
     }
 }
 
@@ -195,6 +206,7 @@ enum ActivePane {
 struct App {
     exec: Exec,
     exec_path: PathBuf,
+    diff: Option<(PathBuf, ExecDiff)>,
     theme: Theme,
     key_bindings: KeyBindings,
     explorer_items: Vec<ExplorerItem>,
@@ -224,15 +236,21 @@ impl App {
 
         explorer_items.push(ExplorerItem::Sections);
 
-        let mut sections: Vec<String> = match &exec {
-            Exec::PE(pe) => pe.sections.keys().cloned().collect(),
-            Exec::ELF(elf) => elf.sections.keys().cloned().collect(),
-        };
+        match &exec {
+            Exec::PE(pe) => {
+                for (index, section) in pe.sections.iter().enumerate() {
+                    explorer_items.push(ExplorerItem::PESection(index, section.header.name.clone()));
+                }
+            }
+            Exec::ELF(elf) => {
+                let mut sections: Vec<String> = elf.sections.keys().cloned().collect();
 
-        sections.sort();
+                sections.sort();
 
-        for name in sections {
-            explorer_items.push(ExplorerItem::Section(name));
+                for name in sections {
+                    explorer_items.push(ExplorerItem::Section(name));
+                }
+            }
         }
 
         match &exec {
@@ -253,8 +271,9 @@ impl App {
         state.select(Some(0));
 
         return App {
-            exec: exec,
-            exec_path: exec_path,
+            exec,
+            exec_path,
+            diff: None,
             theme: Theme::codedark(),
             key_bindings: KeyBindings::load(),
             explorer_items,
@@ -267,6 +286,23 @@ impl App {
         };
     }
 
+    /// Adds the diff against another executable at the top of the explorer
+    fn with_diff(mut self, other_path: PathBuf, diff: ExecDiff) -> Self {
+        let diff_items = [
+            ExplorerItem::Diff,
+            ExplorerItem::DiffHeaders,
+            ExplorerItem::DiffSections,
+            ExplorerItem::DiffImports,
+            ExplorerItem::DiffExports,
+            ExplorerItem::DiffResources,
+        ];
+
+        self.explorer_items.splice(0..0, diff_items);
+        self.diff = Some((other_path, diff));
+
+        return self;
+    }
+
     fn handle_key(&mut self, key: KeyCode, modifiers: KeyModifiers) {
         let bindings = self.key_bindings.clone();
 
@@ -419,50 +455,68 @@ impl App {
 
     #[rustfmt::skip]
     fn activate_selected_item(&mut self) {
-        if let Some(idx) = self.explorer_state.selected() {
-            if let Some(item) = self.explorer_items.get(idx) {
-                match &self.exec {
-                    Exec::PE(pe) => {
-                        self.current_view = match item {
-                            ExplorerItem::PEDosHeader => {
-                                ViewType::Header(pe.get_dos_header().dump())
-                            }
-                            ExplorerItem::PENtHeader => ViewType::Header(pe.get_nt_header().dump()),
-                            ExplorerItem::PEOptionalHeader => {
-                                ViewType::Header(pe.get_optional_header().dump())
-                            }
-                            ExplorerItem::Section(name) => {
-                                let section = pe.sections.get(name).unwrap();
-
-                                ViewType::Section(section.dump(&pe, section.contains_code()))
-                            }
-                            ExplorerItem::PEImportTable => ViewType::PEImportTable,
-                            ExplorerItem::PEExportTable => ViewType::PEExportTable,
-                            ExplorerItem::PEResourceTable => ViewType::PEResourceTable,
-                            ExplorerItem::PEExceptionTable => ViewType::PEExceptionTable,
-                            ExplorerItem::PEDebugDirectory => ViewType::PEDebugDirectory,
-                            _ => self.current_view.clone(),
-                        };
-                    }
-                    Exec::ELF(elf) => {
-                        self.current_view = match item {
-                            ExplorerItem::ELFHeader => {
-                                ViewType::Header(elf.get_elf_header().dump())
-                            }
-                            ExplorerItem::Section(name) => {
-                                let section = elf.sections.get(name).unwrap();
-
-                                ViewType::Section(section.dump(&elf, true, section.contains_code()))
-                            }
-                            _ => self.current_view.clone(),
+        if let Some(idx) = self.explorer_state.selected() && let Some(item) = self.explorer_items.get(idx) {
+            if let Some((_, diff)) = &self.diff {
+                let diff_view = match item {
+                    ExplorerItem::Diff => Some(diff.dump()),
+                    ExplorerItem::DiffHeaders => Some(diff.dump_headers()),
+                    ExplorerItem::DiffSections => Some(diff.dump_sections()),
+                    ExplorerItem::DiffImports => Some(diff.dump_imports()),
+                    ExplorerItem::DiffExports => Some(diff.dump_exports()),
+                    ExplorerItem::DiffResources => Some(diff.dump_resources()),
+                    _ => None,
+                };
+
+                if let Some(dump) = diff_view {
+                    self.current_view = ViewType::Diff(dump);
+                    self.content_scroll = 0;
+                    self.hex_offset = 0;
+                    self.active_pane = ActivePane::Content;
+                    return;
+                }
+            }
+
+            match &self.exec {
+                Exec::PE(pe) => {
+                    self.current_view = match item {
+                        ExplorerItem::PEDosHeader => {
+                            ViewType::Header(pe.get_dos_header().dump())
                         }
-                    }
+                        ExplorerItem::PENtHeader => ViewType::Header(pe.get_nt_header().dump()),
+                        ExplorerItem::PEOptionalHeader => {
+                            ViewType::Header(pe.get_optional_header().dump())
+                        }
+                        ExplorerItem::PESection(index, _) => {
+                            let section = &pe.sections[*index];
+
+                            ViewType::Section(section.dump(pe, true, section.contains_code()))
+                        }
+                        ExplorerItem::PEImportTable => ViewType::PEImportTable,
+                        ExplorerItem::PEExportTable => ViewType::PEExportTable,
+                        ExplorerItem::PEResourceTable => ViewType::PEResourceTable,
+                        ExplorerItem::PEExceptionTable => ViewType::PEExceptionTable,
+                        ExplorerItem::PEDebugDirectory => ViewType::PEDebugDirectory,
+                        _ => self.current_view.clone(),
+                    };
                 }
+                Exec::ELF(elf) => {
+                    self.current_view = match item {
+                        ExplorerItem::ELFHeader => {
+                            ViewType::Header(elf.get_elf_header().dump())
+                        }
+                        ExplorerItem::Section(name) => {
+                            let section = elf.sections.get(name).unwrap();
 
-                self.content_scroll = 0;
-                self.hex_offset = 0;
-                self.active_pane = ActivePane::Content;
+                            ViewType::Section(section.dump(elf, true, section.contains_code()))
+                        }
+                        _ => self.current_view.clone(),
+                    }
+                }
             }
+
+            self.content_scroll = 0;
+            self.hex_offset = 0;
+            self.active_pane = ActivePane::Content;
         }
     }
 
@@ -470,6 +524,7 @@ impl App {
         match &self.current_view {
             ViewType::Welcome => self.render_welcome(),
             ViewType::Header(dump) => self.render_header(dump),
+            ViewType::Diff(dump) => self.render_header(dump),
             ViewType::Section(dump) => self.render_section(dump),
             ViewType::PEImportTable => self.render_import_table(),
             ViewType::PEDebugDirectory => self.render_debug_directory(),
@@ -520,16 +575,13 @@ impl App {
                 ),
                 Style::default().fg(self.theme.key),
             ),
-            Span::styled(format!("{}", value), Style::default().fg(self.theme.value)),
+            Span::styled(value.to_string(), Style::default().fg(self.theme.value)),
             Span::styled(
-                format!(
-                    "{}",
-                    if let Some(text) = comment {
+                (if let Some(text) = comment {
                         format!(" {text}")
                     } else {
                         "".to_string()
-                    }
-                ),
+                    }).to_string(),
                 Style::default()
                     .fg(self.theme.comment)
                     .add_modifier(Modifier::ITALIC),
@@ -553,7 +605,7 @@ impl App {
         let fields_indent = (indent + 1) * indent_size;
 
         for field in dump.iter_fields() {
-            if field.key.len() == 0 {
+            if field.key.is_empty() {
                 lines.push(self.line_from_value(field.value.as_str(), fields_indent));
             } else {
                 lines.push(self.line_from_key_value_comment(
@@ -687,39 +739,40 @@ impl App {
         let comma = if add_comma { "," } else { "" };
         let space = if add_space { " " } else { "" };
 
-        let fmt_text = format!("{}{}{}", comma, space, text.to_string());
+        let fmt_text = format!("{}{}{}", comma, space, text);
 
-        if is_x86_64_register(text) {
+        if Register::parse(text).is_some() {
             return vec![Span::styled(fmt_text, Style::default().fg(self.theme.asm_register))];
-        } else if text.starts_with("[") {
-            let mut spans = vec![Span::styled(format!("{}{}[", comma, space), Style::default().fg(self.theme.asm_separator))];
-
-            for (i, part) in text.trim_matches(|c| matches!(c, '[' | ']')).split_ascii_whitespace().enumerate() {
-                match i {
-                    0 => spans.extend(self.highlight_operand(part, false, false)),
-                    _ => spans.extend(self.highlight_operand(part, false, true)),
+        } else if parse_immediate(text).is_some() {
+            return vec![Span::styled(fmt_text, Style::default().fg(self.theme.asm_immediate))];
+        } else if let Some(open) = text.find('[') {
+            let mut spans = vec![Span::styled(format!("{}{}", comma, space), Style::default().fg(self.theme.asm_separator))];
+
+            /* Size qualifier, "ptr" and segment override before the brackets */
+            for part in text[..open].split_whitespace() {
+                match part.strip_suffix(':').and_then(Register::parse) {
+                    Some(_) => spans.push(Span::styled(part.to_string(), Style::default().fg(self.theme.asm_register))),
+                    None if part == "ptr" || memory_size(part).is_some() => spans.push(Span::styled(format!("{} ", part), Style::default().fg(self.theme.asm_separator))),
+                    None => spans.push(Span::styled(format!("{} ", part), Style::default().fg(self.theme.fg))),
                 }
             }
 
-            spans.push(Span::styled("]", Style::default().fg(self.theme.asm_separator)));
+            spans.push(Span::styled("[", Style::default().fg(self.theme.asm_separator)));
 
-            return spans;
-        } else if char_utils::is_digit(text) {
-            return vec![Span::styled(fmt_text, Style::default().fg(self.theme.asm_immediate))];
-        } else if starts_with_type_qualifier(text){
-            let mut spans = Vec::new();
-
-            for (i, part) in text.splitn(3, char::is_whitespace).enumerate() {
-                match i {
-                    0..1 => {
-                        let comma = if i == 0 && add_comma { "," } else { "" };
-                        let space = if i == 1 { " " } else { space };
-                        spans.push(Span::styled(format!("{}{}{}", comma, space, part), Style::default().fg(self.theme.asm_separator)));
+            for (i, part) in text[open..].trim_matches(|c| matches!(c, '[' | ']')).split_ascii_whitespace().enumerate() {
+                match part.split_once('*') {
+                    _ if matches!(part, "+" | "-") => spans.push(Span::styled(format!(" {}", part), Style::default().fg(self.theme.asm_separator))),
+                    Some((index, scale)) => {
+                        spans.extend(self.highlight_operand(index, false, i > 0));
+                        spans.push(Span::styled("*", Style::default().fg(self.theme.asm_separator)));
+                        spans.extend(self.highlight_operand(scale, false, false));
                     }
-                    _ => spans.extend(self.highlight_operand(part, false, true))
+                    None => spans.extend(self.highlight_operand(part, false, i > 0)),
                 }
             }
 
+            spans.push(Span::styled("]", Style::default().fg(self.theme.asm_separator)));
+
             return spans;
         }
 
@@ -770,7 +823,7 @@ impl App {
 
     fn render_section(&self, dump: &Dump) -> Text<'_> {
         match dump.raw_data() {
-            DumpRawData::Bytes(data) => self.render_section_hex(dump.label(), &data),
+            DumpRawData::Bytes(data) => self.render_section_hex(dump.label(), data),
             DumpRawData::Code(code) => self.render_section_code(dump.label(), code),
             DumpRawData::None() => Text::from("No data found in section"),
         }
@@ -814,6 +867,11 @@ impl App {
 
             if let Some(debug) = &pe.debug_directory {
                 lines.extend_from_slice(&self.lines_from_dump(&debug.dump(), 0, 4));
+
+                if let Some(codeview) = &pe.codeview {
+                    lines.push(Line::from(""));
+                    lines.extend_from_slice(&self.lines_from_dump(&codeview.dump(), 0, 4));
+                }
             } else {
                 lines.push(Line::from("No debug directory found"));
             }
@@ -860,7 +918,10 @@ fn ui(f: &mut Frame, app: &mut App) {
         .split(f.area());
 
     // Title bar
-    let title = format!("execdump - {}", app.exec_path.display());
+    let title = match &app.diff {
+        Some((other_path, _)) => format!("execdump - {} -> {}", app.exec_path.display(), other_path.display()),
+        None => format!("execdump - {}", app.exec_path.display()),
+    };
     let title_block = Block::default()
         .borders(Borders::ALL)
         .border_style(Style::default().fg(app.theme.border))
@@ -953,7 +1014,7 @@ fn ui(f: &mut Frame, app: &mut App) {
     app.content_scroll = scroll;
 }
 
-pub fn main(exec_path: &PathBuf, exec: Exec) -> Result<(), Box<dyn Error>> {
+fn run(mut app: App) -> Result<(), Box<dyn Error>> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
@@ -961,15 +1022,11 @@ pub fn main(exec_path: &PathBuf, exec: Exec) -> Result<(), Box<dyn Error>> {
 
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = App::new(exec, exec_path.clone());
-
     loop {
         terminal.draw(|f| ui(f, &mut app))?;
 
-        if let Event::Key(key) = event::read()? {
-            if key.kind == KeyEventKind::Press {
-                app.handle_key(key.code, key.modifiers);
-            }
+        if let Event::Key(key) = event::read()? && key.kind == KeyEventKind::Press {
+            app.handle_key(key.code, key.modifiers);
         }
 
         if app.should_quit {
@@ -989,3 +1046,19 @@ pub fn main(exec_path: &PathBuf, exec: Exec) -> Result<(), Box<dyn Error>> {
 
     return Ok(());
 }
+
+pub fn main(exec_path: &Path, exec: Exec) -> Result<(), Box<dyn Error>> {
+    return run(App::new(exec, exec_path.to_path_buf()));
+}
+
+/// Explores the old executable, with the changes to the new one listed in the explorer
+pub fn diff_main(
+    old_exec_path: &Path,
+    old_exec: Exec,
+    new_exec_path: &Path,
+    diff: ExecDiff,
+) -> Result<(), Box<dyn Error>> {
+    let app = App::new(old_exec, old_exec_path.to_path_buf()).with_diff(new_exec_path.to_path_buf(), diff);
+
+    return run(app);
+}
@@ -1,105 +1,308 @@
-pub fn is_type_qualifier(s: &str) -> bool {
-    return matches!(s, "byte" | "word" | "dword" | "qword");
+/*
+ * Operands of the x86/x86-64 instructions, parsed from the Intel syntax operand strings of capstone
+ */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterClass {
+    General,
+    InstructionPointer,
+    Segment,
+    Vector,
+    Mask,
+    Mmx,
+    Fpu,
+    Control,
+    Debug,
+    Bound,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Register {
+    pub name: String,
+    pub class: RegisterClass,
+    /// Size in bits
+    pub size: u16,
 }
 
-pub fn starts_with_type_qualifier(s: &str) -> bool {
-    if s.len() < 4 {
+/* 64, 32, 16 and 8-bit names of the general purpose registers */
+#[rustfmt::skip]
+const GENERAL_REGISTERS: [[&str; 4]; 16] = [
+    ["rax", "eax", "ax", "al"], ["rbx", "ebx", "bx", "bl"], ["rcx", "ecx", "cx", "cl"], ["rdx", "edx", "dx", "dl"],
+    ["rsi", "esi", "si", "sil"], ["rdi", "edi", "di", "dil"], ["rbp", "ebp", "bp", "bpl"], ["rsp", "esp", "sp", "spl"],
+    ["r8", "r8d", "r8w", "r8b"], ["r9", "r9d", "r9w", "r9b"], ["r10", "r10d", "r10w", "r10b"], ["r11", "r11d", "r11w", "r11b"],
+    ["r12", "r12d", "r12w", "r12b"], ["r13", "r13d", "r13w", "r13b"], ["r14", "r14d", "r14w", "r14b"], ["r15", "r15d", "r15w", "r15b"],
+];
+
+const GENERAL_REGISTER_SIZES: [u16; 4] = [64, 32, 16, 8];
+
+/// "xmm12" with prefix "xmm" and count 32 -> true, numbers with a leading zero are not register names
+fn is_numbered(name: &str, prefix: &str, count: u32) -> bool {
+    let Some(number) = name.strip_prefix(prefix) else {
+        return false;
+    };
+
+    if number.is_empty() || (number.len() > 1 && number.starts_with('0')) || !number.bytes().all(|b| b.is_ascii_digit()) {
         return false;
     }
 
-    if matches!(&s[..4], "byte" | "word") {
-        return true;
+    return number.parse::<u32>().is_ok_and(|n| n < count);
+}
+
+impl Register {
+    pub fn parse(s: &str) -> Option<Register> {
+        let name = s.trim().to_ascii_lowercase();
+
+        let (class, size) = if let Some(size) = GENERAL_REGISTERS.iter().find_map(|names| names.iter().position(|n| *n == name).map(|i| GENERAL_REGISTER_SIZES[i])) {
+            (RegisterClass::General, size)
+        } else {
+            match name.as_str() {
+                "ah" | "bh" | "ch" | "dh" => (RegisterClass::General, 8),
+                /* Pseudo index registers of the long nops */
+                "eiz" => (RegisterClass::General, 32),
+                "riz" => (RegisterClass::General, 64),
+                "rip" => (RegisterClass::InstructionPointer, 64),
+                "eip" => (RegisterClass::InstructionPointer, 32),
+                "ip" => (RegisterClass::InstructionPointer, 16),
+                "cs" | "ds" | "es" | "fs" | "gs" | "ss" => (RegisterClass::Segment, 16),
+                n if is_numbered(n, "xmm", 32) => (RegisterClass::Vector, 128),
+                n if is_numbered(n, "ymm", 32) => (RegisterClass::Vector, 256),
+                n if is_numbered(n, "zmm", 32) => (RegisterClass::Vector, 512),
+                n if is_numbered(n, "k", 8) => (RegisterClass::Mask, 64),
+                n if is_numbered(n, "mm", 8) => (RegisterClass::Mmx, 64),
+                n if is_numbered(n, "cr", 16) => (RegisterClass::Control, 64),
+                n if is_numbered(n, "dr", 16) => (RegisterClass::Debug, 64),
+                n if is_numbered(n, "bnd", 4) => (RegisterClass::Bound, 128),
+                "st" => (RegisterClass::Fpu, 80),
+                n if n.strip_prefix("st(").and_then(|n| n.strip_suffix(')')).is_some_and(|n| is_numbered(n, "", 8)) => (RegisterClass::Fpu, 80),
+                _ => return None,
+            }
+        };
+
+        return Some(Register { name, class, size });
     }
 
-    if s.len() < 5 {
-        return false;
+    pub fn is_stack_pointer(&self) -> bool {
+        return matches!(self.name.as_str(), "rsp" | "esp" | "sp");
     }
 
-    if matches!(&s[..5], "dword" | "qword") {
-        return true;
+    pub fn is_frame_pointer(&self) -> bool {
+        return matches!(self.name.as_str(), "rbp" | "ebp" | "bp");
     }
 
-    return false;
+    pub fn is_instruction_pointer(&self) -> bool {
+        return self.class == RegisterClass::InstructionPointer;
+    }
 }
 
-pub fn is_x86_64_register(s: &str) -> bool {
-    if s.is_empty() || s.len() > 4 {
-        return false;
+/// Size in bits of the data accessed through a memory operand with the given qualifier
+pub fn memory_size(qualifier: &str) -> Option<u16> {
+    match qualifier.to_ascii_lowercase().as_str() {
+        "byte" => return Some(8),
+        "word" => return Some(16),
+        "dword" => return Some(32),
+        "fword" => return Some(48),
+        "qword" => return Some(64),
+        "tbyte" | "xword" => return Some(80),
+        "xmmword" | "oword" => return Some(128),
+        "ymmword" => return Some(256),
+        "zmmword" => return Some(512),
+        _ => return None,
     }
+}
 
-    let bytes = s.as_bytes();
-    let len = bytes.len();
+/// Decimal or 0x prefixed hexadecimal, optionally negative; values above i64::MAX wrap as their two's complement
+pub fn parse_immediate(s: &str) -> Option<i64> {
+    let s = s.trim();
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
 
-    let mut buf = [0u8; 4];
+    let value = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok()?,
+        None if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) => digits.parse::<u64>().ok()?,
+        None => return None,
+    };
 
-    for (i, &b) in bytes.iter().enumerate() {
-        buf[i] = b.to_ascii_lowercase();
-    }
+    return Some(if negative { (value as i64).wrapping_neg() } else { value as i64 });
+}
 
-    let lower = &buf[..len];
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryOperand {
+    /// Size in bits given by the qualifier ("dword ptr"), None for lea and the other size-less accesses
+    pub size: Option<u16>,
+    pub segment: Option<Register>,
+    pub base: Option<Register>,
+    pub index: Option<Register>,
+    pub scale: u8,
+    pub displacement: i64,
+}
 
-    match len {
-        2 => {
-            matches!(lower,
-                b"ax" | b"bx" | b"cx" | b"dx" |
-                b"si" | b"di" | b"bp" | b"sp" |
-                b"al" | b"ah" | b"bl" | b"bh" |
-                b"cl" | b"ch" | b"dl" | b"dh" |
-                b"r8" | b"r9")
-        }
-        3 => {
-            match lower[0] {
-                b'e' => matches!(lower, b"eax" | b"ebx" | b"ecx" | b"edx" |
-                                        b"esi" | b"edi" | b"ebp" | b"esp"),
-                b'r' => matches!(lower, b"rax" | b"rbx" | b"rcx" | b"rdx" |
-                                        b"rsi" | b"rdi" | b"rbp" | b"rsp" |
-                                        b"rip" | b"r10" | b"r11" | b"r12" |
-                                        b"r13" | b"r14" | b"r15") ||
-                        (lower[1] == b'8' || lower[1] == b'9') &&
-                        matches!(lower[2], b'd' | b'w' | b'b'),
-                b's' => matches!(lower, b"spl" | b"sil"),
-                b'b' | b'd' => lower[2] == b'l' && matches!(lower[1], b'p' | b'i'),
-                b'x' | b'y' | b'z' => lower[1] == b'm' && lower[2] == b'm',
-                _ => false,
+impl MemoryOperand {
+    /// "qword ptr fs:[rax + rcx*8 - 0x10]"
+    pub fn parse(s: &str) -> Option<MemoryOperand> {
+        let mut memory = MemoryOperand { scale: 1, ..MemoryOperand::default() };
+
+        let open = s.find('[')?;
+        let close = open + s[open..].find(']')?;
+
+        for token in s[..open].split_whitespace() {
+            if let Some(segment) = token.strip_suffix(':') {
+                memory.segment = Some(Register::parse(segment).filter(|r| r.class == RegisterClass::Segment)?);
+            } else if token != "ptr" {
+                memory.size = Some(memory_size(token)?);
             }
         }
-        4 => {
-            if lower[0] == b'r' && lower[1] >= b'1' && lower[1] <= b'9' {
-                let num = if lower[2] >= b'0' && lower[2] <= b'9' {
-                    (lower[1] - b'0') * 10 + (lower[2] - b'0')
-                } else {
-                    lower[1] - b'0'
-                };
-
-                if num >= 8 && num <= 9 {
-                    lower[2] == b'd' || lower[2] == b'w' || lower[2] == b'b'
-                } else if num >= 10 && num <= 15 {
-                    lower[3] == 0 || matches!(lower[3], b'd' | b'w' | b'b')
-                } else {
-                    false
+
+        let mut negative = false;
+
+        for token in s[open + 1..close].split_whitespace() {
+            match token {
+                "+" => negative = false,
+                "-" => negative = true,
+                _ => {
+                    if let Some((register, scale)) = token.split_once('*') {
+                        memory.index = Some(Register::parse(register)?);
+                        memory.scale = scale.parse().ok()?;
+                    } else if let Some(register) = Register::parse(token) {
+                        match memory.base {
+                            None => memory.base = Some(register),
+                            Some(_) => memory.index = Some(register),
+                        }
+                    } else {
+                        let value = parse_immediate(token)?;
+                        memory.displacement = memory.displacement.wrapping_add(if negative { value.wrapping_neg() } else { value });
+                    }
                 }
-            } else if matches!(lower[0], b'x' | b'y' | b'z') && lower[1] == b'm' && lower[2] == b'm' {
-                lower[3] >= b'0' && lower[3] <= b'9'
-            } else {
-                lower[0] == b'r' && matches!(lower[1], b'8' | b'9') &&
-                matches!(lower[2], b'd' | b'w' | b'b')
             }
         }
-        5 => {
-            if lower[0] == b'r' && lower[1] == b'1' &&
-               lower[2] >= b'0' && lower[2] <= b'5' &&
-               matches!(lower[3], b'd' | b'w' | b'b') {
-               return true;
-            } else if matches!(lower[0], b'x' | b'y' | b'z') &&
-                      lower[1] == b'm' && lower[2] == b'm' &&
-                      lower[3] >= b'1' && lower[3] <= b'3' &&
-                      lower[4] >= b'0' && lower[4] <= b'9' {
-                let num = (lower[3] - b'0') * 10 + (lower[4] - b'0');
-                num >= 10 && num <= 31
-            } else {
-                return false;
+
+        return Some(memory);
+    }
+
+    /// Index register, without the pseudo registers of the long nops that always read as zero
+    pub fn effective_index(&self) -> Option<&Register> {
+        return self.index.as_ref().filter(|r| !matches!(r.name.as_str(), "eiz" | "riz"));
+    }
+
+    pub fn is_rip_relative(&self) -> bool {
+        return self.base.as_ref().is_some_and(|b| b.is_instruction_pointer());
+    }
+
+    /// Address accessed when it does not depend on registers: rip-relative (from the next instruction) or absolute
+    pub fn absolute_address(&self, next_address: u64) -> Option<u64> {
+        if self.effective_index().is_some() {
+            return None;
+        }
+
+        match self.base {
+            Some(ref base) if base.is_instruction_pointer() => return Some(next_address.wrapping_add(self.displacement as u64)),
+            Some(_) => return None,
+            None => return Some(self.displacement as u64),
+        }
+    }
+
+    /// [register] or [register + displacement], the shape of structure field accesses
+    pub fn field_of(&self) -> Option<(&Register, i64)> {
+        if self.effective_index().is_some() || self.segment.is_some() {
+            return None;
+        }
+
+        return self.base.as_ref().filter(|b| !b.is_instruction_pointer()).map(|b| (b, self.displacement));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operand {
+    Register(Register),
+    Memory(MemoryOperand),
+    Immediate(i64),
+    /// Far pointers, AVX-512 masks and the other forms not modelled
+    Other(String),
+}
+
+impl Operand {
+    pub fn parse(s: &str) -> Operand {
+        let s = s.trim();
+
+        if let Some(register) = Register::parse(s) {
+            return Operand::Register(register);
+        }
+
+        if let Some(value) = parse_immediate(s) {
+            return Operand::Immediate(value);
+        }
+
+        if s.contains('[') && let Some(memory) = MemoryOperand::parse(s) {
+            return Operand::Memory(memory);
+        }
+
+        return Operand::Other(s.to_string());
+    }
+
+    pub fn register(&self) -> Option<&Register> {
+        match self {
+            Operand::Register(register) => return Some(register),
+            _ => return None,
+        }
+    }
+
+    pub fn memory(&self) -> Option<&MemoryOperand> {
+        match self {
+            Operand::Memory(memory) => return Some(memory),
+            _ => return None,
+        }
+    }
+
+    pub fn immediate(&self) -> Option<i64> {
+        match self {
+            Operand::Immediate(value) => return Some(*value),
+            _ => return None,
+        }
+    }
+}
+
+/// Operand strings of an instruction, split on the commas outside of brackets and braces
+pub fn split_operands(op_str: &str) -> Vec<&str> {
+    let mut operands = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (i, c) in op_str.char_indices() {
+        match c {
+            '[' | '{' | '(' => depth += 1,
+            ']' | '}' | ')' => depth -= 1,
+            ',' if depth == 0 => {
+                operands.push(&op_str[start..i]);
+                start = i + 1;
             }
+            _ => {}
         }
-        _ => false,
     }
+
+    if !op_str[start..].trim().is_empty() {
+        operands.push(&op_str[start..]);
+    }
+
+    return operands;
+}
+
+/// Operands of an instruction, split on the commas outside of brackets and braces
+pub fn parse_operands(op_str: &str) -> Vec<Operand> {
+    return split_operands(op_str).into_iter().map(Operand::parse).collect();
+}
+
+/// Target of a direct call or jump, whose only operand is the address
+pub fn direct_target(op_str: &str) -> Option<u64> {
+    match parse_operands(op_str).as_slice() {
+        [Operand::Immediate(target)] => return Some(*target as u64),
+        _ => return None,
+    }
+}
+
+/// Address read or written by the memory operand of an instruction, when it does not depend on registers
+pub fn memory_target(op_str: &str, next_address: u64) -> Option<u64> {
+    return parse_operands(op_str)
+        .iter()
+        .find_map(|operand| operand.memory().and_then(|m| m.absolute_address(next_address)));
 }
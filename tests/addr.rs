@@ -0,0 +1,115 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use common::fixture;
+
+/*
+ * Batch address conversions of the addr subcommand, every line gives the converted address, the section and the
+ * address of the third kind, the command fails when one of the addresses cannot be converted
+ */
+
+/// Exit status and the conversion lines of the report
+fn addr(file_path: &Path, args: &[&str]) -> (bool, Vec<String>) {
+    let output = Command::new(env!("CARGO_BIN_EXE_execdump")).arg("addr").args(args).arg(file_path).output().expect("Unable to run execdump");
+
+    let lines = String::from_utf8_lossy(&output.stdout).lines().skip(1).map(|line| line.trim().to_string()).collect();
+
+    return (output.status.success(), lines);
+}
+
+#[test]
+fn pe_conversions() {
+    let (success, lines) = addr(&fixture("app64.exe"), &["--rva2off", "0x1000,0x2040", "--off2rva", "0x600,0x410", "--va2rva", "0x140001010", "--rva2off", "0x10"]);
+
+    assert!(success);
+    assert_eq!(
+        lines,
+        [
+            "rva2off 0x1000 -> 0x400 (.text + 0x0, va 0x140001000)",
+            "rva2off 0x2040 -> 0x640 (.rdata + 0x40, va 0x140002040)",
+            "rva2off 0x10 -> 0x10 ((headers) + 0x10, va 0x140000010)",
+            "off2rva 0x600 -> 0x2000 (.rdata + 0x0, va 0x140002000)",
+            "off2rva 0x410 -> 0x1010 (.text + 0x10, va 0x140001010)",
+            "va2rva 0x140001010 -> 0x1010 (.text + 0x10, off 0x410)",
+        ]
+    );
+}
+
+#[test]
+fn elf_conversions() {
+    /* No image base, the RVAs are the virtual addresses */
+    let (success, lines) = addr(&fixture("hello.elf"), &["--off2rva", "0x2e8", "--va2rva", "0x2012e8", "--rva2off", "0x2012ea"]);
+
+    assert!(success);
+    assert_eq!(
+        lines,
+        [
+            "rva2off 0x2012ea -> 0x2ea (.text + 0x2)",
+            "off2rva 0x2e8 -> 0x2012e8 (.text + 0x0)",
+            "va2rva 0x2012e8 -> 0x2012e8 (.text + 0x0, off 0x2e8)",
+        ]
+    );
+}
+
+#[test]
+fn out_of_range_addresses_are_errors() {
+    let (success, lines) = addr(&fixture("app64.exe"), &["--rva2off", "0x9000,abc", "--off2rva", "0x800", "--va2rva", "0x100,0x150000000,0x140002000"]);
+
+    assert!(!success);
+    assert_eq!(
+        lines,
+        [
+            "rva2off 0x9000 -> error: RVA 0x9000 is outside of the image",
+            "rva2off abc -> error: Invalid address \"abc\": invalid digit found in string",
+            "off2rva 0x800 -> error: Offset 0x800 is past the end of the file (0x800 bytes)",
+            "va2rva 0x100 -> error: Address 0x100 is below the image base 0x140000000",
+            "va2rva 0x150000000 -> error: RVA 0x10000000 is outside of the image",
+            "va2rva 0x140002000 -> 0x2000 (.rdata + 0x0, off 0x600)",
+        ]
+    );
+
+    let (success, lines) = addr(&fixture("hello.elf"), &["--off2rva", "0x0", "--rva2off", "0x10"]);
+
+    assert!(!success);
+    assert_eq!(
+        lines,
+        [
+            "rva2off 0x10 -> error: Address 0x10 is outside of the sections",
+            "off2rva 0x0 -> error: Offset 0x0 in (headers) is not mapped in memory",
+        ]
+    );
+}
+
+#[test]
+fn zero_filled_addresses_have_no_offset() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("addr");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    /* A section of 0x10 bytes in the file and 0x2000 in memory */
+    let data_path = dir.join("section.bin");
+    let path = dir.join("zero-filled.exe");
+    std::fs::write(&data_path, [0xcc; 0x10]).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_execdump"))
+        .args(["add-section", "--name", ".bss2", "--virtual-size", "8192", "--data", data_path.to_str().unwrap(), "--output", path.to_str().unwrap()])
+        .arg(fixture("app64.exe"))
+        .output()
+        .expect("Unable to run execdump")
+        .status;
+
+    assert!(status.success());
+
+    let (success, lines) = addr(&path, &["--va2rva", "0x140003008,0x140004010", "--rva2off", "0x4010"]);
+
+    assert!(!success);
+    assert_eq!(
+        lines,
+        [
+            "rva2off 0x4010 -> error: RVA 0x4010 is zero-filled, not backed by the file",
+            "va2rva 0x140003008 -> 0x3008 (.bss2 + 0x8, off 0x808)",
+            "va2rva 0x140004010 -> 0x4010 (.bss2 + 0x1010)",
+        ]
+    );
+}
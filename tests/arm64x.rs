@@ -0,0 +1,106 @@
+mod common;
+
+use std::process::Command;
+
+use execdump::arm64x::{is_hybrid, Arm64XReport};
+use execdump::pe::ExcFunctionEntry;
+
+use common::{fixture, fixture_bytes, parse};
+
+/*
+ * ARM64X hybrid image built from a native ARM64 and an ARM64EC object, the ARM64X fixups of the load config turn the
+ * headers of the native view into the ones of the EC view
+ */
+
+#[test]
+fn native_and_ec_views() {
+    let bytes = fixture_bytes("arm64x.exe");
+    let pe = parse(&bytes);
+
+    assert!(is_hybrid(&pe));
+
+    let report = Arm64XReport::from_pe(&pe, &bytes);
+    let fields: Vec<(&str, &str, &str)> = report.fields.iter().map(|f| (f.name, f.native.as_str(), f.ec.as_str())).collect();
+
+    /* The EC view is seen as an x64 image by the emulator */
+    assert_eq!((report.native_machine, report.ec_machine), (0xaa64, 0x8664));
+    assert_eq!(
+        fields,
+        [
+            ("AddressOfEntryPoint", "0x1000", "0x3000"),
+            ("ExceptionTable", "address: 0x5000 sz: 0x10", "address: 0x0 sz: 0x0"),
+            ("LoadConfigTable", "address: 0x4000 sz: 0x140", "address: 0x4140 sz: 0x140"),
+        ]
+    );
+
+    let metadata = report.metadata.as_ref().expect("No ARM64EC metadata");
+    let ranges: Vec<(u32, &str)> = metadata.code_ranges.iter().map(|range| (range.start, range.range_type.as_static_str())).collect();
+
+    assert_eq!(metadata.alternate_entry_point, 0x2000);
+    assert_eq!(ranges, [(0x1000, "ARM64"), (0x2000, "ARM64EC"), (0x3000, "AMD64")]);
+    assert!(report.image_fixups.iter().all(|(_, section)| section.as_deref() == Some(".rdata")));
+}
+
+#[test]
+fn arm64x_flag() {
+    let output = Command::new(env!("CARGO_BIN_EXE_execdump"))
+        .args(["--arm64x", "--pe-exc-table"])
+        .arg(fixture("arm64x.exe"))
+        .output()
+        .expect("Unable to run execdump");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("Exception Table (2 entries)"), "{}", stdout);
+    assert!(stdout.contains("Views        : native ARM64, EC AMD64"), "{}", stdout);
+    assert!(stdout.contains("Native View\n        Machine             : 0xaa64 (ARM64)"), "{}", stdout);
+    assert!(stdout.contains("EC View\n        Machine             : 0x8664 (AMD64)"), "{}", stdout);
+
+    /* Not a hybrid image */
+    let output = Command::new(env!("CARGO_BIN_EXE_execdump")).arg("--arm64x").arg(fixture("app64.exe")).output().unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No ARM64X dynamic relocations nor ARM64EC metadata"));
+}
+
+#[test]
+fn arm64_function_entries() {
+    let mut bytes = fixture_bytes("arm64x.exe");
+    let pe = parse(&bytes);
+    let entries: Vec<(u32, u32)> = pe
+        .exception_table
+        .as_ref()
+        .expect("No exception table")
+        .entries
+        .iter()
+        .map(|entry| match entry {
+            ExcFunctionEntry::Arm64(e) => (e.begin_address, e.flag()),
+            other => panic!("Not an ARM64 entry: {:?}", other),
+        })
+        .collect();
+
+    /* The epilogs of the functions need an .xdata record */
+    assert_eq!(entries, [(0x1000, 0), (0x2000, 0)]);
+
+    /* Packed unwind data of a 0x10 bytes function with a 0x10 bytes frame, chained to x29 and x30 */
+    let pdata = pe.convert_rva_to_file_offset(0x5000).unwrap() as usize;
+    let packed: u32 = 1 | (4 << 2) | (3 << 21) | (1 << 23);
+    bytes[pdata + 4..pdata + 8].copy_from_slice(&packed.to_le_bytes());
+
+    let pe = parse(&bytes);
+    let dump = pe.exception_table.as_ref().unwrap().entries[0].dump();
+    let fields: Vec<(&str, &str)> = dump.iter_fields().map(|f| (f.key, f.value.as_str())).collect();
+
+    assert_eq!(
+        fields,
+        [
+            ("BeginAddress", "0x1000"),
+            ("Flag", "1 (packed)"),
+            ("FunctionLength", "0x10"),
+            ("RegF", "0"),
+            ("RegI", "0"),
+            ("H", "0"),
+            ("CR", "3"),
+            ("FrameSize", "0x10"),
+        ]
+    );
+}
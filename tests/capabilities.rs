@@ -0,0 +1,63 @@
+mod common;
+
+use execdump::capabilities::{normalize_api_name, CapabilitiesReport};
+
+use common::{execdump, fixture_bytes, work_dir};
+
+/*
+ * Matching of the imported APIs with the capability rules
+ */
+
+#[test]
+fn api_names_are_normalized() {
+    let cases = [
+        ("CreateProcessW", "CreateProcess"),
+        ("CreateProcessA", "CreateProcess"),
+        ("RegSetValueExW", "RegSetValueEx"),
+        ("GetDC", "GetDC"),
+        /* The suffix follows an uppercase letter */
+        ("CreateDCW", "CreateDC"),
+        ("CreateDCA", "CreateDC"),
+        ("LoadLibraryExA", "LoadLibraryEx"),
+        /* Too short to have a suffix */
+        ("W", "W"),
+    ];
+
+    for (name, expected) in cases.iter() {
+        assert_eq!(normalize_api_name(name), *expected, "{}", name);
+    }
+}
+
+#[test]
+fn unicode_and_ansi_apis_match_the_rules() {
+    let imports: Vec<String> = ["BitBlt", "CreateDCW"].iter().map(|s| s.to_string()).collect();
+    let report = CapabilitiesReport::from_imports(&imports);
+
+    let screen_capture = report.capabilities.iter().find(|c| c.rule.name == "Screen capture").expect("CreateDCW does not match CreateDC");
+
+    assert_eq!(screen_capture.evidence, imports);
+}
+
+#[test]
+fn attack_implies_capabilities() {
+    let mut bytes = fixture_bytes("app32.exe");
+
+    /* The kernel32.dll imports become the screen capture ones, the names keep their length */
+    for (name, replacement) in [(&b"GetStdHandle@4"[..], &b"GetDC\0\0\0\0\0\0\0\0\0"[..]), (b"WriteFile@20", b"BitBlt\0\0\0\0\0\0")] {
+        let offset = bytes.windows(name.len()).position(|w| w == name).expect("Import name not found");
+        bytes[offset..offset + name.len()].copy_from_slice(replacement);
+    }
+
+    let path = work_dir("attack").join("app32.exe");
+    std::fs::write(&path, &bytes).unwrap();
+
+    let capabilities = execdump(&["--capabilities"], &path);
+
+    assert!(capabilities.contains("Screen capture\n        APIs : BitBlt, GetDC"), "{}", capabilities);
+    assert!(!capabilities.contains("ATT&CK"), "{}", capabilities);
+
+    /* --attack alone dumps the capabilities with their techniques */
+    let attack = execdump(&["--attack"], &path);
+
+    assert!(attack.contains("APIs   : BitBlt, GetDC\n        ATT&CK : T1113 (Screen Capture)"), "{}", attack);
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use std::path::Path;
+
+use execdump::carve::CarveReport;
+
+use common::{execdump, fixture_bytes, work_dir};
+
+/*
+ * Carving of app64.exe and lib64.dll embedded in a blob, and of executables the blob cuts short
+ */
+
+/// app64.exe (0x800 bytes) at 0x1000 and lib64.dll (0xc00 bytes) right after it, between padding and an "MZ" that
+/// starts no executable
+fn blob() -> Vec<u8> {
+    let mut blob = vec![0xcc; 0x1000];
+
+    blob.extend(fixture_bytes("app64.exe"));
+    blob.extend(fixture_bytes("lib64.dll"));
+    blob.extend(b"MZ is not an executable");
+
+    return blob;
+}
+
+#[test]
+fn embedded_executables() {
+    let blob = blob();
+    let mut report = CarveReport::scan(&blob);
+
+    let executables: Vec<(u64, u64, bool, bool, usize, Option<usize>)> = report
+        .executables
+        .iter()
+        .map(|e| (e.file_offset, e.size, e.truncated, e.is_dll, e.number_of_sections, e.parent))
+        .collect();
+
+    assert_eq!(executables, [(0x1000, 0x800, false, false, 2, None), (0x1800, 0xc00, false, true, 4, None)]);
+    assert!(report.executables.iter().all(|e| e.machine == 0x8664 && !e.is_32_bits));
+    assert!(report.rejected.is_empty(), "{:?}", report.rejected);
+
+    let dir = work_dir("embedded");
+    report.extract(&blob, Path::new("blob.bin"), &dir).unwrap();
+
+    assert_eq!(report.executables[0].extracted, Some(dir.join("blob_1000.exe")));
+    assert_eq!(report.executables[1].extracted, Some(dir.join("blob_1800.dll")));
+    assert_eq!(std::fs::read(dir.join("blob_1000.exe")).unwrap(), fixture_bytes("app64.exe"));
+    assert_eq!(std::fs::read(dir.join("blob_1800.dll")).unwrap(), fixture_bytes("lib64.dll"));
+}
+
+#[test]
+fn truncated_executables() {
+    let app = fixture_bytes("app64.exe");
+
+    /* The headers are complete, the file ends in .text */
+    let mut blob = vec![0xcc; 0x10];
+    blob.extend(&app[..0x500]);
+
+    let report = CarveReport::scan(&blob);
+
+    assert_eq!(report.executables.iter().map(|e| (e.file_offset, e.size, e.truncated)).collect::<Vec<_>>(), [(0x10, 0x500, true)]);
+
+    /* The file ends in the optional header */
+    let mut blob = vec![0xcc; 0x10];
+    blob.extend(&app[..0x120]);
+
+    let report = CarveReport::scan(&blob);
+
+    assert!(report.executables.is_empty());
+    assert_eq!(report.rejected.iter().map(|r| (r.file_offset, r.reason.as_str())).collect::<Vec<_>>(), [(0x10, "Unexpected end of file")]);
+}
+
+#[test]
+fn carve_subcommand() {
+    let dir = work_dir("subcommand");
+    let blob_path = dir.join("blob.bin");
+    let mut blob = blob();
+
+    blob.extend(&fixture_bytes("app64.exe")[..0x120]);
+    std::fs::write(&blob_path, &blob).unwrap();
+
+    let output_dir = dir.join("carved");
+    let stdout = execdump(&["carve", "--extract", "--output-dir", &output_dir.display().to_string()], &blob_path);
+
+    assert!(stdout.contains("Carved Executables (2)"), "{}", stdout);
+    assert!(stdout.contains("#1 at 0x1800\n        FileOffset    : 0x1800\n        Size          : 0xc00\n        Format        : PE32+\n        Machine       : AMD64\n        Type          : DLL"), "{}", stdout);
+    assert!(stdout.contains(&format!("Output        : {}", output_dir.join("blob_1000.exe").display())), "{}", stdout);
+    assert!(stdout.contains("Rejected Candidates (1)\n        0x2417: Unexpected end of file"), "{}", stdout);
+
+    assert_eq!(std::fs::read(output_dir.join("blob_1000.exe")).unwrap(), fixture_bytes("app64.exe"));
+    assert_eq!(std::fs::read(output_dir.join("blob_1800.dll")).unwrap(), fixture_bytes("lib64.dll"));
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use execdump::cil::{disassemble_method, find_methods, CilMethod, Operand};
+
+use common::{execdump, fixture, fixture_bytes, parse};
+
+/*
+ * CIL disassembly of the methods of hello.net.exe: Hello.Program::Main (tiny header) prints a user string,
+ * Hello.Program::Add (fat header) has a local and a branch, Hello.Program::.ctor calls System.Object::.ctor
+ */
+
+fn method(query: &str) -> CilMethod {
+    let pe = parse(&fixture_bytes("hello.net.exe"));
+    let dotnet = pe.dotnet.as_ref().expect("No .NET metadata");
+    let methods = find_methods(dotnet, query).unwrap();
+
+    assert_eq!(methods.len(), 1, "{:?}", methods);
+
+    return disassemble_method(&pe, dotnet, methods[0]);
+}
+
+/// Formatted instructions of the method, with the tokens resolved against the metadata
+fn listing(query: &str) -> Vec<String> {
+    let pe = parse(&fixture_bytes("hello.net.exe"));
+    let dotnet = pe.dotnet.as_ref().unwrap();
+    let (instructions, error) = method(query).body.expect("No method body").instructions();
+
+    assert_eq!(error, None);
+
+    return instructions.iter().map(|i| i.format(dotnet)).collect();
+}
+
+#[test]
+fn method_lookup() {
+    let pe = parse(&fixture_bytes("hello.net.exe"));
+    let dotnet = pe.dotnet.as_ref().unwrap();
+
+    assert_eq!(find_methods(dotnet, "0x06000002").unwrap(), [2]);
+    assert_eq!(find_methods(dotnet, "Add").unwrap(), [2]);
+    assert_eq!(find_methods(dotnet, "Program::Add").unwrap(), [2]);
+    assert_eq!(find_methods(dotnet, "Hello.Program::.ctor").unwrap(), [3]);
+
+    assert_eq!(find_methods(dotnet, "Sub").expect_err("Sub is found").to_string(), "No method named \"Sub\"");
+    assert!(find_methods(dotnet, "0x06000004").is_err());
+    assert!(find_methods(dotnet, "0x0a000001").is_err());
+}
+
+#[test]
+fn tiny_method_body() {
+    let main = method("Main");
+
+    assert_eq!((main.token, main.name.as_str(), main.rva), (0x06000001, "Hello.Program::Main", 0x2048));
+    assert_eq!(main.signature, "void Hello.Program::Main()");
+
+    let body = main.body.as_ref().unwrap();
+    assert_eq!((body.fat, body.max_stack, body.code.len()), (false, 8, 11));
+
+    /* ldstr takes an offset in the #US heap, call a MemberRef token */
+    let (instructions, _) = body.instructions();
+    let operands: Vec<&Operand> = instructions.iter().map(|i| &i.operand).collect();
+
+    assert_eq!(operands, [&Operand::Token(0x70000001), &Operand::Token(0x0a000002), &Operand::None]);
+    assert_eq!(listing("Main"), ["IL_0000:  ldstr        \"Hello\"", "IL_0005:  call         void [mscorlib]System.Console::WriteLine(string)", "IL_000a:  ret"]);
+    assert_eq!(listing(".ctor"), ["IL_0000:  ldarg.0", "IL_0001:  call         instance void [mscorlib]System.Object::.ctor()", "IL_0006:  ret"]);
+}
+
+#[test]
+fn fat_method_body() {
+    let add = method("Add");
+    let body = add.body.as_ref().unwrap();
+
+    assert_eq!(add.signature, "int32 Hello.Program::Add(int32, int32)");
+    assert_eq!((body.fat, body.max_stack, body.init_locals, body.local_var_sig_token), (true, 2, true, 0x11000001));
+    assert!(body.exception_clauses.is_empty());
+    assert_eq!(
+        listing("Add"),
+        ["IL_0000:  ldarg.0", "IL_0001:  ldarg.1", "IL_0002:  add", "IL_0003:  stloc.0", "IL_0004:  br.s         IL_0006", "IL_0006:  ldloc.0", "IL_0007:  ret"]
+    );
+}
+
+#[test]
+fn disasm_cil_flag() {
+    let stdout = execdump(&["--disasm-cil", "Add"], &fixture("hello.net.exe"));
+
+    assert!(stdout.contains("CIL Disassembly (1)\n\n0x06000002 Hello.Program::Add"), "{}", stdout);
+    assert!(stdout.contains("Header    : Fat\n    MaxStack  : 2\n    CodeSize  : 0x8\n    Locals    : init [0] int32"), "{}", stdout);
+    assert!(stdout.contains("IL_0004:  br.s         IL_0006"), "{}", stdout);
+
+    let stdout = execdump(&["--disasm-cil", "0x06000001"], &fixture("hello.net.exe"));
+    assert!(stdout.contains("IL_0005:  call         void [mscorlib]System.Console::WriteLine(string)"), "{}", stdout);
+
+    let stdout = execdump(&["--disasm-cil", "Sub"], &fixture("hello.net.exe"));
+    assert!(stdout.contains("No method named \"Sub\""), "{}", stdout);
+
+    let stdout = execdump(&["--disasm-cil", "Main"], &fixture("app64.exe"));
+    assert!(stdout.contains("No .NET metadata found in PE"), "{}", stdout);
+}
@@ -0,0 +1,65 @@
+#![allow(dead_code, reason = "every test file uses a part of the helpers")]
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use execdump::pe::{parse_pe_from_reader, PEReader, PE};
+
+/*
+ * Helpers shared by the integration tests, the fixtures are read from tests/fixtures and the files written by a test go
+ * to its own directory under the target directory
+ */
+
+pub fn fixture(name: &str) -> PathBuf {
+    return PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(name);
+}
+
+pub fn fixture_bytes(name: &str) -> Vec<u8> {
+    return std::fs::read(fixture(name)).expect("Unable to read the fixture");
+}
+
+/// Empty directory of the test, named after the test file, kept after the run to look at the written files
+pub fn work_dir(test: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(env!("CARGO_CRATE_NAME")).join(test);
+
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("Unable to create the test directory");
+
+    return dir;
+}
+
+pub fn copy_fixture(name: &str, dir: &Path) -> PathBuf {
+    let path = dir.join(name);
+    std::fs::copy(fixture(name), &path).expect("Unable to copy the fixture");
+    return path;
+}
+
+pub fn parse(bytes: &[u8]) -> PE {
+    return parse_pe_from_reader(&mut PEReader::from_bytes(bytes), true).expect("Unable to parse the PE");
+}
+
+/// Runs execdump and returns its standard output, the command must succeed
+pub fn execdump(args: &[&str], file_path: &Path) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_execdump")).args(args).arg(file_path).output().expect("Unable to run execdump");
+
+    assert!(output.status.success(), "execdump {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+
+    return String::from_utf8_lossy(&output.stdout).to_string();
+}
+
+/// Lays the file out as the loader maps it, the sections at their virtual addresses
+pub fn map_image(pe: &PE, bytes: &[u8]) -> Vec<u8> {
+    let size_of_headers = pe.get_optional_header().get_size_of_headers() as usize;
+
+    let mut image = vec![0u8; pe.get_optional_header().get_size_of_image() as usize];
+    image[..size_of_headers].copy_from_slice(&bytes[..size_of_headers]);
+
+    for section in pe.sections.iter() {
+        let raw = &bytes[section.header.ptr_to_raw_data as usize..(section.header.ptr_to_raw_data + section.header.size_of_raw_data) as usize];
+        let virtual_address = section.header.virtual_address as usize;
+
+        image[virtual_address..virtual_address + raw.len()].copy_from_slice(raw);
+    }
+
+    return image;
+}
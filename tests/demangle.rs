@@ -0,0 +1,115 @@
+use execdump::demangle::{demangle, demangle_msvc, detect_compiler, Compiler};
+
+/*
+ * MSVC demangling, the expected names are the output of llvm-undname
+ */
+
+/* llvm-undname <mangled> */
+const MSVC_NAMES: [(&str, &str); 78] = [
+    ("?add@@YAHHH@Z", "int __cdecl add(int, int)"),
+    ("?foo@@YAXXZ", "void __cdecl foo(void)"),
+    ("??0Foo@@QEAA@XZ", "public: __cdecl Foo::Foo(void)"),
+    ("??1Foo@@UEAA@XZ", "public: virtual __cdecl Foo::~Foo(void)"),
+    ("??0Foo@@QAE@ABV0@@Z", "public: __thiscall Foo::Foo(class Foo const &)"),
+    ("?bar@Foo@@QEAAHH@Z", "public: int __cdecl Foo::bar(int)"),
+    ("?bar@Foo@@QEBAHPEBD@Z", "public: int __cdecl Foo::bar(char const *) const"),
+    ("?baz@Foo@@SAXXZ", "public: static void __cdecl Foo::baz(void)"),
+    ("?get@Foo@@AEAAAEAV1@XZ", "private: class Foo & __cdecl Foo::get(void)"),
+    ("?run@Bar@ns@@UEAAXPEAUWidget@2@@Z", "public: virtual void __cdecl ns::Bar::run(struct ns::Widget *)"),
+    ("??2@YAPEAX_K@Z", "void * __cdecl operator new(unsigned __int64)"),
+    ("??3@YAXPEAX@Z", "void __cdecl operator delete(void *)"),
+    ("??4Foo@@QEAAAEAV0@AEBV0@@Z", "public: class Foo & __cdecl Foo::operator=(class Foo const &)"),
+    ("??8Foo@@QEBA_NAEBV0@@Z", "public: bool __cdecl Foo::operator==(class Foo const &) const"),
+    ("??BFoo@@QEBAHXZ", "public: int __cdecl Foo::operator int(void) const"),
+    ("??_GFoo@@UEAAPEAXI@Z", "public: virtual void * __cdecl Foo::`scalar deleting dtor'(unsigned int)"),
+    ("??_7Foo@@6B@", "const Foo::`vftable'"),
+    ("?value@Foo@@2HA", "public: static int Foo::value"),
+    ("?count@@3HA", "int count"),
+    ("?ptr@@3PEAHEA", "int *ptr"),
+    ("?name@@3PEBDEB", "char const *name"),
+    ("?f@@YAXPEAPEAD@Z", "void __cdecl f(char **)"),
+    ("?g@@YAXAEAH@Z", "void __cdecl g(int &)"),
+    ("?h@@YAX_J_K@Z", "void __cdecl h(__int64, unsigned __int64)"),
+    ("?i@@YAXMN@Z", "void __cdecl i(float, double)"),
+    ("?j@@YAXPEAX0@Z", "void __cdecl j(void *, void *)"),
+    ("?k@@YAXPEBDPEBD@Z", "void __cdecl k(char const *, char const *)"),
+    ("?cb@@YAXP6AHH@Z@Z", "void __cdecl cb(int (__cdecl *)(int))"),
+    ("?tmpl@@YAXV?$vector@HV?$allocator@H@std@@@std@@@Z", "void __cdecl tmpl(class std::vector<int, class std::allocator<int>>)"),
+    ("?push_back@?$vector@HV?$allocator@H@std@@@std@@QEAAXAEBH@Z", "public: void __cdecl std::vector<int, class std::allocator<int>>::push_back(int const &)"),
+    ("??$max@H@@YAHHH@Z", "int __cdecl max<int>(int, int)"),
+    ("?s@@YAXUPoint@@@Z", "void __cdecl s(struct Point)"),
+    ("?e@@YAXW4Color@@@Z", "void __cdecl e(enum Color)"),
+    ("?stdcall_fn@@YGHH@Z", "int __stdcall stdcall_fn(int)"),
+    ("?fastcall_fn@@YIHH@Z", "int __fastcall fastcall_fn(int)"),
+    ("?thiscall@C@@QAEXXZ", "public: void __thiscall C::thiscall(void)"),
+    ("?v@@YAXHZZ", "void __cdecl v(int, ...)"),
+    ("?u@@YAX_W@Z", "void __cdecl u(wchar_t)"),
+    ("?b@@YAX_N@Z", "void __cdecl b(bool)"),
+    ("?c@@YAXDEF@Z", "void __cdecl c(char, unsigned char, short)"),
+    ("?l@@YAXJK@Z", "void __cdecl l(long, unsigned long)"),
+    ("?vb@@YAXCE@Z", "void __cdecl vb(signed char, unsigned char)"),
+    ("?ref@@YAX$$QEAH@Z", "void __cdecl ref(int &&)"),
+    ("??BFoo@@QEBAPEBDXZ", "public: char const * __cdecl Foo::operator char const *(void) const"),
+    ("??_EFoo@@UEAAPEAXI@Z", "public: virtual void * __cdecl Foo::`vector deleting dtor'(unsigned int)"),
+    ("??_UFoo@@SAPEAX_K@Z", "public: static void * __cdecl Foo::operator new[](unsigned __int64)"),
+    ("??_VFoo@@SAXPEAX@Z", "public: static void __cdecl Foo::operator delete[](void *)"),
+    ("?x@@3HB", "int const x"),
+    ("??$f@$0A@@@YAXXZ", "void __cdecl f<0>(void)"),
+    ("??$f@$00@@YAXXZ", "void __cdecl f<1>(void)"),
+    ("??$f@$0BA@@@YAXXZ", "void __cdecl f<16>(void)"),
+    ("?f@@YAXPAH@Z", "void __cdecl f(int *)"),
+    ("?f@@YAXQAH@Z", "void __cdecl f(int *const)"),
+    ("?size@?$basic_string@DU?$char_traits@D@std@@V?$allocator@D@2@@std@@QEBA_KXZ", "public: unsigned __int64 __cdecl std::basic_string<char, struct std::char_traits<char>, class std::allocator<char>>::size(void) const"),
+    ("?f@?A0x1234@@YAXXZ", "void __cdecl `anonymous namespace'::f(void)"),
+    ("?g@@YAXPEAPEBH@Z", "void __cdecl g(int const **)"),
+    ("?h@@YAXPEBQEAH@Z", "void __cdecl h(int *const *)"),
+    ("?f@Foo@@IEAAXXZ", "protected: void __cdecl Foo::f(void)"),
+    ("?f@Foo@@MEAAXXZ", "protected: virtual void __cdecl Foo::f(void)"),
+    ("?f@Foo@@KAXXZ", "protected: static void __cdecl Foo::f(void)"),
+    ("?f@Foo@@CAXXZ", "private: static void __cdecl Foo::f(void)"),
+    ("?f@Foo@@EEAAXXZ", "private: virtual void __cdecl Foo::f(void)"),
+    ("?f@@YAX_S_U@Z", "void __cdecl f(char16_t, char32_t)"),
+    ("?f@@YAXO@Z", "void __cdecl f(long double)"),
+    ("?f@@YA?AVFoo@@XZ", "class Foo __cdecl f(void)"),
+    ("?f@@YA?BVFoo@@XZ", "class Foo const __cdecl f(void)"),
+    ("?f@@YAXQ6AXXZ@Z", "void __cdecl f(void (__cdecl *const)(void))"),
+    ("?f@@YAXPECREAH@Z", "void __cdecl f(int *volatile *)"),
+    ("?f@@YAXSEAH@Z", "void __cdecl f(int *const volatile)"),
+    ("?p@@3QEAHEA", "int *const p"),
+    ("?name@@3QEBDEB", "char const *const name"),
+    ("?f@@YAXPEIAH@Z", "void __cdecl f(int *__restrict)"),
+    ("?f@@YAXQEIAH@Z", "void __cdecl f(int *const __restrict)"),
+    ("?f@@YAXAEBQEAH@Z", "void __cdecl f(int *const &)"),
+    ("??$g@H@ns@@YAPEAVFoo@0@XZ", "class ns::Foo * __cdecl ns::g<int>(void)"),
+    ("??$g@H@ns@@YAXVFoo@0@V?$g@H@0@@Z", "void __cdecl ns::g<int>(class ns::Foo, class ns::g<int>)"),
+    ("?h@?$B@H@ns@@QEAAXV12@@Z", "public: void __cdecl ns::B<int>::h(class ns::B<int>)"),
+    ("?h@?$B@V?$A@H@ns@@@ns@@QEAAXV?$A@H@2@@Z", "public: void __cdecl ns::B<class ns::A<int>>::h(class ns::A<int>)"),
+];
+
+#[test]
+fn msvc_names_match_llvm_undname() {
+    for (mangled, expected) in MSVC_NAMES.iter() {
+        assert_eq!(demangle_msvc(mangled).as_deref(), Ok(*expected), "{}", mangled);
+    }
+}
+
+#[test]
+fn malformed_msvc_names_are_errors() {
+    for mangled in ["?", "?foo", "?foo@@YA", "?foo@@YAH", "?foo@@YAXPEA", "??_Z@@YAXXZ", "?x@@3"] {
+        assert!(demangle_msvc(mangled).is_err(), "{}", mangled);
+    }
+}
+
+#[test]
+fn compilers_are_detected_from_the_mangling() {
+    assert_eq!(detect_compiler("?foo@@YAXXZ"), Compiler::MSVC);
+    assert_eq!(detect_compiler("_ZN3foo3barEv"), Compiler::GCC);
+    assert_eq!(detect_compiler("_ZN3foo3bar17h0123456789abcdefE"), Compiler::Rust);
+    assert_eq!(detect_compiler("_RNvCs1234_3foo3bar"), Compiler::Rust);
+    assert_eq!(detect_compiler("CreateFileW"), Compiler::Unknown);
+
+    assert_eq!(demangle("?foo@@YAXXZ").as_deref(), Ok("void __cdecl foo(void)"));
+    assert_eq!(demangle("_ZN3foo3barEv").as_deref(), Ok("foo::bar()"));
+    assert_eq!(demangle("_ZN3foo3barEv@@GLIBC_2.2.5").as_deref(), Ok("foo::bar()@@GLIBC_2.2.5"));
+    assert_eq!(demangle("_ZN3foo3bar17h0123456789abcdefE").as_deref(), Ok("foo::bar"));
+}
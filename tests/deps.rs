@@ -0,0 +1,147 @@
+mod common;
+
+use std::process::Command;
+
+use execdump::deps::{DependencyStatus, DependencyTree, ImportRef, MissingDependencies};
+
+use common::{copy_fixture, execdump, fixture, fixture_bytes, parse, work_dir};
+
+/*
+ * Dependencies of deps64.exe resolved in a test directory: lib64.dll is copied next to it but does not export qux,
+ * missing64.dll is not shipped and kernel32.dll is only found in a Windows filesystem root given as a search path
+ */
+
+/// Name, resolved path and unresolved functions of the first level of the tree
+fn dependencies(tree: &DependencyTree) -> Vec<(String, bool, Vec<String>)> {
+    return tree
+        .dependencies
+        .iter()
+        .map(|node| (node.name.clone(), node.path.is_some(), node.unresolved.iter().map(|f| f.to_string()).collect()))
+        .collect();
+}
+
+#[test]
+fn one_dll_present_and_one_missing() {
+    let dir = work_dir("present-and-missing");
+    let exec_path = copy_fixture("deps64.exe", &dir);
+    let lib_path = copy_fixture("lib64.dll", &dir);
+
+    let pe = parse(&fixture_bytes("deps64.exe"));
+    let tree = DependencyTree::resolve(&pe, &exec_path, &[]);
+
+    assert_eq!(
+        dependencies(&tree),
+        [
+            (String::from("lib64.dll"), true, vec![String::from("qux")]),
+            (String::from("missing64.dll"), false, vec![String::from("gone")]),
+            (String::from("kernel32.dll"), false, vec![String::from("ExitProcess")]),
+        ]
+    );
+    assert_eq!(tree.dependencies[0].path.as_ref(), Some(&lib_path));
+    assert_eq!(tree.dependencies[0].unresolved, [ImportRef::Name(String::from("qux"))]);
+    assert_eq!(tree.missing_dlls, ["kernel32.dll", "missing64.dll"]);
+    assert_eq!(tree.unresolved_imports, ["lib64.dll!qux"]);
+
+    /* Nothing next to the executable */
+    let tree = DependencyTree::resolve(&pe, &work_dir("nothing").join("deps64.exe"), &[]);
+    assert_eq!(tree.missing_dlls, ["kernel32.dll", "lib64.dll", "missing64.dll"]);
+    assert!(tree.unresolved_imports.is_empty());
+}
+
+#[test]
+fn search_paths() {
+    let dir = work_dir("search-paths");
+    let exec_path = copy_fixture("deps64.exe", &dir);
+
+    /* A Windows root is searched in System32, the file names are compared without case, kernel32.dll is a copy of lib64.dll */
+    let root = dir.join("root");
+    let system32 = root.join("WINDOWS").join("system32");
+
+    std::fs::create_dir_all(&system32).unwrap();
+    std::fs::copy(fixture("lib64.dll"), system32.join("KERNEL32.DLL")).unwrap();
+    std::fs::copy(fixture("lib64.dll"), root.join("Lib64.dll")).unwrap();
+
+    let pe = parse(&fixture_bytes("deps64.exe"));
+    let tree = DependencyTree::resolve(&pe, &exec_path, std::slice::from_ref(&root));
+
+    assert_eq!(tree.dependencies[0].path, Some(root.join("Lib64.dll")));
+    assert_eq!(tree.dependencies[2].path, Some(system32.join("KERNEL32.DLL")));
+    assert_eq!(tree.missing_dlls, ["missing64.dll"]);
+    assert_eq!(tree.unresolved_imports, ["kernel32.dll!ExitProcess", "lib64.dll!qux"]);
+}
+
+#[test]
+fn deps_flag() {
+    let dir = work_dir("flag");
+    let exec_path = copy_fixture("deps64.exe", &dir);
+    let lib_path = copy_fixture("lib64.dll", &dir);
+
+    let stdout = execdump(&["--deps"], &exec_path);
+
+    assert!(stdout.contains("MissingDlls       : 2\n    UnresolvedImports : 1"), "{}", stdout);
+    assert!(stdout.contains("Unresolved Imports (1)\n        lib64.dll!qux"), "{}", stdout);
+    assert!(stdout.contains(&format!("lib64.dll ({})\n            Unresolved : qux", lib_path.display())), "{}", stdout);
+    assert!(stdout.contains("missing64.dll (MISSING)\n            Unresolved : gone"), "{}", stdout);
+}
+
+#[test]
+fn missing_dependencies_report() {
+    let dir = work_dir("missing-deps");
+    let exec_path = copy_fixture("deps64.exe", &dir);
+    let lib_path = copy_fixture("lib64.dll", &dir);
+
+    let pe = parse(&fixture_bytes("deps64.exe"));
+    let report = MissingDependencies::from_tree(&DependencyTree::resolve(&pe, &exec_path, &[]), "deps64.exe");
+
+    /* kernel32.dll is not in the directory but is known to ship with Windows, its imports are not checked */
+    let checks: Vec<(&str, DependencyStatus, bool, Vec<String>)> = report
+        .checks
+        .iter()
+        .map(|check| (check.name.as_str(), check.status, check.is_failure(), check.unresolved.iter().map(|f| f.to_string()).collect()))
+        .collect();
+
+    assert_eq!(
+        checks,
+        [
+            ("lib64.dll", DependencyStatus::Found, true, vec![String::from("qux")]),
+            ("missing64.dll", DependencyStatus::Missing, true, vec![String::from("gone")]),
+            ("kernel32.dll", DependencyStatus::System, false, vec![]),
+        ]
+    );
+    assert_eq!(report.checks[0].path.as_ref(), Some(&lib_path));
+    assert!(report.checks.iter().all(|check| check.required_by == "deps64.exe"));
+    assert_eq!(report.failures(), 2);
+
+    /* A DLL that cannot be parsed */
+    std::fs::write(&lib_path, &fixture_bytes("lib64.dll")[..0x100]).unwrap();
+    let report = MissingDependencies::from_tree(&DependencyTree::resolve(&pe, &exec_path, &[]), "deps64.exe");
+
+    assert_eq!(report.checks[0].status, DependencyStatus::Invalid);
+    assert!(report.checks[0].error.is_some());
+}
+
+#[test]
+fn missing_deps_flag() {
+    let run = |exec_path: &std::path::Path| Command::new(env!("CARGO_BIN_EXE_execdump")).arg("--missing-deps").arg(exec_path).output().expect("Unable to run execdump");
+
+    let dir = work_dir("missing-deps-flag");
+    copy_fixture("lib64.dll", &dir);
+
+    let output = run(&copy_fixture("deps64.exe", &dir));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("2 of the dependencies cannot be resolved"));
+    assert!(stdout.contains("Dlls     : 3\n    Found    : 1\n    System   : 1\n    Missing  : 1\n    Failures : 2"), "{}", stdout);
+    assert!(stdout.contains("lib64.dll (FOUND, required by deps64.exe)\n        Unresolved : qux"), "{}", stdout);
+    assert!(stdout.contains("missing64.dll (MISSING, required by deps64.exe)\n        Unresolved : gone"), "{}", stdout);
+    assert!(stdout.contains("Resolved (1)\n        kernel32.dll (SYSTEM)"), "{}", stdout);
+
+    /* Every function app64.exe imports is exported by lib64.dll */
+    let output = run(&copy_fixture("app64.exe", &dir));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("Failures : 0"), "{}", stdout);
+    assert!(stdout.contains(&format!("Resolved (1)\n        lib64.dll (FOUND, {})", dir.join("lib64.dll").display())), "{}", stdout);
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use std::process::Command;
+
+use execdump::diff::{diff, diff_pe, ResourceChange, SectionChange, SymbolChange};
+use execdump::exec::parse_exec_from_bytes;
+
+use common::{fixture, fixture_bytes, parse, work_dir};
+
+/*
+ * Structural diffs between the fixtures, and between a fixture and a patched copy of it
+ */
+
+/* File offset of the "foo" Hint/Name entry of app64.exe, 0x70 bytes into .rdata */
+const FOO_NAME_OFFSET: usize = 0x670;
+
+/// Names of the added, removed and modified sections
+fn section_names(changes: &[SectionChange]) -> Vec<String> {
+    return changes
+        .iter()
+        .map(|change| match change {
+            SectionChange::Added { name, .. } => format!("+{}", name),
+            SectionChange::Removed { name, .. } => format!("-{}", name),
+            SectionChange::Modified { name, .. } => format!("~{}", name),
+        })
+        .collect();
+}
+
+fn added(names: &[&str]) -> Vec<SymbolChange> {
+    return names.iter().map(|name| SymbolChange::Added(name.to_string())).collect();
+}
+
+fn removed(names: &[&str]) -> Vec<SymbolChange> {
+    return names.iter().map(|name| SymbolChange::Removed(name.to_string())).collect();
+}
+
+#[test]
+fn executable_against_a_dll() {
+    let app = parse(&fixture_bytes("app64.exe"));
+    let lib = parse(&fixture_bytes("lib64.dll"));
+
+    let changes = diff_pe(&app, &lib);
+
+    assert_eq!(section_names(&changes.section_changes), ["~.rdata", "~.text", "+.data", "+.reloc"]);
+    assert_eq!(changes.import_changes, removed(&["lib64.dll!bar", "lib64.dll!foo"]));
+    assert_eq!(changes.export_changes, added(&["#7", "bar", "foo", "sleep"]));
+    assert!(changes.resource_changes.is_empty());
+
+    let keys: Vec<&str> = changes.header_changes.iter().map(|c| c.key).collect();
+    assert!(["Characteristics", "AddressOfEntryPoint", "ImageBase", "ExportTable", "ImportTable"].iter().all(|key| keys.contains(key)), "{:?}", keys);
+
+    let Some(SectionChange::Modified { old_size, new_size, header_changes, .. }) = changes.section_changes.iter().find(|c| matches!(c, SectionChange::Modified { name, .. } if name == ".text")) else {
+        panic!("No .text change");
+    };
+
+    assert_eq!((*old_size, *new_size), (0x1b, 0x15));
+    assert_eq!(header_changes.iter().map(|c| (c.key, c.old.as_deref(), c.new.as_deref())).collect::<Vec<_>>(), [("VirtualSize", Some("0x1b"), Some("0x15"))]);
+
+    /* The other way round */
+    let changes = diff_pe(&lib, &app);
+
+    assert_eq!(section_names(&changes.section_changes), ["-.data", "~.rdata", "-.reloc", "~.text"]);
+    assert_eq!(changes.import_changes, added(&["lib64.dll!bar", "lib64.dll!foo"]));
+    assert_eq!(changes.export_changes, removed(&["#7", "bar", "foo", "sleep"]));
+}
+
+#[test]
+fn added_resources() {
+    let changes = diff_pe(&parse(&fixture_bytes("app64.exe")), &parse(&fixture_bytes("res64.exe")));
+
+    assert_eq!(changes.section_changes, [SectionChange::Added { name: String::from(".rsrc"), size: 0x360 }]);
+    assert!(changes.import_changes.is_empty() && changes.export_changes.is_empty());
+
+    let resources: Vec<&str> = changes
+        .resource_changes
+        .iter()
+        .map(|change| match change {
+            ResourceChange::Added { path, .. } => path.as_str(),
+            other => panic!("Not an added resource: {:?}", other),
+        })
+        .collect();
+
+    assert_eq!(resources, ["RCDATA/\"CONFIG\"/1033", "RCDATA/42/1033", "STRING/1/1033", "VERSION/1/1033"]);
+}
+
+#[test]
+fn patched_copy() {
+    let original = fixture_bytes("app64.exe");
+    let mut patched = original.clone();
+
+    /* foo becomes fox */
+    patched[FOO_NAME_OFFSET + 2] = b'x';
+
+    let changes = diff_pe(&parse(&original), &parse(&patched));
+
+    assert!(changes.header_changes.is_empty(), "{:?}", changes.header_changes);
+    let [SectionChange::Modified { name, old_size, new_size, changed_bytes, ranges, header_changes }] = changes.section_changes.as_slice() else {
+        panic!("Not a single modified section: {:?}", changes.section_changes);
+    };
+
+    assert_eq!((name.as_str(), *old_size, *new_size, *changed_bytes), (".rdata", 0x7e, 0x7e, 1));
+    assert_eq!(ranges.iter().map(|r| (r.start, r.end)).collect::<Vec<_>>(), [(0x72, 0x73)]);
+    assert!(header_changes.is_empty());
+    assert_eq!(changes.import_changes, [SymbolChange::Removed(String::from("lib64.dll!foo")), SymbolChange::Added(String::from("lib64.dll!fox"))]);
+    assert!(changes.export_changes.is_empty());
+
+    /* A file against itself */
+    let changes = diff(&parse_exec_from_bytes(&original).unwrap(), &parse_exec_from_bytes(&original).unwrap()).unwrap();
+
+    assert!(changes.is_empty());
+    assert!(diff(&parse_exec_from_bytes(&original).unwrap(), &parse_exec_from_bytes(&fixture_bytes("hello.elf")).unwrap()).is_err());
+}
+
+#[test]
+fn diff_subcommand() {
+    let dir = work_dir("subcommand");
+    let mut patched = fixture_bytes("app64.exe");
+
+    patched[FOO_NAME_OFFSET + 2] = b'x';
+    std::fs::write(dir.join("app64.exe"), &patched).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_execdump")).arg("diff").arg(fixture("app64.exe")).arg(dir.join("app64.exe")).output().expect("Unable to run execdump");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("~ .rdata\n            Size          : 0x7e -> 0x7e\n            ChangedBytes  : 0x1\n            ChangedRanges : 0x72..0x73"), "{}", stdout);
+    assert!(stdout.contains("Import Changes (2)\n        - : lib64.dll!foo\n        + : lib64.dll!fox"), "{}", stdout);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_execdump")).arg("diff").arg(fixture("app64.exe")).arg(fixture("app64.exe")).output().unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No structural differences"));
+
+    /* Executables of different formats */
+    let output = Command::new(env!("CARGO_BIN_EXE_execdump")).arg("diff").arg(fixture("app64.exe")).arg(fixture("hello.elf")).output().unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Cannot diff executables of different formats"));
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use std::process::Command;
+
+use execdump::diff::ExportComparison;
+
+use common::{fixture, fixture_bytes, parse, work_dir};
+
+/*
+ * Export comparison of lib64.dll against itself and against an older build of it, a patched copy with other ordinals
+ * and a renamed function
+ */
+
+/* File offsets of the OrdinalBase of the export directory and of the "bar" export name */
+const ORDINAL_BASE_OFFSET: usize = 0x610;
+const BAR_NAME_OFFSET: usize = 0x654;
+
+/// lib64.dll numbered from 6, with bar named bat: #6, bat 7, foo 8, sleep 9
+fn older_build() -> Vec<u8> {
+    let mut bytes = fixture_bytes("lib64.dll");
+
+    bytes[ORDINAL_BASE_OFFSET] = 6;
+    bytes[BAR_NAME_OFFSET + 2] = b't';
+
+    return bytes;
+}
+
+#[test]
+fn same_exports() {
+    let lib = parse(&fixture_bytes("lib64.dll"));
+    let comparison = ExportComparison::compare(&lib, &lib);
+
+    assert_eq!((comparison.reference_dll.as_str(), comparison.unchanged), ("lib64.dll", 4));
+    assert!(comparison.added.is_empty() && comparison.removed.is_empty() && comparison.ordinal_changes.is_empty());
+    assert!(comparison.is_compatible());
+}
+
+#[test]
+fn removed_and_renumbered_exports() {
+    let comparison = ExportComparison::compare(&parse(&older_build()), &parse(&fixture_bytes("lib64.dll")));
+
+    assert_eq!(comparison.unchanged, 0);
+    assert_eq!(comparison.added, [(String::from("#7"), 7), (String::from("bar"), 8)]);
+    assert_eq!(comparison.removed, [(String::from("#6"), 6), (String::from("bat"), 7)]);
+    assert_eq!(comparison.ordinal_changes, [(String::from("foo"), 8, 9), (String::from("sleep"), 9, 10)]);
+    assert!(!comparison.is_compatible());
+
+    /* Exports only added to the reference keep the DLL compatible */
+    let comparison = ExportComparison::compare(&parse(&fixture_bytes("app64.exe")), &parse(&fixture_bytes("lib64.dll")));
+
+    assert_eq!(comparison.added.len(), 4);
+    assert!(comparison.is_compatible());
+}
+
+#[test]
+fn compare_exports_flag() {
+    let run = |reference: &std::path::Path| {
+        return Command::new(env!("CARGO_BIN_EXE_execdump"))
+            .arg("--compare-exports")
+            .arg(reference)
+            .arg(fixture("lib64.dll"))
+            .output()
+            .expect("Unable to run execdump");
+    };
+
+    let output = run(&fixture("lib64.dll"));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("Unchanged      : 4\n    Added          : 0\n    Removed        : 0\n    OrdinalChanged : 0\n    Compatible     : true"), "{}", stdout);
+
+    let path = work_dir("flag").join("lib64.dll");
+    std::fs::write(&path, older_build()).unwrap();
+
+    let output = run(&path);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("4 of the reference exports are removed or renumbered"));
+    assert!(stdout.contains("Compatible     : false"), "{}", stdout);
+    assert!(stdout.contains("Removed (2)\n        - : #6 (ordinal 6)\n        - : bat (ordinal 7)"), "{}", stdout);
+    assert!(stdout.contains("Ordinal Changes (2)\n        ~ : foo (8 -> 9)\n        ~ : sleep (9 -> 10)"), "{}", stdout);
+}
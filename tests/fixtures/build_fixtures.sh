@@ -0,0 +1,65 @@
+#!/bin/sh
+# Rebuilds the fixtures from their assembly and resource sources, needs llvm-mc, llvm-dlltool, llvm-rc, llvm-cvtres, rust-lld, python3 and dd
+set -e
+
+cd "$(dirname "$0")"
+
+LLD=${LLD:-$(find "$(rustc --print sysroot)" -name rust-lld -type f | head -1)}
+TMP=$(mktemp -d)
+trap 'rm -rf "$TMP"' EXIT
+
+# PE32+ DLL exporting by name, by ordinal only and a forwarder
+llvm-mc -filetype=obj -triple x86_64-pc-windows-msvc src/lib64.s -o "$TMP/lib64.obj"
+"$LLD" -flavor link /dll /noentry /machine:x64 /export:foo /export:bar /export:baz,@7,NONAME /export:sleep=kernel32.Sleep \
+    "$TMP/lib64.obj" /implib:"$TMP/lib64.lib" /out:lib64.dll
+
+# PE32+ executable importing from the DLL by name and by ordinal
+llvm-mc -filetype=obj -triple x86_64-pc-windows-msvc src/app64.s -o "$TMP/app64.obj"
+"$LLD" -flavor link /entry:main /subsystem:console /machine:x64 "$TMP/app64.obj" "$TMP/lib64.lib" /out:app64.exe
+
+# The same executable with a string table, named and numbered RCDATA entries and a version information
+llvm-rc -no-preprocess -fo "$TMP/res64.res" src/res64.rc
+llvm-cvtres -machine:x64 -out:"$TMP/res64.obj" "$TMP/res64.res"
+"$LLD" -flavor link /entry:main /subsystem:console /machine:x64 "$TMP/app64.obj" "$TMP/res64.obj" "$TMP/lib64.lib" /out:res64.exe
+
+# The same executable with a CodeView debug directory and its PDB, /brepro makes the GUID a hash of the output
+"$LLD" -flavor link /entry:main /subsystem:console /machine:x64 /debug /pdbaltpath:%_PDB% /brepro "$TMP/app64.obj" "$TMP/lib64.lib" \
+    /out:dbg64.exe /pdb:dbg64.pdb
+
+# PE32+ executable importing a function lib64.dll does not export, from a DLL that is not shipped and from kernel32.dll
+for def in lib64_next missing64 kernel32_64; do
+    llvm-dlltool -m i386:x86-64 -d src/$def.def -l "$TMP/$def.lib"
+done
+llvm-mc -filetype=obj -triple x86_64-pc-windows-msvc src/deps64.s -o "$TMP/deps64.obj"
+"$LLD" -flavor link /entry:main /subsystem:console /machine:x64 "$TMP/deps64.obj" "$TMP/lib64_next.lib" "$TMP/missing64.lib" \
+    "$TMP/kernel32_64.lib" /out:deps64.exe
+
+# PE32 executable importing from kernel32.dll
+llvm-dlltool -m i386 -d src/kernel32.def -l "$TMP/kernel32.lib"
+llvm-mc -filetype=obj -triple i686-pc-windows-msvc src/app32.s -o "$TMP/app32.obj"
+"$LLD" -flavor link /entry:main /subsystem:console /machine:x86 /safeseh:no "$TMP/app32.obj" "$TMP/kernel32.lib" /out:app32.exe
+
+# UEFI application with a base relocation and SBAT metadata
+llvm-mc -filetype=obj -triple x86_64-pc-windows-msvc src/bootx64.s -o "$TMP/bootx64.obj"
+"$LLD" -flavor link /entry:efi_main /subsystem:efi_application /machine:x64 "$TMP/bootx64.obj" /out:bootx64.efi
+
+# The same application as a TE image, the stripped headers format of the UEFI PEI modules
+python3 src/pe2te.py bootx64.efi bootx64.te
+
+# ARM64X hybrid executable, llvm-mc has no ARM64EC target so the machine of the EC object is patched to 0xA641
+llvm-mc -filetype=obj -triple aarch64-pc-windows-msvc src/arm64x.s -o "$TMP/arm64x.obj"
+llvm-mc -filetype=obj -triple aarch64-pc-windows-msvc src/arm64x_ec.s -o "$TMP/arm64x_ec.obj"
+printf '\101\246' | dd of="$TMP/arm64x_ec.obj" bs=1 count=2 conv=notrunc status=none
+"$LLD" -flavor link /entry:main /subsystem:console /machine:arm64x /brepro "$TMP/arm64x.obj" "$TMP/arm64x_ec.obj" /out:arm64x.exe
+
+# ELF64 executable dynamically linked against a stub shared library
+llvm-mc -filetype=obj -triple x86_64-unknown-linux-gnu src/libgreet.s -o "$TMP/libgreet.o"
+"$LLD" -flavor gnu -shared -soname libgreet.so "$TMP/libgreet.o" -o "$TMP/libgreet.so"
+llvm-mc -filetype=obj -triple x86_64-unknown-linux-gnu src/hello.s -o "$TMP/hello.o"
+"$LLD" -flavor gnu -e _start --dynamic-linker /lib64/ld-linux-x86-64.so.2 "$TMP/hello.o" "$TMP/libgreet.so" -o hello.elf
+
+# NE program and LE virtual device driver, written table by table
+python3 src/legacy.py hello16.exe hello.vxd
+
+# .NET assembly with tiny and fat method bodies, its metadata tables are written by hand
+python3 src/dotnet.py hello.net.exe
@@ -0,0 +1,18 @@
+.intel_syntax noprefix
+.text
+.globl _main
+_main:
+    push -11
+    call dword ptr [__imp__GetStdHandle@4]
+    push 0
+    push 0
+    push 6
+    mov ecx, offset message
+    push ecx
+    push eax
+    call dword ptr [__imp__WriteFile@20]
+    push 0
+    call dword ptr [__imp__ExitProcess@4]
+.data
+message:
+    .ascii "hello\n"
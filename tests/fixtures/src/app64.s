@@ -0,0 +1,10 @@
+.intel_syntax noprefix
+.text
+.globl main
+main:
+    sub rsp, 40
+    call qword ptr [rip + __imp_foo]
+    call qword ptr [rip + __imp_bar]
+    call qword ptr [rip + __imp_baz]
+    add rsp, 40
+    ret
@@ -0,0 +1,23 @@
+// Native ARM64 view of the hybrid image, its load config has no ARM64EC metadata
+.text
+.globl main
+.p2align 2
+.seh_proc main
+main:
+    stp x29, x30, [sp, #-16]!
+    .seh_save_fplr_x 16
+    .seh_endprologue
+    mov w0, #0
+    .seh_startepilogue
+    ldp x29, x30, [sp], #16
+    .seh_save_fplr_x 16
+    .seh_endepilogue
+    ret
+    .seh_endproc
+
+.section .rdata,"dr"
+.globl _load_config_used
+.p2align 3
+_load_config_used:
+    .word 0x140
+    .fill 0x13c, 1, 0
@@ -0,0 +1,67 @@
+// ARM64EC view of the hybrid image, the linker fills the code map and the redirections of __chpe_metadata
+.text
+.globl main
+.p2align 2
+.seh_proc main
+main:
+    stp x29, x30, [sp, #-16]!
+    .seh_save_fplr_x 16
+    .seh_endprologue
+    mov w0, #1
+    .seh_startepilogue
+    ldp x29, x30, [sp], #16
+    .seh_save_fplr_x 16
+    .seh_endepilogue
+    ret
+    .seh_endproc
+
+.section .rdata,"dr"
+.globl _load_config_used
+.p2align 3
+_load_config_used:
+    .word 0x140
+    .fill 0xc4, 1, 0
+    .xword __chpe_metadata
+    .fill 0x70, 1, 0
+
+.globl __chpe_metadata
+.p2align 3
+__chpe_metadata:
+    .word 1
+    .rva __hybrid_code_map
+    .word __hybrid_code_map_count
+    .rva __x64_code_ranges_to_entry_points
+    .rva __arm64x_redirection_metadata
+    .rva __os_arm64x_dispatch_call_no_redirect
+    .rva __os_arm64x_dispatch_ret
+    .rva __os_arm64x_check_call
+    .rva __os_arm64x_check_icall
+    .rva __os_arm64x_check_icall_cfg
+    .rva __arm64x_native_entrypoint
+    .rva __hybrid_auxiliary_iat
+    .word __x64_code_ranges_to_entry_points_count
+    .word __arm64x_redirection_metadata_count
+    .rva __os_arm64x_get_x64_information
+    .rva __os_arm64x_set_x64_information
+    .rva __arm64x_extra_rfe_table
+    .word __arm64x_extra_rfe_table_size
+    .rva __os_arm64x_dispatch_fptr
+    .rva __hybrid_auxiliary_iat_copy
+
+// Pointers the loader fills with its dispatch routines
+__os_arm64x_dispatch_call_no_redirect:
+    .xword 0
+__os_arm64x_dispatch_ret:
+    .xword 0
+__os_arm64x_check_call:
+    .xword 0
+__os_arm64x_check_icall:
+    .xword 0
+__os_arm64x_check_icall_cfg:
+    .xword 0
+__os_arm64x_get_x64_information:
+    .xword 0
+__os_arm64x_set_x64_information:
+    .xword 0
+__os_arm64x_dispatch_fptr:
+    .xword 0
@@ -0,0 +1,15 @@
+.intel_syntax noprefix
+.text
+.globl efi_main
+efi_main:
+    mov rax, qword ptr [rip + con_out]
+    xor eax, eax
+    ret
+
+.data
+con_out:
+    .quad efi_main
+
+.section .sbat, "dr"
+    .ascii "sbat,1,SBAT Version,sbat,1,https://github.com/rhboot/shim/blob/main/SBAT.md\n"
+    .ascii "bootx64,1,execdump,bootx64,1,https://github.com/romainaugier/execdump\n"
@@ -0,0 +1,10 @@
+.intel_syntax noprefix
+.text
+.globl main
+main:
+    sub rsp, 40
+    call qword ptr [rip + __imp_foo]
+    call qword ptr [rip + __imp_qux]
+    call qword ptr [rip + __imp_gone]
+    xor ecx, ecx
+    call qword ptr [rip + __imp_ExitProcess]
@@ -0,0 +1,156 @@
+#!/usr/bin/env python3
+# Writes a .NET assembly, there is no CIL toolchain here so the metadata tables (ECMA-335 II.22) are laid out by hand.
+# Hello.Program has the static Main printing a user string with Console.WriteLine, the static Add(int32, int32) with a
+# fat header, a local and a branch, and the .ctor calling Object::.ctor
+import struct
+import sys
+
+SECTION_RVA = 0x2000
+FILE_ALIGNMENT = 0x200
+
+
+def align(value, alignment):
+    return (value + alignment - 1) & ~(alignment - 1)
+
+
+class Heap:
+    """String, blob and user string heaps, the index 0 is the empty entry"""
+
+    def __init__(self):
+        self.data = bytearray(b"\0")
+        self.indices = {}
+
+    def add(self, entry):
+        if entry not in self.indices:
+            self.indices[entry] = len(self.data)
+            self.data += entry
+
+        return self.indices[entry]
+
+
+strings = Heap()
+blobs = Heap()
+user_strings = Heap()
+
+
+def string(name):
+    return strings.add(name.encode() + b"\0")
+
+
+def blob(data):
+    return blobs.add(bytes([len(data)]) + data)
+
+
+def user_string(text):
+    data = text.encode("utf-16-le") + b"\0"
+    return user_strings.add(bytes([len(data)]) + data)
+
+
+# Method bodies, the tiny headers hold the code size, the fat one is 4-byte aligned
+hello = user_string("Hello")
+
+main = bytes([(11 << 2) | 0x2, 0x72]) + struct.pack("<I", 0x70000000 | hello)  # ldstr "Hello"
+main += b"\x28" + struct.pack("<I", 0x0A000002) + b"\x2A"  # call Console::WriteLine(string), ret
+
+add_code = bytes([0x02, 0x03, 0x58, 0x0A, 0x2B, 0x00, 0x06, 0x2A])  # ldarg.0, ldarg.1, add, stloc.0, br.s, ldloc.0, ret
+add = struct.pack("<HHII", 0x3013, 2, len(add_code), 0x11000001) + add_code  # Fat, init locals
+
+ctor = bytes([(7 << 2) | 0x2, 0x02, 0x28]) + struct.pack("<I", 0x0A000001) + b"\x2A"  # ldarg.0, call Object::.ctor, ret
+
+CLR_HEADER_SIZE = 0x48
+main_rva = SECTION_RVA + CLR_HEADER_SIZE
+add_rva = align(main_rva + len(main), 4)
+ctor_rva = add_rva + len(add)
+metadata_rva = align(ctor_rva + len(ctor), 4)
+
+# Tables, every heap and table index takes 2 bytes
+TYPE_REF_OBJECT, TYPE_REF_CONSOLE = 1, 2
+ASSEMBLY_REF_MSCORLIB = 1
+
+tables = {
+    # Generation, Name, Mvid, EncId, EncBaseId
+    0x00: [struct.pack("<HHHHH", 0, string("hello.net.exe"), 1, 0, 0)],
+    # ResolutionScope (AssemblyRef), TypeName, TypeNamespace
+    0x01: [
+        struct.pack("<HHH", (ASSEMBLY_REF_MSCORLIB << 2) | 2, string("Object"), string("System")),
+        struct.pack("<HHH", (ASSEMBLY_REF_MSCORLIB << 2) | 2, string("Console"), string("System")),
+    ],
+    # Flags, TypeName, TypeNamespace, Extends (TypeDefOrRef), FieldList, MethodList
+    0x02: [
+        struct.pack("<IHHHHH", 0, string("<Module>"), 0, 0, 1, 1),
+        struct.pack("<IHHHHH", 0x00100001, string("Program"), string("Hello"), (TYPE_REF_OBJECT << 2) | 1, 1, 1),
+    ],
+    # RVA, ImplFlags, Flags, Name, Signature, ParamList
+    0x06: [
+        struct.pack("<IHHHHH", main_rva, 0, 0x0096, string("Main"), blob(b"\x00\x00\x01"), 1),
+        struct.pack("<IHHHHH", add_rva, 0, 0x0096, string("Add"), blob(b"\x00\x02\x08\x08\x08"), 1),
+        struct.pack("<IHHHHH", ctor_rva, 0, 0x1886, string(".ctor"), blob(b"\x20\x00\x01"), 1),
+    ],
+    # Class (MemberRefParent), Name, Signature
+    0x0A: [
+        struct.pack("<HHH", (TYPE_REF_OBJECT << 3) | 1, string(".ctor"), blob(b"\x20\x00\x01")),
+        struct.pack("<HHH", (TYPE_REF_CONSOLE << 3) | 1, string("WriteLine"), blob(b"\x00\x01\x01\x0E")),
+    ],
+    # Signature of the locals of Add
+    0x11: [struct.pack("<H", blob(b"\x07\x01\x08"))],
+    # HashAlgId, Version, Flags, PublicKey, Name, Culture
+    0x20: [struct.pack("<IHHHHIHHH", 0x8004, 1, 0, 0, 0, 0, 0, string("hello.net"), 0)],
+    # Version, Flags, PublicKeyOrToken, Name, Culture, HashValue
+    0x23: [struct.pack("<HHHHIHHHH", 4, 0, 0, 0, 0, blob(bytes.fromhex("b77a5c561934e089")), string("mscorlib"), 0, 0)],
+}
+
+valid = sum(1 << table for table in tables)
+table_stream = struct.pack("<IBBBBQQ", 0, 2, 0, 0, 1, valid, 0)
+table_stream += b"".join(struct.pack("<I", len(rows)) for _, rows in sorted(tables.items()))
+table_stream += b"".join(b"".join(rows) for _, rows in sorted(tables.items()))
+
+mvid = bytes(range(0x10, 0x20))
+
+streams = [
+    ("#~", table_stream),
+    ("#Strings", bytes(strings.data)),
+    ("#US", bytes(user_strings.data)),
+    ("#GUID", mvid),
+    ("#Blob", bytes(blobs.data)),
+]
+
+version = b"v4.0.30319".ljust(12, b"\0")
+headers_size = 16 + len(version) + 4 + sum(8 + align(len(name) + 1, 4) for name, _ in streams)
+
+metadata_headers = struct.pack("<IHHII", 0x424A5342, 1, 1, 0, len(version)) + version + struct.pack("<HH", 0, len(streams))
+metadata_data = b""
+
+for name, data in streams:
+    data = data.ljust(align(len(data), 4), b"\0")
+    metadata_headers += struct.pack("<II", headers_size + len(metadata_data), len(data)) + name.encode().ljust(align(len(name) + 1, 4), b"\0")
+    metadata_data += data
+
+metadata = metadata_headers + metadata_data
+
+# CLR header: MetaData, ILONLY, entry point Main, no resources nor strong name signature
+clr_header = struct.pack("<IHHIIII", CLR_HEADER_SIZE, 2, 5, metadata_rva, len(metadata), 0x1, 0x06000001).ljust(CLR_HEADER_SIZE, b"\0")
+
+text = bytearray(clr_header)
+text = text.ljust(main_rva - SECTION_RVA, b"\0") + main
+text = text.ljust(add_rva - SECTION_RVA, b"\0") + add
+text = text.ljust(ctor_rva - SECTION_RVA, b"\0") + ctor
+text = text.ljust(metadata_rva - SECTION_RVA, b"\0") + metadata
+
+# PE32 headers with the single .text section, the CLR Runtime Header is the data directory 14
+size_of_image = SECTION_RVA + align(len(text), 0x2000)
+raw_size = align(len(text), FILE_ALIGNMENT)
+
+dos_header = b"MZ" + bytes(0x3A) + struct.pack("<I", 0x80)
+coff_header = struct.pack("<HHIIIHH", 0x14C, 1, 0, 0, 0, 0xE0, 0x0102)
+optional_header = struct.pack(
+    "<HBBIIIIIIIIIHHHHHHIIIIHHIIIIII",
+    0x10B, 8, 0, raw_size, 0, 0, 0, SECTION_RVA, SECTION_RVA, 0x400000, 0x2000, FILE_ALIGNMENT,
+    4, 0, 0, 0, 4, 0, 0, size_of_image, FILE_ALIGNMENT, 0, 3, 0x8540, 0x100000, 0x1000, 0x100000, 0x1000, 0, 16,
+)
+data_directories = bytearray(16 * 8)
+data_directories[14 * 8:15 * 8] = struct.pack("<II", SECTION_RVA, CLR_HEADER_SIZE)
+section_header = b".text\0\0\0" + struct.pack("<IIIIIIHHI", len(text), SECTION_RVA, raw_size, FILE_ALIGNMENT, 0, 0, 0, 0, 0x60000020)
+
+headers = dos_header.ljust(0x80, b"\0") + b"PE\0\0" + coff_header + optional_header + data_directories + section_header
+
+open(sys.argv[1], "wb").write(headers.ljust(FILE_ALIGNMENT, b"\0") + text.ljust(raw_size, b"\0"))
@@ -0,0 +1,9 @@
+.intel_syntax noprefix
+.text
+.globl _start
+.type _start, @function
+_start:
+    call greet@PLT
+    mov edi, eax
+    mov eax, 60
+    syscall
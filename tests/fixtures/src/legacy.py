@@ -0,0 +1,119 @@
+#!/usr/bin/env python3
+# Writes the legacy executables, no maintained toolchain links them anymore so the tables are laid out by hand:
+# hello16.exe, a Windows 3.x NE program with a code and a data segment, importing KERNEL by ordinal and USER by name
+# hello.vxd, a Windows 386 LE virtual device driver with a code and a data object, importing from VMM
+import struct
+import sys
+
+
+def pascal(name):
+    return bytes([len(name)]) + name
+
+
+def names(entries):
+    return b"".join(pascal(name) + struct.pack("<H", ordinal) for name, ordinal in entries) + b"\0"
+
+
+def dos_header(header_offset):
+    return b"MZ" + bytes(0x3A) + struct.pack("<I", header_offset)
+
+
+def ne():
+    header_offset = 0x40
+    alignment_shift = 4
+
+    segment_table = 0x40
+    resident_names = segment_table + 2 * 8
+    resident = names([(b"HELLO", 0), (b"HELLOPROC", 1)])
+    module_references = resident_names + len(resident)
+    imported_names = module_references + 2 * 2
+    imported = b"\0" + pascal(b"KERNEL") + pascal(b"USER") + pascal(b"MESSAGEBOX")
+    entry_table = imported_names + len(imported)
+    entries = b"\1\1" + b"\1" + struct.pack("<H", 0)  # One fixed entry of segment 1 at offset 0
+    entries += b"\0"
+    non_resident_names = header_offset + entry_table + len(entries)
+    non_resident = names([(b"Hello World", 0), (b"HELLOPROC", 1)])
+
+    code_sector = 0x20
+    code = bytes([0x9A, 0, 0, 0, 0, 0x9A, 0, 0, 0, 0, 0xB8, 0, 0x4C, 0xCD, 0x21, 0x90])
+    relocations = struct.pack("<H", 2)
+    relocations += struct.pack("<BBHHH", 3, 1, 0x01, 1, 91)  # KERNEL.91 (INITTASK)
+    relocations += struct.pack("<BBHHH", 3, 2, 0x06, 2, 1 + 7 + 5)  # USER.MESSAGEBOX
+    data_sector = 0x23
+    data = b"Hello World\0".ljust(0x20, b"\0")
+
+    header = b"NE" + struct.pack(
+        "<BBHHIHHHHHHHHHHHHHHHHIHHHBBHHHH",
+        5, 10,
+        entry_table, len(entries),
+        0,
+        0x0002,  # Multiple data segments
+        2, 0x400, 0x1000,
+        0, 1, 0, 2,
+        2, 2, len(non_resident),
+        segment_table, resident_names, resident_names, module_references, imported_names,
+        non_resident_names,
+        0, alignment_shift, 0,
+        2, 0x08,  # Windows, gangload area
+        0, 0, 0,
+        0x030A,
+    )
+
+    segments = struct.pack("<HHHH", code_sector, len(code), 0x0150, len(code))  # MOVEABLE | PRELOAD | RELOC_INFO
+    segments += struct.pack("<HHHH", data_sector, len(data), 0x0041, 0x100)  # DATA | PRELOAD
+
+    out = bytearray(dos_header(header_offset))
+    out += header + segments + resident + struct.pack("<HH", 1, 1 + 7) + imported + entries + non_resident
+    out = out.ljust(code_sector << alignment_shift, b"\0") + code + relocations
+    out = out.ljust(data_sector << alignment_shift, b"\0") + data
+
+    return bytes(out)
+
+
+def le():
+    header_offset = 0x40
+    header_size = 0xC4
+
+    object_table = header_size
+    object_page_table = object_table + 2 * 0x18
+    resident_names = object_page_table + 4
+    resident = names([(b"HELLOVXD", 0), (b"HELLOVXD_DDB", 1)])
+    entry_table = resident_names + len(resident)
+    entries = b"\0"
+    import_modules = entry_table + len(entries)
+    imported = pascal(b"VMM")
+    import_procedures = import_modules + len(imported)
+    data_pages = (header_offset + import_procedures + 1 + 0xF) & ~0xF
+    page = bytes([0xB8, 0, 0, 0, 0, 0xC3]).ljust(0x20, b"\0")
+    non_resident_names = data_pages + len(page)
+    non_resident = names([(b"Hello virtual device", 0)])
+
+    header = b"LE" + struct.pack(
+        "<BBIHHIIIIIIIIIIIIIIIIIIIIIIIIIIIIIIIII",
+        0, 0, 0,
+        2, 4,  # 80386, Windows 386
+        0x0400, 0x00038000,  # VxD module flags
+        1, 1, 0, 2, 0x100,
+        0x1000, len(page),
+        0, 0, 0, 0,
+        object_table, 2, object_page_table, 0,
+        0, 0,
+        resident_names, entry_table,
+        0, 0, 0, 0,
+        import_modules, 1, import_procedures, 0,
+        data_pages, 0,
+        non_resident_names, len(non_resident),
+    ).ljust(header_size - 2, b"\0")
+
+    objects = struct.pack("<IIIIII", 0x100, 0x0000, 0x2045, 1, 1, 0)  # READABLE | EXECUTABLE | PRELOAD | BIG
+    objects += struct.pack("<IIIIII", 0x200, 0x1000, 0x2003, 2, 0, 0)  # READABLE | WRITABLE | BIG
+
+    out = bytearray(dos_header(header_offset))
+    out += header + objects + struct.pack(">I", 1 << 8) + resident + entries + imported + b"\0"
+    out = out.ljust(data_pages, b"\0") + page + non_resident
+
+    return bytes(out)
+
+
+open(sys.argv[1], "wb").write(ne())
+open(sys.argv[2], "wb").write(le())
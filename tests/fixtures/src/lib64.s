@@ -0,0 +1,20 @@
+.intel_syntax noprefix
+.text
+.globl foo
+foo:
+    mov eax, 1
+    ret
+.globl bar
+bar:
+    xor eax, eax
+    ret
+.globl baz
+baz:
+    lea rax, [rip + table]
+    mov rax, qword ptr [rax + 8]
+    ret
+.data
+.p2align 3
+table:
+    .quad foo
+    .quad bar
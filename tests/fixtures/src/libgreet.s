@@ -0,0 +1,10 @@
+.intel_syntax noprefix
+.text
+.globl greet
+.type greet, @function
+greet:
+    lea rax, [rip + message]
+    ret
+.section .rodata
+message:
+    .asciz "hello"
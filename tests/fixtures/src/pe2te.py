@@ -0,0 +1,33 @@
+#!/usr/bin/env python3
+# Converts a PE image to a TE image like the GenFw tool of EDK II: the headers up to the section table are replaced
+# by the 40-byte TE header, StrippedSize is the number of bytes they took
+import struct
+import sys
+
+data = open(sys.argv[1], "rb").read()
+
+e_lfanew = struct.unpack_from("<I", data, 0x3C)[0]
+machine, number_of_sections = struct.unpack_from("<HH", data, e_lfanew + 4)
+size_of_optional_header = struct.unpack_from("<H", data, e_lfanew + 20)[0]
+
+optional = e_lfanew + 24
+magic = struct.unpack_from("<H", data, optional)[0]
+address_of_entry_point, base_of_code = struct.unpack_from("<II", data, optional + 16)
+subsystem = struct.unpack_from("<H", data, optional + 68)[0]
+
+if magic == 0x20B:
+    image_base = struct.unpack_from("<Q", data, optional + 24)[0]
+    data_directories = optional + 112
+else:
+    image_base = struct.unpack_from("<I", data, optional + 28)[0]
+    data_directories = optional + 96
+
+base_relocation_table = data[data_directories + 5 * 8:data_directories + 6 * 8]
+debug = data[data_directories + 6 * 8:data_directories + 7 * 8]
+
+section_table = optional + size_of_optional_header
+
+te_header = b"VZ" + struct.pack("<HBBHIIQ", machine, number_of_sections, subsystem, section_table,
+                                address_of_entry_point, base_of_code, image_base)
+
+open(sys.argv[2], "wb").write(te_header + base_relocation_table + debug + data[section_table:])
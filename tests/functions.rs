@@ -0,0 +1,96 @@
+mod common;
+
+use std::process::Command;
+
+use execdump::functions::{discover_pe_functions, hash_functions, FunctionComparison, FunctionSignature, CHANGED_SIMILARITY_THRESHOLD};
+
+use common::{fixture, fixture_bytes, parse, work_dir};
+
+/*
+ * Similarity of the functions found in the fixtures, against themselves, a patched copy and unrelated code
+ */
+
+/* ModR/M byte of the "sub rsp, 0x28" starting main in dbg64.exe, 0xc4 turns it into "add rsp, 0x28" */
+const DBG64_PROLOG_MODRM: usize = 0x402;
+
+fn signatures(bytes: &[u8]) -> Vec<FunctionSignature> {
+    return hash_functions(&discover_pe_functions(&parse(bytes)).expect("Unable to find the functions"));
+}
+
+#[test]
+fn a_fixture_against_itself() {
+    let signatures = signatures(&fixture_bytes("app64.exe"));
+
+    assert_eq!(signatures.iter().map(|s| (s.address, s.instruction_count)).collect::<Vec<_>>(), [(0x140001000, 6)]);
+    assert_eq!(signatures[0].similarity(&signatures[0]), 1.0);
+
+    let comparison = FunctionComparison::compare(&signatures, &signatures);
+
+    assert_eq!(comparison.identical, [(0x140001000, 0x140001000)]);
+    assert!(comparison.changed.is_empty() && comparison.new.is_empty() && comparison.removed.is_empty());
+}
+
+#[test]
+fn a_changed_and_an_unrelated_function() {
+    /* The function found at the entry point of dbg64.exe runs on into the import thunks, one changed mnemonic leaves most
+     * of its shingles untouched */
+    let original = signatures(&fixture_bytes("dbg64.exe"));
+
+    let mut bytes = fixture_bytes("dbg64.exe");
+    bytes[DBG64_PROLOG_MODRM] = 0xc4;
+
+    let patched = signatures(&bytes);
+    let similarity = original[0].similarity(&patched[0]);
+
+    assert!((CHANGED_SIMILARITY_THRESHOLD..1.0).contains(&similarity), "{}", similarity);
+    assert_ne!(original[0].exact_hash, patched[0].exact_hash);
+
+    let comparison = FunctionComparison::compare(&original, &patched);
+
+    assert!(comparison.identical.is_empty());
+    assert_eq!(comparison.changed, [(0x140001000, 0x140001000, similarity)]);
+
+    /* The main of app32.exe only has a few mnemonic sequences in common with the one of app64.exe */
+    let original = signatures(&fixture_bytes("app64.exe"));
+    let unrelated = signatures(&fixture_bytes("app32.exe"));
+    let similarity = original[0].similarity(&unrelated[0]);
+
+    assert!(similarity < CHANGED_SIMILARITY_THRESHOLD, "{}", similarity);
+
+    let comparison = FunctionComparison::compare(&original, &unrelated);
+
+    assert!(comparison.identical.is_empty() && comparison.changed.is_empty());
+    assert_eq!((comparison.removed, comparison.new), (vec![0x140001000], vec![0x401000]));
+}
+
+#[test]
+fn compare_functions_flag() {
+    let run = |name: &str, other: &std::path::Path| -> String {
+        let output = Command::new(env!("CARGO_BIN_EXE_execdump"))
+            .arg("--compare-functions")
+            .arg(other)
+            .arg(fixture(name))
+            .output()
+            .expect("Unable to run execdump");
+
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+        return String::from_utf8_lossy(&output.stdout).to_string();
+    };
+
+    let stdout = run("app64.exe", &fixture("app64.exe"));
+    assert!(stdout.contains("Identical : 1\n    Changed   : 0\n    New       : 0\n    Removed   : 0"), "{}", stdout);
+
+    let path = work_dir("flag").join("dbg64.exe");
+    let mut bytes = fixture_bytes("dbg64.exe");
+
+    bytes[DBG64_PROLOG_MODRM] = 0xc4;
+    std::fs::write(&path, &bytes).unwrap();
+
+    let stdout = run("dbg64.exe", &path);
+    assert!(stdout.contains("Identical : 0\n    Changed   : 1"), "{}", stdout);
+    assert!(stdout.contains("Changed (1)\n        0x140001000 -> 0x140001000 (similarity: 0."), "{}", stdout);
+
+    let stdout = run("app64.exe", &fixture("app32.exe"));
+    assert!(stdout.contains("New (1)\n        0x401000\n\n    Removed (1)\n        0x140001000"), "{}", stdout);
+}
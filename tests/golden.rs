@@ -0,0 +1,182 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde_json::Value;
+
+/*
+ * Golden-file regression tests, the JSON output of every fixture is compared with the one checked in tests/golden
+ *
+ * EXECDUMP_UPDATE_GOLDEN=1 rewrites the golden files instead of comparing them
+ * EXECDUMP_GOLDEN_CORPUS=<dir> also runs on every executable of the directory, its golden files are kept in <dir>/golden
+ * and recorded on the first run
+ */
+
+const FIXTURES: [&str; 13] = ["app32.exe", "app64.exe", "lib64.dll", "res64.exe", "dbg64.exe", "deps64.exe", "arm64x.exe", "bootx64.efi", "bootx64.te", "hello.elf", "hello16.exe", "hello.vxd", "hello.net.exe"];
+
+/* Flags of the directories with a stable output, the flags of the other format are ignored */
+const DUMP_FLAGS: [&str; 41] = [
+    "--pe-dos-header",
+    "--dos-stub",
+    "--pe-nt-header",
+    "--te-header",
+    "--pe-optional-header",
+    "--entry",
+    "--pe-import",
+    "--pe-import-lookup-table",
+    "--pe-dlls",
+    "--iat",
+    "--exports",
+    "--pe-debug-directory",
+    "--pe-exc-table",
+    "--tls",
+    "--load-config",
+    "--cfg",
+    "--safeseh",
+    "--arm64x",
+    "--relocs",
+    "--symbols",
+    "--resources",
+    "--version-info",
+    "--manifest",
+    "--resource-strings",
+    "--resource-stats",
+    "--signature",
+    "--mitigations",
+    "--timestamps",
+    "--lint",
+    "--efi",
+    "--elf-headers",
+    "--elf-symbols",
+    "--sections",
+    "--sections-data",
+    "--packed",
+    "--anti-analysis",
+    "--capabilities",
+    "--attack",
+    "--hashes",
+    "--fuzzy-hashes",
+    "--buildinfo",
+];
+
+fn manifest_dir() -> PathBuf {
+    return PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+}
+
+fn update_golden() -> bool {
+    return std::env::var_os("EXECDUMP_UPDATE_GOLDEN").is_some_and(|v| v != "0");
+}
+
+fn file_name(path: &Path) -> String {
+    return path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+}
+
+/// Runs execdump on the file, the output does not depend on where the file is
+fn dump_json(path: &Path) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_execdump"))
+        .args(["--output-format", "json"])
+        .args(DUMP_FLAGS)
+        .arg(path)
+        .output()
+        .expect("Unable to run execdump");
+
+    let mut report = match output.status.success() {
+        true => serde_json::from_slice::<Value>(&output.stdout).expect("execdump did not output valid JSON"),
+        false => {
+            /* Files that fail to parse are recorded too, a change in the error is a change in behavior */
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let error = stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or_default();
+
+            serde_json::json!({ "error": error })
+        }
+    };
+
+    report["file"] = Value::String(file_name(path));
+
+    return serde_json::to_string_pretty(&report).unwrap() + "\n";
+}
+
+/// Compares with the golden file, returns a description of the first difference
+fn check_golden(path: &Path, golden_path: &Path, record_missing: bool) -> Option<String> {
+    let actual = dump_json(path);
+
+    if update_golden() || (record_missing && !golden_path.exists()) {
+        std::fs::create_dir_all(golden_path.parent().unwrap()).expect("Unable to create the golden directory");
+        std::fs::write(golden_path, &actual).expect("Unable to write the golden file");
+        return None;
+    }
+
+    let Ok(expected) = std::fs::read_to_string(golden_path) else {
+        return Some(format!("{}: missing golden file {}", path.display(), golden_path.display()));
+    };
+
+    if actual == expected {
+        return None;
+    }
+
+    let mut actual_lines = actual.lines();
+    let mut expected_lines = expected.lines();
+    let mut line = 1;
+
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(e), Some(a)) if e == a => line += 1,
+            (e, a) => {
+                return Some(format!(
+                    "{}: differs from {} at line {}\n  expected: {}\n  actual:   {}",
+                    path.display(),
+                    golden_path.display(),
+                    line,
+                    e.unwrap_or("<end of file>"),
+                    a.unwrap_or("<end of file>")
+                ));
+            }
+        }
+    }
+}
+
+fn assert_no_differences(differences: Vec<String>) {
+    assert!(
+        differences.is_empty(),
+        "{} file(s) differ from their golden output (rerun with EXECDUMP_UPDATE_GOLDEN=1 if the change is intended):\n{}",
+        differences.len(),
+        differences.join("\n")
+    );
+}
+
+#[test]
+fn fixtures_match_golden() {
+    let fixtures_dir = manifest_dir().join("tests").join("fixtures");
+    let golden_dir = manifest_dir().join("tests").join("golden");
+
+    let differences = FIXTURES
+        .iter()
+        .filter_map(|fixture| check_golden(&fixtures_dir.join(fixture), &golden_dir.join(format!("{}.json", fixture)), false))
+        .collect();
+
+    assert_no_differences(differences);
+}
+
+#[test]
+fn corpus_matches_golden() {
+    let Some(corpus_dir) = std::env::var_os("EXECDUMP_GOLDEN_CORPUS").map(PathBuf::from) else {
+        return;
+    };
+
+    let golden_dir = corpus_dir.join("golden");
+
+    let mut files: Vec<PathBuf> = std::fs::read_dir(&corpus_dir)
+        .expect("Unable to read the corpus directory")
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect();
+
+    files.sort();
+
+    let differences = files
+        .iter()
+        .filter_map(|file| check_golden(file, &golden_dir.join(format!("{}.json", file_name(file))), true))
+        .collect();
+
+    assert_no_differences(differences);
+}
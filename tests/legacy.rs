@@ -0,0 +1,143 @@
+mod common;
+
+use execdump::legacy::{detect_legacy_format, LegacyExecutable, LegacyFormat, NEImport};
+
+use common::{execdump, fixture, fixture_bytes};
+
+/*
+ * NE and LE executables written by tests/fixtures/src/legacy.py: the Windows 3.x program hello16.exe and the VxD
+ * hello.vxd, their tables are cut or corrupted in place to check the errors and the escaped names
+ */
+
+/* Offsets of the tables in hello16.exe */
+const NE_RESIDENT_NAMES: usize = 0x90;
+const NE_RELOCATIONS: usize = 0x210;
+
+/* Offsets of the tables in hello.vxd */
+const LE_IMPORT_MODULE_COUNT: usize = 0x40 + 0x74;
+const LE_IMPORT_MODULES: usize = 0x154;
+
+fn parse(bytes: &[u8]) -> LegacyExecutable {
+    return LegacyExecutable::from_bytes(bytes).expect("Unable to parse the executable").expect("Not a legacy executable");
+}
+
+fn parse_error(bytes: &[u8]) -> String {
+    return LegacyExecutable::from_bytes(bytes).expect_err("The executable was parsed").to_string();
+}
+
+#[test]
+fn detect_formats() {
+    assert_eq!(detect_legacy_format(&fixture_bytes("hello16.exe")), Some((LegacyFormat::NE, 0x40)));
+    assert_eq!(detect_legacy_format(&fixture_bytes("hello.vxd")), Some((LegacyFormat::LE, 0x40)));
+
+    /* PE and ELF files are left to their own parsers */
+    assert_eq!(detect_legacy_format(&fixture_bytes("app64.exe")), None);
+    assert_eq!(detect_legacy_format(&fixture_bytes("hello.elf")), None);
+    assert!(LegacyExecutable::from_file(&fixture("app32.exe")).unwrap().is_none());
+}
+
+#[test]
+fn ne_header_segments_imports_and_names() {
+    let LegacyExecutable::NE(ne) = parse(&fixture_bytes("hello16.exe")) else {
+        panic!("Not an NE executable");
+    };
+
+    assert_eq!((ne.header.linker_version, ne.header.linker_revision, ne.header.target_os), (5, 10, 2));
+    assert_eq!((ne.header.initial_cs, ne.header.initial_ip, ne.header.alignment_shift), (1, 0, 4));
+    assert_eq!((ne.module_name.as_str(), ne.description.as_str()), ("HELLO", "Hello World"));
+
+    let segments: Vec<(u64, u32, u16, bool)> = ne.segments.iter().map(|s| (s.file_offset, s.length, s.flags, s.is_data())).collect();
+    assert_eq!(segments, [(0x200, 0x10, 0x0150, false), (0x230, 0x20, 0x0041, true)]);
+
+    /* The imports come from the relocations of the code segment, the module indices start at 1 */
+    assert_eq!(ne.imported_modules, ["KERNEL", "USER"]);
+    assert_eq!(ne.imports.get(&1), Some(&vec![NEImport::Ordinal(91)]));
+    assert_eq!(ne.imports.get(&2), Some(&vec![NEImport::Name(String::from("MESSAGEBOX"))]));
+
+    assert_eq!(ne.resident_names, [(String::from("HELLOPROC"), 1)]);
+    assert_eq!(ne.non_resident_names, [(String::from("HELLOPROC"), 1)]);
+    assert!(ne.warnings.is_empty(), "{:?}", ne.warnings);
+}
+
+#[test]
+fn le_header_objects_imports_and_names() {
+    let LegacyExecutable::LE(le) = parse(&fixture_bytes("hello.vxd")) else {
+        panic!("Not an LE executable");
+    };
+
+    assert_eq!(le.format, LegacyFormat::LE);
+    assert_eq!((le.header.cpu_type, le.header.os_type, le.header.page_size), (2, 4, 0x1000));
+    assert_eq!((le.header.eip_object, le.header.esp_object, le.header.esp), (1, 2, 0x100));
+    assert_eq!((le.module_name.as_str(), le.description.as_str()), ("HELLOVXD", "Hello virtual device"));
+
+    let objects: Vec<(u32, u32, u32, u32)> = le.objects.iter().map(|o| (o.base_address, o.virtual_size, o.flags, o.page_count)).collect();
+    assert_eq!(objects, [(0x0, 0x100, 0x2045, 1), (0x1000, 0x200, 0x2003, 0)]);
+
+    assert_eq!(le.imported_modules, ["VMM"]);
+    assert_eq!(le.resident_names, [(String::from("HELLOVXD_DDB"), 1)]);
+    assert!(le.non_resident_names.is_empty());
+    assert!(le.warnings.is_empty(), "{:?}", le.warnings);
+}
+
+#[test]
+fn dumps() {
+    let stdout = execdump(&[], &fixture("hello16.exe"));
+
+    assert!(stdout.starts_with("NE Executable"), "{}", stdout);
+    assert!(stdout.contains("#1   CODE  offset: 0x000200 length: 0x0010 alloc: 0x0010  MOVEABLE | PRELOAD | RELOC_INFO"), "{}", stdout);
+    assert!(stdout.contains("KERNEL (1 functions)\n            KERNEL.91"), "{}", stdout);
+    assert!(stdout.contains("USER (1 functions)\n            MESSAGEBOX"), "{}", stdout);
+    assert!(stdout.contains("Resident Names (1)\n        @1     HELLOPROC"), "{}", stdout);
+
+    let stdout = execdump(&[], &fixture("hello.vxd"));
+
+    assert!(stdout.starts_with("LE Executable"), "{}", stdout);
+    assert!(stdout.contains("TargetOS      : 4 (Windows 386, virtual device driver)"), "{}", stdout);
+    assert!(stdout.contains("#1   base: 0x00000000 size: 0x000100 pages: 1 (from 1)  READABLE | EXECUTABLE | PRELOAD | BIG"), "{}", stdout);
+    assert!(stdout.contains("Imported Modules (1)\n        VMM"), "{}", stdout);
+}
+
+#[test]
+fn non_printable_names_are_escaped() {
+    let mut bytes = fixture_bytes("hello16.exe");
+
+    /* HELLOPROC becomes H\x01\xffLOPROC */
+    bytes[NE_RESIDENT_NAMES + 10] = 0x01;
+    bytes[NE_RESIDENT_NAMES + 11] = 0xff;
+
+    let LegacyExecutable::NE(ne) = parse(&bytes) else {
+        panic!("Not an NE executable");
+    };
+
+    assert_eq!(ne.resident_names, [(String::from("H\\x01\\xffLOPROC"), 1)]);
+    assert_eq!(ne.warnings, ["H\\x01\\xffLOPROC: Name at 0x98 is not printable UTF-8, non-printable bytes are escaped"]);
+
+    let fields: Vec<(&str, String)> = LegacyExecutable::NE(ne).dump().iter_children().flat_map(|c| c.iter_fields()).map(|f| (f.key, f.value.clone())).collect();
+    assert!(fields.contains(&("", String::from("@1     H\\x01\\xffLOPROC"))), "{:?}", fields);
+}
+
+#[test]
+fn truncated_tables() {
+    let bytes = fixture_bytes("hello16.exe");
+
+    /* In the middle of the relocations of the code segment */
+    assert_eq!(parse_error(&bytes[..NE_RELOCATIONS + 6]), "Unexpected end of file");
+
+    /* In the resident names, the module reference table after them is out of the file */
+    assert_eq!(parse_error(&bytes[..NE_RESIDENT_NAMES + 3]), "Invalid position");
+
+    let bytes = fixture_bytes("hello.vxd");
+
+    /* Import module table with more modules than the file holds */
+    let mut corrupted = bytes.clone();
+    corrupted[LE_IMPORT_MODULE_COUNT..LE_IMPORT_MODULE_COUNT + 4].copy_from_slice(&0x1000u32.to_le_bytes());
+    assert_eq!(parse_error(&corrupted), "Unexpected end of file");
+
+    /* A name running past the end of the file */
+    let mut corrupted = bytes[..LE_IMPORT_MODULES + 4].to_vec();
+    corrupted[LE_IMPORT_MODULES] = 0x10;
+    assert_eq!(parse_error(&corrupted), "Unexpected end of file");
+
+    /* The header itself */
+    assert_eq!(parse_error(&bytes[..0x60]), "Unexpected end of file");
+}
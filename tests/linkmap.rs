@@ -0,0 +1,63 @@
+mod common;
+
+use std::path::PathBuf;
+
+use execdump::linkmap::LinkMap;
+
+use common::{copy_fixture, execdump, fixture_bytes, work_dir};
+
+/*
+ * Link map of a test directory: app64.exe and deps64.exe import from lib64.dll, kept in a subdirectory, deps64.exe
+ * also imports qux that lib64.dll does not export and functions of DLLs outside of the directory
+ */
+
+fn link_map_dir(test: &str) -> PathBuf {
+    let dir = work_dir(test);
+
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+
+    copy_fixture("app64.exe", &dir);
+    copy_fixture("deps64.exe", &dir);
+    copy_fixture("hello.elf", &dir);
+    copy_fixture("lib64.dll", &dir.join("sub"));
+
+    /* Ignored, and skipped as it cannot be parsed */
+    std::fs::write(dir.join("readme.txt"), "not an executable").unwrap();
+    std::fs::write(dir.join("broken.dll"), &fixture_bytes("lib64.dll")[..0x100]).unwrap();
+
+    return dir;
+}
+
+#[test]
+fn resolved_and_unresolved_imports() {
+    let dir = link_map_dir("resolved-and-unresolved");
+    let link_map = LinkMap::from_directory(&dir).unwrap();
+
+    let modules: Vec<String> = link_map.modules.iter().map(|m| m.name()).collect();
+    assert_eq!(modules, ["app64.exe", "deps64.exe", "hello.elf", "lib64.dll"]);
+    assert_eq!(link_map.skipped.iter().map(|(path, _)| path.clone()).collect::<Vec<_>>(), [dir.join("broken.dll")]);
+
+    /* baz is exported by ordinal only, the import of app64.exe stays #7 */
+    let links: Vec<(&str, &str, Vec<&str>)> = link_map
+        .links
+        .iter()
+        .map(|((importer, exporter), symbols)| (importer.as_str(), exporter.as_str(), symbols.iter().map(|s| s.as_str()).collect()))
+        .collect();
+
+    assert_eq!(links, [("app64.exe", "lib64.dll", vec!["bar", "#7", "foo"]), ("deps64.exe", "lib64.dll", vec!["foo"])]);
+
+    /* The imports of missing64.dll, kernel32.dll and libgreet.so cannot be checked */
+    assert_eq!(link_map.unresolved_imports, [(String::from("deps64.exe"), Some(String::from("lib64.dll")), String::from("qux"))]);
+    assert_eq!(link_map.dead_exports.get("lib64.dll"), Some(&vec![String::from("sleep")]));
+}
+
+#[test]
+fn link_map_flag() {
+    let dir = link_map_dir("flag");
+    let stdout = execdump(&["--link-map"], &dir);
+
+    assert!(stdout.contains("Modules           : 4\n    Links             : 2\n    DeadExports       : 1\n    UnresolvedImports : 1"), "{}", stdout);
+    assert!(stdout.contains("deps64.exe\n            -> : lib64.dll (1 symbols)"), "{}", stdout);
+    assert!(stdout.contains("Unresolved Imports (1)\n        deps64.exe: lib64.dll!qux"), "{}", stdout);
+    assert!(stdout.contains(&format!("Skipped : {}", dir.join("broken.dll").display())), "{}", stdout);
+}
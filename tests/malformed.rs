@@ -0,0 +1,364 @@
+mod common;
+
+use execdump::pe::PE;
+
+use common::{fixture_bytes, parse};
+
+/*
+ * Damaged directories of otherwise valid executables, the headers and sections must still parse and the damage be
+ * reported as warnings of the directory instead of failing the whole file
+ */
+
+/// File offset of the root resource directory
+fn resource_directory_offset(pe: &PE) -> usize {
+    let resource_va = pe.get_optional_header().get_resource_table_idd().virtual_address;
+    return pe.convert_rva_to_file_offset(resource_va).expect("The fixture has no resources") as usize;
+}
+
+fn resource_paths(pe: &PE) -> Vec<String> {
+    return pe.resource_table.as_ref().unwrap().entries.iter().map(|entry| entry.path()).collect();
+}
+
+fn assert_headers_and_sections(damaged: &PE, original: &PE) {
+    assert_eq!(damaged.get_number_of_sections(), original.get_number_of_sections());
+    assert_eq!(damaged.section_names_by_index(), original.section_names_by_index());
+    assert_eq!(damaged.hint_name_table.as_ref().map(|hnt| hnt.entries.len()), original.hint_name_table.as_ref().map(|hnt| hnt.entries.len()));
+}
+
+/* Resources */
+
+#[test]
+fn resource_directory_with_overflowing_entry_count() {
+    let mut bytes = fixture_bytes("res64.exe");
+    let original = parse(&bytes);
+    let root = resource_directory_offset(&original);
+
+    /* NumberOfIdEntries of the root directory, the entries run past the end of the file */
+    bytes[root + 14..root + 16].copy_from_slice(&0xffffu16.to_le_bytes());
+
+    let damaged = parse(&bytes);
+    let resources = damaged.resource_table.as_ref().expect("The resources are dropped");
+
+    assert_headers_and_sections(&damaged, &original);
+    assert!(!resources.warnings.is_empty(), "No warning for the damaged resource directory");
+    assert_eq!(resource_paths(&damaged)[..original.resource_table.as_ref().unwrap().entries.len()], resource_paths(&original)[..]);
+}
+
+#[test]
+fn resource_subdirectory_out_of_bounds() {
+    let mut bytes = fixture_bytes("res64.exe");
+    let original = parse(&bytes);
+    let root = resource_directory_offset(&original);
+
+    /* Points the RCDATA subdirectory past the end of the file, the other types are still walked */
+    let rcdata_entry = (0..3)
+        .map(|index| root + 16 + index * 8)
+        .find(|entry| u32::from_le_bytes(bytes[*entry..*entry + 4].try_into().unwrap()) == 10)
+        .expect("The fixture has no RCDATA");
+
+    bytes[rcdata_entry + 4..rcdata_entry + 8].copy_from_slice(&0xfffff000u32.to_le_bytes());
+
+    let damaged = parse(&bytes);
+    let resources = damaged.resource_table.as_ref().unwrap();
+
+    assert_headers_and_sections(&damaged, &original);
+    assert_eq!(resources.warnings.len(), 1, "{:?}", resources.warnings);
+
+    let expected: Vec<String> = resource_paths(&original).into_iter().filter(|path| !path.starts_with("RCDATA")).collect();
+    assert_eq!(resource_paths(&damaged), expected);
+}
+
+#[test]
+fn resource_directory_truncated_by_the_end_of_the_file() {
+    let bytes = fixture_bytes("res64.exe");
+    let original = parse(&bytes);
+    let root = resource_directory_offset(&original);
+
+    /* The root directory and its entries, the subdirectories are cut */
+    let truncated = &bytes[..root + 16 + 3 * 8];
+
+    let damaged = parse(truncated);
+    let resources = damaged.resource_table.as_ref().unwrap();
+
+    assert_headers_and_sections(&damaged, &original);
+    assert!(resources.entries.is_empty());
+    assert_eq!(resources.warnings.len(), 3, "{:?}", resources.warnings);
+}
+
+/* Imports */
+
+/* Index of the import table in the data directories, and the layout of app64.exe */
+const IMPORT_DIRECTORY_INDEX: usize = 1;
+const IMPORT_DESCRIPTOR_SIZE: usize = 20;
+const APP64_IMPORT_DIRECTORY: usize = 0x600;
+const APP64_RDATA_RVA: usize = 0x2000;
+
+/// Sets the RVA and the size of the import directory
+fn set_import_directory(bytes: &mut [u8], pe: &PE, rva: u32, size: u32) {
+    let entry = pe.get_data_directory_offset(IMPORT_DIRECTORY_INDEX) as usize;
+
+    bytes[entry..entry + 4].copy_from_slice(&rva.to_le_bytes());
+    bytes[entry + 4..entry + 8].copy_from_slice(&size.to_le_bytes());
+}
+
+/// DLL names and function names of the parsed imports, the ordinals are left out
+fn imports(pe: &PE) -> Vec<(String, Vec<String>)> {
+    return pe
+        .hint_name_table
+        .as_ref()
+        .map(|table| table.entries.iter().map(|dll| (dll.dll_name.clone(), dll.entries.iter().map(|e| e.name.clone()).collect())).collect())
+        .unwrap_or_default();
+}
+
+fn import_warnings(pe: &PE) -> Vec<String> {
+    return pe.import_directory_table.as_ref().expect("The import directory is dropped").warnings.clone();
+}
+
+#[test]
+fn import_directory_truncated_by_the_end_of_the_file() {
+    let mut bytes = fixture_bytes("app64.exe");
+    let original = parse(&bytes);
+
+    /* The descriptor is moved to the end of the file, the null descriptor after it is cut after 8 bytes */
+    let moved = bytes.len() - IMPORT_DESCRIPTOR_SIZE - 8;
+    bytes.copy_within(APP64_IMPORT_DIRECTORY..APP64_IMPORT_DIRECTORY + IMPORT_DESCRIPTOR_SIZE, moved);
+    bytes[moved + IMPORT_DESCRIPTOR_SIZE..].fill(0xff);
+    set_import_directory(&mut bytes, &original, (APP64_RDATA_RVA + moved - APP64_IMPORT_DIRECTORY) as u32, 0x28);
+
+    let damaged = parse(&bytes);
+
+    assert_headers_and_sections(&damaged, &original);
+    assert_eq!(import_warnings(&damaged), ["Import directory truncated by the end of the file after 1 descriptors"]);
+    assert_eq!(imports(&damaged), imports(&original));
+}
+
+#[test]
+fn import_directory_without_null_descriptor() {
+    let mut bytes = fixture_bytes("app64.exe");
+    let original = parse(&bytes);
+
+    /* The null descriptor is replaced by a copy of the first one, the size only covers one descriptor */
+    bytes.copy_within(APP64_IMPORT_DIRECTORY..APP64_IMPORT_DIRECTORY + IMPORT_DESCRIPTOR_SIZE, APP64_IMPORT_DIRECTORY + IMPORT_DESCRIPTOR_SIZE);
+    set_import_directory(&mut bytes, &original, APP64_RDATA_RVA as u32, IMPORT_DESCRIPTOR_SIZE as u32);
+
+    let damaged = parse(&bytes);
+
+    assert_headers_and_sections(&damaged, &original);
+    assert_eq!(import_warnings(&damaged), ["Import directory has no null descriptor within its size (0x14), stopped after 1 descriptors"]);
+    assert_eq!(imports(&damaged), [(String::from("lib64.dll"), vec![String::from("bar"), String::from("foo")])]);
+}
+
+#[test]
+fn import_lookup_table_past_the_end_of_the_section() {
+    let mut bytes = fixture_bytes("app64.exe");
+    let original = parse(&bytes);
+
+    /* The lookup table starts on the last 8 bytes of .rdata, its first entry names bar and the next one is cut */
+    let table = bytes.len() - 8;
+    let table_rva = (APP64_RDATA_RVA + table - APP64_IMPORT_DIRECTORY) as u32;
+
+    bytes.copy_within(APP64_IMPORT_DIRECTORY + 0x28..APP64_IMPORT_DIRECTORY + 0x30, table);
+    bytes[APP64_IMPORT_DIRECTORY..APP64_IMPORT_DIRECTORY + 4].copy_from_slice(&table_rva.to_le_bytes());
+
+    let damaged = parse(&bytes);
+
+    assert_headers_and_sections(&damaged, &original);
+    assert_eq!(import_warnings(&damaged), ["lib64.dll: Import Lookup Table at 0x21f8 has no null entry, stopped after 1 entries"]);
+    assert_eq!(imports(&damaged), [(String::from("lib64.dll"), vec![String::from("bar")])]);
+    assert!(damaged.import_lookup_tables.as_ref().unwrap()[0].truncated);
+}
+
+#[test]
+fn import_addresses_inside_the_headers() {
+    let mut bytes = fixture_bytes("app64.exe");
+    let original = parse(&bytes);
+
+    /* The headers are not part of any section, the lookup table is not read */
+    bytes[APP64_IMPORT_DIRECTORY..APP64_IMPORT_DIRECTORY + 4].copy_from_slice(&0x10u32.to_le_bytes());
+
+    let damaged = parse(&bytes);
+
+    assert_headers_and_sections(&damaged, &original);
+    assert_eq!(import_warnings(&damaged), ["lib64.dll: Import Lookup Table RVA 0x10 is outside of the sections"]);
+    assert_eq!(imports(&damaged), [(String::from("lib64.dll"), Vec::new())]);
+
+    /* Neither is the DLL name, its functions are still listed */
+    let mut bytes = fixture_bytes("app64.exe");
+    bytes[APP64_IMPORT_DIRECTORY + 12..APP64_IMPORT_DIRECTORY + 16].copy_from_slice(&0x40u32.to_le_bytes());
+
+    let damaged = parse(&bytes);
+
+    assert_eq!(import_warnings(&damaged), ["Descriptor 0: DLL name RVA 0x40 is outside of the sections"]);
+    assert_eq!(imports(&damaged), [(String::new(), vec![String::from("bar"), String::from("foo")])]);
+
+    /* An import directory inside the headers is not parsed */
+    let mut bytes = fixture_bytes("app64.exe");
+    set_import_directory(&mut bytes, &original, 0x40, 0x28);
+
+    let damaged = parse(&bytes);
+
+    assert!(damaged.import_directory_table.is_none() && damaged.hint_name_table.is_none());
+}
+
+#[test]
+fn import_lookup_entries_with_reserved_bits() {
+    let mut bytes = fixture_bytes("app64.exe");
+
+    /* PE32+: bit 40 of the name entry of bar, and a high ordinal of 1 in the ordinal entry of #7 */
+    let table = APP64_IMPORT_DIRECTORY + 0x28;
+    bytes[table..table + 8].copy_from_slice(&0x0000010000002068u64.to_le_bytes());
+    bytes[table + 8..table + 16].copy_from_slice(&0x8000000000010007u64.to_le_bytes());
+
+    let pe = parse(&bytes);
+    let entries = &pe.import_lookup_tables.as_ref().unwrap()[0].entries;
+
+    assert_eq!(
+        import_warnings(&pe),
+        [
+            "lib64.dll: Import Lookup Table entry 0x10000002068 has reserved bits set",
+            "lib64.dll: Import Lookup Table entry 0x8000000000010007 has reserved bits set",
+        ]
+    );
+    assert_eq!(
+        entries.iter().map(|e| (e.by_ordinal, e.ordinal_number, e.hint_name_table_rva, e.reserved_bits_set)).collect::<Vec<_>>(),
+        [(false, 0, 0x2068, true), (true, 7, 0, true), (false, 0, 0x206e, false)]
+    );
+
+    /* The masked fields are still used */
+    assert_eq!(imports(&pe), [(String::from("lib64.dll"), vec![String::from("bar"), String::from("foo")])]);
+
+    let dump = pe.import_lookup_tables.as_ref().unwrap()[0].dump();
+    let flagged: Vec<&str> = dump
+        .iter_children()
+        .filter(|entry| entry.iter_fields().any(|f| f.key == "Warning" && f.value == "reserved bits set"))
+        .map(|entry| entry.iter_fields().next().unwrap().value.as_str())
+        .collect();
+
+    assert_eq!(flagged, ["0x10000002068", "0x8000000000010007"]);
+
+    /* PE32: the ordinal flag leaves no reserved bit to the name entries, bits 16 to 30 of the ordinal entries are */
+    let mut bytes = fixture_bytes("app32.exe");
+    let table = 0x628;
+    bytes[table + 4..table + 8].copy_from_slice(&0x80420007u32.to_le_bytes());
+
+    let pe = parse(&bytes);
+    let entries = &pe.import_lookup_tables.as_ref().unwrap()[0].entries;
+
+    assert_eq!(import_warnings(&pe), ["kernel32.dll: Import Lookup Table entry 0x80420007 has reserved bits set"]);
+    assert_eq!((entries[1].by_ordinal, entries[1].ordinal_number, entries[1].reserved_bits_set), (true, 7, true));
+    assert_eq!(imports(&pe), [(String::from("kernel32.dll"), vec![String::from("ExitProcess@4"), String::from("WriteFile@20")])]);
+}
+
+/* .NET */
+
+/* Index of the CLR runtime header in the data directories */
+const CLR_DIRECTORY_INDEX: usize = 14;
+const CLR_HEADER_SIZE: u32 = 72;
+
+/// Adds a CLR header and a metadata root with the given (offset, size, name) streams in the padding of the .text section
+/// of app64.exe, the streams data is a #Strings heap. The metadata size is the real one unless given
+fn with_dotnet_metadata(streams: &[(u32, u32, &str)], metadata_size: Option<u32>) -> (Vec<u8>, Vec<u8>) {
+    let mut bytes = fixture_bytes("app64.exe");
+    let pe = parse(&bytes);
+    let text = pe.get_section(".text").unwrap();
+
+    let rva = text.header.virtual_address + 0x100;
+    let offset = text.header.ptr_to_raw_data as usize + 0x100;
+
+    let mut metadata = Vec::new();
+    metadata.extend_from_slice(b"BSJB");
+    metadata.extend_from_slice(&[1, 0, 1, 0, 0, 0, 0, 0]);
+    metadata.extend_from_slice(&12u32.to_le_bytes());
+    metadata.extend_from_slice(b"v4.0.30319\0\0");
+    metadata.extend_from_slice(&0u16.to_le_bytes());
+    metadata.extend_from_slice(&(streams.len() as u16).to_le_bytes());
+
+    for (stream_offset, stream_size, name) in streams.iter() {
+        metadata.extend_from_slice(&stream_offset.to_le_bytes());
+        metadata.extend_from_slice(&stream_size.to_le_bytes());
+        metadata.extend_from_slice(name.as_bytes());
+        metadata.resize((metadata.len() + 4) & !3, 0);
+    }
+
+    let strings = b"\0Program\0Main\0\0\0".to_vec();
+    metadata.extend_from_slice(&strings);
+
+    let mut clr_header = vec![0u8; CLR_HEADER_SIZE as usize];
+    clr_header[0..4].copy_from_slice(&CLR_HEADER_SIZE.to_le_bytes());
+    clr_header[4..8].copy_from_slice(&[2, 0, 5, 0]);
+    clr_header[8..12].copy_from_slice(&(rva + CLR_HEADER_SIZE).to_le_bytes());
+    clr_header[12..16].copy_from_slice(&metadata_size.unwrap_or(metadata.len() as u32).to_le_bytes());
+    clr_header[16..20].copy_from_slice(&1u32.to_le_bytes());
+
+    bytes[offset..offset + clr_header.len()].copy_from_slice(&clr_header);
+    bytes[offset + clr_header.len()..offset + clr_header.len() + metadata.len()].copy_from_slice(&metadata);
+
+    let directory = pe.get_data_directory_offset(CLR_DIRECTORY_INDEX) as usize;
+    bytes[directory..directory + 4].copy_from_slice(&rva.to_le_bytes());
+    bytes[directory + 4..directory + 8].copy_from_slice(&CLR_HEADER_SIZE.to_le_bytes());
+
+    return (bytes, strings);
+}
+
+/// Size of the metadata root and stream headers of with_dotnet_metadata, where the #Strings heap starts
+fn streams_offset(streams: &[(u32, u32, &str)]) -> u32 {
+    return 32 + streams.iter().map(|(_, _, name)| 8 + ((name.len() as u32 + 4) & !3)).sum::<u32>();
+}
+
+#[test]
+fn dotnet_stream_past_the_end_of_the_metadata() {
+    let original = parse(&fixture_bytes("app64.exe"));
+
+    let mut streams = vec![(0, 16, "#Strings"), (0x1000, 0x100, "#Blob")];
+    streams[0].0 = streams_offset(&streams);
+
+    let (bytes, strings) = with_dotnet_metadata(&streams, None);
+
+    let damaged = parse(&bytes);
+    let dotnet = damaged.dotnet.as_ref().expect("The .NET data is dropped");
+
+    assert_headers_and_sections(&damaged, &original);
+    assert_eq!(dotnet.metadata_root.version, "v4.0.30319");
+    assert_eq!(dotnet.heaps.strings, strings);
+    assert!(dotnet.heaps.blobs.is_empty());
+    assert_eq!(dotnet.warnings.len(), 1, "{:?}", dotnet.warnings);
+    assert!(dotnet.warnings[0].contains("#Blob"));
+}
+
+#[test]
+fn dotnet_metadata_size_past_the_end_of_the_file() {
+    let original = parse(&fixture_bytes("app64.exe"));
+
+    let mut streams = vec![(0, 16, "#Strings")];
+    streams[0].0 = streams_offset(&streams);
+
+    /* Only the bytes left in the file are read, not the 4 GiB the header asks for */
+    let (bytes, strings) = with_dotnet_metadata(&streams, Some(0xfffffff0));
+
+    let damaged = parse(&bytes);
+    let dotnet = damaged.dotnet.as_ref().unwrap();
+
+    assert_headers_and_sections(&damaged, &original);
+    assert_eq!(dotnet.heaps.strings, strings);
+    assert_eq!(dotnet.warnings.len(), 1, "{:?}", dotnet.warnings);
+    assert!(dotnet.warnings[0].contains("past the end of the file"));
+}
+
+/* Exception table */
+
+#[test]
+fn exception_table_of_an_unsupported_machine() {
+    let mut bytes = fixture_bytes("arm64x.exe");
+    let original = parse(&bytes);
+
+    /* IMAGE_FILE_MACHINE_MIPS16, the function entries are skipped instead of failing the whole file */
+    let machine = original.get_coff_header_offset() as usize;
+    bytes[machine..machine + 2].copy_from_slice(&0x266u16.to_le_bytes());
+
+    let damaged = parse(&bytes);
+    let exception_table = damaged.exception_table.as_ref().expect("The exception table is dropped");
+
+    assert_eq!(damaged.section_names_by_index(), original.section_names_by_index());
+    assert!(exception_table.entries.is_empty());
+    assert_eq!(exception_table.warnings, ["Function entries of MIPS16 images are not decoded, 0x10 bytes skipped"]);
+}
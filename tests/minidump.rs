@@ -0,0 +1,280 @@
+mod common;
+
+use std::process::Command;
+
+use execdump::exec::{parse_exec_memory_image_from_bytes, Exec};
+use execdump::minidump::{is_minidump, Minidump};
+
+use common::{fixture_bytes, map_image, parse, work_dir};
+
+/*
+ * Minidumps written by the tests: the process of app64.exe with one thread, its stack and the module image, the .text
+ * page of the module is not saved
+ */
+
+const STREAM_THREAD_LIST: u32 = 3;
+const STREAM_MODULE_LIST: u32 = 4;
+const STREAM_MEMORY_LIST: u32 = 5;
+const STREAM_SYSTEM_INFO: u32 = 7;
+const STREAM_MEMORY64_LIST: u32 = 9;
+
+const AMD64_CONTEXT_SIZE: usize = 0x4d0;
+const PROCESSOR_ARCHITECTURE_AMD64: u16 = 9;
+
+const STACK_START: u64 = 0x7ff000;
+const STACK_SIZE: u32 = 0x1000;
+const INSTRUCTION_POINTER: u64 = 0x140001000;
+const STACK_POINTER: u64 = 0x7fff28;
+const MODULE_PATH: &str = "C:\\Program Files\\App\\app64.exe";
+
+/// Streams of the minidump, their data is appended after the stream directory
+struct MinidumpWriter {
+    streams: Vec<(u32, Vec<u8>)>,
+}
+
+impl MinidumpWriter {
+    /// Header, stream directory and stream data, the raw data the streams point to is appended by the caller
+    fn write(&self) -> Vec<u8> {
+        let directory_size = self.streams.len() * 12;
+        let mut bytes = Vec::new();
+
+        bytes.extend_from_slice(b"MDMP");
+        bytes.extend_from_slice(&0xa793u32.to_le_bytes());
+        bytes.extend_from_slice(&(self.streams.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&32u32.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&1700000000u32.to_le_bytes());
+        bytes.extend_from_slice(&2u64.to_le_bytes());
+
+        let mut rva = 32 + directory_size;
+
+        for (stream_type, data) in self.streams.iter() {
+            bytes.extend_from_slice(&stream_type.to_le_bytes());
+            bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&(rva as u32).to_le_bytes());
+            rva += data.len();
+        }
+
+        for (_, data) in self.streams.iter() {
+            bytes.extend_from_slice(data);
+        }
+
+        return bytes;
+    }
+}
+
+fn put_u32(data: &mut [u8], offset: usize, value: u32) {
+    data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn put_u64(data: &mut [u8], offset: usize, value: u64) {
+    data[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+}
+
+/// Minidump of app64.exe, the module name, the thread context, the stack and the module memory follow the streams in
+/// this order. Returns the bytes and the image of the module as read back from the dump
+fn app64_minidump() -> (Vec<u8>, Vec<u8>) {
+    let original = fixture_bytes("app64.exe");
+    let pe = parse(&original);
+    let image_base = pe.get_optional_header().get_image_base();
+    let mut image = map_image(&pe, &original);
+
+    let mut system_info = vec![0u8; 56];
+    system_info[0..2].copy_from_slice(&PROCESSOR_ARCHITECTURE_AMD64.to_le_bytes());
+    system_info[6] = 4;
+    put_u32(&mut system_info, 8, 10);
+    put_u32(&mut system_info, 12, 0);
+    put_u32(&mut system_info, 16, 19045);
+
+    let mut name = Vec::new();
+    let utf16: Vec<u8> = MODULE_PATH.encode_utf16().flat_map(|c| c.to_le_bytes()).collect();
+    name.extend_from_slice(&(utf16.len() as u32).to_le_bytes());
+    name.extend_from_slice(&utf16);
+    name.extend_from_slice(&[0, 0]);
+
+    let mut context = vec![0u8; AMD64_CONTEXT_SIZE];
+    put_u64(&mut context, 0xf8, INSTRUCTION_POINTER);
+    put_u64(&mut context, 0x98, STACK_POINTER);
+
+    let stack = vec![0x5a; STACK_SIZE as usize];
+
+    /* Stream data sizes, the raw data offsets are known before writing the lists */
+    let thread_list_size = 4 + 48;
+    let module_list_size = 4 + 108;
+    let memory_list_size = 4 + 16;
+    let memory64_list_size = 16 + 2 * 16;
+
+    let streams_end = 32 + 5 * 12 + thread_list_size + module_list_size + system_info.len() + memory_list_size + memory64_list_size;
+    let name_rva = streams_end;
+    let context_rva = name_rva + name.len();
+    let stack_rva = context_rva + context.len();
+    let image_rva = stack_rva + stack.len();
+
+    let mut thread_list = vec![0u8; thread_list_size];
+    put_u32(&mut thread_list, 0, 1);
+    put_u32(&mut thread_list, 4, 0x1a2c);
+    put_u32(&mut thread_list, 8, 1);
+    put_u64(&mut thread_list, 20, 0x3000);
+    put_u64(&mut thread_list, 28, STACK_START);
+    put_u32(&mut thread_list, 36, STACK_SIZE);
+    put_u32(&mut thread_list, 40, stack_rva as u32);
+    put_u32(&mut thread_list, 44, AMD64_CONTEXT_SIZE as u32);
+    put_u32(&mut thread_list, 48, context_rva as u32);
+
+    let mut module_list = vec![0u8; module_list_size];
+    put_u32(&mut module_list, 0, 1);
+    put_u64(&mut module_list, 4, image_base);
+    put_u32(&mut module_list, 12, image.len() as u32);
+    put_u32(&mut module_list, 20, pe.get_nt_header().coff_header.time_date_stamp);
+    put_u32(&mut module_list, 24, name_rva as u32);
+
+    let mut memory_list = vec![0u8; memory_list_size];
+    put_u32(&mut memory_list, 0, 1);
+    put_u64(&mut memory_list, 4, STACK_START);
+    put_u32(&mut memory_list, 12, STACK_SIZE);
+    put_u32(&mut memory_list, 16, stack_rva as u32);
+
+    /* The headers and everything past the .text page, saved back to back */
+    let mut memory64_list = vec![0u8; memory64_list_size];
+    put_u64(&mut memory64_list, 0, 2);
+    put_u64(&mut memory64_list, 8, image_rva as u64);
+    put_u64(&mut memory64_list, 16, image_base);
+    put_u64(&mut memory64_list, 24, 0x1000);
+    put_u64(&mut memory64_list, 32, image_base + 0x2000);
+    put_u64(&mut memory64_list, 40, image.len() as u64 - 0x2000);
+
+    /* The thread list comes before the system info its context depends on */
+    let writer = MinidumpWriter {
+        streams: vec![
+            (STREAM_THREAD_LIST, thread_list),
+            (STREAM_MODULE_LIST, module_list),
+            (STREAM_SYSTEM_INFO, system_info),
+            (STREAM_MEMORY_LIST, memory_list),
+            (STREAM_MEMORY64_LIST, memory64_list),
+        ],
+    };
+
+    let mut bytes = writer.write();
+    assert_eq!(bytes.len(), streams_end);
+
+    bytes.extend_from_slice(&name);
+    bytes.extend_from_slice(&context);
+    bytes.extend_from_slice(&stack);
+    bytes.extend_from_slice(&image[..0x1000]);
+    bytes.extend_from_slice(&image[0x2000..]);
+
+    image[0x1000..0x2000].fill(0);
+
+    return (bytes, image);
+}
+
+#[test]
+fn modules_threads_and_memory_ranges() {
+    let (bytes, image) = app64_minidump();
+    let image_base = 0x140000000;
+
+    assert!(is_minidump(&bytes));
+
+    let minidump = Minidump::from_bytes(&bytes).unwrap();
+
+    assert!(minidump.warnings.is_empty(), "{:?}", minidump.warnings);
+    assert_eq!((minidump.header.number_of_streams, minidump.header.time_date_stamp, minidump.header.flags), (5, 1700000000, 2));
+    assert_eq!(minidump.streams.iter().map(|s| s.stream_type).collect::<Vec<u32>>(), [3, 4, 7, 5, 9]);
+
+    let system_info = minidump.system_info.as_ref().unwrap();
+    assert_eq!((system_info.processor_architecture, system_info.number_of_processors), (PROCESSOR_ARCHITECTURE_AMD64, 4));
+    assert_eq!((system_info.major_version, system_info.minor_version, system_info.build_number), (10, 0, 19045));
+
+    assert_eq!(minidump.modules.len(), 1);
+    assert_eq!(minidump.modules[0].name, MODULE_PATH);
+    assert_eq!((minidump.modules[0].base_of_image, minidump.modules[0].size_of_image), (image_base, image.len() as u32));
+    assert_eq!(minidump.find_module("APP64.EXE").map(|m| m.name.as_str()), Some(MODULE_PATH));
+    assert_eq!(minidump.find_module(MODULE_PATH).map(|m| m.file_name()), Some("app64.exe"));
+    assert!(minidump.find_module("ntdll.dll").is_none());
+
+    let thread = &minidump.threads[0];
+    assert_eq!((thread.thread_id, thread.suspend_count, thread.teb), (0x1a2c, 1, 0x3000));
+    assert_eq!((thread.stack_start, thread.stack_size, thread.context_size), (STACK_START, STACK_SIZE, AMD64_CONTEXT_SIZE as u32));
+    assert_eq!((thread.instruction_pointer, thread.stack_pointer), (Some(INSTRUCTION_POINTER), Some(STACK_POINTER)));
+
+    /* Sorted by address, the ranges of the 64-bit list follow each other in the file */
+    let ranges: Vec<(u64, u64)> = minidump.memory_ranges.iter().map(|r| (r.start, r.size)).collect();
+    assert_eq!(ranges, [(STACK_START, STACK_SIZE as u64), (image_base, 0x1000), (image_base + 0x2000, image.len() as u64 - 0x2000)]);
+    assert_eq!(minidump.memory_ranges[2].rva, minidump.memory_ranges[1].rva + 0x1000);
+
+    assert_eq!(minidump.saved_size(image_base, image.len() as u64), image.len() as u64 - 0x1000);
+    assert_eq!(minidump.read_memory(&bytes, STACK_POINTER, 8), (vec![0x5a; 8], 8));
+    assert_eq!(minidump.read_memory(&bytes, STACK_START + STACK_SIZE as u64 - 4, 8), (vec![0x5a, 0x5a, 0x5a, 0x5a, 0, 0, 0, 0], 4));
+}
+
+#[test]
+fn module_extraction() {
+    let (bytes, image) = app64_minidump();
+    let minidump = Minidump::from_bytes(&bytes).unwrap();
+
+    /* The .text page is zero-filled */
+    let (extracted, saved) = minidump.extract_module(&bytes, &minidump.modules[0]);
+    assert_eq!(extracted, image);
+    assert_eq!(saved, image.len() as u64 - 0x1000);
+
+    let Exec::PE(pe) = parse_exec_memory_image_from_bytes(&extracted).unwrap() else {
+        panic!("The module is not a PE");
+    };
+
+    let original = parse(&fixture_bytes("app64.exe"));
+    assert_eq!(pe.section_names_by_index(), original.section_names_by_index());
+    assert_eq!(pe.get_optional_header().get_address_of_entry_point(), original.get_optional_header().get_address_of_entry_point());
+    assert_eq!(pe.hint_name_table.as_ref().map(|hnt| hnt.entries.len()), original.hint_name_table.as_ref().map(|hnt| hnt.entries.len()));
+}
+
+#[test]
+fn extract_subcommand() {
+    let dir = work_dir("extract");
+    let (bytes, image) = app64_minidump();
+
+    let path = dir.join("app64.dmp");
+    std::fs::write(&path, &bytes).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_execdump"))
+        .args(["minidump", "--extract", "app64.exe", "--output-dir", dir.to_str().unwrap()])
+        .arg(&path)
+        .output()
+        .expect("Unable to run execdump");
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(std::fs::read(dir.join("app64.exe")).unwrap(), image);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(MODULE_PATH), "{}", stdout);
+    assert!(stdout.contains("Extracted Module (app64.exe)"), "{}", stdout);
+
+    /* The modules missing from the dump fail the command */
+    let status = Command::new(env!("CARGO_BIN_EXE_execdump"))
+        .args(["minidump", "--extract", "ntdll.dll", "--output-dir", dir.to_str().unwrap()])
+        .arg(&path)
+        .output()
+        .expect("Unable to run execdump")
+        .status;
+
+    assert!(!status.success());
+}
+
+#[test]
+fn damaged_streams_are_warnings() {
+    let (mut bytes, _) = app64_minidump();
+
+    /* The module list past the end of the file, the other streams still parse */
+    let module_list_entry = 32 + 12;
+    put_u32(&mut bytes, module_list_entry + 8, 0xfffffff0);
+
+    let minidump = Minidump::from_bytes(&bytes).unwrap();
+
+    assert!(minidump.modules.is_empty());
+    assert_eq!(minidump.threads.len(), 1);
+    assert_eq!(minidump.memory_ranges.len(), 3);
+    assert_eq!(minidump.warnings.len(), 1, "{:?}", minidump.warnings);
+    assert!(minidump.warnings[0].starts_with("MODULE_LIST stream"), "{:?}", minidump.warnings);
+
+    /* Not a minidump */
+    assert!(Minidump::from_bytes(&fixture_bytes("app64.exe")).is_err());
+}
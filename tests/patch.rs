@@ -0,0 +1,524 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use execdump::pe::{compute_checksum, BaseRelocationType, CharacteristicsFlag, DebugEntryData, PE};
+
+use common::{copy_fixture, execdump, fixture, map_image, parse, work_dir};
+
+/*
+ * Round trips of the editing subcommands, a copy of a fixture is edited in a temporary directory then parsed back:
+ * the change must be there, the headers and sections must still parse and the CheckSum must be valid
+ */
+
+fn read_pe(path: &Path) -> (PE, Vec<u8>) {
+    let bytes = std::fs::read(path).expect("Unable to read the edited file");
+    return (parse(&bytes), bytes);
+}
+
+/// Name, virtual address, virtual size, raw size and characteristics of every section, in the order of the section table
+fn section_layout(pe: &PE) -> Vec<(String, u32, u32, u32, u32)> {
+    return pe
+        .sections
+        .iter()
+        .map(|s| (s.header.name.clone(), s.header.virtual_address, s.header.virtual_size, s.header.size_of_raw_data, s.header.characteristics))
+        .collect();
+}
+
+fn section_data<'a>(pe: &'a PE, name: &str) -> &'a [u8] {
+    return &pe.get_section(name).unwrap_or_else(|| panic!("No {} section", name)).data;
+}
+
+fn assert_checksum(pe: &PE, bytes: &[u8]) {
+    assert_ne!(pe.get_optional_header().get_checksum(), 0, "The CheckSum is not written");
+    assert_eq!(pe.get_optional_header().get_checksum(), compute_checksum(bytes, pe.get_checksum_offset()));
+}
+
+/* patch */
+
+#[test]
+fn patch_writes_the_bytes_at_the_rva() {
+    let dir = work_dir("patch");
+    let path = copy_fixture("app64.exe", &dir);
+    let (original, original_bytes) = read_pe(&path);
+
+    let report = execdump(&["patch", "--at", "0x1004", "--rva", "--bytes", "90 90 cc", "--backup", "--fix-checksum"], &path);
+
+    let (patched, patched_bytes) = read_pe(&path);
+
+    assert!(report.contains("NewBytes"), "{}", report);
+    assert_eq!(section_layout(&patched), section_layout(&original));
+    assert_eq!(&section_data(&patched, ".text")[4..7], &[0x90, 0x90, 0xcc]);
+    assert_eq!(section_data(&patched, ".text")[..4], section_data(&original, ".text")[..4]);
+    assert_eq!(section_data(&patched, ".text")[7..], section_data(&original, ".text")[7..]);
+    assert_eq!(section_data(&patched, ".rdata"), section_data(&original, ".rdata"));
+    assert_eq!(patched_bytes.len(), original_bytes.len());
+    assert_checksum(&patched, &patched_bytes);
+
+    let backup = std::fs::read(dir.join("app64.exe.bak")).expect("No backup");
+    assert_eq!(backup, original_bytes);
+}
+
+/* --strip-debug */
+
+#[test]
+fn strip_debug_removes_the_debug_directory_and_the_pdb_path() {
+    let dir = work_dir("strip_debug");
+    let path = copy_fixture("dbg64.exe", &dir);
+    let output = dir.join("stripped.exe");
+    let (original, original_bytes) = read_pe(&path);
+
+    assert!(!original.debug_entries.is_empty());
+
+    execdump(&["--strip-debug", output.to_str().unwrap()], &path);
+
+    let (stripped, stripped_bytes) = read_pe(&output);
+    let debug_directory = stripped.get_optional_header().get_debug_idd();
+
+    assert!(stripped.debug_entries.is_empty());
+    assert_eq!((debug_directory.virtual_address, debug_directory.size), (0, 0));
+    assert_ne!(stripped.get_nt_header().coff_header.characteristics & CharacteristicsFlag::DebugStripped as u16, 0);
+    assert!(!stripped_bytes.windows(b"dbg64.pdb".len()).any(|w| w == b"dbg64.pdb"), "The PDB path is left");
+    assert_eq!(section_layout(&stripped), section_layout(&original));
+    assert_eq!(section_data(&stripped, ".text"), section_data(&original, ".text"));
+    assert_eq!(stripped_bytes.len(), original_bytes.len());
+    assert_checksum(&stripped, &stripped_bytes);
+
+    assert_eq!(std::fs::read(&path).unwrap(), original_bytes, "The input is modified");
+}
+
+/* normalize */
+
+const TIMESTAMP: u32 = 1700000000;
+
+fn normalize(name: &str, dir: &Path) -> (PE, PE, Vec<u8>) {
+    let path = copy_fixture(name, dir);
+    let output = dir.join(format!("normalized-{}", name));
+    let (original, _) = read_pe(&path);
+
+    execdump(&["normalize", "--timestamp", &TIMESTAMP.to_string(), "--output", output.to_str().unwrap()], &path);
+
+    let (normalized, normalized_bytes) = read_pe(&output);
+
+    assert_eq!(normalized.get_nt_header().coff_header.time_date_stamp, TIMESTAMP);
+    assert_eq!(section_layout(&normalized), section_layout(&original));
+    assert_eq!(section_data(&normalized, ".text"), section_data(&original, ".text"));
+    assert_checksum(&normalized, &normalized_bytes);
+
+    return (normalized, original, normalized_bytes);
+}
+
+#[test]
+fn normalize_sets_the_debug_timestamps_and_blanks_the_pdb_path() {
+    let dir = work_dir("normalize_debug");
+    let (normalized, original, _) = normalize("dbg64.exe", &dir);
+
+    assert_eq!(normalized.debug_entries.len(), original.debug_entries.len());
+
+    for entry in normalized.debug_entries.iter() {
+        assert_eq!(entry.directory.time_date_stamp, TIMESTAMP);
+    }
+
+    let codeview = |pe: &PE| {
+        return pe.debug_entries.iter().find_map(|entry| match entry.data {
+            Some(DebugEntryData::CodeView(ref codeview)) => Some(codeview.clone()),
+            _ => None,
+        });
+    };
+
+    let (normalized_codeview, original_codeview) = (codeview(&normalized).unwrap(), codeview(&original).unwrap());
+
+    /* The GUID and age still match the PDB */
+    assert_eq!(original_codeview.pdb_path, "dbg64.pdb");
+    assert_eq!(normalized_codeview.pdb_path, "");
+    assert_eq!(normalized_codeview.guid, original_codeview.guid);
+    assert_eq!(normalized_codeview.age, original_codeview.age);
+}
+
+#[test]
+fn normalize_sets_the_export_and_resource_timestamps() {
+    let dir = work_dir("normalize_directories");
+
+    let (normalized, _, _) = normalize("lib64.dll", &dir);
+    assert_eq!(normalized.export_data.as_ref().unwrap().export_directory_table.time_date_stamp, TIMESTAMP);
+
+    let (normalized, _, normalized_bytes) = normalize("res64.exe", &dir);
+    let root = normalized.convert_rva_to_file_offset(normalized.get_optional_header().get_resource_table_idd().virtual_address).unwrap() as usize;
+    let read_u32 = |offset: usize| u32::from_le_bytes(normalized_bytes[offset..offset + 4].try_into().unwrap());
+    let read_u16 = |offset: usize| u16::from_le_bytes(normalized_bytes[offset..offset + 2].try_into().unwrap()) as usize;
+    let type_entries = read_u16(root + 12) + read_u16(root + 14);
+
+    assert_eq!(read_u32(root + 4), TIMESTAMP);
+
+    /* The directories of the types */
+    for index in 0..type_entries {
+        let subdirectory = read_u32(root + 16 + index * 8 + 4);
+
+        assert_ne!(subdirectory & 0x80000000, 0);
+        assert_eq!(read_u32(root + (subdirectory & 0x7fffffff) as usize + 4), TIMESTAMP);
+    }
+
+    assert!(!normalized.resource_table.as_ref().unwrap().entries.is_empty());
+}
+
+/* --rebase */
+
+/// (RVA, value) of the fields fixed up by the HIGHLOW and DIR64 base relocations
+fn relocated_values(pe: &PE, bytes: &[u8]) -> Vec<(u32, u64)> {
+    let mut values = Vec::new();
+
+    for block in pe.base_relocation_table.as_ref().expect("The fixture has no base relocations").blocks.iter() {
+        for entry in block.entries.iter() {
+            let rva = block.page_rva + entry.offset as u32;
+            let offset = pe.convert_rva_to_file_offset(rva).unwrap() as usize;
+
+            match entry.get_relocation_type() {
+                BaseRelocationType::HighLow => values.push((rva, u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap()) as u64)),
+                BaseRelocationType::Dir64 => values.push((rva, u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap()))),
+                _ => {}
+            }
+        }
+    }
+
+    return values;
+}
+
+#[test]
+fn rebase_moves_the_image_base_and_applies_the_relocations() {
+    let dir = work_dir("rebase");
+
+    for (name, new_image_base) in [("lib64.dll", 0x7ff812340000u64), ("app32.exe", 0x10000000u64)] {
+        let path = copy_fixture(name, &dir);
+        let output = dir.join(format!("rebased-{}", name));
+        let (original, original_bytes) = read_pe(&path);
+
+        execdump(&["--rebase", &format!("{:#x}", new_image_base), "--output", output.to_str().unwrap()], &path);
+
+        let (rebased, rebased_bytes) = read_pe(&output);
+        let delta = new_image_base.wrapping_sub(original.get_optional_header().get_image_base());
+
+        assert_eq!(rebased.get_optional_header().get_image_base(), new_image_base, "{}", name);
+
+        let expected: Vec<(u32, u64)> = relocated_values(&original, &original_bytes)
+            .into_iter()
+            .map(|(rva, value)| match original.is_32_bits() {
+                true => (rva, (value as u32).wrapping_add(delta as u32) as u64),
+                false => (rva, value.wrapping_add(delta)),
+            })
+            .collect();
+
+        assert!(!expected.is_empty(), "{}", name);
+        assert_eq!(relocated_values(&rebased, &rebased_bytes), expected, "{}", name);
+        assert_eq!(section_layout(&rebased), section_layout(&original), "{}", name);
+        assert_eq!(rebased_bytes.len(), original_bytes.len(), "{}", name);
+        assert_checksum(&rebased, &rebased_bytes);
+    }
+}
+
+/* add-section and rename-section */
+
+/// DLL and function names of the imports, "#7" for an ordinal
+fn imports(pe: &PE) -> Vec<(String, Vec<String>)> {
+    let (Some(ilts), Some(hnt)) = (&pe.import_lookup_tables, &pe.hint_name_table) else {
+        return Vec::new();
+    };
+
+    return ilts
+        .iter()
+        .zip(hnt.entries.iter())
+        .map(|(ilt, hnd)| {
+            let mut names = hnd.entries.iter();
+
+            let functions = ilt
+                .entries
+                .iter()
+                .map(|entry| match entry.by_ordinal {
+                    true => format!("#{}", entry.ordinal_number),
+                    false => names.next().map(|hne| hne.name.clone()).unwrap_or_default(),
+                })
+                .collect();
+
+            return (hnd.dll_name.clone(), functions);
+        })
+        .collect();
+}
+
+#[test]
+fn add_section_appends_the_data_and_moves_the_overlay() {
+    let dir = work_dir("add_section");
+    let path = copy_fixture("app64.exe", &dir);
+
+    let overlay = b"execdump overlay".to_vec();
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes.extend_from_slice(&overlay);
+    std::fs::write(&path, &bytes).unwrap();
+
+    let data: Vec<u8> = (0..0x300).map(|i| i as u8).collect();
+    let data_path = dir.join("section.bin");
+    std::fs::write(&data_path, &data).unwrap();
+
+    let output = dir.join("added.exe");
+    let (original, _) = read_pe(&path);
+
+    execdump(&["add-section", "--name", ".extra", "--data", data_path.to_str().unwrap(), "--virtual-size", "4096", "--output", output.to_str().unwrap()], &path);
+
+    let (added, added_bytes) = read_pe(&output);
+    let section = added.sections.last().unwrap();
+
+    assert_eq!(added.get_number_of_sections(), original.get_number_of_sections() + 1);
+    assert_eq!(section_layout(&added)[..original.get_number_of_sections()], section_layout(&original)[..]);
+    assert_eq!(section.header.name, ".extra");
+    assert_eq!(section.header.virtual_address, 0x3000);
+    assert_eq!(section.header.virtual_size, 0x1000);
+    assert_eq!(section.header.size_of_raw_data, 0x400);
+    assert_eq!(section.header.characteristics, 0x40000040);
+
+    let start = section.header.ptr_to_raw_data as usize;
+    assert_eq!(&added_bytes[start..start + data.len()], &data[..]);
+    assert!(added_bytes[start + data.len()..start + 0x400].iter().all(|b| *b == 0));
+    assert_eq!(&added_bytes[start + 0x400..], &overlay[..]);
+
+    assert_eq!(added.get_optional_header().get_size_of_image(), 0x4000);
+    assert_eq!(imports(&added), imports(&original));
+    assert_checksum(&added, &added_bytes);
+}
+
+#[test]
+fn rename_section_only_changes_the_name() {
+    let dir = work_dir("rename_section");
+    let path = copy_fixture("app64.exe", &dir);
+    let output = dir.join("renamed.exe");
+    let (original, original_bytes) = read_pe(&path);
+
+    execdump(&["rename-section", "--name", ".rdata", "--new-name", ".rodata", "--output", output.to_str().unwrap()], &path);
+
+    let (renamed, renamed_bytes) = read_pe(&output);
+
+    let mut expected = section_layout(&original);
+    expected[1].0 = String::from(".rodata");
+
+    assert_eq!(section_layout(&renamed), expected);
+    assert_eq!(section_data(&renamed, ".rodata"), section_data(&original, ".rdata"));
+    assert!(!renamed.has_section(".rdata"));
+    assert_eq!(imports(&renamed), imports(&original));
+    assert_eq!(renamed_bytes.len(), original_bytes.len());
+    assert_checksum(&renamed, &renamed_bytes);
+}
+
+/* --fix-checksum */
+
+#[test]
+fn fix_checksum_only_writes_the_checksum() {
+    let dir = work_dir("fix_checksum");
+    let path = copy_fixture("lib64.dll", &dir);
+    let (original, original_bytes) = read_pe(&path);
+
+    assert_eq!(original.get_optional_header().get_checksum(), 0);
+
+    execdump(&["--fix-checksum"], &path);
+
+    let (fixed, fixed_bytes) = read_pe(&path);
+    let checksum = fixed.get_checksum_offset() as usize;
+
+    assert_checksum(&fixed, &fixed_bytes);
+    assert_eq!(fixed_bytes[..checksum], original_bytes[..checksum]);
+    assert_eq!(fixed_bytes[checksum + 4..], original_bytes[checksum + 4..]);
+    assert_eq!(section_layout(&fixed), section_layout(&original));
+    assert_eq!(std::fs::read(dir.join("lib64.dll.bak")).expect("No backup"), original_bytes);
+
+    /* A valid checksum is left alone, without a new backup */
+    let report = execdump(&["--fix-checksum"], &path);
+
+    assert!(report.contains("unchanged"), "{}", report);
+    assert_eq!(std::fs::read(&path).unwrap(), fixed_bytes);
+    assert!(!dir.join("lib64.dll.bak1").exists());
+}
+
+/* unmap */
+
+#[test]
+fn unmap_restores_the_file_layout() {
+    let dir = work_dir("unmap");
+
+    for name in ["app64.exe", "app32.exe", "res64.exe"] {
+        let (original, original_bytes) = read_pe(&fixture(name));
+
+        let path = dir.join(format!("{}.dmp", name));
+        std::fs::write(&path, map_image(&original, &original_bytes)).unwrap();
+
+        let output = dir.join(name);
+
+        execdump(&["unmap", "--output", output.to_str().unwrap()], &path);
+
+        let (unmapped, unmapped_bytes) = read_pe(&output);
+
+        assert_eq!(section_layout(&unmapped), section_layout(&original), "{}", name);
+
+        for section in original.sections.iter() {
+            assert_eq!(section_data(&unmapped, &section.header.name), &section.data[..], "{} {}", name, section.header.name);
+        }
+
+        assert_eq!(imports(&unmapped), imports(&original), "{}", name);
+        assert_eq!(unmapped.resource_table.as_ref().map(|r| r.entries.len()), original.resource_table.as_ref().map(|r| r.entries.len()), "{}", name);
+        assert_checksum(&unmapped, &unmapped_bytes);
+    }
+}
+
+/* rebuild-iat */
+
+#[test]
+fn rebuild_iat_resolves_the_loaded_addresses() {
+    let dir = work_dir("rebuild_iat");
+    let path = copy_fixture("app64.exe", &dir);
+    let module = fixture("lib64.dll");
+    let module_base = 0x7ff900000000u64;
+
+    let (original, mut bytes) = read_pe(&path);
+    let (library, _) = read_pe(&module);
+    let expected = imports(&original);
+
+    assert_eq!(expected, vec![(String::from("lib64.dll"), vec![String::from("bar"), String::from("#7"), String::from("foo")])]);
+
+    /* What a process dump holds: the loader wrote the function addresses in the IAT and the import directory is gone */
+    let descriptor = &original.import_directory_table.as_ref().unwrap().entries[0];
+    let iat = original.convert_rva_to_file_offset(descriptor.import_address_table_rva).unwrap() as usize;
+
+    for (slot, function) in expected[0].1.iter().enumerate() {
+        let export = library
+            .export_data
+            .as_ref()
+            .unwrap()
+            .entries
+            .iter()
+            .find(|e| e.name.as_deref() == Some(function.as_str()) || format!("#{}", e.ordinal) == *function)
+            .unwrap();
+
+        bytes[iat + slot * 8..iat + slot * 8 + 8].copy_from_slice(&(module_base + export.rva as u64).to_le_bytes());
+    }
+
+    let import_directory = original.get_data_directory_offset(1) as usize;
+    bytes[import_directory..import_directory + 8].fill(0);
+    std::fs::write(&path, &bytes).unwrap();
+
+    let output = dir.join("rebuilt.exe");
+
+    execdump(&["rebuild-iat", "--module", &format!("{}={:#x}", module.display(), module_base), "--output", output.to_str().unwrap()], &path);
+
+    let (rebuilt, rebuilt_bytes) = read_pe(&output);
+
+    assert_eq!(imports(&rebuilt), expected);
+    assert_eq!(rebuilt.import_directory_table.as_ref().unwrap().entries[0].import_address_table_rva, descriptor.import_address_table_rva);
+    assert_eq!(section_layout(&rebuilt)[..original.get_number_of_sections()], section_layout(&original)[..]);
+    assert_eq!(rebuilt.sections.last().unwrap().header.name, ".idata2");
+    assert_checksum(&rebuilt, &rebuilt_bytes);
+}
+
+/* --remove-signature */
+
+/* Index of the security directory in the data directories, it holds a file offset */
+const CERTIFICATE_DIRECTORY_INDEX: usize = 4;
+
+/// Appends a WIN_CERTIFICATE holding a PKCS#7 blob and points the security directory to it, followed by trailing bytes
+fn with_signature(name: &str, dir: &Path, trailing: &[u8]) -> PathBuf {
+    let path = copy_fixture(name, dir);
+    let (pe, mut bytes) = read_pe(&path);
+
+    let blob = [0x30, 0x03, 0x02, 0x01, 0x01, 0x00, 0x00, 0x00];
+    let offset = bytes.len() as u32;
+    let length = 8 + blob.len() as u32;
+
+    bytes.extend_from_slice(&length.to_le_bytes());
+    bytes.extend_from_slice(&0x0200u16.to_le_bytes());
+    bytes.extend_from_slice(&0x0002u16.to_le_bytes());
+    bytes.extend_from_slice(&blob);
+    bytes.extend_from_slice(trailing);
+
+    let directory = pe.get_data_directory_offset(CERTIFICATE_DIRECTORY_INDEX) as usize;
+    bytes[directory..directory + 4].copy_from_slice(&offset.to_le_bytes());
+    bytes[directory + 4..directory + 8].copy_from_slice(&length.to_le_bytes());
+
+    std::fs::write(&path, &bytes).unwrap();
+
+    return path;
+}
+
+#[test]
+fn remove_signature_truncates_the_certificates() {
+    let dir = work_dir("remove_signature");
+    let (original, original_bytes) = read_pe(&fixture("app64.exe"));
+
+    let path = with_signature("app64.exe", &dir, &[0; 8]);
+    let output = dir.join("unsigned.exe");
+
+    assert!(read_pe(&path).0.certificate_table.is_some());
+
+    execdump(&["--remove-signature", "--output", output.to_str().unwrap()], &path);
+
+    let (unsigned, unsigned_bytes) = read_pe(&output);
+    let certificate_directory = unsigned.get_optional_header().get_certificate_table_idd();
+    let checksum = unsigned.get_checksum_offset() as usize;
+
+    assert!(unsigned.certificate_table.is_none());
+    assert_eq!((certificate_directory.virtual_address, certificate_directory.size), (0, 0));
+    assert_eq!(unsigned_bytes.len(), original_bytes.len());
+    assert_eq!(unsigned_bytes[..checksum], original_bytes[..checksum]);
+    assert_eq!(unsigned_bytes[checksum + 4..], original_bytes[checksum + 4..]);
+    assert_eq!(section_layout(&unsigned), section_layout(&original));
+    assert_checksum(&unsigned, &unsigned_bytes);
+}
+
+#[test]
+fn remove_signature_zeroes_the_certificates_followed_by_data() {
+    let dir = work_dir("remove_signature_data");
+    let trailing = b"data after the signature";
+
+    let path = with_signature("app64.exe", &dir, trailing);
+    let (signed, signed_bytes) = read_pe(&path);
+
+    execdump(&["--remove-signature"], &path);
+
+    let (unsigned, unsigned_bytes) = read_pe(&path);
+    let certificate = signed.get_optional_header().get_certificate_table_idd();
+    let (start, end) = (certificate.virtual_address as usize, (certificate.virtual_address + certificate.size) as usize);
+
+    assert!(unsigned.certificate_table.is_none());
+    assert_eq!(unsigned_bytes.len(), signed_bytes.len());
+    assert!(unsigned_bytes[start..end].iter().all(|b| *b == 0));
+    assert_eq!(&unsigned_bytes[end..], trailing);
+    assert_checksum(&unsigned, &unsigned_bytes);
+    assert_eq!(std::fs::read(dir.join("app64.exe.bak")).expect("No backup"), signed_bytes);
+}
+
+/* add-import */
+
+#[test]
+fn add_import_keeps_the_existing_descriptors() {
+    let dir = work_dir("add_import");
+
+    for name in ["app64.exe", "app32.exe"] {
+        let path = copy_fixture(name, &dir);
+        let output = dir.join(format!("imported-{}", name));
+        let (original, _) = read_pe(&path);
+
+        execdump(&["add-import", "--dll", "inject.dll", "--function", "Init", "--function", "#3", "--output", output.to_str().unwrap()], &path);
+
+        let (imported, imported_bytes) = read_pe(&output);
+
+        let mut expected = imports(&original);
+        expected.push((String::from("inject.dll"), vec![String::from("Init"), String::from("#3")]));
+
+        assert_eq!(imports(&imported), expected, "{}", name);
+
+        /* The existing import address tables stay where the code reads them */
+        let iat_rvas = |pe: &PE| pe.import_directory_table.as_ref().unwrap().entries.iter().map(|e| e.import_address_table_rva).collect::<Vec<u32>>();
+        assert_eq!(iat_rvas(&imported)[..iat_rvas(&original).len()], iat_rvas(&original)[..], "{}", name);
+
+        let section = imported.sections.last().unwrap();
+        let import_directory = imported.get_optional_header().get_import_table_idd();
+
+        assert_eq!(section.header.name, ".idata2", "{}", name);
+        assert_eq!(import_directory.virtual_address, section.header.virtual_address, "{}", name);
+        assert_eq!(section_layout(&imported)[..original.get_number_of_sections()], section_layout(&original)[..], "{}", name);
+        assert_checksum(&imported, &imported_bytes);
+    }
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use common::fixture;
+
+/*
+ * Queries piped to the repl subcommand, the answers go to stdout and the errors to stderr without ending the session
+ */
+
+/// Exit status, stdout and stderr lines of a session answering the queries
+fn repl(name: &str, queries: &[&str]) -> (bool, Vec<String>, Vec<String>) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_execdump"))
+        .arg("repl")
+        .arg(fixture(name))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Unable to run execdump");
+
+    let input = queries.iter().map(|query| format!("{}\n", query)).collect::<String>();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    let lines = |bytes: &[u8]| String::from_utf8_lossy(bytes).lines().map(|line| line.trim_end().to_string()).filter(|line| !line.is_empty()).collect();
+
+    return (output.status.success(), lines(&output.stdout), lines(&output.stderr));
+}
+
+#[test]
+fn pe_address_conversions() {
+    let (success, stdout, stderr) = repl("app64.exe", &["rva2off 0x2048", "off2rva 0x410", "rva2off 0x140000010"]);
+
+    assert!(success);
+    assert!(stderr.is_empty(), "{:?}", stderr);
+    assert_eq!(
+        stdout,
+        [
+            "Address",
+            "    Rva            : 0x2048",
+            "    VirtualAddress : 0x140002048",
+            "    Offset         : 0x648",
+            "    Section        : .rdata",
+            "Address",
+            "    Offset         : 0x410",
+            "    Rva            : 0x1010",
+            "    VirtualAddress : 0x140001010",
+            "    Section        : .text",
+            "Address",
+            "    Rva            : 0x10",
+            "    VirtualAddress : 0x140000010",
+            "    Offset         : 0x10",
+            "    Section        : headers",
+        ]
+    );
+}
+
+#[test]
+fn pe_imports_disassembly_and_hex() {
+    let (success, stdout, _) = repl("app64.exe", &["imports", "imports foo", "disasm 0x1000 3", "hex .rdata 0x48 8", "hex 0x140002048 8"]);
+
+    assert!(success);
+    assert_eq!(
+        stdout[..10],
+        [
+            "Imports (1 DLLs)",
+            "    lib64.dll (3)",
+            "        bar",
+            "        #7",
+            "        foo",
+            /* A function filter only lists the matching functions */
+            "Imports (1 DLLs)",
+            "    lib64.dll (1)",
+            "        foo",
+            "Disassembly at 0x140001000 (3 instructions)",
+            "    140001000      sub      rsp, 0x28",
+        ]
+    );
+    assert!(stdout[11].ends_with("; lib64.dll!bar"), "{}", stdout[11]);
+
+    /* The section name and the address give the same bytes */
+    assert_eq!(stdout[12], ".rdata [0x48..0x50]");
+    assert_eq!(stdout[14], "0x140002048 (8 bytes)");
+    assert_eq!(stdout[13], stdout[15]);
+    assert!(stdout[13].starts_with("    140002048  68 20 00 00 00 00 00 00"), "{}", stdout[13]);
+}
+
+#[test]
+fn elf_queries() {
+    let (success, stdout, stderr) = repl("hello.elf", &["imports", "rva2off 0x2012e8", "disasm 0x2012e8 2", "hex .interp 0 8", "off2rva 0x0"]);
+
+    assert!(success);
+    assert_eq!(
+        stdout,
+        [
+            "Imports",
+            "    Needed Libraries (1)",
+            "        libgreet.so",
+            "    Imported Symbols (1)",
+            "        greet",
+            "Address",
+            "    VirtualAddress : 0x2012e8",
+            "    Offset         : 0x2e8",
+            "    Section        : .text",
+            "Disassembly at 0x2012e8 (2 instructions)",
+            "    002012e8      call     0x201310",
+            "    002012ed      mov      edi, eax",
+            ".interp [0x0..0x8]",
+            "    00200238  2F 6C 69 62 36 34 2F 6C                           /lib64/l",
+        ]
+    );
+    assert_eq!(stderr, ["Error: Offset 0x0 is not mapped"]);
+}
+
+#[test]
+fn errors_do_not_end_the_session() {
+    let (success, stdout, stderr) = repl(
+        "app64.exe",
+        &["bogus", "rva2off 0x9000", "disasm", "hex .data", "", "exports", "quit", "sections"],
+    );
+
+    assert!(success);
+    assert_eq!(
+        stderr,
+        [
+            "Error: Unknown command \"bogus\", type help to list the commands",
+            "Error: RVA 0x9000 is not backed by the file",
+            "Error: Usage: disasm <address> [count]",
+            "Error: No section named \".data\"",
+        ]
+    );
+
+    /* Nothing is read past quit */
+    assert_eq!(stdout, ["Exports (0)"]);
+}
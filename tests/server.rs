@@ -0,0 +1,188 @@
+mod common;
+
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use common::fixture;
+
+/*
+ * Requests to the serve subcommand, every test starts its own server on a free port and stops it when done
+ */
+
+/// Server process, killed when dropped
+struct TestServer {
+    child: Child,
+    url: String,
+}
+
+impl TestServer {
+    fn start(root: Option<PathBuf>) -> TestServer {
+        /* The port is released right before the server binds it */
+        let address = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+
+        let mut command = Command::new(env!("CARGO_BIN_EXE_execdump"));
+        command.args(["serve", "--listen", &address.to_string()]).stderr(Stdio::null());
+
+        if let Some(root) = root {
+            command.arg("--root").arg(root);
+        }
+
+        let server = TestServer { child: command.spawn().expect("Unable to run execdump"), url: format!("http://{}", address) };
+        let start = Instant::now();
+
+        while TcpStream::connect(address).is_err() {
+            assert!(start.elapsed() < Duration::from_secs(10), "The server does not listen on {}", address);
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        return server;
+    }
+
+    /// Status and JSON body of the reply, the errors are JSON too
+    fn request(&self, method: &str, path: &str, body: Option<&[u8]>) -> (u16, Value) {
+        let request = ureq::request(method, &format!("{}{}", self.url, path));
+
+        let result = match body {
+            Some(body) => request.send_bytes(body),
+            None => request.call(),
+        };
+
+        let response = match result {
+            Ok(response) => response,
+            Err(ureq::Error::Status(_, response)) => response,
+            Err(e) => panic!("{} {} failed: {}", method, path, e),
+        };
+
+        let status = response.status();
+        let body: Value = serde_json::from_reader(response.into_reader()).expect("The reply is not JSON");
+
+        return (status, body);
+    }
+
+    fn get(&self, path: &str) -> (u16, Value) {
+        return self.request("GET", path, None);
+    }
+
+    /// Uploads the fixture, returns its id
+    fn upload(&self, name: &str) -> String {
+        let (status, summary) = self.request("POST", &format!("/files?name={}", name), Some(&std::fs::read(fixture(name)).unwrap()));
+
+        assert_eq!(status, 200, "{}", summary);
+
+        return summary["id"].as_str().unwrap().to_string();
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Value of the first field of the dump with the key
+fn field<'a>(dump: &'a Value, key: &str) -> Option<&'a str> {
+    return dump["fields"].as_array()?.iter().find(|f| f["key"] == key).and_then(|f| f["value"].as_str());
+}
+
+#[test]
+fn upload_and_query() {
+    let server = TestServer::start(None);
+    let id = server.upload("app64.exe");
+
+    let (status, files) = server.get("/files");
+    assert_eq!(status, 200);
+    assert_eq!(files, serde_json::json!([{ "id": id, "name": "app64.exe", "size": 2048, "format": "PE32+" }]));
+
+    let (status, summary) = server.get(&format!("/files/{}", id));
+    assert_eq!(status, 200);
+    assert_eq!(field(&summary["info"], "ImageBase"), Some("0x140000000"));
+
+    /* The id is the SHA-256 of the file */
+    let (_, hashes) = server.get(&format!("/files/{}/hashes", id));
+    assert_eq!(hashes["children"][0]["label"], "File");
+    assert_eq!(field(&hashes["children"][0], "SHA256"), Some(id.as_str()));
+
+    let (status, address) = server.get(&format!("/files/{}/rva2off?rva=0x2048", id));
+    assert_eq!(status, 200);
+    assert_eq!((field(&address, "Offset"), field(&address, "Section")), (Some("0x648"), Some(".rdata")));
+
+    let (_, imports) = server.get(&format!("/files/{}/imports?filter=foo", id));
+    assert_eq!(imports["children"][0]["label"], "lib64.dll (1)");
+    assert_eq!(field(&imports["children"][0], ""), Some("foo"));
+
+    let (_, hex) = server.get(&format!("/files/{}/hex?section=.rdata&offset=0x48&length=8", id));
+    assert_eq!(hex["label"], ".rdata [0x48..0x50]");
+
+    let (status, report) = server.get(&format!("/files/{}/dump?flags=sections", id));
+    assert_eq!(status, 200);
+    assert_eq!(report["file"], "app64.exe");
+    assert_eq!(report["dumps"][0]["label"], "Sections (2)");
+
+    let (status, summary) = server.request("DELETE", &format!("/files/{}", id), None);
+    assert_eq!((status, summary["id"].as_str()), (200, Some(id.as_str())));
+
+    let (status, _) = server.get(&format!("/files/{}", id));
+    assert_eq!(status, 404);
+    assert_eq!(server.get("/files"), (200, serde_json::json!([])));
+}
+
+#[test]
+fn errors_are_json() {
+    let server = TestServer::start(None);
+    let id = server.upload("hello.elf");
+
+    let cases = [
+        (format!("/files/{}/dump?flags=sections,write-everything", id), 400, "Unknown dump flag \"write-everything\""),
+        (format!("/files/{}/dump?sections-filter=%28", id), 400, "Invalid sections-filter"),
+        (format!("/files/{}/hex", id), 400, "hex needs a section or an address parameter"),
+        (format!("/files/{}/rva2off?rva=0x10", id), 400, "Address 0x10 is not backed by the file"),
+        (format!("/files/{}/strings", id), 404, "Unknown query \"strings\""),
+        (String::from("/files/0123/info"), 404, "No file with id 0123"),
+        (String::from("/nothing"), 404, "No endpoint GET /nothing"),
+    ];
+
+    for (path, expected_status, expected_error) in cases.iter() {
+        let (status, reply) = server.get(path);
+
+        assert_eq!(status, *expected_status, "{}", path);
+        assert!(reply["error"].as_str().is_some_and(|e| e.starts_with(expected_error)), "{}: {}", path, reply);
+    }
+
+    let (status, reply) = server.request("POST", "/files?name=text", Some(b"not an executable"));
+    assert_eq!(status, 422);
+    assert!(reply["error"].as_str().unwrap().starts_with("Cannot parse text"), "{}", reply);
+
+    let (status, _) = server.request("POST", "/files", Some(b""));
+    assert_eq!(status, 400);
+
+    /* Loading by path needs --root */
+    let (status, _) = server.request("POST", "/files?path=app64.exe", Some(b""));
+    assert_eq!(status, 403);
+}
+
+#[test]
+fn files_are_loaded_from_the_root_only() {
+    let server = TestServer::start(Some(fixture("")));
+
+    let (status, summary) = server.request("POST", "/files?path=lib64.dll", Some(b""));
+    assert_eq!(status, 200, "{}", summary);
+    assert_eq!((summary["name"].as_str(), summary["format"].as_str()), (Some("lib64.dll"), Some("PE32+")));
+
+    let id = summary["id"].as_str().unwrap();
+
+    /* baz is exported by ordinal only */
+    let (_, exports) = server.get(&format!("/files/{}/exports?filter=ba", id));
+    assert_eq!(exports["label"], "Exports (1)");
+    assert!(field(&exports, "").is_some_and(|export| export.ends_with(" bar")), "{}", exports);
+
+    /* Paths escaping the root directory */
+    for path in ["../golden.rs", "%2E%2E/golden.rs", "/etc/passwd", "missing.exe"] {
+        let (status, _) = server.request("POST", &format!("/files?path={}", path), Some(b""));
+        assert_eq!(status, 404, "{}", path);
+    }
+}
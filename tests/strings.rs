@@ -0,0 +1,106 @@
+mod common;
+
+use execdump::args::StringsEncoding;
+use execdump::exec::{parse_exec_from_bytes, Exec};
+use execdump::strings::{ExtractedString, StringEncoding, StringsReport};
+use regex::Regex;
+
+use common::fixture_bytes;
+
+/*
+ * ASCII and UTF-16LE strings extraction, and the attribution of the strings to the sections of the fixtures
+ */
+
+fn extract(bytes: &[u8], exec: Option<&Exec>, encoding: StringsEncoding) -> StringsReport {
+    return StringsReport::extract(bytes, exec, encoding, 4, None);
+}
+
+/// Name of the region holding the string, and the string
+fn find<'a>(report: &'a StringsReport, value: &str) -> (&'a str, &'a ExtractedString) {
+    return report
+        .regions
+        .iter()
+        .find_map(|region| region.strings.iter().find(|s| s.value == value).map(|s| (region.name.as_str(), s)))
+        .unwrap_or_else(|| panic!("\"{}\" is not extracted", value));
+}
+
+#[test]
+fn ascii_and_utf16_strings_of_a_buffer() {
+    let mut bytes = b"abc\0tab\tbed\0\x01\x02".to_vec();
+    bytes.extend("Wide string".encode_utf16().flat_map(|c| c.to_le_bytes()));
+    bytes.extend_from_slice(b"\0\0end!");
+
+    let report = extract(&bytes, None, StringsEncoding::All);
+    let strings: Vec<(u64, StringEncoding, &str)> = report.regions[0].strings.iter().map(|s| (s.file_offset, s.encoding, s.value.as_str())).collect();
+
+    /* "abc" is shorter than the minimum, the tabulation is printable, the files that are not executables are one region */
+    assert_eq!(report.regions.len(), 1);
+    assert_eq!(report.regions[0].name, "(file)");
+    assert_eq!(strings, [(4, StringEncoding::Ascii, "tab\tbed"), (14, StringEncoding::Utf16Le, "Wide string"), (38, StringEncoding::Ascii, "end!")]);
+    assert!(report.regions[0].strings.iter().all(|s| s.virtual_address.is_none()));
+}
+
+#[test]
+fn encoding_length_and_filter() {
+    let bytes = fixture_bytes("res64.exe");
+    let exec = parse_exec_from_bytes(&bytes).unwrap();
+
+    let all = extract(&bytes, Some(&exec), StringsEncoding::All);
+    let ascii = extract(&bytes, Some(&exec), StringsEncoding::Ascii);
+    let utf16 = extract(&bytes, Some(&exec), StringsEncoding::Utf16);
+
+    let count = |report: &StringsReport, encoding: StringEncoding| report.regions.iter().flat_map(|r| r.strings.iter()).filter(|s| s.encoding == encoding).count();
+
+    assert!(count(&ascii, StringEncoding::Ascii) > 0 && count(&ascii, StringEncoding::Utf16Le) == 0);
+    assert!(count(&utf16, StringEncoding::Utf16Le) > 0 && count(&utf16, StringEncoding::Ascii) == 0);
+    assert_eq!(all.len(), ascii.len() + utf16.len());
+
+    let long = StringsReport::extract(&bytes, Some(&exec), StringsEncoding::All, 16, None);
+    assert!(long.regions.iter().flat_map(|r| r.strings.iter()).all(|s| s.value.len() >= 16));
+    assert!(long.len() < all.len());
+
+    let filter = Regex::new("^File").unwrap();
+    let filtered = StringsReport::extract(&bytes, Some(&exec), StringsEncoding::All, 4, Some(&filter));
+    let values: Vec<&str> = filtered.regions.iter().flat_map(|r| r.strings.iter()).map(|s| s.value.as_str()).collect();
+    assert_eq!(values, ["FileDescription", "FileVersion"]);
+}
+
+#[test]
+fn pe_strings_are_attributed_to_their_section() {
+    let bytes = fixture_bytes("res64.exe");
+    let exec = parse_exec_from_bytes(&bytes).unwrap();
+    let report = extract(&bytes, Some(&exec), StringsEncoding::All);
+
+    let (region, string) = find(&report, "!This program cannot be run in DOS mode.$");
+    assert_eq!((region, string.encoding, string.virtual_address), ("(headers)", StringEncoding::Ascii, None));
+
+    let (region, string) = find(&report, "lib64.dll");
+    assert_eq!((region, string.encoding, string.file_offset, string.virtual_address), (".rdata", StringEncoding::Ascii, 0x674, Some(0x140002074)));
+
+    /* The version information is UTF-16LE */
+    let (region, string) = find(&report, "Resource fixture");
+    assert_eq!((region, string.encoding, string.file_offset, string.virtual_address), (".rsrc", StringEncoding::Utf16Le, 0xa3c, Some(0x14000323c)));
+
+    let (region, string) = find(&report, "key=value");
+    assert_eq!((region, string.encoding), (".rsrc", StringEncoding::Ascii));
+
+    let names: Vec<&str> = report.regions.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, ["(headers)", ".rdata", ".rsrc"]);
+}
+
+#[test]
+fn elf_strings_are_attributed_to_their_section() {
+    let bytes = fixture_bytes("hello.elf");
+    let exec = parse_exec_from_bytes(&bytes).unwrap();
+    let report = extract(&bytes, Some(&exec), StringsEncoding::All);
+
+    let (region, string) = find(&report, "/lib64/ld-linux-x86-64.so.2");
+    assert_eq!((region, string.file_offset, string.virtual_address), (".interp", 0x238, Some(0x200238)));
+
+    let (region, string) = find(&report, "libgreet.so");
+    assert_eq!((region, string.virtual_address), (".dynstr", Some(0x2002c3)));
+
+    /* The section names are not loaded in memory */
+    let (region, string) = find(&report, ".shstrtab");
+    assert_eq!((region, string.virtual_address), (".shstrtab", None));
+}
@@ -0,0 +1,178 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use execdump::symbols::{fetch_pdb, SymbolStore};
+
+use common::{fixture, fixture_bytes, parse, work_dir};
+
+/*
+ * PDB retrieval for dbg64.exe from symbol store directories and HTTP symbol servers, the PDB is cached with the
+ * <pdb name>/<GUID><age>/<pdb name> layout of symsrv
+ */
+
+const PDB_KEY: &str = "4844505507A641224C4C44205044422E1";
+
+/// Symbol store directory holding the PDB under the key
+fn symbol_store(dir: &Path, key: &str) -> PathBuf {
+    let store = dir.join("store");
+    let pdb_dir = store.join("dbg64.pdb").join(key);
+
+    std::fs::create_dir_all(&pdb_dir).unwrap();
+    std::fs::copy(fixture("dbg64.pdb"), pdb_dir.join("dbg64.pdb")).unwrap();
+
+    return store;
+}
+
+/// HTTP symbol server answering the requests of the PDB under the key, the other paths are not found. Returns its URL
+fn symbol_server(key: &str) -> String {
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", server.server_addr());
+    let pdb_url = format!("/symbols/dbg64.pdb/{}/dbg64.pdb", key);
+    let pdb = std::fs::read(fixture("dbg64.pdb")).unwrap();
+
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let response = match request.url() == pdb_url {
+                true => tiny_http::Response::from_data(pdb.clone()),
+                false => tiny_http::Response::from_data(Vec::new()).with_status_code(404),
+            };
+
+            let _ = request.respond(response);
+        }
+    });
+
+    return format!("{}/symbols", url);
+}
+
+#[test]
+fn codeview_record_key() {
+    let pe = parse(&fixture_bytes("dbg64.exe"));
+    let codeview = pe.codeview.as_ref().expect("No CodeView record");
+
+    assert_eq!((codeview.pdb_name(), codeview.age), (String::from("dbg64.pdb"), 1));
+    assert_eq!(codeview.guid_string(), "48445055-07A6-4122-4C4C-44205044422E");
+    assert_eq!(codeview.symbol_server_key(), PDB_KEY);
+}
+
+#[test]
+fn fetch_from_a_store_directory_then_from_the_cache() {
+    let dir = work_dir("store");
+    let store = symbol_store(&dir, PDB_KEY);
+    let cache = dir.join("cache");
+    let cached = cache.join("dbg64.pdb").join(PDB_KEY).join("dbg64.pdb");
+
+    let mut pe = parse(&fixture_bytes("dbg64.exe"));
+    let servers = [store.display().to_string()];
+
+    fetch_pdb(&mut pe, &SymbolStore::new(&servers, Some(&cache))).unwrap();
+
+    assert_eq!(pe.pdb_path.as_ref(), Some(&cached));
+    assert_eq!(std::fs::read(&cached).unwrap(), std::fs::read(fixture("dbg64.pdb")).unwrap());
+    assert_eq!(pe.pdb_symbols.get(&pe.get_optional_header().get_address_of_entry_point()).map(|s| s.as_str()), Some("main"));
+    assert!(pe.pdb_symbols.values().any(|symbol| symbol == "__imp_foo"), "{:?}", pe.pdb_symbols);
+
+    /* The store is not read again once the PDB is cached */
+    std::fs::remove_dir_all(&store).unwrap();
+
+    let mut pe = parse(&fixture_bytes("dbg64.exe"));
+    fetch_pdb(&mut pe, &SymbolStore::new(&servers, Some(&cache))).unwrap();
+
+    assert_eq!(pe.pdb_path.as_ref(), Some(&cached));
+}
+
+#[test]
+fn fetch_from_an_http_server() {
+    let dir = work_dir("http");
+    let cache = dir.join("cache");
+
+    /* The servers are searched in order, the ones without the PDB are skipped */
+    let servers = [symbol_server("0000"), dir.join("empty-store").display().to_string(), symbol_server(PDB_KEY)];
+
+    let mut pe = parse(&fixture_bytes("dbg64.exe"));
+    fetch_pdb(&mut pe, &SymbolStore::new(&servers, Some(&cache))).unwrap();
+
+    assert_eq!(pe.pdb_path, Some(cache.join("dbg64.pdb").join(PDB_KEY).join("dbg64.pdb")));
+    assert!(!pe.pdb_symbols.is_empty());
+    assert!(!cache.join("dbg64.pdb").join(PDB_KEY).join("dbg64.partial").exists());
+
+    /* Every server is listed in the error when none has the PDB */
+    let servers = [symbol_server("0000"), dir.join("empty-store").display().to_string()];
+
+    let mut pe = parse(&fixture_bytes("dbg64.exe"));
+    let error = fetch_pdb(&mut pe, &SymbolStore::new(&servers, Some(&dir.join("other-cache")))).unwrap_err().to_string();
+
+    assert!(error.starts_with(&format!("dbg64.pdb/{} not found", PDB_KEY)), "{}", error);
+    assert!(servers.iter().all(|server| error.contains(server.as_str())), "{}", error);
+    assert!(pe.pdb_path.is_none() && pe.pdb_symbols.is_empty());
+}
+
+#[test]
+fn pdb_of_another_build_is_refused() {
+    let dir = work_dir("mismatch");
+    let mut bytes = fixture_bytes("dbg64.exe");
+
+    /* Changes the first byte of the GUID of the RSDS record */
+    let rsds = bytes.windows(4).position(|w| w == b"RSDS").expect("No RSDS record");
+    bytes[rsds + 4] ^= 0xff;
+
+    let mut pe = parse(&bytes);
+    let key = pe.codeview.as_ref().unwrap().symbol_server_key();
+    let store = symbol_store(&dir, &key);
+
+    let error = fetch_pdb(&mut pe, &SymbolStore::new(&[store.display().to_string()], Some(&dir.join("cache")))).unwrap_err().to_string();
+
+    assert!(error.contains("does not match the executable"), "{}", error);
+    assert!(pe.pdb_symbols.is_empty());
+
+    /* No CodeView record */
+    let mut pe = parse(&fixture_bytes("app64.exe"));
+    assert!(fetch_pdb(&mut pe, &SymbolStore::new(&[store.display().to_string()], Some(&dir.join("cache")))).is_err());
+}
+
+#[test]
+fn fetch_pdb_flag() {
+    let dir = work_dir("flag");
+    let store = symbol_store(&dir, PDB_KEY);
+    let cache = dir.join("cache");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_execdump"))
+        .args(["--fetch-pdb", "--entry", "--disasm", "--entry-instructions", "3", "--symbol-server"])
+        .arg(&store)
+        .arg("--symbol-cache")
+        .arg(&cache)
+        .arg(fixture("dbg64.exe"))
+        .output()
+        .expect("Unable to run execdump");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success());
+    assert!(stdout.contains(&format!("Path          : {}", cache.join("dbg64.pdb").join(PDB_KEY).join("dbg64.pdb").display())), "{}", stdout);
+    assert!(stdout.contains("Guid          : 48445055-07A6-4122-4C4C-44205044422E"), "{}", stdout);
+    assert!(stdout.contains("Symbol              : main"), "{}", stdout);
+    assert!(stdout.lines().any(|line| line.trim_end().ends_with("call     __imp_foo")), "{}", stdout);
+
+    /* The servers and the cache of _NT_SYMBOL_PATH, the executable is dumped without symbols when the PDB is missing */
+    let output = Command::new(env!("CARGO_BIN_EXE_execdump"))
+        .arg("--fetch-pdb")
+        .arg(fixture("dbg64.exe"))
+        .env("_NT_SYMBOL_PATH", format!("srv*{}*{}", dir.join("env-cache").display(), dir.join("missing").display()))
+        .output()
+        .expect("Unable to run execdump");
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("Warning: cannot fetch the PDB"));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No PDB could be fetched for this PE"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_execdump"))
+        .arg("--fetch-pdb")
+        .arg(fixture("dbg64.exe"))
+        .env("_NT_SYMBOL_PATH", format!("srv*{}*{}", dir.join("env-cache").display(), store.display()))
+        .output()
+        .expect("Unable to run execdump");
+
+    assert!(output.status.success());
+    assert!(dir.join("env-cache").join("dbg64.pdb").join(PDB_KEY).join("dbg64.pdb").is_file());
+}
@@ -0,0 +1,212 @@
+use capstone::prelude::*;
+use execdump::x86_64::{direct_target, memory_target, parse_operands, MemoryOperand, Operand, Register, RegisterClass};
+
+/*
+ * Operands parsed from the capstone operand strings, printed back in the spelling of llvm-objdump to be compared with
+ * its disassembly of the same instructions
+ */
+
+/* llvm-objdump -d -M intel, the instructions are assembled by llvm-mc */
+const INSTRUCTIONS: [(&str, &str); 38] = [
+    ("488b44c8f0", "rax, qword ptr [rax + 8*rcx - 16]"),
+    ("8b05e22f0000", "eax, dword ptr [rip + 12258]"),
+    ("4c8d0500ffffff", "r8, [rip - 256]"),
+    ("488d4c2428", "rcx, [rsp + 40]"),
+    ("48897df8", "qword ptr [rbp - 8], rdi"),
+    ("4883ec28", "rsp, 40"),
+    ("4883e4f0", "rsp, -16"),
+    ("047f", "al, 127"),
+    ("43c6846c0001000041", "byte ptr [r12 + 2*r13 + 256], 65"),
+    ("0fb702", "eax, word ptr [rdx]"),
+    ("64488b042528000000", "rax, qword ptr fs:[40]"),
+    ("65488b0c2560000000", "rcx, qword ptr gs:[96]"),
+    ("55", "rbp"),
+    ("415f", "r15"),
+    ("0f29742420", "xmmword ptr [rsp + 32], xmm6"),
+    ("c5fe6f0416", "ymm0, ymmword ptr [rsi + rdx]"),
+    ("62f16d48efcb", "zmm1, zmm2, zmm3"),
+    ("62f16d49efcb", "zmm1 {k1}, zmm2, zmm3"),
+    ("c5f892c8", "k1, eax"),
+    ("0f6f01", "mm0, qword ptr [rcx]"),
+    ("db28", "tbyte ptr [rax]"),
+    ("d9c9", "st(1)"),
+    ("0f22d8", "cr3, rax"),
+    ("0f21f8", "rax, dr7"),
+    ("88fc", "ah, bh"),
+    ("660f1f0400", "word ptr [rax + rax]"),
+    ("0f1f0400", "dword ptr [rax + rax]"),
+    ("0f1f440000", "dword ptr [rax + rax]"),
+    ("0f1f4000", "dword ptr [rax]"),
+    ("2e660f1f840000000000", "word ptr cs:[rax + rax]"),
+    ("476b8c903412000064", "r9d, dword ptr [r8 + 4*r10 + 4660], 100"),
+    ("837b18ff", "dword ptr [rbx + 24], -1"),
+    ("48b88877665544332211", "rax, 1234605616436508552"),
+    ("6641bb3412", "r11w, 4660"),
+    ("8b148d00004000", "edx, dword ptr [4*rcx + 4194304]"),
+    ("4084fe", "sil, dil"),
+    ("8cd8", "eax, ds"),
+    ("c3", ""),
+];
+
+fn bytes(hex: &str) -> Vec<u8> {
+    return (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect();
+}
+
+/// Mnemonic and operand string of the single instruction of the bytes
+fn disassemble(hex: &str, address: u64) -> (String, String) {
+    let cs = Capstone::new().x86().mode(arch::x86::ArchMode::Mode64).syntax(arch::x86::ArchSyntax::Intel).build().unwrap();
+    let instructions = cs.disasm_all(&bytes(hex), address).unwrap();
+
+    assert_eq!(instructions.len(), 1, "{}", hex);
+
+    let instruction = instructions.iter().next().unwrap();
+    return (instruction.mnemonic().unwrap().to_string(), instruction.op_str().unwrap_or("").to_string());
+}
+
+fn size_qualifier(size: u16) -> &'static str {
+    match size {
+        8 => return "byte",
+        16 => return "word",
+        32 => return "dword",
+        48 => return "fword",
+        64 => return "qword",
+        80 => return "tbyte",
+        128 => return "xmmword",
+        256 => return "ymmword",
+        512 => return "zmmword",
+        _ => panic!("No qualifier for {} bits", size),
+    }
+}
+
+fn print_memory(memory: &MemoryOperand) -> String {
+    let mut terms = Vec::new();
+
+    if let Some(ref base) = memory.base {
+        terms.push(base.name.clone());
+    }
+
+    match memory.index {
+        Some(ref index) if memory.scale == 1 => terms.push(index.name.clone()),
+        Some(ref index) => terms.push(format!("{}*{}", memory.scale, index.name)),
+        None => {}
+    }
+
+    let mut address = terms.join(" + ");
+
+    match memory.displacement {
+        0 if !terms.is_empty() => {}
+        displacement if terms.is_empty() => address = displacement.to_string(),
+        displacement if displacement < 0 => address.push_str(&format!(" - {}", displacement.unsigned_abs())),
+        displacement => address.push_str(&format!(" + {}", displacement)),
+    }
+
+    let size = memory.size.map(|size| format!("{} ptr ", size_qualifier(size))).unwrap_or_default();
+    let segment = memory.segment.as_ref().map(|segment| format!("{}:", segment.name)).unwrap_or_default();
+
+    return format!("{}{}[{}]", size, segment, address);
+}
+
+/// The immediates are printed signed at the size of the destination, as llvm-objdump does for the sign-extended forms
+fn print_operands(operands: &[Operand]) -> String {
+    let destination_size = match operands.first() {
+        Some(Operand::Register(register)) => register.size,
+        Some(Operand::Memory(memory)) => memory.size.unwrap_or(64),
+        _ => 64,
+    };
+
+    let printed: Vec<String> = operands
+        .iter()
+        .map(|operand| match operand {
+            Operand::Register(register) => register.name.clone(),
+            Operand::Memory(memory) => print_memory(memory),
+            Operand::Immediate(value) if destination_size < 64 => {
+                let shift = 64 - destination_size;
+                ((*value << shift) >> shift).to_string()
+            }
+            Operand::Immediate(value) => value.to_string(),
+            Operand::Other(other) => other.clone(),
+        })
+        .collect();
+
+    return printed.join(", ");
+}
+
+#[test]
+fn operands_match_llvm_objdump() {
+    for (hex, expected) in INSTRUCTIONS.iter() {
+        let (_, op_str) = disassemble(hex, 0);
+        assert_eq!(print_operands(&parse_operands(&op_str)), *expected, "{} ({})", hex, op_str);
+    }
+}
+
+#[test]
+fn operand_kinds() {
+    let (_, op_str) = disassemble("476b8c903412000064", 0);
+    let operands = parse_operands(&op_str);
+
+    assert_eq!(operands.len(), 3);
+    assert_eq!(operands[0], Operand::Register(Register { name: "r9d".to_string(), class: RegisterClass::General, size: 32 }));
+    assert_eq!(
+        operands[1].memory().map(|m| (m.size, m.base.as_ref().map(|r| r.name.as_str()), m.index.as_ref().map(|r| r.name.as_str()), m.scale, m.displacement)),
+        Some((Some(32), Some("r8"), Some("r10"), 4, 0x1234))
+    );
+    assert_eq!(operands[2].immediate(), Some(100));
+
+    /* The AVX-512 masks are not modelled */
+    let (_, op_str) = disassemble("62f16d49efcb", 0);
+    assert_eq!(parse_operands(&op_str)[0], Operand::Other("zmm1 {k1}".to_string()));
+}
+
+#[test]
+fn register_classes_and_sizes() {
+    let registers = [
+        ("rax", RegisterClass::General, 64),
+        ("r11w", RegisterClass::General, 16),
+        ("ah", RegisterClass::General, 8),
+        ("sil", RegisterClass::General, 8),
+        ("eiz", RegisterClass::General, 32),
+        ("rip", RegisterClass::InstructionPointer, 64),
+        ("fs", RegisterClass::Segment, 16),
+        ("xmm31", RegisterClass::Vector, 128),
+        ("ymm0", RegisterClass::Vector, 256),
+        ("zmm3", RegisterClass::Vector, 512),
+        ("k7", RegisterClass::Mask, 64),
+        ("mm0", RegisterClass::Mmx, 64),
+        ("st(3)", RegisterClass::Fpu, 80),
+        ("cr3", RegisterClass::Control, 64),
+        ("dr7", RegisterClass::Debug, 64),
+        ("bnd0", RegisterClass::Bound, 128),
+    ];
+
+    for (name, class, size) in registers.iter() {
+        let register = Register::parse(name).unwrap_or_else(|| panic!("{} is not a register", name));
+        assert_eq!((register.class, register.size), (*class, *size), "{}", name);
+    }
+
+    for name in ["xmm32", "k8", "xmm01", "st(8)", "r16", "foo"] {
+        assert!(Register::parse(name).is_none(), "{}", name);
+    }
+}
+
+#[test]
+fn branch_and_memory_targets() {
+    /* call 0x11e <.text+0x11e>, at the address 0x19 */
+    let (mnemonic, op_str) = disassemble("e800010000", 0x19);
+    assert_eq!((mnemonic.as_str(), direct_target(&op_str)), ("call", Some(0x11e)));
+
+    /* mov eax, dword ptr [rip + 12258] # 0x2fed, at the address 0x5 */
+    let (_, op_str) = disassemble("8b05e22f0000", 0x5);
+    assert_eq!(memory_target(&op_str, 0x5 + 6), Some(0x2fed));
+
+    /* lea r8, [rip - 256] # 0xffffffffffffff8e, at the address 0x87 */
+    let (_, op_str) = disassemble("4c8d0500ffffff", 0x87);
+    assert_eq!(memory_target(&op_str, 0x87 + 7), Some(0xffffffffffffff8e));
+
+    /* Depends on the registers */
+    let (_, op_str) = disassemble("488b44c8f0", 0);
+    assert_eq!(memory_target(&op_str, 5), None);
+
+    /* call rax */
+    let (_, op_str) = disassemble("ffd0", 0);
+    assert_eq!(direct_target(&op_str), None);
+}